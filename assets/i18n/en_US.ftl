@@ -10,6 +10,7 @@ sidebar = Show sidebar
 sidebar-position = Sidebar position
 sidebar-left = Left
 sidebar-top = Top
+sidebar-right = Right
 menu = Menu
 file-open = Open book
 file-open-title = Open e-book
@@ -18,15 +19,18 @@ folder-open-title = Open e-book folder
 history = Recently reading
 reload = Reload content
 book-info = Book information
+copy-position-link = Copy position link
+position-link-copied = Position link copied
 dark-theme = Dark theme
 lang = Language
 search-hint = Search
 with-custom-color = Book custom color
 with-custom-font = Book custom font
+text-only = Text only (no images)
 custom-style = Custom styles
 invalid-style = Invalid style: { $error }
 strip-empty-lines = No empty lines
-scroll-for-page = Mouse scroll for page
+scroll-for-page = Mouse wheel turns pages
 tab-chapter = Chapter
 tab-dictionary = Dictionary
 tab-find = Search in book
@@ -43,9 +47,16 @@ font-files = Font files
 dictionary-file = Dictionary file(ifo)
 cache-dictionary = Cache dictionaries data
 select-by-dictionary = Double click to select by dictionary lookup
-ignore-font-weight = Ignore font weight for rendering
 settings-dialog-title = Settings
 settings-dialog = Show settings dialog
+settings-search = Search settings
+settings-appearance = Appearance
+settings-reading = Reading
+settings-dictionaries = Dictionaries
+settings-keys = Keys
+settings-advanced = Advanced
+next-link-key = Next link key
+prev-link-key = Previous link key
 ok-title = Ok
 cancel-title = Cancel
 add-title = Add
@@ -57,15 +68,210 @@ find-text = Text to find
 find-all-book = All books
 find-all-book-tooltip = Find in all books
 find-toggle-tooltip = Start / Stop find
+find-finished = Find finished, { $count } found
 found-chapter-title = Chapter {$index}
 
 dictionary-no-definition = No definition in dictionary
+dictionary-loading = Loading dictionaries…
 failed-load-reading = Failed load reading book info：{ $error }
 
 custom-style-dialog-title = Set custom styles
 goto-dialog-title = Goto line
 goto-placeholder = Line number
+goto-page-dialog-title = Goto page
+goto-page-placeholder = Page number
 invalid-input-title = Invalid input
 invalid-format = Invalid format
-need-restart = (Need restart)
 alert-error-title = Error
+read-aloud = Read aloud
+read-aloud-rate = Reading speed
+read-aloud-unavailable = No media overlay for reading aloud in this chapter
+tab-notes = Notes
+no-notes = No notes in this chapter
+note-reference = Back to reference
+font-weight-mode = Font weight
+font-weight-book = As book
+font-weight-synthesize = Synthesize missing bold
+font-weight-ignore = Ignore
+font-axes = Variable font axes
+invalid-font-axes = Invalid variable font axes
+letter-spacing = Letter spacing
+word-spacing = Word spacing
+text-scale = Text scale
+invalid-text-spacing = Invalid spacing or text scale
+toolbar-items = Toolbar items (drag to reorder)
+status-widgets = Status bar
+status-chapter-title = Chapter title
+status-progress = Progress
+status-clock = Clock
+status-battery = Battery
+status-words = Words on page
+status-battery-value = Battery { $capacity }%
+status-words-value = { $count } words
+focus-mode = Focus mode
+focus-paragraph = Current paragraph
+focus-ruler = Reading ruler
+focus-dim = Focus dim strength
+invalid-focus-dim = Invalid focus dim strength
+eink-mode = E-ink screen profile
+auto-copy-selection = Copy selected text to clipboard automatically
+watch-clipboard = Lookup dictionary for text copied in other applications
+dict-docked = Dock dictionary at right side
+dictionary-pronounce = Play pronunciation
+dictionary-group = Dictionary group
+dictionary-group-all = All dictionaries
+lookup-popup-expand = Full definition
+translate-selection = Translate
+translating = Translating...
+translate-dialog-title = Translation
+translate-failed = Failed translate: { $error }
+export-flashcard = Export flashcard
+flashcard-target = Flashcard export
+flashcard-tsv = TSV file
+flashcard-anki-connect = AnkiConnect
+flashcard-deck = Anki deck
+invalid-flashcard-deck = Anki deck required for AnkiConnect
+flashcard-exported = Flashcard exported to { $target }
+flashcard-failed = Failed export flashcard: { $error }
+tab-annotations = Annotations
+no-annotations = No annotations in this book
+highlight-selection = Highlight
+highlight-vocabulary = Vocabulary
+highlight-quote = Quote
+highlight-idea = Idea
+highlight-question = Question
+annotation-filter-all = All annotations
+annotation-filter-bookmarks = Bookmarks
+annotate-selection = Add note
+annotation-note-title = Note
+annotation-note-empty = Note is empty
+annotation-added = Highlight saved
+annotation-remove = Remove annotation
+annotation-bookmark = Bookmark
+annotation-chapter = Chapter
+bookmark-added = Bookmark saved
+export-annotations = Export annotations
+export-annotations-format = Format of the exported file
+annotations-exported = Annotations saved to { $path }
+restore-session = Reopen books of last session on start
+watch-file = Reload book when file changed on disk
+file-changed-reloaded = File changed on disk, book reloaded
+config-reloaded = Configuration changed on disk, themes, fonts and keys applied
+config-reload-failed = Failed reload configuration: { $error }
+history-open-folder = Open containing folder
+history-rename = Rename
+history-move = Move to folder
+history-delete = Delete file
+history-remove = Remove from history
+history-delete-confirm = Move { $path } to trash and remove from history?
+history-book-opened = The book is opened in a window
+history-target-exists = { $path } already exists
+history-move-folder = Can not move folder to other file system
+rename-dialog-title = Rename
+history-tags = Edit tags
+tags-dialog-title = Tags (separated by space)
+history-finished = Mark as finished
+history-unfinished = Mark as not finished
+history-pin = Pin to top
+history-unpin = Unpin
+history-pinned = Pinned
+history-full-path = Full path
+history-group-folder = Group by folder
+history-size = History entries
+history-problem-title = Reading history
+history-rebuilt = Broken history database moved to { $path }, reading history starts over.
+history-in-memory-moved = Broken history database moved to { $path }, reading progress will not be saved.
+history-in-memory = Failed load history database, reading progress will not be saved.
+book-finished = Finished on { $date }
+book-author = Author: { $author }
+book-language = Language: { $language }
+book-problems = { $count } problems found, broken items skipped or repaired
+status-goal = Reading goal
+status-pages = Page of chapter
+goal-minutes = minutes
+goal-pages = pages
+goal-progress-minutes = { $progress }/{ $goal } min
+goal-progress-pages = { $progress }/{ $goal } pages
+reading-goal = Daily reading goal
+reading-goal-hint = 0 for no goal
+invalid-reading-goal = Invalid reading goal, should be 0 - 10000
+reading-statistics = Reading statistics
+stats-today = Today: { $minutes } min, { $pages } pages
+stats-current-streak = Current streak: { $days } days
+stats-longest-streak = Longest streak: { $days } days
+stats-days-read = Days read: { $days }
+datetime-format = %Y-%m-%d %H:%M
+clock-format = %H:%M
+number-group-separator = ,
+number-decimal-separator = .
+export-page-image = Export page as image
+export-image-resolution = Resolution of the image
+page-image-exported = Page saved to { $path }
+export-pdf = Export chapters as PDF
+print-first-chapter = From chapter
+print-last-chapter = To chapter
+print-paper-size = Paper size
+invalid-chapter-range = First chapter is after the last one
+pdf-exported = PDF saved to { $path }
+open-next-up = Open next up book
+reopen-closed = Reopen closed book
+next-up-queued = { $count } more books queued for next up
+next-up-empty = No book in next up list
+no-closed-book = No book closed in this session
+next-up-hint = Next up: { $name }
+no-book-dropped = No supported book found
+next-book-in-folder = Next book in folder
+prev-book-in-folder = Previous book in folder
+no-sibling-book = No more book in the folder
+downloading = Downloading { $url }: { $size }
+download-failed = Failed download { $url }: { $error }
+download-finished = { $url } downloaded
+open-failed = Failed open { $name }: { $error }
+password-title = Password
+password-required = { $name } is encrypted, please input the password
+invalid-password = Invalid password for { $name }, please try again
+remember-password = Remember password
+format-title = Format
+format-required = No format supports { $name }, please choose one to open it
+search-fold-width = Match full-width and half-width forms when searching
+find-whole-word = Whole word
+find-whole-word-tooltip = Find whole words only, chinese text segmented into words
+accessibility-preset = Accessibility
+accessibility-off = Off
+accessibility-high-contrast = High contrast
+accessibility-dyslexia = Dyslexia friendly (OpenDyslexic font, extra spacing)
+bionic-reading = Bionic reading
+night-light = Night light
+night-light-always = Always
+night-light-scheduled = At night, hours
+night-light-temperature = Color temperature in kelvin, lower is warmer ({ $min } - { $max })
+invalid-night-light = Invalid night light, temperature out of range or hour not in 0 - 23
+paper = Paper
+paper-none = No background image
+paper-select = Select background image
+paper-tile = Tiled
+paper-stretch = Stretched
+paper-opacity = Opacity of background image
+invalid-paper-opacity = Invalid opacity of background image, should be 0 - 100
+paper-load-failed = Failed load background image { $path }: { $error }
+page-turn = Page turn
+page-turn-none = None
+page-turn-slide = Slide
+page-turn-fade = Fade
+page-turn-curl = Curl
+invalid-page-turn-duration = Invalid duration of page turn
+chapter-continuation = Page commands continue into next or previous chapter
+tab-trace = Trace
+no-trace = No trace
+trace-chapter = Chapter { $chapter }
+minimap = Mini-map
+smooth-scroll = Smooth scrolling
+wheel-lines = Lines per wheel tick
+invalid-wheel-lines = Invalid lines per wheel tick
+invalid-history-size = Invalid number of history entries
+invalid-link-keys = Invalid link key, like "l" or "<Shift>l"
+ui-scale = UI scale
+invalid-ui-scale = Invalid UI scale
+ui-scale-changed = UI scale: { $scale }%
+inner-books-dialog-title = Switch book
+filter-inner-book = Book name
@@ -10,6 +10,7 @@ sidebar = 显示侧边栏
 sidebar-position = 侧边栏位置
 sidebar-left = 左
 sidebar-top = 上
+sidebar-right = 右
 menu = 菜单
 file-open = 开卷
 file-open-title = 选择书籍
@@ -18,15 +19,18 @@ folder-open-title = 选择书籍目录
 history = 最近阅读
 reload = 重新加载
 book-info = 书籍信息
+copy-position-link = 复制位置链接
+position-link-copied = 位置链接已复制
 dark-theme = 夜光模式
 lang = 语言
 search-hint = 查找
 with-custom-color = 使用书籍设定颜色
 with-custom-font = 使用书籍设定字体
+text-only = 纯文本（不加载图片）
 custom-style = 自定义样式
 invalid-style = 样式错误：{ $error }
 strip-empty-lines = 隐藏空行
-scroll-for-page = 鼠标滚动页
+scroll-for-page = 鼠标滚轮翻页
 tab-chapter = 章节
 tab-dictionary = 字典
 tab-find = 全文查找
@@ -43,9 +47,16 @@ font-files = 字体文件
 dictionary-file = 字典文件(ifo)
 cache-dictionary = 缓存字典数据
 select-by-dictionary = 基于字典双击选词
-ignore-font-weight = 渲染时忽略字体粗体设置
 settings-dialog-title = 设置
 settings-dialog = 参数设置
+settings-search = 搜索设置
+settings-appearance = 外观
+settings-reading = 阅读
+settings-dictionaries = 词典
+settings-keys = 按键
+settings-advanced = 高级
+next-link-key = 下一个链接按键
+prev-link-key = 上一个链接按键
 ok-title = 确定
 cancel-title = 取消
 add-title = 新增
@@ -57,15 +68,210 @@ find-text = 查找文本
 find-all-book = 所有书籍
 find-all-book-tooltip = 在所有书籍中查找
 find-toggle-tooltip = 查找 / 停止
+find-finished = 查找完成，找到 { $count } 处
 found-chapter-title = 第{$index}章
 
 dictionary-no-definition = 无字典解释
+dictionary-loading = 正在加载字典…
 failed-load-reading = 加载文件信息失败：{ $error }
 
 custom-style-dialog-title = 设定自定义样式
 goto-dialog-title = 跳转到
 goto-placeholder = 行号
+goto-page-dialog-title = 跳转到页
+goto-page-placeholder = 页码
 invalid-input-title = 输入错误
 invalid-format = 格式错误
-need-restart = (需重启)
-alert-error-title = 错误
\ No newline at end of file
+alert-error-title = 错误
+read-aloud = 朗读
+read-aloud-rate = 朗读速度
+read-aloud-unavailable = 本章节没有可供朗读的媒体覆盖
+tab-notes = 注释
+no-notes = 本章节没有注释
+note-reference = 返回引用处
+font-weight-mode = 字体粗细
+font-weight-book = 按书籍设置
+font-weight-synthesize = 缺少粗体时合成
+font-weight-ignore = 忽略
+font-axes = 可变字体轴
+invalid-font-axes = 可变字体轴设置错误
+letter-spacing = 字间距
+word-spacing = 词间距
+text-scale = 文字缩放
+invalid-text-spacing = 间距或文字缩放设置错误
+toolbar-items = 工具栏按钮（拖动调整顺序）
+status-widgets = 状态栏
+status-chapter-title = 章节标题
+status-progress = 进度
+status-clock = 时钟
+status-battery = 电池
+status-words = 本页字数
+status-battery-value = 电量 { $capacity }%
+status-words-value = { $count } 字
+focus-mode = 专注模式
+focus-paragraph = 当前段落
+focus-ruler = 阅读标尺
+focus-dim = 专注变暗程度
+invalid-focus-dim = 专注变暗程度无效
+eink-mode = 墨水屏模式
+auto-copy-selection = 自动复制选中文本到剪贴板
+watch-clipboard = 为其他程序中复制的文本查询词典
+dict-docked = 词典停靠在右侧
+dictionary-pronounce = 播放发音
+dictionary-group = 字典分组
+dictionary-group-all = 全部字典
+lookup-popup-expand = 完整释义
+translate-selection = 翻译
+translating = 正在翻译...
+translate-dialog-title = 翻译
+translate-failed = 翻译失败：{ $error }
+export-flashcard = 导出抽认卡
+flashcard-target = 抽认卡导出
+flashcard-tsv = TSV 文件
+flashcard-anki-connect = AnkiConnect
+flashcard-deck = Anki 牌组
+invalid-flashcard-deck = AnkiConnect 需要指定 Anki 牌组
+flashcard-exported = 抽认卡已导出到 { $target }
+flashcard-failed = 导出抽认卡失败：{ $error }
+tab-annotations = 标注
+no-annotations = 本书没有标注
+highlight-selection = 高亮
+highlight-vocabulary = 词汇
+highlight-quote = 摘录
+highlight-idea = 想法
+highlight-question = 疑问
+annotation-filter-all = 全部标注
+annotation-filter-bookmarks = 书签
+annotate-selection = 添加笔记
+annotation-note-title = 笔记
+annotation-note-empty = 笔记为空
+annotation-added = 高亮已保存
+annotation-remove = 删除标注
+annotation-bookmark = 书签
+annotation-chapter = 章节
+bookmark-added = 书签已保存
+export-annotations = 导出标注
+export-annotations-format = 导出文件的格式
+annotations-exported = 标注已保存到 { $path }
+restore-session = 启动时重新打开上次的书籍
+watch-file = 文件在磁盘上改变时重新加载
+file-changed-reloaded = 文件已改变，已重新加载
+config-reloaded = 配置文件已改变，已应用主题、字体和按键
+config-reload-failed = 重新加载配置失败：{ $error }
+history-open-folder = 打开所在文件夹
+history-rename = 重命名
+history-move = 移动到文件夹
+history-delete = 删除文件
+history-remove = 从历史记录中移除
+history-delete-confirm = 将 { $path } 移到回收站并从历史记录中移除？
+history-book-opened = 该书已在窗口中打开
+history-target-exists = { $path } 已存在
+history-move-folder = 无法将文件夹移动到其他文件系统
+rename-dialog-title = 重命名
+history-tags = 编辑标签
+tags-dialog-title = 标签（以空格分隔）
+history-finished = 标记为已读完
+history-unfinished = 标记为未读完
+history-pin = 置顶
+history-unpin = 取消置顶
+history-pinned = 已置顶
+history-full-path = 完整路径
+history-group-folder = 按文件夹分组
+history-size = 历史记录条数
+history-problem-title = 阅读历史
+history-rebuilt = 损坏的历史数据库已移至 { $path }，阅读历史将重新开始记录。
+history-in-memory-moved = 损坏的历史数据库已移至 { $path }，阅读进度将不会被保存。
+history-in-memory = 历史数据库加载失败，阅读进度将不会被保存。
+book-finished = 于 { $date } 读完
+book-author = 作者：{ $author }
+book-language = 语言：{ $language }
+book-problems = 发现 { $count } 个问题，损坏的内容已跳过或修复
+status-goal = 阅读目标
+status-pages = 章节页码
+goal-minutes = 分钟
+goal-pages = 页
+goal-progress-minutes = { $progress }/{ $goal } 分钟
+goal-progress-pages = { $progress }/{ $goal } 页
+reading-goal = 每日阅读目标
+reading-goal-hint = 0 表示不设目标
+invalid-reading-goal = 无效的阅读目标，应为 0 - 10000
+reading-statistics = 阅读统计
+stats-today = 今日：{ $minutes } 分钟，{ $pages } 页
+stats-current-streak = 当前连续：{ $days } 天
+stats-longest-streak = 最长连续：{ $days } 天
+stats-days-read = 阅读天数：{ $days }
+datetime-format = %Y年%m月%d日 %H:%M
+clock-format = %H:%M
+number-group-separator = ,
+number-decimal-separator = .
+export-page-image = 导出页面为图片
+export-image-resolution = 图片分辨率
+page-image-exported = 页面已保存到 { $path }
+export-pdf = 导出章节为 PDF
+print-first-chapter = 起始章节
+print-last-chapter = 结束章节
+print-paper-size = 纸张大小
+invalid-chapter-range = 起始章节在结束章节之后
+pdf-exported = PDF 已保存到 { $path }
+open-next-up = 打开下一本书
+reopen-closed = 重新打开关闭的书
+next-up-queued = 另有 { $count } 本书已加入待读列表
+next-up-empty = 待读列表中没有书
+no-closed-book = 本次没有关闭过的书
+next-up-hint = 下一本：{ $name }
+no-book-dropped = 没有找到支持的书
+next-book-in-folder = 文件夹中的下一本书
+prev-book-in-folder = 文件夹中的上一本书
+no-sibling-book = 文件夹中没有更多的书
+downloading = 正在下载 { $url }：{ $size }
+download-failed = 下载 { $url } 失败：{ $error }
+download-finished = { $url } 下载完成
+open-failed = 打开 { $name } 失败：{ $error }
+password-title = 密码
+password-required = { $name } 已加密，请输入密码
+invalid-password = { $name } 的密码错误，请重试
+remember-password = 记住密码
+format-title = 格式
+format-required = { $name } 的格式不受支持，请选择打开它的格式
+search-fold-width = 搜索时全角与半角字符互相匹配
+find-whole-word = 全词匹配
+find-whole-word-tooltip = 仅查找完整的词，中文按词语切分
+accessibility-preset = 无障碍
+accessibility-off = 关闭
+accessibility-high-contrast = 高对比度
+accessibility-dyslexia = 阅读障碍友好（OpenDyslexic 字体，加大间距）
+bionic-reading = 仿生阅读
+night-light = 夜间模式
+night-light-always = 始终开启
+night-light-scheduled = 夜间开启，时段
+night-light-temperature = 色温（开尔文），越低越暖（{ $min } - { $max }）
+invalid-night-light = 夜间模式设置无效，色温超出范围或小时不在 0 - 23 之间
+paper = 纸张
+paper-none = 无背景图片
+paper-select = 选择背景图片
+paper-tile = 平铺
+paper-stretch = 拉伸
+paper-opacity = 背景图片不透明度
+invalid-paper-opacity = 背景图片不透明度无效，应为 0 - 100
+paper-load-failed = 加载背景图片 { $path } 失败：{ $error }
+page-turn = 翻页效果
+page-turn-none = 无
+page-turn-slide = 滑动
+page-turn-fade = 淡入淡出
+page-turn-curl = 卷页
+invalid-page-turn-duration = 翻页效果时长无效
+chapter-continuation = 翻页时自动进入下一章或上一章
+tab-trace = 足迹
+no-trace = 没有足迹
+trace-chapter = 第 { $chapter } 章
+minimap = 章节缩略图
+smooth-scroll = 平滑滚动
+wheel-lines = 滚轮每格行数
+invalid-wheel-lines = 无效的滚轮每格行数
+invalid-history-size = 无效的历史记录条数
+invalid-link-keys = 无效的链接按键，例如 "l" 或 "<Shift>l"
+ui-scale = 界面缩放
+invalid-ui-scale = 无效的界面缩放
+ui-scale-changed = 界面缩放：{ $scale }%
+inner-books-dialog-title = 切换书籍
+filter-inner-book = 书名
@@ -2,27 +2,36 @@ use std::borrow::Cow;
 use std::cmp;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Write};
+use std::fs;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::ops::Range;
 use std::slice::Iter;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use fancy_regex::Regex;
 use indexmap::IndexSet;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
 
 use crate::book::epub::EpubLoader;
 use crate::book::haodoo::HaodooLoader;
 use crate::book::html::HtmlLoader;
 use crate::book::txt::TxtLoader;
+use crate::book::chm::ChmLoader;
+#[cfg(feature = "djvu")]
+use crate::book::djvu::DjvuLoader;
 #[cfg(feature = "gui")]
 use crate::color::{Color32, Colors};
 use crate::common::TraceInfo;
-use crate::common::{byte_index_for_char, char_index_for_byte, Position};
+use crate::common::{char_index_for_byte, Position};
+#[cfg(feature = "gui")]
+use crate::common::detect_language;
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::container::BookContent;
 use crate::container::BookContent::{Buf, File, Path};
 use crate::controller::{HighlightInfo, HighlightMode};
+use crate::segment;
 #[cfg(feature = "gui")]
 use crate::gui::HtmlFonts;
 #[cfg(feature = "gui")]
@@ -34,9 +43,18 @@ mod epub;
 mod txt;
 mod html;
 mod haodoo;
+mod chm;
+mod split;
+#[cfg(feature = "djvu")]
+mod djvu;
+#[cfg(feature = "plugin")]
+pub(crate) mod plugin;
 
 pub const EMPTY_CHAPTER_CONTENT: &str = "No content.";
 pub const IMAGE_CHAR: char = '🖼';
+pub const MEDIA_CHAR: char = '▶';
+// link target of audio or video placeholder, followed by the src
+pub const MEDIA_LINK_PREFIX: &str = "tbr-media:";
 
 /// this array is sorted, modify carefully
 pub const TEXT_SELECTION_SPLITTER: [char; 92] = [
@@ -181,6 +199,24 @@ pub struct CharStyle<'a> {
 	pub title: Option<&'a String>,
 }
 
+#[cfg(feature = "gui")]
+impl<'a> CharStyle<'a> {
+	/// title or alt text of image
+	#[inline]
+	pub fn title(&self) -> Option<&'a str>
+	{
+		self.title
+			.map(|title| title.as_str())
+			.or_else(|| self.image?.alt.as_deref())
+	}
+
+	#[inline]
+	pub fn has_title(&self) -> bool
+	{
+		self.title().is_some()
+	}
+}
+
 pub struct Line {
 	chars: Vec<char>,
 	styles: Vec<(TextStyle, Range<usize>)>,
@@ -311,7 +347,7 @@ impl Line {
 		self.chars.iter()
 	}
 
-	pub fn search_pattern_once(&self, regex: &Regex, start: Option<usize>, stop: Option<usize>, rev: bool) -> Option<Range<usize>>
+	pub fn search_pattern_once(&self, pattern: &SearchPattern, start: Option<usize>, stop: Option<usize>, rev: bool) -> Option<Range<usize>>
 	{
 		let mut line = String::new();
 		let start = start.unwrap_or(0);
@@ -319,33 +355,30 @@ impl Line {
 		for index in start..stop {
 			line.push(self.chars[index])
 		}
-		find_pattern(&line, stop - start, regex, start, rev)
+		let bounds = pattern.whole_word.then(|| segment::word_bounds(&self.chars));
+		let bounds = bounds.as_ref().map(|bounds| &bounds[start..=stop]);
+		find_pattern(&line, pattern, bounds, start, rev)
 	}
 
 	/// F: (text: &str, found_range: Range<usize>)
-	pub fn search_pattern<F>(&self, regex: &Regex, f: F) -> Result<(), SearchError>
+	pub fn search_pattern<F>(&self, pattern: &SearchPattern, f: F) -> Result<(), SearchError>
 	where
 		F: Fn(&str, Range<usize>) -> Result<(), SearchError>,
 	{
 		let text = self.to_string();
-		let mut start = 0;
-		let mut slice = text.as_str();
-		let chars = self.chars.len();
-		while let Some(range) = find_pattern(slice, chars, &regex, start, false) {
-			start = range.end;
-			f(&text, range)?;
-			if let Some(byte_index) = byte_index_for_char(&text, chars, start) {
-				slice = &text[byte_index..];
-			} else {
-				break;
+		let bounds = pattern.whole_word.then(|| segment::word_bounds(&self.chars));
+		let (normalized, indices) = normalize_text(&text, pattern.fold_width);
+		for range in found_ranges(&normalized, &indices, pattern, bounds.as_deref()) {
+			if !range.is_empty() {
+				f(&text, range)?;
 			}
 		}
 		Ok(())
 	}
 
-	pub fn link_iter<F, T>(&self, forward: bool, f: F) -> Option<T>
+	pub fn link_iter<F, T>(&self, forward: bool, mut f: F) -> Option<T>
 	where
-		F: Fn(Link) -> (bool, Option<T>),
+		F: FnMut(Link) -> (bool, Option<T>),
 	{
 		let range = 0..self.styles.len();
 		let indeies: Vec<usize> = if forward {
@@ -471,27 +504,8 @@ impl Line {
 	#[allow(unused)]
 	pub fn word_at_offset(&self, offset: usize) -> Option<(usize, usize)>
 	{
-		let pointer_char = self.chars.get(offset)?;
-		if TEXT_SELECTION_SPLITTER.binary_search(pointer_char).is_ok() {
-			return Some((offset, offset));
-		}
-
-		let mut from = offset;
-		for idx in (0..offset).rev() {
-			if TEXT_SELECTION_SPLITTER.binary_search(&self.chars[idx]).is_ok() {
-				break;
-			}
-			from = idx;
-		}
-
-		let mut to = offset;
-		while let Some(ch) = self.chars.get(to + 1) {
-			if TEXT_SELECTION_SPLITTER.binary_search(ch).is_ok() {
-				break;
-			}
-			to += 1;
-		}
-		Some((from, to))
+		let word = segment::word_at(&self.chars, offset)?;
+		Some((word.start, word.end - 1))
 	}
 
 	#[allow(unused)]
@@ -544,6 +558,33 @@ pub struct TocInfo<'a> {
 	pub level: usize,
 }
 
+#[cfg(feature = "gui")]
+pub struct LandmarkInfo<'a> {
+	pub title: &'a str,
+	// type of landmark, like cover, toc, bodymatter or index
+	pub kind: &'a str,
+	pub index: usize,
+}
+
+/// footnote or endnote in chapter
+pub struct NoteInfo {
+	pub note: Range<Position>,
+	// the link refer to the note
+	pub reference: Option<Range<Position>>,
+}
+
+/// text fragment synchronized with audio clip, from media overlay
+#[cfg(feature = "gui")]
+pub struct MediaClip {
+	pub line: usize,
+	pub range: Range<usize>,
+	// full path of audio file in book
+	pub audio: String,
+	// clip of the audio in seconds, None clip end for end of audio
+	pub clip_begin: f64,
+	pub clip_end: Option<f64>,
+}
+
 impl<'a> Listable for TocInfo<'a> {
 	#[inline]
 	fn title(&self) -> &str
@@ -562,6 +603,13 @@ pub trait Book {
 	#[inline]
 	fn name(&self) -> Option<&str> { None }
 	#[inline]
+	#[cfg(feature = "gui")]
+	fn author(&self) -> Option<&str> { None }
+	/// embedded cover image data, for book information
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn cover(&mut self) -> Option<Vec<u8>> { None }
+	#[inline]
 	fn chapter_count(&self) -> usize { 1 }
 	fn prev_chapter(&mut self) -> Result<Option<usize>>
 	{
@@ -601,11 +649,53 @@ pub trait Book {
 	fn leading_space(&self) -> usize { 2 }
 	#[inline]
 	fn link_position(&mut self, _line: usize, _link_index: usize) -> Option<TraceInfo> { None }
+	// print page label of position, for book with page list
+	#[inline]
+	fn page_label(&self, _line: usize, _offset: usize) -> Option<&str> { None }
+	#[inline]
+	fn page_position(&mut self, _label: &str) -> Option<TraceInfo> { None }
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn landmarks(&self) -> Option<Vec<LandmarkInfo<'_>>> { None }
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn landmark_position(&mut self, _index: usize) -> Option<TraceInfo> { None }
+	// footnotes and endnotes of current chapter
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn notes(&self) -> Option<&Vec<NoteInfo>> { None }
+	// media overlay clips of current chapter, for read aloud
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn media_clips(&self) -> Option<&Vec<MediaClip>> { None }
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn audio(&self, _path: &str) -> Option<Vec<u8>> { None }
+	// (absolute path, content) of audio or video in current chapter
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn media(&self, _href: &str) -> Option<(String, Vec<u8>)> { None }
 	// (absolute path, content)
 	#[inline]
 	fn image<'a>(&'a self, _href: &'a str) -> Option<ImageData<'a>> { None }
 	#[inline]
 	fn font_family_names(&self) -> Option<&IndexSet<String>> { None }
+	// broken items skipped or repaired when loading
+	#[inline]
+	fn problems(&self) -> Option<&Vec<String>> { None }
+	// written vertically, rendered as han by default
+	#[inline]
+	fn vertical_writing(&self) -> bool { false }
+	// latin chars not rotated when rendered as han
+	#[inline]
+	fn upright_text(&self) -> bool { false }
+	// primary language code, like "en", from metadata or sampled text of current chapter
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn language(&self) -> Option<String>
+	{
+		detect_language(self.lines()).map(|language| language.to_owned())
+	}
 	#[inline]
 	#[cfg(feature = "gui")]
 	fn color_customizable(&self) -> bool { false }
@@ -688,6 +778,11 @@ pub struct BookLoader {
 
 pub(crate) trait Loader {
 	fn extensions(&self) -> &Vec<&'static str>;
+	// name for choosing the loader of misnamed files, like "epub"
+	fn format(&self) -> &str;
+	// leading bytes of file without known extension recognized by magic
+	#[inline]
+	fn sniff(&self, _head: &[u8]) -> bool { false }
 	fn support(&self, filename: &str) -> bool
 	{
 		let filename = filename.to_lowercase();
@@ -710,6 +805,14 @@ pub(crate) trait Loader {
 	fn load_buf(&self, filename: &str, content: Vec<u8>,
 		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>;
+
+	/// only zip based books use the password
+	fn load_encrypted(&self, filename: &str, content: Vec<u8>, _password: &str,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		self.load_buf(filename, content, loading_chapter, loading)
+	}
 }
 
 impl BookLoader {
@@ -735,22 +838,59 @@ impl BookLoader {
 		false
 	}
 
+	/// names of formats for choosing the loader instead of by extension
+	#[cfg(feature = "gui")]
+	pub fn formats(&self) -> Vec<&str>
+	{
+		self.loaders.iter().map(|loader| loader.format()).collect()
+	}
+
+	/// format of the first loader recognizes the leading bytes
+	pub fn sniff(&self, head: &[u8]) -> Option<&str>
+	{
+		self.loaders
+			.iter()
+			.find(|loader| loader.sniff(head))
+			.map(|loader| loader.format())
+	}
+
+	/// filename with extension of the format appended, so the loader
+	/// chosen by the format, the format can be a name or an extension
+	pub fn format_filename(&self, filename: &str, format: &str) -> Option<String>
+	{
+		let format = format.trim_start_matches('.').to_lowercase();
+		let loader = self.loaders.iter().find(|loader| loader.format() == format
+			|| loader.extensions().iter().any(|ext| ext.trim_start_matches('.') == format))?;
+		let extension = loader.extensions().first()?;
+		Some(format!("{}{}", filename, extension))
+	}
+
 	pub fn load(&self, filename: &str, content: BookContent,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		password: Option<&str>) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		for loader in self.loaders.iter() {
 			if loader.support(filename) {
-				let (book, mut reading) = match content {
-					File(filepath) => {
+				let (book, mut reading) = match (content, password) {
+					(File(filepath), Some(password)) => {
+						let content = fs::read(filepath)?;
+						loader.load_encrypted(filename, content, password, loading_chapter, loading)?
+					}
+					(Path(filepath), Some(password)) => {
+						let content = fs::read(filepath)?;
+						loader.load_encrypted(filename, content, password, loading_chapter, loading)?
+					}
+					(Buf(buf), Some(password)) =>
+						loader.load_encrypted(filename, buf, password, loading_chapter, loading)?,
+					(File(filepath), None) => {
 						let file = OpenOptions::new().read(true).open(filepath)?;
 						loader.load_file(filename, file, loading_chapter, loading)?
 					}
-					Path(filepath) => {
+					(Path(filepath), None) => {
 						let file = OpenOptions::new().read(true).open(filepath)?;
 						loader.load_file(filename, file, loading_chapter, loading)?
 					}
-					Buf(buf) => loader.load_buf(filename, buf, loading_chapter, loading)?,
+					(Buf(buf), None) => loader.load_buf(filename, buf, loading_chapter, loading)?,
 				};
 				reading.chapter = book.current_chapter();
 				let lines = book.lines();
@@ -764,7 +904,7 @@ impl BookLoader {
 				return Ok((book, reading));
 			}
 		}
-		Err(anyhow!("Not support open book: {}", filename))
+		Err(anyhow::Error::new(UnknownFormat(filename.to_owned())))
 	}
 }
 
@@ -776,10 +916,31 @@ impl Default for BookLoader {
 		loaders.push(Box::new(EpubLoader::new()));
 		loaders.push(Box::new(HtmlLoader::new()));
 		loaders.push(Box::new(HaodooLoader::new()));
+		loaders.push(Box::new(ChmLoader::new()));
+		#[cfg(feature = "djvu")]
+		loaders.push(Box::new(DjvuLoader::new()));
+		// built-in loaders first, plugins only for formats not supported
+		#[cfg(feature = "plugin")]
+		for loader in plugin::loaders() {
+			loaders.push(Box::new(loader));
+		}
 		BookLoader { loaders }
 	}
 }
 
+/// no loader supports the extension of the file
+#[derive(Debug)]
+pub struct UnknownFormat(String);
+
+impl Display for UnknownFormat {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		write!(f, "Not support open book: {}", self.0)
+	}
+}
+
+impl Error for UnknownFormat {}
+
 pub struct ChapterError {
 	msg: String,
 }
@@ -815,14 +976,123 @@ impl ChapterError
 	}
 }
 
-fn find_pattern(line: &str, chars: usize, regex: &Regex, start_offset: usize, rev: bool) -> Option<Range<usize>>
+/// regex for searching, matched against normalized text, so composed and
+/// decomposed forms found by each other
+pub struct SearchPattern {
+	regex: Regex,
+	// full-width and half-width forms matched each other, by NFKC
+	fold_width: bool,
+	// found text must start and end at word boundaries, cjk text segmented
+	whole_word: bool,
+}
+
+impl SearchPattern {
+	pub fn new(pattern: &str, fold_width: bool, whole_word: bool) -> Result<Self, fancy_regex::Error>
+	{
+		let pattern = normalize_pattern(pattern, fold_width);
+		let regex = Regex::new(&pattern)?;
+		Ok(SearchPattern { regex, fold_width, whole_word })
+	}
+}
+
+/// full-width punctuations folded to regex meta chars are escaped,
+/// so "（" still searched as it is
+fn normalize_pattern(pattern: &str, fold_width: bool) -> String
+{
+	let pattern = pattern.nfc();
+	if !fold_width {
+		return pattern.collect();
+	}
+	let mut normalized = String::new();
+	for ch in pattern {
+		if ch.is_ascii() {
+			normalized.push(ch);
+			continue;
+		}
+		for folded in ch.nfkc() {
+			if folded.is_ascii_punctuation() && "\\.+*?()|[]{}^$#&-~".contains(folded) {
+				normalized.push('\\');
+			}
+			normalized.push(folded);
+		}
+	}
+	normalized
+}
+
+/// normalized text and original char index of each normalized char,
+/// with the char count of text as last entry
+fn normalize_text(text: &str, fold_width: bool) -> (String, Vec<usize>)
+{
+	#[inline]
+	fn push_segment(segment: &str, start: usize, fold_width: bool,
+		normalized: &mut String, indices: &mut Vec<usize>)
+	{
+		if segment.is_ascii() {
+			for (index, ch) in segment.chars().enumerate() {
+				normalized.push(ch);
+				indices.push(start + index);
+			}
+		} else if fold_width {
+			for ch in segment.nfkc() {
+				normalized.push(ch);
+				indices.push(start);
+			}
+		} else {
+			for ch in segment.nfc() {
+				normalized.push(ch);
+				indices.push(start);
+			}
+		}
+	}
+
+	let mut normalized = String::with_capacity(text.len());
+	let mut indices = Vec::with_capacity(text.len() + 1);
+	// a starter with following combining marks normalized together
+	let mut segment = String::new();
+	let mut segment_start = 0;
+	let mut chars = 0;
+	for (index, ch) in text.chars().enumerate() {
+		if index > 0 && canonical_combining_class(ch) == 0 {
+			push_segment(&segment, segment_start, fold_width, &mut normalized, &mut indices);
+			segment.clear();
+			segment_start = index;
+		}
+		segment.push(ch);
+		chars += 1;
+	}
+	push_segment(&segment, segment_start, fold_width, &mut normalized, &mut indices);
+	indices.push(chars);
+	(normalized, indices)
+}
+
+/// char ranges in original text of matches in normalized text,
+/// matches not on word bounds skipped if bounds supplied
+fn found_ranges<'a>(normalized: &'a str, indices: &'a [usize], pattern: &'a SearchPattern,
+	bounds: Option<&'a [bool]>) -> impl Iterator<Item=Range<usize>> + 'a
+{
+	let chars = indices.len() - 1;
+	pattern.regex
+		.find_iter(normalized)
+		.map_while(|m| m.ok())
+		.filter_map(move |m| {
+			let start = indices[char_index_for_byte(normalized, chars, m.start())?];
+			let end = indices[char_index_for_byte(normalized, chars, m.end())?];
+			match bounds {
+				Some(bounds) if !bounds[start] || !bounds[end] => None,
+				_ => Some(start..end),
+			}
+		})
+}
+
+fn find_pattern(line: &str, pattern: &SearchPattern, bounds: Option<&[bool]>,
+	start_offset: usize, rev: bool) -> Option<Range<usize>>
 {
-	let m = if rev {
-		regex.find_iter(line).last()?.ok()?
+	let (line, indices) = normalize_text(line, pattern.fold_width);
+	let mut found = found_ranges(&line, &indices, pattern, bounds);
+	let range = if rev {
+		found.last()?
 	} else {
-		regex.find_from_pos(line, 0).ok()??
+		found.next()?
 	};
-	let match_start = char_index_for_byte(&line, chars, m.start()).unwrap();
-	let match_end = char_index_for_byte(&line, chars, m.end()).unwrap();
-	Some(Range { start: match_start + start_offset, end: match_end + start_offset })
+	Some(Range { start: range.start + start_offset, end: range.end + start_offset })
 }
@@ -0,0 +1,938 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use ego_tree::NodeRef;
+use elsa::FrozenMap;
+use indexmap::IndexSet;
+use scraper::{Html, Node};
+
+use crate::book::{Book, ChapterError, ImageData, Line, LoadingChapter, Loader, TocInfo};
+#[cfg(feature = "gui")]
+use crate::book::NoteInfo;
+use crate::book::chm::lzx::LzxDecoder;
+use crate::common::{plain_text, TraceInfo};
+use crate::config::{BookLoadingInfo, ReadingInfo};
+#[cfg(feature = "gui")]
+use crate::gui::HtmlFonts;
+#[cfg(feature = "gui")]
+use crate::html_parser::BlockStyle;
+use crate::html_parser::{HtmlContent, HtmlParseOptions, HtmlResolver};
+use crate::list::ListIterator;
+use crate::{frozen_map_get, html_parser};
+
+mod lzx;
+
+const ITSF_SIGNATURE: &[u8] = b"ITSF";
+const ITSP_SIGNATURE: &[u8] = b"ITSP";
+const PMGL_SIGNATURE: &[u8] = b"PMGL";
+const LZXC_SIGNATURE: &[u8] = b"LZXC";
+const SYSTEM_FILE: &str = "#system";
+const COMPRESSED_CONTENT: &str = "::dataspace/storage/mscompressed/content";
+const COMPRESSED_CONTROL: &str = "::dataspace/storage/mscompressed/controldata";
+const COMPRESSED_RESET_TABLE: &str = "::dataspace/storage/mscompressed/transform/{7fc28940-9d31-11d0-9b27-00a0c91e9c7c}/instancedata/resettable";
+const MIN_WINDOW_SIZE: usize = 1 << 15;
+const MAX_WINDOW_SIZE: usize = 1 << 21;
+// reserve no more than this ratio of compressed length for output
+const MAX_RESERVE_RATIO: usize = 8;
+
+type Chapter = HtmlContent;
+
+struct ChmEntry {
+	section: u64,
+	offset: u64,
+	length: u64,
+}
+
+/// compiled html help, content files are stored in directory chunks,
+/// section 0 is uncompressed, section 1 is LZX compressed
+struct ChmArchive {
+	content: Vec<u8>,
+	data_offset: usize,
+	// lowercase name without leading '/' as key
+	entries: HashMap<String, ChmEntry>,
+	compressed: Vec<u8>,
+}
+
+impl ChmArchive {
+	fn new(content: Vec<u8>) -> Result<Self>
+	{
+		if content.len() < 0x60 || &content[0..4] != ITSF_SIGNATURE {
+			bail!("Invalid chm file.");
+		}
+		let version = read_u32(&content, 4)?;
+		let header_len = read_u32(&content, 8)? as usize;
+		let dir_offset = read_u64(&content, 72)? as usize;
+		let dir_len = read_u64(&content, 80)? as usize;
+		let data_offset = if version >= 3 && header_len >= 0x60 {
+			read_u64(&content, 88)? as usize
+		} else {
+			dir_offset + dir_len
+		};
+		let entries = read_directory(&content, dir_offset)?;
+		let mut archive = ChmArchive {
+			content,
+			data_offset,
+			entries,
+			compressed: vec![],
+		};
+		if archive.entries.contains_key(COMPRESSED_CONTENT) {
+			archive.compressed = archive.decompress()?;
+		}
+		Ok(archive)
+	}
+
+	#[inline]
+	fn exists(&self, path: &str) -> bool
+	{
+		self.entries.contains_key(&entry_key(path))
+	}
+
+	fn content(&self, path: &str) -> Result<&[u8]>
+	{
+		let entry = self.entries.get(&entry_key(path))
+			.ok_or_else(|| anyhow!("No {} in chm.", path))?;
+		let invalid = || anyhow!("Invalid entry {} in chm.", path);
+		let start = usize::try_from(entry.offset).map_err(|_| invalid())?;
+		let length = usize::try_from(entry.length).map_err(|_| invalid())?;
+		let end = start.checked_add(length).ok_or_else(invalid)?;
+		let data = match entry.section {
+			0 => {
+				let start = self.data_offset.checked_add(start).ok_or_else(invalid)?;
+				let end = self.data_offset.checked_add(end).ok_or_else(invalid)?;
+				self.content.get(start..end)
+			}
+			1 => self.compressed.get(start..end),
+			_ => None,
+		};
+		data.ok_or_else(invalid)
+	}
+
+	#[inline]
+	fn string(&self, path: &str) -> Result<String>
+	{
+		plain_text(self.content(path)?.to_vec(), false)
+	}
+
+	fn decompress(&self) -> Result<Vec<u8>>
+	{
+		let control = self.content(COMPRESSED_CONTROL)?;
+		if control.len() < 24 || &control[4..8] != LZXC_SIGNATURE {
+			bail!("Invalid lzx control data in chm.");
+		}
+		let version = read_u32(control, 8)?;
+		let mut reset_interval = read_u32(control, 12)? as usize;
+		let mut window_size = read_u32(control, 16)? as usize;
+		let windows_per_reset = read_u32(control, 20)? as usize;
+		if version == 2 {
+			reset_interval = reset_interval.saturating_mul(0x8000);
+			window_size = window_size.saturating_mul(0x8000);
+		}
+		if !window_size.is_power_of_two()
+			|| !(MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE).contains(&window_size) {
+			bail!("Invalid lzx window size in chm.");
+		}
+		let window_bits = window_size.trailing_zeros() as usize;
+		let reset_blocks = (reset_interval / (window_size / 2))
+			.saturating_mul(windows_per_reset)
+			.max(1);
+
+		let reset_table = self.content(COMPRESSED_RESET_TABLE)?;
+		let block_count = read_u32(reset_table, 4)? as usize;
+		let table_offset = read_u32(reset_table, 12)? as usize;
+		let uncompressed_len = read_u64(reset_table, 16)? as usize;
+		let compressed_len = read_u64(reset_table, 24)? as usize;
+		let block_len = read_u64(reset_table, 32)? as usize;
+		if block_len == 0 || block_len > window_size {
+			bail!("Invalid lzx block length in chm.");
+		}
+
+		let input = self.content(COMPRESSED_CONTENT)?;
+		let mut decoder = LzxDecoder::new(window_bits)?;
+		// uncompressed length is untrusted, do not reserve more than input can produce
+		let reserve = uncompressed_len.min(input.len().saturating_mul(MAX_RESERVE_RATIO));
+		let mut output = Vec::with_capacity(reserve);
+		for block in 0..block_count {
+			if output.len() >= uncompressed_len {
+				break;
+			}
+			if block % reset_blocks == 0 {
+				decoder.reset();
+			}
+			let start = read_u64(reset_table, table_offset + block * 8)? as usize;
+			let end = if block + 1 < block_count {
+				read_u64(reset_table, table_offset + (block + 1) * 8)? as usize
+			} else {
+				compressed_len
+			};
+			let frame = input.get(start..end.min(input.len()))
+				.ok_or_else(|| anyhow!("Invalid lzx reset table in chm."))?;
+			let output_len = block_len.min(uncompressed_len - output.len());
+			decoder.decompress(frame, output_len, &mut output)?;
+		}
+		Ok(output)
+	}
+
+	/// title, contents(hhc) and default topic in #SYSTEM
+	fn system_info(&self) -> (Option<String>, Option<String>, Option<String>)
+	{
+		let mut title = None;
+		let mut contents = None;
+		let mut default_topic = None;
+		if let Ok(data) = self.content(SYSTEM_FILE) {
+			let mut offset = 4;
+			while offset + 4 <= data.len() {
+				let code = u16::from_le_bytes([data[offset], data[offset + 1]]);
+				let len = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+				offset += 4;
+				let Some(value) = data.get(offset..offset + len) else {
+					break;
+				};
+				offset += len;
+				let target = match code {
+					0 => &mut contents,
+					2 => &mut default_topic,
+					3 => &mut title,
+					_ => continue,
+				};
+				let end = value.iter().position(|b| *b == 0).unwrap_or(value.len());
+				if end > 0 {
+					if let Ok(text) = plain_text(value[..end].to_vec(), true) {
+						*target = Some(text);
+					}
+				}
+			}
+		}
+		(title, contents, default_topic)
+	}
+}
+
+struct TocEntry {
+	title: String,
+	level: usize,
+	src_file: Option<String>,
+	src_anchor: Option<String>,
+	chapter_index: Option<usize>,
+}
+
+struct ChmBook {
+	archive: ChmArchive,
+	title: Option<String>,
+	toc: Vec<TocEntry>,
+	chapters: Vec<String>,
+	chapter_cache: HashMap<usize, Chapter>,
+	css_cache: FrozenMap<String, String>,
+	font_families: IndexSet<String>,
+	chapter_index: usize,
+	#[cfg(feature = "gui")]
+	fonts: HtmlFonts,
+	custom_style: Option<String>,
+}
+
+pub(crate) struct ChmLoader {
+	extensions: Vec<&'static str>,
+}
+
+impl ChmLoader {
+	#[inline]
+	pub(crate) fn new() -> Self
+	{
+		let extensions = vec![".chm"];
+		ChmLoader { extensions }
+	}
+}
+
+impl Loader for ChmLoader {
+	#[inline]
+	fn extensions(&self) -> &Vec<&'static str>
+	{
+		&self.extensions
+	}
+
+	#[inline]
+	fn format(&self) -> &str
+	{
+		"chm"
+	}
+
+	#[inline]
+	fn sniff(&self, head: &[u8]) -> bool
+	{
+		head.starts_with(ITSF_SIGNATURE)
+	}
+
+	fn load_buf(&self, _filename: &str, content: Vec<u8>,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		let archive = ChmArchive::new(content)?;
+		let reading = get_reading(loading);
+		let book = ChmBook::new(archive, loading_chapter, &reading.custom_style)?;
+		Ok((Box::new(book), reading))
+	}
+}
+
+impl Book for ChmBook {
+	#[inline]
+	fn name(&self) -> Option<&str>
+	{
+		self.title.as_ref().map(|s| s.as_str())
+	}
+
+	#[inline]
+	fn chapter_count(&self) -> usize
+	{
+		self.chapters.len()
+	}
+
+	fn prev_chapter(&mut self) -> Result<Option<usize>>
+	{
+		let mut current = self.chapter_index;
+		while current > 0 {
+			current -= 1;
+			if self.load_chapter(current)?.lines().len() > 0 {
+				self.chapter_index = current;
+				return Ok(Some(current));
+			}
+		}
+		Ok(None)
+	}
+
+	fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
+	{
+		let chapter_count = self.chapter_count();
+		for current in chapter_index..chapter_count {
+			if self.load_chapter(current)?.lines().len() > 0 {
+				self.chapter_index = current;
+				return Ok(Some(current));
+			}
+		}
+		Ok(None)
+	}
+
+	#[inline]
+	fn current_chapter(&self) -> usize
+	{
+		self.chapter_index
+	}
+
+	fn title(&self, line: usize, offset: usize) -> Option<&str>
+	{
+		let toc_index = self.toc_index(line, offset);
+		Some(&self.toc.get(toc_index)?.title)
+	}
+
+	fn toc_index(&self, line: usize, offset: usize) -> usize
+	{
+		let Some(chapter) = self.chapter_cache.get(&self.chapter_index) else {
+			return 0;
+		};
+		let mut matched = 0;
+		for (toc_index, entry) in self.toc.iter().enumerate() {
+			match entry.chapter_index {
+				Some(chapter_index) if chapter_index == self.chapter_index => {
+					if let Some(anchor) = &entry.src_anchor {
+						if let Some(position) = chapter.id_position(anchor) {
+							if position.line > line || (position.line == line && position.offset > offset) {
+								break;
+							}
+						}
+					}
+					matched = toc_index;
+				}
+				Some(chapter_index) if chapter_index < self.chapter_index => matched = toc_index,
+				_ => {}
+			}
+		}
+		matched
+	}
+
+	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo<'_>> + '_>>
+	{
+		let iter = ListIterator::new(|index| {
+			let entry = self.toc.get(index)?;
+			Some(TocInfo { title: &entry.title, index, level: entry.level })
+		});
+		Some(Box::new(iter))
+	}
+
+	fn toc_position(&mut self, toc_index: usize) -> Option<TraceInfo>
+	{
+		let entry = self.toc.get(toc_index)?;
+		let src_file = entry.src_file.clone()?;
+		let src_anchor = entry.src_anchor.clone();
+		self.target_position(Some(&src_file), src_anchor)
+	}
+
+	#[inline]
+	fn lines(&self) -> &Vec<Line>
+	{
+		self.chapter_cache.get(&self.chapter_index).unwrap().lines()
+	}
+
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn notes(&self) -> Option<&Vec<NoteInfo>>
+	{
+		Some(self.chapter_cache.get(&self.chapter_index)?.notes())
+	}
+
+	fn link_position(&mut self, line: usize, link_index: usize) -> Option<TraceInfo>
+	{
+		let cwd = path_cwd(self.chapters.get(self.chapter_index)?);
+		let chapter = self.chapter_cache.get(&self.chapter_index)?;
+		let text = chapter.lines().get(line)?;
+		let link = text.link_at(link_index)?;
+		let (target_file, target_anchor) = split_href(link.target);
+		if target_file.is_empty() {
+			self.target_position(None, target_anchor)
+		} else {
+			let path = resolve_path(&cwd, target_file);
+			self.target_position(Some(&path), target_anchor)
+		}
+	}
+
+	fn image<'h>(&'h self, href: &'h str) -> Option<ImageData<'h>>
+	{
+		let cwd = path_cwd(self.chapters.get(self.chapter_index)?);
+		let (file, _) = split_href(href);
+		let full_path = resolve_path(&cwd, file);
+		// whole archive in memory, no cache needed
+		let bytes = self.archive.content(&full_path).ok()?;
+		Some(ImageData::Borrowed((Cow::Owned(full_path), bytes)))
+	}
+
+	#[cfg(feature = "gui")]
+	fn media(&self, href: &str) -> Option<(String, Vec<u8>)>
+	{
+		let cwd = path_cwd(self.chapters.get(self.chapter_index)?);
+		let (file, _) = split_href(href);
+		let full_path = resolve_path(&cwd, file);
+		let bytes = self.archive.content(&full_path).ok()?.to_vec();
+		Some((full_path, bytes))
+	}
+
+	#[inline]
+	fn font_family_names(&self) -> Option<&IndexSet<String>>
+	{
+		Some(&self.font_families)
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn color_customizable(&self) -> bool
+	{
+		true
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn fonts_customizable(&self) -> bool
+	{
+		true
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn custom_fonts(&self) -> Option<&HtmlFonts>
+	{
+		if self.fonts.has_faces() {
+			Some(&self.fonts)
+		} else {
+			None
+		}
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn style_customizable(&self) -> bool
+	{
+		true
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn block_styles(&self) -> Option<&Vec<BlockStyle>>
+	{
+		self.chapter_cache
+			.get(&self.current_chapter())?
+			.block_styles()
+	}
+}
+
+struct ChmResolver<'a> {
+	cwd: String,
+	archive: &'a ChmArchive,
+	css_cache: &'a FrozenMap<String, String>,
+	custom_style: Option<&'a str>,
+}
+
+impl<'a> HtmlResolver for ChmResolver<'a>
+{
+	#[inline]
+	fn cwd(&self) -> PathBuf
+	{
+		PathBuf::from(&self.cwd)
+	}
+
+	#[inline]
+	fn resolve(&self, path: &PathBuf, sub: &str) -> PathBuf
+	{
+		let cwd = path.to_str().unwrap_or("").replace('\\', "/");
+		PathBuf::from(resolve_path(&cwd, sub))
+	}
+
+	fn css(&self, sub: &str) -> Option<(PathBuf, &str)>
+	{
+		let path = resolve_path(&self.cwd, sub);
+		let cwd = path_cwd(&path);
+		let content = frozen_map_get!(self.css_cache, path, || {
+			self.archive.string(&path).ok()
+		})?;
+		Some((PathBuf::from(cwd), content))
+	}
+
+	#[inline]
+	fn custom_style(&self) -> Option<&str>
+	{
+		self.custom_style
+	}
+}
+
+impl ChmBook {
+	fn new(archive: ChmArchive, loading_chapter: LoadingChapter,
+		custom_style: &Option<String>) -> Result<Self>
+	{
+		let (title, contents, default_topic) = archive.system_info();
+		let contents = contents
+			.map(|path| normalize_path(&path))
+			.filter(|path| archive.exists(path))
+			.or_else(|| archive.entries.keys()
+				.find(|name| name.ends_with(".hhc"))
+				.cloned());
+		let mut toc = match &contents {
+			Some(path) => parse_hhc(&archive.string(path)?),
+			None => vec![],
+		};
+
+		let mut chapters = vec![];
+		let mut chapter_map = HashMap::new();
+		for entry in &mut toc {
+			if let Some(src_file) = &entry.src_file {
+				if !archive.exists(src_file) {
+					continue;
+				}
+				let key = entry_key(src_file);
+				let chapter_index = *chapter_map.entry(key).or_insert_with(|| {
+					chapters.push(src_file.clone());
+					chapters.len() - 1
+				});
+				entry.chapter_index = Some(chapter_index);
+			}
+		}
+		if chapters.is_empty() {
+			if let Some(topic) = default_topic
+				.map(|path| normalize_path(&path))
+				.filter(|path| archive.exists(path)) {
+				chapters.push(topic);
+			} else {
+				let mut files: Vec<&String> = archive.entries.keys()
+					.filter(|name| is_html_file(name))
+					.collect();
+				files.sort();
+				chapters.extend(files.into_iter().cloned());
+			}
+		}
+		let chapter_count = chapters.len();
+		if chapter_count == 0 {
+			bail!("No content in chm.");
+		}
+		let chapter_index = match loading_chapter {
+			LoadingChapter::Index(index) => index.min(chapter_count - 1),
+			LoadingChapter::Last => chapter_count - 1,
+		};
+		let mut book = ChmBook {
+			archive,
+			title,
+			toc,
+			chapters,
+			chapter_cache: HashMap::new(),
+			css_cache: Default::default(),
+			font_families: Default::default(),
+			chapter_index,
+			#[cfg(feature = "gui")]
+			fonts: HtmlFonts::new(),
+			custom_style: custom_style.clone(),
+		};
+		if book.goto_chapter(chapter_index)?.is_none() {
+			book.goto_chapter(0)?;
+		}
+		Ok(book)
+	}
+
+	fn load_chapter(&mut self, chapter_index: usize) -> Result<&Chapter>
+	{
+		let chapter = match self.chapter_cache.entry(chapter_index) {
+			Entry::Occupied(o) => o.into_mut(),
+			Entry::Vacant(v) => {
+				let full_path = self.chapters.get(chapter_index)
+					.ok_or(ChapterError::anyhow("invalid index".to_string()))?;
+				let html_str = self.archive.string(full_path)?;
+				let resolve = ChmResolver {
+					cwd: path_cwd(full_path),
+					archive: &self.archive,
+					css_cache: &self.css_cache,
+					custom_style: self.custom_style.as_ref().map(|s| s.as_ref()),
+				};
+				#[allow(unused)]
+					let (html_content, mut font_faces) = html_parser::parse(HtmlParseOptions::new(&html_str)
+					.with_font_family(&mut self.font_families)
+					.with_resolver(&resolve))?;
+				#[cfg(feature = "gui")]
+				{
+					self.fonts.reload(font_faces, |path| {
+						let path = path.to_str()?.replace('\\', "/");
+						let content = self.archive.content(&path).ok()?;
+						Some(content.to_vec())
+					});
+				}
+				v.insert(html_content)
+			}
+		};
+		Ok(chapter)
+	}
+
+	fn target_position(&mut self, target_file: Option<&str>, target_anchor: Option<String>) -> Option<TraceInfo>
+	{
+		fn target_position_in_chapter(chapter_index: usize, chapter: &Chapter, target_anchor: &Option<String>) -> Option<TraceInfo> {
+			let position = chapter.id_position(target_anchor.as_ref()?)?;
+			Some(TraceInfo {
+				chapter: chapter_index,
+				line: position.line,
+				offset: position.offset,
+			})
+		}
+		let chapter_index = match target_file {
+			Some(target_file) => {
+				let key = entry_key(target_file);
+				self.chapters.iter().position(|path| entry_key(path) == key)?
+			}
+			None => self.chapter_index,
+		};
+		let chapter = self.load_chapter(chapter_index).ok()?;
+		match target_position_in_chapter(chapter_index, chapter, &target_anchor) {
+			Some(ti) => Some(ti),
+			None if target_file.is_some() => Some(TraceInfo {
+				chapter: chapter_index,
+				line: 0,
+				offset: 0,
+			}),
+			None => None,
+		}
+	}
+}
+
+fn read_directory(content: &[u8], dir_offset: usize) -> Result<HashMap<String, ChmEntry>>
+{
+	let header = content.get(dir_offset..)
+		.ok_or_else(|| anyhow!("Invalid chm directory."))?;
+	if header.len() < 0x54 || &header[0..4] != ITSP_SIGNATURE {
+		bail!("Invalid chm directory.");
+	}
+	let header_len = read_u32(header, 8)? as usize;
+	let chunk_size = read_u32(header, 16)? as usize;
+	let chunk_count = read_u32(header, 40)? as usize;
+	if chunk_size < 20 {
+		bail!("Invalid chm directory chunk size.");
+	}
+	let mut entries = HashMap::new();
+	for chunk_index in 0..chunk_count {
+		let Some(chunk) = chunk_index.checked_mul(chunk_size)
+			.and_then(|offset| offset.checked_add(header_len))
+			.and_then(|start| Some(start..start.checked_add(chunk_size)?))
+			.and_then(|range| header.get(range)) else {
+			break;
+		};
+		if &chunk[0..4] != PMGL_SIGNATURE {
+			continue;
+		}
+		let free_space = read_u32(chunk, 4)? as usize;
+		let end = chunk_size.saturating_sub(free_space);
+		let mut offset = 20;
+		while offset < end {
+			let name_len = read_encint(chunk, &mut offset)? as usize;
+			let name = offset.checked_add(name_len)
+				.and_then(|end| chunk.get(offset..end))
+				.ok_or_else(|| anyhow!("Invalid chm directory entry."))?;
+			let name = String::from_utf8_lossy(name).to_string();
+			offset += name_len;
+			let section = read_encint(chunk, &mut offset)?;
+			let entry_offset = read_encint(chunk, &mut offset)?;
+			let length = read_encint(chunk, &mut offset)?;
+			// skip directories
+			if name.ends_with('/') {
+				continue;
+			}
+			entries.insert(entry_key(&name), ChmEntry { section, offset: entry_offset, length });
+		}
+	}
+	Ok(entries)
+}
+
+/// toc in hhc: nested <ul> of <li><object type="text/sitemap">
+fn parse_hhc(text: &str) -> Vec<TocEntry>
+{
+	fn process(node: NodeRef<Node>, level: usize, toc: &mut Vec<TocEntry>)
+	{
+		for child in node.children() {
+			let Node::Element(element) = child.value() else {
+				continue;
+			};
+			match element.name() {
+				"ul" => process(child, level + 1, toc),
+				"object" if element.attr("type")
+					.map_or(false, |t| t.eq_ignore_ascii_case("text/sitemap")) => {
+					let mut title = None;
+					let mut local = None;
+					for param in child.children() {
+						let Node::Element(param) = param.value() else {
+							continue;
+						};
+						if param.name() != "param" {
+							continue;
+						}
+						let (Some(name), Some(value)) = (param.attr("name"), param.attr("value")) else {
+							continue;
+						};
+						if name.eq_ignore_ascii_case("name") {
+							title.get_or_insert_with(|| value.trim().to_owned());
+						} else if name.eq_ignore_ascii_case("local") {
+							local.get_or_insert_with(|| value.trim().to_owned());
+						}
+					}
+					let (src_file, src_anchor) = match &local {
+						Some(local) => {
+							let (file, anchor) = split_href(local);
+							(Some(normalize_path(file)), anchor)
+						}
+						None => (None, None),
+					};
+					let title = title
+						.filter(|title| !title.is_empty())
+						.or_else(|| src_file.clone())
+						.unwrap_or_else(|| "blank".to_owned());
+					toc.push(TocEntry {
+						title,
+						level: level.max(1),
+						src_file,
+						src_anchor,
+						chapter_index: None,
+					});
+				}
+				_ => process(child, level, toc),
+			}
+		}
+	}
+	let html = Html::parse_document(text);
+	let mut toc = vec![];
+	process(html.tree.root(), 0, &mut toc);
+	toc
+}
+
+#[inline]
+fn read_u32(data: &[u8], offset: usize) -> Result<u32>
+{
+	let bytes = offset.checked_add(4)
+		.and_then(|end| data.get(offset..end))
+		.ok_or_else(|| anyhow!("Invalid chm data."))?;
+	Ok(u32::from_le_bytes(bytes.try_into()?))
+}
+
+#[inline]
+fn read_u64(data: &[u8], offset: usize) -> Result<u64>
+{
+	let bytes = offset.checked_add(8)
+		.and_then(|end| data.get(offset..end))
+		.ok_or_else(|| anyhow!("Invalid chm data."))?;
+	Ok(u64::from_le_bytes(bytes.try_into()?))
+}
+
+/// variable length integer, 7 bits per byte, msb first
+fn read_encint(data: &[u8], offset: &mut usize) -> Result<u64>
+{
+	let mut value = 0u64;
+	loop {
+		let byte = *data.get(*offset)
+			.ok_or_else(|| anyhow!("Invalid chm data."))?;
+		*offset += 1;
+		value = (value << 7) | (byte & 0x7f) as u64;
+		if byte & 0x80 == 0 {
+			break Ok(value);
+		}
+	}
+}
+
+#[inline]
+fn entry_key(path: &str) -> String
+{
+	path.trim_start_matches('/').to_lowercase()
+}
+
+#[inline]
+fn is_html_file(name: &str) -> bool
+{
+	name.ends_with(".htm") || name.ends_with(".html") || name.ends_with(".xhtml")
+}
+
+/// split link into file and anchor, file in other chm(ms-its:other.chm::/file.htm) treat as in this chm
+fn split_href(href: &str) -> (&str, Option<String>)
+{
+	let href = match href.rfind("::") {
+		Some(index) => &href[index + 2..],
+		None => href,
+	};
+	let mut split = href.splitn(2, '#');
+	let file = split.next().unwrap_or("");
+	let anchor = split.next().map(|a| a.to_owned());
+	(file, anchor)
+}
+
+#[inline]
+fn path_cwd(path: &str) -> String
+{
+	match path.rfind('/') {
+		Some(index) => path[..index].to_owned(),
+		None => String::new(),
+	}
+}
+
+#[inline]
+fn normalize_path(path: &str) -> String
+{
+	resolve_path("", path)
+}
+
+fn resolve_path(cwd: &str, sub: &str) -> String
+{
+	let sub = sub.replace('\\', "/");
+	let mut parts: Vec<&str> = if sub.starts_with('/') {
+		vec![]
+	} else {
+		cwd.split('/').filter(|p| !p.is_empty()).collect()
+	};
+	for part in sub.split('/') {
+		match part {
+			"" | "." => {}
+			".." => { parts.pop(); }
+			_ => parts.push(part),
+		}
+	}
+	parts.join("/")
+}
+
+#[inline]
+fn get_reading(loading: BookLoadingInfo) -> ReadingInfo
+{
+	#[cfg(not(feature = "gui"))]
+	{ loading.get() }
+	#[cfg(feature = "gui")]
+	loading.get_or_init(|reading| {
+		reading.custom_color = true;
+		reading.custom_font = true;
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::book::chm::lzx::LzxDecoder;
+	use std::collections::HashMap;
+
+	use crate::book::chm::{read_directory, read_encint, resolve_path, split_href, ChmArchive, ChmEntry, COMPRESSED_CONTROL, ITSP_SIGNATURE, LZXC_SIGNATURE};
+
+	#[test]
+	fn test_read_encint()
+	{
+		let data = [0x05, 0x81, 0x00, 0xff, 0x7f, 0x80];
+		let mut offset = 0;
+		assert_eq!(read_encint(&data, &mut offset).unwrap(), 5);
+		assert_eq!(offset, 1);
+		assert_eq!(read_encint(&data, &mut offset).unwrap(), 0x80);
+		assert_eq!(offset, 3);
+		assert_eq!(read_encint(&data, &mut offset).unwrap(), 0x3fff);
+		assert_eq!(offset, 5);
+		// unterminated
+		assert!(read_encint(&data, &mut offset).is_err());
+	}
+
+	#[test]
+	fn test_resolve_path()
+	{
+		assert_eq!(resolve_path("html/sub", "../index.htm"), "html/index.htm");
+		assert_eq!(resolve_path("html", "./a/./b.htm"), "html/a/b.htm");
+		assert_eq!(resolve_path("html", "/root.htm"), "root.htm");
+		assert_eq!(resolve_path("html", "img\\logo.gif"), "html/img/logo.gif");
+		assert_eq!(resolve_path("", "../../a.htm"), "a.htm");
+		assert_eq!(split_href("ms-its:other.chm::/a/b.htm#top"), ("/a/b.htm", Some("top".to_owned())));
+		assert_eq!(split_href("b.htm"), ("b.htm", None));
+	}
+
+	#[test]
+	fn test_lzx_uncompressed_block()
+	{
+		// no intel header, block type 3, length 5, padded to 16 bits,
+		// then r0, r1, r2 and raw bytes
+		let mut frame = vec![0x00, 0x30, 0x50, 0x00];
+		for _ in 0..3 {
+			frame.extend_from_slice(&1u32.to_le_bytes());
+		}
+		frame.extend_from_slice(b"hello");
+		let mut decoder = LzxDecoder::new(15).unwrap();
+		let mut output = vec![];
+		decoder.decompress(&frame, 5, &mut output).unwrap();
+		assert_eq!(output, b"hello");
+
+		assert!(LzxDecoder::new(0).is_err());
+		assert!(LzxDecoder::new(22).is_err());
+	}
+
+	#[test]
+	fn test_read_directory_invalid_chunk_size()
+	{
+		let mut header = vec![0u8; 0x54];
+		header[0..4].copy_from_slice(ITSP_SIGNATURE);
+		header[8..12].copy_from_slice(&0x54u32.to_le_bytes());
+		header[16..20].copy_from_slice(&2u32.to_le_bytes());
+		header[40..44].copy_from_slice(&1u32.to_le_bytes());
+		assert!(read_directory(&header, 0).is_err());
+
+		// chunks out of range are ignored
+		header[16..20].copy_from_slice(&0x1000u32.to_le_bytes());
+		header[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(read_directory(&header, 0).unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_invalid_archive_entries()
+	{
+		let mut control = vec![0u8; 24];
+		control[4..8].copy_from_slice(LZXC_SIGNATURE);
+		control[8..12].copy_from_slice(&1u32.to_le_bytes());
+		control[12..16].copy_from_slice(&2u32.to_le_bytes());
+		control[16..20].copy_from_slice(&1u32.to_le_bytes());
+		control[20..24].copy_from_slice(&1u32.to_le_bytes());
+		let mut entries = HashMap::new();
+		entries.insert(COMPRESSED_CONTROL.to_owned(), ChmEntry { section: 0, offset: 0, length: 24 });
+		entries.insert("overflow.htm".to_owned(), ChmEntry { section: 0, offset: u64::MAX, length: 2 });
+		let archive = ChmArchive {
+			content: control,
+			data_offset: 0,
+			entries,
+			compressed: vec![],
+		};
+		// window size 1 is power of two but not a valid lzx window
+		assert!(archive.decompress().is_err());
+		assert!(archive.content("overflow.htm").is_err());
+	}
+}
@@ -0,0 +1,581 @@
+use anyhow::{bail, Result};
+
+/// LZX decompressor for the MSCompressed section of chm files,
+/// each call of decompress() output one frame(0x8000 bytes at most),
+/// state is kept between frames until reset()
+const MIN_MATCH: usize = 2;
+const NUM_CHARS: usize = 256;
+const NUM_PRIMARY_LENGTHS: usize = 7;
+const NUM_SECONDARY_LENGTHS: usize = 249;
+
+const BLOCK_TYPE_VERBATIM: u32 = 1;
+const BLOCK_TYPE_ALIGNED: u32 = 2;
+const BLOCK_TYPE_UNCOMPRESSED: u32 = 3;
+
+const PRETREE_MAX_SYMBOLS: usize = 20;
+const PRETREE_TABLE_BITS: usize = 6;
+const MAINTREE_MAX_SYMBOLS: usize = NUM_CHARS + 50 * 8;
+const MAINTREE_TABLE_BITS: usize = 12;
+const LENGTH_MAX_SYMBOLS: usize = NUM_SECONDARY_LENGTHS + 1;
+const LENGTH_TABLE_BITS: usize = 12;
+const ALIGNED_MAX_SYMBOLS: usize = 8;
+const ALIGNED_TABLE_BITS: usize = 7;
+
+struct HuffmanTree {
+	table_bits: usize,
+	max_symbols: usize,
+	lens: Vec<u8>,
+	table: Vec<u16>,
+}
+
+impl HuffmanTree {
+	fn new(max_symbols: usize, table_bits: usize) -> Self
+	{
+		HuffmanTree {
+			table_bits,
+			max_symbols,
+			lens: vec![0; max_symbols],
+			table: vec![0; (1 << table_bits) + (max_symbols << 1)],
+		}
+	}
+
+	fn reset(&mut self)
+	{
+		self.lens.fill(0);
+	}
+
+	/// build fast lookup table from code lengths, codes longer than
+	/// table_bits are stored as binary tree after the direct table
+	fn build(&mut self, symbols: usize) -> Result<()>
+	{
+		let table_bits = self.table_bits;
+		let lens = &self.lens;
+		let table = &mut self.table;
+		let mut pos = 0usize;
+		let mut table_mask = 1usize << table_bits;
+		let mut bit_mask = table_mask >> 1;
+		let mut next_symbol = bit_mask;
+		let mut bit_num = 1;
+		while bit_num <= table_bits {
+			for sym in 0..symbols {
+				if lens[sym] as usize == bit_num {
+					let leaf = pos;
+					pos += bit_mask;
+					if pos > table_mask {
+						bail!("Invalid lzx huffman table");
+					}
+					table[leaf..leaf + bit_mask].fill(sym as u16);
+				}
+			}
+			bit_mask >>= 1;
+			bit_num += 1;
+		}
+		if pos != table_mask {
+			table[pos..table_mask].fill(0);
+			pos <<= 16;
+			table_mask <<= 16;
+			bit_mask = 1 << 15;
+			while bit_num <= 16 {
+				for sym in 0..symbols {
+					if lens[sym] as usize == bit_num {
+						let mut leaf = pos >> 16;
+						for fill in 0..bit_num - table_bits {
+							if table[leaf] == 0 {
+								if (next_symbol << 1) + 1 >= table.len() {
+									bail!("Invalid lzx huffman table");
+								}
+								table[next_symbol << 1] = 0;
+								table[(next_symbol << 1) + 1] = 0;
+								table[leaf] = next_symbol as u16;
+								next_symbol += 1;
+							}
+							leaf = (table[leaf] as usize) << 1;
+							if (pos >> (15 - fill)) & 1 != 0 {
+								leaf += 1;
+							}
+						}
+						table[leaf] = sym as u16;
+						pos += bit_mask;
+						if pos > table_mask {
+							bail!("Invalid lzx huffman table");
+						}
+					}
+				}
+				bit_mask >>= 1;
+				bit_num += 1;
+			}
+		}
+		if pos == table_mask {
+			return Ok(());
+		}
+		// empty tree is valid
+		if lens[..symbols].iter().any(|len| *len != 0) {
+			bail!("Invalid lzx huffman table");
+		}
+		Ok(())
+	}
+}
+
+struct BitReader<'a> {
+	input: &'a [u8],
+	position: usize,
+	buffer: u32,
+	bits_left: usize,
+}
+
+impl<'a> BitReader<'a> {
+	#[inline]
+	fn new(input: &'a [u8]) -> Self
+	{
+		BitReader { input, position: 0, buffer: 0, bits_left: 0 }
+	}
+
+	#[inline]
+	fn reinit(&mut self)
+	{
+		self.buffer = 0;
+		self.bits_left = 0;
+	}
+
+	#[inline]
+	fn byte(&self, index: usize) -> u32
+	{
+		*self.input.get(index).unwrap_or(&0) as u32
+	}
+
+	/// input is read as little endian 16 bits words, msb first
+	#[inline]
+	fn ensure(&mut self, bits: usize)
+	{
+		while self.bits_left < bits {
+			let word = self.byte(self.position) | (self.byte(self.position + 1) << 8);
+			self.buffer |= word << (16 - self.bits_left);
+			self.bits_left += 16;
+			self.position += 2;
+		}
+	}
+
+	#[inline]
+	fn peek(&self, bits: usize) -> u32
+	{
+		self.buffer >> (32 - bits)
+	}
+
+	#[inline]
+	fn remove(&mut self, bits: usize)
+	{
+		self.buffer = if bits >= 32 { 0 } else { self.buffer << bits };
+		self.bits_left -= bits;
+	}
+
+	#[inline]
+	fn read(&mut self, bits: usize) -> u32
+	{
+		if bits == 0 {
+			return 0;
+		}
+		self.ensure(bits);
+		let value = self.peek(bits);
+		self.remove(bits);
+		value
+	}
+
+	fn read_symbol(&mut self, tree: &HuffmanTree) -> Result<usize>
+	{
+		self.ensure(16);
+		let mut symbol = tree.table[self.peek(tree.table_bits) as usize] as usize;
+		if symbol >= tree.max_symbols {
+			let mut mask = 1u32 << (32 - tree.table_bits);
+			loop {
+				mask >>= 1;
+				if mask == 0 {
+					bail!("Invalid lzx huffman code");
+				}
+				symbol <<= 1;
+				if self.buffer & mask != 0 {
+					symbol |= 1;
+				}
+				symbol = tree.table[symbol] as usize;
+				if symbol < tree.max_symbols {
+					break;
+				}
+			}
+		}
+		let len = tree.lens[symbol] as usize;
+		self.remove(len);
+		Ok(symbol)
+	}
+
+	#[inline]
+	fn read_u32_le(&mut self) -> u32
+	{
+		let value = self.byte(self.position)
+			| (self.byte(self.position + 1) << 8)
+			| (self.byte(self.position + 2) << 16)
+			| (self.byte(self.position + 3) << 24);
+		self.position += 4;
+		value
+	}
+}
+
+pub(super) struct LzxDecoder {
+	window: Vec<u8>,
+	window_position: usize,
+	r: [usize; 3],
+	main_elements: usize,
+	header_read: bool,
+	block_type: u32,
+	block_length: usize,
+	block_remaining: usize,
+	intel_file_size: i32,
+	intel_current_position: i32,
+	intel_started: bool,
+	frames_read: usize,
+
+	pre_tree: HuffmanTree,
+	main_tree: HuffmanTree,
+	length_tree: HuffmanTree,
+	aligned_tree: HuffmanTree,
+
+	extra_bits: [u8; 51],
+	position_base: [u32; 51],
+}
+
+impl LzxDecoder {
+	pub(super) fn new(window_bits: usize) -> Result<Self>
+	{
+		let position_slots = match window_bits {
+			15 => 30,
+			16 => 32,
+			17 => 34,
+			18 => 36,
+			19 => 38,
+			20 => 42,
+			21 => 50,
+			_ => bail!("Invalid lzx window size"),
+		};
+		let mut extra_bits = [0u8; 51];
+		let mut j = 0;
+		let mut i = 0;
+		while i < 50 {
+			extra_bits[i] = j;
+			extra_bits[i + 1] = j;
+			if i != 0 && j < 17 {
+				j += 1;
+			}
+			i += 2;
+		}
+		extra_bits[50] = j;
+		let mut position_base = [0u32; 51];
+		for i in 0..50 {
+			position_base[i + 1] = position_base[i] + (1 << extra_bits[i]);
+		}
+		let mut decoder = LzxDecoder {
+			window: vec![0; 1 << window_bits],
+			window_position: 0,
+			r: [1, 1, 1],
+			main_elements: NUM_CHARS + (position_slots << 3),
+			header_read: false,
+			block_type: 0,
+			block_length: 0,
+			block_remaining: 0,
+			intel_file_size: 0,
+			intel_current_position: 0,
+			intel_started: false,
+			frames_read: 0,
+			pre_tree: HuffmanTree::new(PRETREE_MAX_SYMBOLS, PRETREE_TABLE_BITS),
+			main_tree: HuffmanTree::new(MAINTREE_MAX_SYMBOLS, MAINTREE_TABLE_BITS),
+			length_tree: HuffmanTree::new(LENGTH_MAX_SYMBOLS, LENGTH_TABLE_BITS),
+			aligned_tree: HuffmanTree::new(ALIGNED_MAX_SYMBOLS, ALIGNED_TABLE_BITS),
+			extra_bits,
+			position_base,
+		};
+		decoder.reset();
+		Ok(decoder)
+	}
+
+	pub(super) fn reset(&mut self)
+	{
+		self.r = [1, 1, 1];
+		self.header_read = false;
+		self.frames_read = 0;
+		self.block_type = 0;
+		self.block_length = 0;
+		self.block_remaining = 0;
+		self.intel_current_position = 0;
+		self.intel_started = false;
+		self.window_position = 0;
+		self.main_tree.reset();
+		self.length_tree.reset();
+	}
+
+	/// decompress one frame from input, append output_len bytes to output
+	pub(super) fn decompress(&mut self, input: &[u8], output_len: usize,
+		output: &mut Vec<u8>) -> Result<()>
+	{
+		let window_size = self.window.len();
+		let window_mask = window_size - 1;
+		let mut bits = BitReader::new(input);
+		if !self.header_read {
+			self.intel_file_size = if bits.read(1) != 0 {
+				let high = bits.read(16);
+				let low = bits.read(16);
+				((high << 16) | low) as i32
+			} else {
+				0
+			};
+			self.header_read = true;
+		}
+
+		let mut togo = output_len;
+		while togo > 0 {
+			if self.block_remaining == 0 {
+				if self.block_type == BLOCK_TYPE_UNCOMPRESSED {
+					if self.block_length & 1 != 0 {
+						bits.position += 1;
+					}
+					bits.reinit();
+				}
+				self.block_type = bits.read(3);
+				let high = bits.read(16) as usize;
+				let low = bits.read(8) as usize;
+				self.block_length = (high << 8) | low;
+				self.block_remaining = self.block_length;
+				match self.block_type {
+					BLOCK_TYPE_ALIGNED => {
+						for i in 0..ALIGNED_MAX_SYMBOLS {
+							self.aligned_tree.lens[i] = bits.read(3) as u8;
+						}
+						self.aligned_tree.build(ALIGNED_MAX_SYMBOLS)?;
+						self.read_main_and_length_trees(&mut bits)?;
+					}
+					BLOCK_TYPE_VERBATIM => self.read_main_and_length_trees(&mut bits)?,
+					BLOCK_TYPE_UNCOMPRESSED => {
+						self.intel_started = true;
+						bits.ensure(16);
+						if bits.bits_left > 16 {
+							bits.position -= 2;
+						}
+						self.r[0] = bits.read_u32_le() as usize;
+						self.r[1] = bits.read_u32_le() as usize;
+						self.r[2] = bits.read_u32_le() as usize;
+					}
+					_ => bail!("Invalid lzx block type: {}", self.block_type),
+				}
+			}
+			if self.block_remaining == 0 {
+				continue;
+			}
+
+			let mut this_run = self.block_remaining.min(togo) as isize;
+			togo -= this_run as usize;
+			self.block_remaining -= this_run as usize;
+			self.window_position &= window_mask;
+			if self.window_position + this_run as usize > window_size {
+				bail!("Invalid lzx data");
+			}
+
+			match self.block_type {
+				BLOCK_TYPE_VERBATIM | BLOCK_TYPE_ALIGNED => {
+					let aligned = self.block_type == BLOCK_TYPE_ALIGNED;
+					while this_run > 0 {
+						let main_element = bits.read_symbol(&self.main_tree)?;
+						if main_element < NUM_CHARS {
+							self.window[self.window_position] = main_element as u8;
+							self.window_position += 1;
+							this_run -= 1;
+							continue;
+						}
+						let main_element = main_element - NUM_CHARS;
+						let mut match_length = main_element & NUM_PRIMARY_LENGTHS;
+						if match_length == NUM_PRIMARY_LENGTHS {
+							match_length += bits.read_symbol(&self.length_tree)?;
+						}
+						match_length += MIN_MATCH;
+						let match_offset = self.match_offset(main_element >> 3, aligned, &mut bits)?;
+						self.copy_match(match_offset, match_length);
+						this_run -= match_length as isize;
+					}
+				}
+				_ => {
+					let start = bits.position;
+					let len = this_run as usize;
+					if start + len > input.len() {
+						bail!("Invalid lzx uncompressed block");
+					}
+					self.window[self.window_position..self.window_position + len]
+						.copy_from_slice(&input[start..start + len]);
+					bits.position += len;
+					self.window_position += len;
+					this_run = 0;
+				}
+			}
+
+			// match run over the frame
+			if this_run < 0 {
+				let over = (-this_run) as usize;
+				if over > self.block_remaining {
+					bail!("Invalid lzx data");
+				}
+				self.block_remaining -= over;
+				togo = togo.saturating_sub(over);
+			}
+		}
+
+		let end = if self.window_position == 0 { window_size } else { self.window_position };
+		if end < output_len {
+			bail!("Invalid lzx frame");
+		}
+		let start = output.len();
+		output.extend_from_slice(&self.window[end - output_len..end]);
+		self.intel_e8_decode(&mut output[start..]);
+		Ok(())
+	}
+
+	fn read_main_and_length_trees(&mut self, bits: &mut BitReader) -> Result<()>
+	{
+		read_lengths(&mut self.pre_tree, &mut self.main_tree.lens, 0, NUM_CHARS, bits)?;
+		read_lengths(&mut self.pre_tree, &mut self.main_tree.lens, NUM_CHARS, self.main_elements, bits)?;
+		self.main_tree.build(self.main_elements)?;
+		if self.main_tree.lens[0xE8] != 0 {
+			self.intel_started = true;
+		}
+		read_lengths(&mut self.pre_tree, &mut self.length_tree.lens, 0, NUM_SECONDARY_LENGTHS, bits)?;
+		self.length_tree.build(NUM_SECONDARY_LENGTHS)
+	}
+
+	fn match_offset(&mut self, slot: usize, aligned: bool, bits: &mut BitReader) -> Result<usize>
+	{
+		let offset = match slot {
+			0 => return Ok(self.r[0]),
+			1 => {
+				self.r.swap(0, 1);
+				return Ok(self.r[0]);
+			}
+			2 => {
+				self.r.swap(0, 2);
+				return Ok(self.r[0]);
+			}
+			_ => {
+				let extra = self.extra_bits[slot] as usize;
+				let base = self.position_base[slot] as usize - 2;
+				if !aligned {
+					base + bits.read(extra) as usize
+				} else if extra > 3 {
+					let verbatim = bits.read(extra - 3) as usize;
+					let aligned = bits.read_symbol(&self.aligned_tree)?;
+					base + (verbatim << 3) + aligned
+				} else if extra == 3 {
+					base + bits.read_symbol(&self.aligned_tree)?
+				} else if extra > 0 {
+					base + bits.read(extra) as usize
+				} else {
+					1
+				}
+			}
+		};
+		self.r[2] = self.r[1];
+		self.r[1] = self.r[0];
+		self.r[0] = offset;
+		Ok(offset)
+	}
+
+	#[inline]
+	fn copy_match(&mut self, offset: usize, length: usize)
+	{
+		let mask = self.window.len() - 1;
+		for _ in 0..length {
+			let source = self.window_position.wrapping_sub(offset) & mask;
+			self.window[self.window_position & mask] = self.window[source];
+			self.window_position += 1;
+		}
+	}
+
+	fn intel_e8_decode(&mut self, data: &mut [u8])
+	{
+		let len = data.len();
+		if !self.intel_started || self.intel_file_size == 0 {
+			return;
+		}
+		self.frames_read += 1;
+		if self.frames_read > 32768 {
+			return;
+		}
+		if len <= 10 {
+			self.intel_current_position += len as i32;
+			return;
+		}
+		let file_size = self.intel_file_size;
+		let mut current = self.intel_current_position;
+		self.intel_current_position = current + len as i32;
+		let mut i = 0;
+		while i < len - 10 {
+			if data[i] != 0xE8 {
+				i += 1;
+				current += 1;
+				continue;
+			}
+			i += 1;
+			let absolute = i32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+			if absolute >= -current && absolute < file_size {
+				let relative = if absolute >= 0 {
+					absolute - current
+				} else {
+					absolute + file_size
+				};
+				data[i..i + 4].copy_from_slice(&relative.to_le_bytes());
+			}
+			i += 4;
+			current += 5;
+		}
+	}
+}
+
+/// code lengths are delta encoded with pre tree
+fn read_lengths(pre_tree: &mut HuffmanTree, lens: &mut [u8], first: usize, last: usize,
+	bits: &mut BitReader) -> Result<()>
+{
+	for i in 0..PRETREE_MAX_SYMBOLS {
+		pre_tree.lens[i] = bits.read(4) as u8;
+	}
+	pre_tree.build(PRETREE_MAX_SYMBOLS)?;
+
+	#[inline]
+	fn delta(len: u8, code: usize) -> u8
+	{
+		((len as usize + 17 - code) % 17) as u8
+	}
+	let mut x = first;
+	while x < last {
+		let code = bits.read_symbol(pre_tree)?;
+		match code {
+			17 => {
+				let count = bits.read(4) as usize + 4;
+				fill(lens, &mut x, last, count, 0);
+			}
+			18 => {
+				let count = bits.read(5) as usize + 20;
+				fill(lens, &mut x, last, count, 0);
+			}
+			19 => {
+				let count = bits.read(1) as usize + 4;
+				let code = bits.read_symbol(pre_tree)?;
+				let len = delta(lens[x], code);
+				fill(lens, &mut x, last, count, len);
+			}
+			_ => {
+				lens[x] = delta(lens[x], code);
+				x += 1;
+			}
+		}
+	}
+	Ok(())
+}
+
+#[inline]
+fn fill(lens: &mut [u8], x: &mut usize, last: usize, count: usize, len: u8)
+{
+	let end = (*x + count).min(last);
+	lens[*x..end].fill(len);
+	*x = end;
+}
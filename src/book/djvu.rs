@@ -0,0 +1,327 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+#[cfg(feature = "gui")]
+use elsa::FrozenMap;
+
+use crate::book::{Book, IMAGE_CHAR, Line, LoadingChapter, Loader, TocInfo};
+#[cfg(feature = "gui")]
+use crate::book::ImageData;
+use crate::common::{plain_text, txt_lines, TraceInfo};
+use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::html_parser::{ImageStyle, TextStyle};
+use crate::list::ListIterator;
+
+/// DjVu documents are decoded by the djvulibre command line tools:
+///     djvused: for page count
+///     djvutxt: for text layer(OCR) of page
+///     ddjvu:   for page image
+/// each page as a chapter, with page image and its text layer
+const DJVU_MAGIC: &[u8] = b"AT&TFORM";
+const DJVUSED: &str = "djvused";
+const DJVUTXT: &str = "djvutxt";
+#[cfg(feature = "gui")]
+const DDJVU: &str = "ddjvu";
+#[cfg(feature = "gui")]
+const PAGE_IMAGE_SIZE: &str = "-size=1600x2400";
+const PAGE_IMAGE_EXT: &str = ".pnm";
+
+static TEMP_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) struct DjvuLoader {
+	extensions: Vec<&'static str>,
+}
+
+impl DjvuLoader {
+	#[inline]
+	pub(crate) fn new() -> Self
+	{
+		let extensions = vec![".djvu", ".djv"];
+		DjvuLoader { extensions }
+	}
+}
+
+impl Loader for DjvuLoader {
+	#[inline]
+	fn extensions(&self) -> &Vec<&'static str>
+	{
+		&self.extensions
+	}
+
+	#[inline]
+	fn format(&self) -> &str
+	{
+		"djvu"
+	}
+
+	#[inline]
+	fn sniff(&self, head: &[u8]) -> bool
+	{
+		head.starts_with(DJVU_MAGIC)
+	}
+
+	fn load_file(&self, filename: &str, file: fs::File,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		// for book in folder container, the reading filename is the folder
+		let path = PathBuf::from_str(loading.filename())?;
+		if path.is_file() && self.support(loading.filename()) {
+			let book = DjvuBook::new(DjvuFile::Origin(path), loading_chapter)?;
+			Ok((Box::new(book), loading.get()))
+		} else {
+			let mut file = file;
+			let mut content = vec![];
+			std::io::Read::read_to_end(&mut file, &mut content)?;
+			self.load_buf(filename, content, loading_chapter, loading)
+		}
+	}
+
+	fn load_buf(&self, _filename: &str, content: Vec<u8>,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		let index = TEMP_INDEX.fetch_add(1, Ordering::Relaxed);
+		let path = std::env::temp_dir()
+			.join(format!("tbr-{}-{}.djvu", std::process::id(), index));
+		fs::write(&path, content)?;
+		let book = DjvuBook::new(DjvuFile::Temp(path), loading_chapter)?;
+		Ok((Box::new(book), loading.get()))
+	}
+}
+
+enum DjvuFile {
+	Origin(PathBuf),
+	// extracted from container, delete when book dropped
+	Temp(PathBuf),
+}
+
+impl DjvuFile {
+	#[inline]
+	fn path(&self) -> &Path
+	{
+		match self {
+			DjvuFile::Origin(path) | DjvuFile::Temp(path) => path,
+		}
+	}
+}
+
+impl Drop for DjvuFile {
+	fn drop(&mut self)
+	{
+		if let DjvuFile::Temp(path) = self {
+			if let Err(err) = fs::remove_file(&path) {
+				eprintln!("Failed delete temp file: {:#?}: {}", path, err.to_string());
+			}
+		}
+	}
+}
+
+struct DjvuBook {
+	file: DjvuFile,
+	page_titles: Vec<String>,
+	pages: Vec<Option<Vec<Line>>>,
+	page_index: usize,
+	#[cfg(feature = "gui")]
+	images: FrozenMap<String, Vec<u8>>,
+}
+
+impl DjvuBook {
+	fn new(file: DjvuFile, loading_chapter: LoadingChapter) -> Result<Self>
+	{
+		let output = run(DJVUSED, &["-e", "n"], file.path())?;
+		let page_count = usize::from_str(output.trim())
+			.map_err(|_| anyhow!("Invalid djvu page count: {}", output))?;
+		if page_count == 0 {
+			bail!("No page in djvu document.");
+		}
+		let page_titles = (1..=page_count)
+			.map(|page| format!("{}", page))
+			.collect();
+		let mut pages = Vec::with_capacity(page_count);
+		pages.resize_with(page_count, || None);
+		let page_index = match loading_chapter {
+			LoadingChapter::Index(index) => index,
+			LoadingChapter::Last => page_count - 1,
+		};
+		let mut book = DjvuBook {
+			file,
+			page_titles,
+			pages,
+			page_index: 0,
+			#[cfg(feature = "gui")]
+			images: FrozenMap::new(),
+		};
+		if book.goto_chapter(page_index)?.is_none() {
+			book.goto_chapter(0)?;
+		}
+		Ok(book)
+	}
+
+	fn load_page(&self, page_index: usize) -> Result<Vec<Line>>
+	{
+		let page_arg = format!("--page={}", page_index + 1);
+		let text = run(DJVUTXT, &[&page_arg], self.file.path())?;
+		let mut lines = vec![];
+		let mut image_line = Line::default();
+		image_line.push(IMAGE_CHAR);
+		let href = page_image_href(page_index);
+		image_line.push_style(TextStyle::Image(ImageStyle::new(&href, None, None, None)), 0..1);
+		lines.push(image_line);
+		for line in txt_lines(&text) {
+			if !line.is_empty() {
+				lines.push(line);
+			}
+		}
+		Ok(lines)
+	}
+
+	#[cfg(feature = "gui")]
+	fn render_page(&self, page_index: usize) -> Option<Vec<u8>>
+	{
+		let index = TEMP_INDEX.fetch_add(1, Ordering::Relaxed);
+		let target = std::env::temp_dir()
+			.join(format!("tbr-{}-{}{}", std::process::id(), index, PAGE_IMAGE_EXT));
+		let page_arg = format!("-page={}", page_index + 1);
+		let status = Command::new(DDJVU)
+			.arg("-format=pnm")
+			.arg(PAGE_IMAGE_SIZE)
+			.arg(&page_arg)
+			.arg(self.file.path())
+			.arg(&target)
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.status()
+			.ok()?;
+		let bytes = if status.success() {
+			fs::read(&target).ok()
+		} else {
+			None
+		};
+		let _ = fs::remove_file(&target);
+		bytes
+	}
+}
+
+impl Book for DjvuBook {
+	#[inline]
+	fn chapter_count(&self) -> usize
+	{
+		self.pages.len()
+	}
+
+	fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
+	{
+		match self.pages.get(chapter_index) {
+			Some(Some(_)) => {}
+			Some(None) => {
+				let lines = self.load_page(chapter_index)?;
+				self.pages[chapter_index] = Some(lines);
+			}
+			None => return Ok(None),
+		}
+		self.page_index = chapter_index;
+		Ok(Some(chapter_index))
+	}
+
+	#[inline]
+	fn current_chapter(&self) -> usize
+	{
+		self.page_index
+	}
+
+	#[inline]
+	fn title(&self, _line: usize, _offset: usize) -> Option<&str>
+	{
+		Some(self.page_titles.get(self.page_index)?.as_str())
+	}
+
+	#[inline]
+	fn toc_index(&self, _line: usize, _offset: usize) -> usize
+	{
+		self.page_index
+	}
+
+	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo<'_>> + '_>>
+	{
+		let iter = ListIterator::new(|index| {
+			let title = self.page_titles.get(index)?;
+			Some(TocInfo { title, index, level: 1 })
+		});
+		Some(Box::new(iter))
+	}
+
+	#[inline]
+	fn toc_position(&mut self, toc_index: usize) -> Option<TraceInfo>
+	{
+		if toc_index < self.pages.len() {
+			Some(TraceInfo { chapter: toc_index, line: 0, offset: 0 })
+		} else {
+			None
+		}
+	}
+
+	fn lines(&self) -> &Vec<Line>
+	{
+		match self.pages.get(self.page_index) {
+			Some(Some(lines)) => lines,
+			Some(None) | None => panic!("page not loaded before using."),
+		}
+	}
+
+	#[inline]
+	fn leading_space(&self) -> usize
+	{
+		0
+	}
+
+	#[cfg(feature = "gui")]
+	fn image<'a>(&'a self, href: &'a str) -> Option<ImageData<'a>>
+	{
+		let page_index = page_index_for_href(href)?;
+		let key = href.to_owned();
+		let bytes = crate::frozen_map_get!(self.images, key, true, || {
+			self.render_page(page_index)
+		})?;
+		Some(ImageData::Borrowed((href.into(), bytes)))
+	}
+}
+
+#[inline]
+fn page_image_href(page_index: usize) -> String
+{
+	format!("page-{}{}", page_index + 1, PAGE_IMAGE_EXT)
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn page_index_for_href(href: &str) -> Option<usize>
+{
+	let page = href
+		.strip_prefix("page-")?
+		.strip_suffix(PAGE_IMAGE_EXT)?;
+	let page = usize::from_str(page).ok()?;
+	if page == 0 {
+		None
+	} else {
+		Some(page - 1)
+	}
+}
+
+fn run(program: &str, args: &[&str], path: &Path) -> Result<String>
+{
+	let output = Command::new(program)
+		.args(args)
+		.arg(path)
+		.stderr(Stdio::null())
+		.output()
+		.map_err(|e| anyhow!("Failed run {} of djvulibre: {}", program, e.to_string()))?;
+	if !output.status.success() {
+		bail!("Failed run {} for {:#?}", program, path);
+	}
+	plain_text(output.stdout, false)
+}
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Cursor;
 use std::io::Read;
@@ -12,26 +12,40 @@ use anyhow::{anyhow, bail, Result};
 use elsa::FrozenMap;
 use indexmap::IndexSet;
 use roxmltree::{Children, ExpandedName, Node};
+use zip::result::ZipError;
 use zip::ZipArchive;
 
 use crate::book::{Book, LoadingChapter, ChapterError, Line, Loader, TocInfo, ImageData};
 #[cfg(feature = "gui")]
+use crate::book::{LandmarkInfo, MediaClip, NoteInfo};
+#[cfg(feature = "gui")]
 use crate::html_parser::BlockStyle;
 use crate::html_parser::{HtmlContent, HtmlParseOptions, HtmlResolver, parse_xml};
 use crate::list::ListIterator;
-use crate::common::TraceInfo;
+use crate::common::{TraceInfo, zip_entry_names};
+#[cfg(feature = "gui")]
+use crate::common::{detect_language, primary_language};
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::{frozen_map_get, html_parser};
 #[cfg(feature = "gui")]
 use crate::gui::HtmlFonts;
 use crate::xhtml::xhtml_to_html;
 
+const CHAPTER_MEDIA_TYPE: &str = "application/xhtml+xml";
+// items of wrong media type with these extensions still read as chapters
+const CHAPTER_EXTENSIONS: [&str; 3] = [".xhtml", ".html", ".htm"];
+
 struct ManifestItem {
 	#[allow(unused)]
 	id: String,
 	href: String,
 	media_type: String,
 	properties: Option<String>,
+	// id of SMIL item for EPUB3 media overlay
+	#[cfg(feature = "gui")]
+	media_overlay: Option<String>,
+	// id of item used if media type not supported
+	fallback: Option<String>,
 }
 
 type ItemId = String;
@@ -45,7 +59,13 @@ struct ContentOPF {
 	pub language: String,
 	pub manifest: Manifest,
 	pub spine: Spine,
+	// spine index of linear="no" items, reachable by links only
+	pub non_linear: HashSet<usize>,
 	pub toc_id: Option<String>,
+	#[cfg(feature = "gui")]
+	pub guide: Vec<Landmark>,
+	// broken items skipped or repaired when parsing
+	pub problems: Vec<String>,
 }
 
 struct NavPoint {
@@ -60,10 +80,37 @@ struct NavPoint {
 	first_chapter_index: usize,
 }
 
+/// print page from page-list nav or pageList of ncx
+struct PageTarget {
+	label: String,
+	src_file: String,
+	src_anchor: Option<String>,
+	chapter_index: Option<usize>,
+}
+
+/// landmarks nav or guide of epub2
+#[cfg(feature = "gui")]
+struct Landmark {
+	kind: String,
+	title: String,
+	src_file: String,
+	src_anchor: Option<String>,
+}
+
 type Chapter = HtmlContent;
 
+// uncompressed mimetype as the first entry of zip
+const EPUB_MAGIC: &[u8] = b"mimetypeapplication/epub+zip";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+const ENCRYPTION_FILE: &str = "META-INF/encryption.xml";
+// fonts obfuscated by these, text still readable
+const FONT_OBFUSCATION_ALGORITHMS: [&str; 2] = [
+	"http://www.idpf.org/2008/embedding",
+	"http://ns.adobe.com/pdf/enc#RC",
+];
+
 trait EpubArchive {
-	fn is_encrypted(&self) -> bool;
 	fn content(&self, path: &str) -> Result<Vec<u8>>;
 	fn string(&self, path: &str) -> Result<String>
 	{
@@ -75,39 +122,49 @@ trait EpubArchive {
 
 struct EpubZipArchive<R: Read + Seek> {
 	zip: RefCell<ZipArchive<R>>,
+	// entry index by translated name, for names not in utf-8
+	names: HashMap<String, usize>,
+	password: Option<String>,
 }
 
 impl<R: Read + Seek> EpubZipArchive<R> {
 	#[inline]
-	fn new(reader: R) -> Result<Self>
+	fn new(reader: R, password: Option<&str>) -> Result<Self>
 	{
-		let zip = ZipArchive::new(reader)?;
-		Ok(EpubZipArchive { zip: RefCell::new(zip) })
+		let mut zip = ZipArchive::new(reader)?;
+		let names = zip_entry_names(&mut zip)?
+			.into_iter()
+			.enumerate()
+			.map(|(index, name)| (name, index))
+			.collect();
+		let password = password.map(|password| password.to_owned());
+		Ok(EpubZipArchive { zip: RefCell::new(zip), names, password })
 	}
 }
 
 impl<R: Read + Seek> EpubArchive for EpubZipArchive<R> {
-	#[inline]
-	fn is_encrypted(&self) -> bool
-	{
-		self.zip.borrow().file_names().find(|f| *f == "META-INF/encryption.xml").is_some()
-	}
-
 	fn content(&self, path: &str) -> Result<Vec<u8>>
 	{
-		match self.zip.borrow_mut().by_name(path) {
+		let mut zip = self.zip.borrow_mut();
+		let file = match (self.names.get(path), &self.password) {
+			(Some(index), Some(password)) => zip.by_index_decrypt(*index, password.as_bytes()),
+			(Some(index), None) => zip.by_index(*index),
+			(None, _) => Err(ZipError::FileNotFound),
+		};
+		match file {
 			Ok(mut file) => {
 				let mut buf = vec![];
 				file.read_to_end(&mut buf)?;
 				Ok(buf)
 			}
-			Err(e) => Err(anyhow!("failed load {}: {}", path, e.to_string())),
+			// keep zip error for asking password
+			Err(e) => Err(anyhow::Error::new(e).context(format!("failed load {}", path))),
 		}
 	}
 
 	fn exists(&self, path: &str) -> bool
 	{
-		self.zip.borrow().index_for_name(path).is_some()
+		self.names.contains_key(path)
 	}
 }
 
@@ -146,12 +203,6 @@ impl EpubExtractedArchive {
 }
 
 impl EpubArchive for EpubExtractedArchive {
-	#[inline]
-	fn is_encrypted(&self) -> bool
-	{
-		self.exists("META-INF/encryption.xml")
-	}
-
 	#[inline]
 	fn content(&self, path: &str) -> Result<Vec<u8>>
 	{
@@ -175,7 +226,12 @@ struct EpubBook {
 	archive: Box<dyn EpubArchive>,
 	content_opf: ContentOPF,
 	toc: Vec<NavPoint>,
+	pages: Vec<PageTarget>,
+	#[cfg(feature = "gui")]
+	landmarks: Vec<Landmark>,
 	chapter_cache: HashMap<usize, Chapter>,
+	#[cfg(feature = "gui")]
+	media_clips: HashMap<usize, Vec<MediaClip>>,
 	css_cache: FrozenMap<String, String>,
 	images: FrozenMap<String, Vec<u8>>,
 	font_families: IndexSet<String>,
@@ -183,6 +239,8 @@ struct EpubBook {
 	#[cfg(feature = "gui")]
 	fonts: HtmlFonts,
 	custom_style: Option<String>,
+	vertical_writing: bool,
+	problems: Vec<String>,
 }
 
 pub struct EpubLoader {
@@ -204,6 +262,18 @@ impl Loader for EpubLoader {
 		&self.extensions
 	}
 
+	#[inline]
+	fn format(&self) -> &str
+	{
+		"epub"
+	}
+
+	fn sniff(&self, head: &[u8]) -> bool
+	{
+		head.starts_with(ZIP_MAGIC)
+			&& head.windows(EPUB_MAGIC.len()).any(|window| window == EPUB_MAGIC)
+	}
+
 	fn support(&self, filename: &str) -> bool
 	{
 		if filename.to_lowercase().ends_with(".epub") {
@@ -221,7 +291,7 @@ impl Loader for EpubLoader {
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let archive: Box<dyn EpubArchive> = if filename.to_lowercase().ends_with(".epub") {
-			Box::new(EpubZipArchive::new(file)?)
+			Box::new(EpubZipArchive::new(file, None)?)
 		} else {
 			Box::new(EpubExtractedArchive::new(filename)?)
 		};
@@ -237,18 +307,69 @@ impl Loader for EpubLoader {
 		if !filename.to_lowercase().ends_with(".epub") {
 			bail!("Not support extracted epub in other container.")
 		}
-		let archive = EpubZipArchive::new(Cursor::new(content))?;
+		let archive = EpubZipArchive::new(Cursor::new(content), None)?;
+		let reading = get_reading(loading);
+		let book = EpubBook::new(Box::new(archive), loading_chapter, &reading.custom_style)?;
+		Ok((Box::new(book), reading))
+	}
+
+	fn load_encrypted(&self, filename: &str, content: Vec<u8>, password: &str,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		if !filename.to_lowercase().ends_with(".epub") {
+			bail!("Not support extracted epub in other container.")
+		}
+		let archive = EpubZipArchive::new(Cursor::new(content), Some(password))?;
 		let reading = get_reading(loading);
 		let book = EpubBook::new(Box::new(archive), loading_chapter, &reading.custom_style)?;
 		Ok((Box::new(book), reading))
 	}
 }
 
+/// drm scheme of the epub, obfuscated fonts only is not drm
+fn drm_scheme(archive: &dyn EpubArchive) -> Option<&'static str>
+{
+	if !archive.exists(ENCRYPTION_FILE) {
+		return None;
+	}
+	let scheme = if archive.exists("META-INF/rights.xml") {
+		"Adobe ADEPT"
+	} else if archive.exists("META-INF/sinf.xml") {
+		"Apple FairPlay"
+	} else {
+		"unknown"
+	};
+	let Ok(text) = archive.string(ENCRYPTION_FILE) else {
+		return Some(scheme);
+	};
+	let Ok(doc) = parse_xml(&text) else {
+		return Some(scheme);
+	};
+	let fonts_only = doc.descendants()
+		.filter(|node| node.tag_name().name() == "EncryptionMethod")
+		.all(|node| node.attribute("Algorithm")
+			.map_or(false, |algorithm| FONT_OBFUSCATION_ALGORITHMS.contains(&algorithm)));
+	if fonts_only {
+		None
+	} else {
+		Some(scheme)
+	}
+}
+
 impl Book for EpubBook {
 	#[inline]
 	fn name(&self) -> Option<&str>
 	{
-		Some(&self.content_opf.title)
+		let title = &self.content_opf.title;
+		if title.is_empty() { None } else { Some(title) }
+	}
+
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn author(&self) -> Option<&str>
+	{
+		self.content_opf.author.as_deref()
 	}
 
 	#[inline]
@@ -265,6 +386,28 @@ impl Book for EpubBook {
 				return Ok(None);
 			} else {
 				current -= 1;
+				if self.content_opf.non_linear.contains(&current) {
+					continue;
+				}
+				let chapter = self.load_chapter(current)?;
+				let lines_count = chapter.lines().len();
+				if lines_count > 0 {
+					self.chapter_index = current;
+					return Ok(Some(current));
+				}
+			}
+		}
+	}
+
+	/// non-linear chapters skipped
+	fn next_chapter(&mut self) -> Result<Option<usize>>
+	{
+		let mut current = self.chapter_index + 1;
+		let chapter_count = self.chapter_count();
+		loop {
+			if current >= chapter_count {
+				return Ok(None);
+			} else if !self.content_opf.non_linear.contains(&current) {
 				let chapter = self.load_chapter(current)?;
 				let lines_count = chapter.lines().len();
 				if lines_count > 0 {
@@ -272,6 +415,7 @@ impl Book for EpubBook {
 					return Ok(Some(current));
 				}
 			}
+			current += 1;
 		}
 	}
 
@@ -368,6 +512,74 @@ impl Book for EpubBook {
 		&self.chapter_cache.get(&self.chapter_index).unwrap().lines()
 	}
 
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn notes(&self) -> Option<&Vec<NoteInfo>>
+	{
+		Some(self.chapter_cache.get(&self.chapter_index)?.notes())
+	}
+
+	fn page_label(&self, line: usize, offset: usize) -> Option<&str>
+	{
+		let chapter = self.chapter_cache.get(&self.chapter_index)?;
+		let mut label = None;
+		// pages are in reading order
+		for page in &self.pages {
+			match page.chapter_index {
+				Some(chapter_index) if chapter_index < self.chapter_index =>
+					label = Some(page.label.as_str()),
+				Some(chapter_index) if chapter_index == self.chapter_index => {
+					if let Some(anchor) = &page.src_anchor {
+						if let Some(position) = chapter.id_position(anchor) {
+							if position.line > line || (position.line == line && position.offset > offset) {
+								break;
+							}
+						}
+					}
+					label = Some(page.label.as_str());
+				}
+				Some(_) => break,
+				None => {}
+			}
+		}
+		label
+	}
+
+	fn page_position(&mut self, label: &str) -> Option<TraceInfo>
+	{
+		let page = self.pages.iter().find(|page| page.label == label)?;
+		let src_file = page.src_file.clone();
+		let src_anchor = page.src_anchor.clone();
+		self.target_position(Some(&src_file), src_anchor)
+	}
+
+	#[cfg(feature = "gui")]
+	fn landmarks(&self) -> Option<Vec<LandmarkInfo<'_>>>
+	{
+		if self.landmarks.is_empty() {
+			return None;
+		}
+		let landmarks = self.landmarks
+			.iter()
+			.enumerate()
+			.map(|(index, landmark)| LandmarkInfo {
+				title: &landmark.title,
+				kind: &landmark.kind,
+				index,
+			})
+			.collect();
+		Some(landmarks)
+	}
+
+	#[cfg(feature = "gui")]
+	fn landmark_position(&mut self, index: usize) -> Option<TraceInfo>
+	{
+		let landmark = self.landmarks.get(index)?;
+		let src_file = landmark.src_file.clone();
+		let src_anchor = landmark.src_anchor.clone();
+		self.target_position(Some(&src_file), src_anchor)
+	}
+
 	fn link_position(&mut self, line: usize, link_index: usize) -> Option<TraceInfo>
 	{
 		let full_path = chapter_path(self.chapter_index, &self.content_opf).ok()?;
@@ -402,12 +614,62 @@ impl Book for EpubBook {
 		}
 	}
 
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn media_clips(&self) -> Option<&Vec<MediaClip>>
+	{
+		self.media_clips.get(&self.chapter_index)
+	}
+
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn audio(&self, path: &str) -> Option<Vec<u8>>
+	{
+		self.archive.content(path).ok()
+	}
+
+	#[cfg(feature = "gui")]
+	fn media(&self, href: &str) -> Option<(String, Vec<u8>)>
+	{
+		let path = chapter_path(self.current_chapter(), &self.content_opf).ok()?;
+		let full_path = concat_path_str(path_cwd(path), href)?;
+		let bytes = self.archive.content(&full_path).ok()?;
+		Some((full_path, bytes))
+	}
+
 	#[inline]
 	fn font_family_names(&self) -> Option<&IndexSet<String>>
 	{
 		Some(&self.font_families)
 	}
 
+	#[inline]
+	fn problems(&self) -> Option<&Vec<String>>
+	{
+		if self.problems.is_empty() { None } else { Some(&self.problems) }
+	}
+
+	#[inline]
+	fn vertical_writing(&self) -> bool
+	{
+		self.vertical_writing || self.chapter_cache.get(&self.chapter_index)
+			.map_or(false, |chapter| chapter.writing_mode().vertical)
+	}
+
+	#[inline]
+	fn upright_text(&self) -> bool
+	{
+		self.chapter_cache.get(&self.chapter_index)
+			.map_or(false, |chapter| chapter.writing_mode().upright)
+	}
+
+	#[cfg(feature = "gui")]
+	fn language(&self) -> Option<String>
+	{
+		primary_language(&self.content_opf.language)
+			.or_else(|| detect_language(self.lines()).map(|language| language.to_owned()))
+	}
+
 	#[cfg(feature = "gui")]
 	#[inline]
 	fn color_customizable(&self) -> bool
@@ -492,8 +754,8 @@ impl EpubBook {
 	pub fn new(archive: Box<dyn EpubArchive>, loading_chapter: LoadingChapter,
 		custom_style: &Option<String>) -> Result<Self>
 	{
-		if archive.is_encrypted() {
-			return Err(anyhow!("Encrypted epub."));
+		if let Some(scheme) = drm_scheme(archive.as_ref()) {
+			bail!("Can not open DRM protected epub, scheme: {}.", scheme);
 		}
 		let container_text = archive.string("META-INF/container.xml")?;
 		let doc = parse_xml(&container_text)?;
@@ -506,36 +768,33 @@ impl EpubBook {
 			None => PathBuf::new(),
 		};
 		let content_opf_text = archive.string(&content_opf_path)?;
-		let content_opf = parse_content_opf(&content_opf_text, &content_opf_dir, archive.as_ref())
+		let mut content_opf = parse_content_opf(&content_opf_text, &content_opf_dir, archive.as_ref())
 			.map_err(|e| anyhow!("Malformatted content.opf file: {}", e.to_string()))?;
 
-		let mut toc = match content_opf.manifest.get(content_opf.toc_id.as_ref().unwrap_or(&"ncx".to_string())) {
-			Some(ManifestItem { href, .. }) => {
-				let ncx_text = archive.string(href)?;
-				let cwd = path_cwd(href);
-				parse_ncx(&ncx_text, &cwd)?
-			}
-			None => {
-				let mut toc = None;
-				for (_id, item) in &content_opf.manifest {
-					if let Some(properties) = &item.properties {
-						if properties.contains("nav") {
-							let nav_text = archive.string(&item.href)?;
-							let cwd = path_cwd(&item.href);
-							toc = Some(parse_nav_doc(&nav_text, &cwd)?);
-							break;
-						}
-					}
-				}
-				if let Some(toc) = toc {
-					toc
-				} else {
-					return Err(anyhow!("Invalid content.opf file, no ncx or nav"));
-				}
+		if content_opf.spine.is_empty() {
+			bail!("No readable item in spine");
+		}
+		let mut problems = std::mem::take(&mut content_opf.problems);
+		let mut toc = match load_toc(&content_opf, archive.as_ref()) {
+			Ok(toc) => toc,
+			Err(err) => {
+				problems.push(format!("Failed load toc: {}, spine items used instead", err));
+				spine_toc(&content_opf)
 			}
 		};
 
+		let mut pages = load_pages(&content_opf, archive.as_ref());
+		#[cfg(feature = "gui")]
+		let landmarks = load_landmarks(&mut content_opf, archive.as_ref());
+
 		let chapter_count = content_opf.spine.len();
+		for page in &mut pages {
+			page.chapter_index = content_opf.spine
+				.iter()
+				.position(|id| content_opf.manifest
+					.get(id)
+					.map_or(false, |item| item.href == page.src_file));
+		}
 
 		let mut chapter_index = 0;
 		for np in &mut toc {
@@ -555,17 +814,26 @@ impl EpubBook {
 
 		let mut chapter_index = match loading_chapter {
 			LoadingChapter::Index(index) => index,
-			LoadingChapter::Last => chapter_count - 1,
+			LoadingChapter::Last => (0..chapter_count)
+				.rev()
+				.find(|index| !content_opf.non_linear.contains(index))
+				.unwrap_or(chapter_count - 1),
 		};
 		if chapter_index >= chapter_count {
 			chapter_index = chapter_count - 1;
 		}
 		let chapter_cache = HashMap::new();
+		let vertical_writing = vertical_writing(&content_opf, archive.as_ref());
 		let mut book = EpubBook {
 			archive,
 			content_opf,
 			toc,
+			pages,
+			#[cfg(feature = "gui")]
+			landmarks,
 			chapter_cache,
+			#[cfg(feature = "gui")]
+			media_clips: HashMap::new(),
 			chapter_index,
 			css_cache: Default::default(),
 			images: Default::default(),
@@ -573,6 +841,8 @@ impl EpubBook {
 			#[cfg(feature = "gui")]
 			fonts: HtmlFonts::new(),
 			custom_style: custom_style.clone(),
+			vertical_writing,
+			problems,
 		};
 		book.load_chapter(chapter_index)?;
 		Ok(book)
@@ -587,7 +857,12 @@ impl EpubBook {
 				let cwd = path_cwd(full_path);
 				let mut html_str = self.archive.string(full_path)?;
 				if full_path.to_lowercase().ends_with(".xhtml") {
-					html_str = xhtml_to_html(&html_str)?;
+					// html parser tolerates unclosed tags and such
+					match xhtml_to_html(&html_str) {
+						Ok(html) => html_str = html,
+						Err(err) => self.problems.push(format!(
+							"Malformed xhtml {}: {}, parsed as html", full_path, err)),
+					}
 				}
 				let mut resolve = EpubResolver {
 					cwd,
@@ -607,6 +882,11 @@ impl EpubBook {
 						Some(content)
 					});
 				}
+				#[cfg(feature = "gui")]
+				if let Some(clips) = load_media_clips(full_path, chapter_index,
+					&html_content, &self.content_opf, self.archive.as_ref()) {
+					self.media_clips.insert(chapter_index, clips);
+				}
 				v.insert(html_content)
 			}
 		};
@@ -712,23 +992,24 @@ fn parse_ncx(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 
 /// parse Navigation document
 /// according to https://www.w3.org/publishing/epub3/epub-packages.html#sec-package-nav-def
-fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
+fn search_nav<'a, 'i>(element: Node<'a, 'i>, type_name: ExpandedName, nav_type: &str) -> Option<Node<'a, 'i>>
 {
-	fn search_nav<'a, 'i>(element: Node<'a, 'i>, type_name: ExpandedName) -> Option<Node<'a, 'i>>
-	{
-		for child in element.children() {
-			if child.is_element() {
-				if child.has_tag_name("nav") && child.attribute(type_name).map_or(false, |t| t == "toc") {
-					return Some(child);
-				}
-				let option = search_nav(child, type_name);
-				if option.is_some() {
-					return option;
-				}
+	for child in element.children() {
+		if child.is_element() {
+			if child.has_tag_name("nav") && child.attribute(type_name).map_or(false, |t| t == nav_type) {
+				return Some(child);
+			}
+			let option = search_nav(child, type_name, nav_type);
+			if option.is_some() {
+				return option;
 			}
 		}
-		None
 	}
+	None
+}
+
+fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
+{
 	fn process(children: Children, toc: &mut Vec<NavPoint>, level: usize, cwd: &PathBuf) -> Result<()>
 	{
 		for child in children {
@@ -788,7 +1069,7 @@ fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 	let namespace = root.lookup_namespace_uri(Some("epub"))
 		.ok_or(anyhow!("Navigation document without epub namespace"))?;
 	let epub_type_name = ExpandedName::from((namespace, "type"));
-	let nav = search_nav(body, epub_type_name).ok_or(anyhow!("Navigation document without nav of toc"))?;
+	let nav = search_nav(body, epub_type_name, "toc").ok_or(anyhow!("Navigation document without nav of toc"))?;
 	let mut toc = vec![];
 	for child in nav.children() {
 		if child.has_tag_name("ol") {
@@ -803,6 +1084,344 @@ fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 	}
 }
 
+#[cfg(feature = "gui")]
+fn load_landmarks(content_opf: &mut ContentOPF, archive: &dyn EpubArchive) -> Vec<Landmark>
+{
+	for item in content_opf.manifest.values() {
+		if item.properties.as_ref().map_or(false, |p| p.contains("nav")) {
+			if let Ok(text) = archive.string(&item.href) {
+				let landmarks = parse_nav_landmarks(&text, &path_cwd(&item.href));
+				if !landmarks.is_empty() {
+					return landmarks;
+				}
+			}
+		}
+	}
+	std::mem::take(&mut content_opf.guide)
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn landmark(kind: &str, title: Option<&str>, href: &str, cwd: &PathBuf) -> Option<Landmark>
+{
+	let kind = kind.trim();
+	if kind.is_empty() {
+		return None;
+	}
+	let title = title
+		.map(|title| title.trim())
+		.filter(|title| !title.is_empty())
+		.unwrap_or(kind);
+	let mut parts = href.split('#');
+	let src_file = concat_path_str(cwd.clone(), parts.next()?)?;
+	let src_anchor = parts.next().map(|a| a.to_owned());
+	Some(Landmark { kind: kind.to_owned(), title: title.to_owned(), src_file, src_anchor })
+}
+
+/// landmarks nav in Navigation document
+#[cfg(feature = "gui")]
+fn parse_nav_landmarks(text: &str, cwd: &PathBuf) -> Vec<Landmark>
+{
+	let mut landmarks = vec![];
+	let Ok(doc) = parse_xml(text) else {
+		return landmarks;
+	};
+	let root = doc.root_element();
+	let Some(body) = get_child(root, "body") else {
+		return landmarks;
+	};
+	let Some(namespace) = root.lookup_namespace_uri(Some("epub")) else {
+		return landmarks;
+	};
+	let epub_type_name = ExpandedName::from((namespace, "type"));
+	let Some(nav) = search_nav(body, epub_type_name, "landmarks") else {
+		return landmarks;
+	};
+	for a in nav.descendants().filter(|node| node.has_tag_name("a")) {
+		let (Some(kind), Some(href)) = (a.attribute(epub_type_name), a.attribute("href")) else {
+			continue;
+		};
+		let title: String = a.descendants()
+			.filter(|node| node.is_text())
+			.filter_map(|node| node.text())
+			.collect();
+		if let Some(landmark) = landmark(kind, Some(&title), href, cwd) {
+			landmarks.push(landmark);
+		}
+	}
+	landmarks
+}
+
+/// toc from both ncx and nav document, the deeper one as the base,
+/// missing labels filled from the other one
+fn load_toc(content_opf: &ContentOPF, archive: &dyn EpubArchive) -> Result<Vec<NavPoint>>
+{
+	let ncx_id = content_opf.toc_id.as_ref().map_or("ncx", |id| id.as_str());
+	let ncx = content_opf.manifest.get(ncx_id).map(|item| {
+		let text = archive.string(&item.href)?;
+		parse_ncx(&text, &path_cwd(&item.href))
+	});
+	let nav = content_opf.manifest.values()
+		.find(|item| item.properties.as_ref().map_or(false, |p| p.contains("nav")))
+		.map(|item| {
+			let text = archive.string(&item.href)?;
+			parse_nav_doc(&text, &path_cwd(&item.href))
+		});
+	let (mut toc, other) = match (ncx, nav) {
+		(None, None) => bail!("Invalid content.opf file, no ncx or nav"),
+		(Some(toc), None) | (None, Some(toc)) => return toc,
+		(Some(Err(err)), Some(Err(_))) => return Err(err),
+		(Some(Ok(toc)), Some(Err(_))) | (Some(Err(_)), Some(Ok(toc))) => return Ok(toc),
+		(Some(Ok(ncx)), Some(Ok(nav))) => {
+			#[inline]
+			fn depth(toc: &[NavPoint]) -> (usize, usize)
+			{
+				(toc.iter().map(|np| np.level).max().unwrap_or(0), toc.len())
+			}
+			if depth(&ncx) >= depth(&nav) { (ncx, nav) } else { (nav, ncx) }
+		}
+	};
+	for np in &mut toc {
+		if np.label.as_ref().map_or(false, |label| !label.trim().is_empty()) {
+			continue;
+		}
+		if let Some(label) = other.iter()
+			.find(|o| o.src_file == np.src_file && o.src_anchor == np.src_anchor)
+			.and_then(|o| o.label.as_ref()) {
+			np.label = Some(label.clone());
+		}
+	}
+	Ok(toc)
+}
+
+/// one entry for every spine item, for toc missing or broken
+fn spine_toc(content_opf: &ContentOPF) -> Vec<NavPoint>
+{
+	content_opf.spine
+		.iter()
+		.enumerate()
+		.map(|(index, id)| NavPoint {
+			id: None,
+			label: None,
+			play_order: None,
+			level: 1,
+			src_file: content_opf.manifest.get(id).map(|item| item.href.clone()),
+			src_anchor: None,
+			first_chapter_index: index,
+		})
+		.collect()
+}
+
+fn load_pages(content_opf: &ContentOPF, archive: &dyn EpubArchive) -> Vec<PageTarget>
+{
+	for item in content_opf.manifest.values() {
+		if item.properties.as_ref().map_or(false, |p| p.contains("nav")) {
+			if let Ok(text) = archive.string(&item.href) {
+				let pages = parse_nav_pages(&text, &path_cwd(&item.href));
+				if !pages.is_empty() {
+					return pages;
+				}
+			}
+		}
+	}
+	let ncx_id = content_opf.toc_id.as_ref().map_or("ncx", |id| id.as_str());
+	if let Some(item) = content_opf.manifest.get(ncx_id) {
+		if let Ok(text) = archive.string(&item.href) {
+			return parse_ncx_pages(&text, &path_cwd(&item.href));
+		}
+	}
+	vec![]
+}
+
+#[inline]
+fn page_target(label: &str, src: &str, cwd: &PathBuf) -> Option<PageTarget>
+{
+	let label = label.trim();
+	if label.is_empty() {
+		return None;
+	}
+	let mut parts = src.split('#');
+	let src_file = concat_path_str(cwd.clone(), parts.next()?)?;
+	let src_anchor = parts.next().map(|a| a.to_owned());
+	Some(PageTarget { label: label.to_owned(), src_file, src_anchor, chapter_index: None })
+}
+
+/// page-list nav in Navigation document
+fn parse_nav_pages(text: &str, cwd: &PathBuf) -> Vec<PageTarget>
+{
+	let mut pages = vec![];
+	let Ok(doc) = parse_xml(text) else {
+		return pages;
+	};
+	let root = doc.root_element();
+	let Some(body) = get_child(root, "body") else {
+		return pages;
+	};
+	let Some(namespace) = root.lookup_namespace_uri(Some("epub")) else {
+		return pages;
+	};
+	let epub_type_name = ExpandedName::from((namespace, "type"));
+	let Some(nav) = search_nav(body, epub_type_name, "page-list") else {
+		return pages;
+	};
+	for a in nav.descendants().filter(|node| node.has_tag_name("a")) {
+		let Some(href) = a.attribute("href") else {
+			continue;
+		};
+		let label: String = a.descendants()
+			.filter(|node| node.is_text())
+			.filter_map(|node| node.text())
+			.collect();
+		if let Some(page) = page_target(&label, href, cwd) {
+			pages.push(page);
+		}
+	}
+	pages
+}
+
+/// pageList in ncx
+fn parse_ncx_pages(text: &str, cwd: &PathBuf) -> Vec<PageTarget>
+{
+	let mut pages = vec![];
+	let Ok(doc) = parse_xml(text) else {
+		return pages;
+	};
+	let Some(page_list) = get_child(doc.root_element(), "pageList") else {
+		return pages;
+	};
+	for target in page_list.children().filter(|node| node.has_tag_name("pageTarget")) {
+		let label = get_child(target, "navLabel")
+			.and_then(|el| get_child(el, "text"))
+			.and_then(|el| el.text());
+		let src = get_child(target, "content")
+			.and_then(|el| el.attribute("src"));
+		if let (Some(label), Some(src)) = (label, src) {
+			if let Some(page) = page_target(label, src, cwd) {
+				pages.push(page);
+			}
+		}
+	}
+	pages
+}
+
+#[cfg(feature = "gui")]
+fn load_media_clips(chapter_path: &str, chapter_index: usize, chapter: &Chapter,
+	content_opf: &ContentOPF, archive: &dyn EpubArchive) -> Option<Vec<MediaClip>>
+{
+	let item_id = content_opf.spine.get(chapter_index)?;
+	let smil_id = content_opf.manifest.get(item_id)?.media_overlay.as_ref()?;
+	let smil = content_opf.manifest.get(smil_id)?;
+	let text = archive.string(&smil.href).ok()?;
+	let cwd = path_cwd(&smil.href);
+	let clips = parse_smil(&text, &cwd, chapter_path, chapter).ok()?;
+	if clips.is_empty() {
+		None
+	} else {
+		Some(clips)
+	}
+}
+
+/// parse <par> of SMIL with text fragment of the chapter, in document order
+#[cfg(feature = "gui")]
+fn parse_smil(text: &str, cwd: &PathBuf, chapter_path: &str, chapter: &Chapter)
+	-> Result<Vec<MediaClip>>
+{
+	let doc = parse_xml(text)?;
+	let mut clips: Vec<MediaClip> = vec![];
+	for par in doc.descendants().filter(|node| node.has_tag_name("par")) {
+		let (Some(text_node), Some(audio_node)) = (get_child(par, "text"), get_child(par, "audio")) else {
+			continue;
+		};
+		let (Some(text_src), Some(audio_src)) = (text_node.attribute("src"), audio_node.attribute("src")) else {
+			continue;
+		};
+		let mut split = text_src.split('#');
+		let (Some(file), Some(anchor)) = (split.next(), split.next()) else {
+			continue;
+		};
+		if concat_path_str(cwd.clone(), file).map_or(true, |path| path != chapter_path) {
+			continue;
+		}
+		let Some(position) = chapter.id_position(anchor) else {
+			continue;
+		};
+		let Some(audio) = concat_path_str(cwd.clone(), audio_src) else {
+			continue;
+		};
+		let clip_begin = audio_node.attribute("clipBegin")
+			.and_then(parse_clock_value)
+			.unwrap_or(0.0);
+		let clip_end = audio_node.attribute("clipEnd")
+			.and_then(parse_clock_value);
+		clips.push(MediaClip {
+			line: position.line,
+			range: position.offset..position.offset,
+			audio,
+			clip_begin,
+			clip_end,
+		});
+	}
+	// fragment ends at next fragment in same line, or end of the line
+	let lines = chapter.lines();
+	for index in 0..clips.len() {
+		let line = clips[index].line;
+		let start = clips[index].range.start;
+		let end = match clips.get(index + 1) {
+			Some(next) if next.line == line && next.range.start > start =>
+				next.range.start,
+			_ => lines.get(line).map_or(start, |line| line.len()),
+		};
+		clips[index].range.end = end;
+	}
+	Ok(clips)
+}
+
+/// SMIL clock value: full clock(1:02:03.5), partial clock(02:03.5)
+/// or timecount(3.5s, 500ms, 2min, 1h, 3.5)
+#[cfg(feature = "gui")]
+fn parse_clock_value(value: &str) -> Option<f64>
+{
+	let value = value.trim();
+	if value.contains(':') {
+		let mut seconds = 0.0;
+		for part in value.split(':') {
+			seconds = seconds * 60.0 + f64::from_str(part).ok()?;
+		}
+		return Some(seconds);
+	}
+	let (number, scale) = if let Some(number) = value.strip_suffix("ms") {
+		(number, 0.001)
+	} else if let Some(number) = value.strip_suffix("min") {
+		(number, 60.0)
+	} else if let Some(number) = value.strip_suffix('h') {
+		(number, 3600.0)
+	} else if let Some(number) = value.strip_suffix('s') {
+		(number, 1.0)
+	} else {
+		(value, 1.0)
+	};
+	Some(f64::from_str(number.trim()).ok()? * scale)
+}
+
+/// chinese or japanese book with vertical writing mode in stylesheets
+fn vertical_writing(content_opf: &ContentOPF, archive: &dyn EpubArchive) -> bool
+{
+	let language = content_opf.language.to_lowercase();
+	let language = language.split(['-', '_']).next().unwrap_or("");
+	if language != "zh" && language != "ja" {
+		return false;
+	}
+	content_opf.manifest
+		.values()
+		.filter(|item| item.media_type == "text/css")
+		.filter_map(|item| archive.string(&item.href).ok())
+		.any(|css| css.match_indices("writing-mode")
+			.any(|(index, _)| css[index..]
+				.split([';', '}'])
+				.next()
+				.map_or(false, |declaration| declaration.contains("vertical"))))
+}
+
 fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
 {
 	manifest
@@ -817,8 +1436,11 @@ fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
 					ManifestItem {
 						id,
 						href,
-						media_type: node.attribute("media-type")?.to_string(),
+						media_type: node.attribute("media-type").unwrap_or("").to_string(),
 						properties: node.attribute("properties").map(|s| s.to_string()),
+						#[cfg(feature = "gui")]
+						media_overlay: node.attribute("media-overlay").map(|s| s.to_string()),
+						fallback: node.attribute("fallback").map(|s| s.to_string()),
 					},
 				));
 			}
@@ -827,23 +1449,73 @@ fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
 		.collect::<HashMap<ItemId, ManifestItem>>()
 }
 
-#[inline]
-fn parse_spine(spine: Node, manifest: &Manifest, archive: &dyn EpubArchive) -> (Spine, Option<String>)
+/// first xhtml item in the fallback chain, or the item itself if none
+fn spine_item(id: &str, manifest: &Manifest, archive: &dyn EpubArchive) -> Option<String>
 {
-	let chapters = spine.children()
-		.filter_map(|node| {
-			if node.has_tag_name("itemref") {
-				let id = node.attribute("idref")?.to_string();
-				let item = manifest.get(&id)?;
-				if archive.exists(&item.href as &str) {
-					return Some(id);
-				}
+	let mut current = id;
+	// fallback chain may be circular
+	for _ in 0..manifest.len() {
+		let Some(item) = manifest.get(current) else {
+			break;
+		};
+		if item.media_type == CHAPTER_MEDIA_TYPE && archive.exists(&item.href) {
+			return Some(current.to_owned());
+		}
+		match &item.fallback {
+			Some(fallback) => current = fallback,
+			None => break,
+		}
+	}
+	let item = manifest.get(id)?;
+	if archive.exists(&item.href) {
+		Some(id.to_owned())
+	} else {
+		None
+	}
+}
+
+/// items missing or not readable skipped and reported in problems
+fn parse_spine(spine: Node, manifest: &Manifest, archive: &dyn EpubArchive,
+	problems: &mut Vec<String>) -> (Spine, HashSet<usize>, Option<String>)
+{
+	let mut chapters = vec![];
+	let mut non_linear = HashSet::new();
+	for node in spine.children() {
+		if !node.has_tag_name("itemref") {
+			continue;
+		}
+		let Some(idref) = node.attribute("idref") else {
+			continue;
+		};
+		let Some(item) = manifest.get(idref) else {
+			problems.push(format!("Spine item not in manifest: {}", idref));
+			continue;
+		};
+		let Some(id) = spine_item(idref, manifest, archive) else {
+			problems.push(format!("Spine item not found: {}", item.href));
+			continue;
+		};
+		let item = &manifest[&id];
+		if item.media_type != CHAPTER_MEDIA_TYPE {
+			if !is_chapter(item) {
+				problems.push(format!("Spine item not supported: {} of {}",
+					item.href, item.media_type));
+				continue;
 			}
-			None
-		})
-		.collect();
+			problems.push(format!("Wrong media type of {}: {}, read as xhtml",
+				item.href, item.media_type));
+		}
+		if node.attribute("linear") == Some("no") {
+			non_linear.insert(chapters.len());
+		}
+		chapters.push(id);
+	}
+	// all non-linear treated as linear
+	if non_linear.len() == chapters.len() {
+		non_linear.clear();
+	}
 	let toc_id = spine.attribute("toc").map(|id| id.to_owned());
-	(chapters, toc_id)
+	(chapters, non_linear, toc_id)
 }
 
 fn parse_content_opf(text: &str, content_opf_dir: &PathBuf, archive: &dyn EpubArchive) -> Result<ContentOPF>
@@ -856,11 +1528,14 @@ fn parse_content_opf(text: &str, content_opf_dir: &PathBuf, archive: &dyn EpubAr
 		.ok_or(anyhow!("No manifest node found in OPF"))?;
 	let spine = get_child(package, "spine")
 		.ok_or(anyhow!("No spine node found in OPF"))?;
-	let title = get_child(metadata, "title")
-		.ok_or(anyhow!("No title node found in OPF metadata"))?
-		.text()
-		.ok_or(anyhow!("No title node found in OPF metadata"))?
-		.to_string();
+	let mut problems = vec![];
+	let title = match get_child(metadata, "title").and_then(|node| node.text()) {
+		Some(title) => title.to_string(),
+		None => {
+			problems.push("No title found in OPF metadata".to_owned());
+			String::new()
+		}
+	};
 	let author = get_child(metadata, "creator")
 		.map(|el| el.text())
 		.flatten()
@@ -869,17 +1544,42 @@ fn parse_content_opf(text: &str, content_opf_dir: &PathBuf, archive: &dyn EpubAr
 		.map_or(String::new(), |e| e.text()
 			.map_or(String::new(), |s| s.to_owned()));
 	let manifest = parse_manifest(manifest, content_opf_dir);
-	let (spine, toc_id) = parse_spine(spine, &manifest, archive);
+	let (spine, non_linear, toc_id) = parse_spine(spine, &manifest, archive, &mut problems);
+	#[cfg(feature = "gui")]
+	let guide = get_child(package, "guide")
+		.map_or_else(|| vec![], |guide| guide
+			.children()
+			.filter(|node| node.has_tag_name("reference"))
+			.filter_map(|node| landmark(
+				node.attribute("type")?,
+				node.attribute("title"),
+				node.attribute("href")?,
+				content_opf_dir))
+			.collect());
 	Ok(ContentOPF {
 		title,
 		author,
 		language,
 		manifest,
 		spine,
+		non_linear,
 		toc_id,
+		#[cfg(feature = "gui")]
+		guide,
+		problems,
 	})
 }
 
+#[inline]
+fn is_chapter(item: &ManifestItem) -> bool
+{
+	if item.media_type == CHAPTER_MEDIA_TYPE || item.media_type == "text/html" {
+		return true;
+	}
+	let href = item.href.to_lowercase();
+	CHAPTER_EXTENSIONS.iter().any(|ext| href.ends_with(ext))
+}
+
 fn toc_title(nav_point: &NavPoint) -> &str {
 	let label = match &nav_point.label {
 		Some(label) => label,
@@ -899,7 +1599,7 @@ fn chapter_path(chapter_index: usize, content_opf: &ContentOPF) -> Result<&str>
 	let item = content_opf.manifest
 		.get(spine)
 		.ok_or(ChapterError::anyhow(format!("Invalid ref id: {}", spine)))?;
-	if item.media_type != "application/xhtml+xml" {
+	if !is_chapter(item) {
 		return Err(ChapterError::anyhow(format!("Referenced content for {} is not valid.", spine)));
 	}
 	Ok(&item.href)
@@ -1,8 +1,12 @@
 use std::borrow::BorrowMut;
 use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::Chars;
 
 use anyhow::{anyhow, bail, Result};
 use encoding_rs::Encoding;
+use flate2::read::ZlibDecoder;
 
 use crate::book::{Book, LoadingChapter, Line, Loader, TocInfo};
 use crate::common::{decode_text, detect_charset, txt_lines};
@@ -48,6 +52,11 @@ use crate::config::{BookLoadingInfo, ReadingInfo};
 //     再來是N筆資料，每筆是一章的內容，PDB檔是Big5碼(是null-terminated string，最後一個byte是0)，uPDB檔是Unicode碼。
 //
 //     第N+2筆資料是書籤，預設是-1。可以不理。
+//
+// Other palm books:
+//     PalmDOC(TEXtREAd): plain text records, maybe PalmDoc compressed, no chapters.
+//     eReader(PNRdPPrs): PML text records, PalmDoc or zlib compressed, chapters
+//         marked by \x, \Xn and \Cn="title" in text. DRM protected books are not supported.
 
 pub(crate) struct HaodooLoader {
 	extensions: Vec<&'static str>,
@@ -57,6 +66,27 @@ const HEADER_LENGTH: usize = 78;
 const PDB_ID: &str = "MTIT";
 const UPDB_ID: &str = "MTIU";
 const PALMDOC_ID: &str = "REAd";
+const EREADER_ID: &str = "PPrs";
+const NAME_LENGTH: usize = 32;
+const EREADER_HEADER_LENGTH: usize = 132;
+const EREADER_NON_TEXT_OFFSET: usize = 12;
+const EREADER_IMAGE_COUNT_OFFSET: usize = 20;
+const EREADER_HAS_METADATA_OFFSET: usize = 24;
+const EREADER_IMAGE_DATA_OFFSET: usize = 40;
+const EREADER_METADATA_OFFSET: usize = 44;
+// image record: "PNG ", 32 bytes name, then png data from 62
+#[cfg(feature = "gui")]
+const EREADER_IMAGE_MAGIC: &[u8; 4] = b"PNG ";
+#[cfg(feature = "gui")]
+const EREADER_IMAGE_NAME_LENGTH: usize = 32;
+#[cfg(feature = "gui")]
+const EREADER_IMAGE_HEADER_LENGTH: usize = 62;
+#[cfg(feature = "gui")]
+const EREADER_COVER_NAME: &str = "cover.png";
+const EREADER_PALMDOC_COMPRESSION: usize = 2;
+const EREADER_ZLIB_COMPRESSION: usize = 10;
+const EREADER_DRM_COMPRESSIONS: [usize; 2] = [260, 272];
+const UNTITLED_CHAPTER: &str = "None";
 const PDB_SEPARATOR: [u8; 1] = [0x1b];
 const UPDB_TITLE_SEPARATOR: [u8; 4] = [0x0d, 0x00, 0x0a, 0x00];
 const UPDB_ESCAPE_SEPARATOR: [u8; 2] = [0x1b, 0x00];
@@ -73,6 +103,7 @@ enum PDBType {
 	PDB { encode: &'static Encoding },
 	UPDB { encode: &'static Encoding },
 	PalmDoc,
+	EReader,
 }
 
 impl HaodooLoader {
@@ -87,6 +118,21 @@ impl Loader for HaodooLoader {
 		&self.extensions
 	}
 
+	#[inline]
+	fn format(&self) -> &str {
+		"haodoo"
+	}
+
+	fn sniff(&self, head: &[u8]) -> bool {
+		if head.len() < HEADER_LENGTH {
+			return false;
+		}
+		let id = &head[ID_OFFSET..ID_OFFSET + ID_LENGTH];
+		[PDB_ID, UPDB_ID, PALMDOC_ID, EREADER_ID]
+			.iter()
+			.any(|book_id| book_id.as_bytes() == id)
+	}
+
 	fn load_file(&self, _filename: &str, file: std::fs::File,
 		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
@@ -107,6 +153,9 @@ impl Loader for HaodooLoader {
 
 struct HaodooBook<R: Read + Seek> {
 	name: String,
+	author: Option<String>,
+	// image records of eReader book
+	images: Range<usize>,
 	reader: R,
 	book_type: PDBType,
 	record_offsets: Vec<usize>,
@@ -142,6 +191,7 @@ fn parse_header<R: Read + Seek>(mut reader: R) -> Result<HaodooBook<R>> {
 		PDB_ID => PDBType::PDB { encode: &encoding_rs::BIG5 },
 		UPDB_ID => PDBType::UPDB { encode: &encoding_rs::UTF_16LE },
 		PALMDOC_ID => PDBType::PalmDoc,
+		EREADER_ID => PDBType::EReader,
 		_ => return Err(anyhow!("Invalid book id: {}", book_id)),
 	};
 	//line records count
@@ -155,8 +205,25 @@ fn parse_header<R: Read + Seek>(mut reader: R) -> Result<HaodooBook<R>> {
 	for index in 0..record_count {
 		record_offsets.push(read_u32(&record_buffer, index << 3))
 	}
+	// database name, overwritten by book info if any
+	let name_end = header[..NAME_LENGTH]
+		.iter()
+		.position(|b| *b == 0)
+		.unwrap_or(NAME_LENGTH);
+	let name = String::from_utf8_lossy(&header[..name_end]).to_string();
+	// author of new haodoo books, title of old ones dropped after toc loaded
+	let author = match book_type {
+		PDBType::PDB { .. } | PDBType::UPDB { .. } => {
+			let author = encoding_rs::BIG5.decode(&header[..name_end]).0;
+			let author = author.trim();
+			if author.is_empty() { None } else { Some(author.to_owned()) }
+		}
+		PDBType::PalmDoc | PDBType::EReader => None,
+	};
 	Ok(HaodooBook {
-		name: String::new(),
+		name,
+		author,
+		images: 0..0,
 		reader,
 		book_type,
 		record_offsets,
@@ -173,13 +240,43 @@ impl<R: Read + Seek + 'static> Book for HaodooBook<R> {
 		Some(&self.name)
 	}
 
-	fn chapter_count(&self) -> usize
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn author(&self) -> Option<&str>
 	{
-		if matches!(self.book_type, PDBType::PalmDoc) {
-			1
-		} else {
-			self.chapters.len()
+		self.author.as_deref()
+	}
+
+	/// image named cover, or the first image
+	#[cfg(feature = "gui")]
+	fn cover(&mut self) -> Option<Vec<u8>>
+	{
+		let mut first = None;
+		for index in self.images.clone() {
+			let Ok(mut record) = self.read_record(index) else {
+				continue;
+			};
+			if record.len() <= EREADER_IMAGE_HEADER_LENGTH
+				|| &record[..EREADER_IMAGE_MAGIC.len()] != EREADER_IMAGE_MAGIC {
+				continue;
+			}
+			let name = &record[EREADER_IMAGE_MAGIC.len()..EREADER_IMAGE_MAGIC.len() + EREADER_IMAGE_NAME_LENGTH];
+			let name = String::from_utf8_lossy(name);
+			let is_cover = name.trim_end_matches('\0').eq_ignore_ascii_case(EREADER_COVER_NAME);
+			let data = record.split_off(EREADER_IMAGE_HEADER_LENGTH);
+			if is_cover {
+				return Some(data);
+			}
+			if first.is_none() {
+				first = Some(data);
+			}
 		}
+		first
+	}
+
+	fn chapter_count(&self) -> usize
+	{
+		self.chapters.len()
 	}
 
 	fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
@@ -338,6 +435,13 @@ impl<R: Read + Seek> HaodooBook<R> {
 			PDBType::UPDB { encode } => {
 				self.parse_toc(&record, encode, &UPDB_ESCAPE_SEPARATOR, &UPDB_TITLE_SEPARATOR, 0)?;
 			}
+			PDBType::PalmDoc | PDBType::EReader => {}
+		}
+		if self.author.as_deref() == Some(self.name.as_str()) {
+			self.author = None;
+		}
+		match self.book_type {
+			PDBType::PDB { .. } | PDBType::UPDB { .. } => {}
 			PDBType::PalmDoc => {
 				let compression = record[1] == 2;
 				let text_count = read_u16(&record, 8);
@@ -353,9 +457,57 @@ impl<R: Read + Seek> HaodooBook<R> {
 				let encoding = detect_charset(&buf, false);
 				let text = decode_text(buf, encoding)?;
 				let lines = txt_lines(&text);
-				self.chapters.push(Chapter { title: String::from("None"), lines: Some(lines) });
+				self.chapters.push(Chapter { title: String::from(UNTITLED_CHAPTER), lines: Some(lines) });
+			}
+			PDBType::EReader => self.load_ereader(&record)?,
+		}
+		Ok(())
+	}
+
+	fn load_ereader(&mut self, record: &[u8]) -> Result<()>
+	{
+		if record.len() != EREADER_HEADER_LENGTH {
+			bail!("Not supported eReader book format.");
+		}
+		let compression = read_u16(record, 0);
+		if EREADER_DRM_COMPRESSIONS.contains(&compression) {
+			bail!("DRM protected eReader book.");
+		}
+		if compression != EREADER_PALMDOC_COMPRESSION && compression != EREADER_ZLIB_COMPRESSION {
+			bail!("Not supported eReader compression: {}", compression);
+		}
+		if read_u16(record, EREADER_HAS_METADATA_OFFSET) != 0 {
+			let metadata = self.read_record(read_u16(record, EREADER_METADATA_OFFSET))?;
+			// title, author, copyright, publisher, isbn, separated by 0
+			let mut fields = metadata.split(|b| *b == 0);
+			if let Some(title) = fields.next() {
+				if !title.is_empty() {
+					self.name = String::from(encoding_rs::WINDOWS_1252.decode(title).0);
+				}
+			}
+			if let Some(author) = fields.next() {
+				if !author.is_empty() {
+					self.author = Some(String::from(encoding_rs::WINDOWS_1252.decode(author).0));
+				}
+			}
+		}
+		let images = read_u16(record, EREADER_IMAGE_DATA_OFFSET);
+		self.images = images..images + read_u16(record, EREADER_IMAGE_COUNT_OFFSET);
+		let non_text_offset = read_u16(record, EREADER_NON_TEXT_OFFSET);
+		let mut buf = vec![];
+		for index in 1..non_text_offset {
+			let record = self.read_record(index)?;
+			if compression == EREADER_ZLIB_COMPRESSION {
+				ZlibDecoder::new(record.as_slice()).read_to_end(&mut buf)?;
+			} else {
+				decompress_palm_doc(record, &mut buf);
 			}
 		}
+		let text = encoding_rs::WINDOWS_1252.decode(&buf).0;
+		self.chapters = pml_chapters(&text);
+		if self.chapters.is_empty() {
+			bail!("No content in eReader book.");
+		}
 		Ok(())
 	}
 
@@ -374,7 +526,7 @@ impl<R: Read + Seek> HaodooBook<R> {
 			PDBType::UPDB { encode, .. } => {
 				encode.decode(&mut record)
 			}
-			PDBType::PalmDoc => {
+			PDBType::PalmDoc | PDBType::EReader => {
 				panic!("no way")
 			}
 		}.0.to_string();
@@ -459,3 +611,125 @@ fn decompress_palm_doc(data: Vec<u8>, output: &mut Vec<u8>)
 		};
 	}
 }
+
+/// convert PML text of eReader into chapters, formatting tags are dropped
+fn pml_chapters(text: &str) -> Vec<Chapter>
+{
+	#[inline]
+	fn skip_attribute(chars: &mut Peekable<Chars>) -> Option<String>
+	{
+		if chars.peek() != Some(&'=') {
+			return None;
+		}
+		chars.next();
+		if chars.next() != Some('"') {
+			return None;
+		}
+		let mut value = String::new();
+		for ch in chars.by_ref() {
+			if ch == '"' {
+				break;
+			}
+			value.push(ch);
+		}
+		Some(value)
+	}
+	#[inline]
+	fn push_chapter(chapters: &mut Vec<Chapter>, title: Option<String>, content: &mut String)
+	{
+		let text = std::mem::take(content);
+		if title.is_none() && text.trim().is_empty() {
+			return;
+		}
+		let title = title
+			.map(|title| title.trim().to_owned())
+			.filter(|title| !title.is_empty())
+			.unwrap_or_else(|| String::from(UNTITLED_CHAPTER));
+		chapters.push(Chapter { title, lines: Some(txt_lines(&text)) });
+	}
+
+	let mut chapters = vec![];
+	let mut content = String::new();
+	let mut title = None;
+	// visible title between \x or \Xn pairs
+	let mut title_text: Option<String> = None;
+	let mut hidden = false;
+	let mut chars = text.chars().peekable();
+	while let Some(ch) = chars.next() {
+		let ch = if ch == '\\' {
+			let Some(tag) = chars.next() else {
+				break;
+			};
+			match tag {
+				'\\' => '\\',
+				'x' | 'X' => {
+					if tag == 'X' {
+						chars.next_if(|c| c.is_ascii_digit());
+					}
+					if let Some(text) = title_text.take() {
+						title = Some(text);
+					} else {
+						push_chapter(&mut chapters, title.take(), &mut content);
+						title_text = Some(String::new());
+					}
+					continue;
+				}
+				'C' => {
+					chars.next_if(|c| c.is_ascii_digit());
+					if let Some(marker) = skip_attribute(&mut chars) {
+						push_chapter(&mut chapters, title.take(), &mut content);
+						title = Some(marker);
+					}
+					continue;
+				}
+				'v' => {
+					hidden = !hidden;
+					continue;
+				}
+				'p' => '\n',
+				'a' => {
+					let code: String = (0..3).filter_map(|_| chars.next_if(|c| c.is_ascii_digit())).collect();
+					let Ok(code) = code.parse::<u8>() else {
+						continue;
+					};
+					let Some(ch) = encoding_rs::WINDOWS_1252.decode(&[code]).0.chars().next() else {
+						continue;
+					};
+					ch
+				}
+				'U' => {
+					let code: String = (0..4).filter_map(|_| chars.next_if(|c| c.is_ascii_hexdigit())).collect();
+					let Some(ch) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) else {
+						continue;
+					};
+					ch
+				}
+				'F' | 'S' => {
+					// \Fn, \Sp, \Sb, \Sd
+					chars.next();
+					skip_attribute(&mut chars);
+					continue;
+				}
+				_ => {
+					// \T, \w, \m, \q, \Q with attribute, others are simple tags
+					skip_attribute(&mut chars);
+					continue;
+				}
+			}
+		} else {
+			ch
+		};
+		if hidden {
+			continue;
+		}
+		content.push(ch);
+		if let Some(text) = &mut title_text {
+			text.push(ch);
+		}
+	}
+	if let Some(text) = title_text {
+		title = Some(text);
+	}
+	push_chapter(&mut chapters, title, &mut content);
+	chapters
+}
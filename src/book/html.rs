@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
@@ -6,24 +7,46 @@ use anyhow::Result;
 use elsa::FrozenMap;
 use indexmap::IndexSet;
 
-use crate::book::{Book, LoadingChapter, Line, Loader, ImageData};
+use crate::book::{Book, ChapterError, LoadingChapter, Line, Loader, ImageData, TocInfo};
+#[cfg(feature = "gui")]
+use crate::book::NoteInfo;
+use crate::book::split::{relocate, split_chapters};
 #[cfg(feature = "gui")]
 use crate::html_parser::BlockStyle;
 use crate::html_parser::{HtmlContent, HtmlParseOptions, HtmlResolver};
-use crate::common::{plain_text, TraceInfo};
+use crate::common::{plain_text, strip_bom, TraceInfo};
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::{frozen_map_get, html_parser};
 #[cfg(feature = "gui")]
 use crate::gui::HtmlFonts;
+use crate::list::ListIterator;
 use crate::xhtml::xhtml_to_html;
 
+const HTML_EXTENSIONS: [&str; 3] = [".html", ".htm", ".xhtml"];
+// lower cased leading of html content after bom and blanks
+const HTML_MAGICS: [&str; 2] = ["<!doctype html", "<html"];
+const XML_DECLARATION: &str = "<?xml";
+
 pub(crate) struct HtmlLoader {
 	extensions: Vec<&'static str>,
 }
 
+/// html file in the folder of the opened one
+struct HtmlChapter {
+	filename: String,
+	// text of the link in the opened file
+	title: Option<String>,
+	content: Option<HtmlContent>,
+}
+
+/// opened html file as the first chapter, html files in the same folder
+/// linked from it as following chapters, for reading local websites
 pub(crate) struct HtmlBook {
 	path: Option<PathBuf>,
-	content: HtmlContent,
+	chapters: Vec<HtmlChapter>,
+	chapter_index: usize,
+	css_cache: FrozenMap<String, String>,
+	custom_style: Option<String>,
 	font_families: IndexSet<String>,
 	#[cfg(feature = "gui")]
 	fonts: HtmlFonts,
@@ -32,7 +55,7 @@ pub(crate) struct HtmlBook {
 impl HtmlLoader {
 	pub(crate) fn new() -> Self
 	{
-		let extensions = vec![".html", ".htm", ".xhtml"];
+		let extensions = HTML_EXTENSIONS.to_vec();
 		HtmlLoader { extensions }
 	}
 }
@@ -80,49 +103,62 @@ impl Loader for HtmlLoader {
 		&self.extensions
 	}
 
+	#[inline]
+	fn format(&self) -> &str
+	{
+		"html"
+	}
+
+	#[inline]
+	fn sniff(&self, head: &[u8]) -> bool
+	{
+		is_html(head)
+	}
+
 	fn load_file(&self, _filename: &str, mut file: fs::File,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let filename = loading.filename();
-		let mut cwd = PathBuf::from_str(filename)?;
+		let path = PathBuf::from_str(filename)?;
+		let mut cwd = path.clone();
 		cwd.pop();
 		let mut content: Vec<u8> = Vec::new();
 		file.read_to_end(&mut content)?;
-		let mut font_families = IndexSet::new();
-		let mut text = plain_text(content, false)?;
-		if filename.to_lowercase().ends_with(".xhtml") {
-			text = xhtml_to_html(&text)?;
-		}
-		let reading = get_reading(loading);
-		#[allow(unused)]
-			let (content, mut font_faces) = html_parser::parse(HtmlParseOptions::new(&text)
-			.with_font_family(&mut font_families)
-			.with_resolver(&HtmlContentResolver {
-				cwd: cwd.clone(),
-				css_cache: FrozenMap::new(),
-				custom_style: reading.custom_style.clone(),
-			}))?;
-		#[cfg(feature = "gui")]
-			let book = {
-			let mut fonts = HtmlFonts::new();
-			fonts.reload(font_faces, |path| {
-				let content = fs::read(path).ok()?;
-				Some(content)
-			});
-			HtmlBook {
-				path: Some(cwd),
-				content,
-				font_families,
-				fonts,
-			}
+		let mut reading = get_reading(loading);
+		let mut book = HtmlBook {
+			path: Some(cwd.clone()),
+			chapters: vec![],
+			chapter_index: 0,
+			css_cache: FrozenMap::new(),
+			custom_style: reading.custom_style.clone(),
+			font_families: IndexSet::new(),
+			#[cfg(feature = "gui")]
+			fonts: HtmlFonts::new(),
 		};
-		#[cfg(not(feature = "gui"))]
-			let book = HtmlBook {
-			path: Some(cwd.to_owned()),
-			content,
-			font_families,
+		let filename = path.file_name()
+			.and_then(|name| name.to_str())
+			.unwrap_or_default()
+			.to_owned();
+		let content = book.parse(&filename, content)?;
+		book.chapters = linked_chapters(&content, &cwd, &filename);
+		if book.chapters.is_empty() {
+			book.chapters = content_chapters(filename, content, &mut reading);
+		} else {
+			book.chapters.insert(0, HtmlChapter {
+				filename,
+				title: None,
+				content: Some(content),
+			});
+		}
+		let chapter_index = match loading_chapter {
+			LoadingChapter::Index(0) => reading.chapter,
+			LoadingChapter::Index(index) => index,
+			LoadingChapter::Last => book.chapters.len() - 1,
 		};
+		if book.goto_chapter(chapter_index)?.is_none() {
+			book.goto_chapter(0)?;
+		}
 		Ok((
 			Box::new(book),
 			reading
@@ -130,21 +166,30 @@ impl Loader for HtmlLoader {
 	}
 
 	fn load_buf(&self, _filename: &str, content: Vec<u8>,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let mut font_families = IndexSet::new();
 		let text = plain_text(content, false)?;
 		let (content, _) = html_parser::parse(HtmlParseOptions::new(&text)
 			.with_font_family(&mut font_families))?;
+		let mut reading = get_reading(loading);
+		let chapters = content_chapters(String::new(), content, &mut reading);
+		let chapter_index = match loading_chapter {
+			LoadingChapter::Index(0) => reading.chapter,
+			LoadingChapter::Index(index) => index,
+			LoadingChapter::Last => chapters.len() - 1,
+		};
 		let book = HtmlBook {
 			path: None,
-			content,
+			chapter_index: chapter_index.min(chapters.len() - 1),
+			chapters,
+			css_cache: FrozenMap::new(),
+			custom_style: None,
 			font_families,
 			#[cfg(feature = "gui")]
 			fonts: HtmlFonts::new(),
 		};
-		let reading = get_reading(loading);
 		Ok((
 			Box::new(book),
 			reading,
@@ -156,25 +201,103 @@ impl Book for HtmlBook {
 	#[inline]
 	fn name(&self) -> Option<&str>
 	{
-		self.content.title()
+		self.chapters.first()?.content.as_ref()?.title()
+	}
+
+	#[inline]
+	fn chapter_count(&self) -> usize
+	{
+		self.chapters.len()
+	}
+
+	fn prev_chapter(&mut self) -> Result<Option<usize>>
+	{
+		let mut current = self.chapter_index;
+		while current > 0 {
+			current -= 1;
+			if self.load_chapter(current)?.lines().len() > 0 {
+				self.chapter_index = current;
+				return Ok(Some(current));
+			}
+		}
+		Ok(None)
+	}
+
+	fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
+	{
+		let chapter_count = self.chapter_count();
+		for current in chapter_index..chapter_count {
+			if self.load_chapter(current)?.lines().len() > 0 {
+				self.chapter_index = current;
+				return Ok(Some(current));
+			}
+		}
+		Ok(None)
+	}
+
+	#[inline]
+	fn current_chapter(&self) -> usize
+	{
+		self.chapter_index
+	}
+
+	fn title(&self, _line: usize, _offset: usize) -> Option<&str>
+	{
+		if self.chapters.len() > 1 {
+			Some(self.chapters.get(self.chapter_index)?.title())
+		} else {
+			None
+		}
+	}
+
+	#[inline]
+	fn toc_index(&self, _line: usize, _offset: usize) -> usize
+	{
+		self.chapter_index
+	}
+
+	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo<'_>> + '_>>
+	{
+		if self.chapters.len() <= 1 {
+			return None;
+		}
+		let iter = ListIterator::new(|index| {
+			let chapter = self.chapters.get(index)?;
+			Some(TocInfo { title: chapter.title(), index, level: 1 })
+		});
+		Some(Box::new(iter))
+	}
+
+	fn toc_position(&mut self, toc_index: usize) -> Option<TraceInfo>
+	{
+		self.load_chapter(toc_index).ok()?;
+		Some(TraceInfo {
+			chapter: toc_index,
+			line: 0,
+			offset: 0,
+		})
 	}
 
 	#[inline]
 	fn lines(&self) -> &Vec<Line>
 	{
-		&self.content.lines()
+		self.current().lines()
+	}
+
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn notes(&self) -> Option<&Vec<NoteInfo>>
+	{
+		Some(self.current().notes())
 	}
 
 	fn link_position(&mut self, line: usize, link_index: usize) -> Option<TraceInfo>
 	{
-		let text = &self.content.lines().get(line)?;
+		let text = &self.current().lines().get(line)?;
 		let link = text.link_at(link_index)?;
-		let link_target = link.target;
-		let mut split = link_target.split('#');
-		split.next()?;
-		let anchor = split.next()?;
-		let position = self.content.id_position(anchor)?;
-		Some(TraceInfo { chapter: 0, line: position.line, offset: position.offset })
+		let (target_file, target_anchor) = split_href(link.target);
+		let target_file = target_file.map(|file| file.to_owned());
+		self.target_position(target_file.as_deref(), target_anchor)
 	}
 
 	fn image<'h>(&'h self, href: &'h str) -> Option<ImageData<'h>>
@@ -188,12 +311,32 @@ impl Book for HtmlBook {
 		}
 	}
 
+	#[cfg(feature = "gui")]
+	fn media(&self, href: &str) -> Option<(String, Vec<u8>)>
+	{
+		let path = self.path.as_ref()?.join(href);
+		let bytes = fs::read(&path).ok()?;
+		Some((path.to_str()?.to_string(), bytes))
+	}
+
 	#[inline]
 	fn font_family_names(&self) -> Option<&IndexSet<String>>
 	{
 		Some(&self.font_families)
 	}
 
+	#[inline]
+	fn vertical_writing(&self) -> bool
+	{
+		self.current().writing_mode().vertical
+	}
+
+	#[inline]
+	fn upright_text(&self) -> bool
+	{
+		self.current().writing_mode().upright
+	}
+
 	#[cfg(feature = "gui")]
 	#[inline]
 	fn color_customizable(&self) -> bool
@@ -230,10 +373,173 @@ impl Book for HtmlBook {
 	#[inline]
 	fn block_styles(&self) -> Option<&Vec<BlockStyle>>
 	{
-		self.content.block_styles()
+		self.current().block_styles()
+	}
+}
+
+impl HtmlChapter {
+	#[inline]
+	fn title(&self) -> &str
+	{
+		self.title.as_deref()
+			.or_else(|| self.content.as_ref()?.title())
+			.unwrap_or(&self.filename)
 	}
 }
 
+impl HtmlBook {
+	#[inline]
+	fn current(&self) -> &HtmlContent
+	{
+		self.chapters[self.chapter_index].content.as_ref().unwrap()
+	}
+
+	fn parse(&mut self, filename: &str, content: Vec<u8>) -> Result<HtmlContent>
+	{
+		let mut text = plain_text(content, false)?;
+		if filename.to_lowercase().ends_with(".xhtml") {
+			text = xhtml_to_html(&text)?;
+		}
+		let resolver = HtmlContentResolver {
+			cwd: self.path.clone().unwrap_or_default(),
+			css_cache: std::mem::take(&mut self.css_cache),
+			custom_style: self.custom_style.clone(),
+		};
+		let result = html_parser::parse(HtmlParseOptions::new(&text)
+		.with_font_family(&mut self.font_families)
+		.with_resolver(&resolver));
+		// css shared by all chapters
+		self.css_cache = resolver.css_cache;
+		#[allow(unused)]
+			let (content, mut font_faces) = result?;
+		#[cfg(feature = "gui")]
+		self.fonts.reload(font_faces, |path| {
+			let content = fs::read(path).ok()?;
+			Some(content)
+		});
+		Ok(content)
+	}
+
+	fn load_chapter(&mut self, chapter_index: usize) -> Result<&HtmlContent>
+	{
+		let chapter = self.chapters.get(chapter_index)
+			.ok_or(ChapterError::anyhow("invalid index".to_string()))?;
+		if chapter.content.is_none() {
+			let filename = chapter.filename.clone();
+			let path = self.path.as_ref()
+				.ok_or(ChapterError::anyhow("invalid index".to_string()))?
+				.join(&filename);
+			let content = fs::read(path)?;
+			let content = self.parse(&filename, content)?;
+			self.chapters[chapter_index].content = Some(content);
+		}
+		Ok(self.chapters[chapter_index].content.as_ref().unwrap())
+	}
+
+	fn target_position(&mut self, target_file: Option<&str>, target_anchor: Option<String>) -> Option<TraceInfo>
+	{
+		let chapter_index = match target_file {
+			Some(target_file) => self.chapters
+				.iter()
+				.position(|chapter| chapter.filename == target_file)?,
+			None => self.chapter_index,
+		};
+		self.load_chapter(chapter_index).ok()?;
+		// parts of a split file share the filename
+		let filename = &self.chapters[chapter_index].filename;
+		let anchor_position = target_anchor.as_ref().and_then(|anchor| self.chapters
+			.iter()
+			.enumerate()
+			.filter(|(_, chapter)| chapter.filename == *filename)
+			.find_map(|(index, chapter)| chapter.content.as_ref()?
+				.id_position(anchor)
+				.map(|position| (index, position))));
+		match anchor_position {
+			Some((index, position)) => Some(TraceInfo {
+				chapter: index,
+				line: position.line,
+				offset: position.offset,
+			}),
+			None if target_file.is_some() => Some(TraceInfo {
+				chapter: chapter_index,
+				line: 0,
+				offset: 0,
+			}),
+			None => None,
+		}
+	}
+}
+
+/// chapters of the single html file, split if huge
+fn content_chapters(filename: String, content: HtmlContent,
+	reading: &mut ReadingInfo) -> Vec<HtmlChapter>
+{
+	let Some(chapters) = split_chapters(content.lines()) else {
+		return vec![HtmlChapter {
+			filename,
+			title: None,
+			content: Some(content),
+		}];
+	};
+	relocate(reading, &chapters);
+	let ranges = chapters
+		.iter()
+		.map(|chapter| chapter.range.clone())
+		.collect::<Vec<_>>();
+	content.split(&ranges)
+		.into_iter()
+		.zip(chapters)
+		.map(|(content, chapter)| HtmlChapter {
+			filename: filename.clone(),
+			title: Some(chapter.title),
+			content: Some(content),
+		})
+		.collect()
+}
+
+/// html files in the same folder linked from content, in link order
+fn linked_chapters(content: &HtmlContent, cwd: &PathBuf, filename: &str) -> Vec<HtmlChapter>
+{
+	let mut chapters = vec![];
+	let mut loaded = HashSet::new();
+	for line in content.lines() {
+		line.link_iter(true, |link| {
+			if let (Some(file), _) = split_href(link.target) {
+				if file != filename
+					&& !loaded.contains(file)
+					&& !file.contains(['/', '\\', ':'])
+					&& HTML_EXTENSIONS.iter().any(|ext| file.to_lowercase().ends_with(ext))
+					&& cwd.join(file).is_file() {
+					let title = line.iter()
+						.skip(link.range.start)
+						.take(link.range.len())
+						.collect::<String>();
+					let title = title.trim();
+					loaded.insert(file.to_owned());
+					chapters.push(HtmlChapter {
+						filename: file.to_owned(),
+						title: if title.is_empty() { None } else { Some(title.to_owned()) },
+						content: None,
+					});
+				}
+			}
+			(false, None::<()>)
+		});
+	}
+	chapters
+}
+
+/// split link into file and anchor, file none for link in current file
+fn split_href(href: &str) -> (Option<&str>, Option<String>)
+{
+	let mut split = href.splitn(2, '#');
+	let file = split.next()
+		.map(|file| file.trim_start_matches("./"))
+		.filter(|file| !file.is_empty());
+	let anchor = split.next().map(|a| a.to_owned());
+	(file, anchor)
+}
+
 #[inline]
 fn get_reading(loading: BookLoadingInfo) -> ReadingInfo
 {
@@ -242,3 +548,15 @@ fn get_reading(loading: BookLoadingInfo) -> ReadingInfo
 		reading.custom_font = true;
 	})
 }
+
+/// html or xhtml in ascii compatible encoding
+pub(crate) fn is_html(head: &[u8]) -> bool
+{
+	let head = strip_bom(head).unwrap_or(head);
+	let text = String::from_utf8_lossy(head).to_lowercase();
+	let text = text.trim_start();
+	if text.starts_with(XML_DECLARATION) {
+		return text.contains(HTML_MAGICS[1]);
+	}
+	HTML_MAGICS.iter().any(|magic| text.starts_with(magic))
+}
@@ -0,0 +1,147 @@
+use std::ffi::{c_char, CStr};
+use std::fs;
+use std::path::Path;
+use std::ptr;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, bail, Result};
+use libloading::Library;
+
+use crate::book::{Book, LoadingChapter, Loader};
+use crate::book::html::HtmlLoader;
+use crate::config::{BookLoadingInfo, ReadingInfo};
+
+/// loader plugins are dynamic libraries in the plugins folder of config dir,
+/// every plugin converts book content of its extensions to html, exports:
+///     uint32_t tbr_plugin_abi_version();
+///     // extensions with dot, separated by ',', e.g. ".fb3,.lit"
+///     const char *tbr_plugin_extensions();
+///     // 0 for html in output, otherwise error message in output
+///     int32_t tbr_plugin_convert(const uint8_t *content, size_t len, char **output);
+///     // free the output of tbr_plugin_convert
+///     void tbr_plugin_free(char *output);
+pub const PLUGINS_FOLDER: &str = "plugins";
+const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type ExtensionsFn = unsafe extern "C" fn() -> *const c_char;
+type ConvertFn = unsafe extern "C" fn(*const u8, usize, *mut *mut c_char) -> i32;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+static PLUGINS: OnceLock<Vec<Plugin>> = OnceLock::new();
+
+struct Plugin {
+	name: String,
+	extensions: Vec<&'static str>,
+	convert: ConvertFn,
+	free: FreeFn,
+	// functions above valid while the library loaded
+	_library: Library,
+}
+
+/// load plugins once at start, broken plugins skipped with error returned
+pub fn init(folder: &Path) -> Vec<String>
+{
+	let mut plugins = vec![];
+	let mut errors = vec![];
+	if let Ok(entries) = fs::read_dir(folder) {
+		for entry in entries.flatten() {
+			let path = entry.path();
+			if !path.is_file() || path.extension().map_or(true, |ext| ext != std::env::consts::DLL_EXTENSION) {
+				continue;
+			}
+			match unsafe { load_plugin(&path) } {
+				Ok(plugin) => plugins.push(plugin),
+				Err(err) => errors.push(format!("Failed load plugin {}: {}", path.display(), err)),
+			}
+		}
+	}
+	let _ = PLUGINS.set(plugins);
+	errors
+}
+
+unsafe fn load_plugin(path: &Path) -> Result<Plugin>
+{
+	let library = Library::new(path)?;
+	let abi_version = *library.get::<AbiVersionFn>(b"tbr_plugin_abi_version\0")?;
+	let version = abi_version();
+	if version != PLUGIN_ABI_VERSION {
+		bail!("abi version {} not supported", version);
+	}
+	let extensions = *library.get::<ExtensionsFn>(b"tbr_plugin_extensions\0")?;
+	let convert = *library.get::<ConvertFn>(b"tbr_plugin_convert\0")?;
+	let free = *library.get::<FreeFn>(b"tbr_plugin_free\0")?;
+	let extensions = extensions();
+	if extensions.is_null() {
+		bail!("no extension");
+	}
+	// plugins never unloaded
+	let extensions = CStr::from_ptr(extensions)
+		.to_string_lossy()
+		.split(',')
+		.map(|ext| ext.trim().to_lowercase())
+		.filter(|ext| !ext.is_empty())
+		.map(|ext| &*Box::leak(ext.into_boxed_str()))
+		.collect::<Vec<_>>();
+	if extensions.is_empty() {
+		bail!("no extension");
+	}
+	let name = path.file_stem()
+		.map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+		.to_string();
+	Ok(Plugin { name, extensions, convert, free, _library: library })
+}
+
+/// loaders of all plugins, for the book loader
+pub(crate) fn loaders() -> Vec<PluginLoader>
+{
+	PLUGINS.get()
+		.map_or_else(Vec::new, |plugins| plugins
+			.iter()
+			.map(|plugin| PluginLoader { plugin, html: HtmlLoader::new() })
+			.collect())
+}
+
+impl Plugin {
+	fn convert(&self, content: &[u8]) -> Result<Vec<u8>>
+	{
+		let mut output: *mut c_char = ptr::null_mut();
+		let code = unsafe { (self.convert)(content.as_ptr(), content.len(), &mut output) };
+		if output.is_null() {
+			return Err(anyhow!("Plugin {} returned nothing", self.name));
+		}
+		let text = unsafe { CStr::from_ptr(output) }.to_bytes().to_vec();
+		unsafe { (self.free)(output) };
+		if code != 0 {
+			bail!("Plugin {} failed: {}", self.name, String::from_utf8_lossy(&text));
+		}
+		Ok(text)
+	}
+}
+
+pub(crate) struct PluginLoader {
+	plugin: &'static Plugin,
+	html: HtmlLoader,
+}
+
+impl Loader for PluginLoader {
+	#[inline]
+	fn extensions(&self) -> &Vec<&'static str>
+	{
+		&self.plugin.extensions
+	}
+
+	#[inline]
+	fn format(&self) -> &str
+	{
+		&self.plugin.name
+	}
+
+	fn load_buf(&self, filename: &str, content: Vec<u8>,
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		-> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		let html = self.plugin.convert(&content)?;
+		self.html.load_buf(filename, html, loading_chapter, loading)
+	}
+}
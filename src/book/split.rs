@@ -0,0 +1,116 @@
+use std::ops::Range;
+
+use fancy_regex::Regex;
+
+use crate::book::Line;
+use crate::config::ReadingInfo;
+
+// books of fewer chars kept in one chapter
+const SPLIT_MIN_CHARS: usize = 200_000;
+// lines of each chapter if split without headings
+const CHAPTER_LINES: usize = 1000;
+// fewer headings treated as none found
+const MIN_HEADINGS: usize = 3;
+const MAX_HEADING_CHARS: usize = 50;
+const HEADING_REGEXP: &str = r"(?i)^(第[0-9０-９零〇一二三四五六七八九十百千两兩]+[章回节節卷集部篇]|(chapter|part|book)\s+([0-9]+|[ivxlcdm]+|[a-z]+)\b|prologue\b|epilogue\b|序章|楔子|尾声|尾聲|后记|後記)";
+
+/// chapter of a huge single chapter book, range of lines in the whole text
+pub(crate) struct VirtualChapter {
+	pub title: String,
+	pub range: Range<usize>,
+}
+
+/// split by headings, or every CHAPTER_LINES lines if not enough headings
+/// found, None if the text small enough for one chapter
+pub(crate) fn split_chapters(lines: &[Line]) -> Option<Vec<VirtualChapter>>
+{
+	let chars: usize = lines.iter().map(|line| line.len()).sum();
+	if chars < SPLIT_MIN_CHARS {
+		return None;
+	}
+	let pattern = Regex::new(HEADING_REGEXP).unwrap();
+	let mut starts = lines
+		.iter()
+		.enumerate()
+		.filter(|(_, line)| is_heading(line, &pattern))
+		.map(|(index, _)| index)
+		.collect::<Vec<_>>();
+	if starts.len() >= MIN_HEADINGS {
+		// text before the first heading like preface kept as a chapter
+		if lines[..starts[0]].iter().all(|line| line.is_blank()) {
+			starts[0] = 0;
+		} else {
+			starts.insert(0, 0);
+		}
+	} else {
+		starts = (0..lines.len()).step_by(CHAPTER_LINES).collect();
+	}
+	let chapters = starts
+		.iter()
+		.enumerate()
+		.map(|(index, start)| {
+			let end = starts.get(index + 1).copied().unwrap_or(lines.len());
+			VirtualChapter {
+				title: chapter_title(&lines[*start..end]),
+				range: *start..end,
+			}
+		})
+		.collect();
+	Some(chapters)
+}
+
+/// lines of the whole text moved into chapters
+pub(crate) fn split_lines(mut lines: Vec<Line>, chapters: &[VirtualChapter]) -> Vec<Vec<Line>>
+{
+	let mut parts = chapters
+		.iter()
+		.rev()
+		.map(|chapter| lines.split_off(chapter.range.start))
+		.collect::<Vec<_>>();
+	parts.reverse();
+	parts
+}
+
+/// reading saved before the book split has the line in whole text
+pub(crate) fn relocate(reading: &mut ReadingInfo, chapters: &[VirtualChapter])
+{
+	if reading.chapter != 0 {
+		return;
+	}
+	let found = chapters
+		.iter()
+		.position(|chapter| chapter.range.contains(&reading.line));
+	if let Some(index) = found {
+		reading.chapter = index;
+		reading.line -= chapters[index].range.start;
+	}
+}
+
+#[inline]
+fn is_heading(line: &Line, pattern: &Regex) -> bool
+{
+	// length with leading spaces
+	if line.len() > MAX_HEADING_CHARS * 2 {
+		return false;
+	}
+	let text = line.to_string();
+	let text = text.trim();
+	!text.is_empty()
+		&& text.chars().count() <= MAX_HEADING_CHARS
+		&& pattern.is_match(text).unwrap_or(false)
+}
+
+/// the first non-blank line, heading if split by headings
+#[inline]
+fn chapter_title(lines: &[Line]) -> String
+{
+	lines
+		.iter()
+		.find(|line| !line.is_blank())
+		.map_or_else(String::new, |line| line
+			.to_string()
+			.trim()
+			.chars()
+			.take(MAX_HEADING_CHARS)
+			.collect())
+}
@@ -1,20 +1,85 @@
 use anyhow::Result;
 
-use crate::book::{Book, LoadingChapter, Line, Loader};
-use crate::common::plain_text_lines;
+use crate::book::{Book, LoadingChapter, Line, Loader, TocInfo};
+use crate::book::split::{relocate, split_chapters, split_lines};
+use crate::book::html::is_html;
+use crate::common::{plain_text_lines, strip_bom, TraceInfo};
 use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::list::ListIterator;
 
+/// huge text split into virtual chapters, so paging and searching
+/// work on a part of it
 pub struct TxtBook {
-	lines: Vec<Line>,
+	chapters: Vec<Vec<Line>>,
+	// empty for not split
+	titles: Vec<String>,
+	chapter_index: usize,
 	leading_space: usize,
 }
 
 impl Book for TxtBook {
-	fn lines(&self) -> &Vec<Line> {
-		&self.lines
+	#[inline]
+	fn chapter_count(&self) -> usize
+	{
+		self.chapters.len()
+	}
+
+	fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
+	{
+		if chapter_index >= self.chapters.len() {
+			return Ok(None);
+		}
+		self.chapter_index = chapter_index;
+		Ok(Some(chapter_index))
+	}
+
+	#[inline]
+	fn current_chapter(&self) -> usize
+	{
+		self.chapter_index
+	}
+
+	#[inline]
+	fn title(&self, _line: usize, _offset: usize) -> Option<&str>
+	{
+		Some(self.titles.get(self.chapter_index)?.as_str())
+	}
+
+	#[inline]
+	fn toc_index(&self, _line: usize, _offset: usize) -> usize
+	{
+		self.chapter_index
+	}
+
+	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo<'_>> + '_>>
+	{
+		if self.titles.is_empty() {
+			return None;
+		}
+		let iter = ListIterator::new(|index| {
+			let title = self.titles.get(index)?;
+			Some(TocInfo { title, index, level: 1 })
+		});
+		Some(Box::new(iter))
 	}
 
-	fn leading_space(&self) -> usize {
+	fn toc_position(&mut self, toc_index: usize) -> Option<TraceInfo>
+	{
+		if toc_index >= self.chapters.len() {
+			return None;
+		}
+		Some(TraceInfo { chapter: toc_index, line: 0, offset: 0 })
+	}
+
+	#[inline]
+	fn lines(&self) -> &Vec<Line>
+	{
+		&self.chapters[self.chapter_index]
+	}
+
+	#[inline]
+	fn leading_space(&self) -> usize
+	{
 		self.leading_space
 	}
 }
@@ -36,8 +101,18 @@ impl Loader for TxtLoader {
 		&self.extensions
 	}
 
+	#[inline]
+	fn format(&self) -> &str {
+		"txt"
+	}
+
+	#[inline]
+	fn sniff(&self, head: &[u8]) -> bool {
+		strip_bom(head).is_some() && !is_html(head)
+	}
+
 	fn load_buf(&self, filename: &str, content: Vec<u8>,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let lines = plain_text_lines(content)?;
@@ -46,7 +121,26 @@ impl Loader for TxtLoader {
 		} else {
 			2
 		};
-		let book = TxtBook { lines, leading_space };
-		Ok((Box::new(book), loading.get()))
+		let mut reading = loading.get();
+		let (chapters, titles) = match split_chapters(&lines) {
+			Some(chapters) => {
+				relocate(&mut reading, &chapters);
+				let titles = chapters.iter().map(|chapter| chapter.title.clone()).collect();
+				(split_lines(lines, &chapters), titles)
+			}
+			None => (vec![lines], vec![]),
+		};
+		let chapter_index = match loading_chapter {
+			LoadingChapter::Index(0) => reading.chapter,
+			LoadingChapter::Index(index) => index,
+			LoadingChapter::Last => chapters.len() - 1,
+		};
+		let book = TxtBook {
+			chapter_index: chapter_index.min(chapters.len() - 1),
+			chapters,
+			titles,
+			leading_space,
+		};
+		Ok((Box::new(book), reading))
 	}
-}
\ No newline at end of file
+}
@@ -230,4 +230,80 @@ impl Colors {
 		matched_color: Color32::BLACK,
 		matched_background: Color32::LIGHT_GRAY,
 	};
+	// pure black on white, for e-ink screens
+	#[cfg(feature = "gui")]
+	pub const EINK: Colors = Colors {
+		color: Color32::BLACK,
+		background: Color32::WHITE,
+		highlight: Color32::WHITE,
+		highlight_background: Color32::BLACK,
+		link: Color32::BLACK,
+		matched_color: Color32::WHITE,
+		matched_background: Color32::DARK_GRAY,
+	};
+	// high contrast palettes of accessibility preset
+	#[cfg(feature = "gui")]
+	pub const HIGH_CONTRAST_BRIGHT: Colors = Colors {
+		color: Color32::BLACK,
+		background: Color32::WHITE,
+		highlight: Color32::WHITE,
+		highlight_background: Color32::DARK_BLUE,
+		link: Color32::DARK_BLUE,
+		matched_color: Color32::BLACK,
+		matched_background: Color32::YELLOW,
+	};
+	#[cfg(feature = "gui")]
+	pub const HIGH_CONTRAST_DARK: Colors = Colors {
+		color: Color32::YELLOW,
+		background: Color32::BLACK,
+		highlight: Color32::BLACK,
+		highlight_background: Color32::LIGHT_BLUE,
+		link: Color32::LIGHT_BLUE,
+		matched_color: Color32::BLACK,
+		matched_background: Color32::WHITE,
+	};
+
+	/// colors filtered by light of the temperature in kelvin, for night light
+	#[cfg(feature = "gui")]
+	pub fn with_temperature(&self, kelvin: u16) -> Colors
+	{
+		let factors = temperature_factors(kelvin);
+		Colors {
+			color: self.color.filtered(&factors),
+			background: self.background.filtered(&factors),
+			highlight: self.highlight.filtered(&factors),
+			highlight_background: self.highlight_background.filtered(&factors),
+			link: self.link.filtered(&factors),
+			matched_color: self.matched_color.filtered(&factors),
+			matched_background: self.matched_background.filtered(&factors),
+		}
+	}
+}
+
+#[cfg(feature = "gui")]
+impl Color32 {
+	#[inline]
+	fn filtered(&self, factors: &[f32; 3]) -> Color32
+	{
+		Color32::from_rgba_premultiplied(
+			(self.r() as f32 * factors[0]) as u8,
+			(self.g() as f32 * factors[1]) as u8,
+			(self.b() as f32 * factors[2]) as u8,
+			self.a())
+	}
+}
+
+/// rgb factors of white light with the temperature,
+/// approximated from black body, neutral at 6600K and above
+#[cfg(feature = "gui")]
+fn temperature_factors(kelvin: u16) -> [f32; 3]
+{
+	let temperature = kelvin.clamp(1000, 6600) as f32 / 100.;
+	let green = 99.470802 * temperature.ln() - 161.119568;
+	let blue = if temperature <= 19. {
+		0.
+	} else {
+		138.517731 * (temperature - 10.).ln() - 305.044793
+	};
+	[1., (green / 255.).clamp(0., 1.), (blue / 255.).clamp(0., 1.)]
 }
@@ -2,8 +2,11 @@ use anyhow::Result;
 use chardetng::EncodingDetector;
 use encoding_rs::{Encoding, UTF_8};
 use std::borrow::Borrow;
+use std::io::{Read, Seek};
 use std::ops::Range;
+use std::sync::OnceLock;
 use unicode_width::UnicodeWidthChar;
+use zip::ZipArchive;
 
 use crate::book::Line;
 
@@ -48,6 +51,26 @@ const HAN_RENDER_CHARS_PAIRS: [(char, char); 36] = [
 ];
 
 /// sorted, for binary search
+// chars sampled for detecting language of text
+#[cfg(feature = "gui")]
+const LANGUAGE_SAMPLE_CHARS: usize = 4000;
+// frequent words telling latin languages apart
+#[cfg(feature = "gui")]
+const LATIN_STOP_WORDS: [(&str, [&str; 6]); 4] = [
+	("en", ["the", "and", "of", "to", "is", "that"]),
+	("fr", ["le", "les", "et", "est", "des", "une"]),
+	("de", ["der", "die", "und", "das", "ist", "nicht"]),
+	("es", ["el", "los", "que", "y", "del", "las"]),
+];
+// least stop words found for a latin language
+#[cfg(feature = "gui")]
+const MIN_STOP_WORDS: usize = 3;
+
+// for zip entry names not in utf-8, detected if not configured
+static ZIP_NAME_ENCODING: OnceLock<&'static Encoding> = OnceLock::new();
+// byte order marks of utf-8, utf-16le and utf-16be
+const UTF_BOMS: [&[u8]; 3] = [b"\xEF\xBB\xBF", b"\xFF\xFE", b"\xFE\xFF"];
+
 const HAN_COMPACT_CHARS: [char; 22] = [
 	'·',
 	'、',
@@ -85,6 +108,7 @@ impl Position {
 	}
 }
 
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct TraceInfo {
 	pub chapter: usize,
 	pub line: usize,
@@ -134,6 +158,59 @@ pub(crate) fn decode_text(content: Vec<u8>, encoding: &'static Encoding) -> Resu
 	Ok(text)
 }
 
+/// content after the byte order mark, None if no bom
+#[inline]
+pub(crate) fn strip_bom(head: &[u8]) -> Option<&[u8]>
+{
+	UTF_BOMS.iter().find_map(|bom| head.strip_prefix(*bom))
+}
+
+/// set once at start from configuration, false for unknown label
+pub fn set_zip_name_encoding(label: &str) -> bool
+{
+	match Encoding::for_label(label.trim().as_bytes()) {
+		Some(encoding) => {
+			let _ = ZIP_NAME_ENCODING.set(encoding);
+			true
+		}
+		None => false,
+	}
+}
+
+/// names of all entries by index, names not in utf-8 like GBK or
+/// Shift-JIS translated with the configured or detected encoding
+pub(crate) fn zip_entry_names<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<Vec<String>>
+{
+	let mut names = Vec::with_capacity(zip.len());
+	let mut raw_names = vec![];
+	for index in 0..zip.len() {
+		// names readable without password
+		let file = zip.by_index_raw(index)?;
+		match std::str::from_utf8(file.name_raw()) {
+			Ok(name) => names.push(Some(name.to_owned())),
+			Err(_) => {
+				raw_names.push(file.name_raw().to_vec());
+				names.push(None);
+			}
+		}
+	}
+	if raw_names.is_empty() {
+		return Ok(names.into_iter().flatten().collect());
+	}
+	let encoding = match ZIP_NAME_ENCODING.get() {
+		Some(encoding) => *encoding,
+		None => detect_charset(&raw_names.join(&b'\n'), true),
+	};
+	let mut decoded = raw_names
+		.iter()
+		.map(|raw| encoding.decode(raw).0.into_owned());
+	let names = names
+		.into_iter()
+		.map(|name| name.or_else(|| decoded.next()).unwrap_or_default())
+		.collect();
+	Ok(names)
+}
+
 pub(crate) fn plain_text_lines(content: Vec<u8>) -> Result<Vec<Line>> {
 	let text = plain_text(content, false)?;
 	Ok(txt_lines(&text))
@@ -269,6 +346,22 @@ pub fn is_compact_for_han(ch: char) -> bool
 	HAN_COMPACT_CHARS.binary_search(&ch).is_ok()
 }
 
+/// han, kana and hangul, written without spaces between words
+#[allow(unused)]
+#[inline]
+pub fn is_cjk(ch: char) -> bool
+{
+	matches!(ch,
+		'\u{2e80}'..='\u{2fdf}'
+		| '\u{3040}'..='\u{30ff}'
+		| '\u{3100}'..='\u{31bf}'
+		| '\u{3400}'..='\u{4dbf}'
+		| '\u{4e00}'..='\u{9fff}'
+		| '\u{ac00}'..='\u{d7af}'
+		| '\u{f900}'..='\u{faff}'
+		| '\u{20000}'..='\u{2fa1f}')
+}
+
 #[inline]
 pub fn han_render_char(ch: char) -> char
 {
@@ -278,9 +371,127 @@ pub fn han_render_char(ch: char) -> char
 	}
 }
 
+/// score of pattern chars found in text by order, ignoring case,
+/// higher for consecutive chars and word starts, None if not all found
+#[allow(unused)]
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<usize>
+{
+	let mut score = 0;
+	let mut chars = text.chars().flat_map(char::to_lowercase).enumerate().peekable();
+	let mut last_matched = None;
+	let mut prev_char = None;
+	for pattern_char in pattern.chars().flat_map(char::to_lowercase) {
+		if pattern_char.is_whitespace() {
+			continue;
+		}
+		loop {
+			let (index, ch) = chars.next()?;
+			let word_start = prev_char.map_or(true, |prev: char| !prev.is_alphanumeric());
+			prev_char = Some(ch);
+			if ch == pattern_char {
+				score += 1;
+				if last_matched.map_or(false, |last| last + 1 == index) {
+					score += 2;
+				}
+				if word_start {
+					score += 2;
+				}
+				last_matched = Some(index);
+				break;
+			}
+		}
+	}
+	Some(score)
+}
+
+/// main language of text by scripts of sampled chars, like "zh" or "ru",
+/// latin languages told apart by frequent words
+#[cfg(feature = "gui")]
+pub fn detect_language(lines: &[Line]) -> Option<&'static str>
+{
+	#[inline]
+	fn script_language(ch: char) -> Option<&'static str>
+	{
+		match ch {
+			'\u{3040}'..='\u{30ff}' => Some("ja"),
+			'\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' => Some("zh"),
+			'\u{1100}'..='\u{11ff}' | '\u{ac00}'..='\u{d7af}' => Some("ko"),
+			'\u{0400}'..='\u{04ff}' => Some("ru"),
+			'\u{0370}'..='\u{03ff}' => Some("el"),
+			'\u{0590}'..='\u{05ff}' => Some("he"),
+			'\u{0600}'..='\u{06ff}' => Some("ar"),
+			'\u{0e00}'..='\u{0e7f}' => Some("th"),
+			'a'..='z' | 'A'..='Z' | '\u{00c0}'..='\u{024f}' => Some("latin"),
+			_ => None,
+		}
+	}
+
+	let mut counts: Vec<(&'static str, usize)> = vec![];
+	let mut latin_text = String::new();
+	let mut sampled = 0;
+	'sample: for line in lines {
+		for ch in line.iter() {
+			let Some(language) = script_language(*ch) else {
+				latin_text.push(' ');
+				continue;
+			};
+			if sampled >= LANGUAGE_SAMPLE_CHARS {
+				break 'sample;
+			}
+			sampled += 1;
+			match counts.iter_mut().find(|(script, _)| *script == language) {
+				Some((_, count)) => *count += 1,
+				None => counts.push((language, 1)),
+			}
+			if language == "latin" {
+				latin_text.extend(ch.to_lowercase());
+			}
+		}
+		latin_text.push(' ');
+	}
+	let count_of = |language| counts.iter()
+		.find(|(script, _)| *script == language)
+		.map_or(0, |(_, count)| *count);
+	let (language, _) = *counts.iter().max_by_key(|(_, count)| *count)?;
+	match language {
+		// kana always mixed with han in japanese
+		"zh" | "ja" => if count_of("ja") * 20 >= count_of("zh") {
+			Some("ja")
+		} else {
+			Some("zh")
+		}
+		"latin" => {
+			let words: Vec<&str> = latin_text.split_whitespace().collect();
+			LATIN_STOP_WORDS.iter()
+				.map(|(language, stop_words)| (*language, words
+					.iter()
+					.filter(|word| stop_words.contains(word))
+					.count()))
+				.filter(|(_, count)| *count >= MIN_STOP_WORDS)
+				.max_by_key(|(_, count)| *count)
+				.map(|(language, _)| language)
+		}
+		language => Some(language),
+	}
+}
+
+/// primary part of language tag, zh-Hant -> zh
+#[cfg(feature = "gui")]
+#[inline]
+pub fn primary_language(tag: &str) -> Option<String>
+{
+	let language = tag.trim().split(['-', '_']).next()?;
+	if language.is_empty() {
+		None
+	} else {
+		Some(language.to_lowercase())
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::common::{is_overlap, overlap_range};
+	use crate::book::Line;
+	use crate::common::{detect_language, fuzzy_score, is_overlap, overlap_range, primary_language, strip_bom};
 
 	#[test]
 	fn test_is_range_overlap()
@@ -299,6 +510,31 @@ mod tests {
 		assert!(!is_overlap(&(10..15), &(15..16)));
 	}
 
+	#[test]
+	fn test_fuzzy_score()
+	{
+		assert_eq!(fuzzy_score("", "chapter"), Some(0));
+		assert_eq!(fuzzy_score("cpt", "chapter"), Some(7));
+		assert_eq!(fuzzy_score("CHA", "chapter"), Some(9));
+		assert_eq!(fuzzy_score("xyz", "chapter"), None);
+		assert!(fuzzy_score("ch 2", "chapter 2").unwrap() > fuzzy_score("ch2", "chapter 12").unwrap());
+	}
+
+	#[test]
+	fn test_detect_language()
+	{
+		let lines = |text: &str| text.lines().map(Line::new).collect::<Vec<_>>();
+		assert_eq!(detect_language(&lines("It is the book of the year, and that is all.")), Some("en"));
+		assert_eq!(detect_language(&lines("Der Hund und die Katze, das ist nicht gut.")), Some("de"));
+		assert_eq!(detect_language(&lines("天地玄黄，宇宙洪荒。日月盈昃，辰宿列张。")), Some("zh"));
+		assert_eq!(detect_language(&lines("吾輩は猫である。名前はまだ無い。")), Some("ja"));
+		assert_eq!(detect_language(&lines("Война и мир")), Some("ru"));
+		assert_eq!(detect_language(&lines("12345")), None);
+		assert_eq!(primary_language("zh-Hant"), Some("zh".to_owned()));
+		assert_eq!(primary_language(" EN_us"), Some("en".to_owned()));
+		assert_eq!(primary_language(""), None);
+	}
+
 	#[test]
 	fn test_overlap_range()
 	{
@@ -315,4 +551,13 @@ mod tests {
 		assert!(overlap_range(&(10..15), &(8..9)).is_none());
 		assert!(overlap_range(&(10..15), &(15..16)).is_none());
 	}
+
+	#[test]
+	fn test_strip_bom()
+	{
+		assert_eq!(strip_bom(b"\xEF\xBB\xBFtext"), Some(&b"text"[..]));
+		assert_eq!(strip_bom(b"\xFF\xFEt\x00"), Some(&b"t\x00"[..]));
+		assert_eq!(strip_bom(b"\xFE\xFF\x00t"), Some(&b"\x00t"[..]));
+		assert_eq!(strip_bom(b"text"), None);
+	}
 }
\ No newline at end of file
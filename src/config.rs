@@ -1,19 +1,46 @@
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+#[cfg(feature = "gui")]
+use std::ops::Range;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 #[cfg(feature = "gui")]
 use gtk4::Orientation;
-use rusqlite::{Connection, Row};
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row};
 use serde_derive::{Deserialize, Serialize};
+use zip::{ZipArchive, ZipWriter};
+use zip::write::SimpleFileOptions;
 
 use crate::color::Colors;
+#[cfg(feature = "gui")]
+use crate::common::Position;
+use crate::common::TraceInfo;
+use crate::container::{is_stdin, is_stdin_name};
+#[cfg(feature = "script")]
+use crate::script::SCRIPT_FILE;
 #[cfg(feature = "i18n")]
 use crate::i18n;
+use crate::secret::SecretKey;
 use crate::terminal::Listable;
 
+const PASSWORD_KEY_FILE: &str = "password.key";
+
+const AUTO_TAG_NEW: &str = "new";
+const AUTO_TAG_IN_PROGRESS: &str = "in-progress";
+const AUTO_TAG_FINISHED: &str = "finished";
+// minimum spacing percent of the dyslexia preset
+#[cfg(feature = "gui")]
+const DYSLEXIA_LETTER_SPACING: u8 = 15;
+#[cfg(feature = "gui")]
+const DYSLEXIA_WORD_SPACING: u8 = 40;
+
 #[derive(Clone)]
 pub struct ReadingInfo {
 	row_id: i64,
@@ -27,6 +54,12 @@ pub struct ReadingInfo {
 	pub strip_empty_lines: bool,
 	pub custom_style: Option<String>,
 	pub font_size: u8,
+	// images not loaded, placeholders only
+	pub text_only: bool,
+	// last read, seconds since epoch, 0 for new reading
+	pub ts: u64,
+	// render direction of the book, global one used if none
+	pub render_han: Option<bool>,
 }
 
 impl ReadingInfo {
@@ -46,6 +79,9 @@ impl ReadingInfo {
 			strip_empty_lines: false,
 			custom_style: None,
 			font_size: default_font_size(),
+			text_only: false,
+			ts: 0,
+			render_han: None,
 		}
 	}
 
@@ -57,7 +93,19 @@ impl ReadingInfo {
 			inner_book,
 			self.row_id,
 			self.custom_style.clone(),
-			self.font_size)
+			self.font_size,
+			self.text_only)
+	}
+
+	/// tag from reading position, finished state not included
+	#[inline]
+	pub fn auto_tag(&self) -> &'static str
+	{
+		if self.inner_book == 0 && self.chapter == 0 && self.line == 0 && self.position == 0 {
+			AUTO_TAG_NEW
+		} else {
+			AUTO_TAG_IN_PROGRESS
+		}
 	}
 
 	#[inline]
@@ -86,10 +134,96 @@ impl Listable for ReadingInfo {
 	}
 }
 
+/// category of highlight, told apart by color
+#[derive(Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+pub enum HighlightCategory {
+	Vocabulary,
+	Quote,
+	Idea,
+	Question,
+}
+
+#[cfg(feature = "gui")]
+impl Default for HighlightCategory {
+	#[inline]
+	fn default() -> Self
+	{
+		HighlightCategory::Vocabulary
+	}
+}
+
+#[cfg(feature = "gui")]
+impl HighlightCategory {
+	pub const ALL: [HighlightCategory; 4] = [
+		HighlightCategory::Vocabulary,
+		HighlightCategory::Quote,
+		HighlightCategory::Idea,
+		HighlightCategory::Question,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			HighlightCategory::Vocabulary => "highlight-vocabulary",
+			HighlightCategory::Quote => "highlight-quote",
+			HighlightCategory::Idea => "highlight-idea",
+			HighlightCategory::Question => "highlight-question",
+		}
+	}
+
+	/// index in ALL, saved in history db
+	#[inline]
+	fn index(&self) -> usize
+	{
+		Self::ALL.iter().position(|category| category == self).unwrap()
+	}
+
+	#[inline]
+	fn from_index(index: Option<usize>) -> Self
+	{
+		index
+			.and_then(|index| Self::ALL.get(index).copied())
+			.unwrap_or_default()
+	}
+}
+
+/// highlighted text with optional note, or bookmark with empty range
+#[cfg(feature = "gui")]
+#[derive(Clone)]
+pub struct AnnotationInfo {
+	pub id: i64,
+	pub chapter: usize,
+	pub chapter_title: Option<String>,
+	pub range: Range<Position>,
+	// text highlighted, or text of the bookmarked line
+	pub text: String,
+	pub note: Option<String>,
+	pub category: HighlightCategory,
+	pub ts: u64,
+}
+
+#[cfg(feature = "gui")]
+impl AnnotationInfo {
+	pub fn new(chapter: usize, chapter_title: Option<String>, range: Range<Position>,
+		text: String, note: Option<String>, category: HighlightCategory) -> Self
+	{
+		AnnotationInfo { id: 0, chapter, chapter_title, range, text, note, category, ts: 0 }
+	}
+
+	#[inline]
+	pub fn is_bookmark(&self) -> bool
+	{
+		self.range.start.line == self.range.end.line
+			&& self.range.start.offset == self.range.end.offset
+	}
+}
+
 #[allow(unused)]
 pub enum BookLoadingInfo<'a> {
 	NewReading(&'a str, usize, usize, u8),
-	ChangeInnerBook(&'a str, usize, i64, Option<String>, u8),
+	ChangeInnerBook(&'a str, usize, i64, Option<String>, u8, bool),
 	History(ReadingInfo),
 	Reload(ReadingInfo),
 }
@@ -121,8 +255,11 @@ impl<'a> BookLoadingInfo<'a> {
 				strip_empty_lines: false,
 				custom_style: None,
 				font_size,
+				text_only: false,
+				ts: 0,
+				render_han: None,
 			},
-			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size) =>
+			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size, text_only) =>
 				ReadingInfo {
 					row_id,
 					filename: filename.to_owned(),
@@ -135,6 +272,9 @@ impl<'a> BookLoadingInfo<'a> {
 					strip_empty_lines: false,
 					custom_style: custom_style.clone(),
 					font_size,
+					text_only,
+					ts: 0,
+					render_han: None,
 				},
 			BookLoadingInfo::History(reading) | BookLoadingInfo::Reload(reading) => reading,
 		}
@@ -158,11 +298,14 @@ impl<'a> BookLoadingInfo<'a> {
 					strip_empty_lines: false,
 					custom_style: None,
 					font_size,
+					text_only: false,
+					ts: 0,
+					render_han: None,
 				};
 				f(&mut reading);
 				reading
 			}
-			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size) => {
+			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size, text_only) => {
 				let mut reading = ReadingInfo {
 					row_id,
 					filename: filename.to_owned(),
@@ -175,6 +318,9 @@ impl<'a> BookLoadingInfo<'a> {
 					strip_empty_lines: false,
 					custom_style: custom_style.clone(),
 					font_size,
+					text_only,
+					ts: 0,
+					render_han: None,
 				};
 				f(&mut reading);
 				reading
@@ -196,6 +342,7 @@ pub struct PathConfig {
 pub enum SidebarPosition {
 	Left,
 	Top,
+	Right,
 }
 
 #[cfg(feature = "gui")]
@@ -213,7 +360,7 @@ impl SidebarPosition {
 	pub fn paned_orientation(&self) -> Orientation
 	{
 		match self {
-			SidebarPosition::Left => Orientation::Horizontal,
+			SidebarPosition::Left | SidebarPosition::Right => Orientation::Horizontal,
 			SidebarPosition::Top => Orientation::Vertical,
 		}
 	}
@@ -223,174 +370,1018 @@ impl SidebarPosition {
 		match self {
 			SidebarPosition::Left => "sidebar-left",
 			SidebarPosition::Top => "sidebar-top",
+			SidebarPosition::Right => "sidebar-right",
 		}
 	}
+	/// sidebar as the end child of paned
+	#[inline]
+	pub fn at_end(&self) -> bool
+	{
+		matches!(self, SidebarPosition::Right)
+	}
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
+/// how to render font weight requested by book
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[cfg(feature = "gui")]
-pub struct GuiConfiguration {
-	#[serde(default)]
-	pub themes: Themes,
-	pub fonts: Vec<PathConfig>,
-	#[serde(default = "default_font_size")]
-	pub default_font_size: u8,
-	#[serde(default = "default_font_size")]
-	pub dict_font_size: u8,
-	pub sidebar_size: u32,
-	#[serde(default)]
-	pub sidebar_position: SidebarPosition,
-	#[serde(default = "default_locale")]
-	pub lang: String,
-	pub dictionaries: Vec<PathConfig>,
-	pub cache_dict: bool,
-	pub strip_empty_lines: bool,
-	pub ignore_font_weight: bool,
-	#[serde(default)]
-	pub scroll_for_page: bool,
-	#[serde(default)]
-	pub select_by_dictionary: bool,
+#[serde(rename_all = "snake_case")]
+pub enum FontWeightMode {
+	// use the weight of available font face
+	Book,
+	// synthesize bold when font face of the weight not found
+	Synthesize,
+	// render all text with normal weight
+	Ignore,
 }
 
 #[cfg(feature = "gui")]
-impl Default for GuiConfiguration
-{
-	fn default() -> Self {
-		GuiConfiguration {
-			themes: Themes::default(),
-			fonts: vec![],
-			default_font_size: default_font_size(),
-			dict_font_size: default_font_size(),
-			sidebar_size: 300,
-			sidebar_position: Default::default(),
-			lang: default_locale(),
-			dictionaries: vec![],
-			cache_dict: false,
-			strip_empty_lines: false,
-			ignore_font_weight: false,
-			scroll_for_page: false,
-			select_by_dictionary: false,
+impl Default for FontWeightMode {
+	#[inline]
+	fn default() -> Self
+	{
+		FontWeightMode::Book
+	}
+}
+
+#[cfg(feature = "gui")]
+impl FontWeightMode {
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			FontWeightMode::Book => "font-weight-book",
+			FontWeightMode::Synthesize => "font-weight-synthesize",
+			FontWeightMode::Ignore => "font-weight-ignore",
 		}
 	}
 }
 
+/// axes value of variable fonts, for text without weight from book
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
 #[cfg(feature = "gui")]
-impl GuiConfiguration {
-	pub fn curr_colors(&self, dark: bool) -> &Colors
+pub struct FontAxes {
+	pub weight: Option<u16>,
+	pub width: Option<u16>,
+}
+
+/// buttons in toolbar
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum ToolbarItem {
+	Sidebar,
+	Menu,
+	ReadAloud,
+	Search,
+}
+
+#[cfg(feature = "gui")]
+impl ToolbarItem {
+	pub const ALL: [ToolbarItem; 4] = [
+		ToolbarItem::Sidebar,
+		ToolbarItem::Menu,
+		ToolbarItem::ReadAloud,
+		ToolbarItem::Search,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
 	{
-		if dark {
-			&self.themes.dark
-		} else {
-			&self.themes.bright
+		match self {
+			ToolbarItem::Sidebar => "sidebar",
+			ToolbarItem::Menu => "menu",
+			ToolbarItem::ReadAloud => "read-aloud",
+			ToolbarItem::Search => "search-hint",
 		}
 	}
+}
 
-	pub fn curr_colors_mut(&mut self, dark: bool) -> &mut Colors
+/// information shown beside status message
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum StatusWidget {
+	ChapterTitle,
+	Progress,
+	Clock,
+	Battery,
+	Words,
+	// today's progress of reading goal
+	Goal,
+	// estimated page of current chapter
+	Pages,
+}
+
+#[cfg(feature = "gui")]
+impl StatusWidget {
+	pub const ALL: [StatusWidget; 7] = [
+		StatusWidget::ChapterTitle,
+		StatusWidget::Progress,
+		StatusWidget::Clock,
+		StatusWidget::Battery,
+		StatusWidget::Words,
+		StatusWidget::Goal,
+		StatusWidget::Pages,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
 	{
-		if dark {
-			&mut self.themes.dark
-		} else {
-			&mut self.themes.bright
+		match self {
+			StatusWidget::ChapterTitle => "status-chapter-title",
+			StatusWidget::Progress => "status-progress",
+			StatusWidget::Clock => "status-clock",
+			StatusWidget::Battery => "status-battery",
+			StatusWidget::Words => "status-words",
+			StatusWidget::Goal => "status-goal",
+			StatusWidget::Pages => "status-pages",
 		}
 	}
 }
 
-pub struct Configuration {
-	pub render_han: bool,
-	pub dark_theme: bool,
-	history: PathBuf,
-	#[cfg(feature = "gui")]
-	pub gui: GuiConfiguration,
+/// reading aid, dim the text out of focus
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum FocusMode {
+	// keep the paragraph under pointer bright
+	Paragraph,
+	// keep the rendered line under pointer bright
+	Ruler,
+}
 
-	config_file: PathBuf,
-	history_db: Connection,
-	orig: RawConfig,
+#[cfg(feature = "gui")]
+impl Default for FocusMode {
+	#[inline]
+	fn default() -> Self
+	{
+		FocusMode::Paragraph
+	}
 }
 
-impl Configuration {
-	pub fn save(&self) -> Result<()>
+#[cfg(feature = "gui")]
+impl FocusMode {
+	pub const ALL: [FocusMode; 2] = [
+		FocusMode::Paragraph,
+		FocusMode::Ruler,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
 	{
-		let raw_config = RawConfig {
-			render_han: self.render_han,
-			dark_theme: self.dark_theme,
-			history: self.history.clone(),
-			#[cfg(feature = "gui")]
-			gui: self.gui.clone(),
-		};
-		if self.orig != raw_config {
-			let text = toml::to_string(&raw_config)?;
-			fs::write(&self.config_file, text)?;
+		match self {
+			FocusMode::Paragraph => "focus-paragraph",
+			FocusMode::Ruler => "focus-ruler",
 		}
-		Ok(())
 	}
+}
 
-	#[cfg(feature = "gui")]
-	pub fn curr_theme(&self) -> &Colors
+/// accessibility presets, applied over themes and text spacing
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum AccessibilityPreset {
+	Off,
+	// high contrast palette for the theme, book colors ignored
+	HighContrast,
+	// OpenDyslexic font with extra letter and word spacing
+	Dyslexia,
+}
+
+#[cfg(feature = "gui")]
+impl Default for AccessibilityPreset {
+	#[inline]
+	fn default() -> Self
 	{
-		if self.dark_theme {
-			&self.gui.themes.dark
-		} else {
-			&self.gui.themes.bright
-		}
+		AccessibilityPreset::Off
 	}
+}
 
-	fn map(row: &Row) -> rusqlite::Result<ReadingInfo>
+#[cfg(feature = "gui")]
+impl AccessibilityPreset {
+	pub const ALL: [AccessibilityPreset; 3] = [
+		AccessibilityPreset::Off,
+		AccessibilityPreset::HighContrast,
+		AccessibilityPreset::Dyslexia,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
 	{
-		Ok(ReadingInfo {
-			row_id: row.get(0)?,
-			filename: row.get(1)?,
-			inner_book: row.get(2)?,
-			chapter: row.get(3)?,
-			line: row.get(4)?,
-			position: row.get(5)?,
-			custom_color: row.get(6)?,
-			custom_font: row.get(7)?,
-			strip_empty_lines: row.get(8)?,
-			custom_style: row.get(9)?,
-			font_size: row.get::<usize, Option<u8>>(10)?.
-				unwrap_or(default_font_size()),
-		})
+		match self {
+			AccessibilityPreset::Off => "accessibility-off",
+			AccessibilityPreset::HighContrast => "accessibility-high-contrast",
+			AccessibilityPreset::Dyslexia => "accessibility-dyslexia",
+		}
 	}
+}
 
-	pub fn history(&self, current: Option<&String>, filter_pattern: Option<&String>)
-		-> Result<Vec<ReadingInfo>>
+/// online service for translating selected text
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum TranslateBackend {
+	Deepl,
+	Google,
+	LibreTranslate,
+}
+
+#[cfg(feature = "gui")]
+impl Default for TranslateBackend {
+	#[inline]
+	fn default() -> Self
 	{
-		Ok(query(&self.history_db, 20, current, filter_pattern)?)
+		TranslateBackend::LibreTranslate
 	}
+}
 
-	pub fn reading<'a>(&self, filename: &'a str) -> Result<BookLoadingInfo<'a>>
+#[cfg(feature = "gui")]
+impl TranslateBackend {
+	#[inline]
+	pub fn default_endpoint(&self) -> &'static str
 	{
-		let mut stmt = self.history_db.prepare("
-select row_id,
-       filename,
-       inner_book,
-       chapter,
-       line,
-       position,
-       custom_color,
-       custom_font,
-       strip_empty_lines,
-       custom_style,
-       font_size,
-       ts
-from history
-where filename = ?
-")?;
-		let mut iter = stmt.query_map([filename], Configuration::map)?;
-		if let Some(info) = iter.next() {
-			Ok(BookLoadingInfo::History(info?))
-		} else {
-			#[cfg(feature = "gui")]
-			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, self.gui.default_font_size)) }
-			#[cfg(not(feature = "gui"))]
-			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, default_font_size())) }
+		match self {
+			TranslateBackend::Deepl => "https://api-free.deepl.com/v2/translate",
+			TranslateBackend::Google => "https://translation.googleapis.com/language/translate/v2",
+			TranslateBackend::LibreTranslate => "https://libretranslate.com/translate",
 		}
 	}
+}
 
-	pub fn reading_by_id(&self, row_id: i64) -> Result<ReadingInfo>
-	{
-		let mut stmt = self.history_db.prepare("
-select row_id,
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[cfg(feature = "gui")]
+pub struct TranslateConfig {
+	#[serde(default)]
+	pub backend: TranslateBackend,
+	// use the default endpoint of backend if not set
+	pub endpoint: Option<String>,
+	pub api_key: Option<String>,
+	// use ui language if not set
+	pub target_lang: Option<String>,
+}
+
+/// where exported flashcards go
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum FlashcardTarget {
+	// append to tsv file for anki import
+	Tsv,
+	// add note by AnkiConnect add-on
+	AnkiConnect,
+}
+
+#[cfg(feature = "gui")]
+impl Default for FlashcardTarget {
+	#[inline]
+	fn default() -> Self
+	{
+		FlashcardTarget::Tsv
+	}
+}
+
+#[cfg(feature = "gui")]
+impl FlashcardTarget {
+	pub const ALL: [FlashcardTarget; 2] = [
+		FlashcardTarget::Tsv,
+		FlashcardTarget::AnkiConnect,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			FlashcardTarget::Tsv => "flashcard-tsv",
+			FlashcardTarget::AnkiConnect => "flashcard-anki-connect",
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct FlashcardConfig {
+	#[serde(default)]
+	pub target: FlashcardTarget,
+	// tbr-flashcards.tsv in home folder if not set
+	pub tsv_file: Option<PathBuf>,
+	#[serde(default = "default_anki_connect_url")]
+	pub anki_connect_url: String,
+	#[serde(default = "default_anki_deck")]
+	pub deck: String,
+	#[serde(default = "default_anki_note_type")]
+	pub note_type: String,
+}
+
+#[cfg(feature = "gui")]
+impl Default for FlashcardConfig {
+	#[inline]
+	fn default() -> Self
+	{
+		FlashcardConfig {
+			target: Default::default(),
+			tsv_file: None,
+			anki_connect_url: default_anki_connect_url(),
+			deck: default_anki_deck(),
+			note_type: default_anki_note_type(),
+		}
+	}
+}
+
+/// unit of daily reading goal
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum GoalUnit {
+	Minutes,
+	// page turns
+	Pages,
+}
+
+#[cfg(feature = "gui")]
+impl Default for GoalUnit {
+	#[inline]
+	fn default() -> Self
+	{
+		GoalUnit::Minutes
+	}
+}
+
+#[cfg(feature = "gui")]
+impl GoalUnit {
+	pub const ALL: [GoalUnit; 2] = [
+		GoalUnit::Minutes,
+		GoalUnit::Pages,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			GoalUnit::Minutes => "goal-minutes",
+			GoalUnit::Pages => "goal-pages",
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[cfg(feature = "gui")]
+pub struct ReadingGoal {
+	#[serde(default)]
+	pub unit: GoalUnit,
+	// daily amount in unit, 0 for no goal
+	#[serde(default)]
+	pub amount: u32,
+}
+
+#[cfg(feature = "gui")]
+impl ReadingGoal {
+	/// reading of the day in goal unit
+	#[inline]
+	pub fn progress(&self, seconds: u64, pages: u64) -> u64
+	{
+		match self.unit {
+			GoalUnit::Minutes => seconds / 60,
+			GoalUnit::Pages => pages,
+		}
+	}
+
+	/// goal reached, or any reading if no goal set
+	#[inline]
+	pub fn reached(&self, seconds: u64, pages: u64) -> bool
+	{
+		if self.amount == 0 {
+			seconds > 0 || pages > 0
+		} else {
+			self.progress(seconds, pages) >= self.amount as u64
+		}
+	}
+}
+
+/// transition between pages in the view
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum PageTurn {
+	None,
+	Slide,
+	Fade,
+	// old page folded away with a shadow
+	Curl,
+}
+
+#[cfg(feature = "gui")]
+impl Default for PageTurn {
+	#[inline]
+	fn default() -> Self
+	{
+		PageTurn::None
+	}
+}
+
+#[cfg(feature = "gui")]
+impl PageTurn {
+	pub const ALL: [PageTurn; 4] = [
+		PageTurn::None,
+		PageTurn::Slide,
+		PageTurn::Fade,
+		PageTurn::Curl,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			PageTurn::None => "page-turn-none",
+			PageTurn::Slide => "page-turn-slide",
+			PageTurn::Fade => "page-turn-fade",
+			PageTurn::Curl => "page-turn-curl",
+		}
+	}
+}
+
+/// how the paper image fills the reading area
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum PaperMode {
+	Tile,
+	Stretch,
+}
+
+#[cfg(feature = "gui")]
+impl Default for PaperMode {
+	#[inline]
+	fn default() -> Self
+	{
+		PaperMode::Tile
+	}
+}
+
+#[cfg(feature = "gui")]
+impl PaperMode {
+	pub const ALL: [PaperMode; 2] = [
+		PaperMode::Tile,
+		PaperMode::Stretch,
+	];
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			PaperMode::Tile => "paper-tile",
+			PaperMode::Stretch => "paper-stretch",
+		}
+	}
+}
+
+/// background image or paper texture drawn beneath text
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct Paper {
+	// no paper without image
+	#[serde(default)]
+	pub path: Option<PathBuf>,
+	#[serde(default)]
+	pub mode: PaperMode,
+	// percent
+	#[serde(default = "default_paper_opacity")]
+	pub opacity: u8,
+}
+
+#[cfg(feature = "gui")]
+impl Default for Paper {
+	fn default() -> Self
+	{
+		Paper {
+			path: None,
+			mode: Default::default(),
+			opacity: default_paper_opacity(),
+		}
+	}
+}
+
+/// dictionaries looked up together, by dictionary names, like "EN-ZH"
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct DictionaryGroup {
+	pub name: String,
+	pub dictionaries: Vec<String>,
+	// selected for books in these languages, like "en"
+	#[serde(default)]
+	pub languages: Vec<String>,
+}
+
+/// keys for cycling links of current page, in gtk accelerator format like "<Shift>l"
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct LinkKeys {
+	#[serde(default = "default_next_link_key")]
+	pub next: String,
+	#[serde(default = "default_prev_link_key")]
+	pub prev: String,
+}
+
+#[cfg(feature = "gui")]
+impl Default for LinkKeys {
+	fn default() -> Self
+	{
+		LinkKeys {
+			next: default_next_link_key(),
+			prev: default_prev_link_key(),
+		}
+	}
+}
+
+/// warm color filter over the theme, always or only at night
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct NightLight {
+	#[serde(default)]
+	pub enabled: bool,
+	// enabled automatically between start and end hour
+	#[serde(default)]
+	pub scheduled: bool,
+	// color temperature in kelvin, lower is warmer
+	#[serde(default = "default_night_light_temperature")]
+	pub temperature: u16,
+	#[serde(default = "default_night_start_hour")]
+	pub start_hour: u8,
+	#[serde(default = "default_night_end_hour")]
+	pub end_hour: u8,
+}
+
+#[cfg(feature = "gui")]
+impl Default for NightLight {
+	fn default() -> Self
+	{
+		NightLight {
+			enabled: false,
+			scheduled: false,
+			temperature: default_night_light_temperature(),
+			start_hour: default_night_start_hour(),
+			end_hour: default_night_end_hour(),
+		}
+	}
+}
+
+#[cfg(feature = "gui")]
+impl NightLight {
+	/// active at the hour of local time
+	pub fn active(&self, hour: u8) -> bool
+	{
+		if self.enabled {
+			true
+		} else if !self.scheduled {
+			false
+		} else if self.start_hour <= self.end_hour {
+			hour >= self.start_hour && hour < self.end_hour
+		} else {
+			// over midnight
+			hour >= self.start_hour || hour < self.end_hour
+		}
+	}
+
+	#[inline]
+	pub fn active_now(&self) -> bool
+	{
+		if !self.enabled && !self.scheduled {
+			return false;
+		}
+		let hour = gtk4::glib::DateTime::now_local()
+			.map_or(0, |now| now.hour() as u8);
+		self.active(hour)
+	}
+}
+
+/// book window open at last exit
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct SessionBook {
+	pub filename: String,
+	// visible sidebar tab, none if sidebar hidden
+	pub sidebar: Option<String>,
+}
+
+/// main window state, restored on next start
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct WindowGeometry {
+	pub width: i32,
+	pub height: i32,
+	pub maximized: bool,
+}
+
+#[cfg(feature = "gui")]
+impl Default for WindowGeometry {
+	#[inline]
+	fn default() -> Self
+	{
+		WindowGeometry {
+			width: 800,
+			height: 600,
+			maximized: true,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct GuiConfiguration {
+	#[serde(default)]
+	pub themes: Themes,
+	pub fonts: Vec<PathConfig>,
+	#[serde(default = "default_font_size")]
+	pub default_font_size: u8,
+	#[serde(default = "default_font_size")]
+	pub dict_font_size: u8,
+	pub sidebar_size: u32,
+	#[serde(default)]
+	pub sidebar_position: SidebarPosition,
+	// dictionary in right panel instead of sidebar tab
+	#[serde(default)]
+	pub dict_docked: bool,
+	#[serde(default = "default_dict_dock_size")]
+	pub dict_dock_size: u32,
+	#[serde(default)]
+	pub window: WindowGeometry,
+	// reopen books of last session when started without book
+	#[serde(default)]
+	pub restore_session: bool,
+	#[serde(default)]
+	pub session: Vec<SessionBook>,
+	// shown toolbar items in order
+	#[serde(default = "default_toolbar")]
+	pub toolbar: Vec<ToolbarItem>,
+	#[serde(default)]
+	pub status_widgets: Vec<StatusWidget>,
+	#[serde(default = "default_locale")]
+	pub lang: String,
+	pub dictionaries: Vec<PathConfig>,
+	pub cache_dict: bool,
+	#[serde(default)]
+	pub dictionary_groups: Vec<DictionaryGroup>,
+	// name of the group looked up, all dictionaries if none
+	#[serde(default)]
+	pub dictionary_group: Option<String>,
+	pub strip_empty_lines: bool,
+	// replaced by font_weight_mode, only for loading old config
+	#[serde(default, skip_serializing)]
+	pub ignore_font_weight: bool,
+	#[serde(default)]
+	pub font_weight_mode: FontWeightMode,
+	#[serde(default)]
+	pub font_axes: FontAxes,
+	// extra space between chars, percent of font size
+	#[serde(default)]
+	pub letter_spacing: u8,
+	// extra space for word break and between CJK and Latin, percent of font size
+	#[serde(default)]
+	pub word_spacing: u8,
+	// percent for all text size
+	#[serde(default = "default_text_scale")]
+	pub text_scale: u16,
+	// percent of toolbar, sidebar and dialogs
+	#[serde(default = "default_ui_scale")]
+	pub ui_scale: u16,
+	#[serde(default)]
+	pub scroll_for_page: bool,
+	// lines stepped by a wheel tick
+	#[serde(default = "default_wheel_lines")]
+	pub wheel_lines: u8,
+	#[serde(default)]
+	pub select_by_dictionary: bool,
+	// copy selected text to clipboard without ctrl+c
+	#[serde(default)]
+	pub auto_copy: bool,
+	// lookup text copied in other applications
+	#[serde(default)]
+	pub watch_clipboard: bool,
+	// reload book when the file changed on disk
+	#[serde(default)]
+	pub watch_file: bool,
+	#[serde(default)]
+	pub focus: bool,
+	#[serde(default)]
+	pub focus_mode: FocusMode,
+	// percent of dimming for text out of focus
+	#[serde(default = "default_focus_dim")]
+	pub focus_dim: u8,
+	// bold the leading part of latin words
+	#[serde(default)]
+	pub bionic_reading: bool,
+	// no animation, black on white, flash on page turn and bigger buttons
+	#[serde(default)]
+	pub eink: bool,
+	#[serde(default)]
+	pub accessibility_preset: AccessibilityPreset,
+	#[serde(default)]
+	pub translate: TranslateConfig,
+	#[serde(default)]
+	pub flashcard: FlashcardConfig,
+	#[serde(default)]
+	pub reading_goal: ReadingGoal,
+	#[serde(default)]
+	pub night_light: NightLight,
+	#[serde(default)]
+	pub paper: Paper,
+	// not animated in e-ink mode
+	#[serde(default)]
+	pub page_turn: PageTurn,
+	#[serde(default = "default_page_turn_millis")]
+	pub page_turn_millis: u16,
+	#[serde(default)]
+	pub link_keys: LinkKeys,
+	// chapter overview beside the view
+	#[serde(default)]
+	pub minimap: bool,
+	// eased line scrolling and momentum of trackpad flicks
+	#[serde(default = "default_smooth_scroll")]
+	pub smooth_scroll: bool,
+	// history entries with folder, or file name only
+	#[serde(default = "default_history_full_path")]
+	pub history_full_path: bool,
+	#[serde(default)]
+	pub history_group_folder: bool,
+}
+
+#[cfg(feature = "gui")]
+impl Default for GuiConfiguration
+{
+	fn default() -> Self {
+		GuiConfiguration {
+			themes: Themes::default(),
+			fonts: vec![],
+			default_font_size: default_font_size(),
+			dict_font_size: default_font_size(),
+			sidebar_size: 300,
+			sidebar_position: Default::default(),
+			dict_docked: false,
+			dict_dock_size: default_dict_dock_size(),
+			window: Default::default(),
+			restore_session: false,
+			session: vec![],
+			toolbar: default_toolbar(),
+			status_widgets: vec![],
+			lang: default_locale(),
+			dictionaries: vec![],
+			cache_dict: false,
+			dictionary_groups: vec![],
+			dictionary_group: None,
+			strip_empty_lines: false,
+			ignore_font_weight: false,
+			font_weight_mode: Default::default(),
+			font_axes: Default::default(),
+			letter_spacing: 0,
+			word_spacing: 0,
+			text_scale: default_text_scale(),
+			ui_scale: default_ui_scale(),
+			scroll_for_page: false,
+			wheel_lines: default_wheel_lines(),
+			select_by_dictionary: false,
+			auto_copy: false,
+			watch_clipboard: false,
+			watch_file: false,
+			focus: false,
+			focus_mode: Default::default(),
+			focus_dim: default_focus_dim(),
+			bionic_reading: false,
+			eink: false,
+			accessibility_preset: Default::default(),
+			translate: Default::default(),
+			flashcard: Default::default(),
+			reading_goal: Default::default(),
+			night_light: Default::default(),
+			paper: Default::default(),
+			page_turn: Default::default(),
+			page_turn_millis: default_page_turn_millis(),
+			link_keys: Default::default(),
+			minimap: false,
+			smooth_scroll: default_smooth_scroll(),
+			history_full_path: default_history_full_path(),
+			history_group_folder: false,
+		}
+	}
+}
+
+#[cfg(feature = "gui")]
+impl GuiConfiguration {
+	pub fn curr_colors(&self, dark: bool) -> &Colors
+	{
+		if dark {
+			&self.themes.dark
+		} else {
+			&self.themes.bright
+		}
+	}
+
+	pub fn curr_colors_mut(&mut self, dark: bool) -> &mut Colors
+	{
+		if dark {
+			&mut self.themes.dark
+		} else {
+			&mut self.themes.bright
+		}
+	}
+
+	#[inline]
+	pub fn active_dictionary_group(&self) -> Option<&DictionaryGroup>
+	{
+		let name = self.dictionary_group.as_ref()?;
+		self.dictionary_groups.iter().find(|group| group.name == *name)
+	}
+
+	/// colors of book not used with fixed palettes
+	#[inline]
+	pub fn book_colors_allowed(&self) -> bool
+	{
+		!self.eink && self.accessibility_preset != AccessibilityPreset::HighContrast
+	}
+
+	/// letter and word spacing, with minimum of the dyslexia preset
+	pub fn text_spacing(&self) -> (u8, u8)
+	{
+		if self.accessibility_preset == AccessibilityPreset::Dyslexia {
+			(self.letter_spacing.max(DYSLEXIA_LETTER_SPACING),
+				self.word_spacing.max(DYSLEXIA_WORD_SPACING))
+		} else {
+			(self.letter_spacing, self.word_spacing)
+		}
+	}
+}
+
+/// broken history db found when loading config, told to user by ui
+pub enum HistoryProblem {
+	/// moved to the backup file, and an empty one created
+	Rebuilt(PathBuf),
+	/// in memory db used, with the backup file if the broken one moved
+	InMemory(Option<PathBuf>),
+}
+
+impl Display for HistoryProblem {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		match self {
+			HistoryProblem::Rebuilt(backup) =>
+				write!(f, "Broken history db moved to {}, reading history starts over.", backup.display()),
+			HistoryProblem::InMemory(Some(backup)) =>
+				write!(f, "Broken history db moved to {}, reading progress will not be saved.", backup.display()),
+			HistoryProblem::InMemory(None) =>
+				write!(f, "Failed load history db, reading progress will not be saved."),
+		}
+	}
+}
+
+pub struct Configuration {
+	pub render_han: bool,
+	pub dark_theme: bool,
+	// full-width and half-width forms matched each other when searching
+	pub search_fold_width: bool,
+	// page commands continue into next or previous chapter
+	pub chapter_continuation: bool,
+	// blank cells between columns of han mode in terminal
+	pub han_column_spacing: usize,
+	// entries listed in history, pinned ones not counted
+	pub history_size: usize,
+	// label like "gbk" or "shift_jis" for zip entry names not in utf-8,
+	// detected if not set
+	pub zip_name_encoding: Option<String>,
+	history: PathBuf,
+	#[cfg(feature = "gui")]
+	pub gui: GuiConfiguration,
+
+	// downloaded books cached here
+	#[cfg(feature = "gui")]
+	pub cache_dir: PathBuf,
+
+	config_file: PathBuf,
+	history_db: Connection,
+	history_problem: Option<HistoryProblem>,
+	// key of passwords saved in history db
+	password_key: PathBuf,
+	orig: RawConfig,
+}
+
+impl Configuration {
+	/// problem of loading history db, taken once for telling user
+	#[inline]
+	pub fn take_history_problem(&mut self) -> Option<HistoryProblem>
+	{
+		self.history_problem.take()
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn locales_dir(&self) -> PathBuf
+	{
+		self.config_file.with_file_name(i18n::LOCALES_FOLDER)
+	}
+
+	#[cfg(feature = "script")]
+	#[inline]
+	pub fn script_file(&self) -> PathBuf
+	{
+		self.config_file.with_file_name(SCRIPT_FILE)
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn config_file(&self) -> &PathBuf
+	{
+		&self.config_file
+	}
+
+	/// gui part of the config file, for edits made while running
+	#[cfg(feature = "gui")]
+	pub fn read_gui(&self) -> Result<GuiConfiguration>
+	{
+		let string = fs::read_to_string(&self.config_file)?;
+		let raw_config: RawConfig = toml::from_str(&string)?;
+		Ok(raw_config.gui)
+	}
+
+	pub fn save(&self) -> Result<()>
+	{
+		let raw_config = RawConfig {
+			render_han: self.render_han,
+			dark_theme: self.dark_theme,
+			search_fold_width: self.search_fold_width,
+			chapter_continuation: self.chapter_continuation,
+			han_column_spacing: self.han_column_spacing,
+			history_size: self.history_size,
+			zip_name_encoding: self.zip_name_encoding.clone(),
+			history: self.history.clone(),
+			#[cfg(feature = "gui")]
+			gui: self.gui.clone(),
+		};
+		if self.orig != raw_config {
+			let text = toml::to_string(&raw_config)?;
+			fs::write(&self.config_file, text)?;
+		}
+		Ok(())
+	}
+
+	#[cfg(feature = "gui")]
+	pub fn curr_theme(&self) -> &Colors
+	{
+		if self.gui.eink {
+			&Colors::EINK
+		} else if self.gui.accessibility_preset == AccessibilityPreset::HighContrast {
+			if self.dark_theme {
+				&Colors::HIGH_CONTRAST_DARK
+			} else {
+				&Colors::HIGH_CONTRAST_BRIGHT
+			}
+		} else if self.dark_theme {
+			&self.gui.themes.dark
+		} else {
+			&self.gui.themes.bright
+		}
+	}
+
+	/// colors for rendering, theme with night light applied
+	#[cfg(feature = "gui")]
+	pub fn render_colors(&self) -> Colors
+	{
+		let colors = self.curr_theme();
+		if !self.gui.eink && self.gui.night_light.active_now() {
+			colors.with_temperature(self.gui.night_light.temperature)
+		} else {
+			colors.clone()
+		}
+	}
+
+	fn map(row: &Row) -> rusqlite::Result<ReadingInfo>
+	{
+		Ok(ReadingInfo {
+			row_id: row.get(0)?,
+			filename: row.get(1)?,
+			inner_book: row.get(2)?,
+			chapter: row.get(3)?,
+			line: row.get(4)?,
+			position: row.get(5)?,
+			custom_color: row.get(6)?,
+			custom_font: row.get(7)?,
+			strip_empty_lines: row.get(8)?,
+			custom_style: row.get(9)?,
+			font_size: row.get::<usize, Option<u8>>(10)?.
+				unwrap_or(default_font_size()),
+			text_only: row.get::<usize, Option<bool>>(11)?.
+				unwrap_or(false),
+			ts: row.get(12)?,
+			render_han: row.get(13)?,
+		})
+	}
+
+	pub fn history(&self, current: Option<&String>, filter_pattern: Option<&String>)
+		-> Result<Vec<ReadingInfo>>
+	{
+		Ok(query(&self.history_db, self.history_size, current, filter_pattern, true)?)
+	}
+
+	pub fn reading<'a>(&self, filename: &'a str) -> Result<BookLoadingInfo<'a>>
+	{
+		let mut stmt = self.history_db.prepare("
+select row_id,
        filename,
        inner_book,
        chapter,
@@ -401,34 +1392,443 @@ select row_id,
        strip_empty_lines,
        custom_style,
        font_size,
-       ts
+       text_only,
+       ts,
+       render_han
+from history
+where filename = ?
+")?;
+		let mut iter = stmt.query_map([filename], Configuration::map)?;
+		if let Some(info) = iter.next() {
+			Ok(BookLoadingInfo::History(info?))
+		} else {
+			#[cfg(feature = "gui")]
+			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, self.gui.default_font_size)) }
+			#[cfg(not(feature = "gui"))]
+			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, default_font_size())) }
+		}
+	}
+
+	pub fn reading_by_id(&self, row_id: i64) -> Result<ReadingInfo>
+	{
+		let mut stmt = self.history_db.prepare("
+select row_id,
+       filename,
+       inner_book,
+       chapter,
+       line,
+       position,
+       custom_color,
+       custom_font,
+       strip_empty_lines,
+       custom_style,
+       font_size,
+       text_only,
+       ts,
+       render_han
 from history
 where row_id = ?
 ")?;
-		let mut iter = stmt.query_map([row_id], Configuration::map)?;
-		if let Some(info) = iter.next() {
-			Ok(info?)
-		} else {
-			panic!("Reading history not exists");
+		let mut iter = stmt.query_map([row_id], Configuration::map)?;
+		if let Some(info) = iter.next() {
+			Ok(info?)
+		} else {
+			panic!("Reading history not exists");
+		}
+	}
+
+	/// add book to history without opening, kept if read before
+	#[cfg(feature = "gui")]
+	pub fn add_history(&self, filename: &str) -> Result<()>
+	{
+		let loading = self.reading(filename)?;
+		if let BookLoadingInfo::NewReading(..) = loading {
+			self.save_reading(&mut loading.get())?;
+		}
+		Ok(())
+	}
+
+	/// forget the book in history
+	#[cfg(feature = "gui")]
+	pub fn remove_history(&self, filename: &str) -> Result<()>
+	{
+		self.history_db.execute("delete from history where filename = ?", [filename])?;
+		self.history_db.execute("delete from annotation where filename = ?", [filename])?;
+		self.history_db.execute("delete from tag where filename = ?", [filename])?;
+		self.history_db.execute("delete from finished where filename = ?", [filename])?;
+		self.history_db.execute("delete from book_password where filename = ?", [filename])?;
+		self.history_db.execute("delete from trace where filename = ?", [filename])?;
+		self.history_db.execute("delete from toc_progress where filename = ?", [filename])?;
+		self.history_db.execute("delete from pinned where filename = ?", [filename])?;
+		Ok(())
+	}
+
+	/// keep reading info of the book file renamed or moved
+	#[cfg(feature = "gui")]
+	pub fn rename_history(&self, filename: &str, new_filename: &str) -> Result<()>
+	{
+		self.history_db.execute("delete from history where filename = ?", [new_filename])?;
+		self.history_db.execute("update history set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from annotation where filename = ?", [new_filename])?;
+		self.history_db.execute("update annotation set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from tag where filename = ?", [new_filename])?;
+		self.history_db.execute("update tag set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from finished where filename = ?", [new_filename])?;
+		self.history_db.execute("update finished set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from book_password where filename = ?", [new_filename])?;
+		self.history_db.execute("update book_password set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from trace where filename = ?", [new_filename])?;
+		self.history_db.execute("update trace set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from toc_progress where filename = ?", [new_filename])?;
+		self.history_db.execute("update toc_progress set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		self.history_db.execute("delete from pinned where filename = ?", [new_filename])?;
+		self.history_db.execute("update pinned set filename = ? where filename = ?",
+			[new_filename, filename])?;
+		Ok(())
+	}
+
+	/// remembered password of encrypted book,
+	/// None if the key file lost, e.g. history db restored on other machine
+	pub fn password(&self, filename: &str) -> Result<Option<String>>
+	{
+		let encrypted: Option<Vec<u8>> = self.history_db
+			.query_row("select encrypted from book_password where filename = ?", [filename],
+				|row| row.get(0))
+			.optional()?;
+		let Some(encrypted) = encrypted else {
+			return Ok(None);
+		};
+		let Some(key) = SecretKey::load(&self.password_key)? else {
+			return Ok(None);
+		};
+		Ok(key.decrypt(&encrypted))
+	}
+
+	/// saved encrypted, never in plain text
+	pub fn save_password(&self, filename: &str, password: &str) -> Result<()>
+	{
+		let key = SecretKey::load_or_create(&self.password_key)?;
+		let encrypted = key.encrypt(password)?;
+		self.history_db.execute("insert or replace into book_password (filename, encrypted) values (?, ?)",
+			(filename, encrypted))?;
+		Ok(())
+	}
+
+	/// navigation trace of last session with the current index,
+	/// only for the same inner book
+	pub fn trace(&self, reading: &ReadingInfo) -> Result<Option<(Vec<TraceInfo>, usize)>>
+	{
+		let trace: Option<(usize, usize, String)> = self.history_db
+			.query_row("select inner_book, current, entries from trace where filename = ?",
+				[&reading.filename], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+			.optional()?;
+		let Some((inner_book, current, entries)) = trace else {
+			return Ok(None);
+		};
+		if inner_book != reading.inner_book {
+			return Ok(None);
+		}
+		let mut trace = vec![];
+		for entry in entries.split(';') {
+			let mut values = entry.split(',').map(usize::from_str);
+			let (Some(Ok(chapter)), Some(Ok(line)), Some(Ok(offset)), None) =
+				(values.next(), values.next(), values.next(), values.next()) else {
+				return Ok(None);
+			};
+			trace.push(TraceInfo { chapter, line, offset });
+		}
+		if current >= trace.len() {
+			return Ok(None);
+		}
+		Ok(Some((trace, current)))
+	}
+
+	/// entries saved as "chapter,line,offset" separated by ';'
+	pub fn save_trace(&self, reading: &ReadingInfo, trace: &[TraceInfo], current: usize) -> Result<()>
+	{
+		if is_stdin(&reading.filename) {
+			return Ok(());
+		}
+		let entries = trace
+			.iter()
+			.map(|info| format!("{},{},{}", info.chapter, info.line, info.offset))
+			.collect::<Vec<_>>()
+			.join(";");
+		self.history_db.execute("insert or replace into trace (filename, inner_book, current, entries) values (?, ?, ?, ?)",
+			(&reading.filename, reading.inner_book, current, entries))?;
+		Ok(())
+	}
+
+	/// toc entries visited of the inner book, true for read through
+	#[cfg(feature = "gui")]
+	pub fn toc_progress(&self, filename: &str, inner_book: usize) -> Result<HashMap<usize, bool>>
+	{
+		let mut stmt = self.history_db.prepare(
+			"select toc_index, finished from toc_progress where filename = ? and inner_book = ?")?;
+		let progress = stmt
+			.query_map((filename, inner_book), |row| Ok((row.get(0)?, row.get(1)?)))?
+			.collect::<rusqlite::Result<_>>()?;
+		Ok(progress)
+	}
+
+	/// toc entries from first to last shown, those before last read through,
+	/// a read through entry never turned back to partially read
+	#[cfg(feature = "gui")]
+	pub fn save_toc_progress(&self, filename: &str, inner_book: usize,
+		first: usize, last: usize, last_finished: bool) -> Result<()>
+	{
+		if is_stdin(filename) {
+			return Ok(());
+		}
+		let transaction = self.history_db.unchecked_transaction()?;
+		for toc_index in first..last {
+			transaction.execute("insert or replace into toc_progress (filename, inner_book, toc_index, finished) values (?, ?, ?, 1)",
+				(filename, inner_book, toc_index))?;
+		}
+		let sql = if last_finished {
+			"insert or replace into toc_progress (filename, inner_book, toc_index, finished) values (?, ?, ?, 1)"
+		} else {
+			"insert or ignore into toc_progress (filename, inner_book, toc_index, finished) values (?, ?, ?, 0)"
+		};
+		transaction.execute(sql, (filename, inner_book, last))?;
+		transaction.commit()?;
+		Ok(())
+	}
+
+	/// time the book finished, in seconds since epoch
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn finished(&self, filename: &str) -> Result<Option<u64>>
+	{
+		finished(&self.history_db, filename)
+	}
+
+	/// first finished time kept when finished again
+	pub fn mark_finished(&self, filename: &str) -> Result<()>
+	{
+		self.history_db.execute("insert or ignore into finished (filename, ts) values (?, ?)",
+			(filename, ReadingInfo::now()))?;
+		Ok(())
+	}
+
+	#[cfg(feature = "gui")]
+	pub fn unmark_finished(&self, filename: &str) -> Result<()>
+	{
+		self.history_db.execute("delete from finished where filename = ?", [filename])?;
+		Ok(())
+	}
+
+	/// pinned books listed first in history, not limited by history size
+	#[cfg(feature = "gui")]
+	pub fn pin(&self, filename: &str) -> Result<()>
+	{
+		self.history_db.execute("insert or ignore into pinned (filename, ts) values (?, ?)",
+			(filename, ReadingInfo::now()))?;
+		Ok(())
+	}
+
+	#[cfg(feature = "gui")]
+	pub fn unpin(&self, filename: &str) -> Result<()>
+	{
+		self.history_db.execute("delete from pinned where filename = ?", [filename])?;
+		Ok(())
+	}
+
+	#[cfg(feature = "gui")]
+	pub fn is_pinned(&self, filename: &str) -> Result<bool>
+	{
+		let pinned = self.history_db
+			.query_row("select 1 from pinned where filename = ?", [filename], |_| Ok(()))
+			.optional()?
+			.is_some();
+		Ok(pinned)
+	}
+
+	/// add reading time and page turns of the book, day in yyyy-mm-dd
+	#[cfg(feature = "gui")]
+	pub fn add_reading_stat(&self, day: &str, filename: &str, seconds: u64,
+		pages: u64) -> Result<()>
+	{
+		self.history_db.execute("
+insert into reading_stat (day, filename, seconds, pages)
+values (?, ?, ?, ?)
+on conflict (day, filename) do update
+set seconds = seconds + excluded.seconds,
+    pages   = pages + excluded.pages
+", (day, filename, seconds, pages))?;
+		Ok(())
+	}
+
+	/// reading seconds and page turns of the day
+	#[cfg(feature = "gui")]
+	pub fn day_reading_stat(&self, day: &str) -> Result<(u64, u64)>
+	{
+		let stat = self.history_db.query_row("
+select coalesce(sum(seconds), 0), coalesce(sum(pages), 0)
+from reading_stat
+where day = ?
+", [day], |row| Ok((row.get(0)?, row.get(1)?)))?;
+		Ok(stat)
+	}
+
+	/// day, reading seconds and page turns of all days read, ordered by day
+	#[cfg(feature = "gui")]
+	pub fn reading_stats(&self) -> Result<Vec<(String, u64, u64)>>
+	{
+		let mut stmt = self.history_db.prepare("
+select day, sum(seconds), sum(pages)
+from reading_stat
+group by day
+order by day
+")?;
+		let stats = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+			.collect::<rusqlite::Result<Vec<_>>>()?;
+		Ok(stats)
+	}
+
+	/// user tags of the book
+	#[cfg(feature = "gui")]
+	pub fn user_tags(&self, filename: &str) -> Result<Vec<String>>
+	{
+		user_tags(&self.history_db, filename)
+	}
+
+	/// user tags with the auto tag of reading state
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn tags(&self, reading: &ReadingInfo) -> Result<Vec<String>>
+	{
+		tags(&self.history_db, reading)
+	}
+
+	#[cfg(feature = "gui")]
+	pub fn set_tags(&self, filename: &str, tags: &[&str]) -> Result<()>
+	{
+		let transaction = self.history_db.unchecked_transaction()?;
+		transaction.execute("delete from tag where filename = ?", [filename])?;
+		for tag in tags {
+			transaction.execute("insert or ignore into tag (filename, name) values (?, ?)",
+				[filename, tag])?;
 		}
+		transaction.commit()?;
+		Ok(())
 	}
 
+	#[inline]
 	pub fn save_reading(&self, reading: &mut ReadingInfo) -> Result<()>
 	{
+		save_reading_info(&self.history_db, reading)
+	}
+
+	#[inline]
+	pub fn history_file(&self) -> &PathBuf
+	{
+		&self.history
+	}
+
+	/// annotations of the inner book in reading order
+	#[cfg(feature = "gui")]
+	pub fn annotations(&self, filename: &str, inner_book: usize) -> Result<Vec<AnnotationInfo>>
+	{
+		let mut stmt = self.history_db.prepare("
+select id,
+       chapter,
+       chapter_title,
+       start_line,
+       start_offset,
+       end_line,
+       end_offset,
+       content,
+       note,
+       category,
+       ts
+from annotation
+where filename = ?
+  and inner_book = ?
+order by chapter, start_line, start_offset, id")?;
+		let annotations = stmt
+			.query_map((filename, inner_book), |row| Ok(AnnotationInfo {
+				id: row.get(0)?,
+				chapter: row.get(1)?,
+				chapter_title: row.get(2)?,
+				range: Position::new(row.get(3)?, row.get(4)?)
+					..Position::new(row.get(5)?, row.get(6)?),
+				text: row.get(7)?,
+				note: row.get(8)?,
+				category: HighlightCategory::from_index(row.get(9)?),
+				ts: row.get(10)?,
+			}))?
+			.collect::<rusqlite::Result<_>>()?;
+		Ok(annotations)
+	}
+
+	/// id and time of the new annotation updated
+	#[cfg(feature = "gui")]
+	pub fn add_annotation(&self, filename: &str, inner_book: usize,
+		annotation: &mut AnnotationInfo) -> Result<()>
+	{
+		if is_stdin(filename) {
+			bail!("Annotations not saved for book read from stdin");
+		}
 		let ts = ReadingInfo::now();
-		if reading.row_id == 0 {
-			self.history_db.execute("
+		let range = &annotation.range;
+		self.history_db.execute("
+insert into annotation (filename, inner_book, chapter, chapter_title,
+                        start_line, start_offset, end_line, end_offset,
+                        content, note, category, ts)
+values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+", (filename, inner_book, annotation.chapter, &annotation.chapter_title,
+			range.start.line, range.start.offset, range.end.line, range.end.offset,
+			&annotation.text, &annotation.note, annotation.category.index(), ts))?;
+		annotation.id = self.history_db.last_insert_rowid();
+		annotation.ts = ts;
+		Ok(())
+	}
+
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn remove_annotation(&self, id: i64) -> Result<()>
+	{
+		self.history_db.execute("delete from annotation where id = ?", [id])?;
+		Ok(())
+	}
+}
+
+/// with a new connection, for saving when the configuration not reachable
+#[inline]
+pub fn save_reading_in(history: &PathBuf, reading: &mut ReadingInfo) -> Result<()>
+{
+	let history_db = Connection::open(history)?;
+	save_reading_info(&history_db, reading)
+}
+
+/// book read from stdin not saved, for content differs every time
+fn save_reading_info(history_db: &Connection, reading: &mut ReadingInfo) -> Result<()>
+{
+	if is_stdin(&reading.filename) {
+		return Ok(());
+	}
+	let ts = ReadingInfo::now();
+	if reading.row_id == 0 {
+		history_db.execute("
 insert into history (filename, inner_book, chapter, line, position,
                      custom_color, custom_font, strip_empty_lines,
-                     custom_style, font_size, ts)
-values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                     custom_style, font_size, text_only, ts, render_han)
+values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
 ", (&reading.filename, reading.inner_book, reading.chapter, reading.line,
-				reading.position, reading.custom_color, reading.custom_font,
-				reading.strip_empty_lines, &reading.custom_style,
-				reading.font_size, ts))?;
-			reading.row_id = self.history_db.last_insert_rowid();
-		} else {
-			self.history_db.execute("
+			reading.position, reading.custom_color, reading.custom_font,
+			reading.strip_empty_lines, &reading.custom_style,
+			reading.font_size, reading.text_only, ts, reading.render_han))?;
+		reading.row_id = history_db.last_insert_rowid();
+	} else {
+		history_db.execute("
 update history
 set filename          = ?,
     inner_book        = ?,
@@ -440,15 +1840,18 @@ set filename          = ?,
     strip_empty_lines = ?,
     custom_style      = ?,
     font_size         = ?,
-    ts                = ?
+    text_only         = ?,
+    ts                = ?,
+    render_han        = ?
 where row_id = ?
 ", (&reading.filename, reading.inner_book, reading.chapter, reading.line,
-				reading.position, reading.custom_color, reading.custom_font,
-				reading.strip_empty_lines, &reading.custom_style,
-				reading.font_size, ts, reading.row_id))?;
-		}
-		Ok(())
+			reading.position, reading.custom_color, reading.custom_font,
+			reading.strip_empty_lines, &reading.custom_style,
+			reading.font_size, reading.text_only, ts, reading.render_han,
+			reading.row_id))?;
 	}
+	reading.ts = ts;
+	Ok(())
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
@@ -479,21 +1882,37 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 			} else {
 				None
 			};
-			let history_db = load_history_db(&raw_config.history)?;
+			let (history_db, history_problem) = open_history_db(&raw_config.history)?;
 			if current.is_none() {
-				if let Some(latest_reading) = query(&history_db, 1, None, None)?.pop() {
+				if let Some(latest_reading) = query(&history_db, 1, None, None, false)?.pop() {
 					current = Some(latest_reading.filename);
 				}
 			}
 			let orig = raw_config.clone();
+			#[cfg(feature = "gui")]
+			let mut raw_config = raw_config;
+			#[cfg(feature = "gui")]
+			if raw_config.gui.ignore_font_weight {
+				raw_config.gui.ignore_font_weight = false;
+				raw_config.gui.font_weight_mode = FontWeightMode::Ignore;
+			}
 			let configuration = Configuration {
 				render_han: raw_config.render_han,
 				dark_theme: raw_config.dark_theme,
+				search_fold_width: raw_config.search_fold_width,
+				chapter_continuation: raw_config.chapter_continuation,
+				han_column_spacing: raw_config.han_column_spacing,
+				history_size: raw_config.history_size,
+				zip_name_encoding: raw_config.zip_name_encoding,
 				history: raw_config.history,
 				#[cfg(feature = "gui")]
 				gui: raw_config.gui,
+				#[cfg(feature = "gui")]
+				cache_dir: cache_dir.clone(),
 				config_file,
 				history_db,
+				history_problem,
+				password_key: config_dir.join(PASSWORD_KEY_FILE),
 				orig,
 			};
 			(current, configuration)
@@ -503,10 +1922,15 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 			let current = filename
 				.map_or(None, |filename| file_path(&filename));
 			let history = config_dir.join("history.sqlite");
-			let history_db = load_history_db(&history)?;
+			let (history_db, history_problem) = open_history_db(&history)?;
 			let orig = RawConfig {
 				render_han: false,
 				dark_theme: false,
+				search_fold_width: false,
+				chapter_continuation: true,
+				han_column_spacing: default_han_column_spacing(),
+				history_size: default_history_size(),
+				zip_name_encoding: None,
 				history: history.clone(),
 				#[cfg(feature = "gui")]
 				gui: Default::default(),
@@ -516,19 +1940,156 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 			(current, Configuration {
 				render_han: false,
 				dark_theme: false,
+				search_fold_width: false,
+				chapter_continuation: true,
+				han_column_spacing: default_han_column_spacing(),
+				history_size: default_history_size(),
+				zip_name_encoding: None,
 				history,
 				#[cfg(feature = "gui")]
 				gui: Default::default(),
+				#[cfg(feature = "gui")]
+				cache_dir: cache_dir.clone(),
 
 				config_file,
 				history_db,
+				history_problem,
+				password_key: config_dir.join(PASSWORD_KEY_FILE),
 				orig,
 			})
 		};
 	return Ok((current, configuration));
 }
 
+const BACKUP_CONFIG_ENTRY: &str = "tbr.toml";
+const BACKUP_HISTORY_ENTRY: &str = "history.sqlite";
+// files and folders in config dir, terminal themes(gui themes saved in config),
+// user script, locales and plugins, those of features not built are not included
+const BACKUP_ENTRIES: &[&str] = &[
+	"dark.toml",
+	"bright.toml",
+	#[cfg(feature = "script")]
+	SCRIPT_FILE,
+	#[cfg(feature = "gui")]
+	i18n::LOCALES_FOLDER,
+	#[cfg(feature = "plugin")]
+	crate::book::plugin::PLUGINS_FOLDER,
+];
+// archive extracted here, moved into config dir when all entries are read
+const BACKUP_STAGING_FOLDER: &str = "import.staging";
+
+/// bundle config(with themes), history db, user script, locales and plugins
+/// into a zip archive
+pub(super) fn export_backup(config_file: &PathBuf, config_dir: &PathBuf,
+	archive: &str) -> Result<()>
+{
+	let string = fs::read_to_string(config_file)?;
+	let raw_config: RawConfig = toml::from_str(&string)?;
+	let history = fs::read(&raw_config.history)?;
+	let mut zip = ZipWriter::new(File::create(archive)?);
+	let options = SimpleFileOptions::default();
+	zip.start_file(BACKUP_CONFIG_ENTRY, options)?;
+	zip.write_all(string.as_bytes())?;
+	zip.start_file(BACKUP_HISTORY_ENTRY, options)?;
+	zip.write_all(&history)?;
+	for entry in BACKUP_ENTRIES {
+		let path = config_dir.join(entry);
+		if path.is_file() {
+			zip.start_file(*entry, options)?;
+			zip.write_all(&fs::read(path)?)?;
+		} else if path.is_dir() {
+			zip_folder(&mut zip, &path, entry, options)?;
+		}
+	}
+	zip.finish()?;
+	Ok(())
+}
+
+fn zip_folder(zip: &mut ZipWriter<File>, folder: &PathBuf, name: &str,
+	options: SimpleFileOptions) -> Result<()>
+{
+	for entry in fs::read_dir(folder)? {
+		let path = entry?.path();
+		let Some(filename) = path.file_name().and_then(|name| name.to_str()) else {
+			continue;
+		};
+		let entry_name = format!("{}/{}", name, filename);
+		if path.is_dir() {
+			zip_folder(zip, &path, &entry_name, options)?;
+		} else {
+			zip.start_file(entry_name, options)?;
+			zip.write_all(&fs::read(&path)?)?;
+		}
+	}
+	Ok(())
+}
+
+/// restore archive created by export_backup, the history db placed in
+/// config dir, for path may differ on other machine, all entries extracted
+/// before existing files and folders replaced, which are renamed with .bak
+pub(super) fn import_backup(archive: &str, config_file: &PathBuf,
+	config_dir: &PathBuf) -> Result<()>
+{
+	let mut zip = ZipArchive::new(File::open(archive)?)?;
+	let mut string = String::new();
+	zip.by_name(BACKUP_CONFIG_ENTRY)?.read_to_string(&mut string)?;
+	// keep as table, so gui settings not lost with terminal only build
+	let mut table: toml::Table = toml::from_str(&string)?;
+	if zip.index_for_name(BACKUP_HISTORY_ENTRY).is_none() {
+		bail!("No history in backup: {}", archive);
+	}
+	let history_file = config_dir.join(BACKUP_HISTORY_ENTRY);
+	table.insert("history".to_owned(), toml::Value::String(
+		history_file.to_string_lossy().to_string()));
+
+	let staging = config_dir.join(BACKUP_STAGING_FOLDER);
+	if staging.exists() {
+		fs::remove_dir_all(&staging)?;
+	}
+	fs::create_dir_all(&staging)?;
+	for index in 0..zip.len() {
+		let mut file = zip.by_index(index)?;
+		if file.is_dir() || file.name() == BACKUP_CONFIG_ENTRY {
+			continue;
+		}
+		let Some(path) = file.enclosed_name() else {
+			bail!("Invalid entry in backup: {}", file.name());
+		};
+		let path = staging.join(path);
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let mut content = vec![];
+		file.read_to_end(&mut content)?;
+		fs::write(path, content)?;
+	}
+	let config_filename = config_file.file_name().unwrap_or(BACKUP_CONFIG_ENTRY.as_ref());
+	fs::write(staging.join(config_filename), toml::to_string(&table)?)?;
+
+	for entry in fs::read_dir(&staging)? {
+		let entry = entry?;
+		let target = config_dir.join(entry.file_name());
+		if target.exists() {
+			let mut backup = target.clone().into_os_string();
+			backup.push(".bak");
+			let backup = PathBuf::from(backup);
+			if backup.is_dir() {
+				fs::remove_dir_all(&backup)?;
+			} else if backup.exists() {
+				fs::remove_file(&backup)?;
+			}
+			fs::rename(&target, &backup)?;
+		}
+		fs::rename(entry.path(), target)?;
+	}
+	fs::remove_dir(&staging)?;
+	Ok(())
+}
+
 fn file_path(filename: &str) -> Option<String> {
+	if is_stdin(filename) {
+		return Some(filename.to_owned());
+	}
 	let filepath = PathBuf::from(filename);
 	if !filepath.exists() {
 		return None;
@@ -555,19 +2116,221 @@ fn default_font_size() -> u8
 	20
 }
 
-const CURRENT_DB_VERSION: u16 = 2;
+#[inline]
+#[cfg(feature = "gui")]
+fn default_toolbar() -> Vec<ToolbarItem>
+{
+	ToolbarItem::ALL.to_vec()
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_text_scale() -> u16
+{
+	100
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_ui_scale() -> u16
+{
+	100
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_anki_connect_url() -> String
+{
+	String::from("http://127.0.0.1:8765")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_anki_deck() -> String
+{
+	String::from("Default")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_anki_note_type() -> String
+{
+	String::from("Basic")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_dict_dock_size() -> u32
+{
+	300
+}
+
+#[inline]
+fn default_chapter_continuation() -> bool
+{
+	true
+}
+
+#[inline]
+fn default_han_column_spacing() -> usize
+{
+	1
+}
+
+#[inline]
+fn default_history_size() -> usize
+{
+	20
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_wheel_lines() -> u8
+{
+	1
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_smooth_scroll() -> bool
+{
+	true
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_history_full_path() -> bool
+{
+	true
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_focus_dim() -> u8
+{
+	60
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_next_link_key() -> String
+{
+	String::from("l")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_prev_link_key() -> String
+{
+	String::from("<Shift>l")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_night_light_temperature() -> u16
+{
+	3400
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_night_start_hour() -> u8
+{
+	21
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_night_end_hour() -> u8
+{
+	7
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_paper_opacity() -> u8
+{
+	100
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_page_turn_millis() -> u16
+{
+	300
+}
+
+/// schema migrations, the one at index n upgrades db from version n to n + 1,
+/// append new one for schema changes, never modify the shipped ones
+const MIGRATIONS: [&str; 12] = [
+	"alter table history add custom_style varchar",
+	"alter table history add font_size unsigned big int",
+	"create table annotation ( id integer primary key, filename varchar, inner_book unsigned big int, chapter unsigned big int, chapter_title varchar, start_line unsigned big int, start_offset unsigned big int, end_line unsigned big int, end_offset unsigned big int, content varchar, note varchar, category unsigned big int, ts unsigned big int )",
+	"create table tag ( filename varchar, name varchar, unique (filename, name) )",
+	"create table finished ( filename varchar primary key, ts unsigned big int )",
+	"create table reading_stat ( day varchar, filename varchar, seconds unsigned big int, pages unsigned big int, unique (day, filename) )",
+	"create table book_password ( filename varchar primary key, encrypted blob )",
+	"create table trace ( filename varchar primary key, inner_book unsigned big int, current unsigned big int, entries varchar )",
+	"create table toc_progress ( filename varchar, inner_book unsigned big int, toc_index unsigned big int, finished boolean, unique (filename, inner_book, toc_index) )",
+	"alter table history add text_only boolean",
+	"alter table history add render_han boolean",
+	"create table pinned ( filename varchar primary key, ts unsigned big int )",
+];
+
+/// open history db, a broken one is backed up and rebuilt,
+/// fall back to in memory db if still failed, so reading still works
+fn open_history_db(path: &PathBuf) -> Result<(Connection, Option<HistoryProblem>)>
+{
+	let err = match load_history_db(path) {
+		Ok(connection) => return Ok((connection, None)),
+		Err(err) => err,
+	};
+	eprintln!("Failed load history db {}: {}", path.display(), err);
+	let mut moved = None;
+	if is_corrupt(&err) {
+		let backup = PathBuf::from(format!("{}.{}.bak", path.display(), ReadingInfo::now()));
+		match fs::rename(path, &backup) {
+			Ok(()) => {
+				eprintln!("Broken history db moved to {}, rebuilding.", backup.display());
+				match load_history_db(path) {
+					Ok(connection) => return Ok((connection, Some(HistoryProblem::Rebuilt(backup)))),
+					Err(err) => eprintln!("Failed rebuild history db: {}", err),
+				}
+				moved = Some(backup);
+			}
+			Err(err) => eprintln!("Failed backup history db: {}", err),
+		}
+	}
+	eprintln!("Using in memory history, reading progress will not be saved.");
+	let connection = Connection::open_in_memory()?;
+	setup_history_db(&connection, true)?;
+	Ok((connection, Some(HistoryProblem::InMemory(moved))))
+}
+
+#[inline]
+fn is_corrupt(err: &anyhow::Error) -> bool
+{
+	matches!(err.downcast_ref::<rusqlite::Error>(),
+		Some(rusqlite::Error::SqliteFailure(err, _))
+		if matches!(err.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase))
+}
 
 #[inline]
 fn load_history_db(path: &PathBuf) -> Result<Connection>
 {
-	let connection = if !path.exists() {
-		// init db
-		let conn = Connection::open(path)?;
-		conn.execute("
-create table info ( version integer )
-			", ())?;
-		conn.execute("insert into info (version) values (?)", [CURRENT_DB_VERSION])?;
-		conn.execute("
+	let init = !path.exists();
+	let connection = Connection::open(path)?;
+	setup_history_db(&connection, init)?;
+	Ok(connection)
+}
+
+fn setup_history_db(connection: &Connection, init: bool) -> Result<()>
+{
+	if init {
+		// schema of version 0, migrations bring it up to date
+		connection.execute_batch("
+create table info ( version integer );
+insert into info (version) values (0);
 create table history
 (
     row_id            integer primary key,
@@ -579,46 +2342,47 @@ create table history
     custom_color      unsigned big int,
     custom_font       unsigned big int,
     strip_empty_lines unsigned big int,
-    custom_style      varchar,
-    font_size         unsigned big int,
     ts                unsigned big int,
     unique (filename)
-)", ())?;
-		conn
-	} else {
-		let connection = Connection::open(path)?;
-		upgrade_db(&connection)?;
-		connection
-	};
-	Ok(connection)
+);")?;
+	}
+	upgrade_db(connection)
 }
 
 #[inline]
 fn upgrade_db(connection: &Connection) -> Result<()>
 {
-	let mut stmt = connection.prepare("select version from info")?;
-	let mut rows = stmt.query([])?;
-	let row = rows.next()?;
-	let version: u64 = if let Some(row) = row {
-		row.get(0)?
-	} else {
-		0
+	let version: Option<usize> = connection
+		.query_row("select version from info", [], |row| row.get(0))
+		.optional()?;
+	// db of version 0 has no version record
+	let version = match version {
+		Some(version) => version,
+		None => {
+			connection.execute("insert into info (version) values (0)", [])?;
+			0
+		}
 	};
-	if version == 0 {
-		connection.execute("alter table history add custom_style varchar", [])?;
-		connection.execute("insert into info (version) values (1)", [])?;
+	if version > MIGRATIONS.len() {
+		bail!("History db version {} is newer than supported version {}, please upgrade tbr.",
+			version, MIGRATIONS.len());
 	}
-	if version < 2 {
-		connection.execute("alter table history add font_size unsigned big int", [])?;
-		connection.execute("update info set version = 2", [])?;
+	for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+		// each migration applied with its version atomically
+		let transaction = connection.unchecked_transaction()?;
+		transaction.execute_batch(migration)?;
+		transaction.execute("update info set version = ?", [index + 1])?;
+		transaction.commit()?;
 	}
 	Ok(())
 }
 
+/// pinned books listed first and not counted by limit if pinned_first
 fn query(conn: &Connection, limit: usize, exclude: Option<&String>,
-	filter_pattern: Option<&String>) -> Result<Vec<ReadingInfo>>
+	filter_pattern: Option<&String>, pinned_first: bool) -> Result<Vec<ReadingInfo>>
 {
-	let mut stmt = conn.prepare("
+	let order = if pinned_first { "pinned desc, ts desc" } else { "ts desc" };
+	let mut stmt = conn.prepare(&format!("
 select row_id,
        filename,
        inner_book,
@@ -630,16 +2394,25 @@ select row_id,
        strip_empty_lines,
        custom_style,
        font_size,
-       ts
+       text_only,
+       ts,
+       render_han,
+       exists(select 1 from pinned where pinned.filename = history.filename) as pinned
 from history
-order by ts desc
-")?;
-	let iter = stmt.query_map([], Configuration::map)?;
+order by {}
+", order))?;
+	let iter = stmt.query_map([], |row| Ok((Configuration::map(row)?, row.get::<usize, bool>(14)?)))?;
+	let (filter_pattern, filter_tags) = match filter_pattern {
+		Some(filter) => parse_history_filter(filter),
+		None => (None, vec![]),
+	};
 	let mut list = vec![];
+	let mut counted = 0;
 	for info in iter {
-		let info = info?;
+		let (info, pinned) = info?;
+		// stdin entries saved by former versions are never listed
 		let path = PathBuf::from_str(&info.filename)?;
-		if !path.exists() {
+		if is_stdin_name(&info.filename) || !path.exists() {
 			continue;
 		}
 		let filename = &info.filename;
@@ -648,19 +2421,77 @@ order by ts desc
 				continue;
 			}
 		}
-		if let Some(pattern) = filter_pattern {
+		if let Some(pattern) = &filter_pattern {
 			if match_filename(&filename, pattern).is_none() {
 				continue;
 			}
 		}
+		if !filter_tags.is_empty() {
+			let tags = tags(conn, &info)?;
+			if !filter_tags.iter().all(|tag| tags.iter().any(|t| t == tag)) {
+				continue;
+			}
+		}
 		list.push(info);
-		if list.len() >= limit {
-			break;
+		if !(pinned_first && pinned) {
+			counted += 1;
+			if counted >= limit {
+				break;
+			}
 		}
 	}
 	Ok(list)
 }
 
+/// filename pattern and tags of history filter, tag starts with #,
+/// e.g. "#novel #in-progress dune"
+pub fn parse_history_filter(filter: &str) -> (Option<String>, Vec<&str>)
+{
+	let mut words = vec![];
+	let mut tags = vec![];
+	for word in filter.split_whitespace() {
+		match word.strip_prefix('#') {
+			Some(tag) if !tag.is_empty() => tags.push(tag),
+			_ => words.push(word),
+		}
+	}
+	let pattern = if words.is_empty() {
+		None
+	} else {
+		Some(words.join(" "))
+	};
+	(pattern, tags)
+}
+
+fn user_tags(conn: &Connection, filename: &str) -> Result<Vec<String>>
+{
+	let mut stmt = conn.prepare("select name from tag where filename = ? order by name")?;
+	let tags = stmt.query_map([filename], |row| row.get(0))?
+		.collect::<rusqlite::Result<Vec<String>>>()?;
+	Ok(tags)
+}
+
+fn tags(conn: &Connection, reading: &ReadingInfo) -> Result<Vec<String>>
+{
+	let mut tags = user_tags(conn, &reading.filename)?;
+	let auto_tag = if finished(conn, &reading.filename)?.is_some() {
+		AUTO_TAG_FINISHED
+	} else {
+		reading.auto_tag()
+	};
+	tags.insert(0, auto_tag.to_owned());
+	Ok(tags)
+}
+
+fn finished(conn: &Connection, filename: &str) -> Result<Option<u64>>
+{
+	let ts = conn
+		.query_row("select ts from finished where filename = ?", [filename],
+			|row| row.get(0))
+		.optional()?;
+	Ok(ts)
+}
+
 pub fn match_filename(filename: &str, pattern: &str) -> Option<Vec<usize>>
 {
 	let mut vec = vec![];
@@ -685,6 +2516,16 @@ pub fn match_filename(filename: &str, pattern: &str) -> Option<Vec<usize>>
 pub struct RawConfig {
 	pub render_han: bool,
 	pub dark_theme: bool,
+	#[serde(default)]
+	pub search_fold_width: bool,
+	#[serde(default = "default_chapter_continuation")]
+	pub chapter_continuation: bool,
+	#[serde(default = "default_han_column_spacing")]
+	pub han_column_spacing: usize,
+	#[serde(default = "default_history_size")]
+	pub history_size: usize,
+	#[serde(default)]
+	pub zip_name_encoding: Option<String>,
 	history: PathBuf,
 	#[cfg(feature = "gui")]
 	#[serde(default)]
@@ -1,18 +1,39 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use ::zip::result::ZipError;
 use anyhow::{anyhow, Result};
+use lexical_sort::{natural_lexical_cmp, StringSort};
 
 use crate::book::{Book, LoadingChapter, EMPTY_CHAPTER_CONTENT};
+#[cfg(feature = "gui")]
+use crate::book::UnknownFormat;
 use crate::BookLoader;
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::container::folder::FolderLoader;
-use crate::container::zip::ZipLoader;
+use crate::container::stdin::StdinLoader;
+use crate::container::zip::{ZIP_MAGIC, ZipLoader};
+pub use crate::container::stdin::{DEFAULT_STDIN_FORMAT, is_stdin, is_stdin_name, read_stdin, STDIN_FILENAME};
 
 mod folder;
+mod stdin;
 mod zip;
 
+// leading bytes read for recognizing file without known extension
+const SNIFF_LENGTH: u64 = 1024;
+
+// formats chosen for misnamed files by command line or gui, by filename,
+// shared by all container managers, like the ones for searching
+static FORMATS: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Default::default);
+
 pub struct ContainerManager {
 	pub book_loader: BookLoader,
 	loaders: Vec<Box<dyn ContainerLoader>>,
+	// passwords of encrypted zip or epub files
+	passwords: HashMap<String, String>,
 }
 
 impl Default for ContainerManager {
@@ -21,9 +42,11 @@ impl Default for ContainerManager {
 		ContainerManager {
 			book_loader: Default::default(),
 			loaders: vec![
+				Box::new(StdinLoader {}),
 				Box::new(ZipLoader {}),
 				Box::new(FolderLoader {})
 			],
+			passwords: HashMap::new(),
 		}
 	}
 }
@@ -31,55 +54,191 @@ impl Default for ContainerManager {
 impl ContainerManager {
 	pub fn open(&self, filename: &str) -> Result<Box<dyn Container>>
 	{
+		if format_of(filename).is_some() {
+			return Ok(Box::new(DummyContainer::new(filename)));
+		}
 		for loader in &self.loaders {
 			if loader.accept(filename) {
-				let book = loader.open(filename, &self.book_loader)?;
+				let book = loader.open(filename, &self.book_loader, self.password(filename))?;
 				return Ok(book);
 			}
 		}
+		if !self.book_loader.support(filename) {
+			if let Some(container) = self.sniff(filename)? {
+				return Ok(container);
+			}
+		}
 		Ok(Box::new(DummyContainer::new(&filename)))
 	}
 
+	/// file without known extension opened by the magic of leading bytes,
+	/// the format recognized kept for reloading
+	fn sniff(&self, filename: &str) -> Result<Option<Box<dyn Container>>>
+	{
+		if !Path::new(filename).is_file() {
+			return Ok(None);
+		}
+		let mut head = vec![];
+		File::open(filename)?
+			.take(SNIFF_LENGTH)
+			.read_to_end(&mut head)?;
+		if let Some(format) = self.book_loader.sniff(&head) {
+			set_format(filename, format);
+			return Ok(Some(Box::new(DummyContainer::new(filename))));
+		}
+		if head.starts_with(ZIP_MAGIC) {
+			let container = ZipLoader {}.open(filename, &self.book_loader, self.password(filename))?;
+			return Ok(Some(container));
+		}
+		Ok(None)
+	}
+
+	#[inline]
+	pub fn set_password(&mut self, filename: &str, password: String)
+	{
+		self.passwords.insert(filename.to_owned(), password);
+	}
+
+	#[inline]
+	pub fn password(&self, filename: &str) -> Option<&str>
+	{
+		self.passwords.get(filename).map(|password| password.as_str())
+	}
+
+	/// books in the folder for opening one by one, not as container
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn folder_books(&self, folder: &str) -> Result<Vec<PathBuf>>
+	{
+		folder::folder_books(folder, &self.book_loader)
+	}
+
+	/// next or previous supported file in the folder of the book, in natural order
+	pub fn sibling_book(&self, filename: &str, forward: bool) -> Result<Option<String>>
+	{
+		let path = PathBuf::from(filename);
+		let (Some(folder), Some(name)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+			return Ok(None);
+		};
+		let mut names = vec![];
+		for entry in fs::read_dir(folder)? {
+			let entry = entry?.path();
+			if !entry.is_file() {
+				continue;
+			}
+			let (Some(entry_filename), Some(entry_name)) = (entry.to_str(), entry.file_name().and_then(|name| name.to_str())) else {
+				continue;
+			};
+			if self.book_loader.support(entry_name)
+				|| self.loaders.iter().any(|loader| loader.accept(entry_filename)) {
+				names.push(entry_name.to_owned());
+			}
+		}
+		names.string_sort_unstable(natural_lexical_cmp);
+		let Some(index) = names.iter().position(|entry_name| entry_name == name) else {
+			return Ok(None);
+		};
+		let sibling = if forward {
+			names.get(index + 1)
+		} else if index > 0 {
+			names.get(index - 1)
+		} else {
+			None
+		};
+		Ok(sibling.and_then(|name| folder.join(name).to_str().map(|name| name.to_owned())))
+	}
+
 	pub fn load_book(&self, container: &mut Box<dyn Container>, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let (book_index, chapter) = match &loading {
 			BookLoadingInfo::NewReading(_, inner_book, chapter, _) => (*inner_book, *chapter),
-			BookLoadingInfo::ChangeInnerBook(_, inner_book, _, _, _) => (*inner_book, 0),
+			BookLoadingInfo::ChangeInnerBook(_, inner_book, ..) => (*inner_book, 0),
 			BookLoadingInfo::History(reading) | BookLoadingInfo::Reload(reading)
 			=> (reading.inner_book, reading.chapter),
 		};
-		let book_name = if let Some(names) = container.inner_book_names() {
-			match names.get(book_index) {
-				Some(name) => name.name.clone(),
-				None => return Err(anyhow!("Invalid book index: {}", book_index)),
-			}
-		} else {
-			container.filename().to_owned()
-		};
+		let (book_name, content) = self.book_source(container, book_index)?;
 		let loading_chapter = if chapter == usize::MAX {
 			LoadingChapter::Last
 		} else {
 			LoadingChapter::Index(chapter)
 		};
-		let content = container.book_content(book_index)?;
-		let (book, reading) = self.book_loader.load(
+		// book in encrypted zip most likely shares the password
+		let (book, mut reading) = self.book_loader.load(
 			&book_name,
 			content,
 			loading_chapter,
-			loading)?;
+			loading,
+			self.password(container.filename()))?;
 		let lines = &mut book.lines();
 		let line_count = lines.len();
 		if line_count == 0 {
 			return Err(anyhow!(EMPTY_CHAPTER_CONTENT));
 		}
+		if reading.render_han.is_none() && book.vertical_writing() {
+			reading.render_han = Some(true);
+		}
 		Ok((book, reading))
 	}
+
+	/// name for choosing the loader and content of the inner book,
+	/// for loading it again without the container
+	pub fn book_source(&self, container: &mut Box<dyn Container>, book_index: usize)
+		-> Result<(String, BookContent)>
+	{
+		let book_name = if let Some(names) = container.inner_book_names() {
+			match names.get(book_index) {
+				Some(name) => name.name.clone(),
+				None => return Err(anyhow!("Invalid book index: {}", book_index)),
+			}
+		} else {
+			let filename = container.filename();
+			match format_of(filename) {
+				Some(format) => self.book_loader.format_filename(filename, &format)
+					.ok_or_else(|| anyhow!("Not support format: {}", format))?,
+				None => filename.to_owned(),
+			}
+		};
+		let content = container.book_content(book_index)?;
+		Ok((book_name, content))
+	}
 }
 
 pub trait ContainerLoader {
 	fn accept(&self, filename: &str) -> bool;
-	fn open(&self, filename: &str, book_loader: &BookLoader) -> Result<Box<dyn Container>>;
+	fn open(&self, filename: &str, book_loader: &BookLoader, password: Option<&str>)
+		-> Result<Box<dyn Container>>;
+}
+
+/// failed for encrypted zip entry without password or with wrong one
+pub fn password_required(err: &anyhow::Error) -> bool
+{
+	err.chain().any(|cause| matches!(cause.downcast_ref::<ZipError>(),
+		Some(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED))
+		| Some(ZipError::InvalidPassword)))
+}
+
+/// book loaded by the loader of the format, not by the extension
+#[inline]
+pub fn set_format(filename: &str, format: &str)
+{
+	if let Ok(mut formats) = FORMATS.lock() {
+		formats.insert(filename.to_owned(), format.to_owned());
+	}
+}
+
+#[inline]
+fn format_of(filename: &str) -> Option<String>
+{
+	FORMATS.lock().ok()?.get(filename).cloned()
+}
+
+/// failed for no loader supports the extension of the file
+#[inline]
+#[cfg(feature = "gui")]
+pub fn format_required(err: &anyhow::Error) -> bool
+{
+	err.chain().any(|cause| cause.is::<UnknownFormat>())
 }
 
 pub trait Container {
@@ -169,6 +328,7 @@ pub fn title_for_filename(filename: &str) -> &str
 		})
 }
 
+#[derive(Clone)]
 pub enum BookContent {
 	File(String),
 	Path(PathBuf),
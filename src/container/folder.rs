@@ -16,7 +16,8 @@ impl ContainerLoader for FolderLoader {
 			.map_or(false, |path| path.is_dir())
 	}
 
-	fn open(&self, filename: &str, book_loader: &BookLoader) -> Result<Box<dyn Container>>
+	fn open(&self, filename: &str, book_loader: &BookLoader, _password: Option<&str>)
+		-> Result<Box<dyn Container>>
 	{
 		let root = PathBuf::from_str(filename)?;
 		let dir = fs::read_dir(filename)?;
@@ -52,6 +53,21 @@ fn load_folder(dir: ReadDir, root: &PathBuf, files: &mut Vec<PathBuf>, names: &m
 	Ok(())
 }
 
+/// supported books in the folder and sub folders, in natural order
+#[cfg(feature = "gui")]
+pub(crate) fn folder_books(folder: &str, book_loader: &BookLoader) -> Result<Vec<PathBuf>>
+{
+	let root = PathBuf::from_str(folder)?;
+	let mut files = vec![];
+	let mut names = vec![];
+	load_folder(fs::read_dir(folder)?, &root, &mut files, &mut names, book_loader)?;
+	names.string_sort_unstable(natural_lexical_cmp);
+	Ok(names
+		.into_iter()
+		.map(|name| files[name.index].clone())
+		.collect())
+}
+
 struct FolderContainer {
 	filename: String,
 	files: Vec<PathBuf>,
@@ -0,0 +1,91 @@
+use std::io::{IsTerminal, Read};
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+
+use crate::book::BookLoader;
+use crate::container::{BookContent, BookName, Container, ContainerLoader};
+
+/// filename in command line for reading book from stdin
+pub const STDIN_FILENAME: &str = "-";
+pub const DEFAULT_STDIN_FORMAT: &str = "txt";
+// pseudo filename of the content, format as extension for choosing loader
+const STDIN_NAME: &str = "<stdin>";
+
+static CONTENT: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// read all content of stdin once at start, pseudo filename returned
+pub fn read_stdin(format: &str, book_loader: &BookLoader) -> Result<String>
+{
+	let Some(filename) = book_loader.format_filename(STDIN_NAME, format) else {
+		bail!("Not support format: {}", format);
+	};
+	let mut stdin = std::io::stdin();
+	if stdin.is_terminal() {
+		bail!("No content piped to stdin");
+	}
+	let mut content = vec![];
+	stdin.read_to_end(&mut content)?;
+	if content.is_empty() {
+		bail!("Empty content read from stdin");
+	}
+	let _ = CONTENT.set(content);
+	Ok(filename)
+}
+
+#[inline]
+pub fn is_stdin(filename: &str) -> bool
+{
+	CONTENT.get().is_some() && is_stdin_name(filename)
+}
+
+/// pseudo filename of stdin content, even not read in this run,
+/// for entries kept in history by former versions
+#[inline]
+pub fn is_stdin_name(filename: &str) -> bool
+{
+	filename.starts_with(STDIN_NAME)
+}
+
+pub(crate) struct StdinLoader {}
+
+impl ContainerLoader for StdinLoader {
+	#[inline]
+	fn accept(&self, filename: &str) -> bool
+	{
+		is_stdin(filename)
+	}
+
+	fn open(&self, filename: &str, _book_loader: &BookLoader, _password: Option<&str>)
+		-> Result<Box<dyn Container>>
+	{
+		Ok(Box::new(StdinContainer { filename: filename.to_owned() }))
+	}
+}
+
+/// content kept in memory, loaded again for reloading
+pub(crate) struct StdinContainer {
+	filename: String,
+}
+
+impl Container for StdinContainer {
+	#[inline]
+	fn filename(&self) -> &str
+	{
+		&self.filename
+	}
+
+	#[inline]
+	fn inner_book_names(&self) -> Option<&Vec<BookName>>
+	{
+		None
+	}
+
+	fn book_content(&mut self, _inner_index: usize) -> Result<BookContent>
+	{
+		match CONTENT.get() {
+			Some(content) => Ok(BookContent::Buf(content.clone())),
+			None => bail!("No content read from stdin"),
+		}
+	}
+}
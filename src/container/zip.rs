@@ -5,10 +5,12 @@ use anyhow::Result;
 use lexical_sort::{natural_lexical_cmp, StringSort};
 use zip::ZipArchive;
 
-use crate::common::plain_text;
+use crate::common::zip_entry_names;
 use crate::container::{BookContent, BookName, Container, ContainerLoader};
 use crate::BookLoader;
 
+pub(super) const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
 pub(crate) struct ZipLoader {}
 
 impl ContainerLoader for ZipLoader {
@@ -17,29 +19,22 @@ impl ContainerLoader for ZipLoader {
 		filename.ends_with(".zip")
 	}
 
-	fn open(&self, filename: &str, book_loader: &BookLoader) -> Result<Box<dyn Container>>
+	fn open(&self, filename: &str, book_loader: &BookLoader, password: Option<&str>)
+		-> Result<Box<dyn Container>>
 	{
 		let file = OpenOptions::new().read(true).open(filename)?;
 		let mut zip = ZipArchive::new(file)?;
-		let mut buf = vec![];
-		for i in 0..zip.len() {
-			let zip_file = zip.by_index(i)?;
-			if buf.len() > 0 {
-				buf.push(b'\n');
-			}
-			buf.extend_from_slice(zip_file.name_raw());
-		}
-		let names = plain_text(buf, true)?;
-		let names = names.split('\n');
+		let names = zip_entry_names(&mut zip)?;
 		let mut files = vec![];
-		for (idx, name) in names.enumerate() {
-			if book_loader.support(name) {
-				files.push(BookName { name: String::from(name), index: idx });
+		for (idx, name) in names.into_iter().enumerate() {
+			if book_loader.support(&name) {
+				files.push(BookName { name, index: idx });
 			}
 		}
 		files.string_sort_unstable(natural_lexical_cmp);
 		let filename = filename.to_owned();
-		Ok(Box::new(ZipContainer { filename, zip, files }))
+		let password = password.map(|password| password.to_owned());
+		Ok(Box::new(ZipContainer { filename, zip, files, password }))
 	}
 }
 
@@ -47,6 +42,7 @@ pub(crate) struct ZipContainer {
 	filename: String,
 	zip: ZipArchive<File>,
 	files: Vec<BookName>,
+	password: Option<String>,
 }
 
 impl Container for ZipContainer {
@@ -65,7 +61,10 @@ impl Container for ZipContainer {
 	fn book_content(&mut self, inner_index: usize) -> Result<BookContent>
 	{
 		let book_name = &self.files[inner_index];
-		let mut zip_file = self.zip.by_index(book_name.index)?;
+		let mut zip_file = match &self.password {
+			Some(password) => self.zip.by_index_decrypt(book_name.index, password.as_bytes())?,
+			None => self.zip.by_index(book_name.index)?,
+		};
 		let mut content = vec![];
 		zip_file.read_to_end(&mut content)?;
 		Ok(BookContent::Buf(content))
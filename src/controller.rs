@@ -1,16 +1,30 @@
+#[cfg(feature = "gui")]
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
 use std::ops::Range;
+#[cfg(feature = "script")]
+use std::rc::Rc;
 use anyhow::{anyhow, bail, Result};
-use fancy_regex::Regex;
+use indexmap::IndexMap;
 
 use crate::{ContainerManager, Position};
-use crate::book::{Book, Line};
+use crate::book::{Book, Line, SearchPattern};
 use crate::common::TraceInfo;
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::container::{Container, load_book, load_container};
+use crate::crash;
+#[cfg(feature = "gui")]
+use crate::download;
+#[cfg(feature = "script")]
+use crate::script::{Script, ScriptCommand};
 
 const TRACE_SIZE: usize = 100;
+// chapters with last reading position remembered
+const CHAPTER_POSITIONS_SIZE: usize = 64;
+// chars of text shown for a trace entry
+#[cfg(feature = "gui")]
+const TRACE_SNIPPET_CHARS: usize = 40;
 
 pub trait Render<C> {
 	// init for book loaded
@@ -43,10 +57,19 @@ pub struct HighlightInfo {
 	pub mode: HighlightMode,
 }
 
+/// trace entry for listing, with title and text at the position
+#[cfg(feature = "gui")]
+pub struct TraceEntry {
+	pub chapter: usize,
+	pub title: Option<String>,
+	pub snippet: String,
+}
+
 pub struct ReadingStatus<'a> {
 	pub title: Option<&'a str>,
 	pub total_line: usize,
 	pub current_line: usize,
+	pub page: Option<&'a str>,
 }
 
 impl<'a> ReadingStatus<'a> {
@@ -63,10 +86,14 @@ impl<'a> Display for ReadingStatus<'a> {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
 	{
 		if let Some(title) = &self.title {
-			write!(f, "{}({}:{})", title, self.total_line, self.current_line)
+			write!(f, "{}({}:{})", title, self.total_line, self.current_line)?;
 		} else {
-			write!(f, "({}:{})", self.total_line, self.current_line)
+			write!(f, "({}:{})", self.total_line, self.current_line)?;
+		}
+		if let Some(page) = &self.page {
+			write!(f, "[{}]", page)?;
 		}
+		Ok(())
 	}
 }
 
@@ -78,19 +105,38 @@ pub struct Controller<C, R: Render<C> + ?Sized>
 	pub book: Box<dyn Book>,
 	pub reading: ReadingInfo,
 	pub search_pattern: String,
+	// full-width and half-width forms matched each other when searching
+	pub search_fold_width: bool,
+	// page commands continue into next or previous chapter
+	pub chapter_continuation: bool,
 	pub render: Box<R>,
+	#[cfg(feature = "script")]
+	script: Option<Rc<Script>>,
 
 	highlight: Option<HighlightInfo>,
 	trace: Vec<TraceInfo>,
 	current_trace: usize,
+	// last reading position of visited chapters in this session,
+	// restored when tracing back into the chapter
+	chapter_positions: IndexMap<usize, Position>,
 	next: Option<Position>,
+	// chapter changed by last page command, for showing chapter title
+	#[cfg(feature = "gui")]
+	chapter_crossed: bool,
+	// title and snippet of traced positions, chapters loaded once for listing
+	#[cfg(feature = "gui")]
+	trace_texts: HashMap<TraceInfo, (Option<String>, String)>,
 }
 
 impl<C, R: Render<C> + ?Sized> Controller<C, R>
 {
-	pub fn new(loading: BookLoadingInfo, render: Box<R>, render_context: &mut C) -> Result<Self>
+	pub fn new(loading: BookLoadingInfo, password: Option<String>, render: Box<R>,
+		render_context: &mut C) -> Result<Self>
 	{
-		let container_manager = Default::default();
+		let mut container_manager = ContainerManager::default();
+		if let Some(password) = password {
+			container_manager.set_password(loading.filename(), password);
+		}
 		let mut container = load_container(&container_manager, loading.filename())?;
 		let (book, reading) = load_book(&container_manager, &mut container, loading)?;
 		Ok(Controller::from_data(
@@ -116,10 +162,19 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 			book,
 			reading,
 			search_pattern: "".to_string(),
+			search_fold_width: false,
+			chapter_continuation: true,
 			trace,
+			#[cfg(feature = "script")]
+			script: None,
 			current_trace: 0,
+			chapter_positions: IndexMap::new(),
 			highlight: None,
 			next: None,
+			#[cfg(feature = "gui")]
+			chapter_crossed: false,
+			#[cfg(feature = "gui")]
+			trace_texts: HashMap::new(),
 			render,
 		}
 	}
@@ -152,6 +207,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 			&self.highlight,
 			context);
 		self.next = next;
+		self.remember_position();
 	}
 
 	#[inline]
@@ -168,6 +224,21 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		self.reading.position = offset;
 		self.next = next;
 		self.push_trace(true);
+		self.remember_position();
+	}
+
+	/// most recently read chapter at the end, least recent dropped when full,
+	/// and reading drawn kept for saving when crashed
+	fn remember_position(&mut self)
+	{
+		crash::track_reading(&self.reading);
+		let chapter = self.reading.chapter;
+		let position = Position::new(self.reading.line, self.reading.position);
+		self.chapter_positions.shift_remove(&chapter);
+		self.chapter_positions.insert(chapter, position);
+		if self.chapter_positions.len() > CHAPTER_POSITIONS_SIZE {
+			self.chapter_positions.shift_remove_index(0);
+		}
 	}
 
 	#[inline]
@@ -180,11 +251,42 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 	{
 		let title = self.book
 			.title(self.reading.line, self.reading.position);
+		let page = self.book
+			.page_label(self.reading.line, self.reading.position);
 		ReadingStatus {
 			title,
 			total_line: self.book.lines().len(),
 			current_line: self.reading.line + 1,
+			page,
+		}
+	}
+
+	/// last page of the last chapter in the last inner book displayed
+	pub fn at_end(&self) -> bool
+	{
+		if self.next.is_some() || self.book.current_chapter() + 1 < self.book.chapter_count() {
+			return false;
 		}
+		match self.container.inner_book_names() {
+			Some(names) => self.reading.inner_book + 1 >= names.len(),
+			None => true,
+		}
+	}
+
+	/// start position of next page, None for last page
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn next_page_position(&self) -> Option<&Position>
+	{
+		self.next.as_ref()
+	}
+
+	/// line of search match, focused link or selection start
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn highlight_line(&self) -> Option<usize>
+	{
+		self.highlight.as_ref().map(|highlight| highlight.line)
 	}
 
 	pub fn search(&mut self, pattern: &str, context: &mut C) -> Result<()>
@@ -211,10 +313,15 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		self.book = book;
 		self.reading = reading;
 		self.trace.clear();
+		self.chapter_positions.clear();
+		#[cfg(feature = "gui")]
+		self.trace_texts.clear();
 		self.trace.push(TraceInfo { chapter: self.reading.chapter, line: self.reading.line, offset: self.reading.position });
 		self.current_trace = 0;
 		self.book_loaded(context);
 		self.redraw(context);
+		#[cfg(feature = "script")]
+		self.book_opened(context)?;
 		Ok(self.status().to_string())
 	}
 
@@ -237,6 +344,9 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		self.book = book;
 		self.reading = reading;
 		self.trace.clear();
+		self.chapter_positions.clear();
+		#[cfg(feature = "gui")]
+		self.trace_texts.clear();
 		self.trace.push(TraceInfo { chapter: self.reading.chapter, line: self.reading.line, offset: self.reading.position });
 		self.current_trace = 0;
 		self.book_loaded(context);
@@ -254,26 +364,59 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		Ok(())
 	}
 
+	pub fn goto_page(&mut self, label: &str, context: &mut C) -> Result<()>
+	{
+		let trace_info = self.book.page_position(label)
+			.ok_or_else(|| anyhow!("Invalid page: {}", label))?;
+		self.do_goto_toc(trace_info, context)
+			.ok_or_else(|| anyhow!("Invalid page: {}", label))?;
+		Ok(())
+	}
+
 	pub fn next_page(&mut self, context: &mut C) -> Result<()> {
 		if let Some(next) = &self.next {
 			let line = next.line;
 			let offset = next.offset;
 			self.redraw_at(line, offset, context);
-		} else if !self.switch_chapter(true, context)? {
+		} else if !self.chapter_continuation {
+			return Ok(());
+		} else if self.switch_chapter(true, context)? {
+			#[cfg(feature = "gui")]
+			{
+				self.chapter_crossed = true;
+			}
+		} else {
 			let book_index = self.reading.inner_book + 1;
 			if let Some(names) = self.container.inner_book_names() {
 				let book_count = names.len();
 				if book_index < book_count {
 					self.do_switch_book(book_index, context)?;
+					#[cfg(feature = "gui")]
+					{
+						self.chapter_crossed = true;
+					}
 				}
 			}
 		}
+		#[cfg(feature = "script")]
+		self.page_turned(context)?;
 		Ok(())
 	}
 
+	/// true if chapter changed by page command since last call
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn take_chapter_crossed(&mut self) -> bool
+	{
+		std::mem::take(&mut self.chapter_crossed)
+	}
+
 	pub fn prev_page(&mut self, context: &mut C) -> Result<()>
 	{
 		if self.reading.line == 0 && self.reading.position == 0 {
+			if !self.chapter_continuation {
+				return Ok(());
+			}
 			let reading = &mut self.reading;
 			if let Some(current_chapter) = self.book.prev_chapter()? {
 				reading.chapter = current_chapter;
@@ -281,6 +424,10 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 				// prev need decrease this invalid reading.line
 				let position = self.render.prev_page(self.book.as_ref(), lines, lines.len(), 0, context);
 				self.redraw_at(position.line, position.offset, context);
+				#[cfg(feature = "gui")]
+				{
+					self.chapter_crossed = true;
+				}
 			} else {
 				if reading.inner_book > 0 {
 					let loading = BookLoadingInfo::NewReading(
@@ -298,16 +445,25 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 					new_reading.position = position.offset;
 					self.reading = new_reading;
 					self.trace.clear();
+					self.chapter_positions.clear();
+					#[cfg(feature = "gui")]
+					self.trace_texts.clear();
 					self.trace.push(TraceInfo { chapter: self.reading.chapter, line: self.reading.line, offset: self.reading.position });
 					self.current_trace = 0;
 					self.book_loaded(context);
 					self.redraw(context);
+					#[cfg(feature = "gui")]
+					{
+						self.chapter_crossed = true;
+					}
 				}
 			}
 		} else {
 			let position = self.render.prev_page(self.book.as_ref(), self.book.lines(), self.reading.line, self.reading.position, context);
 			self.redraw_at(position.line, position.offset, context);
 		}
+		#[cfg(feature = "script")]
+		self.page_turned(context)?;
 		Ok(())
 	}
 
@@ -375,6 +531,13 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		}
 	}
 
+	#[cfg(feature = "gui")]
+	pub fn goto_landmark(&mut self, index: usize, context: &mut C) -> Option<String>
+	{
+		let trace_info = self.book.landmark_position(index)?;
+		self.do_goto_toc(trace_info, context)
+	}
+
 	fn do_goto_toc(&mut self, trace_info: TraceInfo, context: &mut C) -> Option<String>
 	{
 		if self.reading.chapter != trace_info.chapter {
@@ -471,7 +634,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 	fn search_next(&mut self, start_line: usize, start_position: usize, context: &mut C) -> Result<()> {
 		let book = self.book.as_ref();
 		let lines = book.lines();
-		let regex = Regex::new(&self.search_pattern)?;
+		let regex = SearchPattern::new(&self.search_pattern, self.search_fold_width, false)?;
 		let mut position = start_position;
 		for idx in start_line..lines.len() {
 			let line = &lines[idx];
@@ -492,7 +655,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 
 	fn search_prev(&mut self, start_line: usize, start_position: usize, context: &mut C) -> Result<()> {
 		let lines = self.book.lines();
-		let regex = Regex::new(&self.search_pattern)?;
+		let regex = SearchPattern::new(&self.search_pattern, self.search_fold_width, false)?;
 		for idx in (0..=start_line).rev() {
 			let range = if idx == start_line {
 				if start_position == 0 {
@@ -536,20 +699,102 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		}
 	}
 
+	/// navigation trace with the current index, for saving between sessions
+	#[inline]
+	pub fn trace(&self) -> (&[TraceInfo], usize)
+	{
+		(&self.trace, self.current_trace)
+	}
+
+	/// restore trace of last session, reading position appended if moved since
+	pub fn restore_trace(&mut self, trace: Vec<TraceInfo>, current: usize)
+	{
+		let chapter_count = self.book.chapter_count();
+		if current >= trace.len() || trace.iter().any(|info| info.chapter >= chapter_count) {
+			return;
+		}
+		self.trace = trace;
+		self.current_trace = current;
+		self.push_trace(false);
+	}
+
+	/// entries of trace for listing, chapters of entries not listed before
+	/// loaded for the text, current chapter restored after
+	#[cfg(feature = "gui")]
+	pub fn trace_entries(&mut self) -> Result<Vec<TraceEntry>>
+	{
+		let trace = &self.trace;
+		self.trace_texts.retain(|info, _| trace.contains(info));
+		let current_chapter = self.book.current_chapter();
+		let mut chapters = self.trace
+			.iter()
+			.filter(|info| !self.trace_texts.contains_key(info))
+			.map(|info| info.chapter)
+			.collect::<Vec<_>>();
+		chapters.sort_unstable();
+		chapters.dedup();
+		// current chapter last, no reload needed after
+		if let Some(index) = chapters.iter().position(|chapter| *chapter == current_chapter) {
+			chapters.remove(index);
+			chapters.push(current_chapter);
+		}
+		for chapter in chapters {
+			if self.book.current_chapter() != chapter
+				&& self.book.goto_chapter(chapter)? != Some(chapter) {
+				continue;
+			}
+			let lines = self.book.lines();
+			for info in &self.trace {
+				if info.chapter != chapter || self.trace_texts.contains_key(info) {
+					continue;
+				}
+				let title = self.book.title(info.line, info.offset)
+					.map(|title| title.to_owned());
+				let snippet = lines.get(info.line).map_or_else(String::new, |line| line.iter()
+					.skip(info.offset)
+					.take(TRACE_SNIPPET_CHARS)
+					.collect::<String>()
+					.trim()
+					.to_owned());
+				self.trace_texts.insert(info.clone(), (title, snippet));
+			}
+		}
+		if self.book.current_chapter() != current_chapter {
+			self.book.goto_chapter(current_chapter)?;
+		}
+		let entries = self.trace
+			.iter()
+			.map(|info| {
+				let (title, snippet) = self.trace_texts.get(info).cloned().unwrap_or_default();
+				TraceEntry { chapter: info.chapter, title, snippet }
+			})
+			.collect();
+		Ok(entries)
+	}
+
 	pub fn goto_trace(&mut self, backward: bool, context: &mut C) -> Result<()>
 	{
-		let reading = &mut self.reading;
-		if backward {
+		let index = if backward {
 			if self.current_trace == 0 {
 				return Ok(());
-			} else {
-				self.current_trace -= 1;
 			}
+			self.current_trace - 1
 		} else if self.current_trace == self.trace.len() - 1 {
 			return Ok(());
 		} else {
-			self.current_trace += 1;
+			self.current_trace + 1
+		};
+		self.goto_trace_at(index, context)
+	}
+
+	/// jump to the trace entry at index directly
+	pub fn goto_trace_at(&mut self, index: usize, context: &mut C) -> Result<()>
+	{
+		if index >= self.trace.len() {
+			return Ok(());
 		}
+		self.current_trace = index;
+		let reading = &mut self.reading;
 		let current_trace = &self.trace[self.current_trace];
 		if reading.chapter == current_trace.chapter {
 			reading.line = current_trace.line;
@@ -557,8 +802,14 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		} else if let Some(new_chapter) = self.book.goto_chapter(current_trace.chapter)? {
 			assert_eq!(new_chapter, current_trace.chapter);
 			reading.chapter = new_chapter;
-			reading.line = current_trace.line;
-			reading.position = current_trace.offset;
+			// back to where the chapter left, trace may hold the jump target only
+			if let Some(position) = self.chapter_positions.get(&new_chapter) {
+				reading.line = position.line;
+				reading.position = position.offset;
+			} else {
+				reading.line = current_trace.line;
+				reading.position = current_trace.offset;
+			}
 		} else {
 			return Ok(());
 		}
@@ -611,6 +862,21 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		Ok(self.status().to_string())
 	}
 
+	/// jump to the exact position, for position links
+	pub fn goto_position(&mut self, inner_book: usize, chapter: usize,
+		line: usize, offset: usize, context: &mut C) -> Result<String>
+	{
+		if inner_book != self.reading.inner_book || chapter != self.reading.chapter {
+			self.goto(inner_book, chapter, 0, 0, None, context)?;
+		}
+		let lines = self.book.lines();
+		let line = line.min(lines.len().saturating_sub(1));
+		let offset = lines.get(line).map_or(0, |text| offset.min(text.len().saturating_sub(1)));
+		self.highlight = None;
+		self.redraw_at(line, offset, context);
+		Ok(self.status().to_string())
+	}
+
 	pub fn switch_link_prev(&mut self, context: &mut C)
 	{
 		let (mut line, mut position) = match &self.highlight {
@@ -676,6 +942,50 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		self.highlight_setup(context);
 	}
 
+	/// focus next or previous link of current page, wrapped at page ends
+	#[cfg(feature = "gui")]
+	pub fn cycle_link(&mut self, forward: bool, context: &mut C)
+	{
+		let lines = self.book.lines();
+		let (end_line, end_offset) = match &self.next {
+			Some(next) => (next.line, next.offset),
+			None => (lines.len(), 0),
+		};
+		let mut links = vec![];
+		for index in self.reading.line..(end_line + 1).min(lines.len()) {
+			lines[index].link_iter(true, |link| {
+				let in_page = (index > self.reading.line || link.range.end > self.reading.position)
+					&& (index < end_line || link.range.start < end_offset);
+				if in_page {
+					links.push(HighlightInfo {
+						line: index,
+						start: link.range.start,
+						end: link.range.end,
+						mode: HighlightMode::Link(link.index),
+					});
+				}
+				(false, None::<()>)
+			});
+		}
+		let current = match &self.highlight {
+			Some(HighlightInfo { mode: HighlightMode::Link(..), line, start, .. }) => Some((*line, *start)),
+			_ => None,
+		};
+		let found = match current {
+			Some(current) if forward => links.iter().position(|link| (link.line, link.start) > current),
+			Some(current) => links.iter().rposition(|link| (link.line, link.start) < current),
+			None => None,
+		};
+		let index = match found {
+			Some(index) => index,
+			None if links.is_empty() => return,
+			None if forward => 0,
+			None => links.len() - 1,
+		};
+		self.highlight = Some(links.swap_remove(index));
+		self.redraw(context);
+	}
+
 	pub fn try_goto_link(&mut self, context: &mut C) -> Result<()>
 	{
 		match self.highlight {
@@ -720,6 +1030,32 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		Ok(())
 	}
 
+	/// where the link goes, title of target or the url of external link
+	#[cfg(feature = "gui")]
+	pub fn link_preview(&mut self, line: usize, link_index: usize) -> Result<Option<String>>
+	{
+		let Some(link) = self.book.lines().get(line).and_then(|text| text.link_at(link_index)) else {
+			return Ok(None);
+		};
+		if download::is_url(link.target) {
+			return Ok(Some(link.target.to_owned()));
+		}
+		let Some(pos) = self.book.link_position(line, link_index) else {
+			return Ok(None);
+		};
+		let current_chapter = self.book.current_chapter();
+		if pos.chapter == current_chapter {
+			return Ok(self.book.title(pos.line, pos.offset).map(str::to_owned));
+		}
+		let title = if self.book.goto_chapter(pos.chapter)? == Some(pos.chapter) {
+			self.book.title(pos.line, pos.offset).map(str::to_owned)
+		} else {
+			None
+		};
+		self.book.goto_chapter(current_chapter)?;
+		Ok(title)
+	}
+
 	#[allow(unused)]
 	pub fn select_text(&mut self, from: Position, to: Position, context: &mut C)
 	{
@@ -766,12 +1102,82 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		}
 	}
 
+	/// script attached with book opened hook called
+	#[cfg(feature = "script")]
+	pub fn attach_script(&mut self, script: Rc<Script>, context: &mut C) -> Result<()>
+	{
+		self.script = Some(script);
+		self.book_opened(context)
+	}
+
+	#[cfg(all(feature = "script", feature = "gui"))]
+	#[inline]
+	pub fn text_selected(&mut self, context: &mut C) -> Result<()>
+	{
+		if let (Some(script), Some(range)) = (self.script.clone(), self.selection_range()) {
+			if let Some(text) = self.selected() {
+				let commands = script.text_selected(text, self.reading.chapter, range.start.line)?;
+				self.run_script(commands, context)?;
+			}
+		}
+		Ok(())
+	}
+
+	#[cfg(feature = "script")]
+	#[inline]
+	fn book_opened(&mut self, context: &mut C) -> Result<()>
+	{
+		if let Some(script) = self.script.clone() {
+			let commands = script.book_opened(&self.reading.filename, self.reading_book_name())?;
+			self.run_script(commands, context)?;
+		}
+		Ok(())
+	}
+
+	#[cfg(feature = "script")]
+	#[inline]
+	fn page_turned(&mut self, context: &mut C) -> Result<()>
+	{
+		if let Some(script) = self.script.clone() {
+			let reading = &self.reading;
+			let commands = script.page_turned(reading.chapter, reading.line, reading.position)?;
+			self.run_script(commands, context)?;
+		}
+		Ok(())
+	}
+
+	#[cfg(feature = "script")]
+	fn run_script(&mut self, commands: Vec<ScriptCommand>, context: &mut C) -> Result<()>
+	{
+		for command in commands {
+			match command {
+				ScriptCommand::Goto { chapter, line, offset } => {
+					self.goto(self.reading.inner_book, chapter, line, offset, None, context)?;
+				}
+				ScriptCommand::Search(pattern) => self.search(&pattern, context)?,
+			}
+		}
+		Ok(())
+	}
+
 	#[inline]
 	pub fn toc_index(&self) -> usize
 	{
 		self.book.toc_index(self.reading.line, self.reading.position)
 	}
 
+	/// toc entries of current page, the first, the last and
+	/// true if the last ends in the page
+	#[cfg(feature = "gui")]
+	pub fn page_toc_range(&self) -> (usize, usize, bool)
+	{
+		let first = self.toc_index();
+		match &self.next {
+			Some(next) => (first, self.book.toc_index(next.line, next.offset).max(first), false),
+			None => (first, self.book.toc_index(self.book.lines().len(), 0).max(first), true),
+		}
+	}
+
 	#[inline]
 	#[allow(unused)]
 	pub fn selected(&self) -> Option<&str>
@@ -779,6 +1185,18 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		highlight_selection(&self.highlight)
 	}
 
+	/// range of selected text, end exclusive
+	#[inline]
+	#[allow(unused)]
+	pub fn selection_range(&self) -> Option<Range<Position>>
+	{
+		if let Some(HighlightInfo { line, start, end, mode: HighlightMode::Selection(_, end_line) }) = &self.highlight {
+			Some(Position::new(*line, *start)..Position::new(*end_line, *end))
+		} else {
+			None
+		}
+	}
+
 	#[inline]
 	#[allow(unused)]
 	pub fn has_selection(&self) -> bool
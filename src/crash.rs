@@ -0,0 +1,131 @@
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::panic;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "gui")]
+use std::rc::Weak;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{package_name, version};
+use crate::config::{Configuration, ReadingInfo, save_reading_in};
+
+// reports saved here in config dir
+const CRASH_FOLDER: &str = "crash";
+
+/// state of the ui thread for saving when panic
+#[derive(Default)]
+struct CrashState {
+	// last drawn reading of every book opened, by filename
+	readings: HashMap<String, ReadingInfo>,
+	last_action: Option<String>,
+	#[cfg(feature = "gui")]
+	configuration: Option<Weak<RefCell<Configuration>>>,
+}
+
+thread_local! {
+	static STATE: RefCell<CrashState> = RefCell::new(CrashState::default());
+}
+
+/// save reading position of books opened and configuration when panic,
+/// with a crash report in config dir, then the default hook called
+pub fn install_hook(config_dir: &Path, configuration: &Configuration)
+{
+	let report_dir = config_dir.join(CRASH_FOLDER);
+	let history = configuration.history_file().clone();
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		// only ui thread has the state
+		let state = STATE.try_with(|state| state.try_borrow_mut()
+			.map(|mut state| std::mem::take(&mut *state))
+			.ok())
+			.ok()
+			.flatten();
+		let report = report(info, state.as_ref());
+		if let Some(state) = state {
+			save_state(state, &history);
+		}
+		match write_report(&report_dir, &report) {
+			Ok(path) => eprintln!("Crash report saved: {}", path.display()),
+			Err(err) => eprintln!("Failed save crash report: {}", err),
+		}
+		default_hook(info);
+	}));
+}
+
+/// configuration of gui shared by windows, saved when panic if not borrowed
+#[cfg(feature = "gui")]
+pub fn track_configuration(configuration: Weak<RefCell<Configuration>>)
+{
+	let _ = STATE.try_with(|state| if let Ok(mut state) = state.try_borrow_mut() {
+		state.configuration = Some(configuration);
+	});
+}
+
+#[inline]
+pub fn track_reading(reading: &ReadingInfo)
+{
+	let _ = STATE.try_with(|state| if let Ok(mut state) = state.try_borrow_mut() {
+		state.readings.insert(reading.filename.clone(), reading.clone());
+	});
+}
+
+#[inline]
+pub fn track_action(action: &str)
+{
+	let _ = STATE.try_with(|state| if let Ok(mut state) = state.try_borrow_mut() {
+		state.last_action = Some(action.to_owned());
+	});
+}
+
+fn save_state(state: CrashState, history: &PathBuf)
+{
+	for (filename, mut reading) in state.readings {
+		if let Err(err) = save_reading_in(history, &mut reading) {
+			eprintln!("Failed save reading of {}: {}", filename, err);
+		}
+	}
+	#[cfg(feature = "gui")]
+	if let Some(configuration) = state.configuration.and_then(|configuration| configuration.upgrade()) {
+		if let Ok(configuration) = configuration.try_borrow() {
+			if let Err(err) = configuration.save() {
+				eprintln!("Failed save configuration: {}", err);
+			}
+		}
+	}
+}
+
+fn report(info: &PanicHookInfo, state: Option<&CrashState>) -> String
+{
+	let mut report = String::new();
+	let _ = writeln!(report, "{} v{}", package_name!(), version!());
+	let _ = writeln!(report, "{}", info);
+	if let Some(state) = state {
+		let _ = writeln!(report, "last action: {}",
+			state.last_action.as_deref().unwrap_or("none"));
+		for reading in state.readings.values() {
+			let format = Path::new(&reading.filename)
+				.extension()
+				.map_or_else(|| "unknown".into(), |ext| ext.to_string_lossy().to_lowercase());
+			let _ = writeln!(report, "book: {}, format: {}, inner book: {}, chapter: {}, line: {}, position: {}",
+				reading.filename, format, reading.inner_book, reading.chapter,
+				reading.line, reading.position);
+		}
+	}
+	let _ = writeln!(report, "\n{}", Backtrace::force_capture());
+	report
+}
+
+fn write_report(report_dir: &PathBuf, report: &str) -> std::io::Result<PathBuf>
+{
+	fs::create_dir_all(report_dir)?;
+	let ts = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs());
+	let path = report_dir.join(format!("crash-{}.txt", ts));
+	fs::write(&path, report)?;
+	Ok(path)
+}
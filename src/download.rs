@@ -0,0 +1,78 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Result};
+
+// books downloaded by curl, so no http client needed
+pub const CURL_COMMAND: &str = "curl";
+const BOOKS_CACHE_FOLDER: &str = "books";
+const PARTIAL_SUFFIX: &str = ".part";
+
+#[inline]
+pub fn is_url(filename: &str) -> bool
+{
+	let filename = filename.to_lowercase();
+	filename.starts_with("http://") || filename.starts_with("https://")
+}
+
+/// cache file of the url, file name in url kept for detecting book type
+pub fn cached_path(cache_dir: &Path, url: &str) -> PathBuf
+{
+	let path = url.split(['?', '#']).next().unwrap_or(url);
+	let name = path.rsplit('/')
+		.next()
+		.filter(|name| !name.is_empty())
+		.unwrap_or("book")
+		.chars()
+		.map(|ch| if ch.is_alphanumeric() || ch == '.' || ch == '-' || ch == '_' { ch } else { '_' })
+		.collect::<String>();
+	cache_dir
+		.join(BOOKS_CACHE_FOLDER)
+		.join(format!("{:016x}-{}", fnv_hash(url), name))
+}
+
+/// downloading file, renamed to cached path when finished
+#[inline]
+pub fn partial_path(path: &Path) -> PathBuf
+{
+	let mut partial = OsString::from(path.as_os_str());
+	partial.push(PARTIAL_SUFFIX);
+	PathBuf::from(partial)
+}
+
+/// download with progress bar of curl, cached file reused
+pub fn download(url: &str, cache_dir: &Path) -> Result<PathBuf>
+{
+	let path = cached_path(cache_dir, url);
+	if path.is_file() {
+		return Ok(path);
+	}
+	if let Some(folder) = path.parent() {
+		fs::create_dir_all(folder)?;
+	}
+	let partial = partial_path(&path);
+	let status = Command::new(CURL_COMMAND)
+		.args(["-fL", "--progress-bar", "-o"])
+		.arg(&partial)
+		.arg(url)
+		.status()?;
+	if !status.success() {
+		let _ = fs::remove_file(&partial);
+		bail!("Failed download {}", url);
+	}
+	fs::rename(&partial, &path)?;
+	Ok(path)
+}
+
+// stable between runs, unlike the std hasher
+fn fnv_hash(text: &str) -> u64
+{
+	let mut hash: u64 = 0xcbf29ce484222325;
+	for byte in text.bytes() {
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+	hash
+}
@@ -1,40 +1,55 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::collections::HashMap;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::collections::{HashMap, VecDeque};
 use std::env;
+use std::fs;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{bail, Result};
-use gtk4::{AlertDialog, Align, Application, ApplicationWindow, Button, CssProvider, DropTarget, EventControllerKey, FileDialog, FileFilter, gdk, GestureClick, HeaderBar, Image, Label, Orientation, Paned, Popover, PopoverMenu, PositionType, SearchEntry, Separator, Stack, ToggleButton, Widget, Window};
-use gtk4::gdk::{Display, DragAction, Key, ModifierType, Rectangle, Texture};
-use gtk4::gdk_pixbuf::Pixbuf;
-use gtk4::gio::{ApplicationFlags, Cancellable, File, MemoryInputStream, Menu, MenuItem, MenuModel, SimpleAction, SimpleActionGroup};
+use gtk4::{AccessibleRole, AlertDialog, Align, Application, ApplicationWindow, Button, CssProvider, DropTarget, EventControllerKey, EventControllerMotion, FileDialog, FileFilter, gdk, GestureClick, HeaderBar, Image, Label, Orientation, Overlay, Paned, Picture, Popover, PopoverMenu, PositionType, Revealer, RevealerTransitionType, SearchEntry, Separator, Stack, TextDirection, ToggleButton, Widget, Window};
+use gtk4::accessible::Property;
+use gtk4::gdk::{Display, DragAction, FileList, Key, ModifierType, Rectangle, Texture};
+use gtk4::gdk_pixbuf::{InterpType, Pixbuf};
+use gtk4::gio::{ApplicationFlags, Cancellable, File, FileMonitor, FileMonitorEvent, FileMonitorFlags, MemoryInputStream, Menu, MenuItem, MenuModel, Notification, SimpleAction, SimpleActionGroup};
 use gtk4::glib;
-use gtk4::glib::{Bytes, closure_local, ExitCode, format_size, Variant};
-use gtk4::glib::prelude::{ObjectExt, StaticType, ToVariant};
+use gtk4::glib::{Bytes, closure_local, ControlFlow, ExitCode, format_size, Variant};
+use gtk4::glib::prelude::{Cast, ObjectExt, StaticType, ToVariant};
 use gtk4::graphene::Point;
-use gtk4::prelude::{ActionExt, ActionMapExt, ApplicationExt, ApplicationExtManual, BoxExt, ButtonExt, DisplayExt, DrawingAreaExt, EditableExt, EventControllerExt, FileExt, GtkApplicationExt, GtkWindowExt, IsA, NativeExt, OrientableExt, PopoverExt, SeatExt, SurfaceExt, ToggleButtonExt, WidgetExt};
+use gtk4::prelude::{AccessibleExtManual, ActionExt, ActionMapExt, ApplicationExt, ApplicationExtManual, BoxExt, ButtonExt, DisplayExt, DrawingAreaExt, EditableExt, EventControllerExt, FileExt, FileMonitorExt, GtkApplicationExt, GtkWindowExt, IsA, NativeExt, OrientableExt, PopoverExt, SeatExt, SurfaceExt, ToggleButtonExt, WidgetExt};
 use pangocairo::glib::Propagation;
 use pangocairo::pango::EllipsizeMode;
 use resvg::{tiny_skia, usvg};
 
-use crate::{Asset, I18n, package_name};
-use crate::book::{Book, Line};
+use crate::{Asset, crash, download, I18n, package_name};
+#[cfg(feature = "script")]
+use crate::script::Script;
+use crate::book::{Book, BookLoader, Line, MEDIA_LINK_PREFIX};
+use crate::color::Color32;
 use crate::common::{Position, txt_lines};
-use crate::config::{BookLoadingInfo, Configuration, ReadingInfo, SidebarPosition};
-use crate::container::{BookContent, BookName, Container, load_book, load_container};
+use crate::config::{AccessibilityPreset, AnnotationInfo, BookLoadingInfo, Configuration, FocusMode, HighlightCategory, HistoryProblem, Paper, ReadingInfo, SessionBook, SidebarPosition, ToolbarItem};
+use crate::container::{BookContent, BookName, Container, ContainerManager, format_required, is_stdin, load_book, load_container, password_required, set_format, title_for_filename};
 use crate::controller::Controller;
+use crate::gui::annotation_list::AnnotationList;
 use crate::gui::chapter_list::ChapterList;
 use crate::gui::dict::{DictionaryBook, DictionaryManager};
 pub use crate::gui::font::HtmlFonts;
 use crate::gui::font::UserFonts;
 use crate::gui::history::HistoryList;
+use crate::gui::lookup_popup::LookupPopup;
 use crate::gui::render::RenderContext;
 use crate::gui::find_list::FindList;
+use crate::gui::minimap::MiniMap;
+use crate::gui::note_list::NoteList;
+use crate::gui::trace_list::TraceList;
+use crate::gui::read_aloud::ReadAloud;
 use crate::gui::settings::Settings;
+use crate::gui::status::StatusWidgets;
+use crate::gui::toast::{ToastLevel, Toasts};
 use crate::gui::view::{GuiView, update_mouse_pointer};
+use crate::link::PositionLink;
 use crate::open::Opener;
 
 mod render;
@@ -47,6 +62,20 @@ mod font;
 mod dialogs;
 mod history;
 mod find_list;
+mod note_list;
+mod minimap;
+mod trace_list;
+mod read_aloud;
+mod status;
+mod http;
+mod translate;
+mod flashcard;
+mod stats;
+mod print;
+mod toast;
+mod lookup_popup;
+mod annotation_list;
+mod annotation_export;
 
 const MODIFIER_NONE: ModifierType = ModifierType::empty();
 const MODIFIER_CTRL_SHIFT: ModifierType = ModifierType::CONTROL_MASK.union(ModifierType::SHIFT_MASK);
@@ -56,28 +85,76 @@ const ICON_SIZE: i32 = 32;
 const INLINE_ICON_SIZE: i32 = 16;
 const MIN_FONT_SIZE: u8 = 20;
 const MAX_FONT_SIZE: u8 = 50;
+const MIN_UI_SCALE: u16 = 50;
+const MAX_UI_SCALE: u16 = 300;
+const UI_SCALE_STEP: u16 = 10;
+// gtk default of -gtk-icon-size
+const DEFAULT_ICON_SIZE: u16 = 16;
+// max height of cover in book information
+const COVER_HEIGHT: i32 = 240;
+// problems of loading book listed in book information, others copied only
+const MAX_PROBLEMS_SHOWN: usize = 10;
 const FONT_FILE_EXTENSIONS: [&str; 3] = ["ttf", "otf", "ttc"];
 const DICT_FILE_EXTENSIONS: [&str; 1] = ["ifo"];
 const SIDEBAR_CHAPTER_LIST_NAME: &str = "chapter_list";
 const SIDEBAR_DICT_NAME: &str = "dictionary_list";
 const SIDEBAR_FIND_NAME: &str = "find_list";
+const SIDEBAR_NOTE_NAME: &str = "note_list";
+const SIDEBAR_ANNOTATION_NAME: &str = "annotation_list";
+const SIDEBAR_TRACE_NAME: &str = "trace_list";
+// pointer distance to top edge for revealing toolbar in fullscreen
+const TOOLBAR_REVEAL_EDGE: f64 = 4.;
+// books remembered for reopening after their windows closed
+const MAX_RECENTLY_CLOSED: usize = 10;
 
 const OPEN_FILE_KEY: &str = "file-open";
 const OPEN_FOLDER_KEY: &str = "folder-open";
+const NEXT_UP_KEY: &str = "open-next-up";
+const REOPEN_CLOSED_KEY: &str = "reopen-closed";
+const NEXT_BOOK_KEY: &str = "next-book-in-folder";
+const PREV_BOOK_KEY: &str = "prev-book-in-folder";
 const HISTORY_KEY: &str = "history";
 const RELOAD_KEY: &str = "reload";
 const BOOK_INFO_KEY: &str = "book-info";
+const COPY_POSITION_LINK_KEY: &str = "copy-position-link";
+const STATISTICS_KEY: &str = "reading-statistics";
+const EXPORT_IMAGE_KEY: &str = "export-page-image";
+const EXPORT_PDF_KEY: &str = "export-pdf";
+const ADD_BOOKMARK_KEY: &str = "add-bookmark";
+const EXPORT_ANNOTATIONS_KEY: &str = "export-annotations";
+// resolutions of exported page image
+const IMAGE_SCALES: [f64; 3] = [1., 2., 3.];
 const SIDEBAR_KEY: &str = "sidebar";
 const THEME_KEY: &str = "dark-theme";
 const CUSTOM_COLOR_KEY: &str = "with-custom-color";
 const CUSTOM_FONT_KEY: &str = "with-custom-font";
+const TEXT_ONLY_KEY: &str = "text-only";
 const CUSTOM_STYLE_KEY: &str = "custom-style";
+const FOCUS_KEY: &str = "focus-mode";
+const BIONIC_READING_KEY: &str = "bionic-reading";
+const MINIMAP_KEY: &str = "minimap";
 const SETTINGS_KEY: &str = "settings-dialog";
 
 const COPY_CONTENT_KEY: &str = "copy-content";
 const DICT_LOOKUP_KEY: &str = "lookup-dictionary";
+const TRANSLATE_KEY: &str = "translate-selection";
+const FLASHCARD_KEY: &str = "export-flashcard";
+const HIGHLIGHT_KEY: &str = "highlight-selection";
+const ANNOTATE_KEY: &str = "annotate-selection";
 
 const README_TEXT_FILENAME: &str = "readme";
+const EINK_CSS_CLASS: &str = "eink";
+const DYSLEXIA_CSS_CLASS: &str = "dyslexia";
+// longer text in clipboard is not a word or phrase for lookup
+const MAX_WATCHED_TEXT_CHARS: usize = 64;
+// wait for file written completely before reload
+const FILE_RELOAD_DELAY_MILLIS: u64 = 500;
+const DOWNLOAD_PROGRESS_MILLIS: u64 = 500;
+const CHAPTER_INTERSTITIAL_MILLIS: u64 = 1200;
+// interval for checking night light schedule
+const NIGHT_LIGHT_CHECK_SECONDS: u32 = 60;
+// text of the bookmarked line kept for listing
+const BOOKMARK_TEXT_CHARS: usize = 80;
 
 type GuiController = Controller<RenderContext, GuiView>;
 type IconMap = HashMap<String, Texture>;
@@ -172,7 +249,7 @@ fn custom_settings(book: &dyn Book, reading: &ReadingInfo)
 	(custom_color, custom_font, custom_style)
 }
 
-fn build_ui(app: &Application, current: Option<String>,
+fn build_ui(app: &Application, current: Option<String>, password: Option<(String, bool)>,
 	cfg: Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
 	-> Result<Option<GuiContext>>
 {
@@ -197,26 +274,47 @@ fn build_ui(app: &Application, current: Option<String>,
 		}
 	};
 
-	let colors = configuration.curr_theme().clone();
-	let (i18n, icons, fonts, db, css_provider) = if let Some(gc) = gui_contexts.get(0) {
-		(gc.i18n.clone(), gc.icons.clone(), gc.fonts.clone(), gc.db.clone(), gc.css_provider.clone())
+	let colors = configuration.render_colors();
+	let (i18n, icons, fonts, db, css_provider, next_up, recently_closed) = if let Some(gc) = gui_contexts.get(0) {
+		(gc.i18n.clone(), gc.icons.clone(), gc.fonts.clone(), gc.db.clone(), gc.css_provider.clone(),
+			gc.next_up.clone(), gc.recently_closed.clone())
 	} else {
-		let i18n = I18n::new(&configuration.gui.lang)?;
+		let i18n = I18n::new(&configuration.gui.lang, &configuration.locales_dir())?;
+		// for all widgets created later, windows rebuilt when locale changed
+		Widget::set_default_direction(if i18n.is_rtl() {
+			TextDirection::Rtl
+		} else {
+			TextDirection::Ltr
+		});
 		let i18n = Rc::new(i18n);
 		let icons = load_icons();
 		let icons = Rc::new(icons);
-		let fonts = font::user_fonts(&configuration.gui.fonts)?;
+		let fonts = font::user_fonts(&configuration.gui.fonts, &configuration.gui.font_axes)?;
 		let fonts = Rc::new(fonts);
-		let db = DictionaryBook::load(&configuration.gui.dictionaries, configuration.gui.cache_dict);
-		let db = Rc::new(RefCell::new(db));
+		let db = Rc::new(RefCell::new(DictionaryBook::new()));
+		db.borrow_mut().set_group(configuration.gui.active_dictionary_group());
+		load_dictionaries(&db, &configuration, gcs);
 		let css_provider = view::init_css(&colors);
-		(i18n, icons, fonts, db, css_provider)
+		let next_up = Rc::new(RefCell::new(VecDeque::new()));
+		let recently_closed = Rc::new(RefCell::new(vec![]));
+		(i18n, icons, fonts, db, css_provider, next_up, recently_closed)
 	};
 
-	let container_manager = Default::default();
+	let mut container_manager = ContainerManager::default();
 	let (container, book, reading) = if let Some(loading) = loading {
-		let mut container = load_container(&container_manager, loading.filename())?;
+		let filename = loading.filename().to_owned();
+		let (password, remember) = match password {
+			Some((password, remember)) => (Some(password), remember),
+			None => (configuration.password(&filename)?, false),
+		};
+		if let Some(password) = &password {
+			container_manager.set_password(&filename, password.clone());
+		}
+		let mut container = load_container(&container_manager, &filename)?;
 		let (book, reading) = load_book(&container_manager, &mut container, loading)?;
+		if let (true, Some(password)) = (remember, &password) {
+			configuration.save_password(&filename, password)?;
+		}
 		(container, book, reading)
 	} else {
 		let readme = i18n.msg("readme");
@@ -225,58 +323,85 @@ fn build_ui(app: &Application, current: Option<String>,
 		(container, book, ReadingInfo::fake(README_TEXT_FILENAME))
 	};
 
+	let (letter_spacing, word_spacing) = configuration.gui.text_spacing();
 	let mut render_context = RenderContext::new(
 		colors,
 		reading.font_size,
-		reading.custom_color,
+		reading.custom_color && configuration.gui.book_colors_allowed(),
 		reading.custom_font,
 		book.leading_space(),
 		configuration.gui.strip_empty_lines,
-		configuration.gui.ignore_font_weight);
+		configuration.gui.font_weight_mode,
+		configuration.gui.font_axes.clone(),
+		letter_spacing,
+		word_spacing,
+		configuration.gui.text_scale,
+		configuration.gui.bionic_reading);
 	let view = GuiView::new(
 		"main",
 		configuration.render_han,
 		book.custom_fonts(),
 		fonts.clone(),
 		&mut render_context);
+	view.set_eink(configuration.gui.eink);
+	view.set_page_turn(configuration.gui.page_turn, configuration.gui.page_turn_millis);
+	view.set_smooth_scroll(configuration.gui.smooth_scroll);
+	let opener = Rc::new(RefCell::new(Opener::default()));
 	let (dm, dict_view, lookup_entry) = DictionaryManager::new(
 		db.clone(),
-		&configuration.gui.dictionaries,
-		configuration.gui.cache_dict,
 		configuration.gui.dict_font_size,
 		fonts.clone(),
+		&opener,
 		&i18n,
 		&icons,
 	);
+	dm.borrow_mut().set_auto_copy(configuration.gui.auto_copy);
+	dm.borrow_mut().set_groups(&configuration.gui.dictionary_groups,
+		configuration.gui.dictionary_group.as_deref());
 
 	let dark_theme = configuration.dark_theme;
+	let search_fold_width = configuration.search_fold_width;
+	let chapter_continuation = configuration.chapter_continuation;
+	let trace = configuration.trace(&reading)?;
 	drop(configuration);
 
 	let (custom_color, custom_font, custom_style) = custom_settings(book.as_ref(), &reading);
-	let controller = Controller::from_data(
+	let mut controller = Controller::from_data(
 		reading,
 		container_manager,
 		container,
 		book,
 		Box::new(view.clone()),
 		&mut render_context);
+	controller.search_fold_width = search_fold_width;
+	controller.chapter_continuation = chapter_continuation;
+	if let Some((trace, current)) = trace {
+		controller.restore_trace(trace, current);
+	}
 
 	let ctx = Rc::new(RefCell::new(render_context));
 	let ctrl = Rc::new(RefCell::new(controller));
 	let settings = Settings::new(gcs.clone());
-	let (gc, chapter_list_view, find_list_view, find_entry) = GuiContext::new(app, settings,
-		current, &cfg, &ctrl, &ctx, db, dm,
-		icons, i18n.clone(), fonts, css_provider);
+	let (gc, chapter_list_view, find_list_view, note_list_view,
+		annotation_list_view, trace_list_view, find_entry) = GuiContext::new(app, settings,
+		current, &cfg, &ctrl, &ctx, db, dm, dict_view, opener,
+		icons, i18n.clone(), fonts, css_provider, next_up, recently_closed);
 
 	// now setup ui
-	setup_sidebar(&gc, &view, &dict_view, chapter_list_view, &find_list_view);
+	setup_sidebar(&gc, &view, chapter_list_view, &find_list_view,
+		&note_list_view, &annotation_list_view, &trace_list_view);
 	setup_view(&gc, &view);
 	setup_chapter_list(&gc);
 	setup_find_list(&gc);
+	setup_note_list(&gc);
+	setup_annotation_list(&gc);
+	setup_trace_list(&gc);
+	setup_minimap(&gc);
+	setup_dictionary_group(&gc, gcs);
 
-	let (toolbar, search_box)
-		= setup_toolbar(&gc, &view, &lookup_entry, &find_entry, dark_theme,
+	setup_toolbar(&gc, &view, &lookup_entry, &find_entry, dark_theme,
 		custom_color, custom_font, custom_style);
+	let search_box = &gc.search_box;
 
 	{
 		let gc = gc.clone();
@@ -298,6 +423,21 @@ fn build_ui(app: &Application, current: Option<String>,
 		let key_event = EventControllerKey::new();
 		key_event.connect_key_pressed(move |_, key, _, modifier| {
 			let (key, modifier) = ignore_cap(key, modifier);
+			let link_forward = {
+				let link_keys = &gc.cfg().gui.link_keys;
+				if key_matches(&link_keys.next, key, modifier) {
+					Some(true)
+				} else if key_matches(&link_keys.prev, key, modifier) {
+					Some(false)
+				} else {
+					None
+				}
+			};
+			if let Some(forward) = link_forward {
+				apply(&gc, |controller, render_context|
+					controller.cycle_link(forward, render_context));
+				return Propagation::Stop;
+			}
 			match (key, modifier) {
 				(Key::space | Key::Page_Down, MODIFIER_NONE) => {
 					handle(&gc, |controller, render_context|
@@ -359,22 +499,12 @@ fn build_ui(app: &Application, current: Option<String>,
 						controller.goto_trace(true, render_context));
 					Propagation::Stop
 				}
-				(Key::Tab, MODIFIER_NONE) => {
-					apply(&gc, |controller, render_context|
-						controller.switch_link_next(render_context));
-					Propagation::Stop
-				}
-				(Key::Tab, ModifierType::SHIFT_MASK) => {
-					apply(&gc, |controller, render_context|
-						controller.switch_link_prev(render_context));
-					Propagation::Stop
-				}
 				(Key::Return, MODIFIER_NONE) => {
 					handle(&gc, |controller, render_context|
 						controller.try_goto_link(render_context));
 					Propagation::Stop
 				}
-				(Key::equal, ModifierType::CONTROL_MASK) => {
+				(Key::Up, ModifierType::CONTROL_MASK) => {
 					apply(&gc, |controller, render_context| {
 						let reading = &mut controller.reading;
 						if reading.font_size < MAX_FONT_SIZE {
@@ -388,7 +518,7 @@ fn build_ui(app: &Application, current: Option<String>,
 					});
 					Propagation::Stop
 				}
-				(Key::minus, ModifierType::CONTROL_MASK) => {
+				(Key::Down, ModifierType::CONTROL_MASK) => {
 					apply(&gc, |controller, render_context| {
 						let reading = &mut controller.reading;
 						if reading.font_size > MIN_FONT_SIZE {
@@ -402,12 +532,20 @@ fn build_ui(app: &Application, current: Option<String>,
 					});
 					Propagation::Stop
 				}
+				(Key::equal, ModifierType::CONTROL_MASK) => {
+					zoom_ui(&gc, true);
+					Propagation::Stop
+				}
+				(Key::minus, ModifierType::CONTROL_MASK) => {
+					zoom_ui(&gc, false);
+					Propagation::Stop
+				}
 				(Key::c, ModifierType::CONTROL_MASK) => {
 					copy_selection(&ctrl.borrow());
 					Propagation::Stop
 				}
 				(Key::k, ModifierType::CONTROL_MASK) => {
-					switch_stack(SIDEBAR_DICT_NAME, &gc, false);
+					switch_dictionary(&gc, false);
 					gc.dm().focus_lookup();
 					Propagation::Stop
 				}
@@ -420,20 +558,56 @@ fn build_ui(app: &Application, current: Option<String>,
 		view.add_controller(key_event);
 	}
 
-	setup_window(&gc, toolbar, view, search_box, find_entry);
-
 	{
+		// connect before setup_window, so session saved with configuration
 		let gcs = gcs.clone();
+		let cfg = cfg.clone();
 		gc.window.connect_close_request(move |win| {
-			gcs.borrow_mut().retain(|c| c.window != *win);
+			let mut gui_contexts = gcs.borrow_mut();
+			let closing = session_of(&gui_contexts);
+			gui_contexts.retain(|c| c.window != *win);
+			// the last window closed means exit, keep it in session
+			let session = if gui_contexts.is_empty() {
+				closing
+			} else {
+				session_of(&gui_contexts)
+			};
+			cfg.borrow_mut().gui.session = session;
 			Propagation::Proceed
 		});
 	}
+	setup_window(&gc, view, find_entry);
+	status::setup(&gc);
+	set_eink(&gc, gc.cfg().gui.eink);
+	set_accessibility_preset(&gc, gc.cfg().gui.accessibility_preset);
+	schedule_night_light(&gc);
+	set_paper(&gc, &gc.cfg().gui.paper);
+	gc.update_focus();
+	watch_file(&gc, gc.cfg().gui.watch_file);
+	#[cfg(feature = "script")]
+	attach_script(&gc);
+	// only the first window tells it
+	let history_problem = gc.cfg_mut().take_history_problem();
+	if let Some(problem) = history_problem {
+		alert(&gc.i18n.msg("history-problem-title"), &history_problem_msg(&gc.i18n, &problem), &gc.window);
+	}
 
 	gui_contexts.insert(gc_idx, gc.clone());
 	Ok(Some(gc))
 }
 
+/// errors of user script only reported, the book still opened
+#[cfg(feature = "script")]
+fn attach_script(gc: &GuiContext)
+{
+	match Script::load(&gc.cfg().script_file()) {
+		Ok(Some(script)) => handle(gc, |controller, render_context|
+			controller.attach_script(Rc::new(script), render_context)),
+		Ok(None) => {}
+		Err(err) => gc.error(&err.to_string()),
+	}
+}
+
 #[inline]
 fn copy_selection(ctrl: &GuiController)
 {
@@ -450,6 +624,122 @@ fn copy_to_clipboard(selected_text: &str)
 	}
 }
 
+/// set primary selection for middle-click paste, and the clipboard with auto copy
+fn publish_selection(selected_text: &str, auto_copy: bool)
+{
+	if let Some(display) = Display::default() {
+		display.primary_clipboard().set_text(selected_text);
+		if auto_copy {
+			display.clipboard().set_text(selected_text);
+		}
+	}
+}
+
+/// lookups of all windows refreshed after dictionaries loaded
+fn load_dictionaries(db: &Rc<RefCell<DictionaryBook>>, configuration: &Configuration,
+	gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let gcs = Rc::downgrade(gcs);
+	DictionaryBook::reload(db, &configuration.gui.dictionaries,
+		configuration.gui.cache_dict, move || {
+			if let Some(gcs) = gcs.upgrade() {
+				for gc in gcs.borrow().iter() {
+					gc.dm_mut().lookup_for_reload();
+				}
+			}
+		});
+}
+
+/// group of dictionaries shared by windows, the group of book language
+/// selected after loaded
+fn setup_dictionary_group(gc: &GuiContext, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let gcs = Rc::downgrade(gcs);
+	{
+		let gc = gc.clone();
+		let gcs = gcs.clone();
+		glib::idle_add_local_once(move || {
+			let Some(language) = gc.ctrl().book.language() else {
+				return;
+			};
+			let group = gc.cfg().gui.dictionary_groups
+				.iter()
+				.find(|group| group.languages
+					.iter()
+					.any(|group_language| group_language.eq_ignore_ascii_case(&language)))
+				.map(|group| group.name.clone());
+			if group.is_some() {
+				switch_dictionary_group(&gc, &gcs, group);
+			}
+		});
+	}
+	let gc2 = gc.clone();
+	gc.dm().handle_group_switch(move |group|
+		switch_dictionary_group(&gc2, &gcs, group));
+}
+
+fn switch_dictionary_group(gc: &GuiContext, gcs: &Weak<RefCell<Vec<GuiContext>>>,
+	group: Option<String>)
+{
+	{
+		// selected in other windows for syncing
+		let Ok(mut configuration) = gc.cfg.try_borrow_mut() else {
+			return;
+		};
+		if configuration.gui.dictionary_group == group {
+			return;
+		}
+		configuration.gui.dictionary_group = group.clone();
+		gc.db.borrow_mut().set_group(configuration.gui.active_dictionary_group());
+	}
+	if let Some(gcs) = gcs.upgrade() {
+		for gc in gcs.borrow().iter() {
+			let mut dm = gc.dm_mut();
+			dm.select_group(group.as_deref());
+			dm.lookup_for_reload();
+		}
+	}
+}
+
+/// lookup words copied in other applications, with the first window
+fn watch_clipboard(cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let Some(display) = Display::default() else {
+		return;
+	};
+	let cfg = cfg.clone();
+	let gcs = gcs.clone();
+	let last_text = Rc::new(RefCell::new(String::new()));
+	display.clipboard().connect_changed(move |clipboard| {
+		let enabled = cfg.try_borrow()
+			.map_or(false, |configuration| configuration.gui.watch_clipboard);
+		// ignore text copied in tbr
+		if !enabled || clipboard.is_local() {
+			return;
+		}
+		let gcs = gcs.clone();
+		let last_text = last_text.clone();
+		clipboard.read_text_async(None::<&Cancellable>, move |result| {
+			let Ok(Some(text)) = result else {
+				return;
+			};
+			let text = text.trim();
+			if text.is_empty()
+				|| text.chars().count() > MAX_WATCHED_TEXT_CHARS
+				|| *last_text.borrow() == text {
+				return;
+			}
+			last_text.replace(text.to_owned());
+			let gcs = gcs.borrow();
+			if let Some(gc) = gcs.first() {
+				switch_dictionary(gc, false);
+				gc.dm_mut().set_lookup(text.to_owned());
+				gc.window.present();
+			}
+		});
+	});
+}
+
 #[inline]
 fn lookup_selection(gc: &GuiContext)
 {
@@ -458,6 +748,38 @@ fn lookup_selection(gc: &GuiContext)
 	}
 }
 
+/// compact definition near the selection, or in dictionary panel
+/// if selection not in current page
+fn popup_lookup(gc: &GuiContext, view: &GuiView, lookup_popup: &LookupPopup)
+{
+	let Some(word) = gc.ctrl().selected().map(|text| text.trim().to_owned()) else {
+		return;
+	};
+	if word.is_empty() {
+		return;
+	}
+	let Some(rect) = view.selection_rect() else {
+		switch_dictionary(gc, false);
+		gc.dm_mut().set_lookup(word);
+		return;
+	};
+	let mut db = gc.db.borrow_mut();
+	let definition = if db.is_loading() {
+		gc.i18n.msg("dictionary-loading").to_string()
+	} else {
+		db.definition_text(&word)
+			.unwrap_or_else(|| gc.i18n.msg("dictionary-no-definition").to_string())
+	};
+	drop(db);
+	let rect = Rectangle::new(
+		rect.min.x as i32,
+		rect.min.y as i32,
+		rect.width().ceil() as i32,
+		rect.height().ceil() as i32,
+	);
+	lookup_popup.show(&word, &definition, rect);
+}
+
 #[inline]
 fn apply<F>(gc: &GuiContext, f: F)
 where
@@ -484,9 +806,19 @@ where
 	};
 	match result {
 		Ok(_) => {
-			let controller = gc.ctrl();
-			let msg = controller.status().to_string();
+			let mut controller = gc.ctrl_mut();
+			let crossed = controller.take_chapter_crossed();
+			let status = controller.status();
+			let msg = status.to_string();
+			let crossed_title = if crossed {
+				status.title.map(|title| title.to_owned())
+			} else {
+				None
+			};
 			drop(controller);
+			if let Some(title) = crossed_title {
+				gc.interstitial.show(&title);
+			}
 			gc.update(&msg, ChapterListSyncMode::ReloadIfNeeded(orig_inner_book));
 		}
 		Err(err) => gc.error(&err.to_string()),
@@ -538,19 +870,85 @@ fn setup_popup_menu(gc: &GuiContext, view: &GuiView) -> PopoverMenu
 	let action_name = format!("popup.{}", COPY_CONTENT_KEY);
 	menu.append(Some(&title), Some(&action_name));
 
-	let lookup_action = SimpleAction::new(DICT_LOOKUP_KEY, None);
+	let lookup_popup = LookupPopup::create(i18n, view);
 	{
 		let gc = gc.clone();
-		lookup_action.connect_activate(move |_, _| {
-			switch_stack(SIDEBAR_DICT_NAME, &gc, false);
-			lookup_selection(&gc);
+		lookup_popup.handle_expand(move |word| {
+			switch_dictionary(&gc, false);
+			gc.dm_mut().set_lookup(word);
 		});
 	}
+	let lookup_action = SimpleAction::new(DICT_LOOKUP_KEY, None);
+	{
+		let gc = gc.clone();
+		let view = view.clone();
+		lookup_action.connect_activate(move |_, _|
+			popup_lookup(&gc, &view, &lookup_popup));
+	}
 	action_group.add_action(&lookup_action);
 	let title = i18n.msg(DICT_LOOKUP_KEY);
 	let menu_action_name = format!("popup.{}", DICT_LOOKUP_KEY);
 	menu.append(Some(&title), Some(&menu_action_name));
 
+	let translate_action = SimpleAction::new(TRANSLATE_KEY, None);
+	{
+		let gc = gc.clone();
+		translate_action.connect_activate(move |_, _| {
+			let selected = gc.ctrl().selected().map(|text| text.to_owned());
+			if let Some(text) = selected {
+				translate::translate(&gc, text);
+			}
+		});
+	}
+	action_group.add_action(&translate_action);
+	let title = i18n.msg(TRANSLATE_KEY);
+	let menu_action_name = format!("popup.{}", TRANSLATE_KEY);
+	menu.append(Some(&title), Some(&menu_action_name));
+
+	let flashcard_action = SimpleAction::new(FLASHCARD_KEY, None);
+	{
+		let gc = gc.clone();
+		flashcard_action.connect_activate(move |_, _| flashcard::export(&gc));
+	}
+	action_group.add_action(&flashcard_action);
+	let title = i18n.msg(FLASHCARD_KEY);
+	let menu_action_name = format!("popup.{}", FLASHCARD_KEY);
+	menu.append(Some(&title), Some(&menu_action_name));
+
+	// highlight with color of the category chosen
+	let highlight_menu = Menu::new();
+	for category in HighlightCategory::ALL {
+		let action_key = category.i18n_key();
+		let highlight_action = SimpleAction::new(action_key, None);
+		{
+			let gc = gc.clone();
+			highlight_action.connect_activate(move |_, _|
+				gc.annotate_selection(None, category));
+		}
+		action_group.add_action(&highlight_action);
+		let title = i18n.msg(action_key);
+		let menu_action_name = format!("popup.{}", action_key);
+		highlight_menu.append(Some(&title), Some(&menu_action_name));
+	}
+	let title = i18n.msg(HIGHLIGHT_KEY);
+	menu.append_submenu(Some(&title), &highlight_menu);
+
+	let annotate_action = SimpleAction::new(ANNOTATE_KEY, None);
+	{
+		let gc = gc.clone();
+		annotate_action.connect_activate(move |_, _| {
+			let gc2 = gc.clone();
+			dialogs::annotation_note(&gc, &gc.window, move |note| {
+				gc2.annotate_selection(Some(note.to_owned()), HighlightCategory::default());
+				Ok(())
+			});
+		});
+	}
+	action_group.add_action(&annotate_action);
+	let title = i18n.msg(ANNOTATE_KEY);
+	let menu_action_name = format!("popup.{}", ANNOTATE_KEY);
+	menu.append(Some(&title), Some(&menu_action_name));
+
 	let pm = PopoverMenu::builder()
 		.has_arrow(false)
 		.position(PositionType::Bottom)
@@ -569,12 +967,16 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		let from = Position::new(from_line, from_offset);
 		let to = Position::new(to_line, to_offset);
 		gc.ctrl_mut().select_text(from, to, &mut gc.ctx_mut());
+		#[cfg(feature = "script")]
+		if done {
+			handle(gc, |controller, render_context|
+				controller.text_selected(render_context));
+		}
 		if done {
 			if let Some(selected_text) = gc.ctrl().selected() {
-				if let Some(current_tab) = gc.sidebar_stack.visible_child_name() {
-					if current_tab == SIDEBAR_DICT_NAME {
-						gc.dm_mut().set_lookup(selected_text.to_owned());
-					}
+				publish_selection(selected_text, gc.cfg().gui.auto_copy);
+				if dictionary_visible(gc) {
+					gc.dm_mut().set_lookup(selected_text.to_owned());
 				}
 			}
 		}
@@ -601,12 +1003,30 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 	{
 		if let Some(line) = controller.book.lines().get(line) {
 			if let Some(link) = line.link_at(link_index) {
-				opener.open_link(link.target)?;
+				if !play_media(controller, link.target, opener)? {
+					opener.open_link(link.target)?;
+				}
 			}
 		}
 		Ok(())
 	}
 
+	/// false if target is not audio or video placeholder
+	fn play_media(controller: &GuiController, target: &str, opener: &mut Opener) -> Result<bool>
+	{
+		let Some(src) = target.strip_prefix(MEDIA_LINK_PREFIX) else {
+			return Ok(false);
+		};
+		if src.starts_with("http://") || src.starts_with("https://") {
+			opener.open_link(src)?;
+		} else if let Some((path, bytes)) = controller.book.media(src) {
+			opener.open_media(&path, &bytes)?;
+		} else {
+			bail!("Media not found: {}", src);
+		}
+		Ok(true)
+	}
+
 	view.setup_gesture();
 	{
 		let gc = gc.clone();
@@ -615,6 +1035,8 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			let mut controller = gc.ctrl_mut();
 			view.resized(width, height, &mut render_context);
 			controller.redraw(&mut render_context);
+			// estimated pages changed with layout
+			status::schedule_refresh(&gc);
 		});
 	}
 
@@ -648,8 +1070,18 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			GuiView::OPEN_LINK_SIGNAL,
 			false,
 			closure_local!(move |_: GuiView, line: u64, link_index: u64| {
-				handle(&gc, |controller, render_context|
-					controller.goto_link(line as usize,	link_index as usize, render_context));
+				handle(&gc, |controller, render_context| {
+					let target = controller.book.lines()
+						.get(line as usize)
+						.and_then(|text| text.link_at(link_index as usize))
+						.map(|link| link.target.to_owned());
+					if let Some(target) = target {
+						if play_media(controller, &target, &mut gc.opener())? {
+							return Ok(());
+						}
+					}
+					controller.goto_link(line as usize, link_index as usize, render_context)
+				});
 	        }),
 		);
 	}
@@ -667,6 +1099,31 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		);
 	}
 
+	{
+		// pinch on image signal
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::ZOOM_IMAGE_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, line: u64, offset: u64| {
+				let controller = gc.ctrl();
+				let image = controller.book.lines()
+					.get(line as usize)
+					.and_then(|line| line.image_at(offset as usize))
+					.and_then(|image| {
+						let href = image.href();
+						let image_data = controller.book.image(href)?;
+						let pixbuf = load_image(image_data.bytes())?;
+						Some((href.to_owned(), pixbuf))
+					});
+				drop(controller);
+				if let Some((href, pixbuf)) = image {
+					dialogs::image_zoom(&pixbuf, &href, &gc.window);
+				}
+			}),
+		);
+	}
+
 	{
 		// open link external signal
 		let gc = gc.clone();
@@ -751,8 +1208,10 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 	}
 
 	{
-		// show title
+		// show title, or where the link goes
 		let gc = gc.clone();
+		// (chapter, line, link index) with the preview, links resolved once
+		let link_preview: RefCell<Option<((usize, usize, usize), Option<String>)>> = RefCell::new(None);
 		view.connect_closure(
 			GuiView::SHOW_TITLE_SIGNAL,
 			false,
@@ -760,14 +1219,36 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 				if show {
 					let line_no = line as usize;
 					let controller = gc.ctrl();
-					if let Some(line) = controller.book.lines().get(line_no) {
+					let link_index = if let Some(line) = controller.book.lines().get(line_no) {
 						let render_context = gc.ctx();
 						let char_style =  line.char_style_at(offset as usize, false, &render_context.colors);
-						if let Some(title) = char_style.title {
+						if let Some(title) = char_style.title() {
 							view.set_tooltip_text(Some(title));
 							return;
 						}
+						char_style.link.map(|(link_index, _)| link_index)
+					} else {
+						None
 					};
+					let chapter = controller.reading.chapter;
+					drop(controller);
+					if let Some(link_index) = link_index {
+						let key = (chapter, line_no, link_index);
+						let mut link_preview = link_preview.borrow_mut();
+						let preview = match link_preview.as_ref() {
+							Some((cached, preview)) if *cached == key => preview.clone(),
+							_ => {
+								let preview = gc.ctrl_mut().link_preview(line_no, link_index)
+									.unwrap_or(None);
+								link_preview.replace((key, preview.clone()));
+								preview
+							}
+						};
+						if let Some(preview) = preview {
+							view.set_tooltip_text(Some(&preview));
+							return;
+						}
+					}
 				}
 				view.set_tooltip_text(None);
 			}),
@@ -780,31 +1261,107 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		view.connect_closure(
 			GuiView::SCROLL_SIGNAL,
 			false,
+			closure_local!(move |_: GuiView, ticks: i32| {
+				// e-ink screens suffer from scrolling
+				let (scroll_for_page, wheel_lines) = {
+					let configuration = gc.cfg();
+					(configuration.gui.scroll_for_page || configuration.gui.eink,
+						configuration.gui.wheel_lines.max(1) as u32)
+				};
+				// a page for every tick, or lines of the tick
+				let steps = if scroll_for_page {
+					ticks.unsigned_abs()
+				} else {
+					ticks.unsigned_abs() * wheel_lines
+				};
+				if ticks > 0 {
+					handle(&gc, |controller, render_context| {
+						for _ in 0..steps {
+							if scroll_for_page {
+								controller.next_page(render_context)?;
+							} else {
+								controller.step_next(render_context)?;
+							}
+						}
+						Ok(())
+					});
+				} else {
+					handle(&gc, |controller, render_context| {
+						for _ in 0..steps {
+							if scroll_for_page {
+								controller.prev_page(render_context)?;
+							} else {
+								controller.step_prev(render_context)?;
+							}
+						}
+						Ok(())
+					});
+				}
+	        }),
+		);
+	}
+
+	{
+		// tap zone signal
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::PAGE_SIGNAL,
+			false,
 			closure_local!(move |_: GuiView, delta: i32| {
 				if delta > 0 {
-					if gc.cfg().gui.scroll_for_page{
-						handle(&gc, |controller, render_context|
-							controller.next_page(render_context));
-					} else {
-						handle(&gc, |controller, render_context|
-							controller.step_next(render_context));
-					}
+					handle(&gc, |controller, render_context|
+						controller.next_page(render_context));
 				} else {
-					if gc.cfg().gui.scroll_for_page{
-						handle(&gc, |controller, render_context|
-							controller.prev_page(render_context));
-					} else {
-						handle(&gc, |controller, render_context|
-							controller.step_prev(render_context));
-					}
+					handle(&gc, |controller, render_context|
+						controller.prev_page(render_context));
 				}
-	        }),
+			}),
+		);
+	}
+
+	{
+		// swipe signal
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::CHAPTER_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, forward: bool| {
+				handle(&gc, |controller, render_context|
+					controller.switch_chapter(forward, render_context));
+			}),
+		);
+	}
+
+	{
+		// pinch zoom signal
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::ZOOM_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, scale: f64| {
+				apply(&gc, |controller, render_context| {
+					let reading = &mut controller.reading;
+					let font_size = (reading.font_size as f64 * scale)
+						.round()
+						.clamp(MIN_FONT_SIZE as f64, MAX_FONT_SIZE as f64) as u8;
+					if font_size != reading.font_size {
+						reading.font_size = font_size;
+						controller.render.set_font_size(
+							reading.font_size,
+							controller.book.custom_fonts(),
+							render_context);
+						controller.redraw(render_context);
+					}
+				});
+			}),
 		);
 	}
 }
 
-fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
-	chapter_list_view: gtk4::Box, find_list_view: &gtk4::Box)
+fn setup_sidebar(gc: &GuiContext, view: &GuiView,
+	chapter_list_view: gtk4::Box, find_list_view: &gtk4::Box,
+	note_list_view: &gtk4::Box, annotation_list_view: &gtk4::Box,
+	trace_list_view: &gtk4::Box)
 {
 	let i18n = &gc.i18n;
 	let stack = &gc.sidebar_stack;
@@ -812,12 +1369,31 @@ fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
 		&chapter_list_view,
 		Some(SIDEBAR_CHAPTER_LIST_NAME), &i18n.msg("tab-chapter"));
 	stack.add_titled(
-		dict_view,
+		&gc.dict_view,
 		Some(SIDEBAR_DICT_NAME), &i18n.msg("tab-dictionary"));
 	stack.add_titled(
 		find_list_view,
 		Some(SIDEBAR_FIND_NAME), &i18n.msg("tab-find"));
+	stack.add_titled(
+		note_list_view,
+		Some(SIDEBAR_NOTE_NAME), &i18n.msg("tab-notes"));
+	stack.add_titled(
+		annotation_list_view,
+		Some(SIDEBAR_ANNOTATION_NAME), &i18n.msg("tab-annotations"));
+	stack.add_titled(
+		trace_list_view,
+		Some(SIDEBAR_TRACE_NAME), &i18n.msg("tab-trace"));
 	stack.set_visible_child(&chapter_list_view);
+	{
+		let gc = gc.clone();
+		stack.connect_visible_child_name_notify(move |stack| {
+			match stack.visible_child_name() {
+				Some(name) if name == SIDEBAR_NOTE_NAME => gc.note_list.sync_notes(false),
+				Some(name) if name == SIDEBAR_TRACE_NAME => gc.sync_trace_list(),
+				_ => {}
+			}
+		});
+	}
 
 	let sidebar_tab_switch = gtk4::StackSwitcher::builder()
 		.stack(&stack)
@@ -826,24 +1402,59 @@ fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
 	sidebar.append(&sidebar_tab_switch);
 	sidebar.append(&gc.sidebar_stack);
 
-	let sidebar_position = &gc.cfg().gui.sidebar_position;
-	set_sidebar_position(gc, sidebar_position);
-
 	let paned = &gc.paned;
 	paned.set_start_child(Some(&sidebar));
-	paned.set_end_child(Some(view));
+	// mini-map at the right edge of the view
+	let view_box = gtk4::Box::new(Orientation::Horizontal, 0);
+	view.set_hexpand(true);
+	view_box.append(view);
+	view_box.append(gc.minimap.widget());
+	paned.set_end_child(Some(&view_box));
 	paned.set_position(0);
+	let sidebar_position = &gc.cfg().gui.sidebar_position;
+	set_sidebar_position(gc, sidebar_position);
 
-	let gc = gc.clone();
-	paned.connect_position_notify(move |paned| {
-		let position = paned.position();
-		if position > 0 {
-			sidebar_updated(
-				&mut gc.cfg_mut(),
-				&mut gc.dm_mut(),
-				position)
-		}
-	});
+	{
+		let gc = gc.clone();
+		paned.connect_position_notify(move |_| {
+			if gc.sidebar_moving.get() {
+				return;
+			}
+			let size = gc.sidebar_size();
+			if size > 0 {
+				sidebar_updated(
+					&mut gc.cfg_mut(),
+					&mut gc.dm_mut(),
+					size)
+			}
+		});
+	}
+
+	// keep the dock size when allocated, the end child not resized with window
+	let dict_paned = &gc.dict_paned;
+	{
+		let gc = gc.clone();
+		dict_paned.connect_max_position_notify(move |dict_paned| {
+			if gc.dict_dock.is_visible() {
+				let size = gc.cfg().gui.dict_dock_size as i32;
+				dict_paned.set_position(dict_paned.max_position() - size);
+			}
+		});
+	}
+	{
+		let gc = gc.clone();
+		dict_paned.connect_position_notify(move |dict_paned| {
+			if !gc.dict_dock.is_visible() {
+				return;
+			}
+			let size = dict_paned.max_position() - dict_paned.position();
+			if size > 0 {
+				gc.cfg_mut().gui.dict_dock_size = size as u32;
+				gc.dm_mut().resize(size, None);
+			}
+		});
+	}
+	set_dict_docked(gc, gc.cfg().gui.dict_docked);
 }
 
 fn sidebar_updated(configuration: &mut Configuration,
@@ -851,37 +1462,133 @@ fn sidebar_updated(configuration: &mut Configuration,
 	position: i32)
 {
 	configuration.gui.sidebar_size = position as u32;
-	dictionary_manager.resize(position, None);
+	if !configuration.gui.dict_docked {
+		dictionary_manager.resize(position, None);
+	}
 }
 
-#[inline]
-fn set_sidebar_position(gc: &GuiContext, position: &SidebarPosition)
+/// move dictionary between sidebar tab and right dock
+fn set_dict_docked(gc: &GuiContext, docked: bool)
 {
-	gc.paned.set_orientation(position.paned_orientation());
+	let dict_view = &gc.dict_view;
+	let dict_dock = &gc.dict_dock;
+	let stack = &gc.sidebar_stack;
+	let in_dock = dict_view.parent().map_or(false, |parent| parent == *dict_dock);
+	if docked == in_dock {
+		return;
+	}
+	if docked {
+		stack.remove(dict_view);
+		dict_dock.append(dict_view);
+		dict_dock.set_visible(true);
+		let size = gc.cfg().gui.dict_dock_size as i32;
+		let dict_paned = &gc.dict_paned;
+		if dict_paned.max_position() > size {
+			dict_paned.set_position(dict_paned.max_position() - size);
+		}
+	} else {
+		dict_dock.remove(dict_view);
+		dict_dock.set_visible(false);
+		stack.add_titled(
+			dict_view,
+			Some(SIDEBAR_DICT_NAME), &gc.i18n.msg("tab-dictionary"));
+		let size = gc.sidebar_size();
+		if size > 0 {
+			gc.dm_mut().resize(size, None);
+		}
+	}
 }
 
-fn setup_chapter_list(gc1: &GuiContext)
+/// show dictionary in dock or sidebar, false if toggled off
+fn switch_dictionary(gc: &GuiContext, toggle: bool) -> bool
 {
-	{
-		let gc = gc1.clone();
-		gc1.chapter_list.handle_item_click(move |is_book, index| {
-			let mut controller = gc.ctrl_mut();
-			let mut render_context = gc.ctx_mut();
-			if is_book {
-				let (error, msg) = match controller.switch_book(index, &mut render_context) {
-					Ok(msg) => {
-						gc.find_list.set_inner_book(index);
+	if gc.cfg().gui.dict_docked {
+		let dict_dock = &gc.dict_dock;
+		let visible = !(toggle && dict_dock.is_visible());
+		dict_dock.set_visible(visible);
+		if !visible {
+			gc.ctrl().render.grab_focus();
+		}
+		visible
+	} else {
+		switch_stack(SIDEBAR_DICT_NAME, gc, toggle)
+	}
+}
+
+#[inline]
+fn dictionary_visible(gc: &GuiContext) -> bool
+{
+	if gc.cfg().gui.dict_docked {
+		gc.dict_dock.is_visible()
+	} else {
+		gc.sidebar_stack.visible_child_name()
+			.map_or(false, |current_tab| current_tab == SIDEBAR_DICT_NAME)
+	}
+}
+
+/// sidebar docked at end moved to the other side of paned, size kept
+fn set_sidebar_position(gc: &GuiContext, position: &SidebarPosition)
+{
+	let paned = &gc.paned;
+	paned.set_orientation(position.paned_orientation());
+	let at_end = position.at_end();
+	if gc.sidebar_at_end.get() == at_end {
+		return;
+	}
+	gc.sidebar_moving.replace(true);
+	let size = gc.sidebar_size();
+	let start = paned.start_child();
+	let end = paned.end_child();
+	paned.set_start_child(None::<&Widget>);
+	paned.set_end_child(None::<&Widget>);
+	paned.set_start_child(end.as_ref());
+	paned.set_end_child(start.as_ref());
+	// the view resized with window, not the sidebar
+	paned.set_resize_start_child(true);
+	paned.set_resize_end_child(!at_end);
+	gc.sidebar_at_end.replace(at_end);
+	gc.set_sidebar_size(size);
+	gc.sidebar_moving.replace(false);
+}
+
+fn setup_chapter_list(gc1: &GuiContext)
+{
+	{
+		let gc = gc1.clone();
+		gc1.chapter_list.handle_item_click(move |is_book, index| {
+			let mut controller = gc.ctrl_mut();
+			let mut render_context = gc.ctx_mut();
+			if is_book {
+				let (error, msg) = match controller.switch_book(index, &mut render_context) {
+					Ok(msg) => {
+						gc.find_list.set_inner_book(index);
 						(false, msg)
 					}
 					Err(e) => (true, e.to_string())
 				};
 				update_title(&gc.window, &controller);
 				update_status(error, &msg, &gc.status_bar);
+				drop(controller);
+				drop(render_context);
+				gc.sync_annotations(false);
 			} else if let Some(msg) = controller.goto_toc(index, &mut render_context) {
 				update_status(false, &msg, &gc.status_bar);
 			}
 		});
 	}
+	{
+		let gc = gc1.clone();
+		gc1.chapter_list.handle_landmark_click(move |index| {
+			let msg = {
+				let mut controller = gc.ctrl_mut();
+				let mut render_context = gc.ctx_mut();
+				controller.goto_landmark(index, &mut render_context)
+			};
+			if let Some(msg) = msg {
+				gc.update(&msg, ChapterListSyncMode::NoReload);
+			}
+		});
+	}
 	{
 		let gc = gc1.clone();
 		gc1.chapter_list.handle_cancel(move |empty| {
@@ -897,6 +1604,11 @@ fn setup_chapter_list(gc1: &GuiContext)
 
 fn setup_find_list(gc1: &GuiContext)
 {
+	{
+		let gc = gc1.clone();
+		gc1.find_list.handle_finished(move |count| gc.notify(
+			&gc.i18n.args_msg("find-finished", vec![("count", count)])));
+	}
 	let gc = gc1.clone();
 	gc1.find_list.set_callback(move |found_entry| {
 		let mut controller = gc.ctrl_mut();
@@ -917,11 +1629,82 @@ fn setup_find_list(gc1: &GuiContext)
 	})
 }
 
+fn setup_note_list(gc1: &GuiContext)
+{
+	let gc = gc1.clone();
+	gc1.note_list.handle_position_click(move |range| {
+		let mut controller = gc.ctrl_mut();
+		let inner_book = controller.reading.inner_book;
+		let chapter = controller.reading.chapter;
+		let start = &range.start;
+		let end = if range.end.line == start.line {
+			range.end.offset
+		} else {
+			controller.book.lines().get(start.line).map_or(start.offset, Line::len)
+		};
+		let result = controller.goto(inner_book, chapter, start.line,
+			start.offset, Some(start.offset..end), &mut gc.ctx_mut());
+		drop(controller);
+		match result {
+			Ok(msg) => gc.message(&msg),
+			Err(err) => gc.error(&err.to_string()),
+		}
+	});
+}
+
+fn setup_annotation_list(gc1: &GuiContext)
+{
+	{
+		let gc = gc1.clone();
+		gc1.annotation_list.handle_annotation_click(move |annotation| {
+			let mut controller = gc.ctrl_mut();
+			let inner_book = controller.reading.inner_book;
+			let start = &annotation.range.start;
+			let result = controller.goto(inner_book, annotation.chapter, start.line,
+				start.offset, None, &mut gc.ctx_mut());
+			drop(controller);
+			match result {
+				Ok(msg) => gc.update(&msg, ChapterListSyncMode::NoReload),
+				Err(err) => gc.error(&err.to_string()),
+			}
+		});
+	}
+	{
+		let gc = gc1.clone();
+		gc1.annotation_list.handle_annotation_remove(move |id| {
+			if let Err(err) = gc.cfg().remove_annotation(id) {
+				gc.error(&err.to_string());
+				return;
+			}
+			gc.sync_annotations(true);
+		});
+	}
+	gc1.sync_annotations(false);
+}
+
+fn setup_minimap(gc1: &GuiContext)
+{
+	let gc = gc1.clone();
+	gc1.minimap.handle_line_click(move |line| {
+		handle(&gc, |controller, render_context|
+			controller.goto_line(line + 1, render_context));
+	});
+	gc1.minimap.sync(&gc1.ctx().colors);
+}
+
+fn setup_trace_list(gc1: &GuiContext)
+{
+	let gc = gc1.clone();
+	gc1.trace_list.handle_index_click(move |index| {
+		handle(&gc, |controller, render_context|
+			controller.goto_trace_at(index, render_context));
+	});
+}
+
 fn switch_stack(tab_name: &str, gc: &GuiContext, toggle: bool) -> bool
 {
-	let paned = &gc.paned;
 	let stack = &gc.sidebar_stack;
-	if paned.position() == 0 {
+	if gc.sidebar_size() == 0 {
 		stack.set_visible_child_name(tab_name);
 		gc.toggle_sidebar();
 		true
@@ -944,16 +1727,70 @@ fn switch_stack(tab_name: &str, gc: &GuiContext, toggle: bool) -> bool
 }
 
 #[inline]
-fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
-	search_box: SearchEntry, find_entry: SearchEntry)
+fn setup_window(gc: &GuiContext, view: GuiView, find_entry: SearchEntry)
 {
 	let header_bar = HeaderBar::new();
 	header_bar.set_height_request(32);
-	header_bar.pack_start(&toolbar);
-	header_bar.pack_end(&gc.status_bar);
+	header_bar.pack_start(&gc.toolbar);
+	header_bar.pack_end(gc.status_widgets.container());
 	let window = &gc.window;
 	window.set_titlebar(Some(&header_bar));
-	window.set_child(Some(&gc.paned));
+
+	// titlebar hidden in fullscreen, toolbar slides in from top edge
+	let reveal_bar = gtk4::Box::builder()
+		.css_classes(vec!["toolbar", "background"])
+		.build();
+	let revealer = Revealer::builder()
+		.transition_type(RevealerTransitionType::SlideDown)
+		.valign(Align::Start)
+		.child(&reveal_bar)
+		.build();
+	let overlay = Overlay::new();
+	overlay.set_child(Some(&gc.dict_paned));
+	overlay.add_overlay(&revealer);
+	overlay.add_overlay(&gc.interstitial.revealer);
+	overlay.add_overlay(gc.toasts.widget());
+	window.set_child(Some(&overlay));
+	{
+		let gc = gc.clone();
+		let revealer = revealer.clone();
+		window.connect_fullscreened_notify(move |window| {
+			let toolbar = &gc.toolbar;
+			let status_bar = gc.status_widgets.container();
+			if window.is_fullscreened() {
+				header_bar.remove(toolbar);
+				header_bar.remove(status_bar);
+				reveal_bar.append(toolbar);
+				reveal_bar.append(status_bar);
+			} else {
+				revealer.set_reveal_child(false);
+				reveal_bar.remove(toolbar);
+				reveal_bar.remove(status_bar);
+				header_bar.pack_start(toolbar);
+				header_bar.pack_end(status_bar);
+			}
+		});
+	}
+	{
+		let motion = EventControllerMotion::new();
+		let window2 = window.clone();
+		let revealer = revealer.clone();
+		motion.connect_motion(move |_, _, y| {
+			if !window2.is_fullscreened() {
+				return;
+			}
+			let reveal = if revealer.reveals_child() {
+				y <= revealer.height() as f64
+			} else {
+				y <= TOOLBAR_REVEAL_EDGE
+			};
+			if reveal != revealer.reveals_child() {
+				revealer.set_reveal_child(reveal);
+			}
+		});
+		window.add_controller(motion);
+	}
+	let search_box = gc.search_box.clone();
 	window.set_default_widget(Some(&view));
 	window.set_focus(Some(&view));
 	window.add_css_class("main-window");
@@ -978,6 +1815,7 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 		let gc = gc.clone();
 		window_key_event.connect_key_pressed(move |_, key, _, modifier| {
 			let (key, modifier) = ignore_cap(key, modifier);
+			crash::track_action(&gtk4::accelerator_name(key, modifier));
 			match (key, modifier) {
 				(Key::Control_L, MODIFIER_NONE) => {
 					let view = &gc.ctrl().render;
@@ -995,7 +1833,7 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					Propagation::Stop
 				}
 				(Key::d, MODIFIER_NONE) => {
-					if switch_stack(SIDEBAR_DICT_NAME, &gc, true) {
+					if switch_dictionary(&gc, true) {
 						lookup_selection(&gc);
 					}
 					Propagation::Stop
@@ -1010,6 +1848,9 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					Propagation::Stop
 				}
 				(Key::slash, MODIFIER_NONE) | (Key::f, ModifierType::CONTROL_MASK) => {
+					if gc.window.is_fullscreened() {
+						revealer.set_reveal_child(true);
+					}
 					search_box.grab_focus();
 					if let Some(pattern) = gc.ctrl().selected() {
 						search_box.set_text(pattern)
@@ -1021,8 +1862,16 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					gc.goto();
 					Propagation::Stop
 				}
+				(Key::G, MODIFIER_CTRL_SHIFT) => {
+					gc.goto_page();
+					Propagation::Stop
+				}
+				(Key::K, MODIFIER_CTRL_SHIFT) => {
+					gc.switch_inner_book();
+					Propagation::Stop
+				}
 				(Key::Escape, MODIFIER_NONE) => {
-					if gc.paned.position() != 0 {
+					if gc.sidebar_size() != 0 {
 						gc.toggle_sidebar();
 						Propagation::Stop
 					} else {
@@ -1045,10 +1894,22 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					gc.open_folder_dialog();
 					Propagation::Stop
 				}
+				(Key::T, MODIFIER_CTRL_SHIFT) => {
+					gc.reopen_closed();
+					Propagation::Stop
+				}
 				(Key::h, MODIFIER_NONE) => {
 					gc.show_history();
 					Propagation::Stop
 				}
+				(Key::bracketleft, MODIFIER_NONE) => {
+					gc.open_sibling(false);
+					Propagation::Stop
+				}
+				(Key::bracketright, MODIFIER_NONE) => {
+					gc.open_sibling(true);
+					Propagation::Stop
+				}
 				(Key::t, MODIFIER_NONE) => {
 					gc.switch_theme();
 					Propagation::Stop
@@ -1061,10 +1922,18 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					gc.custom_font_action.activate(None);
 					Propagation::Stop
 				}
+				(Key::I, ModifierType::SHIFT_MASK) => {
+					gc.text_only_action.activate(None);
+					Propagation::Stop
+				}
 				(Key::S, ModifierType::SHIFT_MASK) => {
 					gc.custom_style_dialog();
 					Propagation::Stop
 				}
+				(Key::f, MODIFIER_NONE) => {
+					gc.toggle_focus();
+					Propagation::Stop
+				}
 				(Key::s, ModifierType::CONTROL_MASK) => {
 					gc.show_settings();
 					Propagation::Stop
@@ -1098,13 +1967,19 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 		window.connect_close_request(move |_| {
 			let mut controller = gc.ctrl_mut();
 			if controller.reading.filename != README_TEXT_FILENAME {
+				gc.track_closed(&controller.reading.filename);
 				let configuration = gc.cfg_mut();
 				if let Err(e) = configuration.save_reading(&mut controller.reading) {
 					eprintln!("Failed save reading info: {}", e.to_string());
 				}
+				let (trace, current) = controller.trace();
+				if let Err(e) = configuration.save_trace(&controller.reading, trace, current) {
+					eprintln!("Failed save trace: {}", e.to_string());
+				}
 			}
 			let mut configuration = gc.cfg_mut();
 			configuration.gui.dict_font_size = gc.dm.borrow().font_size();
+			save_window_geometry(&mut configuration, &gc.window);
 			if let Err(e) = configuration.save() {
 				eprintln!("Failed save configuration: {}", e.to_string());
 			}
@@ -1115,12 +1990,112 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 	window.present();
 }
 
+/// monitor the reading file, reload after changes done
+fn watch_file(gc: &GuiContext, watch: bool)
+{
+	if let Some(monitor) = gc.file_monitor.borrow_mut().take() {
+		monitor.cancel();
+	}
+	if !watch {
+		return;
+	}
+	let filename = gc.ctrl().reading.filename.clone();
+	if filename == README_TEXT_FILENAME || is_stdin(&filename) {
+		return;
+	}
+	let monitor = match File::for_path(&filename)
+		.monitor(FileMonitorFlags::NONE, None::<&Cancellable>) {
+		Ok(monitor) => monitor,
+		Err(err) => {
+			eprintln!("Failed watch file {}: {}", filename, err.to_string());
+			return;
+		}
+	};
+	let weak = Rc::downgrade(&gc.inner);
+	monitor.connect_changed(move |_, _, _, event| {
+		if !matches!(event, FileMonitorEvent::ChangesDoneHint | FileMonitorEvent::Created) {
+			return;
+		}
+		let Some(inner) = weak.upgrade() else {
+			return;
+		};
+		let gc = GuiContext { inner };
+		// editors may write file in several steps
+		if gc.reload_pending.replace(true) {
+			return;
+		}
+		let filename = filename.clone();
+		glib::timeout_add_local_once(Duration::from_millis(FILE_RELOAD_DELAY_MILLIS), move || {
+			gc.reload_pending.set(false);
+			if gc.ctrl().reading.filename != filename
+				|| !PathBuf::from(&filename).exists() {
+				return;
+			}
+			if gc.reload_book() {
+				gc.toast(&gc.i18n.msg("file-changed-reloaded"));
+			}
+		});
+	});
+	gc.file_monitor.replace(Some(monitor));
+}
+
+/// monitor the config file, themes, fonts and keys applied after edited
+fn watch_config(app: &Application, cfg: &Rc<RefCell<Configuration>>,
+	gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let config_file = cfg.borrow().config_file().clone();
+	let monitor = match File::for_path(&config_file)
+		.monitor(FileMonitorFlags::NONE, None::<&Cancellable>) {
+		Ok(monitor) => monitor,
+		Err(err) => {
+			eprintln!("Failed watch config {}: {}", config_file.display(), err.to_string());
+			return;
+		}
+	};
+	let gcs = gcs.clone();
+	let reload_pending = Rc::new(Cell::new(false));
+	monitor.connect_changed(move |_, _, _, event| {
+		if !matches!(event, FileMonitorEvent::ChangesDoneHint | FileMonitorEvent::Created) {
+			return;
+		}
+		// editors may write file in several steps
+		if reload_pending.replace(true) {
+			return;
+		}
+		let gcs = gcs.clone();
+		let reload_pending = reload_pending.clone();
+		glib::timeout_add_local_once(Duration::from_millis(FILE_RELOAD_DELAY_MILLIS), move || {
+			reload_pending.set(false);
+			settings::reload_config(&gcs);
+		});
+	});
+	// monitor kept until quit
+	app.connect_shutdown(move |_| {
+		monitor.cancel();
+	});
+}
+
+/// default size is the unmaximized size in gtk4, keep it when maximized
+fn save_window_geometry(configuration: &mut Configuration, window: &ApplicationWindow)
+{
+	let geometry = &mut configuration.gui.window;
+	geometry.maximized = window.is_maximized();
+	if !geometry.maximized && !window.is_fullscreened() {
+		let (width, height) = window.default_size();
+		if width > 0 && height > 0 {
+			geometry.width = width;
+			geometry.height = height;
+		}
+	}
+}
+
+/// switch render type of current book only
 fn switch_render(gc: &GuiContext)
 {
-	let mut configuration = gc.cfg_mut();
-	let render_han = !configuration.render_han;
-	configuration.render_han = render_han;
+	let default_render_han = gc.cfg().render_han;
 	let mut controller = gc.ctrl_mut();
+	let render_han = !controller.reading.render_han.unwrap_or(default_render_han);
+	controller.reading.render_han = Some(render_han);
 	let mut render_context = gc.ctx_mut();
 	controller.render.reload_render(render_han, &mut render_context);
 	controller.redraw(&mut render_context);
@@ -1130,21 +2105,14 @@ fn switch_render(gc: &GuiContext)
 fn setup_toolbar(gc: &GuiContext, view: &GuiView, lookup_entry: &SearchEntry,
 	find_entry: &SearchEntry,
 	dark_theme: bool, custom_color: Option<bool>, custom_font: Option<bool>,
-	custom_style: Option<Option<String>>) -> (gtk4::Box, SearchEntry)
+	custom_style: Option<Option<String>>)
 {
-	let i18n = &gc.i18n;
-
-	let toolbar = gtk4::Box::builder()
-		.css_classes(vec!["toolbar"])
-		.build();
-
 	let sidebar_button = &gc.sidebar_btn;
 	{
 		let gc = gc.clone();
 		sidebar_button.connect_clicked(move |_| {
 			gc.toggle_sidebar();
 		});
-		toolbar.append(sidebar_button);
 	}
 
 	{
@@ -1163,14 +2131,37 @@ fn setup_toolbar(gc: &GuiContext, view: &GuiView, lookup_entry: &SearchEntry,
 
 	// add file drop support
 	{
-		let drop_target = DropTarget::new(File::static_type(), DragAction::COPY);
+		let drop_target = DropTarget::new(glib::Type::INVALID, DragAction::COPY);
+		drop_target.set_types(&[FileList::static_type(), String::static_type()]);
 		let gc = gc.clone();
 		drop_target.connect_drop(move |_, value, _, _| {
-			if let Ok(file) = value.get::<File>() {
-				if let Some(path) = file.path() {
-					gc.open_file(&path);
-					return true;
+			if let Ok(files) = value.get::<FileList>() {
+				let mut paths = vec![];
+				for file in files.files() {
+					match file.path() {
+						Some(path) => paths.push(path),
+						None => {
+							let uri = file.uri();
+							if download::is_url(&uri) {
+								gc.open_url(uri.to_string());
+							}
+						}
+					}
+				}
+				if !paths.is_empty() {
+					gc.open_dropped(paths);
+				}
+				return true;
+			}
+			if let Ok(text) = value.get::<String>() {
+				let mut dropped = false;
+				for url in text.lines().map(|line| line.trim()) {
+					if download::is_url(url) {
+						gc.open_url(url.to_owned());
+						dropped = true;
+					}
 				}
+				return dropped;
 			}
 			false
 		});
@@ -1178,16 +2169,45 @@ fn setup_toolbar(gc: &GuiContext, view: &GuiView, lookup_entry: &SearchEntry,
 	}
 
 	setup_main_menu(gc, view, dark_theme, custom_color, custom_font, custom_style);
-	toolbar.append(&gc.menu_btn);
 
-	let search_box = SearchEntry::builder()
-		.placeholder_text(i18n.msg("search-hint"))
-		.activates_default(true)
-		.enable_undo(true)
-		.build();
-	toolbar.append(&search_box);
+	{
+		let read_aloud_button = gc.read_aloud.button();
+		let gc = gc.clone();
+		read_aloud_button.connect_clicked(move |_| read_aloud::toggle(&gc));
+	}
+	{
+		let rate_dropdown = gc.read_aloud.rate_dropdown();
+		let gc = gc.clone();
+		rate_dropdown.connect_selected_notify(move |_| read_aloud::rate_changed(&gc));
+	}
+
+	layout_toolbar(gc, &gc.cfg().gui.toolbar);
+}
+
+/// arrange toolbar items in configured order, menu always kept for settings
+fn layout_toolbar(gc: &GuiContext, items: &Vec<ToolbarItem>)
+{
+	let toolbar = &gc.toolbar;
+	while let Some(child) = toolbar.first_child() {
+		toolbar.remove(&child);
+	}
+	for item in items {
+		toolbar.append(&toolbar_widget(gc, item));
+	}
+	if !items.contains(&ToolbarItem::Menu) {
+		toolbar.append(&gc.menu_btn);
+	}
+}
 
-	(toolbar, search_box)
+#[inline]
+fn toolbar_widget(gc: &GuiContext, item: &ToolbarItem) -> Widget
+{
+	match item {
+		ToolbarItem::Sidebar => gc.sidebar_btn.clone().upcast(),
+		ToolbarItem::Menu => gc.menu_btn.clone().upcast(),
+		ToolbarItem::ReadAloud => gc.read_aloud.widget().clone().upcast(),
+		ToolbarItem::Search => gc.search_box.clone().upcast(),
+	}
 }
 
 fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
@@ -1208,7 +2228,11 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 	where
 		F: Fn(&SimpleAction, Option<&Variant>) + 'static,
 	{
-		action.connect_activate(callback);
+		let name = key.to_owned();
+		action.connect_activate(move |action, parameter| {
+			crash::track_action(&name);
+			callback(action, parameter);
+		});
 		let title = i18n.msg(key);
 		let action_name = format!("main.{}", key);
 		let menu_item = MenuItem::new(Some(&title), Some(&action_name));
@@ -1228,7 +2252,11 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			action.set_state(&false.to_variant());
 			action.set_enabled(false);
 		}
-		action.connect_activate(callback);
+		let name = key.to_owned();
+		action.connect_activate(move |action, parameter| {
+			crash::track_action(&name);
+			callback(action, parameter);
+		});
 		let title = i18n.msg(key);
 		let action_name = format!("main.{}", key);
 		let menu_item = MenuItem::new(Some(&title), Some(&action_name));
@@ -1262,6 +2290,30 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			NEXT_UP_KEY, move |_, _| gc.open_next_up());
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			REOPEN_CLOSED_KEY, move |_, _| gc.reopen_closed());
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			NEXT_BOOK_KEY, move |_, _| gc.open_sibling(true));
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			PREV_BOOK_KEY, move |_, _| gc.open_sibling(false));
+	}
+
 	gc.history_list.setup(button, &gc);
 	{
 		let gc = gc.clone();
@@ -1291,6 +2343,46 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			COPY_POSITION_LINK_KEY, move |_, _| {
+				let link = PositionLink::of(&gc.ctrl().reading);
+				copy_to_clipboard(&link.to_string());
+				gc.toast(&gc.i18n.msg("position-link-copied"));
+			});
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			EXPORT_IMAGE_KEY, move |_, _| gc.export_page_image());
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			EXPORT_PDF_KEY, move |_, _| print::export_pdf(&gc));
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			STATISTICS_KEY, move |_, _| stats::dialog(&gc));
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			ADD_BOOKMARK_KEY, move |_, _| gc.add_bookmark());
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			EXPORT_ANNOTATIONS_KEY, move |_, _| annotation_export::export(&gc));
+	}
+
 	{
 		let gc = gc.clone();
 		create_action(&section, &action_group, i18n,
@@ -1327,6 +2419,16 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	{
+		let action = &gc.text_only_action;
+		let text_only = gc.ctrl().reading.text_only;
+		let gc = gc.clone();
+		append_toggle_action(&section, &action_group, i18n,
+			TEXT_ONLY_KEY, action, Some(text_only), move |_, _| {
+				gc.toggle_text_only();
+			});
+	}
+
 	{
 		let action = &gc.custom_style_action;
 		if custom_style.is_none() {
@@ -1339,6 +2441,36 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	{
+		let action = &gc.focus_action;
+		let focus = gc.cfg().gui.focus;
+		let gc = gc.clone();
+		append_toggle_action(&section, &action_group, i18n,
+			FOCUS_KEY, action, Some(focus), move |_, _| {
+				gc.toggle_focus();
+			});
+	}
+
+	{
+		let action = &gc.bionic_reading_action;
+		let bionic_reading = gc.cfg().gui.bionic_reading;
+		let gc = gc.clone();
+		append_toggle_action(&section, &action_group, i18n,
+			BIONIC_READING_KEY, action, Some(bionic_reading), move |_, _| {
+				gc.toggle_bionic_reading();
+			});
+	}
+
+	{
+		let action = &gc.minimap_action;
+		let minimap = gc.cfg().gui.minimap;
+		let gc = gc.clone();
+		append_toggle_action(&section, &action_group, i18n,
+			MINIMAP_KEY, action, Some(minimap), move |_, _| {
+				gc.toggle_minimap();
+			});
+	}
+
 	let pm = PopoverMenu::builder()
 		.has_arrow(false)
 		.position(PositionType::Bottom)
@@ -1376,25 +2508,149 @@ fn create_action(name: &str) -> SimpleAction
 	SimpleAction::new(name, None)
 }
 
-#[inline]
-fn create_toggle_action(name: &str) -> SimpleAction
-{
-	SimpleAction::new_stateful(name, None, &false.to_variant())
+thread_local! {
+	// css of ui scale, for all windows and dialogs
+	static UI_SCALE_CSS: CssProvider = {
+		let css_provider = CssProvider::new();
+		gtk4::style_context_add_provider_for_display(
+			&Display::default().expect("Could not connect to a display."),
+			&css_provider,
+			gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+		);
+		css_provider
+	};
 }
 
-fn create_toggle_button(active: bool, name: &str, i18n_key: &str,
+/// scale text and icons of all windows, the book view has its own font size
+fn set_ui_scale(scale: u16)
+{
+	let css = if scale == 100 {
+		String::new()
+	} else {
+		format!("window {{font-size: {}%;}} image {{-gtk-icon-size: {}px;}}",
+			scale, DEFAULT_ICON_SIZE * scale / 100)
+	};
+	UI_SCALE_CSS.with(|css_provider| css_provider.load_from_string(&css));
+}
+
+/// change ui scale by a step, persisted in configuration
+fn zoom_ui(gc: &GuiContext, zoom_in: bool)
+{
+	let scale = {
+		let mut configuration = gc.cfg_mut();
+		let scale = configuration.gui.ui_scale;
+		let scale = if zoom_in {
+			scale.saturating_add(UI_SCALE_STEP).min(MAX_UI_SCALE)
+		} else {
+			scale.saturating_sub(UI_SCALE_STEP).max(MIN_UI_SCALE)
+		};
+		configuration.gui.ui_scale = scale;
+		scale
+	};
+	set_ui_scale(scale);
+	gc.message(&gc.i18n.args_msg("ui-scale-changed", vec![("scale", scale)]));
+}
+
+/// e-ink profile for all windows, gtk animations are global
+fn set_eink(gc: &GuiContext, eink: bool)
+{
+	if let Some(settings) = gtk4::Settings::default() {
+		settings.set_gtk_enable_animations(!eink);
+	}
+	if eink {
+		gc.window.add_css_class(EINK_CSS_CLASS);
+	} else {
+		gc.window.remove_css_class(EINK_CSS_CLASS);
+	}
+	gc.ctrl().render.set_eink(eink);
+}
+
+/// font family of dyslexia preset set by css, for pango of both renders
+fn set_accessibility_preset(gc: &GuiContext, preset: AccessibilityPreset)
+{
+	let view = &gc.ctrl().render;
+	if preset == AccessibilityPreset::Dyslexia {
+		view.add_css_class(DYSLEXIA_CSS_CLASS);
+	} else {
+		view.remove_css_class(DYSLEXIA_CSS_CLASS);
+	}
+}
+
+/// load image of paper for the view, error reported and no paper if failed
+fn set_paper(gc: &GuiContext, paper: &Paper)
+{
+	let texture = match &paper.path {
+		Some(path) => match Texture::from_filename(path) {
+			Ok(texture) => Some(texture),
+			Err(err) => {
+				gc.error(&gc.i18n.args_msg("paper-load-failed", vec![
+					("path", path.to_string_lossy().to_string()),
+					("error", err.to_string()),
+				]));
+				None
+			}
+		},
+		None => None,
+	};
+	let paper = texture.map(|texture| (texture, paper.mode, paper.opacity.min(100) as f32 / 100.));
+	gc.ctrl().render.set_paper(paper);
+}
+
+/// follow night light schedule, until the window closed
+fn schedule_night_light(gc: &GuiContext)
+{
+	let weak = Rc::downgrade(&gc.inner);
+	glib::timeout_add_seconds_local(NIGHT_LIGHT_CHECK_SECONDS, move || {
+		match weak.upgrade() {
+			Some(inner) => {
+				GuiContext { inner }.update_colors();
+				ControlFlow::Continue
+			}
+			None => ControlFlow::Break,
+		}
+	});
+}
+
+/// mode and dim color of focus, None when disabled
+fn focus_of(configuration: &Configuration) -> Option<(FocusMode, Color32)>
+{
+	let gui = &configuration.gui;
+	if !gui.focus {
+		return None;
+	}
+	let background = &configuration.render_colors().background;
+	let alpha = (gui.focus_dim.min(100) as u16 * 255 / 100) as u8;
+	// not multiplied, cairo source takes straight alpha
+	let color = Color32::from_rgba_premultiplied(
+		background.r(),
+		background.g(),
+		background.b(),
+		alpha);
+	Some((gui.focus_mode, color))
+}
+
+#[inline]
+fn create_toggle_action(name: &str) -> SimpleAction
+{
+	SimpleAction::new_stateful(name, None, &false.to_variant())
+}
+
+fn create_toggle_button(active: bool, name: &str, i18n_key: &str,
 	icons: &IconMap, i18n: &I18n)
 	-> ToggleButton
 {
 	let image = load_button_image(name, icons, false);
 	let tooltip = i18n.msg(i18n_key);
-	ToggleButton::builder()
+	let button = ToggleButton::builder()
 		.child(&image)
 		.focus_on_click(false)
 		.focusable(false)
-		.tooltip_text(tooltip)
+		.tooltip_text(tooltip.as_ref())
 		.active(active)
-		.build()
+		.build();
+	// icon only, named for screen readers
+	button.update_property(&[Property::Label(&tooltip)]);
+	button
 }
 
 #[inline]
@@ -1407,6 +2663,9 @@ fn create_button(name: &str, tooltip: Option<&str>, icons: &IconMap, inline: boo
 		.focusable(false)
 		.build();
 	button.set_tooltip_text(tooltip);
+	if let Some(tooltip) = tooltip {
+		button.update_property(&[Property::Label(tooltip)]);
+	}
 
 	if inline {
 		button.add_css_class("inline");
@@ -1428,7 +2687,6 @@ fn update_title(window: &ApplicationWindow, controller: &GuiController)
 fn setup_env() -> Result<bool>
 {
 	use dirs::home_dir;
-	use std::fs;
 
 	// any better way to know if a usable backend for gtk4 available?
 	if !env::var("WAYLAND_DISPLAY")
@@ -1453,6 +2711,47 @@ fn setup_env() -> Result<bool>
 	Ok(true)
 }
 
+/// chapter title shown over the view for a moment, when page commands
+/// continue into another chapter
+struct ChapterInterstitial {
+	revealer: Revealer,
+	label: Label,
+	// latest showing, not hidden by timeout of older ones
+	serial: Rc<Cell<u32>>,
+}
+
+impl ChapterInterstitial {
+	fn new() -> Self
+	{
+		let label = Label::builder()
+			.css_classes(vec!["chapter-interstitial"])
+			.build();
+		let revealer = Revealer::builder()
+			.transition_type(RevealerTransitionType::Crossfade)
+			.halign(Align::Center)
+			.valign(Align::Center)
+			.can_target(false)
+			.child(&label)
+			.build();
+		ChapterInterstitial { revealer, label, serial: Rc::new(Cell::new(0)) }
+	}
+
+	fn show(&self, title: &str)
+	{
+		self.label.set_label(title);
+		self.revealer.set_reveal_child(true);
+		let serial = self.serial.get().wrapping_add(1);
+		self.serial.set(serial);
+		let revealer = self.revealer.clone();
+		let current = self.serial.clone();
+		glib::timeout_add_local_once(Duration::from_millis(CHAPTER_INTERSTITIAL_MILLIS), move || {
+			if current.get() == serial {
+				revealer.set_reveal_child(false);
+			}
+		});
+	}
+}
+
 struct GuiContextInner {
 	current: Option<String>,
 	cfg: Rc<RefCell<Configuration>>,
@@ -1460,19 +2759,46 @@ struct GuiContextInner {
 	ctx: Rc<RefCell<RenderContext>>,
 	dm: Rc<RefCell<DictionaryManager>>,
 	opener: Rc<RefCell<Opener>>,
+	// reload book when changed on disk
+	file_monitor: RefCell<Option<FileMonitor>>,
+	reload_pending: Cell<bool>,
+	// for reading time statistics
+	last_page_turn: Cell<Option<u64>>,
 	window: ApplicationWindow,
 	history_list: HistoryList,
 	status_bar: Label,
+	status_widgets: StatusWidgets,
 	paned: Paned,
+	// sidebar docked at right, the end child of paned
+	sidebar_at_end: Cell<bool>,
+	// skip position changes while moving sidebar
+	sidebar_moving: Cell<bool>,
+	dict_view: gtk4::Box,
+	// right panel for docked dictionary
+	dict_dock: gtk4::Box,
+	dict_paned: Paned,
 	sidebar_stack: Stack,
 	sidebar_btn: ToggleButton,
 	theme_action: SimpleAction,
 	custom_color_action: SimpleAction,
 	custom_font_action: SimpleAction,
+	text_only_action: SimpleAction,
 	custom_style_action: SimpleAction,
+	focus_action: SimpleAction,
+	bionic_reading_action: SimpleAction,
+	minimap_action: SimpleAction,
 	menu_btn: Button,
+	toolbar: gtk4::Box,
+	search_box: SearchEntry,
 	chapter_list: ChapterList,
 	find_list: FindList,
+	note_list: NoteList,
+	annotation_list: AnnotationList,
+	trace_list: TraceList,
+	minimap: MiniMap,
+	interstitial: ChapterInterstitial,
+	toasts: Toasts,
+	read_aloud: ReadAloud,
 	icons: Rc<IconMap>,
 	i18n: Rc<I18n>,
 	fonts: Rc<Option<UserFonts>>,
@@ -1480,6 +2806,10 @@ struct GuiContextInner {
 	file_dialog: FileDialog,
 	settings: Settings,
 	db: Rc<RefCell<DictionaryBook>>,
+	// dropped books waiting for opening, shared by windows
+	next_up: Rc<RefCell<VecDeque<String>>>,
+	// books of windows closed in this session, the last closed at end
+	recently_closed: Rc<RefCell<Vec<String>>>,
 }
 
 enum ChapterListSyncMode {
@@ -1507,24 +2837,33 @@ impl GuiContext {
 	fn new(app: &Application, settings: Settings, current: Option<String>,
 		cfg: &Rc<RefCell<Configuration>>, ctrl: &Rc<RefCell<GuiController>>,
 		ctx: &Rc<RefCell<RenderContext>>, db: Rc<RefCell<DictionaryBook>>,
-		dm: Rc<RefCell<DictionaryManager>>,
-		icons: Rc<IconMap>, i18n: Rc<I18n>, fonts: Rc<Option<UserFonts>>,
-		css_provider: CssProvider) -> (Self, gtk4::Box, gtk4::Box, SearchEntry)
+		dm: Rc<RefCell<DictionaryManager>>, dict_view: gtk4::Box,
+		opener: Rc<RefCell<Opener>>, icons: Rc<IconMap>, i18n: Rc<I18n>, fonts: Rc<Option<UserFonts>>,
+		css_provider: CssProvider, next_up: Rc<RefCell<VecDeque<String>>>,
+		recently_closed: Rc<RefCell<Vec<String>>>)
+		-> (Self, gtk4::Box, gtk4::Box, gtk4::Box, gtk4::Box, gtk4::Box, SearchEntry)
 	{
+		let geometry = cfg.borrow().gui.window.clone();
 		let window = ApplicationWindow::builder()
 			.application(app)
-			.default_width(800)
-			.default_height(600)
-			.maximized(true)
+			.default_width(geometry.width)
+			.default_height(geometry.height)
+			.maximized(geometry.maximized)
 			.title(package_name!())
 			.build();
 
 		let (chapter_list, chapter_list_view) = ChapterList::create(&icons, &i18n, &ctrl);
 		let (find_list, find_list_view, find_entry) = FindList::create(&current, &i18n, &icons);
+		let (note_list, note_list_view) = NoteList::create(&icons, &i18n, &ctrl);
+		let (annotation_list, annotation_list_view) = AnnotationList::create(&icons, &i18n, &ctrl, &cfg);
+		let (trace_list, trace_list_view) = TraceList::create(&i18n, &ctrl);
+		let minimap = MiniMap::create(&ctrl, cfg.borrow().gui.minimap);
 
 		let controller = ctrl.borrow();
 		let status_msg = controller.status().to_string();
+		// status messages announced by screen readers, page turns included
 		let status_bar = Label::builder()
+			.accessible_role(AccessibleRole::Status)
 			.label(&status_msg)
 			.max_width_chars(50)
 			.ellipsize(EllipsizeMode::Start)
@@ -1532,10 +2871,24 @@ impl GuiContext {
 			.halign(Align::End)
 			.hexpand(true)
 			.build();
+		let status_widgets = StatusWidgets::new(&status_bar,
+			&cfg.borrow().gui.status_widgets);
 
 		find_list.set_inner_book(controller.reading.inner_book);
+		find_list.set_fold_width(cfg.borrow().search_fold_width);
 
 		let paned = Paned::new(Orientation::Horizontal);
+		let dict_dock = gtk4::Box::builder()
+			.orientation(Orientation::Vertical)
+			.visible(false)
+			.build();
+		let dict_paned = Paned::builder()
+			.orientation(Orientation::Horizontal)
+			.start_child(&paned)
+			.end_child(&dict_dock)
+			.resize_end_child(false)
+			.shrink_end_child(false)
+			.build();
 		let sidebar_stack = Stack::builder()
 			.vexpand(true)
 			.build();
@@ -1544,7 +2897,11 @@ impl GuiContext {
 		let theme_action = create_toggle_action(THEME_KEY);
 		let custom_color_action = create_toggle_action(CUSTOM_COLOR_KEY);
 		let custom_font_action = create_toggle_action(CUSTOM_FONT_KEY);
+		let text_only_action = create_toggle_action(TEXT_ONLY_KEY);
 		let custom_style_action = create_action(CUSTOM_STYLE_KEY);
+		let focus_action = create_toggle_action(FOCUS_KEY);
+		let bionic_reading_action = create_toggle_action(BIONIC_READING_KEY);
+		let minimap_action = create_toggle_action(MINIMAP_KEY);
 
 		let file_dialog = FileDialog::new();
 		file_dialog.set_title(&i18n.msg("file-open-title"));
@@ -1557,6 +2914,15 @@ impl GuiContext {
 
 		let history_list = HistoryList::new(controller.render.as_ref(), &cfg);
 		let menu_btn = create_button("menu.svg", Some(&i18n.msg("menu")), &icons, false);
+		let read_aloud = ReadAloud::new(&icons, &i18n);
+		let toolbar = gtk4::Box::builder()
+			.css_classes(vec!["toolbar"])
+			.build();
+		let search_box = SearchEntry::builder()
+			.placeholder_text(i18n.msg("search-hint"))
+			.activates_default(true)
+			.enable_undo(true)
+			.build();
 
 		let inner = GuiContextInner {
 			current,
@@ -1564,20 +2930,42 @@ impl GuiContext {
 			ctrl: ctrl.clone(),
 			ctx: ctx.clone(),
 			dm,
-			opener: Rc::new(RefCell::new(Default::default())),
+			opener,
+			file_monitor: RefCell::new(None),
+			reload_pending: Cell::new(false),
+			last_page_turn: Cell::new(None),
 			window,
 			history_list,
 			status_bar,
+			status_widgets,
 			paned,
+			sidebar_at_end: Cell::new(false),
+			sidebar_moving: Cell::new(false),
+			dict_view,
+			dict_dock,
+			dict_paned,
 			sidebar_stack,
 			sidebar_btn,
 			theme_action,
 			custom_color_action,
 			custom_font_action,
+			text_only_action,
 			custom_style_action,
+			focus_action,
+			bionic_reading_action,
+			minimap_action,
 			menu_btn,
+			toolbar,
+			search_box,
 			chapter_list,
 			find_list,
+			note_list,
+			annotation_list,
+			trace_list,
+			minimap,
+			interstitial: ChapterInterstitial::new(),
+			toasts: Toasts::new(),
+			read_aloud,
 			icons,
 			i18n,
 			fonts,
@@ -1585,8 +2973,11 @@ impl GuiContext {
 			file_dialog,
 			settings,
 			db,
+			next_up,
+			recently_closed,
 		};
-		(GuiContext { inner: Rc::new(inner) }, chapter_list_view, find_list_view, find_entry)
+		(GuiContext { inner: Rc::new(inner) }, chapter_list_view, find_list_view, note_list_view,
+			annotation_list_view, trace_list_view, find_entry)
 	}
 
 	#[inline]
@@ -1647,7 +3038,7 @@ impl GuiContext {
 	fn show_history(&self)
 	{
 		match self.cfg().history(self.current.as_ref(), None) {
-			Ok(infos) => self.history_list.popup(infos),
+			Ok(infos) => self.history_list.popup(infos, self),
 			Err(err) => self.error(&err.to_string()),
 		}
 	}
@@ -1705,7 +3096,207 @@ impl GuiContext {
 		});
 	}
 
-	fn reload_book(&self)
+	/// books in dropped folders added to history, first book opened,
+	/// others queued for next up
+	fn open_dropped(&self, paths: Vec<PathBuf>)
+	{
+		let mut books = vec![];
+		for path in paths {
+			let Some(filename) = path.canonicalize().ok()
+				.and_then(|path| path.to_str().map(|path| path.to_owned())) else {
+				continue;
+			};
+			if !path.is_dir() {
+				books.push(filename);
+				continue;
+			}
+			let folder_books = self.ctrl().container_manager.folder_books(&filename);
+			match folder_books {
+				Ok(folder_books) => {
+					let configuration = self.cfg();
+					for book in folder_books {
+						let Some(book) = book.to_str() else {
+							continue;
+						};
+						if let Err(err) = configuration.add_history(book) {
+							self.error(&err.to_string());
+						}
+						books.push(book.to_owned());
+					}
+				}
+				Err(err) => self.error(&err.to_string()),
+			}
+		}
+		if books.is_empty() {
+			self.error(&self.i18n.msg("no-book-dropped"));
+			return;
+		}
+		let first = books.remove(0);
+		let queued = books.len();
+		self.next_up.borrow_mut().extend(books);
+		if queued > 0 {
+			self.message(&self.i18n.args_msg("next-up-queued", vec![
+				("count", queued),
+			]));
+		}
+		self.open_file(&PathBuf::from(first));
+	}
+
+	/// download to cache with size in status bar, cached file opened directly
+	fn open_url(&self, url: String)
+	{
+		let path = download::cached_path(&self.cfg().cache_dir, &url);
+		if path.is_file() {
+			self.open_file(&path);
+			return;
+		}
+		if let Some(folder) = path.parent() {
+			if let Err(err) = fs::create_dir_all(folder) {
+				self.error(&err.to_string());
+				return;
+			}
+		}
+		let partial = download::partial_path(&path);
+		let progress = {
+			let gc = self.clone();
+			let partial = partial.clone();
+			let url = url.clone();
+			glib::timeout_add_local(Duration::from_millis(DOWNLOAD_PROGRESS_MILLIS), move || {
+				let size = fs::metadata(&partial).map_or(0, |metadata| metadata.len());
+				gc.message(&gc.i18n.args_msg("downloading", vec![
+					("url", url.clone()),
+					("size", format_size(size).to_string()),
+				]));
+				ControlFlow::Continue
+			})
+		};
+		let gc = self.clone();
+		let target = partial.clone();
+		let result = http::download(&url.clone(), &target, move |result| {
+			progress.remove();
+			let result = result.and_then(|()| fs::rename(&partial, &path)
+				.map_err(|err| err.to_string()));
+			match result {
+				Ok(()) => {
+					gc.message("");
+					gc.notify(&gc.i18n.args_msg("download-finished", vec![
+						("url", url),
+					]));
+					gc.open_file(&path);
+				}
+				Err(err) => {
+					let _ = fs::remove_file(&partial);
+					gc.message("");
+					let msg = gc.i18n.args_msg("download-failed", vec![
+						("url", url),
+						("error", err),
+					]);
+					gc.notify(&msg);
+					gc.toast_error(&msg);
+				}
+			}
+		});
+		if let Err(err) = result {
+			self.error(&err.to_string());
+		}
+	}
+
+	fn open_next_up(&self)
+	{
+		let next = self.next_up.borrow_mut().pop_front();
+		match next {
+			Some(filename) => self.open_file(&PathBuf::from(filename)),
+			None => self.message(&self.i18n.msg("next-up-empty")),
+		}
+	}
+
+	fn track_closed(&self, filename: &str)
+	{
+		let mut recently_closed = self.recently_closed.borrow_mut();
+		recently_closed.retain(|closed| closed != filename);
+		if recently_closed.len() >= MAX_RECENTLY_CLOSED {
+			recently_closed.remove(0);
+		}
+		recently_closed.push(filename.to_owned());
+	}
+
+	/// the last closed book not opened again, at position saved when closed
+	fn reopen_closed(&self)
+	{
+		let closed = loop {
+			let closed = self.recently_closed.borrow_mut().pop();
+			match closed {
+				Some(filename) if self.settings.is_opened(&filename) => continue,
+				closed => break closed,
+			}
+		};
+		match closed {
+			Some(filename) => self.open_file(&PathBuf::from(filename)),
+			None => self.message(&self.i18n.msg("no-closed-book")),
+		}
+	}
+
+	fn open_sibling(&self, forward: bool)
+	{
+		let controller = self.ctrl();
+		if controller.reading.filename == README_TEXT_FILENAME {
+			return;
+		}
+		let sibling = controller.container_manager
+			.sibling_book(&controller.reading.filename, forward);
+		drop(controller);
+		match sibling {
+			Ok(Some(filename)) => self.open_file(&PathBuf::from(filename)),
+			Ok(None) => self.message(&self.i18n.msg("no-sibling-book")),
+			Err(err) => self.error(&err.to_string()),
+		}
+	}
+
+	/// choose resolution then the file, page saved as png
+	fn export_page_image(&self)
+	{
+		let i18n = &self.i18n;
+		let mut buttons = IMAGE_SCALES
+			.iter()
+			.map(|scale| format!("{}x", scale))
+			.collect::<Vec<_>>();
+		buttons.push(i18n.msg("cancel-title").to_string());
+		let dialog = AlertDialog::builder()
+			.modal(true)
+			.message(i18n.msg(EXPORT_IMAGE_KEY))
+			.detail(i18n.msg("export-image-resolution"))
+			.buttons(buttons.iter().map(|b| b.as_str()).collect::<Vec<_>>())
+			.cancel_button(IMAGE_SCALES.len() as i32)
+			.default_button(0)
+			.build();
+		let gc = self.clone();
+		dialog.choose(Some(&self.window), None::<&Cancellable>, move |result| {
+			let Some(scale) = result.ok().and_then(|index| IMAGE_SCALES.get(index as usize)) else {
+				return;
+			};
+			let scale = *scale;
+			let file_dialog = FileDialog::new();
+			file_dialog.set_title(&gc.i18n.msg(EXPORT_IMAGE_KEY));
+			file_dialog.set_modal(true);
+			let name = format!("{}.png", gc.ctrl().reading_book_name());
+			file_dialog.set_initial_name(Some(&name));
+			let gc2 = gc.clone();
+			file_dialog.save(Some(&gc.window), None::<&Cancellable>, move |result| {
+				let Some(path) = result.ok().and_then(|file| file.path()) else {
+					return;
+				};
+				let background = gc2.ctx().colors.background.clone();
+				match gc2.ctrl().render.export_png(&path, scale, &background) {
+					Ok(()) => gc2.toast(&gc2.i18n.args_msg("page-image-exported", vec![
+						("path", path.to_string_lossy()),
+					])),
+					Err(err) => gc2.toast_error(&err.to_string()),
+				}
+			});
+		});
+	}
+
+	fn reload_book(&self) -> bool
 	{
 		let mut controller = self.ctrl_mut();
 		let loading = BookLoadingInfo::Reload(controller.reading.clone());
@@ -1713,9 +3304,14 @@ impl GuiContext {
 			Ok(msg) => {
 				drop(controller);
 				self.chapter_list.sync_chapter_list(ChapterListSyncMode::Reload);
-				update_status(false, &msg, &self.status_bar)
+				self.note_list.sync_notes(true);
+				update_status(false, &msg, &self.status_bar);
+				true
+			}
+			Err(err) => {
+				self.error(&err.to_string());
+				false
 			}
-			Err(err) => self.error(&err.to_string()),
 		}
 	}
 
@@ -1733,24 +3329,80 @@ impl GuiContext {
 		}
 
 		let mut text = String::new();
-		let controller = self.ctrl_mut();
+		let mut controller = self.ctrl_mut();
+		let cover = controller.book.cover()
+			.and_then(|bytes| load_image(&bytes));
 		let reading = &controller.reading;
-		let path = PathBuf::from_str(&reading.filename)?;
-		let meta = path.metadata()?;
 		let container = gtk4::Box::new(Orientation::Vertical, 10);
 		container.append(&label(&reading.filename, &mut text));
-		container.append(&label(&format_size(meta.len()), &mut text));
+		// no file for book read from stdin
+		if !is_stdin(&reading.filename) {
+			let meta = PathBuf::from_str(&reading.filename)?.metadata()?;
+			container.append(&label(&format_size(meta.len()), &mut text));
+		}
+		if let Some(ts) = self.cfg().finished(&reading.filename)? {
+			let date = glib::DateTime::from_unix_local(ts as i64)
+				.and_then(|date| date.format("%F"))?;
+			let finished = self.i18n.args_msg("book-finished", vec![
+				("date", date.as_str()),
+			]);
+			container.append(&label(&finished, &mut text));
+		}
 		container.append(&Separator::new(Orientation::Horizontal));
+		if let Some(cover) = cover {
+			let height = cover.height().min(COVER_HEIGHT);
+			let width = cover.width() * height / cover.height().max(1);
+			let cover = cover.scale_simple(width.max(1), height.max(1), InterpType::Bilinear)
+				.unwrap_or(cover);
+			container.append(&Picture::builder()
+				.paintable(&Texture::for_pixbuf(&cover))
+				.can_shrink(false)
+				.halign(Align::Center)
+				.build());
+		}
 		if let Some(book_names) = controller.container.inner_book_names() {
 			if let Some(name) = book_names.get(reading.inner_book) {
 				container.append(&label(&name.name(), &mut text));
 			}
 		}
+		if let Some(name) = controller.book.name() {
+			container.append(&label(name, &mut text));
+		}
+		if let Some(author) = controller.book.author() {
+			let author = self.i18n.args_msg("book-author", vec![
+				("author", author),
+			]);
+			container.append(&label(&author, &mut text));
+		}
+		if let Some(language) = controller.book.language() {
+			let language = self.i18n.args_msg("book-language", vec![
+				("language", language),
+			]);
+			container.append(&label(&language, &mut text));
+		}
 		let status = controller.status();
 		if let Some(title) = status.title {
 			container.append(&label(title, &mut text));
 		}
 		container.append(&label(&status.position(), &mut text));
+		if let Some(page) = status.page {
+			container.append(&label(page, &mut text));
+		}
+		if let Some(problems) = controller.book.problems() {
+			container.append(&Separator::new(Orientation::Horizontal));
+			let count = problems.len().to_string();
+			container.append(&label(&self.i18n.args_msg("book-problems", vec![
+				("count", count.as_str()),
+			]), &mut text));
+			for (index, problem) in problems.iter().enumerate() {
+				if index < MAX_PROBLEMS_SHOWN {
+					container.append(&label(problem, &mut text));
+				} else {
+					text.push('\n');
+					text.push_str(problem);
+				}
+			}
+		}
 		let popover = Popover::builder()
 			.child(&container)
 			.build();
@@ -1782,15 +3434,41 @@ impl GuiContext {
 
 	fn toggle_sidebar(&self)
 	{
-		let paned = &self.paned;
-		let (on, position) = if paned.position() == 0 {
+		let (on, size) = if self.sidebar_size() == 0 {
 			(true, self.cfg().gui.sidebar_size as i32)
 		} else {
 			self.ctrl().render.grab_focus();
 			(false, 0)
 		};
 		self.sidebar_btn.set_active(on);
-		paned.set_position(position);
+		self.set_sidebar_size(size);
+	}
+
+	/// size of the sidebar, 0 for hidden
+	fn sidebar_size(&self) -> i32
+	{
+		let paned = &self.paned;
+		if self.sidebar_at_end.get() {
+			paned.max_position().saturating_sub(paned.position()).max(0)
+		} else {
+			paned.position()
+		}
+	}
+
+	fn set_sidebar_size(&self, size: i32)
+	{
+		let paned = &self.paned;
+		if self.sidebar_at_end.get() {
+			// max position not known before allocated
+			let position = if size == 0 {
+				i32::MAX
+			} else {
+				paned.max_position().saturating_sub(size).max(0)
+			};
+			paned.set_position(position);
+		} else {
+			paned.set_position(size);
+		}
 	}
 
 	fn switch_theme(&self)
@@ -1800,10 +3478,31 @@ impl GuiContext {
 		self.theme_action.set_state(&dark_theme.to_variant());
 		configuration.dark_theme = dark_theme;
 		let mut render_context = self.ctx_mut();
-		render_context.colors = configuration.curr_theme().clone();
+		render_context.colors = configuration.render_colors();
+		let mut controller = self.ctrl_mut();
+		controller.redraw(&mut render_context);
+		view::update_css(&self.css_provider, &render_context.colors);
+		controller.render.set_focus(focus_of(&configuration));
+		drop(controller);
+		self.minimap.sync(&render_context.colors);
+	}
+
+	/// redraw when colors for rendering changed by night light schedule
+	fn update_colors(&self)
+	{
+		let configuration = self.cfg();
+		let colors = configuration.render_colors();
+		let mut render_context = self.ctx_mut();
+		if render_context.colors == colors {
+			return;
+		}
+		render_context.colors = colors;
 		let mut controller = self.ctrl_mut();
 		controller.redraw(&mut render_context);
 		view::update_css(&self.css_provider, &render_context.colors);
+		controller.render.set_focus(focus_of(&configuration));
+		drop(controller);
+		self.minimap.sync(&render_context.colors);
 	}
 
 	fn toggle_custom_color(&self)
@@ -1813,7 +3512,7 @@ impl GuiContext {
 		self.custom_color_action.set_state(&custom_color.to_variant());
 		controller.reading.custom_color = custom_color;
 		let mut render_context = self.ctx_mut();
-		render_context.custom_color = custom_color;
+		render_context.custom_color = custom_color && self.cfg().gui.book_colors_allowed();
 		controller.redraw(&mut render_context);
 	}
 
@@ -1831,6 +3530,59 @@ impl GuiContext {
 		controller.redraw(&mut render_context);
 	}
 
+	fn toggle_text_only(&self)
+	{
+		let mut controller = self.ctrl_mut();
+		let text_only = !controller.reading.text_only;
+		self.text_only_action.set_state(&text_only.to_variant());
+		controller.reading.text_only = text_only;
+		let mut render_context = self.ctx_mut();
+		render_context.text_only = text_only;
+		if text_only {
+			controller.render.clear_image_cache();
+		}
+		controller.redraw(&mut render_context);
+	}
+
+	fn toggle_focus(&self)
+	{
+		let mut configuration = self.cfg_mut();
+		configuration.gui.focus = !configuration.gui.focus;
+		drop(configuration);
+		self.update_focus();
+	}
+
+	fn toggle_bionic_reading(&self)
+	{
+		let mut configuration = self.cfg_mut();
+		let bionic_reading = !configuration.gui.bionic_reading;
+		configuration.gui.bionic_reading = bionic_reading;
+		drop(configuration);
+		self.bionic_reading_action.set_state(&bionic_reading.to_variant());
+		let mut render_context = self.ctx_mut();
+		render_context.bionic_reading = bionic_reading;
+		self.ctrl_mut().redraw(&mut render_context);
+	}
+
+	fn toggle_minimap(&self)
+	{
+		let mut configuration = self.cfg_mut();
+		let minimap = !configuration.gui.minimap;
+		configuration.gui.minimap = minimap;
+		drop(configuration);
+		self.minimap_action.set_state(&minimap.to_variant());
+		self.minimap.set_visible(minimap);
+		self.minimap.sync(&self.ctx().colors);
+	}
+
+	/// sync focus mode of view with configuration
+	fn update_focus(&self)
+	{
+		let configuration = self.cfg();
+		self.focus_action.set_state(&configuration.gui.focus.to_variant());
+		self.ctrl().render.set_focus(focus_of(&configuration));
+	}
+
 	fn custom_style_dialog(&self)
 	{
 		let controller = self.ctrl();
@@ -1869,11 +3621,170 @@ impl GuiContext {
 		});
 	}
 
+	fn goto_page(&self)
+	{
+		let gc = self.clone();
+		dialogs::goto_page(self, &self.window, move |label| {
+			let mut controller = gc.ctrl_mut();
+			controller.goto_page(label, gc.ctx_mut().deref_mut())?;
+			let msg = controller.status().to_string();
+			drop(controller);
+			gc.update(&msg, ChapterListSyncMode::NoReload);
+			Ok(())
+		});
+	}
+
+	/// quick switch for containers with many inner books
+	fn switch_inner_book(&self)
+	{
+		let (names, current) = {
+			let controller = self.ctrl();
+			let Some(names) = controller.container.inner_book_names() else {
+				return;
+			};
+			let names = names
+				.iter()
+				.map(|name| name.name().clone())
+				.collect::<Vec<_>>();
+			(names, controller.reading.inner_book)
+		};
+		let gc = self.clone();
+		dialogs::inner_books(self, &self.window, names, current, move |index| {
+			let mut controller = gc.ctrl_mut();
+			let orig_inner_book = controller.reading.inner_book;
+			let result = controller.switch_book(index, gc.ctx_mut().deref_mut());
+			update_title(&gc.window, &controller);
+			drop(controller);
+			match result {
+				Ok(msg) => {
+					gc.find_list.set_inner_book(index);
+					gc.update(&msg, ChapterListSyncMode::ReloadIfNeeded(orig_inner_book));
+				}
+				Err(err) => gc.error(&err.to_string()),
+			}
+		});
+	}
+
 	#[inline]
 	fn update(&self, msg: &str, chapter_list_sync_mode: ChapterListSyncMode)
 	{
 		self.message(msg);
 		self.chapter_list.sync_chapter_list(chapter_list_sync_mode);
+		self.note_list.sync_notes(false);
+		self.sync_annotations(false);
+		self.minimap.sync(&self.ctx().colors);
+		if self.sidebar_size() > 0 && self.sidebar_stack.visible_child_name()
+			.map_or(false, |name| name == SIDEBAR_TRACE_NAME) {
+			self.sync_trace_list();
+		}
+		self.check_finished();
+		stats::track(self);
+		stats::track_toc(self);
+		status::schedule_refresh(self);
+	}
+
+	/// reload annotations of the inner book if changed or forced,
+	/// redraw for highlights changed
+	fn sync_annotations(&self, force: bool)
+	{
+		match self.annotation_list.sync_annotations(force) {
+			Ok(true) => {
+				let marks = self.annotation_list.marks();
+				let mut render_context = self.ctx_mut();
+				if marks.is_empty() && render_context.annotations.is_empty() {
+					return;
+				}
+				render_context.annotations = marks;
+				self.ctrl_mut().redraw(&mut render_context);
+			}
+			Ok(false) => {}
+			Err(err) => self.error(&err.to_string()),
+		}
+	}
+
+	/// highlight selected text, with note if any
+	fn annotate_selection(&self, note: Option<String>, category: HighlightCategory)
+	{
+		let controller = self.ctrl();
+		let (Some(text), Some(range)) = (controller.selected(), controller.selection_range()) else {
+			return;
+		};
+		let chapter_title = controller.book
+			.title(range.start.line, range.start.offset)
+			.map(|title| title.to_owned());
+		let annotation = AnnotationInfo::new(controller.reading.chapter, chapter_title,
+			range, text.to_owned(), note, category);
+		drop(controller);
+		self.save_annotation(annotation);
+	}
+
+	/// bookmark current position, with text of the line from there
+	fn add_bookmark(&self)
+	{
+		let controller = self.ctrl();
+		let reading = &controller.reading;
+		let position = Position::new(reading.line, reading.position);
+		let text = controller.book.lines()
+			.get(reading.line)
+			.map_or_else(String::new, |line| line
+				.iter()
+				.skip(reading.position)
+				.take(BOOKMARK_TEXT_CHARS)
+				.collect::<String>()
+				.trim()
+				.to_owned());
+		let chapter_title = controller.book
+			.title(reading.line, reading.position)
+			.map(|title| title.to_owned());
+		let annotation = AnnotationInfo::new(reading.chapter, chapter_title,
+			position.clone()..position, text, None, HighlightCategory::default());
+		drop(controller);
+		self.save_annotation(annotation);
+	}
+
+	fn save_annotation(&self, mut annotation: AnnotationInfo)
+	{
+		let controller = self.ctrl();
+		let result = self.cfg().add_annotation(&controller.reading.filename,
+			controller.reading.inner_book, &mut annotation);
+		drop(controller);
+		match result {
+			Ok(()) => {
+				self.sync_annotations(true);
+				let key = if annotation.is_bookmark() {
+					"bookmark-added"
+				} else {
+					"annotation-added"
+				};
+				self.toast(&self.i18n.msg(key));
+			}
+			Err(err) => self.error(&err.to_string()),
+		}
+	}
+
+	#[inline]
+	fn sync_trace_list(&self)
+	{
+		if let Err(err) = self.trace_list.sync_trace() {
+			self.error(&err.to_string());
+		}
+	}
+
+	/// mark book finished when the last page reached
+	fn check_finished(&self)
+	{
+		let controller = self.ctrl();
+		let filename = &controller.reading.filename;
+		if filename == README_TEXT_FILENAME || !controller.at_end() {
+			return;
+		}
+		if let Err(err) = self.cfg().mark_finished(filename) {
+			self.error(&err.to_string());
+		} else if let Some(next) = self.next_up.borrow().front() {
+			self.message(&self.i18n.args_msg("next-up-hint", vec![
+				("name", title_for_filename(next)),
+			]));
+		}
 	}
 
 	#[inline]
@@ -1888,6 +3799,35 @@ impl GuiContext {
 		update_status(true, msg, &self.status_bar);
 	}
 
+	/// for results of background tasks, not lost when status updated by reading
+	#[inline]
+	fn toast(&self, msg: &str)
+	{
+		self.toasts.show(ToastLevel::Info, msg);
+	}
+
+	#[inline]
+	fn toast_error(&self, msg: &str)
+	{
+		self.toasts.show(ToastLevel::Error, msg);
+	}
+
+	/// desktop notification for background task finished,
+	/// only when the window not focused
+	fn notify(&self, msg: &str)
+	{
+		if self.window.is_active() {
+			return;
+		}
+		if let Some(app) = self.window.application() {
+			let title = self.window.title()
+				.map_or_else(|| package_name!().to_owned(), |title| title.to_string());
+			let notification = Notification::new(&title);
+			notification.set_body(Some(msg));
+			app.send_notification(None, &notification);
+		}
+	}
+
 	#[inline]
 	fn show_settings(&self)
 	{
@@ -1907,18 +3847,37 @@ fn update_status(error: bool, msg: &str, status_bar: &Label)
 	status_bar.set_tooltip_text(Some(msg));
 }
 
+#[inline]
 fn show(app: &Application, current: Option<String>,
 	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
 {
-	match build_ui(app, current, cfg.clone(), gcs) {
+	show_book(app, current, None, cfg, gcs)
+}
+
+fn show_book(app: &Application, current: Option<String>, password: Option<(String, bool)>,
+	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let retry = password.is_some();
+	match build_ui(app, current.clone(), password, cfg.clone(), gcs) {
 		Ok(Some(gc)) => {
 			// clean temp files
 			app.connect_shutdown(move |_| gc.opener().cleanup());
 		}
 		// previous opened
 		Ok(None) => {}
+		Err(err) if password_required(&err) && current.is_some() =>
+			ask_password(app, current.unwrap(), retry, cfg, gcs),
+		Err(err) if format_required(&err) && current.is_some() =>
+			ask_format(app, current.unwrap(), cfg, gcs),
 		Err(err) => {
-			if let Some(window) = app.active_window() {
+			// opened from a reading window
+			let gc = gcs.borrow().iter().find(|gc| gc.window.is_active()).cloned();
+			if let Some(gc) = gc {
+				gc.toast_error(&gc.i18n.args_msg("open-failed", vec![
+					("name", current.as_deref().map_or("", title_for_filename).to_owned()),
+					("error", err.to_string()),
+				]));
+			} else if let Some(window) = app.active_window() {
 				alert("Failed start tbr", &err.to_string(), &window);
 			} else if let Some(window) = app.windows().get(0) {
 				alert("Failed start tbr", &err.to_string(), window);
@@ -1929,6 +3888,51 @@ fn show(app: &Application, current: Option<String>,
 	}
 }
 
+/// book opened again with the password entered
+fn ask_password(app: &Application, filename: String, retry: bool,
+	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let Some(i18n) = dialog_i18n(cfg, gcs) else {
+		return;
+	};
+	let cfg = cfg.clone();
+	let gcs = gcs.clone();
+	dialogs::password(app, &i18n, &filename.clone(), retry, move |app, password, remember|
+		show_book(app, Some(filename.clone()), Some((password, remember)), &cfg, &gcs));
+}
+
+/// book of unknown extension opened again by the loader of format chosen
+fn ask_format(app: &Application, filename: String,
+	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let Some(i18n) = dialog_i18n(cfg, gcs) else {
+		return;
+	};
+	let book_loader = BookLoader::default();
+	let cfg = cfg.clone();
+	let gcs = gcs.clone();
+	dialogs::format(app, &i18n, &filename.clone(), book_loader.formats(), move |app, format| {
+		set_format(&filename, &format);
+		show(app, Some(filename.clone()), &cfg, &gcs);
+	});
+}
+
+/// i18n of opened windows, or loaded for dialogs before any window
+fn dialog_i18n(cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>) -> Option<Rc<I18n>>
+{
+	if let Some(gc) = gcs.borrow().get(0) {
+		return Some(gc.i18n.clone());
+	}
+	let configuration = cfg.borrow();
+	match I18n::new(&configuration.gui.lang, &configuration.locales_dir()) {
+		Ok(i18n) => Some(Rc::new(i18n)),
+		Err(err) => {
+			eprintln!("Failed start tbr: {}", err.to_string());
+			None
+		}
+	}
+}
+
 fn mouse_pointer(view: &impl IsA<Widget>) -> Option<(f32, f32)>
 {
 	let pointer = view.display().default_seat()?.pointer()?;
@@ -1945,6 +3949,16 @@ fn mouse_pointer(view: &impl IsA<Widget>) -> Option<(f32, f32)>
 }
 
 #[inline]
+/// key with modifiers matches the accelerator, like "l" or "<Shift>l"
+fn key_matches(accelerator: &str, key: Key, modifier: ModifierType) -> bool
+{
+	match gtk4::accelerator_parse(accelerator) {
+		Some((accel_key, accel_modifier)) =>
+			accel_key.to_lower() == key.to_lower() && accel_modifier == modifier,
+		None => false,
+	}
+}
+
 fn ignore_cap(key: Key, modifier: ModifierType) -> (Key, ModifierType)
 {
 	if modifier & ModifierType::LOCK_MASK == MODIFIER_NONE {
@@ -1956,7 +3970,8 @@ fn ignore_cap(key: Key, modifier: ModifierType) -> (Key, ModifierType)
 	}
 }
 
-pub fn start(current: Option<String>, configuration: Configuration)
+pub fn start(current: Option<String>, configuration: Configuration,
+	book_specified: bool, link: Option<PositionLink>)
 	-> Result<Option<(Option<String>, Configuration)>>
 {
 	#[cfg(unix)]
@@ -1971,14 +3986,27 @@ pub fn start(current: Option<String>, configuration: Configuration)
 
 	let mut args = env::args().collect::<Vec<_>>();
 	args.drain(1..);
-	let start_without_file = if let Some(filename) = current {
+	let session = if !book_specified && configuration.gui.restore_session {
+		configuration.gui.session.clone()
+	} else {
+		vec![]
+	};
+	// latest reading book as fallback if session books all failed
+	let (start_without_file, fallback) = if !session.is_empty() {
+		(true, current)
+	} else if let Some(link) = link {
+		// opened by link, so the position applied
+		args.push(link.to_string());
+		(false, None)
+	} else if let Some(filename) = current {
 		args.push(filename);
-		false
+		(false, None)
 	} else {
-		true
+		(true, None)
 	};
 
 	let cfg = Rc::new(RefCell::new(configuration));
+	crash::track_configuration(Rc::downgrade(&cfg));
 	let gcs = Rc::new(RefCell::new(vec![]));
 	{
 		let cfg = cfg.clone();
@@ -1992,14 +4020,17 @@ pub fn start(current: Option<String>, configuration: Configuration)
 				gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
 			);
 			Window::set_default_icon_name("tbr-icon");
+			set_ui_scale(cfg.borrow().gui.ui_scale);
 
 			#[cfg(unix)]
 			{
-				handle_signal(2, app.clone());
-				handle_signal(15, app.clone());
+				handle_signal(2, app.clone(), &cfg, &gcs);
+				handle_signal(15, app.clone(), &cfg, &gcs);
 			}
-			if start_without_file {
-				show(app, None, &cfg, &gcs);
+			watch_clipboard(&cfg, &gcs);
+			watch_config(app, &cfg, &gcs);
+			if start_without_file && !restore_session(app, &session, &cfg, &gcs) {
+				show(app, fallback.clone(), &cfg, &gcs);
 			}
 		});
 	}
@@ -2007,16 +4038,22 @@ pub fn start(current: Option<String>, configuration: Configuration)
 	{
 		app.connect_open(move |app, files, _| {
 			if !files.is_empty() {
-				if let Some(path) = files[0].path() {
-					if let Some(path) = path.to_str() {
-						let current = Some(path.to_owned());
-						show(app, current, &cfg, &gcs);
-						let mut gui_contexts = gcs.borrow_mut();
-						if let Ok(idx) = get_gc(gui_contexts.as_ref(), README_TEXT_FILENAME) {
-							let gc = gui_contexts.remove(idx);
-							drop(gui_contexts);
-							gc.window.close();
-						}
+				let link = PositionLink::parse(&files[0].uri());
+				let path = match &link {
+					Some(link) => Some(link.filename.clone()),
+					None => files[0].path()
+						.and_then(|path| path.to_str().map(|path| path.to_owned())),
+				};
+				if let Some(path) = path {
+					show(app, Some(path), &cfg, &gcs);
+					let mut gui_contexts = gcs.borrow_mut();
+					if let Ok(idx) = get_gc(gui_contexts.as_ref(), README_TEXT_FILENAME) {
+						let gc = gui_contexts.remove(idx);
+						drop(gui_contexts);
+						gc.window.close();
+					}
+					if let Some(link) = link {
+						goto_link(&gcs, &link);
 					}
 				}
 			}
@@ -2031,15 +4068,40 @@ pub fn start(current: Option<String>, configuration: Configuration)
 }
 
 #[cfg(unix)]
-fn handle_signal(signum: i32, app: Application)
+fn handle_signal(signum: i32, app: Application, cfg: &Rc<RefCell<Configuration>>,
+	gcs: &Rc<RefCell<Vec<GuiContext>>>)
 {
+	let cfg = cfg.clone();
+	let gcs = gcs.clone();
 	glib::unix_signal_add_local_once(signum, move || {
+		// windows closed one by one, keep all of them in session
+		let session = session_of(&gcs.borrow());
 		for win in app.windows() {
 			win.close();
 		}
+		let mut configuration = cfg.borrow_mut();
+		if !session.is_empty() && configuration.gui.session != session {
+			configuration.gui.session = session;
+			if let Err(e) = configuration.save() {
+				eprintln!("Failed save configuration: {}", e.to_string());
+			}
+		}
 	});
 }
 
+fn history_problem_msg(i18n: &I18n, problem: &HistoryProblem) -> String
+{
+	match problem {
+		HistoryProblem::Rebuilt(backup) => i18n.args_msg("history-rebuilt", vec![
+			("path", backup.to_string_lossy()),
+		]),
+		HistoryProblem::InMemory(Some(backup)) => i18n.args_msg("history-in-memory-moved", vec![
+			("path", backup.to_string_lossy()),
+		]),
+		HistoryProblem::InMemory(None) => i18n.msg("history-in-memory").to_string(),
+	}
+}
+
 #[inline]
 fn alert(title: &str, msg: &str, parent: &impl IsA<Window>)
 {
@@ -2058,6 +4120,87 @@ fn app_open(app: &Application, filepath: &str)
 }
 
 #[inline]
+/// books in windows, with sidebar state
+fn session_of(gcs: &Vec<GuiContext>) -> Vec<SessionBook>
+{
+	gcs.iter()
+		.filter_map(|gc| {
+			let filename = gc.ctrl().reading.filename.clone();
+			if filename == README_TEXT_FILENAME || is_stdin(&filename) {
+				return None;
+			}
+			let sidebar = if gc.sidebar_size() > 0 {
+				gc.sidebar_stack.visible_child_name().map(|name| name.to_string())
+			} else {
+				None
+			};
+			Some(SessionBook { filename, sidebar })
+		})
+		.collect()
+}
+
+/// windows closed and opened again with books and sidebars kept,
+/// for changes applied only when widgets created, like locale
+fn rebuild_windows(gc: &GuiContext, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let Some(app) = gc.window.application() else {
+		return;
+	};
+	let cfg = gc.cfg.clone();
+	let gcs = gcs.clone();
+	// settings dialog and contexts still borrowed
+	glib::idle_add_local_once(move || {
+		let session = session_of(&gcs.borrow());
+		// not exit when the last window closed
+		let _hold = app.hold();
+		for window in app.windows() {
+			window.close();
+		}
+		if !restore_session(&app, &session, &cfg, &gcs) {
+			show(&app, None, &cfg, &gcs);
+		}
+	});
+}
+
+/// open books of last session, false if none opened
+fn restore_session(app: &Application, session: &Vec<SessionBook>,
+	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>) -> bool
+{
+	for book in session {
+		show(app, Some(book.filename.clone()), cfg, gcs);
+		if let Some(tab_name) = &book.sidebar {
+			let gc = {
+				let gui_contexts = gcs.borrow();
+				get_gc(&gui_contexts, &book.filename)
+					.ok()
+					.map(|idx| gui_contexts[idx].clone())
+			};
+			if let Some(gc) = gc {
+				if gc.sidebar_stack.child_by_name(tab_name).is_some() {
+					switch_stack(tab_name, &gc, false);
+				}
+			}
+		}
+	}
+	!gcs.borrow().is_empty()
+}
+
+/// position of link in the window of its book
+fn goto_link(gcs: &Rc<RefCell<Vec<GuiContext>>>, link: &PositionLink)
+{
+	let gc = {
+		let gui_contexts = gcs.borrow();
+		get_gc(gui_contexts.as_ref(), &link.filename)
+			.ok()
+			.map(|idx| gui_contexts[idx].clone())
+	};
+	if let Some(gc) = gc {
+		handle(&gc, |controller, render_context| controller.goto_position(
+			link.inner_book, link.chapter, link.line, link.offset, render_context));
+		gc.window.present();
+	}
+}
+
 fn get_gc(gcs: &Vec<GuiContext>, filename: &str) -> core::result::Result<usize, usize>
 {
 	gcs.binary_search_by(|gc| gc.ctrl().reading.filename.as_str().cmp(filename))
@@ -0,0 +1,271 @@
+use std::fs;
+use std::path::PathBuf;
+
+use gtk4::{AlertDialog, FileDialog};
+use gtk4::gio::Cancellable;
+use gtk4::prelude::FileExt;
+
+use crate::config::AnnotationInfo;
+use crate::gui::GuiContext;
+
+const EXPORT_FORMATS: [ExportFormat; 2] = [ExportFormat::Markdown, ExportFormat::Org];
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+	Markdown,
+	Org,
+}
+
+impl ExportFormat {
+	#[inline]
+	fn name(&self) -> &'static str
+	{
+		match self {
+			ExportFormat::Markdown => "Markdown",
+			ExportFormat::Org => "Org",
+		}
+	}
+
+	#[inline]
+	fn extension(&self) -> &'static str
+	{
+		match self {
+			ExportFormat::Markdown => "md",
+			ExportFormat::Org => "org",
+		}
+	}
+}
+
+/// localized words in exported file
+struct Labels {
+	chapter: String,
+	bookmark: String,
+}
+
+/// choose format then the file, annotations of reading inner book
+/// written grouped by chapter
+pub(super) fn export(gc: &GuiContext)
+{
+	if gc.annotation_list.annotations().is_empty() {
+		gc.toast(&gc.i18n.msg("no-annotations"));
+		return;
+	}
+	let i18n = &gc.i18n;
+	let mut buttons = EXPORT_FORMATS
+		.iter()
+		.map(|format| format.name().to_owned())
+		.collect::<Vec<_>>();
+	buttons.push(i18n.msg("cancel-title").to_string());
+	let dialog = AlertDialog::builder()
+		.modal(true)
+		.message(i18n.msg("export-annotations"))
+		.detail(i18n.msg("export-annotations-format"))
+		.buttons(buttons.iter().map(|b| b.as_str()).collect::<Vec<_>>())
+		.cancel_button(EXPORT_FORMATS.len() as i32)
+		.default_button(0)
+		.build();
+	let gc1 = gc.clone();
+	dialog.choose(Some(&gc.window), None::<&Cancellable>, move |result| {
+		let Some(format) = result.ok().and_then(|index| EXPORT_FORMATS.get(index as usize)) else {
+			return;
+		};
+		let format = *format;
+		let file_dialog = FileDialog::new();
+		file_dialog.set_title(&gc1.i18n.msg("export-annotations"));
+		file_dialog.set_modal(true);
+		let name = format!("{}.{}", gc1.ctrl().reading_book_name(), format.extension());
+		file_dialog.set_initial_name(Some(&name));
+		let gc2 = gc1.clone();
+		file_dialog.save(Some(&gc1.window), None::<&Cancellable>, move |result| {
+			if let Some(path) = result.ok().and_then(|file| file.path()) {
+				save(&gc2, format, path);
+			}
+		});
+	});
+}
+
+fn save(gc: &GuiContext, format: ExportFormat, path: PathBuf)
+{
+	let labels = Labels {
+		chapter: gc.i18n.msg("annotation-chapter").to_string(),
+		bookmark: gc.i18n.msg("annotation-bookmark").to_string(),
+	};
+	let title = gc.ctrl().reading_book_name().to_owned();
+	let text = render(&title, &gc.annotation_list.annotations(), format, &labels);
+	match fs::write(&path, text) {
+		Ok(()) => gc.toast(&gc.i18n.args_msg("annotations-exported", vec![
+			("path", path.to_string_lossy()),
+		])),
+		Err(err) => gc.toast_error(&err.to_string()),
+	}
+}
+
+/// annotations in reading order, a heading for each chapter
+fn render(title: &str, annotations: &[AnnotationInfo], format: ExportFormat,
+	labels: &Labels) -> String
+{
+	let mut text = String::new();
+	heading(&mut text, 1, title, format);
+	let mut chapter = None;
+	for annotation in annotations {
+		if chapter != Some(annotation.chapter) {
+			chapter = Some(annotation.chapter);
+			let chapter_title = annotation.chapter_title
+				.as_ref()
+				.map(|title| title.trim())
+				.filter(|title| !title.is_empty())
+				.map_or_else(
+					|| format!("{} {}", labels.chapter, annotation.chapter + 1),
+					|title| title.to_owned());
+			text.push('\n');
+			heading(&mut text, 2, &chapter_title, format);
+		}
+		text.push('\n');
+		if annotation.is_bookmark() {
+			text.push_str(&format!("- {}: {}\n", labels.bookmark,
+				single_line(&annotation.text)));
+		} else {
+			quote(&mut text, &annotation.text, format);
+		}
+		if let Some(note) = &annotation.note {
+			text.push('\n');
+			for line in note.lines() {
+				text.push_str(&escape_line(line, format, false));
+				text.push('\n');
+			}
+		}
+	}
+	text
+}
+
+#[inline]
+fn heading(text: &mut String, level: usize, title: &str, format: ExportFormat)
+{
+	let mark = match format {
+		ExportFormat::Markdown => "#",
+		ExportFormat::Org => "*",
+	};
+	text.push_str(&mark.repeat(level));
+	text.push(' ');
+	text.push_str(&single_line(title));
+	text.push('\n');
+}
+
+fn quote(text: &mut String, content: &str, format: ExportFormat)
+{
+	match format {
+		ExportFormat::Markdown => for line in content.lines() {
+			text.push('>');
+			if !line.is_empty() {
+				text.push(' ');
+				text.push_str(&escape_line(line, format, true));
+			}
+			text.push('\n');
+		}
+		ExportFormat::Org => {
+			text.push_str("#+begin_quote\n");
+			for line in content.lines() {
+				text.push_str(&escape_line(line, format, true));
+				text.push('\n');
+			}
+			text.push_str("#+end_quote\n");
+		}
+	}
+}
+
+#[inline]
+fn single_line(text: &str) -> String
+{
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// keep lines of book text or notes from becoming headings or comments,
+/// escaped by comma in org blocks, and zero width space out of blocks
+fn escape_line(line: &str, format: ExportFormat, in_block: bool) -> String
+{
+	match format {
+		ExportFormat::Markdown if line.starts_with('#') => format!("\\{}", line),
+		ExportFormat::Org if line.starts_with('*') || line.starts_with('#') => if in_block {
+			format!(",{}", line)
+		} else {
+			format!("\u{200B}{}", line)
+		}
+		_ => line.to_owned(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::common::Position;
+	use crate::config::{AnnotationInfo, HighlightCategory};
+	use crate::gui::annotation_export::{ExportFormat, Labels, render};
+
+	fn annotations() -> Vec<AnnotationInfo>
+	{
+		vec![
+			AnnotationInfo::new(0, Some("Opening".to_owned()),
+				Position::new(1, 0)..Position::new(1, 5), "Hello".to_owned(), None,
+				HighlightCategory::Vocabulary),
+			AnnotationInfo::new(0, Some("Opening".to_owned()),
+				Position::new(3, 2)..Position::new(3, 2), "A line\nbookmarked".to_owned(), None,
+				HighlightCategory::default()),
+			AnnotationInfo::new(2, None,
+				Position::new(0, 0)..Position::new(1, 3), "* first\n# second".to_owned(),
+				Some("# my note".to_owned()), HighlightCategory::Quote),
+		]
+	}
+
+	fn labels() -> Labels
+	{
+		Labels {
+			chapter: "Chapter".to_owned(),
+			bookmark: "Bookmark".to_owned(),
+		}
+	}
+
+	#[test]
+	fn markdown()
+	{
+		let text = render("Book", &annotations(), ExportFormat::Markdown, &labels());
+		assert_eq!(text, "# Book
+
+## Opening
+
+> Hello
+
+- Bookmark: A line bookmarked
+
+## Chapter 3
+
+> * first
+> \\# second
+
+\\# my note
+");
+	}
+
+	#[test]
+	fn org()
+	{
+		let text = render("Book", &annotations(), ExportFormat::Org, &labels());
+		assert_eq!(text, "* Book
+
+** Opening
+
+#+begin_quote
+Hello
+#+end_quote
+
+- Bookmark: A line bookmarked
+
+** Chapter 3
+
+#+begin_quote
+,* first
+,# second
+#+end_quote
+
+\u{200B}# my note
+");
+	}
+}
@@ -0,0 +1,320 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::rc::Rc;
+
+use anyhow::Result;
+use gtk4::{Align, DropDown, Label, ListBox, ListBoxRow, Orientation, PolicyType, SelectionMode};
+use gtk4::pango::{EllipsizeMode, WrapMode};
+use gtk4::prelude::{BoxExt, ButtonExt, ListBoxRowExt, WidgetExt};
+
+use crate::color::Color32;
+use crate::config::{AnnotationInfo, Configuration, HighlightCategory};
+use crate::gui::{create_button, GuiController, IconMap};
+use crate::gui::render::AnnotationMark;
+use crate::i18n::I18n;
+
+// lines of highlighted text in list
+const ANNOTATION_TEXT_LINES: i32 = 3;
+// alpha of highlight backgrounds, readable with both bright and dark themes
+const HIGHLIGHT_ALPHA: u8 = 96;
+
+/// annotations listed, selected in the dropdown
+#[derive(Clone, Copy)]
+enum AnnotationFilter {
+	All,
+	Category(HighlightCategory),
+	Bookmark,
+}
+
+impl AnnotationFilter {
+	/// all, then the categories, bookmarks at last
+	fn from_index(index: usize) -> Self
+	{
+		match index {
+			0 => AnnotationFilter::All,
+			index if index <= HighlightCategory::ALL.len() =>
+				AnnotationFilter::Category(HighlightCategory::ALL[index - 1]),
+			_ => AnnotationFilter::Bookmark,
+		}
+	}
+
+	fn matches(&self, annotation: &AnnotationInfo) -> bool
+	{
+		match self {
+			AnnotationFilter::All => true,
+			AnnotationFilter::Category(category) =>
+				!annotation.is_bookmark() && annotation.category == *category,
+			AnnotationFilter::Bookmark => annotation.is_bookmark(),
+		}
+	}
+}
+
+struct AnnotationListInner {
+	list: ListBox,
+	ctrl: Rc<RefCell<GuiController>>,
+	cfg: Rc<RefCell<Configuration>>,
+	entries: RefCell<Vec<AnnotationInfo>>,
+	// indices of entries listed with the filter
+	shown: RefCell<Vec<usize>>,
+	filter: Cell<AnnotationFilter>,
+	// inner book of loaded annotations
+	loaded: Cell<Option<usize>>,
+	syncing: Cell<bool>,
+	annotation_clicked: RefCell<Option<Rc<dyn Fn(&AnnotationInfo)>>>,
+	annotation_remove: RefCell<Option<Rc<dyn Fn(i64)>>>,
+	icons: Rc<IconMap>,
+	i18n: Rc<I18n>,
+}
+
+/// highlights, notes and bookmarks of reading inner book
+#[derive(Clone)]
+pub struct AnnotationList {
+	inner: Rc<AnnotationListInner>,
+}
+
+impl AnnotationList {
+	pub fn create(icons: &Rc<IconMap>, i18n: &Rc<I18n>,
+		ctrl: &Rc<RefCell<GuiController>>, cfg: &Rc<RefCell<Configuration>>)
+		-> (Self, gtk4::Box)
+	{
+		let list = ListBox::builder()
+			.selection_mode(SelectionMode::Single)
+			.build();
+		list.add_css_class("navigation-sidebar");
+		list.add_css_class("boxed-list");
+		list.set_placeholder(Some(&Label::new(Some(&i18n.msg("no-annotations")))));
+
+		let mut filter_names = vec![i18n.msg("annotation-filter-all")];
+		for category in HighlightCategory::ALL {
+			filter_names.push(i18n.msg(category.i18n_key()));
+		}
+		filter_names.push(i18n.msg("annotation-filter-bookmarks"));
+		let filter_names = filter_names
+			.iter()
+			.map(|name| name.as_ref())
+			.collect::<Vec<_>>();
+		let filter_dropdown = DropDown::from_strings(&filter_names);
+
+		let container = gtk4::Box::builder()
+			.orientation(Orientation::Vertical)
+			.spacing(0)
+			.vexpand(true)
+			.build();
+		container.append(&filter_dropdown);
+		container.append(&gtk4::ScrolledWindow::builder()
+			.child(&list)
+			.hscrollbar_policy(PolicyType::Never)
+			.vexpand(true)
+			.build());
+
+		let annotation_list = AnnotationList {
+			inner: Rc::new(AnnotationListInner {
+				list,
+				ctrl: ctrl.clone(),
+				cfg: cfg.clone(),
+				entries: RefCell::new(vec![]),
+				shown: RefCell::new(vec![]),
+				filter: Cell::new(AnnotationFilter::All),
+				loaded: Cell::new(None),
+				syncing: Cell::new(false),
+				annotation_clicked: RefCell::new(None),
+				annotation_remove: RefCell::new(None),
+				icons: icons.clone(),
+				i18n: i18n.clone(),
+			})
+		};
+		{
+			let annotation_list2 = annotation_list.clone();
+			filter_dropdown.connect_selected_notify(move |dropdown| {
+				let filter = AnnotationFilter::from_index(dropdown.selected() as usize);
+				annotation_list2.inner.filter.replace(filter);
+				annotation_list2.reload_rows();
+			});
+		}
+		{
+			let annotation_list2 = annotation_list.clone();
+			annotation_list.inner.list.connect_row_selected(move |_, row| {
+				if annotation_list2.inner.syncing.get() {
+					return;
+				}
+				if let Some(row) = row {
+					let row_index = row.index();
+					if row_index >= 0 {
+						annotation_list2.clicked(row_index as usize);
+					}
+				}
+			});
+		}
+		(annotation_list, container)
+	}
+
+	#[inline]
+	pub fn handle_annotation_click<F>(&self, annotation_clicked: F)
+		where F: Fn(&AnnotationInfo) + 'static
+	{
+		self.inner.annotation_clicked.replace(Some(Rc::new(annotation_clicked)));
+	}
+
+	/// callback with id of the annotation to remove
+	#[inline]
+	pub fn handle_annotation_remove<F>(&self, annotation_remove: F)
+		where F: Fn(i64) + 'static
+	{
+		self.inner.annotation_remove.replace(Some(Rc::new(annotation_remove)));
+	}
+
+	/// reload annotations if inner book changed or forced,
+	/// true if reloaded
+	pub fn sync_annotations(&self, force: bool) -> Result<bool>
+	{
+		let controller = self.inner.ctrl.borrow();
+		let inner_book = controller.reading.inner_book;
+		if !force && self.inner.loaded.get() == Some(inner_book) {
+			return Ok(false);
+		}
+		let annotations = self.inner.cfg.borrow()
+			.annotations(&controller.reading.filename, inner_book)?;
+		drop(controller);
+		self.inner.loaded.replace(Some(inner_book));
+		self.inner.entries.replace(annotations);
+		self.reload_rows();
+		Ok(true)
+	}
+
+	#[inline]
+	pub fn annotations(&self) -> Ref<'_, Vec<AnnotationInfo>>
+	{
+		self.inner.entries.borrow()
+	}
+
+	/// backgrounds of highlighted text for render
+	pub fn marks(&self) -> Vec<AnnotationMark>
+	{
+		self.inner.entries
+			.borrow()
+			.iter()
+			.filter(|annotation| !annotation.is_bookmark())
+			.map(|annotation| AnnotationMark {
+				chapter: annotation.chapter,
+				range: annotation.range.clone(),
+				color: category_color(annotation.category),
+			})
+			.collect()
+	}
+
+	fn reload_rows(&self)
+	{
+		let filter = self.inner.filter.get();
+		let entries = self.inner.entries.borrow();
+		let mut shown = vec![];
+		let mut rows = vec![];
+		for (index, annotation) in entries.iter().enumerate() {
+			if filter.matches(annotation) {
+				shown.push(index);
+				rows.push(self.create_row(annotation));
+			}
+		}
+		drop(entries);
+		self.inner.shown.replace(shown);
+		let list = &self.inner.list;
+		self.inner.syncing.replace(true);
+		list.remove_all();
+		for row in rows {
+			list.append(&row);
+		}
+		self.inner.syncing.replace(false);
+	}
+
+	fn create_row(&self, annotation: &AnnotationInfo) -> ListBoxRow
+	{
+		let text = if annotation.is_bookmark() {
+			format!("{}: {}", self.inner.i18n.msg("annotation-bookmark"), annotation.text)
+		} else {
+			annotation.text.clone()
+		};
+		let label = Label::builder()
+			.label(&text)
+			.halign(Align::Start)
+			.hexpand(true)
+			.xalign(0.)
+			.wrap(true)
+			.wrap_mode(WrapMode::WordChar)
+			.lines(ANNOTATION_TEXT_LINES)
+			.ellipsize(EllipsizeMode::End)
+			.tooltip_text(&text)
+			.build();
+		let content = gtk4::Box::new(Orientation::Vertical, 2);
+		content.append(&label);
+		if let Some(note) = &annotation.note {
+			let note_label = Label::builder()
+				.label(note)
+				.halign(Align::Start)
+				.xalign(0.)
+				.wrap(true)
+				.wrap_mode(WrapMode::WordChar)
+				.css_classes(vec!["dim-label"])
+				.build();
+			content.append(&note_label);
+		}
+		let view = gtk4::Box::new(Orientation::Horizontal, 4);
+		if !annotation.is_bookmark() {
+			let color = category_color(annotation.category);
+			let tooltip = self.inner.i18n.msg(annotation.category.i18n_key());
+			let swatch = Label::builder()
+				.use_markup(true)
+				.label(format!("<span foreground=\"#{:02x}{:02x}{:02x}\">●</span>",
+					color.r(), color.g(), color.b()))
+				.valign(Align::Start)
+				.tooltip_text(tooltip.as_ref())
+				.build();
+			view.append(&swatch);
+		}
+		view.append(&content);
+		let tooltip = self.inner.i18n.msg("annotation-remove");
+		let button = create_button("remove.svg", Some(&tooltip), &self.inner.icons, true);
+		{
+			let annotation_list = self.clone();
+			let id = annotation.id;
+			button.connect_clicked(move |_| annotation_list.remove(id));
+		}
+		view.append(&button);
+		let row = ListBoxRow::new();
+		row.set_child(Some(&view));
+		row
+	}
+
+	fn clicked(&self, row_index: usize)
+	{
+		let Some(index) = self.inner.shown.borrow().get(row_index).copied() else {
+			return;
+		};
+		let entries = self.inner.entries.borrow();
+		let Some(annotation) = entries.get(index).cloned() else {
+			return;
+		};
+		drop(entries);
+		let callback = self.inner.annotation_clicked.borrow().clone();
+		if let Some(callback) = callback {
+			callback(&annotation);
+		}
+	}
+
+	fn remove(&self, id: i64)
+	{
+		let callback = self.inner.annotation_remove.borrow().clone();
+		if let Some(callback) = callback {
+			callback(id);
+		}
+	}
+}
+
+/// background of highlights by category
+#[inline]
+fn category_color(category: HighlightCategory) -> Color32
+{
+	match category {
+		HighlightCategory::Vocabulary => Color32::from_rgba_premultiplied(255, 215, 0, HIGHLIGHT_ALPHA),
+		HighlightCategory::Quote => Color32::from_rgba_premultiplied(76, 175, 80, HIGHLIGHT_ALPHA),
+		HighlightCategory::Idea => Color32::from_rgba_premultiplied(33, 150, 243, HIGHLIGHT_ALPHA),
+		HighlightCategory::Question => Color32::from_rgba_premultiplied(233, 30, 99, HIGHLIGHT_ALPHA),
+	}
+}
@@ -1,14 +1,18 @@
 use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
-use gtk4::{Align, gdk, GestureClick, Label, ListBox, ListBoxRow, Orientation, PolicyType, SearchEntry, SelectionMode};
+use gtk4::{Align, Button, FlowBox, gdk, GestureClick, Label, ListBox, ListBoxRow, Orientation, PolicyType, SearchEntry, SelectionMode};
 use gtk4::graphene::Point;
 use gtk4::pango::EllipsizeMode;
-use gtk4::prelude::{AdjustmentExt, BoxExt, EditableExt, ListBoxRowExt, WidgetExt};
+use gtk4::prelude::{AdjustmentExt, BoxExt, ButtonExt, EditableExt, ListBoxRowExt, WidgetExt};
 use crate::gui::{GuiController, ChapterListSyncMode, IconMap, load_button_image};
 use crate::i18n::I18n;
 
 pub const BOOK_NAME_LABEL_CLASS: &str = "book-name";
 pub const TOC_LABEL_CLASS: &str = "toc";
+const TOC_PROGRESS_CLASS: &str = "toc-progress";
+const TOC_READ_MARK: &str = "✓";
+const TOC_PARTIALLY_READ_MARK: &str = "◐";
 
 struct ChapterListEntry {
 	title: String,
@@ -16,6 +20,8 @@ struct ChapterListEntry {
 	index: usize,
 	level: usize,
 	reading: bool,
+	// mark of read through or partially read, only for toc entry
+	progress: Option<Label>,
 }
 
 impl ChapterListEntry {
@@ -27,17 +33,29 @@ impl ChapterListEntry {
 			level,
 			index,
 			reading,
+			progress: None,
 		}
 	}
 }
 
+/// toc entries visited of reading book, true for read through
+#[derive(Default)]
+struct TocProgress {
+	// (filename, inner book) of loaded progress
+	loaded: Option<(String, usize)>,
+	entries: HashMap<usize, bool>,
+}
+
 struct ChapterListInner {
 	collapse: Cell<bool>,
 	list: ListBox,
+	landmarks: FlowBox,
+	landmark_clicked: RefCell<Option<Rc<dyn Fn(usize)>>>,
 	filter_input: SearchEntry,
 	ctrl: Rc<RefCell<GuiController>>,
 	syncing: Cell<bool>,
 	rows: RefCell<Vec<ChapterListEntry>>,
+	progress: RefCell<TocProgress>,
 	icons: Rc<IconMap>,
 }
 
@@ -70,7 +88,13 @@ impl ChapterList {
 			.spacing(0)
 			.vexpand(true)
 			.build();
+		let landmarks = FlowBox::builder()
+			.selection_mode(SelectionMode::None)
+			.max_children_per_line(4)
+			.visible(false)
+			.build();
 		container.append(&filter_input);
+		container.append(&landmarks);
 		container.append(&gtk4::ScrolledWindow::builder()
 			.child(&list)
 			.hscrollbar_policy(PolicyType::Never)
@@ -81,10 +105,13 @@ impl ChapterList {
 			inner: Rc::new(ChapterListInner {
 				collapse,
 				list,
+				landmarks,
+				landmark_clicked: RefCell::new(None),
 				filter_input: filter_input.clone(),
 				ctrl: ctrl.clone(),
 				syncing,
 				rows,
+				progress: RefCell::new(TocProgress::default()),
 				icons: icons.clone(),
 			})
 		};
@@ -158,6 +185,12 @@ impl ChapterList {
 		});
 	}
 
+	pub fn handle_landmark_click<F>(&self, landmark_clicked: F)
+		where F: Fn(usize) + 'static
+	{
+		self.inner.landmark_clicked.replace(Some(Rc::new(landmark_clicked)));
+	}
+
 	pub fn handle_cancel<F>(&self, cancel: F)
 		where F: Fn(bool) + 'static
 	{
@@ -243,6 +276,56 @@ impl ChapterList {
 		self.block_reactive(false);
 	}
 
+	#[inline]
+	pub(super) fn progress_loaded(&self, filename: &str, inner_book: usize) -> bool
+	{
+		self.inner.progress.borrow().loaded.as_ref()
+			.map_or(false, |(loaded_filename, loaded_inner_book)|
+				loaded_filename == filename && *loaded_inner_book == inner_book)
+	}
+
+	pub(super) fn set_progress(&self, filename: &str, inner_book: usize,
+		entries: HashMap<usize, bool>)
+	{
+		self.inner.progress.replace(TocProgress {
+			loaded: Some((filename.to_owned(), inner_book)),
+			entries,
+		});
+		self.refresh_progress();
+	}
+
+	/// toc entries from first to last shown, those before last read through
+	pub(super) fn mark_progress(&self, first: usize, last: usize, last_finished: bool)
+	{
+		let mut progress = self.inner.progress.borrow_mut();
+		let mut changed = false;
+		for toc_index in first..=last {
+			let finished = toc_index < last || last_finished;
+			let entry = progress.entries.entry(toc_index).or_insert_with(|| {
+				changed = true;
+				finished
+			});
+			if finished && !*entry {
+				*entry = true;
+				changed = true;
+			}
+		}
+		drop(progress);
+		if changed {
+			self.refresh_progress();
+		}
+	}
+
+	fn refresh_progress(&self)
+	{
+		let progress = self.inner.progress.borrow();
+		for entry in self.inner.rows.borrow().iter() {
+			if let Some(label) = &entry.progress {
+				label.set_label(progress_mark(progress.entries.get(&entry.index)));
+			}
+		}
+	}
+
 	#[inline]
 	fn ctrl(&self) -> Ref<GuiController>
 	{
@@ -296,11 +379,15 @@ pub fn load_entries(chapter_list: &ChapterList)
 		}
 		toc_level = 0;
 	}
+	load_landmarks(chapter_list, &controller);
+	let progress = chapter_list.inner.progress.borrow();
 	let mut rows = vec![];
-	for entry in entries.iter() {
-		let row = create_list_row(&entry, toc_level, icons);
+	for entry in entries.iter_mut() {
+		let row = create_list_row(entry, toc_level, icons,
+			progress.entries.get(&entry.index));
 		rows.push(row);
 	}
+	drop(progress);
 	drop(entries);
 	list.remove_all();
 	for row in rows {
@@ -332,7 +419,45 @@ pub fn load_entries(chapter_list: &ChapterList)
 	}
 }
 
-fn create_list_row(entry: &ChapterListEntry, toc_level: usize, icons: &IconMap) -> ListBoxRow
+fn load_landmarks(chapter_list: &ChapterList, controller: &GuiController)
+{
+	let landmarks_box = &chapter_list.inner.landmarks;
+	landmarks_box.remove_all();
+	let Some(landmarks) = controller.book.landmarks() else {
+		landmarks_box.set_visible(false);
+		return;
+	};
+	for landmark in landmarks {
+		let button = Button::builder()
+			.label(landmark.title)
+			.tooltip_text(landmark.kind)
+			.build();
+		button.add_css_class("flat");
+		let index = landmark.index;
+		let chapter_list = chapter_list.clone();
+		button.connect_clicked(move |_| {
+			let callback = chapter_list.inner.landmark_clicked.borrow().clone();
+			if let Some(callback) = callback {
+				callback(index);
+			}
+		});
+		landmarks_box.append(&button);
+	}
+	landmarks_box.set_visible(true);
+}
+
+#[inline]
+fn progress_mark(finished: Option<&bool>) -> &'static str
+{
+	match finished {
+		Some(true) => TOC_READ_MARK,
+		Some(false) => TOC_PARTIALLY_READ_MARK,
+		None => "",
+	}
+}
+
+fn create_list_row(entry: &mut ChapterListEntry, toc_level: usize, icons: &IconMap,
+	finished: Option<&bool>) -> ListBoxRow
 {
 	let title = &entry.title;
 	let label = Label::builder()
@@ -366,6 +491,16 @@ fn create_list_row(entry: &ChapterListEntry, toc_level: usize, icons: &IconMap)
 
 	view.append(&icon);
 	view.append(&label);
+	if !entry.book {
+		let progress = Label::builder()
+			.label(progress_mark(finished))
+			.halign(Align::End)
+			.hexpand(true)
+			.css_classes(vec![TOC_PROGRESS_CLASS, "dim-label"])
+			.build();
+		view.append(&progress);
+		entry.progress = Some(progress);
+	}
 
 	let row = ListBoxRow::new();
 	row.set_child(Some(&view));
@@ -1,12 +1,19 @@
 use std::borrow::Cow;
 
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
 use anyhow::Result;
-use gtk4::{Align, Button, Entry, EventControllerKey, glib, Orientation, ScrolledWindow, Separator, TextBuffer, TextView, Widget, Window};
-use gtk4::gdk::Key;
-use gtk4::prelude::{BoxExt, ButtonExt, EditableExt, EntryExt, GtkWindowExt, IsA, TextBufferExt, WidgetExt};
+use gtk4::{Align, Application, Button, CheckButton, ContentFit, DropDown, Entry, EventControllerKey, GestureZoom, glib, Grid, Label, ListBox, ListBoxRow, Orientation, PasswordEntry, Picture, PolicyType, ScrolledWindow, SearchEntry, SelectionMode, Separator, SpinButton, TextBuffer, TextView, Widget, Window, WrapMode};
+use gtk4::gdk::{Key, Texture};
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::{BoxExt, ButtonExt, Cast, CheckButtonExt, EditableExt, EntryExt, GestureExt, GridExt, GtkApplicationExt, GtkWindowExt, IsA, ListBoxRowExt, TextBufferExt, WidgetExt};
 
+use crate::common::fuzzy_score;
 use crate::gui::{alert, GuiContext, MODIFIER_NONE};
 use crate::html_parser;
+use crate::i18n::I18n;
 
 pub(crate) fn custom_styles<F>(style: &Option<String>, gc: &GuiContext,
 	main_win: &impl IsA<Window>, callback: F)
@@ -65,6 +72,549 @@ pub(crate) fn goto<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
 	entry.connect_activate(move |_| ok_btn.emit_clicked());
 }
 
+/// new name of file, without folder
+pub(crate) fn rename<F>(gc: &GuiContext, name: &str, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&str) -> Result<()> + 'static
+{
+	let entry = Entry::builder()
+		.text(name)
+		.width_chars(40)
+		.build();
+	let ok_btn = input_dialog(&entry, "rename-dialog-title", gc, main_win, move |gc, entry| {
+		let text = entry.text();
+		let name = text.trim();
+		if name.is_empty() || name.contains(std::path::MAIN_SEPARATOR) {
+			return Err(gc.i18n.msg("invalid-format"));
+		}
+		callback(name)
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+	entry.connect_activate(move |_| ok_btn.emit_clicked());
+}
+
+/// tags separated by space or comma
+pub(crate) fn tags<F>(gc: &GuiContext, tags: &str, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&str) -> Result<()> + 'static
+{
+	let entry = Entry::builder()
+		.text(tags)
+		.width_chars(40)
+		.build();
+	let ok_btn = input_dialog(&entry, "tags-dialog-title", gc, main_win, move |_, entry| {
+		callback(entry.text().as_str())
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+	entry.connect_activate(move |_| ok_btn.emit_clicked());
+}
+
+/// chapter range from 1 and index of the paper size for printing
+pub(crate) fn print_range<F>(gc: &GuiContext, chapter_count: usize, current: usize,
+	papers: &[String], main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&GuiContext, usize, usize, usize) + 'static
+{
+	let i18n = &gc.i18n;
+	let grid = Grid::builder()
+		.row_spacing(10)
+		.column_spacing(10)
+		.build();
+	let max = chapter_count.max(1) as f64;
+	let first = SpinButton::with_range(1., max, 1.);
+	first.set_value((current + 1) as f64);
+	let last = SpinButton::with_range(1., max, 1.);
+	last.set_value((current + 1) as f64);
+	let paper_names = papers.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+	let paper = DropDown::from_strings(&paper_names);
+	for (row, (key, widget)) in [
+		("print-first-chapter", first.upcast_ref::<Widget>()),
+		("print-last-chapter", last.upcast_ref()),
+		("print-paper-size", paper.upcast_ref()),
+	].into_iter().enumerate() {
+		grid.attach(&Label::builder()
+			.label(i18n.msg(key))
+			.halign(Align::Start)
+			.build(), 0, row as i32, 1, 1);
+		grid.attach(widget, 1, row as i32, 1, 1);
+	}
+	input_dialog(&grid, "export-pdf", gc, main_win, move |gc, _| {
+		let first = first.value_as_int() as usize;
+		let last = last.value_as_int() as usize;
+		if first > last {
+			return Err(gc.i18n.msg("invalid-chapter-range"));
+		}
+		callback(gc, first - 1, last - 1, paper.selected() as usize);
+		Ok(())
+	});
+}
+
+/// password of encrypted book, asked before any window of the book created
+pub(crate) fn password<F>(app: &Application, i18n: &I18n, filename: &str, retry: bool, callback: F)
+	where F: Fn(&Application, String, bool) + 'static
+{
+	let main = gtk4::Box::new(Orientation::Vertical, 10);
+	main.set_margin_top(10);
+	main.set_margin_bottom(10);
+	main.set_margin_start(10);
+	main.set_margin_end(10);
+	let dialog = Window::builder()
+		.application(app)
+		.title(i18n.msg("password-title"))
+		.resizable(false)
+		.modal(true)
+		.child(&main)
+		.build();
+	if let Some(window) = app.active_window() {
+		dialog.set_transient_for(Some(&window));
+	}
+
+	let key = if retry { "invalid-password" } else { "password-required" };
+	main.append(&Label::builder()
+		.label(i18n.args_msg(key, vec![("name", filename)]))
+		.halign(Align::Start)
+		.wrap(true)
+		.build());
+	let entry = PasswordEntry::builder()
+		.show_peek_icon(true)
+		.activates_default(true)
+		.build();
+	main.append(&entry);
+	let remember = CheckButton::with_label(&i18n.msg("remember-password"));
+	main.append(&remember);
+
+	main.append(&Separator::new(Orientation::Horizontal));
+
+	let button_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	button_box.set_halign(Align::End);
+	let ok_btn = Button::builder()
+		.label(i18n.msg("ok-title"))
+		.build();
+	{
+		let dialog = dialog.clone();
+		let app = app.clone();
+		let entry = entry.clone();
+		ok_btn.connect_clicked(move |_| {
+			let password = entry.text();
+			if password.is_empty() {
+				return;
+			}
+			// book window created before the dialog closed, so the app kept running
+			callback(&app, password.to_string(), remember.is_active());
+			dialog.close();
+		});
+		button_box.append(&ok_btn);
+	}
+	{
+		let dialog = dialog.clone();
+		let cancel_btn = Button::builder()
+			.label(i18n.msg("cancel-title"))
+			.build();
+		cancel_btn.connect_clicked(move |_| {
+			dialog.close();
+		});
+		button_box.append(&cancel_btn);
+	}
+	main.append(&button_box);
+	dialog.set_default_widget(Some(&ok_btn));
+
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if key == Key::Escape && modifier == MODIFIER_NONE {
+				dialog.close();
+				glib::Propagation::Stop
+			} else {
+				glib::Propagation::Proceed
+			}
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+	entry.grab_focus();
+}
+
+/// loader chosen for file of unknown extension
+pub(crate) fn format<F>(app: &Application, i18n: &I18n, filename: &str, formats: Vec<&str>, callback: F)
+	where F: Fn(&Application, String) + 'static
+{
+	let main = gtk4::Box::new(Orientation::Vertical, 10);
+	main.set_margin_top(10);
+	main.set_margin_bottom(10);
+	main.set_margin_start(10);
+	main.set_margin_end(10);
+	let dialog = Window::builder()
+		.application(app)
+		.title(i18n.msg("format-title"))
+		.resizable(false)
+		.modal(true)
+		.child(&main)
+		.build();
+	if let Some(window) = app.active_window() {
+		dialog.set_transient_for(Some(&window));
+	}
+
+	main.append(&Label::builder()
+		.label(i18n.args_msg("format-required", vec![("name", filename)]))
+		.halign(Align::Start)
+		.wrap(true)
+		.build());
+	let dropdown = DropDown::from_strings(&formats);
+	main.append(&dropdown);
+
+	main.append(&Separator::new(Orientation::Horizontal));
+
+	let button_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	button_box.set_halign(Align::End);
+	let ok_btn = Button::builder()
+		.label(i18n.msg("ok-title"))
+		.build();
+	{
+		let dialog = dialog.clone();
+		let app = app.clone();
+		let formats = formats.iter().map(|format| format.to_string()).collect::<Vec<_>>();
+		ok_btn.connect_clicked(move |_| {
+			let Some(format) = formats.get(dropdown.selected() as usize) else {
+				return;
+			};
+			callback(&app, format.clone());
+			dialog.close();
+		});
+		button_box.append(&ok_btn);
+	}
+	{
+		let dialog = dialog.clone();
+		let cancel_btn = Button::builder()
+			.label(i18n.msg("cancel-title"))
+			.build();
+		cancel_btn.connect_clicked(move |_| {
+			dialog.close();
+		});
+		button_box.append(&cancel_btn);
+	}
+	main.append(&button_box);
+	dialog.set_default_widget(Some(&ok_btn));
+
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if key == Key::Escape && modifier == MODIFIER_NONE {
+				dialog.close();
+				glib::Propagation::Stop
+			} else {
+				glib::Propagation::Proceed
+			}
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+	ok_btn.grab_focus();
+}
+
+#[inline]
+pub(crate) fn goto_page<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&str) -> Result<()> + 'static
+{
+	let entry = Entry::builder()
+		.placeholder_text(gc.i18n.msg("goto-page-placeholder"))
+		.build();
+	let ok_btn = input_dialog(&entry, "goto-page-dialog-title", gc, main_win, move |gc, entry| {
+		let text = entry.text();
+		let label = text.trim();
+		if label.is_empty() {
+			return Err(gc.i18n.msg("invalid-format"));
+		}
+		callback(label)
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+	entry.connect_activate(move |_| ok_btn.emit_clicked());
+}
+
+/// note for the highlighted text
+pub(crate) fn annotation_note<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&str) -> Result<()> + 'static
+{
+	let buf = TextBuffer::builder()
+		.enable_undo(true)
+		.build();
+	let text = TextView::builder()
+		.buffer(&buf)
+		.editable(true)
+		.wrap_mode(WrapMode::WordChar)
+		.height_request(150)
+		.width_request(400)
+		.build();
+	input_dialog(&text, "annotation-note-title", gc, main_win, move |gc, _| {
+		let (start, end) = buf.bounds();
+		let note = buf.text(&start, &end, true);
+		let note = note.trim();
+		if note.is_empty() {
+			return Err(gc.i18n.msg("annotation-note-empty"));
+		}
+		callback(note)
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+}
+
+/// pick inner book by name, fuzzy matched and best first
+pub(crate) fn inner_books<F>(gc: &GuiContext, main_win: &impl IsA<Window>,
+	names: Vec<String>, current: usize, callback: F)
+	where F: Fn(usize) + 'static
+{
+	let entry = SearchEntry::builder()
+		.placeholder_text(gc.i18n.msg("filter-inner-book"))
+		.build();
+	let list = ListBox::builder()
+		.selection_mode(SelectionMode::Browse)
+		.build();
+	list.add_css_class("navigation-sidebar");
+	let main = gtk4::Box::new(Orientation::Vertical, 10);
+	main.set_margin_top(10);
+	main.set_margin_bottom(10);
+	main.set_margin_start(10);
+	main.set_margin_end(10);
+	main.append(&entry);
+	main.append(&ScrolledWindow::builder()
+		.child(&list)
+		.hscrollbar_policy(PolicyType::Never)
+		.vexpand(true)
+		.build());
+	let dialog = Window::builder()
+		.title(gc.i18n.msg("inner-books-dialog-title"))
+		.transient_for(main_win)
+		.modal(true)
+		.default_width(400)
+		.default_height(500)
+		.child(&main)
+		.build();
+
+	// inner book index of rows
+	let shown = Rc::new(RefCell::new(vec![]));
+	let fill = {
+		let list = list.clone();
+		let shown = shown.clone();
+		move |pattern: &str| {
+			let mut matched = names
+				.iter()
+				.enumerate()
+				.filter_map(|(index, name)| fuzzy_score(pattern, name)
+					.map(|score| (index, score)))
+				.collect::<Vec<_>>();
+			// stable sort keeps the book order for same score
+			matched.sort_by(|(_, a), (_, b)| b.cmp(a));
+			list.remove_all();
+			for (index, _) in &matched {
+				let label = Label::builder()
+					.label(&names[*index])
+					.halign(Align::Start)
+					.ellipsize(EllipsizeMode::End)
+					.build();
+				list.append(&ListBoxRow::builder().child(&label).build());
+			}
+			let selected = if pattern.is_empty() {
+				matched.iter().position(|(index, _)| *index == current).unwrap_or(0)
+			} else {
+				0
+			};
+			if let Some(row) = list.row_at_index(selected as i32) {
+				list.select_row(Some(&row));
+			}
+			shown.replace(matched.into_iter().map(|(index, _)| index).collect());
+		}
+	};
+	fill("");
+	entry.connect_search_changed(move |entry| fill(entry.text().trim()));
+
+	let callback = Rc::new(callback);
+	let activate = {
+		let dialog = dialog.clone();
+		move |row: &ListBoxRow| {
+			let index = shown.borrow().get(row.index() as usize).cloned();
+			if let Some(index) = index {
+				dialog.close();
+				callback(index);
+			}
+		}
+	};
+	let activate = Rc::new(activate);
+	{
+		let list2 = list.clone();
+		let activate = activate.clone();
+		entry.connect_activate(move |_| if let Some(row) = list2.selected_row() {
+			activate(&row);
+		});
+	}
+	list.connect_row_activated(move |_, row| activate(row));
+	{
+		// arrow keys move selection while typing
+		let list = list.clone();
+		entry.connect_next_match(move |_| move_selection(&list, 1));
+	}
+	{
+		let list = list.clone();
+		entry.connect_previous_match(move |_| move_selection(&list, -1));
+	}
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if modifier != MODIFIER_NONE {
+				return glib::Propagation::Proceed;
+			}
+			match key {
+				Key::Escape => dialog.close(),
+				Key::Down => move_selection(&list, 1),
+				Key::Up => move_selection(&list, -1),
+				_ => return glib::Propagation::Proceed,
+			}
+			glib::Propagation::Stop
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+	entry.grab_focus();
+}
+
+#[inline]
+fn move_selection(list: &ListBox, delta: i32)
+{
+	let index = list.selected_row().map_or(0, |row| row.index() + delta);
+	if let Some(row) = list.row_at_index(index.max(0)) {
+		list.select_row(Some(&row));
+		row.grab_focus();
+	}
+}
+
+const MIN_IMAGE_SCALE: f64 = 0.1;
+const MAX_IMAGE_SCALE: f64 = 10.;
+const IMAGE_SCALE_STEP: f64 = 1.25;
+
+/// view image in a window, zoom with pinch or +/- keys
+pub(crate) fn image_zoom(pixbuf: &Pixbuf, title: &str, main_win: &impl IsA<Window>)
+{
+	let width = pixbuf.width() as f64;
+	let height = pixbuf.height() as f64;
+	let picture = Picture::builder()
+		.paintable(&Texture::for_pixbuf(pixbuf))
+		.content_fit(ContentFit::Contain)
+		.width_request(width as i32)
+		.height_request(height as i32)
+		.build();
+	let scroll_view = ScrolledWindow::builder()
+		.child(&picture)
+		.hexpand(true)
+		.vexpand(true)
+		.build();
+	let dialog = Window::builder()
+		.title(title)
+		.transient_for(main_win)
+		.default_width(800)
+		.default_height(600)
+		.child(&scroll_view)
+		.build();
+
+	let scale = Rc::new(Cell::new(1.));
+	let zoom = {
+		let picture = picture.clone();
+		let scale = scale.clone();
+		move |value: f64| {
+			let value = value.clamp(MIN_IMAGE_SCALE, MAX_IMAGE_SCALE);
+			scale.replace(value);
+			picture.set_size_request((width * value) as i32, (height * value) as i32);
+		}
+	};
+	let zoom = Rc::new(zoom);
+
+	let gesture = GestureZoom::new();
+	{
+		let base = Rc::new(Cell::new(1.));
+		let scale = scale.clone();
+		let base2 = base.clone();
+		gesture.connect_begin(move |_, _| {
+			base2.replace(scale.get());
+		});
+		let zoom = zoom.clone();
+		gesture.connect_scale_changed(move |_, delta| zoom(base.get() * delta));
+	}
+	scroll_view.add_controller(gesture);
+
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if modifier != MODIFIER_NONE {
+				return glib::Propagation::Proceed;
+			}
+			match key {
+				Key::Escape => dialog.close(),
+				Key::plus | Key::equal => zoom(scale.get() * IMAGE_SCALE_STEP),
+				Key::minus => zoom(scale.get() / IMAGE_SCALE_STEP),
+				Key::_0 => zoom(1.),
+				_ => return glib::Propagation::Proceed,
+			}
+			glib::Propagation::Stop
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+}
+
+/// original text and its translation, both selectable
+pub(crate) fn translation(text: &str, translation: &str, title: &str,
+	main_win: &impl IsA<Window>)
+{
+	let main = gtk4::Box::new(Orientation::Vertical, 10);
+	main.set_margin_top(10);
+	main.set_margin_bottom(10);
+	main.set_margin_start(10);
+	main.set_margin_end(10);
+	let original = Label::builder()
+		.label(text)
+		.wrap(true)
+		.selectable(true)
+		.xalign(0.)
+		.css_classes(vec!["dim-label"])
+		.build();
+	main.append(&original);
+	main.append(&Separator::new(Orientation::Horizontal));
+	let translated = Label::builder()
+		.label(translation)
+		.wrap(true)
+		.selectable(true)
+		.xalign(0.)
+		.valign(Align::Start)
+		.build();
+	main.append(&translated);
+	let dialog = Window::builder()
+		.title(title)
+		.transient_for(main_win)
+		.default_width(500)
+		.child(&ScrolledWindow::builder()
+			.child(&main)
+			.propagate_natural_height(true)
+			.max_content_height(600)
+			.build())
+		.build();
+
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if key == Key::Escape && modifier == MODIFIER_NONE {
+				dialog.close();
+				glib::Propagation::Stop
+			} else {
+				glib::Propagation::Proceed
+			}
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+}
+
 fn input_dialog<F, W>(widget: &W, title: &str,
 	gc: &GuiContext, main_win: &impl IsA<Window>, callback: F) -> Button
 	where
@@ -3,28 +3,36 @@ use std::cell::RefCell;
 use std::cmp::max;
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::time::Duration;
 use elsa::FrozenMap;
 use fancy_regex::{Regex, Captures};
-use gtk4::{Button, EventControllerKey, Orientation, ScrolledWindow, SearchEntry};
+use gtk4::{Button, DropDown, EventControllerKey, MediaFile, Orientation, ScrolledWindow, SearchEntry, StringList, StringObject};
 use gtk4::gdk::{Key, ModifierType};
-use gtk4::glib::closure_local;
+use gtk4::glib::{closure_local, ControlFlow};
 use gtk4::glib;
-use gtk4::prelude::{BoxExt, ButtonExt, DrawingAreaExt, EditableExt, ObjectExt, WidgetExt};
+use gtk4::prelude::{BoxExt, ButtonExt, Cast, DrawingAreaExt, EditableExt, ListModelExt, MediaStreamExt, ObjectExt, WidgetExt};
 use indexmap::IndexSet;
 use stardict::{StarDict, WordDefinition};
 use crate::book::{Book, ImageData, Line, TEXT_SELECTION_SPLITTER};
 use crate::{html_parser, package_name};
 use crate::color::{Color32, Colors};
 use crate::common::Position;
-use crate::config::PathConfig;
+use crate::config::{DictionaryGroup, PathConfig};
 use crate::controller::{highlight_selection, HighlightInfo, Render};
-use crate::gui::{copy_to_clipboard, create_button, IconMap, ignore_cap, MAX_FONT_SIZE, MIN_FONT_SIZE, MODIFIER_NONE};
+use crate::gui::{copy_to_clipboard, create_button, IconMap, ignore_cap, MAX_FONT_SIZE, MIN_FONT_SIZE, MODIFIER_NONE, publish_selection};
 use crate::gui::font::UserFonts;
 use crate::gui::render::{RenderContext, ScrollRedrawMethod};
 use crate::gui::view::{GuiView, ScrollPosition};
 use crate::html_parser::{HtmlContent, HtmlParseOptions};
 use crate::i18n::I18n;
+use crate::open::Opener;
+
+mod lemma;
 
 const HTML_DEFINITION_HEAD: &str = "
 <style type=\"text/css\">
@@ -33,10 +41,20 @@ const HTML_DEFINITION_HEAD: &str = "
 	}
 	.dict-word {
 	}
+	.dict-lemma {
+	  font-style: italic;
+	}
 </style>
 <body>
 ";
 const HTML_DEFINITION_TAIL: &str = "</body>";
+const SOUND_LINK_PREFIX: &str = "sound://";
+const SOUND_REGEXP: &str = r#"sound://([^"'\s>]+)"#;
+// resource named by the word, for dictionaries without sound links
+const SOUND_EXTENSIONS: [&str; 4] = ["mp3", "ogg", "wav", "spx"];
+const HTML_TAG_REGEXP: &str = "<[^>]*>";
+// interval checking dictionaries loaded in background
+const LOADING_CHECK_MILLIS: u64 = 100;
 const INJECT_REGEXP: &str = r#"(<[\\s]*img[^>]+src[\\s]*=[\\s]*")([^"]+)("[^>]*>)|((<[\\s]*u)([^>]*>)(((?!</u>).)*)(</u>))"#;
 
 pub(super) struct DictionaryManager {
@@ -45,25 +63,42 @@ pub(super) struct DictionaryManager {
 	highlight: Option<HighlightInfo>,
 	backward_btn: Button,
 	forward_btn: Button,
+	pronounce_btn: Button,
 	lookup_input: SearchEntry,
+	group_dropdown: DropDown,
 	render_context: RenderContext,
+	opener: Rc<RefCell<Opener>>,
 	i18n: Rc<I18n>,
+	// playing pronunciation, and saved audio files by href
+	playing: Option<MediaFile>,
+	audio_files: HashMap<String, PathBuf>,
 
 	words: Vec<(String, f64)>,
 	current_index: Option<usize>,
+	auto_copy: bool,
 }
 
 pub(super) struct LookupResult {
 	dict_name: String,
 	definitions: Vec<WordDefinition>,
+	// inflected form chain to the found lemma, e.g. running → run
+	lemma_chain: Option<String>,
 }
 
 pub(super) struct DictionaryBook {
 	dictionaries: Vec<Box<dyn StarDict>>,
+	// dictionaries empty while loading, results of outdated loading dropped
+	loading: bool,
+	load_id: usize,
+	// names of dictionaries looked up, all if none
+	group: Option<Vec<String>>,
 	cache: HashMap<String, Vec<LookupResult>>,
 	resources: FrozenMap<String, Vec<u8>>,
 	replacer: Regex,
+	sound_finder: Regex,
 	font_families: IndexSet<String>,
+	// (dict name, href) of pronunciation audio for looked up word
+	pronunciation: Option<(String, String)>,
 
 	content: HtmlContent,
 }
@@ -106,8 +141,10 @@ impl Book for DictionaryBook
 }
 
 impl DictionaryBook {
-	fn load_dictionaries(dictionaries: &mut Vec<Box<dyn StarDict>>, dictionary_paths: &Vec<PathConfig>, cache_dict: bool)
+	fn load_dictionaries(dictionary_paths: &Vec<PathConfig>, cache_dict: bool)
+		-> Vec<Box<dyn StarDict + Send>>
 	{
+		let mut dictionaries: Vec<Box<dyn StarDict + Send>> = vec![];
 		for config in dictionary_paths {
 			if config.enabled {
 				if cache_dict {
@@ -122,36 +159,92 @@ impl DictionaryBook {
 				}
 			}
 		}
+		dictionaries
 	}
 
-	pub(super) fn load(dictionary_paths: &Vec<PathConfig>, cache_dict: bool) -> Self
+	/// without dictionaries, loaded by reload
+	pub(super) fn new() -> Self
 	{
-		let mut dictionaries = vec![];
-		Self::load_dictionaries(&mut dictionaries, dictionary_paths, cache_dict);
 		DictionaryBook {
-			dictionaries,
+			dictionaries: vec![],
+			loading: false,
+			load_id: 0,
+			group: None,
 			cache: HashMap::new(),
 			resources: FrozenMap::new(),
 			replacer: Regex::new(INJECT_REGEXP).unwrap(),
+			sound_finder: Regex::new(SOUND_REGEXP).unwrap(),
 			content: HtmlContent::empty(),
 			font_families: Default::default(),
+			pronunciation: None,
 		}
 	}
 
-	pub(super) fn reload(&mut self, dictionary_paths: &Vec<PathConfig>, cache_dict: bool)
+	/// load dictionaries in background for large indices,
+	/// the loaded callback called after dictionaries replaced
+	pub(super) fn reload<F>(db: &Rc<RefCell<Self>>, dictionary_paths: &Vec<PathConfig>,
+		cache_dict: bool, loaded: F)
+		where F: Fn() + 'static
+	{
+		let load_id = {
+			let mut book = db.borrow_mut();
+			book.dictionaries.clear();
+			book.cache.clear();
+			book.loading = true;
+			book.load_id += 1;
+			book.load_id
+		};
+		let (tx, rx) = mpsc::channel();
+		let dictionary_paths = dictionary_paths.clone();
+		thread::spawn(move || {
+			let _ = tx.send(Self::load_dictionaries(&dictionary_paths, cache_dict));
+		});
+		let db = Rc::downgrade(db);
+		glib::timeout_add_local(Duration::from_millis(LOADING_CHECK_MILLIS), move || {
+			let dictionaries = match rx.try_recv() {
+				Ok(dictionaries) => dictionaries,
+				Err(TryRecvError::Empty) => return ControlFlow::Continue,
+				Err(TryRecvError::Disconnected) => vec![],
+			};
+			let Some(db) = db.upgrade() else {
+				return ControlFlow::Break;
+			};
+			let mut book = db.borrow_mut();
+			if book.load_id != load_id {
+				return ControlFlow::Break;
+			}
+			book.dictionaries = dictionaries
+				.into_iter()
+				.map(|dict| dict as Box<dyn StarDict>)
+				.collect();
+			book.cache.clear();
+			book.loading = false;
+			drop(book);
+			loaded();
+			ControlFlow::Break
+		});
+	}
+
+	pub(super) fn set_group(&mut self, group: Option<&DictionaryGroup>)
 	{
-		self.dictionaries.clear();
+		self.group = group.map(|group| group.dictionaries.clone());
 		self.cache.clear();
-		Self::load_dictionaries(&mut self.dictionaries, dictionary_paths, cache_dict);
 	}
 
 	fn lookup(&mut self, word: &str, i18n: &I18n)
 	{
+		if self.loading {
+			self.pronunciation = None;
+			self.content = self.message_content(&i18n.msg("dictionary-loading"));
+			return;
+		}
 		let results = self.cache
 			.entry(word.to_owned())
 			.or_insert_with(|| {
-				lookup_internal(&mut self.dictionaries, word)
+				lookup_word(&mut self.dictionaries, self.group.as_deref(), word)
 			});
+		self.pronunciation = find_pronunciation(results, word,
+			&self.dictionaries, &self.sound_finder);
 		let content = if !results.is_empty() {
 			let mut text = String::from(HTML_DEFINITION_HEAD);
 			for single in &mut *results {
@@ -174,44 +267,109 @@ impl DictionaryBook {
 					.0
 			}
 		} else {
-			let mut text = "<html><body>".to_string();
-			let msg = i18n.msg("dictionary-no-definition");
-			text.push_str(&msg);
-			text.push_str("</body></html>");
-			html_parser::parse(HtmlParseOptions::new(&text)
-				.with_font_family(&mut self.font_families))
-				.unwrap()
-				.0
+			self.message_content(&i18n.msg("dictionary-no-definition"))
 		};
 		self.content = content;
 	}
 
+	fn message_content(&mut self, msg: &str) -> HtmlContent
+	{
+		let mut text = "<html><body>".to_string();
+		text.push_str(msg);
+		text.push_str("</body></html>");
+		html_parser::parse(HtmlParseOptions::new(&text)
+			.with_font_family(&mut self.font_families))
+			.unwrap()
+			.0
+	}
+
+	#[inline]
+	pub(super) fn is_loading(&self) -> bool
+	{
+		self.loading
+	}
+
+	/// definitions in plain text, for flashcard export and lookup popup
+	pub(super) fn definition_text(&mut self, word: &str) -> Option<String>
+	{
+		if self.loading {
+			return None;
+		}
+		let results = self.cache
+			.entry(word.to_owned())
+			.or_insert_with(|| lookup_word(&mut self.dictionaries, self.group.as_deref(), word));
+		if results.is_empty() {
+			return None;
+		}
+		let tag_finder = Regex::new(HTML_TAG_REGEXP).unwrap();
+		let mut text = String::new();
+		for result in results.iter() {
+			for definition in &result.definitions {
+				for segment in &definition.segments {
+					let segment_text = if segment.types.contains('h') || segment.types.contains('g') {
+						let stripped = tag_finder.replace_all(&segment.text, "");
+						html_escape::decode_html_entities(&stripped).into_owned()
+					} else {
+						segment.text.clone()
+					};
+					let segment_text = segment_text.trim();
+					if !segment_text.is_empty() {
+						if !text.is_empty() {
+							text.push('\n');
+						}
+						text.push_str(segment_text);
+					}
+				}
+			}
+		}
+		Some(text)
+	}
+
+	/// audio of the sound link, or pronunciation of looked up word if no link
+	fn pronunciation_audio(&self, sound_link: Option<&str>) -> Option<(String, Vec<u8>)>
+	{
+		if let Some(href) = sound_link {
+			for dict in &self.dictionaries {
+				if let Ok(Some(bytes)) = dict.get_resource(href) {
+					return Some((format!("{}:{}", dict.dict_name(), href), bytes));
+				}
+			}
+			return None;
+		}
+		let (dict_name, href) = self.pronunciation.as_ref()?;
+		let dict = self.dictionaries
+			.iter()
+			.find(|dict| dict.dict_name() == dict_name)?;
+		let bytes = dict.get_resource(href).ok()??;
+		Some((format!("{}:{}", dict_name, href), bytes))
+	}
+
 	#[inline]
 	fn lookup_at_pos(&mut self, line_no: usize, offset: usize) -> Option<(usize, usize)>
 	{
 		let line = self.content.lines().get(line_no)?;
-		word_at_offset(line, offset, &mut self.dictionaries, &mut self.cache)
+		word_at_offset(line, offset, &mut self.dictionaries, self.group.as_deref(), &mut self.cache)
 	}
 
 	#[inline]
 	pub fn lookup_at_offset(&mut self, line: &Line, offset: usize) -> Option<(usize, usize)>
 	{
-		word_at_offset(line, offset, &mut self.dictionaries, &mut self.cache)
+		word_at_offset(line, offset, &mut self.dictionaries, self.group.as_deref(), &mut self.cache)
 	}
 }
 
 pub fn word_at_offset(line: &Line, offset: usize,
-	dictionaries: &mut Vec<Box<dyn StarDict>>,
+	dictionaries: &mut Vec<Box<dyn StarDict>>, group: Option<&[String]>,
 	cache: &mut HashMap<String, Vec<LookupResult>>)
 	-> Option<(usize, usize)>
 {
-	fn exists(dictionaries: &mut Vec<Box<dyn StarDict>>, pattern: &str,
-		cache: &mut HashMap<String, Vec<LookupResult>>) -> bool
+	fn exists(dictionaries: &mut Vec<Box<dyn StarDict>>, group: Option<&[String]>,
+		pattern: &str, cache: &mut HashMap<String, Vec<LookupResult>>) -> bool
 	{
 		if let Some(result) = cache.get(pattern) {
 			return !result.is_empty();
 		}
-		let result = lookup_internal(dictionaries, pattern);
+		let result = lookup_word(dictionaries, group, pattern);
 		let exists = !result.is_empty();
 		cache.insert(pattern.to_owned(), result);
 		exists
@@ -234,7 +392,7 @@ pub fn word_at_offset(line: &Line, offset: usize,
 						break;
 					}
 					line.sub_str(&mut text, from..to);
-					if exists(dictionaries, &text, cache) {
+					if exists(dictionaries, group, &text, cache) {
 						return Some((from, to - 1));
 					}
 				} else {
@@ -248,25 +406,84 @@ pub fn word_at_offset(line: &Line, offset: usize,
 	line.word_at_offset(offset)
 }
 
-fn lookup_internal(dictionaries: &mut Vec<Box<dyn StarDict>>, word: &str)
-	-> Vec<LookupResult>
+fn lookup_internal(dictionaries: &mut Vec<Box<dyn StarDict>>, group: Option<&[String]>,
+	word: &str) -> Vec<LookupResult>
 {
 	let mut result = vec![];
 	for dict in dictionaries {
 		let dict_name = dict.dict_name().to_owned();
+		if let Some(group) = group {
+			if !group.contains(&dict_name) {
+				continue;
+			}
+		}
 		if let Ok(Some(definitions)) = dict.lookup(word) {
 			result.push(LookupResult {
 				dict_name,
 				definitions,
+				lemma_chain: None,
 			});
 		}
 	}
 	result
 }
 
+/// lookup the word, or its lemma if the inflected form not found
+fn lookup_word(dictionaries: &mut Vec<Box<dyn StarDict>>, group: Option<&[String]>,
+	word: &str) -> Vec<LookupResult>
+{
+	let result = lookup_internal(dictionaries, group, word);
+	if !result.is_empty() || dictionaries.is_empty() {
+		return result;
+	}
+	for chain in lemma::lemma_chains(word) {
+		let mut result = lookup_internal(dictionaries, group, chain.last().unwrap());
+		if !result.is_empty() {
+			let lemma_chain = chain.join(" → ");
+			for single in &mut result {
+				single.lemma_chain = Some(lemma_chain.clone());
+			}
+			return result;
+		}
+	}
+	result
+}
+
+/// first sound link in definitions, or audio resource named by the word
+fn find_pronunciation(results: &Vec<LookupResult>, word: &str,
+	dictionaries: &Vec<Box<dyn StarDict>>, sound_finder: &Regex)
+	-> Option<(String, String)>
+{
+	for result in results {
+		for definition in &result.definitions {
+			for segment in &definition.segments {
+				if let Ok(Some(caps)) = sound_finder.captures(&segment.text) {
+					if let Some(href) = caps.get(1) {
+						return Some((result.dict_name.clone(), href.as_str().to_owned()));
+					}
+				}
+			}
+		}
+	}
+	let word = word.trim();
+	if word.is_empty() {
+		return None;
+	}
+	for dict in dictionaries {
+		for ext in SOUND_EXTENSIONS {
+			let href = format!("{}.{}", word, ext);
+			if let Ok(Some(_)) = dict.get_resource(&href) {
+				return Some((dict.dict_name().to_owned(), href));
+			}
+		}
+	}
+	None
+}
+
 impl DictionaryManager {
-	pub fn new(db: Rc<RefCell<DictionaryBook>>, dictionary_paths: &Vec<PathConfig>, cache_dict: bool, font_size: u8,
-		fonts: Rc<Option<UserFonts>>, i18n: &Rc<I18n>, icons: &Rc<IconMap>)
+	pub fn new(db: Rc<RefCell<DictionaryBook>>, font_size: u8,
+		fonts: Rc<Option<UserFonts>>, opener: &Rc<RefCell<Opener>>,
+		i18n: &Rc<I18n>, icons: &Rc<IconMap>)
 		-> (Rc<RefCell<Self>>, gtk4::Box, SearchEntry)
 	{
 		let mut render_context = RenderContext::new(
@@ -276,8 +493,13 @@ impl DictionaryManager {
 			true,
 			0,
 			false,
+			Default::default(),
+			Default::default(),
+			0,
+			0,
+			100,
 			false);
-		let mut book = db.borrow_mut();
+		let book = db.borrow();
 		let view = GuiView::new(
 			"dict",
 			false,
@@ -286,6 +508,13 @@ impl DictionaryManager {
 			&mut render_context);
 		let backward_btn = create_button("backward_disabled.svg", None, icons, false);
 		let forward_btn = create_button("forward_disabled.svg", None, icons, false);
+		let pronounce_btn = create_button("play.svg",
+			Some(&i18n.msg("dictionary-pronounce")), icons, false);
+		pronounce_btn.set_sensitive(false);
+		let group_dropdown = DropDown::builder()
+			.tooltip_text(i18n.msg("dictionary-group"))
+			.visible(false)
+			.build();
 		let lookup_input = SearchEntry::builder()
 			.placeholder_text(i18n.msg("lookup-dictionary").as_ref())
 			.activates_default(true)
@@ -295,14 +524,14 @@ impl DictionaryManager {
 		toolbar.append(&backward_btn);
 		toolbar.append(&forward_btn);
 		toolbar.append(&lookup_input);
+		toolbar.append(&pronounce_btn);
+		toolbar.append(&group_dropdown);
 		let dict_box = gtk4::Box::new(Orientation::Vertical, 0);
 		dict_box.append(&toolbar);
 		dict_box.append(&ScrolledWindow::builder()
 			.child(&view)
 			.vexpand(true)
 			.build());
-
-		book.reload(dictionary_paths, cache_dict);
 		drop(book);
 
 		let dm = DictionaryManager {
@@ -311,16 +540,26 @@ impl DictionaryManager {
 			highlight: None,
 			backward_btn: backward_btn.clone(),
 			forward_btn: forward_btn.clone(),
+			pronounce_btn: pronounce_btn.clone(),
 			lookup_input: lookup_input.clone(),
+			group_dropdown,
 			render_context,
+			opener: opener.clone(),
 			i18n: i18n.clone(),
+			playing: None,
+			audio_files: HashMap::new(),
 
 			words: vec![],
 			current_index: None,
+			auto_copy: false,
 		};
 		let dm = Rc::new(RefCell::new(dm));
 
 		setup_ui(&dm, &backward_btn, &forward_btn);
+		{
+			let dm = dm.clone();
+			pronounce_btn.connect_clicked(move |_| dm.borrow_mut().pronounce(None));
+		}
 
 		(dm, dict_box, lookup_input)
 	}
@@ -333,6 +572,54 @@ impl DictionaryManager {
 		}
 	}
 
+	/// group dropdown hidden without any group
+	pub fn set_groups(&mut self, groups: &Vec<DictionaryGroup>, active: Option<&str>)
+	{
+		let group_list = StringList::default();
+		group_list.append(&self.i18n.msg("dictionary-group-all"));
+		let mut selected = 0;
+		for (idx, group) in groups.iter().enumerate() {
+			group_list.append(&group.name);
+			if active == Some(group.name.as_str()) {
+				selected = idx + 1;
+			}
+		}
+		self.group_dropdown.set_model(Some(&group_list));
+		self.group_dropdown.set_selected(selected as u32);
+		self.group_dropdown.set_visible(!groups.is_empty());
+	}
+
+	/// select the group without lookup
+	pub fn select_group(&mut self, group: Option<&str>)
+	{
+		let dropdown = &self.group_dropdown;
+		let Some(model) = dropdown.model() else {
+			return;
+		};
+		let selected = group.and_then(|group| (1..model.n_items())
+			.find(|idx| model.item(*idx)
+				.and_then(|item| item.downcast::<StringObject>().ok())
+				.map_or(false, |item| item.string() == group)))
+			.unwrap_or(0);
+		dropdown.set_selected(selected);
+	}
+
+	/// callback with name of the group selected, none for all dictionaries
+	pub fn handle_group_switch<F>(&self, group_switched: F)
+		where F: Fn(Option<String>) + 'static
+	{
+		self.group_dropdown.connect_selected_notify(move |dropdown| {
+			let group = if dropdown.selected() == 0 {
+				None
+			} else {
+				dropdown.selected_item()
+					.and_then(|item| item.downcast::<StringObject>().ok())
+					.map(|item| item.string().to_string())
+			};
+			group_switched(group);
+		});
+	}
+
 	#[inline]
 	pub fn focus_lookup(&self)
 	{
@@ -350,6 +637,20 @@ impl DictionaryManager {
 			&mut self.render_context);
 	}
 
+	#[inline]
+	pub fn set_auto_copy(&mut self, auto_copy: bool)
+	{
+		self.auto_copy = auto_copy;
+	}
+
+	#[inline]
+	fn publish_selection(&self)
+	{
+		if let Some(selected_text) = highlight_selection(&self.highlight) {
+			publish_selection(selected_text, self.auto_copy);
+		}
+	}
+
 	#[inline]
 	pub fn set_fonts(&mut self, fonts: Rc<Option<UserFonts>>)
 	{
@@ -409,13 +710,50 @@ impl DictionaryManager {
 		let book = self.db.borrow();
 		if let Some(line) = book.lines().get(line) {
 			if let Some(link) = line.link_at(link_index) {
-				let target = link.target.trim().to_owned();
-				drop(book);
-				self.set_lookup(target);
+				let target = link.target.trim();
+				if let Some(href) = target.strip_prefix(SOUND_LINK_PREFIX) {
+					let href = href.to_owned();
+					drop(book);
+					self.pronounce(Some(&href));
+				} else {
+					let target = target.to_owned();
+					drop(book);
+					self.set_lookup(target);
+				}
 			}
 		}
 	}
 
+	/// play pronunciation with gtk media, audio saved to temp file first
+	fn pronounce(&mut self, sound_link: Option<&str>)
+	{
+		let book = self.db.borrow();
+		let Some((key, bytes)) = book.pronunciation_audio(sound_link) else {
+			return;
+		};
+		drop(book);
+		let path = if let Some(path) = self.audio_files.get(&key) {
+			path.clone()
+		} else {
+			match self.opener.borrow_mut().audio_file(&key, &bytes) {
+				Ok(path) => {
+					self.audio_files.insert(key, path.clone());
+					path
+				}
+				Err(err) => {
+					eprintln!("Failed play pronunciation: {}", err.to_string());
+					return;
+				}
+			}
+		};
+		if let Some(playing) = self.playing.take() {
+			playing.pause();
+		}
+		let media = MediaFile::for_filename(path);
+		media.play();
+		self.playing = Some(media);
+	}
+
 	#[inline]
 	fn switch_word(&mut self, forward: bool) -> Option<usize>
 	{
@@ -467,7 +805,10 @@ impl DictionaryManager {
 	fn lookup(&mut self, current_index: usize, init: bool)
 	{
 		let (word, pos) = &self.words[current_index];
-		self.db.borrow_mut().lookup(word, &self.i18n);
+		let mut book = self.db.borrow_mut();
+		book.lookup(word, &self.i18n);
+		self.pronounce_btn.set_sensitive(book.pronunciation.is_some());
+		drop(book);
 		let redraw_mode = if init {
 			ScrollRedrawMethod::ResetScroll
 		} else {
@@ -482,6 +823,10 @@ impl DictionaryManager {
 fn render_definition(result: &LookupResult, text: &mut String, replacer: &Regex)
 {
 	text.push_str(&format!("<h3 class=\"dict-name\">{}</h3>", result.dict_name));
+	if let Some(lemma_chain) = &result.lemma_chain {
+		text.push_str(&format!("<p class=\"dict-lemma\">{}</p>",
+			html_escape::encode_text(lemma_chain)));
+	}
 	for definition in &result.definitions {
 		text.push_str(&format!("<h3 class=\"dict-word\">{}</h3>", definition.word));
 		for segment in &definition.segments {
@@ -517,6 +862,11 @@ fn render_definition_text(result: &LookupResult, html: &mut String)
 	html.push_str("<h style='color: blue;'><b>");
 	html.push_str(&result.dict_name);
 	html.push_str("</b></h><br/>");
+	if let Some(lemma_chain) = &result.lemma_chain {
+		html.push_str("<p><i>");
+		html.push_str(lemma_chain);
+		html.push_str("</i></p>");
+	}
 	for definition in &result.definitions {
 		html.push_str("<h><b>");
 		html.push_str(&definition.word);
@@ -649,6 +999,7 @@ fn setup_ui(dm: &Rc<RefCell<DictionaryManager>>, backward_btn: &Button, forward_
 					from_offset as usize,
 					to_line as usize,
 					to_offset as usize);
+				dictionary_manager.publish_selection();
 	        }),
 		);
 	}
@@ -679,6 +1030,7 @@ fn setup_ui(dm: &Rc<RefCell<DictionaryManager>>, backward_btn: &Button, forward_
 				if let Some((from, to)) = book.lookup_at_pos(line_no, offset as usize) {
 					drop(book);
 					dictionary_manager.select_text(line_no, from, line_no, to);
+					dictionary_manager.publish_selection();
 				}
 			}),
 		);
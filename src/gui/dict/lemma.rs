@@ -0,0 +1,190 @@
+// simple suffix rules for inflected forms, tried when the word not found
+// (suffix, replacement, min stem chars)
+type Rule = (&'static str, &'static str, usize);
+
+// lemma chain depth, e.g. Running -> running -> run
+const MAX_DEPTH: usize = 2;
+
+const IRREGULARS: [(&str, &str); 32] = [
+	("am", "be"),
+	("are", "be"),
+	("is", "be"),
+	("was", "be"),
+	("were", "be"),
+	("been", "be"),
+	("has", "have"),
+	("had", "have"),
+	("did", "do"),
+	("does", "do"),
+	("done", "do"),
+	("went", "go"),
+	("gone", "go"),
+	("made", "make"),
+	("said", "say"),
+	("saw", "see"),
+	("seen", "see"),
+	("took", "take"),
+	("taken", "take"),
+	("came", "come"),
+	("got", "get"),
+	("gave", "give"),
+	("given", "give"),
+	("children", "child"),
+	("men", "man"),
+	("women", "woman"),
+	("feet", "foot"),
+	("teeth", "tooth"),
+	("mice", "mouse"),
+	("better", "good"),
+	("best", "good"),
+	("worse", "bad"),
+];
+
+const ENGLISH_RULES: [Rule; 22] = [
+	("ies", "y", 2),
+	("ied", "y", 2),
+	("ier", "y", 2),
+	("iest", "y", 2),
+	("ily", "y", 2),
+	("ves", "f", 2),
+	("ves", "fe", 2),
+	("ches", "ch", 1),
+	("shes", "sh", 1),
+	("sses", "ss", 1),
+	("xes", "x", 1),
+	("ing", "", 2),
+	("ing", "e", 2),
+	("ed", "", 2),
+	("ed", "e", 2),
+	("est", "", 2),
+	("est", "e", 2),
+	("er", "", 2),
+	("er", "e", 2),
+	("ly", "", 3),
+	("'s", "", 1),
+	("s", "", 2),
+];
+
+const SPANISH_RULES: [Rule; 20] = [
+	("ces", "z", 1),
+	("iones", "ión", 1),
+	("es", "", 3),
+	("as", "o", 2),
+	("os", "o", 2),
+	("a", "o", 2),
+	("ando", "ar", 2),
+	("iendo", "er", 2),
+	("iendo", "ir", 2),
+	("ado", "ar", 2),
+	("ada", "ar", 2),
+	("ido", "er", 2),
+	("ido", "ir", 2),
+	("aba", "ar", 2),
+	("aban", "ar", 2),
+	("amos", "ar", 2),
+	("emos", "er", 2),
+	("imos", "ir", 2),
+	("ía", "er", 2),
+	("ían", "er", 2),
+];
+
+const FRENCH_RULES: [Rule; 6] = [
+	("aux", "al", 1),
+	("eaux", "eau", 1),
+	("x", "", 3),
+	("es", "", 2),
+	("e", "", 3),
+	("ée", "er", 2),
+];
+
+const GERMAN_RULES: [Rule; 5] = [
+	("en", "", 3),
+	("ern", "", 3),
+	("er", "", 3),
+	("n", "", 3),
+	("e", "", 3),
+];
+
+/// candidate lemma chains of the word, most likely first,
+/// each chain from the word itself to the lemma
+pub(super) fn lemma_chains(word: &str) -> Vec<Vec<String>>
+{
+	let word = word.trim();
+	let mut chains = vec![];
+	if word.is_empty() || !word.chars().all(|ch| ch.is_alphabetic() || ch == '\'' || ch == '-') {
+		return chains;
+	}
+	// breadth first, so nearer lemmas tried first
+	let mut level = vec![vec![word.to_owned()]];
+	for _ in 0..MAX_DEPTH {
+		let mut next_level = vec![];
+		for chain in &level {
+			for candidate in candidates(chain.last().unwrap()) {
+				if candidate == word
+					|| chains.iter().any(|exists: &Vec<String>| exists.last() == Some(&candidate)) {
+					continue;
+				}
+				let mut next = chain.clone();
+				next.push(candidate);
+				chains.push(next.clone());
+				next_level.push(next);
+			}
+		}
+		level = next_level;
+	}
+	chains
+}
+
+fn candidates(word: &str) -> Vec<String>
+{
+	let mut candidates = vec![];
+	let lower = word.to_lowercase();
+	if lower != word {
+		candidates.push(lower);
+		// the lower case word takes the rules in next depth
+		return candidates;
+	}
+	for (inflected, lemma) in IRREGULARS {
+		if inflected == word {
+			candidates.push(lemma.to_owned());
+		}
+	}
+	for rules in [&ENGLISH_RULES[..], &SPANISH_RULES, &FRENCH_RULES, &GERMAN_RULES] {
+		for (suffix, replacement, min_stem) in rules {
+			let Some(stem) = word.strip_suffix(suffix) else {
+				continue;
+			};
+			if stem.chars().count() < *min_stem {
+				continue;
+			}
+			push_candidate(&mut candidates, format!("{}{}", stem, replacement));
+			// running -> run, stopped -> stop
+			if replacement.is_empty() {
+				if let Some(stem) = undouble(stem) {
+					push_candidate(&mut candidates, stem.to_owned());
+				}
+			}
+		}
+	}
+	candidates
+}
+
+#[inline]
+fn push_candidate(candidates: &mut Vec<String>, candidate: String)
+{
+	if !candidates.contains(&candidate) {
+		candidates.push(candidate);
+	}
+}
+
+fn undouble(stem: &str) -> Option<&str>
+{
+	let mut chars = stem.char_indices().rev();
+	let (last_index, last) = chars.next()?;
+	let (_, prev) = chars.next()?;
+	if last == prev && !"aeiouls".contains(last) {
+		Some(&stem[..last_index])
+	} else {
+		None
+	}
+}
@@ -1,21 +1,23 @@
-use crate::book::{SearchError, Line};
+use crate::book::{Book, BookLoader, Line, LoadingChapter, SearchError, SearchPattern};
 use crate::common::{byte_index_for_char, char_width};
 use crate::config::BookLoadingInfo;
-use crate::container::{load_book, load_container, Container, ContainerManager};
+use crate::container::{load_container, Container, ContainerManager};
 use crate::gui::{load_button_image, IconMap};
 use crate::i18n::I18n;
 use anyhow::Result;
-use fancy_regex::Regex;
 use gtk4::glib::{idle_add_local, markup_escape_text, ControlFlow};
 use gtk4::pango::EllipsizeMode;
 use gtk4::prelude::{BoxExt, ButtonExt, CheckButtonExt, EditableExt, ListBoxRowExt, WidgetExt};
 use gtk4::{Align, Button, CheckButton, Image, Label, ListBox, Orientation, PolicyType, SearchEntry, SelectionMode};
 use std::borrow::Cow;
 use std::cell::{RefCell, RefMut};
+use std::collections::BTreeMap;
 use std::ops::Range;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 use std::thread::spawn;
 
 #[derive(Clone)]
@@ -39,9 +41,12 @@ pub struct FoundEntry {
 struct FindListInner {
 	filename: Option<String>,
 	inner_book: usize,
+	fold_width: bool,
+	whole_word: CheckButton,
 	list: ListBox,
 	rows: Vec<FoundEntry>,
 	i18n: Rc<I18n>,
+	finished: Option<Rc<dyn Fn(usize)>>,
 }
 
 // create too much label in idle thread will freeze the UI
@@ -80,12 +85,17 @@ impl FindList {
 			.label(i18n.msg("find-all-book"))
 			.tooltip_text(i18n.msg("find-all-book-tooltip"))
 			.build();
+		let whole_word = CheckButton::builder()
+			.label(i18n.msg("find-whole-word"))
+			.tooltip_text(i18n.msg("find-whole-word-tooltip"))
+			.build();
 		let input_box = gtk4::Box::builder()
 			.orientation(Orientation::Horizontal)
 			.spacing(0)
 			.hexpand(true)
 			.build();
 		input_box.append(&all_book);
+		input_box.append(&whole_word);
 		input_box.append(&input);
 		input_box.append(&ctrl_btn);
 
@@ -104,9 +114,12 @@ impl FindList {
 		let inner = FindListInner {
 			filename: filename.to_owned(),
 			inner_book: 0,
+			fold_width: false,
+			whole_word,
 			list,
 			rows: Default::default(),
 			i18n: i18n.clone(),
+			finished: None,
 		};
 		let find_list = FindList { inner: Rc::new(RefCell::new(inner)) };
 
@@ -154,6 +167,19 @@ impl FindList {
 		self.inner.borrow_mut().inner_book = inner_book;
 	}
 
+	#[inline]
+	pub fn set_fold_width(&self, fold_width: bool)
+	{
+		self.inner.borrow_mut().fold_width = fold_width;
+	}
+
+	/// callback with the count of entries found, when find not stopped by user
+	pub fn handle_finished<F>(&self, finished: F)
+		where F: Fn(usize) + 'static
+	{
+		self.inner.borrow_mut().finished = Some(Rc::new(finished));
+	}
+
 	pub fn set_callback<F>(&self, f: F)
 	where
 		F: Fn(&FoundEntry) -> bool + 'static,
@@ -179,46 +205,116 @@ impl FindList {
 
 fn find_in_book(container_manager: &ContainerManager,
 	container: &mut Box<dyn Container>, filename: &str, inner_book: usize,
-	regex: &Regex, tx: &Sender<FoundEntry>, state: &Arc<Mutex<FindState>>) -> Result<(), SearchError>
+	regex: &SearchPattern, tx: &Sender<FoundEntry>, state: &Arc<Mutex<FindState>>) -> Result<(), SearchError>
 {
-	let loading = BookLoadingInfo::NewReading(&filename, inner_book, 0, 16);
-	if let Ok((mut book, _)) = load_book(&container_manager, container, loading) {
-		let mut chapter = 0;
-		loop {
-			let chapter_title = book.title(0, 0);
-			for (idx, line) in book.lines().iter().enumerate() {
-				line.search_pattern(&regex, |text, range| {
-					let (display_text, highlight_display_bytes) = make_display_text(line, text, &range)
-						.ok_or(SearchError::Custom(Cow::Borrowed("Failed setup display text for found")))?;
-					tx.send(FoundEntry {
-						inner_book,
-						chapter,
-						chapter_title: chapter_title.map(|t| t.to_owned()),
-						toc_title: book.title(idx, range.start).map(|t| t.to_owned()),
-						line: idx,
-						range,
-						display_text,
-						highlight_display_bytes,
-					}).map_err(|_| SearchError::Canceled)?;
-					Ok(())
-				})?;
-			}
-			if let Ok(state) = state.try_lock() {
-				if matches!(*state, FindState::Stopping) {
-					return Ok(());
+	// read once from the opened container, each worker loads its book from it
+	let (book_name, content) = match container_manager.book_source(container, inner_book) {
+		Ok(source) => source,
+		Err(_) => return Ok(()),
+	};
+	let password = container_manager.password(filename);
+	// chapters are taken in turn by worker threads, each with the book
+	// loaded once, results are forwarded to ui in chapter order
+	let next_chapter = AtomicUsize::new(0);
+	let canceled = AtomicBool::new(false);
+	let (chapter_tx, chapter_rx) = mpsc::channel();
+	thread::scope(|scope| {
+		let next_chapter = &next_chapter;
+		let canceled = &canceled;
+		let book_name = &book_name;
+		let content = &content;
+		scope.spawn(move || {
+			rayon::broadcast(|_| {
+				let loading = BookLoadingInfo::NewReading(filename, inner_book, 0, 16);
+				let Ok((mut book, _)) = BookLoader::default().load(book_name,
+					content.clone(), LoadingChapter::Index(0), loading, password) else {
+					return;
+				};
+				let chapter_count = book.chapter_count();
+				loop {
+					if canceled.load(Ordering::Relaxed) || is_stopping(state) {
+						return;
+					}
+					let chapter = next_chapter.fetch_add(1, Ordering::Relaxed);
+					if chapter >= chapter_count {
+						return;
+					}
+					let found = find_in_chapter(book.as_mut(), inner_book, chapter, regex);
+					if chapter_tx.send((chapter, found)).is_err() {
+						canceled.store(true, Ordering::Relaxed);
+					}
 				}
+			});
+		});
+		let result = merge_found(chapter_rx, tx);
+		if result.is_err() {
+			canceled.store(true, Ordering::Relaxed);
+		}
+		result
+	})
+}
+
+fn find_in_chapter(book: &mut dyn Book, inner_book: usize, chapter: usize,
+	regex: &SearchPattern) -> Result<Vec<FoundEntry>, SearchError>
+{
+	match book.goto_chapter(chapter) {
+		// empty chapters are skipped by book, and will be searched with its own index
+		Ok(Some(c)) if c == chapter => {}
+		_ => return Ok(vec![]),
+	}
+	let found = RefCell::new(vec![]);
+	let chapter_title = book.title(0, 0);
+	for (idx, line) in book.lines().iter().enumerate() {
+		line.search_pattern(&regex, |text, range| {
+			let (display_text, highlight_display_bytes) = make_display_text(line, text, &range)
+				.ok_or(SearchError::Custom(Cow::Borrowed("Failed setup display text for found")))?;
+			found.borrow_mut().push(FoundEntry {
+				inner_book,
+				chapter,
+				chapter_title: chapter_title.map(|t| t.to_owned()),
+				toc_title: book.title(idx, range.start).map(|t| t.to_owned()),
+				line: idx,
+				range,
+				display_text,
+				highlight_display_bytes,
+			});
+			Ok(())
+		})?;
+	}
+	Ok(found.into_inner())
+}
+
+/// send found entries of chapters in order, chapters may be finished
+/// by workers in any order
+fn merge_found(chapter_rx: Receiver<(usize, Result<Vec<FoundEntry>, SearchError>)>,
+	tx: &Sender<FoundEntry>) -> Result<(), SearchError>
+{
+	let mut pending = BTreeMap::new();
+	let mut next_chapter = 0;
+	for (chapter, found) in chapter_rx {
+		pending.insert(chapter, found?);
+		while let Some(entries) = pending.remove(&next_chapter) {
+			for entry in entries {
+				tx.send(entry).map_err(|_| SearchError::Canceled)?;
 			}
-			match book.next_chapter() {
-				Ok(Some(c)) => chapter = c,
-				_ => break,
-			}
+			next_chapter += 1;
 		}
 	}
 	Ok(())
 }
 
 #[inline]
-fn do_find(filename: String, search_book: Option<usize>, regex: Regex,
+fn is_stopping(state: &Arc<Mutex<FindState>>) -> bool
+{
+	if let Ok(state) = state.try_lock() {
+		matches!(*state, FindState::Stopping)
+	} else {
+		false
+	}
+}
+
+#[inline]
+fn do_find(filename: String, search_book: Option<usize>, regex: SearchPattern,
 	tx: Sender<FoundEntry>, state: Arc<Mutex<FindState>>) -> Result<(), SearchError>
 {
 	let container_manager = Default::default();
@@ -267,7 +363,7 @@ fn find(mut inner: RefMut<FindListInner>, input: &SearchEntry,
 	};
 	let text = input.text();
 	let pattern = text.as_str().trim();
-	let regex = match Regex::new(&pattern) {
+	let regex = match SearchPattern::new(&pattern, inner.fold_width, inner.whole_word.is_active()) {
 		Ok(regex) => regex,
 		Err(_) => return true,
 	};
@@ -402,7 +498,7 @@ fn start_find(input: &SearchEntry, all_book: &CheckButton,
 	let (tx, rx) = mpsc::channel();
 	if let Ok(inner) = find_list.inner.try_borrow_mut() {
 		if find(inner, &input, &all_book, tx.clone(), state.clone()) {
-			toggle_find(false, &input, &all_book, &ctrl_btn, &start_icon, &stop_icon);
+			toggle_find(false, &input, &all_book, &find_list, &ctrl_btn, &start_icon, &stop_icon);
 		} else {
 			return;
 		}
@@ -415,10 +511,20 @@ fn start_find(input: &SearchEntry, all_book: &CheckButton,
 		if next {
 			ControlFlow::Continue
 		} else {
-			if let Ok(mut state) = state.lock() {
+			let stopped = state.lock().map_or(false, |mut state| {
+				let stopped = matches!(*state, FindState::Stopping);
 				*state = FindState::Idle;
+				stopped
+			});
+			toggle_find(true, &input, &all_book, &find_list, &ctrl_btn, &start_icon, &stop_icon);
+			if !stopped {
+				let finished = find_list.inner.try_borrow()
+					.ok()
+					.and_then(|inner| Some((inner.finished.clone()?, inner.rows.len())));
+				if let Some((finished, count)) = finished {
+					finished(count);
+				}
 			}
-			toggle_find(true, &input, &all_book, &ctrl_btn, &start_icon, &stop_icon);
 			ControlFlow::Break
 		}
 	});
@@ -448,15 +554,18 @@ fn retrieve_entries(find_list: &FindList, state: &Arc<Mutex<FindState>>,
 	true
 }
 
-fn toggle_find(enable: bool, input: &SearchEntry, all_book: &CheckButton, ctrl_btn: &Button, start_icon: &Image, stop_icon: &Image)
+fn toggle_find(enable: bool, input: &SearchEntry, all_book: &CheckButton, find_list: &FindList, ctrl_btn: &Button, start_icon: &Image, stop_icon: &Image)
 {
+	let whole_word = find_list.inner.borrow().whole_word.clone();
 	if enable {
 		input.set_sensitive(true);
 		all_book.set_sensitive(true);
+		whole_word.set_sensitive(true);
 		ctrl_btn.set_child(Some(start_icon));
 	} else {
 		input.set_sensitive(false);
 		all_book.set_sensitive(false);
+		whole_word.set_sensitive(false);
 		ctrl_btn.set_child(Some(stop_icon));
 	}
 }
@@ -0,0 +1,206 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use dirs::home_dir;
+use serde_json::{json, Value};
+
+use crate::book::Line;
+use crate::common::Position;
+use crate::config::{FlashcardConfig, FlashcardTarget};
+use crate::gui::{GuiContext, http};
+
+const TSV_FILE_NAME: &str = "tbr-flashcards.tsv";
+// file headers recognized by anki import
+const TSV_HEADERS: &str = "#separator:tab\n#html:true\n#columns:Word\tDefinition\tContext\tBook\n";
+const SENTENCE_ENDS: [char; 8] = ['.', '!', '?', ';', '。', '！', '？', '；'];
+const ANKI_CONNECT_VERSION: u8 = 6;
+const ANKI_NOTE_TAG: &str = "tbr";
+
+/// fields in html
+struct Flashcard {
+	word: String,
+	definition: String,
+	context: String,
+	book: String,
+}
+
+/// export selected text with definition, sentence and book title
+pub(super) fn export(gc: &GuiContext)
+{
+	let Some(card) = create_card(gc) else {
+		return;
+	};
+	let flashcard = gc.cfg().gui.flashcard.clone();
+	match flashcard.target {
+		FlashcardTarget::Tsv => match append_tsv(&flashcard, &card) {
+			Ok(path) => gc.toast(&gc.i18n.args_msg("flashcard-exported", vec![
+				("target", path.to_string_lossy().to_string()),
+			])),
+			Err(err) => failed(gc, &err.to_string()),
+		}
+		FlashcardTarget::AnkiConnect => add_note(gc, &flashcard, card),
+	}
+}
+
+fn create_card(gc: &GuiContext) -> Option<Flashcard>
+{
+	let controller = gc.ctrl();
+	let word = controller.selected()?.trim().to_owned();
+	if word.is_empty() {
+		return None;
+	}
+	let range = controller.selection_range()?;
+	let context = sentence_of(controller.book.lines(), &range);
+	let book = html_escape::encode_text(controller.reading_book_name()).into_owned();
+	drop(controller);
+	let definition = gc.db.borrow_mut()
+		.definition_text(&word)
+		.map_or_else(String::new, |definition| html_text(&definition));
+	Some(Flashcard {
+		word: html_text(&word),
+		definition,
+		context,
+		book,
+	})
+}
+
+#[inline]
+fn failed(gc: &GuiContext, err: &str)
+{
+	gc.toast_error(&gc.i18n.args_msg("flashcard-failed", vec![
+		("error", err.trim()),
+	]));
+}
+
+#[inline]
+fn html_text(text: &str) -> String
+{
+	html_escape::encode_text(text)
+		.replace('\t', " ")
+		.replace('\n', "<br>")
+}
+
+/// the sentence around selection, with the selected text in bold
+fn sentence_of(lines: &Vec<Line>, range: &Range<Position>) -> String
+{
+	let Some(start_line) = lines.get(range.start.line) else {
+		return String::new();
+	};
+	let mut from = range.start.offset.min(start_line.len());
+	while from > 0 {
+		let char = start_line.char_at(from - 1).unwrap();
+		if SENTENCE_ENDS.contains(&char) {
+			break;
+		}
+		from -= 1;
+	}
+	let end_line_index = range.end.line.min(lines.len() - 1);
+	let end_line = &lines[end_line_index];
+	let mut to = range.end.offset.min(end_line.len());
+	while to < end_line.len() {
+		let char = end_line.char_at(to).unwrap();
+		to += 1;
+		if SENTENCE_ENDS.contains(&char) {
+			break;
+		}
+	}
+
+	let mut text = String::new();
+	for line_index in range.start.line..=end_line_index {
+		let line = &lines[line_index];
+		let line_from = if line_index == range.start.line { from } else { 0 };
+		let line_to = if line_index == end_line_index { to } else { line.len() };
+		if line_index != range.start.line {
+			text.push(' ');
+		}
+		for offset in line_from..line_to {
+			if line_index == range.start.line && offset == range.start.offset {
+				text.push_str("<b>");
+			}
+			let char = line.char_at(offset).unwrap();
+			let mut buf = [0; 4];
+			text.push_str(&html_text(char.encode_utf8(&mut buf)));
+			if offset + 1 == range.end.offset && line_index == range.end.line {
+				text.push_str("</b>");
+			}
+		}
+	}
+	text.trim().to_owned()
+}
+
+fn append_tsv(flashcard: &FlashcardConfig, card: &Flashcard) -> Result<PathBuf>
+{
+	let path = match &flashcard.tsv_file {
+		Some(path) => path.clone(),
+		None => home_dir()
+			.ok_or(anyhow!("No home folder"))?
+			.join(TSV_FILE_NAME),
+	};
+	let new_file = !path.exists();
+	let mut file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&path)?;
+	if new_file {
+		file.write_all(TSV_HEADERS.as_bytes())?;
+	}
+	writeln!(file, "{}\t{}\t{}\t{}", card.word, card.definition, card.context, card.book)?;
+	Ok(path)
+}
+
+/// add note with front of the word, back of other fields
+fn add_note(gc: &GuiContext, flashcard: &FlashcardConfig, card: Flashcard)
+{
+	let mut back = card.definition;
+	if !card.context.is_empty() {
+		if !back.is_empty() {
+			back.push_str("<br><br>");
+		}
+		back.push_str(&card.context);
+	}
+	back.push_str(&format!("<br><i>{}</i>", card.book));
+	let body = json!({
+		"action": "addNote",
+		"version": ANKI_CONNECT_VERSION,
+		"params": {
+			"note": {
+				"deckName": flashcard.deck,
+				"modelName": flashcard.note_type,
+				"fields": {
+					"Front": card.word,
+					"Back": back,
+				},
+				"tags": [ANKI_NOTE_TAG],
+			}
+		}
+	});
+	let result = {
+		let gc = gc.clone();
+		let deck = flashcard.deck.clone();
+		http::post_json(&flashcard.anki_connect_url, &[], &body, move |response| {
+			let result = response.and_then(|response|
+				parse_response(&response).map_err(|err| err.to_string()));
+			match result {
+				Ok(()) => gc.toast(&gc.i18n.args_msg("flashcard-exported", vec![
+					("target", deck),
+				])),
+				Err(err) => failed(&gc, &err),
+			}
+		})
+	};
+	if let Err(err) = result {
+		failed(gc, &err.to_string());
+	}
+}
+
+fn parse_response(response: &str) -> Result<()>
+{
+	let value: Value = serde_json::from_str(response)?;
+	if let Some(error) = value["error"].as_str() {
+		bail!("{}", error)
+	}
+	Ok(())
+}
@@ -1,19 +1,28 @@
+use std::fs;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::PathBuf;
 use std::sync::Arc;
-use ab_glyph::{Font, FontRef, FontVec, OutlinedGlyph, Rect};
+use ab_glyph::{Font, FontRef, FontVec, OutlinedGlyph, PxScale, Rect, ScaleFont, VariableFont};
 use anyhow::{anyhow, Result};
 use fontdb::{Database, Query};
 use indexmap::IndexMap;
 use lightningcss::properties::font::GenericFontFamily;
 use ouroboros::self_referencing;
-use crate::config::PathConfig;
+use crate::config::{FontAxes, PathConfig};
 use crate::html_parser::{FontWeight, HtmlFontFaceDesc};
 
+pub const FONT_PREVIEW_TEXT: &str = "漢字 The quick brown fox 123";
+// requested weight from this is bold, synthesize it for lighter face
+const SYNTHESIZE_BOLD_WEIGHT: u16 = 600;
+const WEIGHT_AXIS: &[u8; 4] = b"wght";
+const WIDTH_AXIS: &[u8; 4] = b"wdth";
+
 pub trait Fonts {
+	/// outlined glyph with its bounds, and whether bold needs synthesizing
 	fn query(&self, char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_names: Option<&str>) -> Option<(OutlinedGlyph, Rect)>;
+		synthesize: bool, font_family_names: Option<&str>)
+		-> Option<(OutlinedGlyph, Rect, bool)>;
 }
 
 #[self_referencing]
@@ -26,7 +35,8 @@ pub struct UserFonts {
 
 impl Fonts for UserFonts {
 	fn query(&self, char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_names: Option<&str>) -> Option<(OutlinedGlyph, Rect)>
+		synthesize: bool, font_family_names: Option<&str>)
+		-> Option<(OutlinedGlyph, Rect, bool)>
 	{
 		self.with_db(|db| {
 			let mut families = vec![];
@@ -35,7 +45,7 @@ impl Fonts for UserFonts {
 				for (_, font) in self.borrow_fonts() {
 					if let Some(outlined) = get_glyph(char, font_size, font) {
 						let rect = font.glyph_bounds(outlined.glyph());
-						return Some((outlined, rect));
+						return Some((outlined, rect, false));
 					}
 				}
 				return None;
@@ -54,14 +64,43 @@ impl Fonts for UserFonts {
 			};
 			let id = db.query(&query)?;
 			let font = self.borrow_fonts().get(&id)?;
+			if synthesize && need_synthesize(font_weight, db.face(id)?.weight.0) {
+				// variable font has the weight
+				let mut font = font.clone();
+				if font.set_variation(WEIGHT_AXIS, font_weight.value() as f32) {
+					let outlined = get_glyph(char, font_size, &font)?;
+					let rect = font.glyph_bounds(outlined.glyph());
+					return Some((outlined, rect, false));
+				}
+				let outlined = get_glyph(char, font_size, &font)?;
+				let rect = font.glyph_bounds(outlined.glyph());
+				return Some((outlined, rect, true));
+			}
 			let outlined = get_glyph(char, font_size, font)?;
 			let rect = font.glyph_bounds(outlined.glyph());
-			Some((outlined, rect))
+			Some((outlined, rect, false))
 		})
 	}
 }
 
-fn create_user_fonts(db: Database) -> Result<Option<UserFonts>>
+#[inline]
+fn need_synthesize(font_weight: &FontWeight, face_weight: u16) -> bool
+{
+	font_weight.value() >= SYNTHESIZE_BOLD_WEIGHT && face_weight < SYNTHESIZE_BOLD_WEIGHT
+}
+
+/// set configured axes for variable font, static font not changed
+fn apply_font_axes(font: &mut impl VariableFont, axes: &FontAxes)
+{
+	if let Some(weight) = axes.weight {
+		font.set_variation(WEIGHT_AXIS, weight as f32);
+	}
+	if let Some(width) = axes.width {
+		font.set_variation(WIDTH_AXIS, width as f32);
+	}
+}
+
+fn create_user_fonts(db: Database, axes: &FontAxes) -> Result<Option<UserFonts>>
 {
 	if db.len() > 0 {
 		let mut err = None;
@@ -72,7 +111,10 @@ fn create_user_fonts(db: Database) -> Result<Option<UserFonts>>
 				for info in db.faces() {
 					if let fontdb::Source::Binary(bytes) = &info.source {
 						match FontRef::try_from_slice_and_index(bytes.as_ref().as_ref(), info.index) {
-							Ok(font) => { fonts.insert(info.id, font); }
+							Ok(mut font) => {
+								apply_font_axes(&mut font, axes);
+								fonts.insert(info.id, font);
+							}
 							Err(_) => err = Some(anyhow!("Error load font: {:#?}", info)),
 						}
 					}
@@ -90,7 +132,7 @@ fn create_user_fonts(db: Database) -> Result<Option<UserFonts>>
 	}
 }
 
-pub fn user_fonts(font_paths: &Vec<PathConfig>) -> Result<Option<UserFonts>>
+pub fn user_fonts(font_paths: &Vec<PathConfig>, axes: &FontAxes) -> Result<Option<UserFonts>>
 {
 	if font_paths.is_empty() {
 		Ok(None)
@@ -108,8 +150,69 @@ pub fn user_fonts(font_paths: &Vec<PathConfig>) -> Result<Option<UserFonts>>
 				}
 			}
 		}
-		create_user_fonts(db)
+		create_user_fonts(db, axes)
+	}
+}
+
+/// sample text rendered with font file, for font list in settings
+pub struct FontPreview {
+	pub family: String,
+	pub width: usize,
+	pub height: usize,
+	// RGBA
+	pub pixels: Vec<u8>,
+}
+
+pub fn font_preview(path: &PathBuf, text: &str, font_size: f32,
+	color: (u8, u8, u8)) -> Option<FontPreview>
+{
+	let data = Arc::new(fs::read(path).ok()?);
+	let mut db = Database::new();
+	db.load_font_source(fontdb::Source::Binary(data.clone()));
+	let face = db.faces().next()?;
+	let family = face.families
+		.first()
+		.map_or_else(|| face.post_script_name.clone(), |(name, _)| name.clone());
+	let font = FontRef::try_from_slice_and_index(data.as_slice(), face.index).ok()?;
+	let font = font.as_scaled(PxScale::from(font_size));
+
+	let ascent = font.ascent();
+	let height = (ascent - font.descent()).ceil() as usize;
+	let mut glyphs = vec![];
+	let mut x = 0.;
+	let mut prev = None;
+	for char in text.chars() {
+		let id = font.glyph_id(char);
+		if let Some(prev) = prev {
+			x += font.kern(prev, id);
+		}
+		glyphs.push(id.with_scale_and_position(font.scale(), ab_glyph::point(x, ascent)));
+		x += font.h_advance(id);
+		prev = Some(id);
 	}
+	let width = x.ceil() as usize;
+	if width == 0 || height == 0 {
+		return None;
+	}
+	let mut pixels = vec![0; width * height * 4];
+	for glyph in glyphs {
+		if let Some(outlined) = font.outline_glyph(glyph) {
+			let bounds = outlined.px_bounds();
+			outlined.draw(|gx, gy, coverage| {
+				let px = bounds.min.x as i32 + gx as i32;
+				let py = bounds.min.y as i32 + gy as i32;
+				if px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+					let offset = (py as usize * width + px as usize) * 4;
+					pixels[offset] = color.0;
+					pixels[offset + 1] = color.1;
+					pixels[offset + 2] = color.2;
+					let alpha = (coverage * 255.).round() as u8;
+					pixels[offset + 3] = pixels[offset + 3].max(alpha);
+				}
+			});
+		}
+	}
+	Some(FontPreview { family, width, height, pixels })
 }
 
 struct HtmlFontFace {
@@ -118,7 +221,8 @@ struct HtmlFontFace {
 }
 
 pub struct HtmlFonts {
-	fonts: Vec<(PathBuf, FontVec)>,
+	// path, font and weight of the face
+	fonts: Vec<(PathBuf, FontVec, u16)>,
 	faces: Vec<HtmlFontFace>,
 }
 
@@ -153,11 +257,12 @@ impl HtmlFonts {
 		for face in font_faces {
 			let mut refs = vec![];
 			for source in face.sources {
-				match self.fonts.binary_search_by(|(key, _)| key.cmp(&source)) {
+				match self.fonts.binary_search_by(|(key, _, _)| key.cmp(&source)) {
 					Ok(idx) => refs.push(idx),
 					Err(idx) => if let Some(content) = data_resolver(&source) {
+						let weight = face_weight(&content);
 						if let Ok(font) = FontVec::try_from_vec(content) {
-							self.fonts.insert(idx, (source, font));
+							self.fonts.insert(idx, (source, font, weight));
 							for v in &mut refs {
 								if *v >= idx {
 									*v += 1;
@@ -207,16 +312,17 @@ impl HtmlFonts {
 		true
 	}
 
-	fn find(&self, char: char, font_size: f32, _font_weight: &FontWeight,
-		font_family: &str) -> Option<(OutlinedGlyph, Rect)>
+	fn find(&self, char: char, font_size: f32, font_weight: &FontWeight,
+		synthesize: bool, font_family: &str) -> Option<(OutlinedGlyph, Rect, bool)>
 	{
 		if let Ok(idx) = self.faces.binary_search_by(|face| {
 			face.family.as_str().cmp(font_family)
 		}) {
-			if let Some((_, font)) = self.fonts.get(idx) {
+			if let Some((_, font, weight)) = self.fonts.get(idx) {
 				if let Some(outlined) = get_glyph(char, font_size, font) {
 					let rect = font.glyph_bounds(outlined.glyph());
-					return Some((outlined, rect));
+					let embolden = synthesize && need_synthesize(font_weight, *weight);
+					return Some((outlined, rect, embolden));
 				}
 			}
 		}
@@ -226,20 +332,21 @@ impl HtmlFonts {
 
 impl Fonts for HtmlFonts {
 	fn query(&self, char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_names: Option<&str>) -> Option<(OutlinedGlyph, Rect)>
+		synthesize: bool, font_family_names: Option<&str>)
+		-> Option<(OutlinedGlyph, Rect, bool)>
 	{
 		if let Some(names) = font_family_names {
 			for name in names.split(',') {
 				let name = name.trim();
 				if let Some(found) = self.find(
-					char, font_size, font_weight, name) {
+					char, font_size, font_weight, synthesize, name) {
 					return Some(found);
 				}
 			}
 		} else {
 			let name = GenericFontFamily::Default.as_str();
 			if let Some(found) = self.find(
-				char, font_size, font_weight, name) {
+				char, font_size, font_weight, synthesize, name) {
 				return Some(found);
 			}
 		}
@@ -247,6 +354,17 @@ impl Fonts for HtmlFonts {
 	}
 }
 
+/// weight of font face in data, normal for unknown
+fn face_weight(content: &Vec<u8>) -> u16
+{
+	let mut db = Database::new();
+	db.load_font_data(content.clone());
+	let weight = db.faces()
+		.next()
+		.map_or(fontdb::Weight::NORMAL.0, |face| face.weight.0);
+	weight
+}
+
 fn get_glyph(char: char, font_size: f32, font: &impl Font) -> Option<OutlinedGlyph>
 {
 	if let Some(scale) = font.pt_to_px_scale(font_size) {
@@ -1,28 +1,75 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
 
-use gtk4::{Align, EventControllerKey, glib, Label, ListBox, ListBoxRow, Orientation, Popover, SearchEntry, SelectionMode, StringList, StringObject, Widget};
-use gtk4::gdk::Key;
+use anyhow::{bail, Result};
+use gtk4::{AlertDialog, Align, CheckButton, EventControllerKey, FileDialog, FileLauncher, GestureClick, glib, Label, ListBox, ListBoxRow, Orientation, PolicyType, Popover, PopoverMenu, PositionType, ScrolledWindow, SearchEntry, SelectionMode, Separator, StringList, StringObject, Widget};
+use gtk4::gdk::{Key, ModifierType, Rectangle};
+use gtk4::gio::{Cancellable, File, Menu, MenuModel, SimpleAction, SimpleActionGroup};
 use gtk4::glib::markup_escape_text;
 use gtk4::pango::EllipsizeMode;
-use gtk4::prelude::{BoxExt, Cast, EditableExt, IsA, ListBoxRowExt, ListModelExt, PopoverExt, WidgetExt};
+use gtk4::prelude::{ActionMapExt, AdjustmentExt, BoxExt, Cast, CheckButtonExt, EditableExt, FileExt, IsA, ListBoxRowExt, ListModelExt, PopoverExt, WidgetExt};
 use crate::color::Color32;
 
-use crate::config::{Configuration, match_filename, ReadingInfo};
-use crate::gui::{GuiContext, ignore_cap, MODIFIER_NONE};
+use crate::config::{Configuration, match_filename, parse_history_filter, ReadingInfo};
+use crate::gui::{dialogs, GuiContext, ignore_cap, MODIFIER_NONE};
 use crate::gui::view::GuiView;
 
+const HISTORY_ACTION_GROUP: &str = "history";
+const TAGS_KEY: &str = "history-tags";
+const PIN_KEY: &str = "history-pin";
+const UNPIN_KEY: &str = "history-unpin";
+const FINISHED_KEY: &str = "history-finished";
+const UNFINISHED_KEY: &str = "history-unfinished";
+const OPEN_FOLDER_KEY: &str = "history-open-folder";
+const RENAME_KEY: &str = "history-rename";
+const MOVE_KEY: &str = "history-move";
+const DELETE_KEY: &str = "history-delete";
+const REMOVE_KEY: &str = "history-remove";
+// list scrolled when taller, for long history
+const HISTORY_MAX_HEIGHT: i32 = 480;
+const HISTORY_MIN_WIDTH: i32 = 360;
+
+/// details of listed entries, for creating rows and group headers
+#[derive(Default)]
+struct HistoryEntries {
+	// last read time and tags, by filename
+	details: HashMap<String, String>,
+	pinned: HashSet<String>,
+	pinned_title: String,
+	full_path: bool,
+	group_folder: bool,
+}
+
+impl HistoryEntries {
+	/// pinned entries in a group, others by folder if grouping
+	fn group_of(&self, filename: &str) -> Option<String>
+	{
+		if self.pinned.contains(filename) {
+			Some(String::new())
+		} else if self.group_folder {
+			Some(folder_of(filename).to_owned())
+		} else {
+			None
+		}
+	}
+}
+
 pub(super) struct HistoryList {
 	search: SearchEntry,
 	list_box: ListBox,
 	list: StringList,
 	popover: Popover,
+	full_path_cb: CheckButton,
+	group_folder_cb: CheckButton,
 
 	filter_pattern: Rc<RefCell<Option<String>>>,
 	match_tag_header: Rc<RefCell<String>>,
+	entries: Rc<RefCell<HistoryEntries>>,
 }
 
 impl HistoryList {
@@ -37,29 +84,77 @@ impl HistoryList {
 			.selection_mode(SelectionMode::Single)
 			.build();
 		let list = StringList::new(&[]);
-		let match_tag_header = {
+		let (match_tag_header, full_path_cb, group_folder_cb) = {
 			let configuration = cfg.borrow();
 			let gui = &configuration.gui;
 			let dark = configuration.dark_theme;
 			let colors = gui.curr_colors(dark);
-			make_matched_tag_header(&colors.matched_color, &colors.matched_background)
+			(make_matched_tag_header(&colors.matched_color, &colors.matched_background),
+				CheckButton::builder().active(gui.history_full_path).build(),
+				CheckButton::builder().active(gui.history_group_folder).build())
 		};
 		let match_tag_header = Rc::new(RefCell::new(match_tag_header));
+		let entries = Rc::new(RefCell::new(HistoryEntries::default()));
 		{
 			let pattern = filter_pattern.clone();
 			let match_tag_header = match_tag_header.clone();
+			let entries = entries.clone();
 			list_box.bind_model(Some(&list), move |obj| {
 				let obj = obj.downcast_ref::<StringObject>().unwrap();
-				gtk4::Widget::from(create_history_entry(
-					obj.string().as_str(),
-					pattern.borrow().as_ref().map(|s: &String| s.as_str()),
+				let filename = obj.string();
+				let pattern = pattern.borrow();
+				let name_pattern = pattern.as_ref()
+					.and_then(|pattern: &String| parse_history_filter(pattern).0);
+				let entries = entries.borrow();
+				create_history_entry(
+					filename.as_str(),
+					name_pattern.as_ref().map(|s| s.as_str()),
 					&match_tag_header.borrow(),
-				))
+					&entries,
+				)
+			});
+		}
+		{
+			let list = list.clone();
+			let entries = entries.clone();
+			list_box.set_header_func(move |row, before| {
+				let entries = entries.borrow();
+				let group_of = |row: &ListBoxRow| list
+					.string(row.index() as u32)
+					.and_then(|filename| entries.group_of(filename.as_str()));
+				let group = group_of(row);
+				let prev_group = before.and_then(|before| group_of(before));
+				if before.is_none() && group.is_none() || group == prev_group {
+					row.set_header(None::<&Widget>);
+					return;
+				}
+				let header: Widget = match &group {
+					Some(group) => Label::builder()
+						.label(if group.is_empty() { &entries.pinned_title } else { group })
+						.halign(Align::Start)
+						.ellipsize(EllipsizeMode::Start)
+						.css_classes(vec!["dim-label", "caption-heading"])
+						.build()
+						.upcast(),
+					None => Separator::new(Orientation::Horizontal).upcast(),
+				};
+				row.set_header(Some(&header));
 			});
 		}
 
+		let options = gtk4::Box::new(Orientation::Horizontal, 10);
+		options.append(&full_path_cb);
+		options.append(&group_folder_cb);
+		let scrolled = ScrolledWindow::builder()
+			.child(&list_box)
+			.hscrollbar_policy(PolicyType::Never)
+			.propagate_natural_height(true)
+			.max_content_height(HISTORY_MAX_HEIGHT)
+			.min_content_width(HISTORY_MIN_WIDTH)
+			.build();
 		container.append(&search);
-		container.append(&list_box);
+		container.append(&options);
+		container.append(&scrolled);
 		let popover = Popover::builder()
 			.child(&container)
 			.default_widget(&search)
@@ -75,44 +170,38 @@ impl HistoryList {
 			});
 		}
 		{
+			/// wrapped around at both ends
 			#[inline]
-			fn next_row(list: &ListBox) -> Option<ListBoxRow>
-			{
-				let selected = list.selected_row()?;
-				let idx = selected.index() + 1;
-				if let Some(row) = list.row_at_index(idx) {
-					Some(row)
-				} else {
-					list.row_at_index(0)
-				}
-			}
-			#[inline]
-			fn prev_row(list: &ListBox) -> Option<ListBoxRow>
+			fn step_row(list: &ListBox, count: u32, step: i32) -> Option<ListBoxRow>
 			{
-				let selected = list.selected_row()?;
-				let idx = selected.index();
-				if idx == 0 {
-					let last = list.last_child()?;
-					let row = last.downcast::<ListBoxRow>()
-						.ok()?;
-					list.row_at_index(row.index())
-				} else {
-					list.row_at_index(idx - 1)
+				if count == 0 {
+					return None;
 				}
+				let index = match list.selected_row() {
+					Some(row) => (row.index() + step).rem_euclid(count as i32),
+					None => 0,
+				};
+				list.row_at_index(index)
 			}
-			let list = list_box.clone();
+			let list_box = list_box.clone();
+			let list = list.clone();
+			let scrolled = scrolled.clone();
 			let key_event = EventControllerKey::new();
 			key_event.connect_key_pressed(move |_, key, _, modifier| {
 				let (key, modifier) = ignore_cap(key, modifier);
+				let count = list.n_items();
 				let target = match (key, modifier) {
-					(Key::Down, MODIFIER_NONE) => next_row(&list),
-					(Key::Up, MODIFIER_NONE) => prev_row(&list),
+					(Key::Down, MODIFIER_NONE) => step_row(&list_box, count, 1),
+					(Key::Up, MODIFIER_NONE) => step_row(&list_box, count, -1),
+					(Key::Page_Up, MODIFIER_NONE) => list_box.row_at_index(0),
+					(Key::Page_Down, MODIFIER_NONE) => list_box.row_at_index(count as i32 - 1),
 					_ => {
 						None
 					}
 				};
 				if let Some(row) = target {
-					list.select_row(Some(&row));
+					list_box.select_row(Some(&row));
+					scroll_to_row(&scrolled, &list_box, &row);
 					glib::Propagation::Stop
 				} else {
 					glib::Propagation::Proceed
@@ -129,8 +218,11 @@ impl HistoryList {
 			list_box,
 			list,
 			popover,
+			full_path_cb,
+			group_folder_cb,
 			filter_pattern,
 			match_tag_header,
+			entries,
 		}
 	}
 
@@ -152,6 +244,22 @@ impl HistoryList {
 		}
 
 		self.popover.set_parent(parent);
+		self.full_path_cb.set_label(Some(&gc.i18n.msg("history-full-path")));
+		self.group_folder_cb.set_label(Some(&gc.i18n.msg("history-group-folder")));
+		{
+			let gc = gc.clone();
+			self.full_path_cb.connect_toggled(move |cb| {
+				gc.cfg_mut().gui.history_full_path = cb.is_active();
+				gc.history_list.refresh(&gc);
+			});
+		}
+		{
+			let gc = gc.clone();
+			self.group_folder_cb.connect_toggled(move |cb| {
+				gc.cfg_mut().gui.history_group_folder = cb.is_active();
+				gc.history_list.refresh(&gc);
+			});
+		}
 
 		{
 			let gc = gc.clone();
@@ -163,17 +271,38 @@ impl HistoryList {
 				}
 			});
 		}
+		{
+			// pin or unpin selected entry
+			let gc = gc.clone();
+			let key_event = EventControllerKey::new();
+			key_event.connect_key_pressed(move |_, key, _, modifier| {
+				let (key, modifier) = ignore_cap(key, modifier);
+				if (key, modifier) != (Key::p, ModifierType::CONTROL_MASK) {
+					return glib::Propagation::Proceed;
+				}
+				if let Some(path) = gc.history_list.selected_path() {
+					let pinned = gc.history_list.entries.borrow().pinned
+						.contains(path.to_string_lossy().as_ref());
+					if pinned {
+						unpin(&gc, path);
+					} else {
+						pin(&gc, path);
+					}
+				}
+				glib::Propagation::Stop
+			});
+			self.search.add_controller(key_event);
+		}
 
 		{
 			let gc = gc.clone();
 			let list = self.list.clone();
 			self.list_box.connect_row_activated(move |_, row| open(&gc, row.index(), &list));
 		}
+		self.setup_context_menu(gc);
 		{
 			let filter_pattern = self.filter_pattern.clone();
 			let gc = gc.clone();
-			let list = self.list.clone();
-			let list_box = self.list_box.clone();
 			self.search.connect_search_changed(move |entry| {
 				let text = entry.text();
 				let text = text.as_str().trim();
@@ -186,16 +315,88 @@ impl HistoryList {
 				}
 				if let Some(infos) = gc.filter_history(pattern.as_ref()) {
 					drop(pattern);
-					update_history(infos, &list, &list_box);
+					gc.history_list.update_history(infos, &gc);
 				}
 			});
 		}
 	}
 
+	/// file operations on entry, with right click
+	fn setup_context_menu(&self, gc: &GuiContext)
+	{
+		let operations: [(&str, fn(&GuiContext, PathBuf)); 10] = [
+			(TAGS_KEY, edit_tags),
+			(PIN_KEY, pin),
+			(UNPIN_KEY, unpin),
+			(FINISHED_KEY, mark_finished),
+			(UNFINISHED_KEY, unmark_finished),
+			(OPEN_FOLDER_KEY, open_folder),
+			(RENAME_KEY, rename),
+			(MOVE_KEY, move_to),
+			(DELETE_KEY, delete),
+			(REMOVE_KEY, remove),
+		];
+		let action_group = SimpleActionGroup::new();
+		let menu = Menu::new();
+		for (key, operation) in operations {
+			let action = SimpleAction::new(key, None);
+			{
+				let gc = gc.clone();
+				action.connect_activate(move |_, _| {
+					if let Some(path) = gc.history_list.selected_path() {
+						operation(&gc, path);
+					}
+				});
+			}
+			action_group.add_action(&action);
+			let action_name = format!("{}.{}", HISTORY_ACTION_GROUP, key);
+			menu.append(Some(&gc.i18n.msg(key)), Some(&action_name));
+		}
+		self.list_box.insert_action_group(HISTORY_ACTION_GROUP, Some(&action_group));
+
+		let pm = PopoverMenu::builder()
+			.has_arrow(false)
+			.position(PositionType::Bottom)
+			.menu_model(&MenuModel::from(menu))
+			.build();
+		pm.set_parent(&self.list_box);
+		let gesture = GestureClick::builder()
+			.button(3)
+			.build();
+		let list_box = self.list_box.clone();
+		gesture.connect_pressed(move |_, _, x, y| {
+			if let Some(row) = list_box.row_at_y(y as i32) {
+				list_box.select_row(Some(&row));
+				pm.set_pointing_to(Some(&Rectangle::new(x as i32, y as i32, 1, 1)));
+				pm.popup();
+			}
+		});
+		self.list_box.add_controller(gesture);
+	}
+
 	#[inline]
-	pub fn popup(&self, infos: Vec<ReadingInfo>)
+	fn selected_path(&self) -> Option<PathBuf>
 	{
-		update_history(infos, &self.list, &self.list_box);
+		let row = self.list_box.selected_row()?;
+		let str = self.list.string(row.index() as u32)?;
+		let Ok(path) = PathBuf::from_str(str.as_str());
+		Some(path)
+	}
+
+	/// reload entries after history changed
+	fn refresh(&self, gc: &GuiContext)
+	{
+		let pattern = self.filter_pattern.borrow();
+		if let Some(infos) = gc.filter_history(pattern.as_ref()) {
+			drop(pattern);
+			self.update_history(infos, gc);
+		}
+	}
+
+	#[inline]
+	pub fn popup(&self, infos: Vec<ReadingInfo>, gc: &GuiContext)
+	{
+		self.update_history(infos, gc);
 		self.popover.popup();
 	}
 
@@ -205,25 +406,310 @@ impl HistoryList {
 		let header = make_matched_tag_header(&color, &background);
 		*self.match_tag_header.borrow_mut() = header;
 	}
+
+	/// entries of the same folder together if grouping, in order of
+	/// their latest reading, selection kept if still listed
+	fn update_history(&self, mut infos: Vec<ReadingInfo>, gc: &GuiContext)
+	{
+		let selected = self.selected_path();
+		let mut vec = vec![];
+		{
+			// details needed when entries created by splice
+			let configuration = gc.cfg();
+			let datetime_format = gc.i18n.msg("datetime-format");
+			let mut entries = self.entries.borrow_mut();
+			entries.details.clear();
+			entries.pinned.clear();
+			entries.full_path = configuration.gui.history_full_path;
+			entries.group_folder = configuration.gui.history_group_folder;
+			entries.pinned_title = gc.i18n.msg("history-pinned").to_string();
+			for ri in &infos {
+				let mut details = glib::DateTime::from_unix_local(ri.ts as i64)
+					.ok()
+					.and_then(|time| time.format(&datetime_format).ok())
+					.map_or_else(Vec::new, |time| vec![time.to_string()]);
+				if let Ok(tags) = configuration.tags(ri) {
+					details.extend(tags.iter().map(|tag| format!("#{}", tag)));
+				}
+				if !details.is_empty() {
+					entries.details.insert(ri.filename.clone(), details.join(" "));
+				}
+				if configuration.is_pinned(&ri.filename).unwrap_or(false) {
+					entries.pinned.insert(ri.filename.clone());
+				}
+			}
+			if entries.group_folder {
+				let mut folders = HashMap::new();
+				for ri in &infos {
+					let next = folders.len();
+					folders.entry(folder_of(&ri.filename).to_owned()).or_insert(next);
+				}
+				// stable, pinned entries kept first
+				infos.sort_by_key(|ri| (!entries.pinned.contains(&ri.filename),
+					folders[folder_of(&ri.filename)]));
+			}
+		}
+		for ri in &infos {
+			vec.push(ri.filename.as_str());
+		}
+		self.list.splice(0, self.list.n_items(), &vec);
+		self.list_box.invalidate_headers();
+		let index = selected
+			.and_then(|path| vec.iter().position(|filename| Path::new(filename) == path))
+			.unwrap_or(0);
+		self.list_box.select_row(self.list_box.row_at_index(index as i32).as_ref());
+	}
+}
+
+/// scroll the list to show the row selected by keys
+fn scroll_to_row(scrolled: &ScrolledWindow, list_box: &ListBox, row: &ListBoxRow)
+{
+	let Some(bounds) = row.compute_bounds(list_box) else {
+		return;
+	};
+	let adjustment = scrolled.vadjustment();
+	let top = bounds.y() as f64;
+	let bottom = top + bounds.height() as f64;
+	if top < adjustment.value() {
+		adjustment.set_value(top);
+	} else if bottom > adjustment.value() + adjustment.page_size() {
+		adjustment.set_value(bottom - adjustment.page_size());
+	}
+}
+
+#[inline]
+fn folder_of(filename: &str) -> &str
+{
+	Path::new(filename)
+		.parent()
+		.and_then(|folder| folder.to_str())
+		.unwrap_or("")
+}
+
+fn pin(gc: &GuiContext, path: PathBuf)
+{
+	let result = gc.cfg().pin(&path.to_string_lossy());
+	entry_changed(gc, result);
+}
+
+fn unpin(gc: &GuiContext, path: PathBuf)
+{
+	let result = gc.cfg().unpin(&path.to_string_lossy());
+	entry_changed(gc, result);
+}
+
+/// user tags separated by space or comma
+fn edit_tags(gc: &GuiContext, path: PathBuf)
+{
+	let filename = path.to_string_lossy().to_string();
+	let tags = match gc.cfg().user_tags(&filename) {
+		Ok(tags) => tags.join(" "),
+		Err(err) => {
+			gc.error(&err.to_string());
+			return;
+		}
+	};
+	let gc2 = gc.clone();
+	dialogs::tags(gc, &tags, &gc.window, move |text| {
+		let tags = text
+			.split([' ', ','])
+			.map(|tag| tag.trim_start_matches('#'))
+			.filter(|tag| !tag.is_empty())
+			.collect::<Vec<_>>();
+		gc2.cfg().set_tags(&filename, &tags)?;
+		gc2.history_list.refresh(&gc2);
+		Ok(())
+	});
+}
+
+fn mark_finished(gc: &GuiContext, path: PathBuf)
+{
+	let result = gc.cfg().mark_finished(&path.to_string_lossy());
+	entry_changed(gc, result);
+}
+
+fn unmark_finished(gc: &GuiContext, path: PathBuf)
+{
+	let result = gc.cfg().unmark_finished(&path.to_string_lossy());
+	entry_changed(gc, result);
+}
+
+#[inline]
+fn entry_changed(gc: &GuiContext, result: Result<()>)
+{
+	match result {
+		Ok(()) => gc.history_list.refresh(gc),
+		Err(err) => gc.error(&err.to_string()),
+	}
+}
+
+fn open_folder(gc: &GuiContext, path: PathBuf)
+{
+	let gc = gc.clone();
+	FileLauncher::new(Some(&File::for_path(&path)))
+		.open_containing_folder(Some(&gc.window.clone()), None::<&Cancellable>, move |result| {
+			if let Err(err) = result {
+				gc.error(&err.to_string());
+			}
+		});
+}
+
+/// reading info in other windows would be saved with old filename
+#[inline]
+fn check_opened(gc: &GuiContext, path: &Path) -> Result<()>
+{
+	if gc.settings.is_opened(&path.to_string_lossy()) {
+		bail!("{}", gc.i18n.msg("history-book-opened"))
+	}
+	Ok(())
+}
+
+fn rename(gc: &GuiContext, path: PathBuf)
+{
+	let Some(name) = path.file_name() else {
+		return;
+	};
+	let name = name.to_string_lossy().to_string();
+	let gc2 = gc.clone();
+	dialogs::rename(gc, &name, &gc.window, move |name| {
+		let Some(folder) = path.parent() else {
+			return Ok(());
+		};
+		move_book(&gc2, &path, &folder.join(name))
+	});
+}
+
+fn move_to(gc: &GuiContext, path: PathBuf)
+{
+	let dialog = FileDialog::new();
+	dialog.set_title(&gc.i18n.msg(MOVE_KEY));
+	dialog.set_modal(true);
+	if let Some(folder) = path.parent() {
+		dialog.set_initial_folder(Some(&File::for_path(folder)));
+	}
+	let gc = gc.clone();
+	dialog.select_folder(Some(&gc.window.clone()), None::<&Cancellable>, move |result| {
+		let Ok(folder) = result else {
+			return;
+		};
+		let (Some(folder), Some(name)) = (folder.path(), path.file_name()) else {
+			return;
+		};
+		if let Err(err) = move_book(&gc, &path, &folder.join(name)) {
+			gc.error(&err.to_string());
+		}
+	});
+}
+
+/// rename or move the book file, and its history
+fn move_book(gc: &GuiContext, path: &Path, target: &Path) -> Result<()>
+{
+	if path == target {
+		return Ok(());
+	}
+	check_opened(gc, path)?;
+	if target.exists() {
+		bail!("{}", gc.i18n.args_msg("history-target-exists", vec![
+			("path", target.to_string_lossy()),
+		]));
+	}
+	if fs::rename(path, target).is_err() {
+		// rename not work across file systems
+		if path.is_dir() {
+			bail!("{}", gc.i18n.msg("history-move-folder"));
+		}
+		fs::copy(path, target)?;
+		fs::remove_file(path)?;
+	}
+	gc.cfg().rename_history(&path.to_string_lossy(), &target.to_string_lossy())?;
+	gc.history_list.refresh(gc);
+	Ok(())
+}
+
+/// move the book to trash after confirmed
+fn delete(gc: &GuiContext, path: PathBuf)
+{
+	if let Err(err) = check_opened(gc, &path) {
+		gc.error(&err.to_string());
+		return;
+	}
+	let i18n = &gc.i18n;
+	let dialog = AlertDialog::builder()
+		.modal(true)
+		.message(i18n.msg(DELETE_KEY))
+		.detail(i18n.args_msg("history-delete-confirm", vec![
+			("path", path.to_string_lossy()),
+		]))
+		.buttons([i18n.msg("cancel-title").as_ref(), i18n.msg(DELETE_KEY).as_ref()])
+		.cancel_button(0)
+		.default_button(0)
+		.build();
+	let gc = gc.clone();
+	dialog.choose(Some(&gc.window.clone()), None::<&Cancellable>, move |result| {
+		if result != Ok(1) {
+			return;
+		}
+		if let Err(err) = File::for_path(&path).trash(None::<&Cancellable>) {
+			gc.error(&err.to_string());
+			return;
+		}
+		remove(&gc, path);
+	});
+}
+
+/// remove from history only, file kept
+fn remove(gc: &GuiContext, path: PathBuf)
+{
+	if let Err(err) = gc.cfg().remove_history(&path.to_string_lossy()) {
+		gc.error(&err.to_string());
+		return;
+	}
+	gc.history_list.refresh(gc);
 }
 
 #[inline]
 fn create_history_entry(path_str: &str, pattern: Option<&str>,
+	matched_tag_header: &str, entries: &HistoryEntries) -> Widget
+{
+	let name = if entries.full_path {
+		path_str
+	} else {
+		Path::new(path_str)
+			.file_name()
+			.and_then(|name| name.to_str())
+			.unwrap_or(path_str)
+	};
+	let label = create_path_label(name, path_str, pattern, matched_tag_header);
+	let Some(details) = entries.details.get(path_str) else {
+		return label.upcast();
+	};
+	label.set_hexpand(true);
+	let entry = gtk4::Box::new(Orientation::Horizontal, 10);
+	entry.append(&label);
+	entry.append(&Label::builder()
+		.label(details)
+		.css_classes(vec!["dim-label"])
+		.build());
+	entry.upcast()
+}
+
+#[inline]
+fn create_path_label(name: &str, path_str: &str, pattern: Option<&str>,
 	matched_tag_header: &str) -> Label
 {
 	if let Some(pattern) = pattern {
-		let markup = path_markup(path_str, pattern, matched_tag_header);
+		let markup = path_markup(name, pattern, matched_tag_header);
 		let str = markup.as_ref();
 		Label::builder()
 			.use_markup(true)
 			.label(str)
 			.halign(Align::Start)
 			.ellipsize(EllipsizeMode::End)
-			.tooltip_markup(str)
+			.tooltip_text(path_str)
 			.build()
 	} else {
 		Label::builder()
-			.label(path_str)
+			.label(name)
 			.halign(Align::Start)
 			.ellipsize(EllipsizeMode::End)
 			.tooltip_text(path_str)
@@ -258,18 +744,8 @@ fn path_markup<'a>(path: &'a str, pattern: &str, matched_tag_header: &str) -> Co
 			return Cow::Owned(text);
 		}
 	}
-	Cow::Borrowed(path)
-}
-
-#[inline]
-fn update_history(infos: Vec<ReadingInfo>, list: &StringList, list_box: &ListBox)
-{
-	let mut vec = vec![];
-	for ri in &infos {
-		vec.push(ri.filename.as_str());
-	}
-	list.splice(0, list.n_items(), &vec);
-	list_box.select_row(list_box.row_at_index(0).as_ref());
+	// label used as markup
+	Cow::Owned(markup_escape_text(path).to_string())
 }
 
 #[inline]
@@ -0,0 +1,106 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use gtk4::gio::{Cancellable, Subprocess, SubprocessFlags};
+use serde_json::Value;
+
+use crate::download::CURL_COMMAND;
+
+/// post json body, callback with response body or error message
+pub(super) fn post_json<F>(url: &str, headers: &[String], body: &Value, callback: F)
+	-> Result<()>
+	where F: FnOnce(Result<String, String>) + 'static
+{
+	let process = Subprocess::newv(
+		&[OsStr::new(CURL_COMMAND), OsStr::new("-sS"), OsStr::new("-K"), OsStr::new("-")],
+		SubprocessFlags::STDIN_PIPE | SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE)?;
+	let request = curl_config(url, headers, body)?;
+	process.communicate_utf8_async(Some(request), None::<&Cancellable>, move |result| {
+		let response = match result {
+			Ok((Some(stdout), _)) if !stdout.is_empty() => Ok(stdout.to_string()),
+			Ok((_, stderr)) => Err(stderr.map_or_else(String::new, |stderr| stderr.trim().to_owned())),
+			Err(err) => Err(err.to_string()),
+		};
+		callback(response);
+	});
+	Ok(())
+}
+
+/// download url to file, callback with error message when failed
+pub(super) fn download<F>(url: &str, path: &Path, callback: F) -> Result<()>
+	where F: FnOnce(Result<(), String>) + 'static
+{
+	let process = Subprocess::newv(
+		&[OsStr::new(CURL_COMMAND), OsStr::new("-sSfL"), OsStr::new("-o"), path.as_os_str(), OsStr::new(url)],
+		SubprocessFlags::STDERR_PIPE)?;
+	let child = process.clone();
+	process.communicate_utf8_async(None, None::<&Cancellable>, move |result| {
+		let result = match result {
+			Ok(_) if child.is_successful() => Ok(()),
+			Ok((_, stderr)) => Err(stderr.map_or_else(String::new, |stderr| stderr.trim().to_owned())),
+			Err(err) => Err(err.to_string()),
+		};
+		callback(result);
+	});
+	Ok(())
+}
+
+/// curl config read from stdin, keep api key out of process arguments
+fn curl_config(url: &str, headers: &[String], body: &Value) -> Result<String>
+{
+	let mut config = format!("url = \"{}\"\n", curl_quote(url)?);
+	config.push_str("header = \"Content-Type: application/json\"\n");
+	for header in headers {
+		config.push_str(&format!("header = \"{}\"\n", curl_quote(header)?));
+	}
+	config.push_str(&format!("data = \"{}\"\n", curl_quote(&body.to_string())?));
+	Ok(config)
+}
+
+/// quote value in curl config, line breaks escaped so no other option
+/// can be injected, other control characters rejected
+fn curl_quote(text: &str) -> Result<String>
+{
+	let mut quoted = String::with_capacity(text.len());
+	for ch in text.chars() {
+		match ch {
+			'\\' => quoted.push_str("\\\\"),
+			'"' => quoted.push_str("\\\""),
+			'\n' => quoted.push_str("\\n"),
+			'\r' => quoted.push_str("\\r"),
+			'\t' => quoted.push_str("\\t"),
+			ch if ch.is_control() => bail!("Invalid control character in request: {:?}", ch),
+			ch => quoted.push(ch),
+		}
+	}
+	Ok(quoted)
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use crate::gui::http::{curl_config, curl_quote};
+
+	#[test]
+	fn test_curl_quote()
+	{
+		assert_eq!(curl_quote(r#"a "b" \c"#).unwrap(), r#"a \"b\" \\c"#);
+		assert_eq!(curl_quote("a\nb\rc\td").unwrap(), r"a\nb\rc\td");
+		assert!(curl_quote("a\u{1b}b").is_err());
+	}
+
+	#[test]
+	fn test_curl_config_injection()
+	{
+		let url = "http://localhost:8765\noutput = \"/tmp/x\"";
+		let headers = vec!["X-Key: 1\r\nurl = http://evil".to_owned()];
+		let config = curl_config(url, &headers, &json!({ "text": "line\noutput = x" })).unwrap();
+		// one option per line, nothing injected
+		assert_eq!(config.lines().count(), 4);
+		assert!(config.lines().all(|line| line.starts_with("url = ")
+			|| line.starts_with("header = ")
+			|| line.starts_with("data = ")));
+	}
+}
@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk4::{Align, Button, EventControllerKey, glib, Label, Orientation, PolicyType, Popover, PositionType, ScrolledWindow, Widget};
+use gtk4::gdk::{Key, Rectangle};
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::{BoxExt, ButtonExt, IsA, PopoverExt, WidgetExt};
+
+use crate::i18n::I18n;
+
+// definition cut to keep the popup compact, full one in dictionary panel
+const MAX_DEFINITION_CHARS: usize = 400;
+const POPUP_WIDTH_CHARS: i32 = 36;
+const POPUP_MAX_HEIGHT: i32 = 240;
+
+struct LookupPopupInner {
+	popover: Popover,
+	word: Label,
+	definition: Label,
+	lookup: RefCell<String>,
+	expanded: RefCell<Option<Rc<dyn Fn(String)>>>,
+}
+
+/// compact definition of the selected word anchored near the selection,
+/// expandable to the dictionary panel, closed by escape or click outside
+#[derive(Clone)]
+pub(super) struct LookupPopup {
+	inner: Rc<LookupPopupInner>,
+}
+
+impl LookupPopup {
+	pub fn create(i18n: &I18n, parent: &impl IsA<Widget>) -> Self
+	{
+		let word = Label::builder()
+			.halign(Align::Start)
+			.xalign(0.)
+			.ellipsize(EllipsizeMode::End)
+			.css_classes(vec!["heading"])
+			.build();
+		let definition = Label::builder()
+			.halign(Align::Start)
+			.valign(Align::Start)
+			.xalign(0.)
+			.wrap(true)
+			.max_width_chars(POPUP_WIDTH_CHARS)
+			.build();
+		let expand_btn = Button::builder()
+			.label(i18n.msg("lookup-popup-expand"))
+			.halign(Align::End)
+			.build();
+		let container = gtk4::Box::new(Orientation::Vertical, 6);
+		container.append(&word);
+		container.append(&ScrolledWindow::builder()
+			.child(&definition)
+			.hscrollbar_policy(PolicyType::Never)
+			.propagate_natural_height(true)
+			.max_content_height(POPUP_MAX_HEIGHT)
+			.build());
+		container.append(&expand_btn);
+		let popover = Popover::builder()
+			.child(&container)
+			.position(PositionType::Bottom)
+			.build();
+		popover.set_parent(parent);
+		popover.connect_closed(|popover| {
+			if let Some(parent) = popover.parent() {
+				parent.grab_focus();
+			}
+		});
+
+		let popup = LookupPopup {
+			inner: Rc::new(LookupPopupInner {
+				popover,
+				word,
+				definition,
+				lookup: RefCell::new(String::new()),
+				expanded: RefCell::new(None),
+			})
+		};
+		{
+			let popup = popup.clone();
+			expand_btn.connect_clicked(move |_| popup.expand());
+		}
+		let key_event = EventControllerKey::new();
+		{
+			let popup = popup.clone();
+			key_event.connect_key_pressed(move |_, key, _, _| match key {
+				Key::Return | Key::KP_Enter => {
+					popup.expand();
+					glib::Propagation::Stop
+				}
+				_ => glib::Propagation::Proceed,
+			});
+		}
+		popup.inner.popover.add_controller(key_event);
+		popup
+	}
+
+	/// callback with the word for looking up in dictionary panel
+	pub fn handle_expand<F>(&self, expanded: F)
+		where F: Fn(String) + 'static
+	{
+		self.inner.expanded.replace(Some(Rc::new(expanded)));
+	}
+
+	/// pointed to the rect of parent, clamped inside it so the popup
+	/// not placed outside of view for selection partially shown
+	pub fn show(&self, word: &str, definition: &str, rect: Rectangle)
+	{
+		let inner = &self.inner;
+		inner.lookup.replace(word.to_owned());
+		inner.word.set_label(word);
+		inner.definition.set_label(&compact(definition));
+
+		let (width, height) = inner.popover.parent()
+			.map_or((rect.width(), rect.height()), |parent| (parent.width(), parent.height()));
+		let x = rect.x().clamp(0, (width - 1).max(0));
+		let y = rect.y().clamp(0, (height - 1).max(0));
+		let rect = Rectangle::new(
+			x,
+			y,
+			rect.width().min(width - x).max(1),
+			rect.height().min(height - y).max(1),
+		);
+		inner.popover.set_pointing_to(Some(&rect));
+		inner.popover.popup();
+	}
+
+	fn expand(&self)
+	{
+		self.inner.popover.popdown();
+		let callback = self.inner.expanded.borrow().clone();
+		if let Some(callback) = callback {
+			callback(self.inner.lookup.borrow().clone());
+		}
+	}
+}
+
+#[inline]
+fn compact(definition: &str) -> String
+{
+	match definition.char_indices().nth(MAX_DEFINITION_CHARS) {
+		Some((index, _)) => format!("{}…", definition[..index].trim_end()),
+		None => definition.to_owned(),
+	}
+}
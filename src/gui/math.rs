@@ -145,6 +145,15 @@ impl Rect {
 	pub fn contains(&self, p: &Pos2) -> bool {
 		self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
 	}
+
+	/// smallest rect contains both
+	#[inline(always)]
+	pub fn union(&self, other: &Rect) -> Rect {
+		Rect {
+			min: pos2(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+			max: pos2(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+		}
+	}
 }
 
 impl Display for Rect {
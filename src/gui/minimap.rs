@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk4::{DrawingArea, GestureClick};
+use gtk4::cairo::Context as CairoContext;
+use gtk4::prelude::{DrawingAreaExtManual, GestureSingleExt, WidgetExt};
+
+use crate::book::SearchPattern;
+use crate::color::{Color32, Colors};
+use crate::gui::GuiController;
+use crate::gui::render::handle_cairo;
+
+const MINIMAP_WIDTH: i32 = 24;
+// alpha of paragraph density bars and the viewport
+const DENSITY_ALPHA: f64 = 0.35;
+const VIEWPORT_ALPHA: f64 = 0.2;
+// minimum height of marks, so single line visible in long chapter
+const MIN_MARK_HEIGHT: f64 = 2.;
+
+#[derive(Default)]
+struct MiniMapMarks {
+	// (inner book, chapter, search pattern) of loaded marks
+	loaded: Option<(usize, usize, String)>,
+	// text length of lines, relative to the longest
+	density: Vec<f32>,
+	matches: Vec<usize>,
+	notes: Vec<usize>,
+	// first and last line of current page
+	viewport: (usize, usize),
+	highlight: Option<usize>,
+	colors: Option<Colors>,
+}
+
+struct MiniMapInner {
+	area: DrawingArea,
+	ctrl: Rc<RefCell<GuiController>>,
+	marks: Rc<RefCell<MiniMapMarks>>,
+	line_clicked: RefCell<Option<Rc<dyn Fn(usize)>>>,
+}
+
+/// strip of current chapter beside the view, with paragraph density,
+/// search matches, notes and current page, click to jump
+#[derive(Clone)]
+pub struct MiniMap {
+	inner: Rc<MiniMapInner>,
+}
+
+impl MiniMap {
+	pub fn create(ctrl: &Rc<RefCell<GuiController>>, visible: bool) -> Self
+	{
+		let area = DrawingArea::builder()
+			.width_request(MINIMAP_WIDTH)
+			.vexpand(true)
+			.visible(visible)
+			.build();
+		let marks = Rc::new(RefCell::new(MiniMapMarks::default()));
+		{
+			let marks = marks.clone();
+			area.set_draw_func(move |_, cairo, width, height| {
+				draw(&marks.borrow(), cairo, width as f64, height as f64);
+			});
+		}
+		let minimap = MiniMap {
+			inner: Rc::new(MiniMapInner {
+				area,
+				ctrl: ctrl.clone(),
+				marks,
+				line_clicked: RefCell::new(None),
+			})
+		};
+		let click = GestureClick::new();
+		click.set_button(gtk4::gdk::BUTTON_PRIMARY);
+		{
+			let minimap = minimap.clone();
+			click.connect_pressed(move |_, _, _, y| {
+				let height = minimap.inner.area.height();
+				let total = minimap.inner.marks.borrow().density.len();
+				if height <= 0 || total == 0 {
+					return;
+				}
+				let line = ((y / height as f64) * total as f64) as usize;
+				let callback = minimap.inner.line_clicked.borrow().clone();
+				if let Some(callback) = callback {
+					callback(line.min(total - 1));
+				}
+			});
+		}
+		minimap.inner.area.add_controller(click);
+		minimap
+	}
+
+	#[inline]
+	pub fn widget(&self) -> &DrawingArea
+	{
+		&self.inner.area
+	}
+
+	/// callback with the line index clicked
+	pub fn handle_line_click<F>(&self, line_clicked: F)
+		where F: Fn(usize) + 'static
+	{
+		self.inner.line_clicked.replace(Some(Rc::new(line_clicked)));
+	}
+
+	#[inline]
+	pub fn set_visible(&self, visible: bool)
+	{
+		self.inner.area.set_visible(visible);
+	}
+
+	/// reload marks if chapter or search pattern changed, and current page
+	pub fn sync(&self, colors: &Colors)
+	{
+		if !self.inner.area.is_visible() {
+			return;
+		}
+		let controller = self.inner.ctrl.borrow();
+		let mut marks = self.inner.marks.borrow_mut();
+		let loaded = (controller.reading.inner_book, controller.reading.chapter,
+			controller.search_pattern.clone());
+		let lines = controller.book.lines();
+		if marks.loaded.as_ref() != Some(&loaded) {
+			let longest = lines.iter().map(|line| line.len()).max().unwrap_or(0).max(1);
+			marks.density = lines
+				.iter()
+				.map(|line| line.len() as f32 / longest as f32)
+				.collect();
+			marks.matches = if controller.search_pattern.is_empty() {
+				vec![]
+			} else if let Ok(pattern) = SearchPattern::new(
+				&controller.search_pattern, controller.search_fold_width, false) {
+				lines
+					.iter()
+					.enumerate()
+					.filter(|(_, line)| line.search_pattern_once(&pattern, None, None, false).is_some())
+					.map(|(index, _)| index)
+					.collect()
+			} else {
+				vec![]
+			};
+			marks.notes = controller.book.notes()
+				.map_or_else(Vec::new, |notes| notes
+					.iter()
+					.map(|info| info.reference.as_ref().unwrap_or(&info.note).start.line)
+					.collect());
+			marks.loaded = Some(loaded);
+		}
+		let first = controller.reading.line;
+		let last = controller.next_page_position()
+			.map_or(lines.len().saturating_sub(1), |next| next.line.max(first));
+		marks.viewport = (first, last);
+		marks.highlight = controller.highlight_line();
+		marks.colors = Some(colors.clone());
+		drop(marks);
+		drop(controller);
+		self.inner.area.queue_draw();
+	}
+}
+
+fn draw(marks: &MiniMapMarks, cairo: &CairoContext, width: f64, height: f64)
+{
+	let Some(colors) = &marks.colors else {
+		return;
+	};
+	let total = marks.density.len();
+	if total == 0 {
+		return;
+	}
+	let line_height = height / total as f64;
+	let mark = |line: usize, color: &Color32, alpha: f64| {
+		set_color(cairo, color, alpha);
+		cairo.rectangle(0., line as f64 * line_height, width,
+			line_height.max(MIN_MARK_HEIGHT));
+		handle_cairo(cairo.fill());
+	};
+
+	set_color(cairo, &colors.background, 1.);
+	handle_cairo(cairo.paint());
+	// lines in the same pixel row merged with the longest one
+	set_color(cairo, &colors.color, DENSITY_ALPHA);
+	let rows = (height as usize).max(1);
+	let mut row_density = vec![0f32; rows];
+	for (index, density) in marks.density.iter().enumerate() {
+		let row = (index * rows / total).min(rows - 1);
+		row_density[row] = row_density[row].max(*density);
+	}
+	let row_height = height / rows as f64;
+	for (row, density) in row_density.iter().enumerate() {
+		if *density > 0. {
+			cairo.rectangle(0., row as f64 * row_height,
+				width * *density as f64, row_height);
+		}
+	}
+	handle_cairo(cairo.fill());
+
+	let (first, last) = marks.viewport;
+	set_color(cairo, &colors.highlight_background, VIEWPORT_ALPHA);
+	cairo.rectangle(0., first as f64 * line_height, width,
+		((last + 1 - first) as f64 * line_height).max(MIN_MARK_HEIGHT));
+	handle_cairo(cairo.fill());
+
+	for line in &marks.notes {
+		mark(*line, &colors.link, 1.);
+	}
+	for line in &marks.matches {
+		mark(*line, &colors.matched_background, 1.);
+	}
+	if let Some(line) = marks.highlight {
+		mark(line, &colors.highlight_background, 1.);
+	}
+}
+
+#[inline]
+fn set_color(cairo: &CairoContext, color: &Color32, alpha: f64)
+{
+	cairo.set_source_rgba(
+		color.r() as f64 / 255.,
+		color.g() as f64 / 255.,
+		color.b() as f64 / 255.,
+		alpha,
+	);
+}
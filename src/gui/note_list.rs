@@ -0,0 +1,217 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::cmp;
+use std::ops::Range;
+use std::rc::Rc;
+
+use gtk4::{Align, Label, ListBox, ListBoxRow, Orientation, PolicyType, SelectionMode};
+use gtk4::pango::{EllipsizeMode, WrapMode};
+use gtk4::prelude::{BoxExt, ButtonExt, ListBoxRowExt, WidgetExt};
+
+use crate::book::Line;
+use crate::common::Position;
+use crate::gui::{create_button, GuiController, IconMap};
+use crate::i18n::I18n;
+
+// lines of note text in list
+const NOTE_TEXT_LINES: i32 = 4;
+
+struct NoteEntry {
+	note: Range<Position>,
+	reference: Option<Range<Position>>,
+}
+
+struct NoteListInner {
+	list: ListBox,
+	ctrl: Rc<RefCell<GuiController>>,
+	entries: RefCell<Vec<NoteEntry>>,
+	// (inner book, chapter) of loaded notes
+	loaded: Cell<Option<(usize, usize)>>,
+	syncing: Cell<bool>,
+	position_clicked: RefCell<Option<Rc<dyn Fn(&Range<Position>)>>>,
+	icons: Rc<IconMap>,
+	i18n: Rc<I18n>,
+}
+
+/// footnotes and endnotes of current chapter, for reading notes in sequence
+#[derive(Clone)]
+pub struct NoteList {
+	inner: Rc<NoteListInner>,
+}
+
+impl NoteList {
+	pub fn create(icons: &Rc<IconMap>, i18n: &Rc<I18n>,
+		ctrl: &Rc<RefCell<GuiController>>) -> (Self, gtk4::Box)
+	{
+		let list = ListBox::builder()
+			.selection_mode(SelectionMode::Single)
+			.build();
+		list.add_css_class("navigation-sidebar");
+		list.add_css_class("boxed-list");
+		list.set_placeholder(Some(&Label::new(Some(&i18n.msg("no-notes")))));
+
+		let container = gtk4::Box::builder()
+			.orientation(Orientation::Vertical)
+			.spacing(0)
+			.vexpand(true)
+			.build();
+		container.append(&gtk4::ScrolledWindow::builder()
+			.child(&list)
+			.hscrollbar_policy(PolicyType::Never)
+			.vexpand(true)
+			.build());
+
+		let note_list = NoteList {
+			inner: Rc::new(NoteListInner {
+				list,
+				ctrl: ctrl.clone(),
+				entries: RefCell::new(vec![]),
+				loaded: Cell::new(None),
+				syncing: Cell::new(false),
+				position_clicked: RefCell::new(None),
+				icons: icons.clone(),
+				i18n: i18n.clone(),
+			})
+		};
+		{
+			let note_list2 = note_list.clone();
+			note_list.inner.list.connect_row_selected(move |_, row| {
+				if note_list2.inner.syncing.get() {
+					return;
+				}
+				if let Some(row) = row {
+					let row_index = row.index();
+					if row_index >= 0 {
+						note_list2.clicked(row_index as usize, false);
+					}
+				}
+			});
+		}
+		(note_list, container)
+	}
+
+	/// callback with the range to goto, the note or its reference
+	pub fn handle_position_click<F>(&self, position_clicked: F)
+		where F: Fn(&Range<Position>) + 'static
+	{
+		self.inner.position_clicked.replace(Some(Rc::new(position_clicked)));
+	}
+
+	/// reload notes if chapter changed or forced
+	pub fn sync_notes(&self, force: bool)
+	{
+		let controller = self.ctrl();
+		let reading = (controller.reading.inner_book, controller.reading.chapter);
+		if !force && self.inner.loaded.get() == Some(reading) {
+			return;
+		}
+		self.inner.loaded.replace(Some(reading));
+		let mut entries = self.inner.entries.borrow_mut();
+		entries.clear();
+		let mut rows = vec![];
+		if let Some(notes) = controller.book.notes() {
+			let lines = controller.book.lines();
+			for info in notes {
+				let text = note_text(lines, &info.note);
+				if text.is_empty() {
+					continue;
+				}
+				rows.push(self.create_row(&text, info.reference.is_some(), entries.len()));
+				entries.push(NoteEntry {
+					note: info.note.clone(),
+					reference: info.reference.clone(),
+				});
+			}
+		}
+		drop(entries);
+		drop(controller);
+		let list = &self.inner.list;
+		self.inner.syncing.replace(true);
+		list.remove_all();
+		for row in rows {
+			list.append(&row);
+		}
+		self.inner.syncing.replace(false);
+	}
+
+	fn create_row(&self, text: &str, with_reference: bool, index: usize) -> ListBoxRow
+	{
+		let label = Label::builder()
+			.label(text)
+			.halign(Align::Start)
+			.hexpand(true)
+			.xalign(0.)
+			.wrap(true)
+			.wrap_mode(WrapMode::WordChar)
+			.lines(NOTE_TEXT_LINES)
+			.ellipsize(EllipsizeMode::End)
+			.tooltip_text(text)
+			.build();
+		let view = gtk4::Box::new(Orientation::Horizontal, 4);
+		view.append(&label);
+		if with_reference {
+			let tooltip = self.inner.i18n.msg("note-reference");
+			let button = create_button("backward.svg", Some(&tooltip), &self.inner.icons, true);
+			let note_list = self.clone();
+			button.connect_clicked(move |_| note_list.clicked(index, true));
+			view.append(&button);
+		}
+		let row = ListBoxRow::new();
+		row.set_child(Some(&view));
+		row
+	}
+
+	fn clicked(&self, index: usize, reference: bool)
+	{
+		let entries = self.inner.entries.borrow();
+		let Some(entry) = entries.get(index) else {
+			return;
+		};
+		let range = if reference {
+			match &entry.reference {
+				Some(range) => range.clone(),
+				None => return,
+			}
+		} else {
+			entry.note.clone()
+		};
+		drop(entries);
+		let callback = self.inner.position_clicked.borrow().clone();
+		if let Some(callback) = callback {
+			callback(&range);
+		}
+	}
+
+	#[inline]
+	fn ctrl(&self) -> Ref<'_, GuiController>
+	{
+		self.inner.ctrl.borrow()
+	}
+}
+
+fn note_text(lines: &Vec<Line>, range: &Range<Position>) -> String
+{
+	let mut text = String::new();
+	let end_line = cmp::min(range.end.line, lines.len().saturating_sub(1));
+	for line_index in range.start.line..=end_line {
+		let Some(line) = lines.get(line_index) else {
+			break;
+		};
+		let start = if line_index == range.start.line {
+			range.start.offset
+		} else {
+			0
+		};
+		let end = if line_index == range.end.line {
+			cmp::min(range.end.offset, line.len())
+		} else {
+			line.len()
+		};
+		if start < end {
+			if !text.is_empty() {
+				text.push('\n');
+			}
+			text.extend(line.iter().skip(start).take(end - start));
+		}
+	}
+	text.trim().to_owned()
+}
@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk4::{FileDialog, PageSetup, PaperSize, PrintOperation, PrintOperationAction, Unit};
+use gtk4::gio::Cancellable;
+use gtk4::prelude::{FileExt, PrintOperationExt, WidgetExt};
+use indexmap::IndexSet;
+
+use crate::color::Colors;
+use crate::common::Position;
+use crate::gui::{dialogs, GuiContext};
+use crate::gui::render::{BlockBackgroundEntry, create_render, GuiRender, RenderLine, TextDecoration};
+
+// gtk names of paper sizes for printing
+const PAPER_SIZES: [&str; 5] = ["iso_a4", "iso_a5", "iso_b5", "na_letter", "na_legal"];
+// view pixels in a point, so printed text sized as on screen
+const PIXELS_PER_POINT: f64 = 96. / 72.;
+
+type Page = (Vec<RenderLine>, Vec<TextDecoration>, Vec<BlockBackgroundEntry>);
+
+struct PrintPages {
+	render: Box<dyn GuiRender>,
+	pages: Vec<Page>,
+	font_family_names: Option<IndexSet<String>>,
+}
+
+/// export chapters of reading book to pdf, with the active render
+pub(super) fn export_pdf(gc: &GuiContext)
+{
+	let controller = gc.ctrl();
+	let chapter_count = controller.book.chapter_count();
+	let current = controller.book.current_chapter();
+	drop(controller);
+	let papers = PAPER_SIZES
+		.iter()
+		.map(|name| PaperSize::new(Some(name)))
+		.collect::<Vec<_>>();
+	let paper_names = papers
+		.iter()
+		.map(|paper| paper.display_name().to_string())
+		.collect::<Vec<_>>();
+	dialogs::print_range(gc, chapter_count, current, &paper_names, &gc.window, move |gc, first, last, paper| {
+		let file_dialog = FileDialog::new();
+		file_dialog.set_title(&gc.i18n.msg("export-pdf"));
+		file_dialog.set_modal(true);
+		let name = format!("{}.pdf", gc.ctrl().reading_book_name());
+		file_dialog.set_initial_name(Some(&name));
+		let gc2 = gc.clone();
+		let paper = papers[paper].clone();
+		file_dialog.save(Some(&gc.window), None::<&Cancellable>, move |result| {
+			if let Some(path) = result.ok().and_then(|file| file.path()) {
+				export(&gc2, first..=last, &paper, path);
+			}
+		});
+	});
+}
+
+fn export(gc: &GuiContext, chapters: RangeInclusive<usize>, paper: &PaperSize, path: PathBuf)
+{
+	let page_setup = PageSetup::new();
+	page_setup.set_paper_size_and_default_margins(paper);
+	let operation = PrintOperation::new();
+	operation.set_default_page_setup(Some(&page_setup));
+	operation.set_unit(Unit::Points);
+	operation.set_job_name(gc.ctrl().reading_book_name());
+	operation.set_export_filename(&path);
+
+	let print_pages: Rc<RefCell<Option<PrintPages>>> = Rc::new(RefCell::new(None));
+	{
+		let gc = gc.clone();
+		let print_pages = print_pages.clone();
+		operation.connect_begin_print(move |operation, context| {
+			let width = context.width() * PIXELS_PER_POINT;
+			let height = context.height() * PIXELS_PER_POINT;
+			let pages = layout_pages(&gc, chapters.clone(), width as f32, height as f32);
+			operation.set_n_pages(pages.pages.len().max(1) as i32);
+			print_pages.replace(Some(pages));
+		});
+	}
+	{
+		let gc = gc.clone();
+		operation.connect_draw_page(move |_, context, page_nr| {
+			let print_pages = print_pages.borrow();
+			let Some(print_pages) = print_pages.as_ref() else {
+				return;
+			};
+			let Some((render_lines, block_borders, block_backgrounds)) = print_pages.pages.get(page_nr as usize) else {
+				return;
+			};
+			let cairo = context.cairo_context();
+			cairo.scale(1. / PIXELS_PER_POINT, 1. / PIXELS_PER_POINT);
+			print_pages.render.draw(
+				render_lines,
+				block_borders,
+				block_backgrounds,
+				&print_pages.font_family_names,
+				&cairo,
+				&gc.ctrl().render.get_pango());
+		});
+	}
+	match operation.run(PrintOperationAction::Export, Some(&gc.window)) {
+		Ok(_) => gc.toast(&gc.i18n.args_msg("pdf-exported", vec![
+			("path", path.to_string_lossy()),
+		])),
+		Err(err) => gc.toast_error(&err.to_string()),
+	}
+}
+
+/// lay out pages of the chapters with a new render sized to the paper,
+/// view restored after
+fn layout_pages(gc: &GuiContext, chapters: RangeInclusive<usize>, width: f32, height: f32)
+	-> PrintPages
+{
+	let mut controller = gc.ctrl_mut();
+	let mut render_context = gc.ctx_mut();
+	let pango = controller.render.get_pango();
+	let mut render = create_render(controller.reading.render_han
+		.unwrap_or(gc.cfg().render_han));
+	// printed on white paper
+	let colors = std::mem::replace(&mut render_context.colors, Colors::EINK);
+	render.apply_font_modified(controller.book.custom_fonts(), &pango, &mut render_context);
+	render_context.update_render_rect(width, height);
+	render.reset_baseline(&render_context);
+	render.reset_render_context(&mut render_context);
+
+	let current_chapter = controller.book.current_chapter();
+	let mut pages = vec![];
+	for chapter in chapters {
+		match controller.book.goto_chapter(chapter) {
+			Ok(Some(_)) => {}
+			Ok(None) => break,
+			Err(err) => {
+				gc.error(&err.to_string());
+				break;
+			}
+		}
+		let book = controller.book.as_ref();
+		let lines = book.lines();
+		let mut position = Position::new(0, 0);
+		loop {
+			let (render_lines, block_borders, block_backgrounds, next) = render.gui_redraw(
+				book, lines, position.line, position.offset, &None, &pango, &mut render_context);
+			pages.push((render_lines, block_borders, block_backgrounds));
+			match next {
+				Some(next) if next.line != position.line || next.offset != position.offset =>
+					position = next,
+				_ => break,
+			}
+		}
+	}
+	let font_family_names = controller.book.font_family_names().cloned();
+	if let Err(err) = controller.book.goto_chapter(current_chapter) {
+		gc.error(&err.to_string());
+	}
+
+	render_context.colors = colors;
+	let view = &controller.render;
+	view.reload_fonts(controller.book.custom_fonts(), &mut render_context);
+	view.resized(view.width(), view.height(), &mut render_context);
+	PrintPages { render, pages, font_family_names }
+}
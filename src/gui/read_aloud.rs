@@ -0,0 +1,319 @@
+use std::cell::{Cell, RefCell};
+use std::ops::DerefMut;
+
+use gtk4::{ApplicationInhibitFlags, DropDown, MediaFile, Orientation, ToggleButton, Window};
+use gtk4::prelude::{BoxExt, Cast, GtkApplicationExt, GtkWindowExt, MediaStreamExt, ToggleButtonExt, WidgetExt};
+
+use crate::gui::{ChapterListSyncMode, create_toggle_button, GuiContext, IconMap};
+use crate::i18n::I18n;
+
+const READ_ALOUD_KEY: &str = "read-aloud";
+// clips in same audio are mostly continuous, not seek for small gap
+const SEEK_TOLERANCE: f64 = 0.5;
+const MICROSECONDS: f64 = 1_000_000.0;
+// playback rates in rate drop down, tempo changed by ffmpeg
+const RATES: [f64; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+const DEFAULT_RATE_INDEX: u32 = 1;
+
+struct Playing {
+	media: MediaFile,
+	audio: String,
+	rate: f64,
+	inner_book: usize,
+	chapter: usize,
+	clip: usize,
+}
+
+/// read aloud with EPUB3 media overlay, highlight text fragment of playing clip
+pub(super) struct ReadAloud {
+	widget: gtk4::Box,
+	button: ToggleButton,
+	rate: DropDown,
+	playing: RefCell<Option<Playing>>,
+}
+
+impl ReadAloud {
+	pub(super) fn new(icons: &IconMap, i18n: &I18n) -> Self
+	{
+		let button = create_toggle_button(false, "play.svg",
+			READ_ALOUD_KEY, icons, i18n);
+		// the button active while playing
+		let reason = i18n.msg(READ_ALOUD_KEY).to_string();
+		let inhibit_cookie = Cell::new(None);
+		button.connect_active_notify(move |button|
+			inhibit_idle(button, &reason, &inhibit_cookie));
+		let rate_names: Vec<String> = RATES.iter()
+			.map(|rate| format!("{}×", rate))
+			.collect();
+		let rate_names: Vec<&str> = rate_names.iter().map(|name| name.as_str()).collect();
+		let rate = DropDown::from_strings(&rate_names);
+		rate.set_selected(DEFAULT_RATE_INDEX);
+		rate.set_focusable(false);
+		rate.set_tooltip_text(Some(i18n.msg("read-aloud-rate").as_ref()));
+		let widget = gtk4::Box::new(Orientation::Horizontal, 0);
+		widget.add_css_class("linked");
+		widget.append(&button);
+		widget.append(&rate);
+		ReadAloud { widget, button, rate, playing: RefCell::new(None) }
+	}
+
+	#[inline]
+	pub(super) fn widget(&self) -> &gtk4::Box
+	{
+		&self.widget
+	}
+
+	#[inline]
+	pub(super) fn button(&self) -> &ToggleButton
+	{
+		&self.button
+	}
+
+	#[inline]
+	pub(super) fn rate_dropdown(&self) -> &DropDown
+	{
+		&self.rate
+	}
+
+	#[inline]
+	fn rate(&self) -> f64
+	{
+		RATES.get(self.rate.selected() as usize)
+			.copied()
+			.unwrap_or(1.0)
+	}
+}
+
+/// screensaver and suspend inhibited while playing, released when paused or stopped
+fn inhibit_idle(button: &ToggleButton, reason: &str, cookie: &Cell<Option<u32>>)
+{
+	let Some(window) = button.root().and_then(|root| root.downcast::<Window>().ok()) else {
+		return;
+	};
+	let Some(app) = window.application() else {
+		return;
+	};
+	match (button.is_active(), cookie.get()) {
+		(true, None) => {
+			let id = app.inhibit(Some(&window),
+				ApplicationInhibitFlags::IDLE | ApplicationInhibitFlags::SUSPEND,
+				Some(reason));
+			if id != 0 {
+				cookie.set(Some(id));
+			}
+		}
+		(false, Some(id)) => {
+			app.uninhibit(id);
+			cookie.set(None);
+		}
+		_ => {}
+	}
+}
+
+/// play or pause, start from the fragment at reading position
+pub(super) fn toggle(gc: &GuiContext)
+{
+	let paused = gc.read_aloud.playing
+		.borrow()
+		.as_ref()
+		.map(|playing| (playing.media.clone(), playing.rate, playing.inner_book,
+			playing.chapter, playing.clip));
+	if let Some((media, rate, inner_book, chapter, clip)) = paused {
+		let controller = gc.ctrl();
+		let same_chapter = inner_book == controller.reading.inner_book
+			&& chapter == controller.reading.chapter;
+		drop(controller);
+		if same_chapter {
+			let play = !media.is_playing();
+			// rate changed while paused
+			if play && rate != gc.read_aloud.rate() {
+				if !play_clip(gc, chapter, clip) {
+					stop(gc);
+				}
+				return;
+			}
+			media.set_playing(play);
+			gc.read_aloud.button.set_active(play);
+			return;
+		}
+		stop(gc);
+	}
+
+	let controller = gc.ctrl();
+	let reading = &controller.reading;
+	let chapter = reading.chapter;
+	let clip = controller.book.media_clips().map(|clips| clips
+		.iter()
+		.position(|clip| clip.line > reading.line
+			|| (clip.line == reading.line && clip.range.end > reading.position))
+		.unwrap_or(0));
+	drop(controller);
+	match clip {
+		Some(clip) => if !play_clip(gc, chapter, clip) {
+			stop(gc);
+		}
+		None => {
+			gc.read_aloud.button.set_active(false);
+			gc.error(&gc.i18n.msg("read-aloud-unavailable"));
+		}
+	}
+}
+
+/// restart playing clip with the rate selected
+pub(super) fn rate_changed(gc: &GuiContext)
+{
+	let playing = gc.read_aloud.playing
+		.borrow()
+		.as_ref()
+		.map(|playing| (playing.media.is_playing(), playing.chapter, playing.clip));
+	let Some((true, chapter, clip)) = playing else {
+		return;
+	};
+	if !play_clip(gc, chapter, clip) {
+		stop(gc);
+	}
+}
+
+pub(super) fn stop(gc: &GuiContext)
+{
+	let playing = gc.read_aloud.playing.borrow_mut().take();
+	if let Some(playing) = playing {
+		playing.media.pause();
+		gc.ctrl_mut().clear_highlight(gc.ctx_mut().deref_mut());
+	}
+	gc.read_aloud.button.set_active(false);
+}
+
+fn play_clip(gc: &GuiContext, chapter: usize, index: usize) -> bool
+{
+	let controller = gc.ctrl();
+	let Some(clip) = controller.book.media_clips()
+		.and_then(|clips| clips.get(index)) else {
+		return false;
+	};
+	let line = clip.line;
+	let range = clip.range.clone();
+	let audio = clip.audio.clone();
+	let clip_begin = clip.clip_begin;
+	let inner_book = controller.reading.inner_book;
+	drop(controller);
+	let rate = gc.read_aloud.rate();
+
+	// no borrow held while calling media, notify handlers may fire synchronously
+	let current = gc.read_aloud.playing
+		.borrow()
+		.as_ref()
+		.map(|playing| (playing.media.clone(), playing.audio == audio && playing.rate == rate));
+	let current = match current {
+		Some((media, true)) => Some(media),
+		Some((media, false)) => {
+			media.pause();
+			None
+		}
+		None => None,
+	};
+	let media = if let Some(media) = current {
+		let timestamp = media.timestamp() as f64 / MICROSECONDS * rate;
+		if (timestamp - clip_begin).abs() > SEEK_TOLERANCE && media.is_seekable() {
+			media.seek((clip_begin / rate * MICROSECONDS) as i64);
+		}
+		media
+	} else {
+		let Some(bytes) = gc.ctrl().book.audio(&audio) else {
+			return false;
+		};
+		let path = match gc.opener().audio_file_at_rate(&audio, &bytes, rate) {
+			Ok(path) => path,
+			Err(err) => {
+				gc.error(&err.to_string());
+				return false;
+			}
+		};
+		let media = MediaFile::for_filename(path);
+		{
+			let gc = gc.clone();
+			media.connect_timestamp_notify(move |_| sync(&gc));
+		}
+		{
+			let gc = gc.clone();
+			media.connect_ended_notify(move |_| sync(&gc));
+		}
+		media
+	};
+	gc.read_aloud.playing.replace(Some(Playing {
+		media: media.clone(),
+		audio,
+		rate,
+		inner_book,
+		chapter,
+		clip: index,
+	}));
+
+	let result = gc.ctrl_mut().goto(inner_book, chapter, line, range.start,
+		Some(range), gc.ctx_mut().deref_mut());
+	match result {
+		Ok(msg) => gc.update(&msg, ChapterListSyncMode::NoReload),
+		Err(err) => gc.error(&err.to_string()),
+	}
+	media.play();
+	gc.read_aloud.button.set_active(true);
+	true
+}
+
+/// follow the timing of clips, with the timestamp of playing audio
+fn sync(gc: &GuiContext)
+{
+	let Ok(playing) = gc.read_aloud.playing.try_borrow() else {
+		return;
+	};
+	let Some(Playing { media, rate, inner_book, chapter, clip, .. }) = playing.as_ref() else {
+		return;
+	};
+	let media = media.clone();
+	let rate = *rate;
+	let inner_book = *inner_book;
+	let chapter = *chapter;
+	let clip = *clip;
+	drop(playing);
+
+	let controller = gc.ctrl();
+	let reading = &controller.reading;
+	let current = if inner_book == reading.inner_book && chapter == reading.chapter {
+		controller.book.media_clips()
+			.and_then(|clips| clips.get(clip))
+			.map(|clip| (clip.clip_begin, clip.clip_end))
+	} else {
+		None
+	};
+	drop(controller);
+	let Some((clip_begin, clip_end)) = current else {
+		// reading moved to other chapter
+		stop(gc);
+		return;
+	};
+	let timestamp = media.timestamp() as f64 / MICROSECONDS * rate;
+	if media.is_ended() || clip_end.map_or(false, |clip_end| timestamp >= clip_end) {
+		play_next(gc, chapter, clip);
+	} else if timestamp + SEEK_TOLERANCE < clip_begin
+		&& media.is_seekable() && !media.is_seeking() {
+		media.seek((clip_begin / rate * MICROSECONDS) as i64);
+	}
+}
+
+fn play_next(gc: &GuiContext, chapter: usize, clip: usize)
+{
+	if play_clip(gc, chapter, clip + 1) {
+		return;
+	}
+	// continue with next chapter
+	let switched = gc.ctrl_mut()
+		.switch_chapter(true, gc.ctx_mut().deref_mut())
+		.unwrap_or(false);
+	if switched {
+		let chapter = gc.ctrl().reading.chapter;
+		if play_clip(gc, chapter, 0) {
+			return;
+		}
+	}
+	stop(gc);
+}
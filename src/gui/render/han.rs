@@ -3,6 +3,7 @@ use std::ops::Range;
 
 use gtk4::cairo::Context as CairoContext;
 use gtk4::pango::Layout as PangoContext;
+use unicode_width::UnicodeWidthChar;
 
 use crate::book::{Book, Line};
 use crate::color::Color32;
@@ -10,7 +11,7 @@ use crate::common::{han_render_char, is_compact_for_han, with_leading};
 use crate::controller::HighlightInfo;
 use crate::gui::math::{Pos2, pos2, Rect, vec2};
 use crate::gui::render::{CharCell, CharDrawData, GuiRender, ImageDrawingData, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, update_for_highlight, vline};
-use crate::gui::render::imp::draw_border;
+use crate::gui::render::imp::{char_spacing, draw_border};
 use crate::html_parser;
 use crate::html_parser::{BorderLines, TextDecorationLine, TextStyle};
 
@@ -85,11 +86,22 @@ impl GuiRender for GuiHanRender
 		let mut line_space = 0.0;
 		let default_size = context.default_font_measure.x;
 
-		let view_rect = &context.render_rect;
-		let view_size = view_rect.size();
+		let orientations = if book.upright_text() {
+			vec![HanOrientation::Upright; text.len()]
+		} else {
+			orientations(text)
+		};
+		// start and x offsets of the combined run in drawing, with total width
+		let mut combined: Option<(usize, Vec<f32>)> = None;
+		let mut image_drawn = None;
 		for i in start_offset..end_offset {
 			let char_style = text.char_style_at(i, context.custom_color, &context.colors);
-			let (cell, mut rect) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size) {
+			let Some(image) = self.char_image(text, i, &char_style, book, context,
+				&mut image_drawn) else {
+				continue;
+			};
+			let mut keep_top = false;
+			let (cell, mut rect) = if let Some((path, size)) = image {
 				let left = self.baseline - size.x;
 				let bottom = top + size.y;
 				let rect = Rect::from_min_max(
@@ -102,8 +114,13 @@ impl GuiRender for GuiHanRender
 				if i == 0 && with_leading(text) {
 					top = context.render_rect.min.y + context.leading_space;
 				}
+				let orientation = orientations[i];
 				let char = text.char_at(i).unwrap();
-				let char = han_render_char(char);
+				let char = if orientation == HanOrientation::Upright {
+					vertical_form(han_render_char(char))
+				} else {
+					char
+				};
 				let measures = self.get_char_measures(
 					pango,
 					char,
@@ -113,15 +130,58 @@ impl GuiRender for GuiHanRender
 					book.font_family_names(),
 					book.custom_fonts(),
 					context);
-				let (char_height, y_offset) = if is_compact_for_han(char) {
-					(measures.draw_size.y * 2., -measures.draw_offset.y + (measures.draw_size.y / 2.))
-				} else {
-					(measures.size.y, 0.)
+				let prev_char = i.checked_sub(1)
+					.and_then(|i| text.char_at(i))
+					.map(han_render_char);
+				let (space_before, space_after) = char_spacing(char, prev_char, measures.font_size, context);
+				let (mut cell_offset, cell_size, rotated) = match orientation {
+					HanOrientation::Upright => {
+						let (char_height, y_offset) = if is_compact_for_han(char) {
+							(measures.draw_size.y * 2., -measures.draw_offset.y + (measures.draw_size.y / 2.))
+						} else {
+							(measures.size.y, 0.)
+						};
+						(vec2(-measures.draw_offset.x, y_offset + space_before),
+							vec2(measures.draw_size.x, char_height), false)
+					}
+					HanOrientation::Rotated => {
+						// rotated around the top of glyph, centered in the cell
+						let width = measures.size.y.min(default_size);
+						(vec2((width + measures.size.y) / 2., space_before),
+							vec2(width, measures.size.x), true)
+					}
+					HanOrientation::Combined(run_end) => {
+						if combined.is_none() {
+							let mut x = 0.;
+							let mut offsets = vec![];
+							for j in i..run_end {
+								offsets.push(x);
+								x += self.get_char_measures(
+									pango,
+									text.char_at(j).unwrap(),
+									&char_style.font_scale,
+									&char_style.font_weight,
+									&char_style.font_family,
+									book.font_family_names(),
+									book.custom_fonts(),
+									context).size.x;
+							}
+							offsets.push(x);
+							combined = Some((i, offsets));
+						}
+						let (start, offsets) = combined.as_ref().unwrap();
+						let width = *offsets.last().unwrap();
+						let x = offsets[i - start];
+						keep_top = i + 1 < run_end;
+						if !keep_top {
+							combined = None;
+						}
+						(vec2(x, space_before), vec2(width, measures.size.y), false)
+					}
 				};
-				let mut cell_offset = vec2(-measures.draw_offset.x, y_offset);
-				let cell_size = vec2(measures.draw_size.x, char_height);
 				let color = char_style.color.clone();
-				let mut rect = Rect::new(self.baseline - cell_size.x, top, cell_size.x, cell_size.y);
+				let mut rect = Rect::new(self.baseline - cell_size.x, top, cell_size.x,
+					cell_size.y + space_before + space_after);
 				if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
 					if lines.contains(BorderLines::Left) {
 						if lines.contains(BorderLines::Right) {
@@ -153,7 +213,9 @@ impl GuiRender for GuiHanRender
 					}
 				}
 
-				let background = update_for_highlight(line, i, char_style.background.clone(), &context.colors, highlight);
+				let background = context.annotation_background(book.current_chapter(), line, i)
+					.or_else(|| char_style.background.clone());
+				let background = update_for_highlight(line, i, background, &context.colors, highlight);
 				let cell = CharCell {
 					char,
 					font_size: measures.font_size,
@@ -163,6 +225,7 @@ impl GuiRender for GuiHanRender
 					background,
 					cell_offset,
 					cell_size,
+					rotated,
 				};
 				if let Some((link_index, _)) = char_style.link {
 					(RenderCell::Link(cell, link_index), rect)
@@ -202,12 +265,12 @@ impl GuiRender for GuiHanRender
 					line_space = line_size / 2.0
 				}
 			}
-			top = rect.max.y;
+			top = if keep_top { rect.min.y } else { rect.max.y };
 			let dc = RenderChar {
 				cell,
 				offset: i,
 				rect,
-				has_title: char_style.title.is_some(),
+				has_title: char_style.has_title(),
 			};
 			draw_chars.push(dc);
 		}
@@ -555,6 +618,85 @@ impl GuiRender for GuiHanRender
 	}
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum HanOrientation {
+	Upright,
+	// latin words rotated clockwise
+	Rotated,
+	// short numbers in one cell horizontally, with end of the run
+	Combined(usize),
+}
+
+// numbers with more digits rotated
+const MAX_COMBINED_DIGITS: usize = 2;
+
+// sorted, for binary search
+const VERTICAL_FORMS: [(char, char); 12] = [
+	('‘', '﹃'),
+	('’', '﹄'),
+	('“', '﹁'),
+	('”', '﹂'),
+	('‥', '︰'),
+	('、', '︑'),
+	('。', '︒'),
+	('！', '︕'),
+	('，', '︐'),
+	('：', '︓'),
+	('；', '︔'),
+	('？', '︖'),
+];
+
+/// presentation form of punctuation for vertical text
+#[inline]
+fn vertical_form(ch: char) -> char
+{
+	match VERTICAL_FORMS.binary_search_by(|(key, _)| key.cmp(&ch)) {
+		Ok(idx) => VERTICAL_FORMS[idx].1,
+		Err(_) => ch,
+	}
+}
+
+#[inline]
+fn is_latin(ch: char) -> bool
+{
+	ch.is_alphanumeric() && ch.width() == Some(1)
+}
+
+/// orientation of every char in line, runs of latin letters and digits
+/// with spaces and punctuations between them are rotated,
+/// or combined if only a short number
+fn orientations(text: &Line) -> Vec<HanOrientation>
+{
+	let len = text.len();
+	let mut orientations = vec![HanOrientation::Upright; len];
+	let mut start = 0;
+	while start < len {
+		if !is_latin(text.char_at(start).unwrap()) {
+			start += 1;
+			continue;
+		}
+		let mut end = start + 1;
+		let mut next = end;
+		while let Some(ch) = text.char_at(next) {
+			next += 1;
+			if is_latin(ch) {
+				end = next;
+			} else if ch != ' ' && !ch.is_ascii_punctuation() {
+				break;
+			}
+		}
+		let orientation = if end - start <= MAX_COMBINED_DIGITS
+			&& (start..end).all(|i| text.char_at(i).unwrap().is_ascii_digit()) {
+			HanOrientation::Combined(end)
+		} else {
+			HanOrientation::Rotated
+		};
+		orientations[start..end].fill(orientation);
+		start = end;
+	}
+	orientations
+}
+
 fn align_line(render_line: &mut RenderLine, draw_chars: Vec<RenderChar>)
 {
 	let line_size = render_line.line_size();
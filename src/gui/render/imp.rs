@@ -10,9 +10,10 @@ use std::collections::HashMap;
 use std::ops::Range;
 use std::rc::Rc;
 
-use crate::book::{Book, CharStyle, Line};
+use crate::book::{Book, CharStyle, IMAGE_CHAR, Line};
 use crate::color::{Color32, Colors};
-use crate::common::{overlap_range, Position};
+use crate::common::{is_cjk, overlap_range, Position};
+use crate::config::{FontAxes, FontWeightMode};
 use crate::controller::{HighlightInfo, HighlightMode};
 use crate::gui::font::{Fonts, HtmlFonts, UserFonts};
 use crate::gui::load_image;
@@ -95,6 +96,8 @@ pub struct CharCell {
 	pub background: Option<Color32>,
 	pub cell_offset: Vec2,
 	pub cell_size: Vec2,
+	// rotated clockwise around the draw position
+	pub rotated: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -215,6 +218,24 @@ impl RenderLine
 	{
 		self.chars.last()
 	}
+
+	/// rect contains chars of the offset range, None if none of them in this line
+	pub fn range_bounds(&self, range: &Range<usize>) -> Option<Rect>
+	{
+		self.chars
+			.iter()
+			.filter(|dc| range.contains(&dc.offset))
+			.map(|dc| dc.rect.clone())
+			.reduce(|rect, bounds| rect.union(&bounds))
+	}
+
+	/// rect contains all chars of this line
+	pub fn bounds(&self) -> Option<Rect>
+	{
+		let mut chars = self.chars.iter();
+		let first = chars.next()?;
+		Some(chars.fold(first.rect.clone(), |rect, dc| rect.union(&dc.rect)))
+	}
 }
 
 pub enum CharDrawData {
@@ -259,6 +280,7 @@ pub struct PangoDrawData {
 	char: String,
 	font_size: u8,
 	font_weight: FontWeight,
+	font_variations: Option<String>,
 	font_family: Option<u16>,
 	size: Vec2,
 	draw_offset: Pos2,
@@ -268,12 +290,14 @@ pub struct PangoDrawData {
 impl PangoDrawData {
 	fn measure(char: char, font_size: f32, font_weight: &FontWeight,
 		font_family_idx: &Option<u16>, font_family_names: Option<&IndexSet<String>>,
-		layout: &PangoContext) -> Self
+		font_axes: &FontAxes, layout: &PangoContext) -> Self
 	{
 		let text = char.to_string();
 		let font_size = font_size as u8;
+		let font_variations = pango_font_variations(font_weight, font_axes);
 		let font_family_names = get_font_family_names(font_family_idx, font_family_names);
-		set_pango_font_size(font_size, &font_weight, font_family_names, layout);
+		set_pango_font_size(font_size, &font_weight, font_variations.as_deref(),
+			font_family_names, layout);
 		layout.set_text(&text);
 		let (ink_rect, logical_rect) = layout.pixel_extents();
 		let logical_x = logical_rect.x() as f32;
@@ -291,6 +315,7 @@ impl PangoDrawData {
 			char: text,
 			font_size,
 			font_weight: font_weight.clone(),
+			font_variations,
 			font_family: font_family_idx.clone(),
 			size,
 			draw_offset,
@@ -302,7 +327,8 @@ impl PangoDrawData {
 		font_family_names: Option<&IndexSet<String>>, layout: &PangoContext)
 	{
 		let font_family_names = get_font_family_names(&self.font_family, font_family_names);
-		set_pango_font_size(self.font_size, &self.font_weight, font_family_names, layout);
+		set_pango_font_size(self.font_size, &self.font_weight,
+			self.font_variations.as_deref(), font_family_names, layout);
 		layout.set_text(&self.char);
 
 		let x_offset = offset_x as f64;
@@ -322,19 +348,28 @@ pub struct OutlineDrawData {
 
 impl OutlineDrawData {
 	fn measure(char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_idx: &Option<u16>, font_family_names: Option<&IndexSet<String>>,
+		synthesize: bool, font_family_idx: &Option<u16>,
+		font_family_names: Option<&IndexSet<String>>,
 		fonts: Option<&impl Fonts>) -> Option<Self>
 	{
 		if let Some(fonts) = fonts {
 			let font_family_names = get_font_family_names(font_family_idx, font_family_names);
-			if let Some((outline, rect)) = fonts.query(char, font_size, font_weight, font_family_names) {
+			if let Some((outline, rect, embolden)) = fonts.query(
+				char, font_size, font_weight, synthesize, font_family_names) {
 				let mut points = vec![];
 				outline.draw(|_, _, a| {
 					points.push((a * 255.) as u8);
 				});
 				let bounds = outline.px_bounds();
-				let draw_size = vec2(bounds.width(), bounds.height());
-				let size = vec2(rect.width(), rect.height());
+				let mut draw_size = vec2(bounds.width(), bounds.height());
+				let mut size = vec2(rect.width(), rect.height());
+				if embolden {
+					let stroke = synthesize_stroke(font_size);
+					points = embolden_points(points, draw_size.x as usize,
+						draw_size.y as usize, stroke);
+					draw_size.x += stroke as f32;
+					size.x += stroke as f32;
+				}
 				let offset_x = bounds.min.x - rect.min.x;
 				let offset_y = bounds.min.y - rect.min.y;
 				let draw_offset = pos2(offset_x, offset_y);
@@ -371,6 +406,13 @@ impl OutlineDrawData {
 	}
 }
 
+/// background of annotated text in chapter
+pub struct AnnotationMark {
+	pub chapter: usize,
+	pub range: Range<Position>,
+	pub color: Color32,
+}
+
 pub struct RenderContext
 {
 	pub colors: Colors,
@@ -387,6 +429,8 @@ pub struct RenderContext
 	pub custom_font: bool,
 	// strip empty lines
 	pub strip_empty_lines: bool,
+	// images not loaded
+	pub text_only: bool,
 
 	pub render_rect: Rect,
 	pub leading_chars: usize,
@@ -397,14 +441,27 @@ pub struct RenderContext
 	// method for redraw with scrolling
 	pub scroll_redraw_method: ScrollRedrawMethod,
 
-	// ignore font weight
-	pub ignore_font_weight: bool,
+	// annotations of reading inner book
+	pub annotations: Vec<AnnotationMark>,
+
+	// render font weight of book
+	pub font_weight_mode: FontWeightMode,
+	// axes of variable fonts
+	pub font_axes: FontAxes,
+	// percent of font size
+	pub letter_spacing: u8,
+	pub word_spacing: u8,
+	// percent for all text size
+	pub text_scale: u16,
+	// bold the leading part of latin words, by xi render only
+	pub bionic_reading: bool,
 }
 
 impl RenderContext {
 	pub fn new(colors: Colors, font_size: u8, custom_color: bool, custom_font: bool,
-		leading_chars: usize, strip_empty_lines: bool, ignore_font_weight: bool)
-		-> Self
+		leading_chars: usize, strip_empty_lines: bool, font_weight_mode: FontWeightMode,
+		font_axes: FontAxes, letter_spacing: u8, word_spacing: u8, text_scale: u16,
+		bionic_reading: bool) -> Self
 	{
 		RenderContext {
 			colors,
@@ -414,15 +471,38 @@ impl RenderContext {
 			custom_color,
 			custom_font,
 			strip_empty_lines,
-			ignore_font_weight,
+			text_only: false,
+			font_weight_mode,
+			font_axes,
+			letter_spacing,
+			word_spacing,
+			text_scale,
+			bionic_reading,
 			render_rect: Rect::NOTHING,
 			leading_chars,
 			leading_space: 0.0,
 			max_page_size: 0.0,
 			scroll_redraw_method: ScrollRedrawMethod::NoResetScroll,
+			annotations: vec![],
 		}
 	}
 
+	/// background of the char if annotated, the latest annotation on top
+	pub fn annotation_background(&self, chapter: usize, line: usize, offset: usize)
+		-> Option<Color32>
+	{
+		self.annotations
+			.iter()
+			.rev()
+			.find(|mark| {
+				let Range { start, end } = &mark.range;
+				mark.chapter == chapter
+					&& (line > start.line || (line == start.line && offset >= start.offset))
+					&& (line < end.line || (line == end.line && offset < end.offset))
+			})
+			.map(|mark| mark.color.clone())
+	}
+
 	#[inline]
 	pub fn x_padding(&self) -> f32
 	{
@@ -495,11 +575,16 @@ pub struct CharMeasures {
 	pub font_family_idx: Option<u16>,
 }
 
+// measured glyphs kept, cleared when too many font sizes used
+const MAX_CACHED_GLYPHS: usize = 1 << 16;
+
+/// font size in quarter pixels, so scaled sizes not mixed up,
+/// family in the lowest 16 bits
 #[inline(always)]
-fn cache_key(char: char, font_size: u8, font_weight: u8, font_family_idx: &Option<u16>) -> u64
+fn cache_key(char: char, font_size: f32, font_weight: u8, font_family_idx: &Option<u16>) -> u64
 {
-	(char as u64) << 32
-		| (font_size as u64) << 24
+	(char as u64) << 40
+		| ((font_size * 4.).round() as u64 & 0xffff) << 24
 		| (font_weight as u64) << 16
 		| font_family_idx.unwrap_or(0xffff) as u64
 }
@@ -591,15 +676,19 @@ pub trait GuiRender {
 	#[inline]
 	fn cache_get(&self, char: char, font_size: f32, font_weight: &FontWeight, font_family_idx: &Option<u16>) -> Option<&CharDrawData>
 	{
-		let key = cache_key(char, font_size as u8, font_weight.key(), font_family_idx);
+		let key = cache_key(char, font_size, font_weight.key(), font_family_idx);
 		self.cache().get(&key)
 	}
 	#[inline]
 	fn cache_insert(&mut self, char: char, font_size: f32, font_weight: &FontWeight,
 		font_family_idx: &Option<u16>, data: CharDrawData)
 	{
-		let key = cache_key(char, font_size as u8, font_weight.key(), font_family_idx);
-		self.cache_mut().insert(key, data);
+		let key = cache_key(char, font_size, font_weight.key(), font_family_idx);
+		let cache = self.cache_mut();
+		if cache.len() >= MAX_CACHED_GLYPHS {
+			cache.clear();
+		}
+		cache.insert(key, data);
 	}
 	fn clear_cache_with_family(&mut self)
 	{
@@ -917,14 +1006,23 @@ pub trait GuiRender {
 						let draw_position = Pos2::new(dc.rect.min.x + cell.cell_offset.x, dc.rect.min.y + cell.cell_offset.y);
 						// should always exists
 						if let Some(draw_data) = self.cache_get(cell.char, cell.font_size, &cell.font_weight, &cell.font_family) {
-							draw_char(
-								cairo,
-								draw_data,
-								&draw_position,
-								&cell.color,
-								font_family_names,
-								layout,
-							);
+							if cell.rotated {
+								handle_cairo(cairo.save());
+								cairo.translate(draw_position.x as f64, draw_position.y as f64);
+								cairo.rotate(std::f64::consts::FRAC_PI_2);
+								draw_char(cairo, draw_data, &Pos2::ZERO, &cell.color,
+									font_family_names, layout);
+								handle_cairo(cairo.restore());
+							} else {
+								draw_char(
+									cairo,
+									draw_data,
+									&draw_position,
+									&cell.color,
+									font_family_names,
+									layout,
+								);
+							}
 						}
 					}
 				}
@@ -1026,6 +1124,28 @@ pub trait GuiRender {
 		}
 	}
 
+	/// image of the char, or none for drawing as text, outer none for
+	/// alt text of drawn image, which is hidden
+	fn char_image(&mut self, text: &Line, offset: usize, char_style: &CharStyle,
+		book: &dyn Book, context: &RenderContext,
+		image_drawn: &mut Option<bool>) -> Option<Option<(String, Pos2)>>
+	{
+		let view_size = &context.render_rect.size();
+		let font_size = context.font_size;
+		if char_style.image.is_none() || context.text_only {
+			*image_drawn = None;
+			return Some(None);
+		}
+		if text.char_at(offset) == Some(IMAGE_CHAR) {
+			let image = self.with_image(char_style, book, view_size, font_size);
+			*image_drawn = Some(image.is_some());
+			return Some(image);
+		}
+		let drawn = *image_drawn.get_or_insert_with(||
+			self.with_image(char_style, book, view_size, font_size).is_some());
+		if drawn { None } else { Some(None) }
+	}
+
 	fn with_image(&mut self, char_style: &CharStyle, book: &dyn Book,
 		view_size: &Vec2, font_size: u8) -> Option<(String, Pos2)>
 	{
@@ -1083,6 +1203,13 @@ pub trait GuiRender {
 		pango: &PangoContext, render_context: &mut RenderContext)
 	{
 		self.cache_mut().clear();
+		self.apply_font_size_modified(book_fonts, pango, render_context);
+	}
+
+	/// font size is in the cache key, so measures of other sizes kept
+	fn apply_font_size_modified(&mut self, book_fonts: Option<&HtmlFonts>,
+		pango: &PangoContext, render_context: &mut RenderContext)
+	{
 		let measures = self.get_char_measures(
 			pango,
 			HAN_CHAR,
@@ -1103,7 +1230,7 @@ pub trait GuiRender {
 		const SPACE: char = ' ';
 		const FULL_SPACE: char = '　';
 
-		let font_size = scale_font_size(render_context.font_size, &font_scale);
+		let font_size = scale_font_size(render_context.font_size, render_context.text_scale, &font_scale);
 		let font_weight = load_font_weight(&font_weight, render_context);
 		let render_fonts = if render_context.custom_font {
 			book_fonts
@@ -1126,14 +1253,14 @@ pub trait GuiRender {
 			SPACE => {
 				let measures = self.measure_char(
 					layout, 'S', font_size, font_weight, font_family_idx,
-					font_family_names, render_fonts, &render_context.fonts);
+					font_family_names, render_fonts, render_context);
 				self.cache_insert(SPACE, font_size, &font_weight, font_family_idx, CharDrawData::Space(measures.size));
 				measures
 			}
 			FULL_SPACE => {
 				let measures = self.measure_char(
 					layout, HAN_CHAR, font_size, font_weight, font_family_idx,
-					font_family_names, render_fonts, &render_context.fonts);
+					font_family_names, render_fonts, render_context);
 				self.cache_insert(FULL_SPACE, font_size, &font_weight, font_family_idx, CharDrawData::Space(measures.size));
 				measures
 			}
@@ -1145,20 +1272,22 @@ pub trait GuiRender {
 				font_family_idx,
 				font_family_names,
 				render_fonts,
-				&render_context.fonts)
+				render_context)
 		}
 	}
 
 	fn measure_char(&mut self, layout: &PangoContext, char: char, font_size: f32,
 		font_weight: &FontWeight, font_family_idx: &Option<u16>,
 		font_family_names: Option<&IndexSet<String>>,
-		book_fonts: Option<&HtmlFonts>, fonts: &Option<UserFonts>)
+		book_fonts: Option<&HtmlFonts>, render_context: &RenderContext)
 		-> CharMeasures
 	{
+		let synthesize = render_context.font_weight_mode == FontWeightMode::Synthesize;
 		if let Some(draw_data) = OutlineDrawData::measure(
 			char,
 			font_size,
 			font_weight,
+			synthesize,
 			font_family_idx,
 			font_family_names,
 			book_fonts) {
@@ -1176,9 +1305,10 @@ pub trait GuiRender {
 			char,
 			font_size,
 			font_weight,
+			synthesize,
 			font_family_idx,
 			font_family_names,
-			fonts.as_ref()) {
+			render_context.fonts.as_ref().as_ref()) {
 			let measures = CharMeasures {
 				size: draw_data.size,
 				draw_size: draw_data.draw_size,
@@ -1196,6 +1326,7 @@ pub trait GuiRender {
 				font_weight,
 				font_family_idx,
 				font_family_names,
+				&render_context.font_axes,
 				layout);
 			let measures = CharMeasures {
 				size: draw_data.size,
@@ -1316,9 +1447,9 @@ pub fn update_for_highlight(render_line: usize, offset: usize, background: Optio
 }
 
 #[inline]
-fn scale_font_size(font_size: u8, scale: &FontScale) -> f32
+fn scale_font_size(font_size: u8, text_scale: u16, scale: &FontScale) -> f32
 {
-	let scaled_size = scale.scale(font_size as f32);
+	let scaled_size = scale.scale(font_size as f32 * text_scale as f32 / 100.);
 	if scaled_size < 9.0 {
 		9.0
 	} else {
@@ -1326,16 +1457,86 @@ fn scale_font_size(font_size: u8, scale: &FontScale) -> f32
 	}
 }
 
+/// extra space before and after the char, for letter and word spacing
+pub fn char_spacing(char: char, prev: Option<char>, font_size: f32,
+	render_context: &RenderContext) -> (f32, f32)
+{
+	let after = font_size * render_context.letter_spacing as f32 / 100.;
+	if render_context.word_spacing == 0 {
+		return (0., after);
+	}
+	let word_spacing = font_size * render_context.word_spacing as f32 / 100.;
+	if char == ' ' || char == '\t' {
+		return (0., after + word_spacing);
+	}
+	let before = match prev {
+		Some(prev) if (is_cjk(prev) && char.is_alphanumeric() && !is_cjk(char))
+			|| (is_cjk(char) && prev.is_alphanumeric() && !is_cjk(prev)) => word_spacing,
+		_ => 0.,
+	};
+	(before, after)
+}
+
 #[inline]
 fn load_font_weight<'a>(font_weight: &'a FontWeight, render_context: &RenderContext) -> &'a FontWeight
 {
-	if render_context.ignore_font_weight {
+	if render_context.font_weight_mode == FontWeightMode::Ignore {
 		&FontWeight::NORMAL
 	} else {
 		font_weight
 	}
 }
 
+/// variations for pango, configured weight only for text without weight
+fn pango_font_variations(font_weight: &FontWeight, font_axes: &FontAxes) -> Option<String>
+{
+	let mut variations = vec![];
+	if font_weight.is_default() {
+		if let Some(weight) = font_axes.weight {
+			variations.push(format!("wght={}", weight));
+		}
+	}
+	if let Some(width) = font_axes.width {
+		variations.push(format!("wdth={}", width));
+	}
+	if variations.is_empty() {
+		None
+	} else {
+		Some(variations.join(","))
+	}
+}
+
+#[inline]
+fn synthesize_stroke(font_size: f32) -> usize
+{
+	(font_size / 24.).round().max(1.) as usize
+}
+
+/// fake bold by widening glyph coverage horizontally
+fn embolden_points(points: Vec<u8>, width: usize, height: usize, stroke: usize) -> Vec<u8>
+{
+	if width == 0 || points.len() < width * height {
+		return points;
+	}
+	let new_width = width + stroke;
+	let mut emboldened = vec![0; new_width * height];
+	for y in 0..height {
+		for x in 0..width {
+			let alpha = points[y * width + x];
+			if alpha == 0 {
+				continue;
+			}
+			for offset in 0..=stroke {
+				let idx = y * new_width + x + offset;
+				if emboldened[idx] < alpha {
+					emboldened[idx] = alpha;
+				}
+			}
+		}
+	}
+	emboldened
+}
+
 #[inline]
 fn draw_double_line<D>(cairo: &CairoContext, stroke_width: f32,
 	middle: f64, draw: D)
@@ -1536,11 +1737,12 @@ pub fn handle_cairo<T>(result: Result<T, cairo::Error>)
 
 #[inline(always)]
 fn set_pango_font_size(font_size: u8, font_weight: &FontWeight,
-	font_families: Option<&str>, layout: &PangoContext)
+	font_variations: Option<&str>, font_families: Option<&str>, layout: &PangoContext)
 {
 	let mut description = FontDescription::new();
 	description.set_size(font_size as i32 * PANGO_SCALE);
 	description.set_weight(font_weight.gtk());
+	description.set_variations(font_variations);
 	if let Some(font_families) = font_families {
 		description.set_family(font_families);
 	}
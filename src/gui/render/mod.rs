@@ -2,6 +2,8 @@ mod imp;
 mod han;
 mod xi;
 
+pub use imp::handle_cairo;
+pub use imp::AnnotationMark;
 pub use imp::BlockBackgroundEntry;
 pub use imp::GuiRender;
 pub use imp::PointerPosition;
@@ -10,9 +10,9 @@ use crate::common::with_leading;
 use crate::controller::HighlightInfo;
 use crate::gui::math::{Pos2, pos2, Rect, Vec2};
 use crate::gui::render::{CharCell, CharDrawData, GuiRender, hline, ImageDrawingData, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, update_for_highlight};
-use crate::gui::render::imp::draw_border;
+use crate::gui::render::imp::{char_spacing, draw_border};
 use crate::html_parser;
-use crate::html_parser::{BorderLines, TextDecorationLine, TextStyle};
+use crate::html_parser::{BorderLines, FontWeight, TextDecorationLine, TextStyle};
 
 pub(super) struct GuiXiRender {
 	images: HashMap<String, ImageDrawingData>,
@@ -123,11 +123,19 @@ impl GuiRender for GuiXiRender
 
 		let mut left = context.render_rect.min.x;
 		let max_left = context.render_rect.max.x;
-		let view_rect = &context.render_rect;
-		let view_size = view_rect.size();
+		let emphasis = if context.bionic_reading {
+			bionic_emphasis(text)
+		} else {
+			vec![]
+		};
+		let mut image_drawn = None;
 		for i in start_offset..end_offset {
 			let char_style = text.char_style_at(i, context.custom_color, &context.colors);
-			let (cell, mut rect, is_blank_char, can_break) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size) {
+			let Some(image) = self.char_image(text, i, &char_style, book, context,
+				&mut image_drawn) else {
+				continue;
+			};
+			let (cell, mut rect, is_blank_char, can_break) = if let Some((path, size)) = image {
 				let bottom = self.baseline + size.y;
 				let right = left + size.x;
 				let rect = Rect::from_min_max(
@@ -141,20 +149,32 @@ impl GuiRender for GuiXiRender
 					left += context.leading_space;
 				}
 				let char = text.char_at(i).unwrap();
+				// weight of book kept for text already weighted
+				let font_weight = if emphasis.get(i).copied().unwrap_or(false)
+					&& char_style.font_weight.is_default() {
+					&FontWeight::BOLD
+				} else {
+					&char_style.font_weight
+				};
 				let measures = self.get_char_measures(
 					pango,
 					char,
 					&char_style.font_scale,
-					&char_style.font_weight,
+					font_weight,
 					&char_style.font_family,
 					book.font_family_names(),
 					book.custom_fonts(),
 					context);
 
-				let mut rect = Rect::new(left, self.baseline, measures.size.x, measures.size.y);
+				let prev_char = i.checked_sub(1).and_then(|i| text.char_at(i));
+				let (space_before, space_after) = char_spacing(char, prev_char, measures.font_size, context);
+				let mut rect = Rect::new(left, self.baseline,
+					measures.size.x + space_before + space_after, measures.size.y);
 				let color = char_style.color.clone();
-				let background = update_for_highlight(line, i, char_style.background.clone(), &context.colors, highlight);
-				let cell_offset = if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
+				let background = context.annotation_background(book.current_chapter(), line, i)
+					.or_else(|| char_style.background.clone());
+				let background = update_for_highlight(line, i, background, &context.colors, highlight);
+				let mut cell_offset = if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
 					if lines.contains(BorderLines::Left) {
 						if lines.contains(BorderLines::Right) {
 							let draw_width = measures.size.x;
@@ -196,6 +216,7 @@ impl GuiRender for GuiXiRender
 				} else {
 					Vec2::ZERO
 				};
+				cell_offset.x += space_before;
 				let blank_char = char == ' ' || char == '\t';
 				let cell = CharCell {
 					char: if blank_char { ' ' } else { char },
@@ -206,6 +227,7 @@ impl GuiRender for GuiXiRender
 					background,
 					cell_offset,
 					cell_size: measures.size,
+					rotated: false,
 				};
 				let render_cell = if let Some((link_index, _)) = char_style.link {
 					RenderCell::Link(cell, link_index)
@@ -275,7 +297,7 @@ impl GuiRender for GuiXiRender
 				cell,
 				offset: i,
 				rect,
-				has_title: char_style.title.is_some(),
+				has_title: char_style.has_title(),
 			});
 			if is_blank_char {
 				break_position = Some(draw_chars.len());
@@ -602,3 +624,33 @@ impl GuiRender for GuiXiRender
 		mouse_pos.y += scroll_value;
 	}
 }
+
+#[inline]
+fn is_latin(char: char) -> bool
+{
+	char.is_ascii_alphabetic()
+		|| matches!(char, '\u{c0}'..='\u{24f}' if char != '\u{d7}' && char != '\u{f7}')
+}
+
+/// chars in the leading half of every latin word for bionic reading,
+/// computed for rendering only, line text untouched
+fn bionic_emphasis(text: &Line) -> Vec<bool>
+{
+	let len = text.len();
+	let mut emphasis = vec![false; len];
+	let mut start = 0;
+	while start < len {
+		if !text.char_at(start).map_or(false, is_latin) {
+			start += 1;
+			continue;
+		}
+		let mut end = start + 1;
+		while end < len && text.char_at(end).map_or(false, is_latin) {
+			end += 1;
+		}
+		let count = (end - start + 1) / 2;
+		emphasis[start..start + count].fill(true);
+		start = end;
+	}
+	emphasis
+}
@@ -2,24 +2,55 @@ use std::cell::RefCell;
 use std::path::PathBuf;
 use std::rc::Rc;
 
-use gtk4::{AlertDialog, Align, ApplicationWindow, Button, CheckButton, ColorDialog, ColorDialogButton, DropDown, Entry, EventControllerKey, FileDialog, FileFilter, glib, Label, ListBox, ListBoxRow, Orientation, PolicyType, ScrolledWindow, SelectionMode, Separator, StringList, Window};
-use gtk4::gdk::Key;
+use gtk4::{AlertDialog, Align, ApplicationWindow, Button, CheckButton, ColorDialog, ColorDialogButton, DragSource, DropDown, DropTarget, Entry, EventControllerKey, FileDialog, FileFilter, glib, Label, ListBox, ListBoxRow, Orientation, Picture, PolicyType, ScrolledWindow, SearchEntry, SelectionMode, Separator, Stack, StackPage, StackSwitcher, StringList, StringObject, Widget, Window};
+use gtk4::gdk::{ContentProvider, DragAction, Key, MemoryFormat, MemoryTexture};
 use gtk4::gio::{Cancellable, File, ListStore};
-use gtk4::glib::Object;
-use gtk4::glib::prelude::Cast;
+use gtk4::glib::{Bytes, Object};
+use gtk4::glib::prelude::{Cast, IsA, StaticType, ToValue};
 use gtk4::prelude::{BoxExt, ButtonExt, CheckButtonExt, EditableExt, FileExt, GtkWindowExt, ListBoxRowExt, ListModelExt, WidgetExt};
 use gtk4::subclass::prelude::ObjectSubclassIsExt;
 use crate::color::Color32;
 
-use crate::config::{Configuration, PathConfig, SidebarPosition};
-use crate::gui::{alert, create_button, DICT_FILE_EXTENSIONS, font, FONT_FILE_EXTENSIONS, GuiContext, IconMap, MAX_FONT_SIZE, MIN_FONT_SIZE, MODIFIER_NONE, set_sidebar_position, sidebar_updated};
-use crate::gui::font::UserFonts;
+use crate::config::{AccessibilityPreset, Configuration, FlashcardTarget, FocusMode, FontAxes, FontWeightMode, GoalUnit, LinkKeys, NightLight, PageTurn, Paper, PaperMode, PathConfig, ReadingGoal, SidebarPosition, StatusWidget, ToolbarItem};
+use crate::gui::{alert, create_button, DICT_FILE_EXTENSIONS, focus_of, font, FONT_FILE_EXTENSIONS, GuiContext, IconMap, layout_toolbar, load_dictionaries, MAX_FONT_SIZE, MAX_UI_SCALE, MIN_FONT_SIZE, MIN_UI_SCALE, MODIFIER_NONE, rebuild_windows, set_accessibility_preset, set_dict_docked, set_eink, set_paper, set_sidebar_position, set_ui_scale, sidebar_updated, status, view, watch_file};
+use crate::gui::font::{FONT_PREVIEW_TEXT, UserFonts};
 use crate::I18n;
 
-const SIDEBAR_POSITIONS: [SidebarPosition; 2] = [
+const FONT_PREVIEW_SIZE: f32 = 20.;
+// i18n keys of pages in order
+const SETTINGS_PAGES: [&str; 5] = [
+	"settings-appearance",
+	"settings-reading",
+	"settings-dictionaries",
+	"settings-keys",
+	"settings-advanced",
+];
+
+const SIDEBAR_POSITIONS: [SidebarPosition; 3] = [
 	SidebarPosition::Left,
 	SidebarPosition::Top,
+	SidebarPosition::Right,
+];
+const FONT_WEIGHT_MODES: [FontWeightMode; 3] = [
+	FontWeightMode::Book,
+	FontWeightMode::Synthesize,
+	FontWeightMode::Ignore,
 ];
+// css range of font-weight, and enough for font-stretch percentage
+const MIN_FONT_AXIS: u16 = 1;
+const MAX_FONT_AXIS: u16 = 1000;
+// percent of font size
+const MAX_SPACING: u16 = 100;
+const MIN_TEXT_SCALE: u16 = 50;
+const MAX_TEXT_SCALE: u16 = 200;
+const MIN_FOCUS_DIM: u16 = 10;
+const MAX_FOCUS_DIM: u16 = 100;
+const MAX_READING_GOAL: u32 = 10000;
+const MIN_COLOR_TEMPERATURE: u16 = 1000;
+const MAX_COLOR_TEMPERATURE: u16 = 6500;
+const MAX_PAGE_TURN_MILLIS: u16 = 2000;
+const MAX_WHEEL_LINES: u16 = 20;
+const MAX_HISTORY_SIZE: u16 = 200;
 
 pub(super) struct Settings {
 	gcs: Rc<RefCell<Vec<GuiContext>>>,
@@ -32,6 +63,15 @@ impl Settings {
 		Settings { gcs }
 	}
 
+	/// book opened in any window
+	#[inline]
+	pub fn is_opened(&self, filename: &str) -> bool
+	{
+		self.gcs.borrow()
+			.iter()
+			.any(|gc| gc.ctrl().reading.filename == filename)
+	}
+
 	#[inline]
 	pub fn dialog(&self, gc: &GuiContext)
 	{
@@ -45,16 +85,45 @@ impl Settings {
 
 struct SettingsParam<'a> {
 	render_han: bool,
+	search_fold_width: bool,
+	chapter_continuation: bool,
 	locale: &'a str,
 	fonts: Vec<PathConfig>,
 	dictionaries: Vec<PathConfig>,
 	cache_dict: bool,
-	ignore_font_weight: bool,
+	font_weight_mode: FontWeightMode,
+	font_axes: FontAxes,
 	strip_empty_lines: bool,
 	scroll_for_page: bool,
+	wheel_lines: u8,
+	smooth_scroll: bool,
+	eink: bool,
+	restore_session: bool,
+	watch_file: bool,
+	history_size: usize,
 	default_font_size: u8,
+	letter_spacing: u8,
+	word_spacing: u8,
+	text_scale: u16,
+	ui_scale: u16,
+	accessibility_preset: AccessibilityPreset,
+	focus_mode: FocusMode,
+	focus_dim: u8,
 	sidebar_position: &'a SidebarPosition,
+	dict_docked: bool,
+	toolbar: Vec<ToolbarItem>,
+	status_widgets: Vec<StatusWidget>,
 	select_by_dictionary: bool,
+	auto_copy: bool,
+	watch_clipboard: bool,
+	flashcard_target: FlashcardTarget,
+	flashcard_deck: String,
+	reading_goal: ReadingGoal,
+	night_light: NightLight,
+	paper: Paper,
+	page_turn: PageTurn,
+	page_turn_millis: u16,
+	link_keys: LinkKeys,
 	color_color: Color32,
 	color_background: Color32,
 	color_highlight: Color32,
@@ -75,6 +144,26 @@ fn append_checkbox(title: &str, checked: bool, settings: &gtk4::Box) -> CheckBut
 	cb
 }
 
+/// entry for percent value, with range hint
+fn append_percent_entry(title: &str, value: u16, min: u16, max: u16,
+	settings: &gtk4::Box) -> Entry
+{
+	let entry = Entry::builder()
+		.text(&format!("{}", value))
+		.width_chars(6)
+		.build();
+
+	let entry_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	entry_box.append(&title_label(title));
+	entry_box.append(&entry);
+	entry_box.append(&Label::builder()
+		.label(&format!("% ({} - {})", min, max))
+		.build());
+
+	settings.append(&entry_box);
+	entry
+}
+
 fn append_color_btn(title: &str, color: Color32, color_dialog: &ColorDialog,
 	settings: &gtk4::Box, i18n: &I18n) -> ColorDialogButton
 {
@@ -93,6 +182,94 @@ fn append_color_btn(title: &str, color: Color32, color_dialog: &ColorDialog,
 	btn
 }
 
+/// title label with its widget, shown or hidden together when filtering
+#[inline]
+fn append_group(label: &impl IsA<Widget>, widget: &impl IsA<Widget>, settings: &gtk4::Box)
+{
+	let group = gtk4::Box::new(Orientation::Vertical, 10);
+	group.append(label);
+	group.append(widget);
+	settings.append(&group);
+}
+
+/// entry for key in gtk accelerator format, like "<Shift>l"
+fn append_key_entry(title: &str, accelerator: &str, settings: &gtk4::Box) -> Entry
+{
+	let entry = Entry::builder()
+		.text(accelerator)
+		.width_chars(12)
+		.build();
+	let key_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	key_box.append(&title_label(title));
+	key_box.append(&entry);
+	settings.append(&key_box);
+	entry
+}
+
+/// scrollable page of the stack, with i18n key as name
+fn create_page(stack: &Stack, key: &str, i18n: &I18n) -> (gtk4::Box, StackPage)
+{
+	let page = gtk4::Box::new(Orientation::Vertical, 10);
+	page.set_margin_top(10);
+	page.set_margin_bottom(10);
+	page.set_margin_start(10);
+	page.set_margin_end(10);
+	let stack_page = stack.add_titled(&ScrolledWindow::builder()
+		.child(&page)
+		.hscrollbar_policy(PolicyType::Never)
+		.vexpand(true)
+		.build(), Some(key), &i18n.msg(key));
+	(page, stack_page)
+}
+
+/// options not matching the search text hidden, and pages without any match
+fn filter_settings(stack: &Stack, pages: &[(gtk4::Box, StackPage)], text: &str)
+{
+	let text = text.trim().to_lowercase();
+	let mut first_matched = None;
+	let mut visible_matched = false;
+	for (page, stack_page) in pages {
+		let mut matched = false;
+		let mut child = page.first_child();
+		while let Some(widget) = child {
+			let visible = text.is_empty()
+				|| widget_text(&widget).to_lowercase().contains(&text);
+			widget.set_visible(visible);
+			matched |= visible;
+			child = widget.next_sibling();
+		}
+		stack_page.set_visible(matched);
+		if matched {
+			let page_widget = stack_page.child();
+			if stack.visible_child().as_ref() == Some(&page_widget) {
+				visible_matched = true;
+			}
+			first_matched.get_or_insert(page_widget);
+		}
+	}
+	if !visible_matched {
+		if let Some(page_widget) = first_matched {
+			stack.set_visible_child(&page_widget);
+		}
+	}
+}
+
+/// texts of all labels in the widget
+fn widget_text(widget: &Widget) -> String
+{
+	let mut text = String::new();
+	if let Some(label) = widget.downcast_ref::<Label>() {
+		text.push_str(&label.label());
+	}
+	let mut child = widget.first_child();
+	while let Some(widget) = child {
+		text.push('\n');
+		text.push_str(&widget_text(&widget));
+		child = widget.next_sibling();
+	}
+	text
+}
+
 fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 	i18n: &Rc<I18n>, icons: &Rc<IconMap>, apply: F) -> Window
 	where F: Fn(SettingsParam, Option<Option<UserFonts>>) + 'static
@@ -106,17 +283,38 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		.modal(true)
 		.build();
 
-	let settings = gtk4::Box::new(Orientation::Vertical, 10);
-	settings.set_margin_top(10);
-	settings.set_margin_bottom(10);
-	settings.set_margin_start(10);
-	settings.set_margin_end(10);
-	let dialog_box = gtk4::Box::new(Orientation::Vertical, 0);
-	dialog_box.append(&ScrolledWindow::builder()
-		.child(&settings)
-		.hscrollbar_policy(PolicyType::Never)
+	let stack = Stack::builder()
 		.vexpand(true)
+		.build();
+	let pages: Vec<_> = SETTINGS_PAGES
+		.iter()
+		.map(|key| create_page(&stack, key, i18n))
+		.collect();
+	let appearance = &pages[0].0;
+	let reading = &pages[1].0;
+	let dictionaries = &pages[2].0;
+	let keys = &pages[3].0;
+	let advanced = &pages[4].0;
+	let search_entry = SearchEntry::builder()
+		.placeholder_text(i18n.msg("settings-search"))
+		.margin_top(10)
+		.margin_start(10)
+		.margin_end(10)
+		.build();
+	{
+		let stack = stack.clone();
+		let pages = pages.clone();
+		search_entry.connect_search_changed(move |entry|
+			filter_settings(&stack, &pages, &entry.text()));
+	}
+	let dialog_box = gtk4::Box::new(Orientation::Vertical, 0);
+	dialog_box.append(&search_entry);
+	dialog_box.append(&StackSwitcher::builder()
+		.stack(&stack)
+		.halign(Align::Center)
+		.margin_top(10)
 		.build());
+	dialog_box.append(&stack);
 	dialog.set_child(Some(&dialog_box));
 
 	let configuration = cfg.borrow();
@@ -139,8 +337,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 
 		locale_box.append(&title_label(&i18n.msg("lang")));
 		locale_box.append(&locale_dropdown);
-		locale_box.append(&Label::new(Some(&i18n.msg("need-restart"))));
-		settings.append(&locale_box);
+		appearance.append(&locale_box);
 		locale_dropdown
 	};
 
@@ -157,22 +354,87 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 			!han,
 			&b);
 		xi_cb.set_group(Some(&han_cb));
-		settings.append(&b);
+		appearance.append(&b);
 		han_cb
 	};
 
-	let ignore_font_weight_cb = append_checkbox(
-		&i18n.msg("ignore-font-weight"),
-		configuration.gui.ignore_font_weight,
-		&settings);
+	let font_weight_mode_dropdown = {
+		let mode_box = gtk4::Box::new(Orientation::Horizontal, 0);
+		let mode_list = StringList::default();
+		let mut current_mode = 0;
+		for (idx, entry) in FONT_WEIGHT_MODES.iter().enumerate() {
+			mode_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == configuration.gui.font_weight_mode {
+				current_mode = idx;
+			}
+		};
+		let mode_dropdown = DropDown::builder()
+			.margin_start(10)
+			.model(&mode_list)
+			.selected(current_mode as u32)
+			.build();
+
+		mode_box.append(&title_label(&i18n.msg("font-weight-mode")));
+		mode_box.append(&mode_dropdown);
+		appearance.append(&mode_box);
+		mode_dropdown
+	};
 	let strip_empty_lines_cb = append_checkbox(
 		&i18n.msg("strip-empty-lines"),
 		configuration.gui.strip_empty_lines,
-		&settings);
+		&reading);
+	let search_fold_width_cb = append_checkbox(
+		&i18n.msg("search-fold-width"),
+		configuration.search_fold_width,
+		&reading);
+	let chapter_continuation_cb = append_checkbox(
+		&i18n.msg("chapter-continuation"),
+		configuration.chapter_continuation,
+		&reading);
 	let scroll_for_page_cb = append_checkbox(
 		&i18n.msg("scroll-for-page"),
 		configuration.gui.scroll_for_page,
-		&settings);
+		&reading);
+	let wheel_lines_entry = {
+		let wheel_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		let entry = Entry::builder()
+			.text(&format!("{}", configuration.gui.wheel_lines))
+			.width_chars(6)
+			.build();
+		wheel_box.append(&title_label(&i18n.msg("wheel-lines")));
+		wheel_box.append(&entry);
+		wheel_box.append(&Label::new(Some(&format!("(1 - {})", MAX_WHEEL_LINES))));
+		reading.append(&wheel_box);
+		entry
+	};
+	let smooth_scroll_cb = append_checkbox(
+		&i18n.msg("smooth-scroll"),
+		configuration.gui.smooth_scroll,
+		&reading);
+	let eink_cb = append_checkbox(
+		&i18n.msg("eink-mode"),
+		configuration.gui.eink,
+		&appearance);
+	let restore_session_cb = append_checkbox(
+		&i18n.msg("restore-session"),
+		configuration.gui.restore_session,
+		&advanced);
+	let watch_file_cb = append_checkbox(
+		&i18n.msg("watch-file"),
+		configuration.gui.watch_file,
+		&advanced);
+	let history_size_entry = {
+		let history_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		let entry = Entry::builder()
+			.text(&format!("{}", configuration.history_size))
+			.width_chars(6)
+			.build();
+		history_box.append(&title_label(&i18n.msg("history-size")));
+		history_box.append(&entry);
+		history_box.append(&Label::new(Some(&format!("(1 - {})", MAX_HISTORY_SIZE))));
+		advanced.append(&history_box);
+		entry
+	};
 
 	let sidebar_position_dropdown = {
 		let sidebar_position_box = gtk4::Box::new(Orientation::Horizontal, 0);
@@ -192,10 +454,13 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 
 		sidebar_position_box.append(&title_label(&i18n.msg("sidebar-position")));
 		sidebar_position_box.append(&sidebar_position_dropdown);
-		settings.append(&sidebar_position_box);
-		settings.append(&sidebar_position_dropdown);
+		appearance.append(&sidebar_position_box);
 		sidebar_position_dropdown
 	};
+	let dict_docked_cb = append_checkbox(
+		&i18n.msg("dict-docked"),
+		configuration.gui.dict_docked,
+		&dictionaries);
 
 	let font_size_entry = {
 		let entry = Entry::builder()
@@ -209,10 +474,111 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 			.label(&format!("({} - {})", MIN_FONT_SIZE, MAX_FONT_SIZE))
 			.build());
 
-		settings.append(&fs_box);
+		appearance.append(&fs_box);
 		entry
 	};
 
+	let (font_weight_axis_entry, font_width_axis_entry) = {
+		#[inline]
+		fn axis_entry(value: Option<u16>, placeholder: &str) -> Entry
+		{
+			Entry::builder()
+				.text(&value.map_or_else(String::new, |value| format!("{}", value)))
+				.placeholder_text(placeholder)
+				.width_chars(6)
+				.build()
+		}
+		let axes = &configuration.gui.font_axes;
+		let weight_entry = axis_entry(axes.weight, "wght");
+		let width_entry = axis_entry(axes.width, "wdth");
+
+		let axes_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		axes_box.append(&title_label(&i18n.msg("font-axes")));
+		axes_box.append(&weight_entry);
+		axes_box.append(&width_entry);
+		axes_box.append(&Label::builder()
+			.label(&format!("({} - {})", MIN_FONT_AXIS, MAX_FONT_AXIS))
+			.build());
+
+		appearance.append(&axes_box);
+		(weight_entry, width_entry)
+	};
+
+	let letter_spacing_entry = append_percent_entry(
+		&i18n.msg("letter-spacing"),
+		configuration.gui.letter_spacing as u16,
+		0,
+		MAX_SPACING,
+		&appearance);
+	let word_spacing_entry = append_percent_entry(
+		&i18n.msg("word-spacing"),
+		configuration.gui.word_spacing as u16,
+		0,
+		MAX_SPACING,
+		&appearance);
+	let text_scale_entry = append_percent_entry(
+		&i18n.msg("text-scale"),
+		configuration.gui.text_scale,
+		MIN_TEXT_SCALE,
+		MAX_TEXT_SCALE,
+		&appearance);
+	let ui_scale_entry = append_percent_entry(
+		&i18n.msg("ui-scale"),
+		configuration.gui.ui_scale,
+		MIN_UI_SCALE,
+		MAX_UI_SCALE,
+		&appearance);
+
+	let accessibility_dropdown = {
+		let preset_box = gtk4::Box::new(Orientation::Horizontal, 0);
+		let preset_list = StringList::default();
+		let mut current_preset = 0;
+		for (idx, entry) in AccessibilityPreset::ALL.iter().enumerate() {
+			preset_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == configuration.gui.accessibility_preset {
+				current_preset = idx;
+			}
+		};
+		let preset_dropdown = DropDown::builder()
+			.margin_start(10)
+			.model(&preset_list)
+			.selected(current_preset as u32)
+			.build();
+
+		preset_box.append(&title_label(&i18n.msg("accessibility-preset")));
+		preset_box.append(&preset_dropdown);
+		appearance.append(&preset_box);
+		preset_dropdown
+	};
+
+	let focus_mode_dropdown = {
+		let mode_box = gtk4::Box::new(Orientation::Horizontal, 0);
+		let mode_list = StringList::default();
+		let mut current_mode = 0;
+		for (idx, entry) in FocusMode::ALL.iter().enumerate() {
+			mode_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == configuration.gui.focus_mode {
+				current_mode = idx;
+			}
+		};
+		let mode_dropdown = DropDown::builder()
+			.margin_start(10)
+			.model(&mode_list)
+			.selected(current_mode as u32)
+			.build();
+
+		mode_box.append(&title_label(&i18n.msg("focus-mode")));
+		mode_box.append(&mode_dropdown);
+		reading.append(&mode_box);
+		mode_dropdown
+	};
+	let focus_dim_entry = append_percent_entry(
+		&i18n.msg("focus-dim"),
+		configuration.gui.focus_dim as u16,
+		MIN_FOCUS_DIM,
+		MAX_FOCUS_DIM,
+		&reading);
+
 	let colors = configuration.gui.
 		curr_colors(configuration.dark_theme);
 	let color_dialog = ColorDialog::new();
@@ -220,43 +586,43 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		"color-color",
 		colors.color.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_background_btn = append_color_btn(
 		"color-background",
 		colors.background.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_highlight_btn = append_color_btn(
 		"color-highlight",
 		colors.highlight.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_highlight_background_btn = append_color_btn(
 		"color-highlight-background",
 		colors.highlight_background.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_link_btn = append_color_btn(
 		"color-link",
 		colors.link.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_matched_btn = append_color_btn(
 		"color-matched",
 		colors.matched_color.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 	let color_matched_background_btn = append_color_btn(
 		"color-matched-background",
 		colors.matched_background.clone(),
 		&color_dialog,
-		&settings,
+		&appearance,
 		i18n);
 
 	let font_list = {
@@ -264,6 +630,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		let (label, view, font_list, font_add_btn) = create_list(
 			&title,
 			&configuration.gui.fonts,
+			true,
 			i18n,
 			icons,
 		);
@@ -295,8 +662,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				});
 			});
 		}
-		settings.append(&label);
-		settings.append(&view);
+		append_group(&label, &view, appearance);
 		font_list
 	};
 
@@ -305,6 +671,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		let (label, view, dict_list, dict_add_btn) = create_list(
 			&title,
 			&configuration.gui.dictionaries,
+			false,
 			i18n,
 			icons,
 		);
@@ -347,21 +714,20 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				});
 			});
 		}
-		settings.append(&label);
-		settings.append(&view);
+		append_group(&label, &view, dictionaries);
 		dict_list
 	};
 
 	let cache_dict_cb = append_checkbox(
 		&i18n.msg("cache-dictionary"),
 		configuration.gui.cache_dict,
-		&settings);
+		&dictionaries);
 
 	let disable_select_by_dictionary = dict_list.n_items() == 0;
 	let select_by_dictionary_cb = append_checkbox(
 		&i18n.msg("select-by-dictionary"),
 		if disable_select_by_dictionary { false } else { configuration.gui.select_by_dictionary },
-		&settings);
+		&dictionaries);
 	if disable_select_by_dictionary {
 		select_by_dictionary_cb.set_sensitive(false);
 	}
@@ -376,6 +742,225 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 			}
 		});
 	}
+	let auto_copy_cb = append_checkbox(
+		&i18n.msg("auto-copy-selection"),
+		configuration.gui.auto_copy,
+		&dictionaries);
+	let watch_clipboard_cb = append_checkbox(
+		&i18n.msg("watch-clipboard"),
+		configuration.gui.watch_clipboard,
+		&dictionaries);
+
+	let (flashcard_target_dropdown, flashcard_deck_entry) = {
+		let flashcard_box = gtk4::Box::new(Orientation::Horizontal, 0);
+		let target_list = StringList::default();
+		let mut current_target = 0;
+		for (idx, entry) in FlashcardTarget::ALL.iter().enumerate() {
+			target_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == configuration.gui.flashcard.target {
+				current_target = idx;
+			}
+		};
+		let target_dropdown = DropDown::builder()
+			.margin_start(10)
+			.model(&target_list)
+			.selected(current_target as u32)
+			.build();
+		let deck_entry = Entry::builder()
+			.margin_start(10)
+			.text(&configuration.gui.flashcard.deck)
+			.placeholder_text(i18n.msg("flashcard-deck"))
+			.tooltip_text(i18n.msg("flashcard-deck"))
+			.build();
+
+		flashcard_box.append(&title_label(&i18n.msg("flashcard-target")));
+		flashcard_box.append(&target_dropdown);
+		flashcard_box.append(&deck_entry);
+		dictionaries.append(&flashcard_box);
+		(target_dropdown, deck_entry)
+	};
+
+	let (goal_amount_entry, goal_unit_dropdown) = {
+		let goal_box = gtk4::Box::new(Orientation::Horizontal, 0);
+		let goal = &configuration.gui.reading_goal;
+		let amount_entry = Entry::builder()
+			.margin_start(10)
+			.text(&format!("{}", goal.amount))
+			.width_chars(6)
+			.tooltip_text(i18n.msg("reading-goal-hint"))
+			.build();
+		let unit_list = StringList::default();
+		let mut current_unit = 0;
+		for (idx, entry) in GoalUnit::ALL.iter().enumerate() {
+			unit_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == goal.unit {
+				current_unit = idx;
+			}
+		};
+		let unit_dropdown = DropDown::builder()
+			.margin_start(10)
+			.model(&unit_list)
+			.selected(current_unit as u32)
+			.build();
+
+		goal_box.append(&title_label(&i18n.msg("reading-goal")));
+		goal_box.append(&amount_entry);
+		goal_box.append(&unit_dropdown);
+		reading.append(&goal_box);
+		(amount_entry, unit_dropdown)
+	};
+
+	let (night_light_cb, night_scheduled_cb, temperature_entry, night_start_entry, night_end_entry) = {
+		let night_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		let night_light = &configuration.gui.night_light;
+		let enabled_cb = CheckButton::builder()
+			.label(i18n.msg("night-light-always"))
+			.active(night_light.enabled)
+			.build();
+		let temperature_entry = Entry::builder()
+			.text(&format!("{}", night_light.temperature))
+			.width_chars(6)
+			.tooltip_text(i18n.args_msg("night-light-temperature", vec![
+				("min", MIN_COLOR_TEMPERATURE),
+				("max", MAX_COLOR_TEMPERATURE),
+			]))
+			.build();
+		let scheduled_cb = CheckButton::builder()
+			.label(i18n.msg("night-light-scheduled"))
+			.active(night_light.scheduled)
+			.build();
+		let start_entry = Entry::builder()
+			.text(&format!("{}", night_light.start_hour))
+			.width_chars(3)
+			.build();
+		let end_entry = Entry::builder()
+			.text(&format!("{}", night_light.end_hour))
+			.width_chars(3)
+			.build();
+
+		night_box.append(&title_label(&i18n.msg("night-light")));
+		night_box.append(&temperature_entry);
+		night_box.append(&Label::new(Some("K")));
+		night_box.append(&enabled_cb);
+		night_box.append(&scheduled_cb);
+		night_box.append(&start_entry);
+		night_box.append(&Label::new(Some("-")));
+		night_box.append(&end_entry);
+		appearance.append(&night_box);
+		(enabled_cb, scheduled_cb, temperature_entry, start_entry, end_entry)
+	};
+
+	let (page_turn_dropdown, page_turn_millis_entry) = {
+		let turn_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		let turn_list = StringList::default();
+		let mut current_turn = 0;
+		for (idx, entry) in PageTurn::ALL.iter().enumerate() {
+			turn_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == configuration.gui.page_turn {
+				current_turn = idx;
+			}
+		};
+		let turn_dropdown = DropDown::builder()
+			.model(&turn_list)
+			.selected(current_turn as u32)
+			.build();
+		let millis_entry = Entry::builder()
+			.text(&format!("{}", configuration.gui.page_turn_millis))
+			.width_chars(6)
+			.build();
+
+		turn_box.append(&title_label(&i18n.msg("page-turn")));
+		turn_box.append(&turn_dropdown);
+		turn_box.append(&millis_entry);
+		turn_box.append(&Label::new(Some(&format!("ms (0 - {})", MAX_PAGE_TURN_MILLIS))));
+		reading.append(&turn_box);
+		(turn_dropdown, millis_entry)
+	};
+
+	let (paper_entry, paper_mode_dropdown, paper_opacity_entry) = {
+		let paper_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		let paper = &configuration.gui.paper;
+		let path_entry = Entry::builder()
+			.text(paper.path.as_ref().map_or_else(String::new, path_str))
+			.placeholder_text(i18n.msg("paper-none"))
+			.hexpand(true)
+			.build();
+		let browse_btn = create_button("add.svg", Some(&i18n.msg("paper-select")), icons, true);
+		let image_dialog = FileDialog::new();
+		image_dialog.set_title(&i18n.msg("paper-select"));
+		image_dialog.set_modal(true);
+		let filter = FileFilter::new();
+		filter.add_pixbuf_formats();
+		image_dialog.set_default_filter(Some(&filter));
+		{
+			let dialog = dialog.clone();
+			let path_entry = path_entry.clone();
+			browse_btn.connect_clicked(move |_| {
+				let path_entry = path_entry.clone();
+				image_dialog.open(Some(&dialog), None::<&Cancellable>, move |result| {
+					if let Some(path) = result.ok().and_then(|file| file.path()) {
+						path_entry.set_text(&path_str(&path));
+					}
+				});
+			});
+		}
+		let mode_list = StringList::default();
+		let mut current_mode = 0;
+		for (idx, entry) in PaperMode::ALL.iter().enumerate() {
+			mode_list.append(&i18n.msg(entry.i18n_key()));
+			if *entry == paper.mode {
+				current_mode = idx;
+			}
+		};
+		let mode_dropdown = DropDown::builder()
+			.model(&mode_list)
+			.selected(current_mode as u32)
+			.build();
+		let opacity_entry = Entry::builder()
+			.text(&format!("{}", paper.opacity))
+			.width_chars(4)
+			.tooltip_text(i18n.msg("paper-opacity"))
+			.build();
+
+		paper_box.append(&title_label(&i18n.msg("paper")));
+		paper_box.append(&path_entry);
+		paper_box.append(&browse_btn);
+		paper_box.append(&mode_dropdown);
+		paper_box.append(&opacity_entry);
+		paper_box.append(&Label::new(Some("%")));
+		appearance.append(&paper_box);
+		(path_entry, mode_dropdown, opacity_entry)
+	};
+
+	let (toolbar_list, toolbar_hidden) = create_toolbar_list(
+		&configuration.gui.toolbar,
+		&appearance,
+		i18n);
+
+	let status_widget_cbs = {
+		let label = title_label(&i18n.msg("status-widgets"));
+		label.set_margin_top(10);
+		let b = gtk4::Box::new(Orientation::Horizontal, 10);
+		let cbs: Vec<_> = StatusWidget::ALL
+			.into_iter()
+			.map(|widget| {
+				let cb = append_checkbox(
+					&i18n.msg(widget.i18n_key()),
+					configuration.gui.status_widgets.contains(&widget),
+					&b);
+				(widget, cb)
+			})
+			.collect();
+		append_group(&label, &b, appearance);
+		cbs
+	};
+
+	let (next_link_key_entry, prev_link_key_entry) = {
+		let link_keys = &configuration.gui.link_keys;
+		let next_entry = append_key_entry(&i18n.msg("next-link-key"), &link_keys.next, keys);
+		let prev_entry = append_key_entry(&i18n.msg("prev-link-key"), &link_keys.prev, keys);
+		(next_entry, prev_entry)
+	};
 
 	let button_box = gtk4::Box::new(Orientation::Horizontal, 10);
 	button_box.set_halign(Align::End);
@@ -402,6 +987,27 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-default-font-size"), &dialog);
 				return;
 			};
+			let (letter_spacing, word_spacing, text_scale) = match (
+				parse_percent(&letter_spacing_entry, 0, MAX_SPACING),
+				parse_percent(&word_spacing_entry, 0, MAX_SPACING),
+				parse_percent(&text_scale_entry, MIN_TEXT_SCALE, MAX_TEXT_SCALE)) {
+				(Some(letter_spacing), Some(word_spacing), Some(text_scale)) =>
+					(letter_spacing as u8, word_spacing as u8, text_scale),
+				_ => {
+					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-text-spacing"), &dialog);
+					return;
+				}
+			};
+			let Some(ui_scale) = parse_percent(&ui_scale_entry, MIN_UI_SCALE, MAX_UI_SCALE) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-ui-scale"), &dialog);
+				return;
+			};
+			let Some(focus_dim) = parse_percent(&focus_dim_entry, MIN_FOCUS_DIM, MAX_FOCUS_DIM) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-focus-dim"), &dialog);
+				return;
+			};
+			let accessibility_preset = AccessibilityPreset::ALL[accessibility_dropdown.selected() as usize];
+			let focus_mode = FocusMode::ALL[focus_mode_dropdown.selected() as usize];
 			let render_han = render_han_cb.is_active();
 			let locale = {
 				let idx = locale_dropdown.selected();
@@ -410,9 +1016,23 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 					.unwrap_or(locales.get(0).unwrap())
 					.locale
 			};
-			let ignore_font_weight = ignore_font_weight_cb.is_active();
+			let font_weight_mode = FONT_WEIGHT_MODES[font_weight_mode_dropdown.selected() as usize];
+			let font_axes = match (parse_font_axis(&font_weight_axis_entry),
+				parse_font_axis(&font_width_axis_entry)) {
+				(Ok(weight), Ok(width)) => FontAxes { weight, width },
+				_ => {
+					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-font-axes"), &dialog);
+					return;
+				}
+			};
 			let strip_empty_lines = strip_empty_lines_cb.is_active();
+			let search_fold_width = search_fold_width_cb.is_active();
+			let chapter_continuation = chapter_continuation_cb.is_active();
 			let scroll_for_page = scroll_for_page_cb.is_active();
+			let smooth_scroll = smooth_scroll_cb.is_active();
+			let eink = eink_cb.is_active();
+			let restore_session = restore_session_cb.is_active();
+			let watch_file = watch_file_cb.is_active();
 			let fonts = collect_path_list(&font_list, |path|
 				path.exists() && path.is_file());
 			let dictionaries = collect_path_list(&dict_list, |path|
@@ -423,9 +1043,88 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				&SIDEBAR_POSITIONS[idx as usize]
 			};
 			let select_by_dictionary = select_by_dictionary_cb.is_active();
+			let dict_docked = dict_docked_cb.is_active();
+			let auto_copy = auto_copy_cb.is_active();
+			let watch_clipboard = watch_clipboard_cb.is_active();
+			let flashcard_target = FlashcardTarget::ALL[flashcard_target_dropdown.selected() as usize];
+			let flashcard_deck = flashcard_deck_entry.text().trim().to_owned();
+			if flashcard_target == FlashcardTarget::AnkiConnect && flashcard_deck.is_empty() {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-flashcard-deck"), &dialog);
+				return;
+			}
+			let reading_goal = match goal_amount_entry.text().trim().parse::<u32>() {
+				Ok(amount) if amount <= MAX_READING_GOAL => ReadingGoal {
+					unit: GoalUnit::ALL[goal_unit_dropdown.selected() as usize],
+					amount,
+				},
+				_ => {
+					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-reading-goal"), &dialog);
+					return;
+				}
+			};
+			let night_light = match (
+				temperature_entry.text().trim().parse::<u16>(),
+				night_start_entry.text().trim().parse::<u8>(),
+				night_end_entry.text().trim().parse::<u8>()) {
+				(Ok(temperature), Ok(start_hour), Ok(end_hour))
+				if temperature >= MIN_COLOR_TEMPERATURE && temperature <= MAX_COLOR_TEMPERATURE
+					&& start_hour < 24 && end_hour < 24 => NightLight {
+					enabled: night_light_cb.is_active(),
+					scheduled: night_scheduled_cb.is_active(),
+					temperature,
+					start_hour,
+					end_hour,
+				},
+				_ => {
+					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-night-light"), &dialog);
+					return;
+				}
+			};
+			let Some(page_turn_millis) = parse_percent(&page_turn_millis_entry, 0, MAX_PAGE_TURN_MILLIS) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-page-turn-duration"), &dialog);
+				return;
+			};
+			let page_turn = PageTurn::ALL[page_turn_dropdown.selected() as usize];
+			let Some(wheel_lines) = parse_percent(&wheel_lines_entry, 1, MAX_WHEEL_LINES) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-wheel-lines"), &dialog);
+				return;
+			};
+			let Some(history_size) = parse_percent(&history_size_entry, 1, MAX_HISTORY_SIZE) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-history-size"), &dialog);
+				return;
+			};
+			let Some(paper_opacity) = parse_percent(&paper_opacity_entry, 0, 100) else {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-paper-opacity"), &dialog);
+				return;
+			};
+			let paper_path = paper_entry.text().trim().to_owned();
+			let paper = Paper {
+				path: if paper_path.is_empty() { None } else { Some(PathBuf::from(paper_path)) },
+				mode: PaperMode::ALL[paper_mode_dropdown.selected() as usize],
+				opacity: paper_opacity as u8,
+			};
+			let link_keys = LinkKeys {
+				next: next_link_key_entry.text().trim().to_owned(),
+				prev: prev_link_key_entry.text().trim().to_owned(),
+			};
+			if gtk4::accelerator_parse(&link_keys.next).is_none()
+				|| gtk4::accelerator_parse(&link_keys.prev).is_none() {
+				alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-link-keys"), &dialog);
+				return;
+			}
+			let toolbar = collect_toolbar_items(&toolbar_list, &toolbar_hidden.borrow());
+			let status_widgets = status_widget_cbs
+				.iter()
+				.filter_map(|(widget, cb)| if cb.is_active() { Some(*widget) } else { None })
+				.collect();
 
-			let new_fonts = if paths_modified(&cfg.borrow().gui.fonts, &fonts) {
-				let new_fonts = match font::user_fonts(&fonts) {
+			let fonts_modified = {
+				let configuration = cfg.borrow();
+				paths_modified(&configuration.gui.fonts, &fonts)
+					|| configuration.gui.font_axes != font_axes
+			};
+			let new_fonts = if fonts_modified {
+				let new_fonts = match font::user_fonts(&fonts, &font_axes) {
 					Ok(fonts) => fonts,
 					Err(err) => {
 						let title = i18n.msg("font-files");
@@ -451,16 +1150,45 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 			let color_matched_background = Color32::from(color_matched_background_btn.rgba());
 			let params = SettingsParam {
 				render_han,
+				search_fold_width,
+				chapter_continuation,
 				locale,
 				fonts,
 				dictionaries,
 				cache_dict,
-				ignore_font_weight,
+				font_weight_mode,
+				font_axes,
 				strip_empty_lines,
 				scroll_for_page,
+				wheel_lines: wheel_lines as u8,
+				smooth_scroll,
+				eink,
+				restore_session,
+				watch_file,
+				history_size: history_size as usize,
 				default_font_size,
+				letter_spacing,
+				word_spacing,
+				text_scale,
+				ui_scale,
+				accessibility_preset,
+				focus_mode,
+				focus_dim: focus_dim as u8,
 				sidebar_position,
+				dict_docked,
+				toolbar,
+				status_widgets,
 				select_by_dictionary,
+				auto_copy,
+				watch_clipboard,
+				flashcard_target,
+				flashcard_deck,
+				reading_goal,
+				night_light,
+				paper,
+				page_turn,
+				page_turn_millis,
+				link_keys,
 				color_color,
 				color_background,
 				color_highlight,
@@ -555,8 +1283,10 @@ fn check_and_add(path: &PathBuf, list: &ListStore)
 	}
 }
 
-fn create_list(title: &str, paths: &Vec<PathConfig>, i18n: &Rc<I18n>,
-	icons: &Rc<IconMap>) -> (gtk4::Box, ScrolledWindow, ListStore, Button)
+/// rows of font list show preview of the font, and can be reordered by dragging
+fn create_list(title: &str, paths: &Vec<PathConfig>, font_list: bool,
+	i18n: &Rc<I18n>, icons: &Rc<IconMap>)
+	-> (gtk4::Box, ScrolledWindow, ListStore, Button)
 {
 	let model = ListStore::new::<PathConfigEntry>();
 	for config in paths {
@@ -573,6 +1303,7 @@ fn create_list(title: &str, paths: &Vec<PathConfig>, i18n: &Rc<I18n>,
 		list.bind_model(Some(&model), move |obj| {
 			gtk4::Widget::from(create_list_row(
 				obj,
+				font_list,
 				&i18n,
 				&icons,
 				&model_to_remove,
@@ -597,8 +1328,8 @@ fn create_list(title: &str, paths: &Vec<PathConfig>, i18n: &Rc<I18n>,
 	(label_box, view, model, list_add_btn)
 }
 
-fn create_list_row(obj: &Object, i18n: &I18n, icons: &IconMap, list: &ListStore)
-	-> ListBoxRow
+fn create_list_row(obj: &Object, font_list: bool, i18n: &I18n, icons: &IconMap,
+	list: &ListStore) -> ListBoxRow
 {
 	let entry = obj.downcast_ref::<PathConfigEntry>()
 		.expect("Needs to be PathConfigEntry");
@@ -606,9 +1337,20 @@ fn create_list_row(obj: &Object, i18n: &I18n, icons: &IconMap, list: &ListStore)
 	let remove_btn = create_button("remove.svg", Some(&i18n.msg("remove-title")), icons, true);
 	let entry_box = gtk4::Box::new(Orientation::Horizontal, 10);
 	entry_box.append(&remove_btn);
-	let checkbox = append_checkbox(&path_str(&config.path), config.enabled, &entry_box);
+	let checkbox = if font_list {
+		let font_box = gtk4::Box::new(Orientation::Vertical, 2);
+		let checkbox = append_checkbox(&path_str(&config.path), config.enabled, &font_box);
+		append_font_preview(&config.path, &font_box);
+		entry_box.append(&font_box);
+		checkbox
+	} else {
+		append_checkbox(&path_str(&config.path), config.enabled, &entry_box)
+	};
 	let row = ListBoxRow::new();
 	row.set_child(Some(&entry_box));
+	if font_list {
+		setup_row_reorder(&row, list);
+	}
 
 	{
 		let entry = entry.clone();
@@ -629,6 +1371,170 @@ fn create_list_row(obj: &Object, i18n: &I18n, icons: &IconMap, list: &ListStore)
 	row
 }
 
+fn append_font_preview(path: &PathBuf, font_box: &gtk4::Box)
+{
+	let family_label = Label::builder()
+		.halign(Align::Start)
+		.css_classes(vec!["dim-label"])
+		.build();
+	let picture = Picture::builder()
+		.halign(Align::Start)
+		.can_shrink(false)
+		.build();
+	{
+		let path = path.clone();
+		let family_label = family_label.clone();
+		// render with the text color of current theme
+		picture.connect_realize(move |picture| {
+			let color = picture.color();
+			let color = (
+				(color.red() * 255.) as u8,
+				(color.green() * 255.) as u8,
+				(color.blue() * 255.) as u8);
+			if let Some(preview) = font::font_preview(&path, FONT_PREVIEW_TEXT, FONT_PREVIEW_SIZE, color) {
+				family_label.set_label(&preview.family);
+				let bytes = Bytes::from_owned(preview.pixels);
+				let texture = MemoryTexture::new(
+					preview.width as i32,
+					preview.height as i32,
+					MemoryFormat::R8g8b8a8,
+					&bytes,
+					preview.width * 4);
+				picture.set_paintable(Some(&texture));
+			}
+		});
+	}
+	font_box.append(&family_label);
+	font_box.append(&picture);
+}
+
+/// shown toolbar items in order, with hidden ones at the end
+fn create_toolbar_list(items: &Vec<ToolbarItem>, settings: &gtk4::Box,
+	i18n: &Rc<I18n>) -> (ListStore, Rc<RefCell<Vec<ToolbarItem>>>)
+{
+	let model = ListStore::new::<StringObject>();
+	let mut hidden = vec![];
+	for item in items {
+		model.append(&StringObject::new(item.i18n_key()));
+	}
+	for item in ToolbarItem::ALL {
+		if !items.contains(&item) {
+			model.append(&StringObject::new(item.i18n_key()));
+			hidden.push(item);
+		}
+	}
+	let hidden = Rc::new(RefCell::new(hidden));
+
+	let list = ListBox::builder()
+		.selection_mode(SelectionMode::None)
+		.build();
+	{
+		let i18n = i18n.clone();
+		let hidden = hidden.clone();
+		let model_to_reorder = model.clone();
+		list.bind_model(Some(&model), move |obj| {
+			let key = obj.downcast_ref::<StringObject>()
+				.expect("Needs to be StringObject")
+				.string();
+			let item = toolbar_item(&key).expect("Needs to be toolbar item");
+			let shown = !hidden.borrow().contains(&item);
+			let entry_box = gtk4::Box::new(Orientation::Horizontal, 10);
+			let checkbox = append_checkbox(&i18n.msg(&key), shown, &entry_box);
+			// keep menu for reaching settings
+			if item == ToolbarItem::Menu {
+				checkbox.set_active(true);
+				checkbox.set_sensitive(false);
+			}
+			{
+				let hidden = hidden.clone();
+				checkbox.connect_toggled(move |cb| {
+					let mut hidden = hidden.borrow_mut();
+					hidden.retain(|hidden_item| *hidden_item != item);
+					if !cb.is_active() {
+						hidden.push(item);
+					}
+				});
+			}
+			let row = ListBoxRow::new();
+			row.set_child(Some(&entry_box));
+			setup_row_reorder(&row, &model_to_reorder);
+			gtk4::Widget::from(row)
+		});
+	}
+	let label = title_label(&i18n.msg("toolbar-items"));
+	label.set_margin_top(10);
+	append_group(&label, &ScrolledWindow::builder()
+		.child(&list)
+		.has_frame(true)
+		.propagate_natural_height(true)
+		.build(), settings);
+	(model, hidden)
+}
+
+#[inline]
+fn toolbar_item(key: &str) -> Option<ToolbarItem>
+{
+	ToolbarItem::ALL
+		.into_iter()
+		.find(|item| item.i18n_key() == key)
+}
+
+fn collect_toolbar_items(list: &ListStore, hidden: &Vec<ToolbarItem>) -> Vec<ToolbarItem>
+{
+	let mut items = vec![];
+	for i in 0..list.n_items() {
+		if let Some(obj) = list.item(i) {
+			let key = obj.downcast_ref::<StringObject>()
+				.expect("Needs to be StringObject")
+				.string();
+			if let Some(item) = toolbar_item(&key) {
+				if !hidden.contains(&item) {
+					items.push(item);
+				}
+			}
+		}
+	}
+	items
+}
+
+/// drag row to change order of the list
+fn setup_row_reorder(row: &ListBoxRow, list: &ListStore)
+{
+	let drag_source = DragSource::builder()
+		.actions(DragAction::MOVE)
+		.build();
+	{
+		let row = row.clone();
+		drag_source.connect_prepare(move |_, _, _| {
+			Some(ContentProvider::for_value(&row.index().to_value()))
+		});
+	}
+	row.add_controller(drag_source);
+
+	let drop_target = DropTarget::new(i32::static_type(), DragAction::MOVE);
+	{
+		let row = row.clone();
+		let list = list.clone();
+		drop_target.connect_drop(move |_, value, _, _| {
+			let Ok(from) = value.get::<i32>() else {
+				return false;
+			};
+			let to = row.index();
+			if from < 0 || to < 0 || from == to {
+				return false;
+			}
+			if let Some(item) = list.item(from as u32) {
+				list.remove(from as u32);
+				list.insert(to as u32, &item);
+				true
+			} else {
+				false
+			}
+		});
+	}
+	row.add_controller(drop_target);
+}
+
 fn collect_path_list<F>(list: &ListStore, validator: F) -> Vec<PathConfig>
 	where F: Fn(&PathBuf) -> bool
 {
@@ -646,6 +1552,30 @@ fn collect_path_list<F>(list: &ListStore, validator: F) -> Vec<PathConfig>
 	vec
 }
 
+fn parse_percent(entry: &Entry, min: u16, max: u16) -> Option<u16>
+{
+	let value = entry.text().trim().parse::<u16>().ok()?;
+	if value >= min && value <= max {
+		Some(value)
+	} else {
+		None
+	}
+}
+
+/// empty for not set
+fn parse_font_axis(entry: &Entry) -> Result<Option<u16>, ()>
+{
+	let text = entry.text();
+	let text = text.trim();
+	if text.is_empty() {
+		return Ok(None);
+	}
+	match text.parse::<u16>() {
+		Ok(value) if value >= MIN_FONT_AXIS && value <= MAX_FONT_AXIS => Ok(Some(value)),
+		_ => Err(()),
+	}
+}
+
 fn paths_modified(orig: &Vec<PathConfig>, new: &Vec<PathConfig>) -> bool
 {
 	if new.len() != orig.len() {
@@ -667,7 +1597,8 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 	let gui_contexts = gcs.borrow();
 	let mut configuration = gc.cfg_mut();
 
-	// need restart
+	// labels set when widgets created, so windows rebuilt
+	let locale_changed = configuration.gui.lang != params.locale;
 	configuration.gui.lang = params.locale.to_owned();
 
 	let mut redraw = false;
@@ -679,14 +1610,86 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 		false
 	};
 
+	if configuration.chapter_continuation != params.chapter_continuation {
+		configuration.chapter_continuation = params.chapter_continuation;
+		for gc in gui_contexts.iter() {
+			gc.ctrl_mut().chapter_continuation = params.chapter_continuation;
+		}
+	}
+	if configuration.search_fold_width != params.search_fold_width {
+		configuration.search_fold_width = params.search_fold_width;
+		for gc in gui_contexts.iter() {
+			gc.ctrl_mut().search_fold_width = params.search_fold_width;
+			gc.find_list.set_fold_width(params.search_fold_width);
+		}
+	}
 	configuration.gui.scroll_for_page = params.scroll_for_page;
+	configuration.gui.wheel_lines = params.wheel_lines;
+	if configuration.gui.smooth_scroll != params.smooth_scroll {
+		configuration.gui.smooth_scroll = params.smooth_scroll;
+		for gc in gui_contexts.iter() {
+			gc.ctrl().render.set_smooth_scroll(params.smooth_scroll);
+		}
+	}
+	configuration.gui.restore_session = params.restore_session;
+	configuration.history_size = params.history_size;
+	if configuration.gui.watch_file != params.watch_file {
+		configuration.gui.watch_file = params.watch_file;
+		for gc in gui_contexts.iter() {
+			watch_file(gc, params.watch_file);
+		}
+	}
 	configuration.gui.default_font_size = params.default_font_size;
 	configuration.gui.select_by_dictionary = params.select_by_dictionary;
+	configuration.gui.watch_clipboard = params.watch_clipboard;
+	configuration.gui.flashcard.target = params.flashcard_target;
+	if !params.flashcard_deck.is_empty() {
+		configuration.gui.flashcard.deck = params.flashcard_deck.clone();
+	}
+	if configuration.gui.auto_copy != params.auto_copy {
+		configuration.gui.auto_copy = params.auto_copy;
+		for gc in gui_contexts.iter() {
+			gc.dm_mut().set_auto_copy(params.auto_copy);
+		}
+	}
+	configuration.gui.focus_mode = params.focus_mode;
+	configuration.gui.focus_dim = params.focus_dim;
+	configuration.gui.link_keys = params.link_keys.clone();
 
-	if configuration.gui.ignore_font_weight != params.ignore_font_weight {
-		configuration.gui.ignore_font_weight = params.ignore_font_weight;
+	let font_options_changed = if configuration.gui.font_weight_mode != params.font_weight_mode
+		|| configuration.gui.font_axes != params.font_axes {
+		configuration.gui.font_weight_mode = params.font_weight_mode;
+		configuration.gui.font_axes = params.font_axes.clone();
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	let preset_changed = if configuration.gui.accessibility_preset != params.accessibility_preset {
+		configuration.gui.accessibility_preset = params.accessibility_preset;
+		for gc in gui_contexts.iter() {
+			set_accessibility_preset(gc, params.accessibility_preset);
+		}
+		true
+	} else {
+		false
+	};
+	let spacing_changed = if preset_changed
+		|| configuration.gui.letter_spacing != params.letter_spacing
+		|| configuration.gui.word_spacing != params.word_spacing
+		|| configuration.gui.text_scale != params.text_scale {
+		configuration.gui.letter_spacing = params.letter_spacing;
+		configuration.gui.word_spacing = params.word_spacing;
+		configuration.gui.text_scale = params.text_scale;
 		redraw = true;
+		true
+	} else {
+		false
 	};
+	if configuration.gui.ui_scale != params.ui_scale {
+		configuration.gui.ui_scale = params.ui_scale;
+		set_ui_scale(params.ui_scale);
+	}
 	if configuration.gui.strip_empty_lines != params.strip_empty_lines {
 		configuration.gui.strip_empty_lines = params.strip_empty_lines;
 		redraw = true;
@@ -694,19 +1697,75 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 	if configuration.gui.sidebar_position != *params.sidebar_position {
 		configuration.gui.sidebar_position = params.sidebar_position.clone();
 		set_sidebar_position(gc, &configuration.gui.sidebar_position);
-		let position = gc.paned.position();
-		if position > 0 {
-			sidebar_updated(&mut configuration, &mut gc.dm_mut(), position);
+		let size = gc.sidebar_size();
+		if size > 0 {
+			sidebar_updated(&mut configuration, &mut gc.dm_mut(), size);
 			redraw = true;
 		}
 	}
 
+	if configuration.gui.dict_docked != params.dict_docked {
+		configuration.gui.dict_docked = params.dict_docked;
+		drop(configuration);
+		for gc in gui_contexts.iter() {
+			set_dict_docked(gc, params.dict_docked);
+		}
+		configuration = gc.cfg_mut();
+	}
+	if configuration.gui.toolbar != params.toolbar {
+		configuration.gui.toolbar = params.toolbar.clone();
+		for gc in gui_contexts.iter() {
+			layout_toolbar(gc, &configuration.gui.toolbar);
+		}
+	}
+	if configuration.gui.status_widgets != params.status_widgets
+		|| configuration.gui.reading_goal != params.reading_goal {
+		configuration.gui.status_widgets = params.status_widgets.clone();
+		configuration.gui.reading_goal = params.reading_goal.clone();
+		for gc in gui_contexts.iter() {
+			status::schedule_refresh(gc);
+		}
+	}
+
 	if new_fonts.is_some() {
 		redraw = true;
 	}
 
-	let colors_changed = apply_colors(&mut configuration, &params, gc);
+	let eink_changed = if configuration.gui.eink != params.eink {
+		configuration.gui.eink = params.eink;
+		for gc in gui_contexts.iter() {
+			set_eink(gc, params.eink);
+		}
+		true
+	} else {
+		false
+	};
+	if configuration.gui.page_turn != params.page_turn
+		|| configuration.gui.page_turn_millis != params.page_turn_millis {
+		configuration.gui.page_turn = params.page_turn;
+		configuration.gui.page_turn_millis = params.page_turn_millis;
+		for gc in gui_contexts.iter() {
+			gc.ctrl().render.set_page_turn(params.page_turn, params.page_turn_millis);
+		}
+	}
+	if configuration.gui.paper != params.paper {
+		configuration.gui.paper = params.paper.clone();
+		for gc in gui_contexts.iter() {
+			set_paper(gc, &params.paper);
+		}
+	}
+	let night_light_changed = if configuration.gui.night_light != params.night_light {
+		configuration.gui.night_light = params.night_light.clone();
+		true
+	} else {
+		false
+	};
+	let colors_changed = apply_colors(&mut configuration, &params, gc)
+		|| eink_changed
+		|| preset_changed
+		|| night_light_changed;
 	if colors_changed {
+		view::update_css(&gc.css_provider, &configuration.render_colors());
 		redraw = true;
 	}
 
@@ -714,7 +1773,7 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 		|| configuration.gui.cache_dict != params.cache_dict {
 		configuration.gui.dictionaries = params.dictionaries;
 		configuration.gui.cache_dict = params.cache_dict;
-		gc.db.borrow_mut().reload(&configuration.gui.dictionaries, params.cache_dict);
+		load_dictionaries(&gc.db, &configuration, gcs);
 		true
 	} else {
 		false
@@ -738,20 +1797,120 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 			let mut render_context = gc.ctx_mut();
 			let mut controller = gc.ctrl_mut();
 			if reload_render {
-				controller.render.reload_render(configuration.render_han, &mut render_context);
+				controller.render.set_default_render_han(configuration.render_han);
+				let render_han = controller.reading.render_han.unwrap_or(configuration.render_han);
+				controller.render.reload_render(render_han, &mut render_context);
 			}
 			if set_fonts {
 				gc.dm_mut().set_fonts(fonts_data.clone());
 				controller.render.set_fonts(controller.book.custom_fonts(), fonts_data.clone(), &mut render_context);
 			}
 			if colors_changed {
-				render_context.colors = configuration.gui.curr_colors(configuration.dark_theme).clone();
+				render_context.colors = configuration.render_colors();
+			}
+			if eink_changed || preset_changed {
+				render_context.custom_color = controller.reading.custom_color
+					&& configuration.gui.book_colors_allowed();
 			}
-			render_context.ignore_font_weight = params.ignore_font_weight;
 			render_context.strip_empty_lines = params.strip_empty_lines;
+			if font_options_changed {
+				render_context.font_weight_mode = params.font_weight_mode;
+				render_context.font_axes = params.font_axes.clone();
+			}
+			if spacing_changed {
+				let (letter_spacing, word_spacing) = configuration.gui.text_spacing();
+				render_context.letter_spacing = letter_spacing;
+				render_context.word_spacing = word_spacing;
+				render_context.text_scale = params.text_scale;
+			}
+			if font_options_changed || spacing_changed {
+				controller.render.reload_fonts(controller.book.custom_fonts(), &mut render_context);
+			}
+			controller.redraw(&mut render_context);
+		}
+	}
+
+	// dim color follows background
+	let focus = focus_of(&configuration);
+	for gc in gui_contexts.iter() {
+		gc.ctrl().render.set_focus(focus.clone());
+	}
+	if locale_changed {
+		rebuild_windows(gc, gcs);
+	}
+}
+
+/// apply themes, fonts and keys edited in config file while running
+pub(super) fn reload_config(gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	let gui_contexts = gcs.borrow();
+	let Some(gc) = gui_contexts.first() else {
+		return;
+	};
+	let gui = match gc.cfg().read_gui() {
+		Ok(gui) => gui,
+		Err(err) => {
+			gc.toast_error(&gc.i18n.args_msg("config-reload-failed", vec![
+				("error", err.to_string()),
+			]));
+			return;
+		}
+	};
+	let mut configuration = gc.cfg_mut();
+	let keys_changed = if configuration.gui.link_keys != gui.link_keys {
+		configuration.gui.link_keys = gui.link_keys;
+		true
+	} else {
+		false
+	};
+	let colors_changed = if configuration.gui.themes != gui.themes {
+		configuration.gui.themes = gui.themes;
+		view::update_css(&gc.css_provider, &configuration.render_colors());
+		let colors = configuration.gui.curr_colors(configuration.dark_theme);
+		for gc in gui_contexts.iter() {
+			gc.history_list.set_matched_colors(
+				colors.matched_color.clone(),
+				colors.matched_background.clone());
+		}
+		true
+	} else {
+		false
+	};
+	let new_fonts = if paths_modified(&configuration.gui.fonts, &gui.fonts) {
+		match font::user_fonts(&gui.fonts, &configuration.gui.font_axes) {
+			Ok(fonts) => {
+				configuration.gui.fonts = gui.fonts;
+				Some(Rc::new(fonts))
+			}
+			Err(err) => {
+				gc.toast_error(&gc.i18n.args_msg("config-reload-failed", vec![
+					("error", err.to_string()),
+				]));
+				None
+			}
+		}
+	} else {
+		None
+	};
+	if !keys_changed && !colors_changed && new_fonts.is_none() {
+		return;
+	}
+	if colors_changed || new_fonts.is_some() {
+		for gc in gui_contexts.iter() {
+			let mut render_context = gc.ctx_mut();
+			let mut controller = gc.ctrl_mut();
+			if let Some(fonts_data) = &new_fonts {
+				gc.dm_mut().set_fonts(fonts_data.clone());
+				controller.render.set_fonts(controller.book.custom_fonts(), fonts_data.clone(), &mut render_context);
+			}
+			if colors_changed {
+				render_context.colors = configuration.render_colors();
+				controller.render.set_focus(focus_of(&configuration));
+			}
 			controller.redraw(&mut render_context);
 		}
 	}
+	gc.toast(&gc.i18n.msg("config-reloaded"));
 }
 
 #[inline]
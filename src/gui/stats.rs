@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gtk4::{DrawingArea, EventControllerKey, glib, Label, Orientation, Window};
+use gtk4::gdk::Key;
+use gtk4::glib::DateTime;
+use gtk4::prelude::{BoxExt, DrawingAreaExtManual, GtkWindowExt, WidgetExt};
+
+use crate::config::{Configuration, GoalUnit, ReadingGoal};
+use crate::gui::{GuiContext, MODIFIER_NONE, README_TEXT_FILENAME};
+
+// time of page turn after longer idle not counted as reading
+const IDLE_SECONDS: u64 = 300;
+const DAY_FORMAT: &str = "%F";
+const HEATMAP_WEEKS: i32 = 20;
+const CELL_SIZE: f64 = 14.;
+const CELL_GAP: f64 = 3.;
+
+/// record reading time since last page turn and the page turn of today
+pub(super) fn track(gc: &GuiContext)
+{
+	let now = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |now| now.as_secs());
+	let seconds = match gc.last_page_turn.replace(Some(now)) {
+		Some(last) if now >= last && now - last <= IDLE_SECONDS => now - last,
+		_ => 0,
+	};
+	let controller = gc.ctrl();
+	let filename = &controller.reading.filename;
+	if filename == README_TEXT_FILENAME {
+		return;
+	}
+	let Some(day) = today() else {
+		return;
+	};
+	if let Err(err) = gc.cfg().add_reading_stat(&day, filename, seconds, 1) {
+		gc.error(&err.to_string());
+	}
+}
+
+/// record toc entries of current page, for progress in chapter list
+pub(super) fn track_toc(gc: &GuiContext)
+{
+	let controller = gc.ctrl();
+	let filename = &controller.reading.filename;
+	if filename == README_TEXT_FILENAME || controller.book.toc_iterator().is_none() {
+		return;
+	}
+	let inner_book = controller.reading.inner_book;
+	let (first, last, last_finished) = controller.page_toc_range();
+	let configuration = gc.cfg();
+	let chapter_list = &gc.chapter_list;
+	if !chapter_list.progress_loaded(filename, inner_book) {
+		match configuration.toc_progress(filename, inner_book) {
+			Ok(progress) => chapter_list.set_progress(filename, inner_book, progress),
+			Err(err) => {
+				gc.error(&err.to_string());
+				return;
+			}
+		}
+	}
+	if let Err(err) = configuration.save_toc_progress(filename, inner_book,
+		first, last, last_finished) {
+		gc.error(&err.to_string());
+		return;
+	}
+	chapter_list.mark_progress(first, last, last_finished);
+}
+
+/// today's reading in goal unit, None if no goal set
+pub(super) fn goal_progress(gc: &GuiContext, configuration: &Configuration) -> Option<String>
+{
+	let goal = &configuration.gui.reading_goal;
+	if goal.amount == 0 {
+		return None;
+	}
+	let day = today()?;
+	let (seconds, pages) = configuration.day_reading_stat(&day).ok()?;
+	Some(progress_msg(gc, goal, seconds, pages))
+}
+
+#[inline]
+fn progress_msg(gc: &GuiContext, goal: &ReadingGoal, seconds: u64, pages: u64) -> String
+{
+	let key = match goal.unit {
+		GoalUnit::Minutes => "goal-progress-minutes",
+		GoalUnit::Pages => "goal-progress-pages",
+	};
+	gc.i18n.args_msg(key, vec![
+		("progress", gc.i18n.format_number(goal.progress(seconds, pages) as f64, 0)),
+		("goal", gc.i18n.format_number(goal.amount as f64, 0)),
+	])
+}
+
+#[inline]
+fn day_of(date: &DateTime) -> Option<String>
+{
+	Some(date.format(DAY_FORMAT).ok()?.to_string())
+}
+
+#[inline]
+fn today() -> Option<String>
+{
+	day_of(&DateTime::now_local().ok()?)
+}
+
+#[inline]
+fn next_day(day: &str) -> Option<String>
+{
+	let mut parts = day.split('-').map(|part| part.parse::<i32>().ok());
+	let (Some(Some(year)), Some(Some(month)), Some(Some(day))) = (parts.next(), parts.next(), parts.next()) else {
+		return None;
+	};
+	let date = DateTime::from_local(year, month, day, 0, 0, 0.).ok()?;
+	day_of(&date.add_days(1).ok()?)
+}
+
+/// streaks and heatmap of recent weeks
+pub(super) fn dialog(gc: &GuiContext)
+{
+	let configuration = gc.cfg();
+	let stats = match configuration.reading_stats() {
+		Ok(stats) => stats,
+		Err(err) => {
+			gc.error(&err.to_string());
+			return;
+		}
+	};
+	let goal = configuration.gui.reading_goal.clone();
+	drop(configuration);
+	let Ok(today) = DateTime::now_local() else {
+		return;
+	};
+	let days: HashMap<String, (u64, u64)> = stats
+		.iter()
+		.map(|(day, seconds, pages)| (day.clone(), (*seconds, *pages)))
+		.collect();
+	let reached = |date: &DateTime| day_of(date)
+		.and_then(|day| days.get(&day))
+		.map_or(false, |(seconds, pages)| goal.reached(*seconds, *pages));
+
+	// today not finished yet, so streak kept from yesterday
+	let mut current_streak = 0;
+	let mut date = if reached(&today) { Some(today.clone()) } else { today.add_days(-1).ok() };
+	while let Some(day) = date {
+		if !reached(&day) {
+			break;
+		}
+		current_streak += 1;
+		date = day.add_days(-1).ok();
+	}
+	let mut longest_streak = 0;
+	let mut streak = 0;
+	let mut previous: Option<&String> = None;
+	for (day, seconds, pages) in &stats {
+		if !goal.reached(*seconds, *pages) {
+			previous = None;
+			continue;
+		}
+		let continued = previous
+			.and_then(|previous| next_day(previous))
+			.map_or(false, |next| &next == day);
+		streak = if continued { streak + 1 } else { 1 };
+		longest_streak = longest_streak.max(streak);
+		previous = Some(day);
+	}
+
+	let i18n = &gc.i18n;
+	let main = gtk4::Box::new(Orientation::Vertical, 10);
+	main.set_margin_top(10);
+	main.set_margin_bottom(10);
+	main.set_margin_start(10);
+	main.set_margin_end(10);
+	let (seconds, pages) = day_of(&today)
+		.and_then(|day| days.get(&day).cloned())
+		.unwrap_or((0, 0));
+	let today_msg = if goal.amount == 0 {
+		i18n.args_msg("stats-today", vec![
+			("minutes", i18n.format_number((seconds / 60) as f64, 0)),
+			("pages", i18n.format_number(pages as f64, 0)),
+		])
+	} else {
+		progress_msg(gc, &goal, seconds, pages)
+	};
+	for msg in [
+		today_msg,
+		i18n.args_msg("stats-current-streak", vec![
+			("days", i18n.format_number(current_streak as f64, 0)),
+		]),
+		i18n.args_msg("stats-longest-streak", vec![
+			("days", i18n.format_number(longest_streak as f64, 0)),
+		]),
+		i18n.args_msg("stats-days-read", vec![
+			("days", i18n.format_number(stats.len() as f64, 0)),
+		]),
+	] {
+		main.append(&Label::builder()
+			.label(&msg)
+			.xalign(0.)
+			.build());
+	}
+	main.append(&heatmap(&today, days, &goal));
+
+	let dialog = Window::builder()
+		.title(i18n.msg("reading-statistics"))
+		.transient_for(&gc.window)
+		.resizable(false)
+		.child(&main)
+		.build();
+	let key_event = EventControllerKey::new();
+	{
+		let dialog = dialog.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if key == Key::Escape && modifier == MODIFIER_NONE {
+				dialog.close();
+				glib::Propagation::Stop
+			} else {
+				glib::Propagation::Proceed
+			}
+		});
+	}
+	dialog.add_controller(key_event);
+	dialog.present();
+}
+
+/// weeks in columns from monday, darker for more reading
+fn heatmap(today: &DateTime, days: HashMap<String, (u64, u64)>, goal: &ReadingGoal) -> DrawingArea
+{
+	let area = DrawingArea::builder()
+		.content_width((HEATMAP_WEEKS as f64 * (CELL_SIZE + CELL_GAP)) as i32)
+		.content_height((7. * (CELL_SIZE + CELL_GAP)) as i32)
+		.build();
+	// full intensity for goal reached, or the most read day
+	let full = if goal.amount == 0 {
+		days.values().map(|(seconds, pages)| goal.progress(*seconds, *pages)).max().unwrap_or(0)
+	} else {
+		goal.amount as u64
+	}.max(1);
+	let weekday = today.day_of_week() - 1;
+	let (Ok(start), Some(today)) = (
+		today.add_days(-(weekday + (HEATMAP_WEEKS - 1) * 7)),
+		day_of(today)) else {
+		return area;
+	};
+	let goal = goal.clone();
+	area.set_draw_func(move |_, cairo, _, _| {
+		for week in 0..HEATMAP_WEEKS {
+			for weekday in 0..7 {
+				let Some(day) = start.add_days(week * 7 + weekday).ok()
+					.and_then(|date| day_of(&date)) else {
+					continue;
+				};
+				// yyyy-mm-dd in order as string
+				if day > today {
+					continue;
+				}
+				let progress = days.get(&day)
+					.map_or(0, |(seconds, pages)| goal.progress(*seconds, *pages));
+				if progress == 0 {
+					cairo.set_source_rgba(0.5, 0.5, 0.5, 0.2);
+				} else {
+					let intensity = (progress as f64 / full as f64).min(1.);
+					cairo.set_source_rgba(0.13, 0.55, 0.25, 0.3 + 0.7 * intensity);
+				}
+				let x = week as f64 * (CELL_SIZE + CELL_GAP);
+				let y = weekday as f64 * (CELL_SIZE + CELL_GAP);
+				rounded_rectangle(cairo, x, y, CELL_SIZE, 2.);
+				if let Err(err) = cairo.fill() {
+					eprintln!("Failed cairo call: {}", err.to_string());
+				}
+			}
+		}
+	});
+	area
+}
+
+#[inline]
+fn rounded_rectangle(cairo: &gtk4::cairo::Context, x: f64, y: f64, size: f64, radius: f64)
+{
+	cairo.new_sub_path();
+	cairo.arc(x + size - radius, y + radius, radius, -PI / 2., 0.);
+	cairo.arc(x + size - radius, y + size - radius, radius, 0., PI / 2.);
+	cairo.arc(x + radius, y + size - radius, radius, PI / 2., PI);
+	cairo.arc(x + radius, y + radius, radius, PI, PI * 1.5);
+	cairo.close_path();
+}
@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use gtk4::{glib, Label, Orientation};
+use gtk4::glib::ControlFlow;
+use gtk4::prelude::{BoxExt, WidgetExt};
+
+use crate::book::Line;
+use crate::common::{is_cjk, Position};
+use crate::config::StatusWidget;
+use crate::gui::{GuiContext, stats};
+
+// for clock and battery
+const REFRESH_SECONDS: u32 = 15;
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+// pages averaged for chars per page
+const ESTIMATE_PAGES: f64 = 10.;
+
+/// status message with information widgets, each toggleable in settings
+pub(super) struct StatusWidgets {
+	container: gtk4::Box,
+	labels: Vec<(StatusWidget, Label)>,
+	estimate: RefCell<PageEstimate>,
+}
+
+/// chars per page averaged from pages shown, reset when layout changed
+#[derive(Default)]
+struct PageEstimate {
+	// (font size, text scale, view width, view height) of the estimate
+	layout: Option<(u8, u16, i32, i32)>,
+	// (chapter, line, offset) of last page counted
+	sampled: Option<(usize, usize, usize)>,
+	chars_per_page: f64,
+	pages: f64,
+}
+
+impl PageEstimate {
+	fn sample(&mut self, layout: (u8, u16, i32, i32), page: (usize, usize, usize), chars: usize)
+	{
+		if self.layout != Some(layout) {
+			*self = PageEstimate { layout: Some(layout), ..Default::default() };
+		}
+		if self.sampled == Some(page) || chars == 0 {
+			return;
+		}
+		self.sampled = Some(page);
+		self.pages = (self.pages + 1.).min(ESTIMATE_PAGES);
+		self.chars_per_page += (chars as f64 - self.chars_per_page) / self.pages;
+	}
+
+	/// current page and total pages of the chapter, from 1
+	fn pages(&self, before: usize, total: usize) -> Option<(usize, usize)>
+	{
+		if self.chars_per_page < 1. {
+			return None;
+		}
+		let page = (before as f64 / self.chars_per_page) as usize + 1;
+		let pages = (total as f64 / self.chars_per_page).ceil() as usize;
+		Some((page, pages.max(page)))
+	}
+}
+
+impl StatusWidgets {
+	pub(super) fn new(status_bar: &Label, enabled: &Vec<StatusWidget>) -> Self
+	{
+		let container = gtk4::Box::builder()
+			.orientation(Orientation::Horizontal)
+			.spacing(10)
+			.hexpand(true)
+			.build();
+		container.append(status_bar);
+		let labels = StatusWidget::ALL
+			.into_iter()
+			.map(|widget| {
+				let label = Label::builder()
+					.css_classes(vec!["dim-label"])
+					.visible(enabled.contains(&widget))
+					.build();
+				container.append(&label);
+				(widget, label)
+			})
+			.collect();
+		StatusWidgets { container, labels, estimate: RefCell::new(PageEstimate::default()) }
+	}
+
+	#[inline]
+	pub(super) fn container(&self) -> &gtk4::Box
+	{
+		&self.container
+	}
+}
+
+/// refresh widgets periodically, until the window closed
+pub(super) fn setup(gc: &GuiContext)
+{
+	let weak = Rc::downgrade(&gc.inner);
+	glib::timeout_add_seconds_local(REFRESH_SECONDS, move || {
+		match weak.upgrade() {
+			Some(inner) => {
+				refresh(&GuiContext { inner });
+				ControlFlow::Continue
+			}
+			None => ControlFlow::Break,
+		}
+	});
+	let gc2 = gc.clone();
+	gc.status_bar.connect_label_notify(move |_| schedule_refresh(&gc2));
+	refresh(gc);
+}
+
+/// controller or configuration may be borrowed when status updated, refresh later
+#[inline]
+pub(super) fn schedule_refresh(gc: &GuiContext)
+{
+	let gc = gc.clone();
+	glib::idle_add_local_once(move || refresh(&gc));
+}
+
+fn refresh(gc: &GuiContext)
+{
+	let (Ok(configuration), Ok(controller)) = (gc.cfg.try_borrow(), gc.ctrl.try_borrow()) else {
+		return;
+	};
+	let enabled = &configuration.gui.status_widgets;
+	let book = controller.book.as_ref();
+	let reading = &controller.reading;
+	for (widget, label) in &gc.status_widgets.labels {
+		if !enabled.contains(widget) {
+			label.set_visible(false);
+			continue;
+		}
+		let text = match widget {
+			StatusWidget::ChapterTitle => book
+				.title(reading.line, reading.position)
+				.map(|title| title.to_owned()),
+			StatusWidget::Progress => {
+				let lines = book.lines().len();
+				let chapter_progress = if lines == 0 {
+					0.
+				} else {
+					reading.line as f64 / lines as f64
+				};
+				let progress = (book.current_chapter() as f64 + chapter_progress)
+					/ book.chapter_count().max(1) as f64;
+				Some(format!("{}%", gc.i18n.format_number(progress * 100., 1)))
+			}
+			StatusWidget::Clock => glib::DateTime::now_local()
+				.ok()
+				.and_then(|now| now.format(&gc.i18n.msg("clock-format")).ok())
+				.map(|now| now.to_string()),
+			StatusWidget::Battery => battery_capacity()
+				.map(|capacity| gc.i18n.args_msg("status-battery-value", vec![
+					("capacity", capacity),
+				])),
+			StatusWidget::Words => {
+				let start = Position::new(reading.line, reading.position);
+				let words = count_words(book.lines(), &start,
+					controller.next_page_position());
+				Some(gc.i18n.args_msg("status-words-value", vec![
+					("count", gc.i18n.format_number(words as f64, 0)),
+				]))
+			}
+			StatusWidget::Goal => stats::goal_progress(gc, &configuration),
+			StatusWidget::Pages => {
+				let lines = book.lines();
+				let start = Position::new(reading.line, reading.position);
+				let mut estimate = gc.status_widgets.estimate.borrow_mut();
+				// last page of chapter usually not full
+				if let Some(next) = controller.next_page_position() {
+					let view = &controller.render;
+					let layout = (reading.font_size, configuration.gui.text_scale,
+						view.width(), view.height());
+					let page = (book.current_chapter(), reading.line, reading.position);
+					estimate.sample(layout, page, count_chars(lines, &start, next));
+				}
+				let before = count_chars(lines, &Position::new(0, 0), &start);
+				let total = lines.iter().map(|line| line.len()).sum();
+				estimate.pages(before, total)
+					.map(|(page, pages)| format!("{}/{}", page, pages))
+			}
+		};
+		match text {
+			Some(text) => {
+				label.set_text(&text);
+				label.set_visible(true);
+			}
+			None => label.set_visible(false),
+		}
+	}
+}
+
+/// capacity of first battery, laptop only
+fn battery_capacity() -> Option<u8>
+{
+	for entry in fs::read_dir(POWER_SUPPLY_DIR).ok()? {
+		let Ok(entry) = entry else {
+			continue;
+		};
+		let path = entry.path();
+		let is_battery = fs::read_to_string(path.join("type"))
+			.map_or(false, |kind| kind.trim() == "Battery");
+		if is_battery {
+			if let Ok(capacity) = fs::read_to_string(path.join("capacity")) {
+				if let Ok(capacity) = capacity.trim().parse() {
+					return Some(capacity);
+				}
+			}
+		}
+	}
+	None
+}
+
+/// chars from start to end, end exclusive
+fn count_chars(lines: &Vec<Line>, start: &Position, end: &Position) -> usize
+{
+	let mut count = 0;
+	for line_index in start.line..(end.line + 1).min(lines.len()) {
+		let len = lines[line_index].len();
+		let from = if line_index == start.line { start.offset.min(len) } else { 0 };
+		let to = if line_index == end.line { end.offset.min(len) } else { len };
+		count += to.saturating_sub(from);
+	}
+	count
+}
+
+/// each CJK char as a word, and latin words split by other chars
+fn count_words(lines: &Vec<Line>, start: &Position, end: Option<&Position>) -> usize
+{
+	let mut count = 0;
+	let end_line = end.map_or(lines.len(), |end| end.line + 1);
+	for line_index in start.line..end_line.min(lines.len()) {
+		let line = &lines[line_index];
+		let from = if line_index == start.line { start.offset } else { 0 };
+		let to = match end {
+			Some(end) if end.line == line_index => end.offset.min(line.len()),
+			_ => line.len(),
+		};
+		let mut in_word = false;
+		for char in line.iter().skip(from).take(to.saturating_sub(from)) {
+			if is_cjk(*char) {
+				count += 1;
+				in_word = false;
+			} else if char.is_alphanumeric() {
+				if !in_word {
+					count += 1;
+					in_word = true;
+				}
+			} else {
+				in_word = false;
+			}
+		}
+	}
+	count
+}
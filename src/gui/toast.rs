@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use gtk4::{Align, GestureClick, glib, Label, Orientation, Revealer, RevealerTransitionType};
+use gtk4::prelude::{BoxExt, GestureSingleExt, WidgetExt};
+
+// toasts shown at most, the oldest dropped
+const MAX_TOASTS: usize = 4;
+const INFO_MILLIS: u64 = 3000;
+const ERROR_MILLIS: u64 = 6000;
+const TRANSITION_MILLIS: u32 = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum ToastLevel {
+	Info,
+	Error,
+}
+
+/// messages stacked at the bottom of view, not replaced by each other
+/// like the status bar, expired after a while or dismissed by click
+pub(super) struct Toasts {
+	container: gtk4::Box,
+}
+
+impl Toasts {
+	pub fn new() -> Self
+	{
+		let container = gtk4::Box::builder()
+			.orientation(Orientation::Vertical)
+			.spacing(6)
+			.halign(Align::Center)
+			.valign(Align::End)
+			.margin_bottom(24)
+			.build();
+		Toasts { container }
+	}
+
+	#[inline]
+	pub fn widget(&self) -> &gtk4::Box
+	{
+		&self.container
+	}
+
+	pub fn show(&self, level: ToastLevel, msg: &str)
+	{
+		if msg.is_empty() {
+			return;
+		}
+		let container = &self.container;
+		let mut count = 0;
+		let mut child = container.first_child();
+		while let Some(widget) = child {
+			count += 1;
+			child = widget.next_sibling();
+		}
+		while count >= MAX_TOASTS {
+			if let Some(oldest) = container.first_child() {
+				container.remove(&oldest);
+			}
+			count -= 1;
+		}
+
+		let label = Label::builder()
+			.label(msg)
+			.wrap(true)
+			.max_width_chars(60)
+			.css_classes(vec!["toast"])
+			.build();
+		let millis = match level {
+			ToastLevel::Info => INFO_MILLIS,
+			ToastLevel::Error => {
+				label.add_css_class("error");
+				ERROR_MILLIS
+			}
+		};
+		let revealer = Revealer::builder()
+			.transition_type(RevealerTransitionType::SlideUp)
+			.transition_duration(TRANSITION_MILLIS)
+			.child(&label)
+			.build();
+		container.append(&revealer);
+		revealer.set_reveal_child(true);
+
+		let click = GestureClick::new();
+		click.set_button(gtk4::gdk::BUTTON_PRIMARY);
+		{
+			let container = container.clone();
+			let revealer = revealer.clone();
+			click.connect_released(move |_, _, _, _| dismiss(&container, &revealer));
+		}
+		label.add_controller(click);
+		let container = container.clone();
+		glib::timeout_add_local_once(Duration::from_millis(millis),
+			move || dismiss(&container, &revealer));
+	}
+}
+
+/// hide with transition then remove, if not removed already
+fn dismiss(container: &gtk4::Box, revealer: &Revealer)
+{
+	if revealer.parent().is_none() || !revealer.reveals_child() {
+		return;
+	}
+	revealer.set_reveal_child(false);
+	let container = container.clone();
+	let revealer = revealer.clone();
+	glib::timeout_add_local_once(Duration::from_millis(TRANSITION_MILLIS as u64), move || {
+		if revealer.parent().is_some() {
+			container.remove(&revealer);
+		}
+	});
+}
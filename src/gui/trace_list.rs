@@ -0,0 +1,143 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anyhow::Result;
+use gtk4::{Align, Label, ListBox, ListBoxRow, Orientation, PolicyType, SelectionMode};
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::{BoxExt, ListBoxRowExt, WidgetExt};
+
+use crate::common::TraceInfo;
+use crate::controller::TraceEntry;
+use crate::gui::GuiController;
+use crate::i18n::I18n;
+
+struct TraceListInner {
+	list: ListBox,
+	ctrl: Rc<RefCell<GuiController>>,
+	// trace of listed entries
+	loaded: RefCell<Vec<TraceInfo>>,
+	syncing: Cell<bool>,
+	index_clicked: RefCell<Option<Rc<dyn Fn(usize)>>>,
+	i18n: Rc<I18n>,
+}
+
+/// navigation trace of reading book, for jumping to any entry directly
+#[derive(Clone)]
+pub struct TraceList {
+	inner: Rc<TraceListInner>,
+}
+
+impl TraceList {
+	pub fn create(i18n: &Rc<I18n>, ctrl: &Rc<RefCell<GuiController>>) -> (Self, gtk4::Box)
+	{
+		let list = ListBox::builder()
+			.selection_mode(SelectionMode::Single)
+			.build();
+		list.add_css_class("navigation-sidebar");
+		list.add_css_class("boxed-list");
+		list.set_placeholder(Some(&Label::new(Some(&i18n.msg("no-trace")))));
+
+		let container = gtk4::Box::builder()
+			.orientation(Orientation::Vertical)
+			.spacing(0)
+			.vexpand(true)
+			.build();
+		container.append(&gtk4::ScrolledWindow::builder()
+			.child(&list)
+			.hscrollbar_policy(PolicyType::Never)
+			.vexpand(true)
+			.build());
+
+		let trace_list = TraceList {
+			inner: Rc::new(TraceListInner {
+				list,
+				ctrl: ctrl.clone(),
+				loaded: RefCell::new(vec![]),
+				syncing: Cell::new(false),
+				index_clicked: RefCell::new(None),
+				i18n: i18n.clone(),
+			})
+		};
+		{
+			let trace_list2 = trace_list.clone();
+			trace_list.inner.list.connect_row_selected(move |_, row| {
+				if trace_list2.inner.syncing.get() {
+					return;
+				}
+				if let Some(row) = row {
+					let row_index = row.index();
+					if row_index >= 0 {
+						let callback = trace_list2.inner.index_clicked.borrow().clone();
+						if let Some(callback) = callback {
+							callback(row_index as usize);
+						}
+					}
+				}
+			});
+		}
+		(trace_list, container)
+	}
+
+	/// callback with the index of trace entry to goto
+	pub fn handle_index_click<F>(&self, index_clicked: F)
+		where F: Fn(usize) + 'static
+	{
+		self.inner.index_clicked.replace(Some(Rc::new(index_clicked)));
+	}
+
+	/// reload entries if trace changed, with current one selected
+	pub fn sync_trace(&self) -> Result<()>
+	{
+		let mut controller = self.inner.ctrl.borrow_mut();
+		let (trace, current) = controller.trace();
+		let entries = if *self.inner.loaded.borrow() == trace {
+			None
+		} else {
+			self.inner.loaded.replace(trace.to_vec());
+			Some(controller.trace_entries()?)
+		};
+		drop(controller);
+		let list = &self.inner.list;
+		self.inner.syncing.replace(true);
+		if let Some(entries) = entries {
+			list.remove_all();
+			for entry in &entries {
+				list.append(&self.create_row(entry));
+			}
+		}
+		if let Some(row) = list.row_at_index(current as i32) {
+			list.select_row(Some(&row));
+		}
+		self.inner.syncing.replace(false);
+		Ok(())
+	}
+
+	fn create_row(&self, entry: &TraceEntry) -> ListBoxRow
+	{
+		let title = match &entry.title {
+			Some(title) => title.clone(),
+			None => self.inner.i18n.args_msg("trace-chapter", vec![
+				("chapter", entry.chapter + 1),
+			]),
+		};
+		let title = Label::builder()
+			.label(&title)
+			.halign(Align::Start)
+			.xalign(0.)
+			.ellipsize(EllipsizeMode::End)
+			.build();
+		let snippet = Label::builder()
+			.label(&entry.snippet)
+			.halign(Align::Start)
+			.xalign(0.)
+			.ellipsize(EllipsizeMode::End)
+			.css_classes(vec!["dim-label"])
+			.build();
+		let view = gtk4::Box::new(Orientation::Vertical, 2);
+		view.append(&title);
+		view.append(&snippet);
+		let row = ListBoxRow::new();
+		row.set_child(Some(&view));
+		row
+	}
+}
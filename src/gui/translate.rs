@@ -0,0 +1,125 @@
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+
+use crate::config::{TranslateBackend, TranslateConfig};
+use crate::gui::{dialogs, GuiContext, http};
+
+/// translate text with configured backend, show result in dialog
+pub(super) fn translate(gc: &GuiContext, text: String)
+{
+	let configuration = gc.cfg();
+	let translate = &configuration.gui.translate;
+	let target_lang = translate.target_lang
+		.clone()
+		.unwrap_or_else(|| ui_lang(&configuration.gui.lang));
+	let (url, headers, body) = request(translate, &text, &target_lang);
+	let backend = translate.backend;
+	drop(configuration);
+
+	let result = {
+		let gc = gc.clone();
+		http::post_json(&url, &headers, &body, move |response| {
+			let translation = response.and_then(|response|
+				parse_response(backend, &response).map_err(|err| err.to_string()));
+			match translation {
+				Ok(translation) => {
+					gc.message("");
+					let title = gc.i18n.msg("translate-dialog-title");
+					dialogs::translation(&text, &translation, &title, &gc.window);
+				}
+				Err(err) => failed(&gc, &err),
+			}
+		})
+	};
+	match result {
+		Ok(()) => gc.message(&gc.i18n.msg("translating")),
+		Err(err) => failed(gc, &err.to_string()),
+	}
+}
+
+#[inline]
+fn failed(gc: &GuiContext, err: &str)
+{
+	gc.message("");
+	gc.toast_error(&gc.i18n.args_msg("translate-failed", vec![
+		("error", err.trim()),
+	]));
+}
+
+/// the language part of locale, en_US -> en
+#[inline]
+fn ui_lang(locale: &str) -> String
+{
+	locale.split(['_', '-']).next().unwrap_or(locale).to_owned()
+}
+
+/// url, headers and json body of the backend
+fn request(translate: &TranslateConfig, text: &str, target_lang: &str)
+	-> (String, Vec<String>, Value)
+{
+	let backend = translate.backend;
+	let endpoint = translate.endpoint
+		.as_deref()
+		.unwrap_or_else(|| backend.default_endpoint());
+	let api_key = translate.api_key.as_deref().unwrap_or("");
+	match backend {
+		TranslateBackend::Deepl => (
+			endpoint.to_owned(),
+			vec![format!("Authorization: DeepL-Auth-Key {}", api_key)],
+			json!({
+				"text": [text],
+				"target_lang": target_lang.to_uppercase(),
+			}),
+		),
+		TranslateBackend::Google => (
+			format!("{}?key={}", endpoint, api_key),
+			vec![],
+			json!({
+				"q": text,
+				"target": target_lang,
+				"format": "text",
+			}),
+		),
+		TranslateBackend::LibreTranslate => (
+			endpoint.to_owned(),
+			vec![],
+			json!({
+				"q": text,
+				"source": "auto",
+				"target": target_lang,
+				"format": "text",
+				"api_key": api_key,
+			}),
+		),
+	}
+}
+
+fn parse_response(backend: TranslateBackend, response: &str) -> Result<String>
+{
+	let value: Value = serde_json::from_str(response)?;
+	let translations = match backend {
+		TranslateBackend::Deepl => value["translations"].as_array()
+			.map(|translations| translations
+				.iter()
+				.filter_map(|translation| translation["text"].as_str())
+				.collect::<Vec<_>>()),
+		TranslateBackend::Google => value["data"]["translations"].as_array()
+			.map(|translations| translations
+				.iter()
+				.filter_map(|translation| translation["translatedText"].as_str())
+				.collect::<Vec<_>>()),
+		TranslateBackend::LibreTranslate => value["translatedText"].as_str()
+			.map(|translation| vec![translation]),
+	};
+	match translations {
+		Some(translations) if !translations.is_empty() => Ok(translations.join("\n")),
+		_ => {
+			let error = &value["error"];
+			if let Some(message) = error.as_str().or_else(|| error["message"].as_str())
+				.or_else(|| value["message"].as_str()) {
+				bail!("{}", message)
+			}
+			Err(anyhow!("{}", response))
+		}
+	}
+}
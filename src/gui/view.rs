@@ -1,24 +1,56 @@
+use std::cell::Cell;
+use std::cmp::min;
+use std::path::Path;
 use std::rc::Rc;
-
-use glib::Object;
-use gtk4::{CssProvider, EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, gdk, GestureClick, GestureDrag, glib};
-use gtk4::gdk::{Display, ModifierType};
+use std::time::Duration;
+
+use anyhow::Result;
+use glib::{ControlFlow, Object};
+use gtk4::{CssProvider, EventController, EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, gdk, GestureClick, GestureDrag, GestureLongPress, GestureSwipe, GestureZoom, glib};
+use gtk4::cairo::{Context as CairoContext, Format, ImageSurface};
+use gtk4::gdk::{Display, InputSource, MemoryFormat, MemoryTexture, ModifierType};
+use gtk4::gdk::prelude::TextureExt;
+use gtk4::gdk::prelude::DeviceExt;
 use gtk4::pango::Layout as PangoContext;
-use gtk4::prelude::{EventControllerExt, GestureDragExt, GestureExt, ObjectExt, WidgetExt};
+use gtk4::accessible::Property;
+use gtk4::prelude::{AccessibleExtManual, EventControllerExt, GestureDragExt, GestureExt, IsA, ObjectExt, WidgetExt, WidgetExtManual};
 use gtk4::Scrollable;
 use gtk4::subclass::prelude::ObjectSubclassIsExt;
 
 use crate::book::{Book, Line};
-use crate::color::Colors;
+use crate::color::{Color32, Colors};
 use crate::common::Position;
-use crate::config::ReadingInfo;
-use crate::controller::{HighlightInfo, Render};
+use crate::config::{FocusMode, PageTurn, PaperMode, ReadingInfo};
+use crate::controller::{HighlightInfo, HighlightMode, Render};
 use crate::gui::font::UserFonts;
 use crate::gui::HtmlFonts;
-use crate::gui::math::{Pos2, pos2};
-use crate::gui::render::RenderContext;
+use crate::gui::math::{Pos2, pos2, Rect};
+use crate::gui::render::{RenderCell, RenderContext};
 
 const MIN_TEXT_SELECT_DISTANCE: f32 = 4.0;
+// keep the page dark for e-ink screen refresh
+const EINK_FLASH_MILLIS: u64 = 150;
+// pixels per second
+const MIN_SWIPE_VELOCITY: f64 = 500.;
+const MIN_ZOOM_DELTA: f64 = 0.1;
+const SELECTION_HANDLE_RADIUS: f32 = 8.;
+// scrolling from the position of last page, eased out
+const SMOOTH_SCROLL_MILLIS: u64 = 150;
+// trackpad pixels for a wheel tick
+const SURFACE_SCROLL_STEP: f64 = 24.;
+// momentum of flicks slowed down by the rate every second, stopped when too slow
+const MOMENTUM_FRICTION: f64 = 4.;
+const MIN_MOMENTUM: f64 = 2.;
+// outline of the link focused by keyboard
+const LINK_FOCUS_WIDTH: f64 = 2.;
+const LINK_FOCUS_PADDING: f32 = 2.;
+// width of shadow at the fold of page curl
+const PAGE_CURL_SHADOW_WIDTH: f64 = 24.;
+// pixel layout of cairo ARGB32 surface
+#[cfg(target_endian = "little")]
+const SURFACE_MEMORY_FORMAT: MemoryFormat = MemoryFormat::B8g8r8a8Premultiplied;
+#[cfg(target_endian = "big")]
+const SURFACE_MEMORY_FORMAT: MemoryFormat = MemoryFormat::A8r8g8b8Premultiplied;
 
 pub enum ScrollPosition {
 	LineNext,
@@ -41,7 +73,7 @@ pub enum ClickTarget {
 glib::wrapper! {
     pub struct GuiView(ObjectSubclass<imp::GuiView>)
         @extends gtk4::Widget, gtk4::DrawingArea,
-		@implements Scrollable
+		@implements gtk4::Accessible, Scrollable
 	;
 }
 
@@ -58,7 +90,30 @@ impl Render<RenderContext> for GuiView {
 		offset: usize, highlight: &Option<HighlightInfo>, context: &mut RenderContext)
 		-> Option<Position>
 	{
-		let next = self.imp().redraw(book, lines, line, offset, highlight, context, &self.get_pango());
+		let imp = self.imp();
+		let next = imp.redraw(book, lines, line, offset, highlight, context, &self.get_pango());
+		self.update_accessible(book, lines, line, offset, highlight, &next);
+		if imp.page_turning_started() {
+			self.add_tick_callback(|view, _| {
+				view.queue_draw();
+				let imp = view.imp();
+				// both checked, so finished ones dropped
+				let turning = imp.page_turning();
+				let scrolling = imp.scrolling();
+				if turning || scrolling {
+					ControlFlow::Continue
+				} else {
+					ControlFlow::Break
+				}
+			});
+		}
+		if imp.start_flash(line, offset) {
+			let view = self.clone();
+			glib::timeout_add_local_once(Duration::from_millis(EINK_FLASH_MILLIS), move || {
+				view.imp().stop_flash();
+				view.queue_draw();
+			});
+		}
 		self.queue_draw();
 		next
 	}
@@ -103,6 +158,10 @@ impl GuiView {
 	pub const SCROLL_SIGNAL: &'static str = "scroll";
 	pub const SELECT_WORD_SIGNAL: &'static str = "select-word";
 	pub const SHOW_TITLE_SIGNAL: &'static str = "title";
+	pub const PAGE_SIGNAL: &'static str = "page";
+	pub const CHAPTER_SIGNAL: &'static str = "chapter";
+	pub const ZOOM_SIGNAL: &'static str = "zoom";
+	pub const ZOOM_IMAGE_SIGNAL: &'static str = "zoom-image";
 
 	pub fn new(instance_name: &str, render_han: bool, book_fonts: Option<&HtmlFonts>,
 		user_fonts: Rc<Option<UserFonts>>, render_context: &mut RenderContext) -> Self
@@ -116,52 +175,108 @@ impl GuiView {
 
 		let imp = view.imp();
 		let pango = &view.get_pango();
+		imp.set_default_render_han(render_han);
 		imp.set_render_type(render_han, render_context);
 		imp.set_fonts(book_fonts, user_fonts, pango, render_context);
 		view
 	}
 
+	/// chapter title as label, and for description the selected text,
+	/// the focused link, or text of the page, so screen readers follow
+	/// page turns and selection
+	fn update_accessible(&self, book: &dyn Book, lines: &Vec<Line>, line: usize,
+		offset: usize, highlight: &Option<HighlightInfo>, next: &Option<Position>)
+	{
+		let heading = book.title(line, offset)
+			.or_else(|| book.name())
+			.unwrap_or_default();
+		let mut description = String::new();
+		match highlight {
+			Some(HighlightInfo { mode: HighlightMode::Selection(text, _), .. }) =>
+				description.push_str(text),
+			Some(HighlightInfo { mode: HighlightMode::Link(_), line, start, end }) =>
+				if let Some(line) = lines.get(*line) {
+					line.sub_str(&mut description, *start..min(*end, line.len()));
+				}
+			_ => {
+				let (end_line, end_offset) = match next {
+					Some(next) => (next.line, next.offset),
+					None => (lines.len(), 0),
+				};
+				for index in line..min(end_line + 1, lines.len()) {
+					let chars = &lines[index];
+					let from = if index == line { offset } else { 0 };
+					let to = if index == end_line { min(end_offset, chars.len()) } else { chars.len() };
+					for char_index in from..to {
+						if let Some(ch) = chars.char_at(char_index) {
+							description.push(ch);
+						}
+					}
+					description.push('\n');
+				}
+			}
+		}
+		self.update_property(&[
+			Property::Label(heading),
+			Property::Description(&description),
+		]);
+	}
+
 	pub fn setup_gesture(&self)
 	{
 		let drag_gesture = GestureDrag::builder()
 			.button(gdk::BUTTON_PRIMARY)
 			.build();
 		let view = self.clone();
+		drag_gesture.connect_begin(move |drag, _| {
+			let imp = view.imp();
+			let touch = is_touch(drag);
+			imp.set_touch_input(touch);
+			// touch drag only select text from a selection handle or after long press
+			let anchor = if touch {
+				drag.start_point()
+					.and_then(|(x, y)| imp.handle_anchor(pos2(x as f32, y as f32)))
+			} else {
+				None
+			};
+			imp.set_touch_anchor(anchor);
+		});
+		let view = self.clone();
 		drag_gesture.connect_update(move |drag, seq| {
-			if let Some(bp) = drag.start_point() {
-				if let Some(ep) = drag.point(seq) {
-					drag.set_state(gtk4::EventSequenceState::Claimed);
-					let from = pos2(bp.0 as f32, bp.1 as f32);
-					let to = pos2(ep.0 as f32, ep.1 as f32);
-					if let Some((from, to)) = view.calc_selection(from, to) {
-						view.emit_by_name::<()>(GuiView::SELECTING_TEXT_SIGNAL, &[
-							&(from.line as u64),
-							&(from.offset as u64),
-							&(to.line as u64),
-							&(to.offset as u64),
-						]);
-					} else {
-						view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]);
-					}
+			let Some(from) = drag_from(&view, drag) else {
+				return;
+			};
+			if let Some(ep) = drag.point(seq) {
+				drag.set_state(gtk4::EventSequenceState::Claimed);
+				let to = pos2(ep.0 as f32, ep.1 as f32);
+				if let Some((from, to)) = view.calc_selection(from, to) {
+					view.emit_by_name::<()>(GuiView::SELECTING_TEXT_SIGNAL, &[
+						&(from.line as u64),
+						&(from.offset as u64),
+						&(to.line as u64),
+						&(to.offset as u64),
+					]);
+				} else {
+					view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]);
 				}
 			}
 		});
 		let view = self.clone();
 		drag_gesture.connect_end(move |drag, seq| {
 			view.grab_focus();
-			if let Some(bp) = drag.start_point() {
-				if let Some(ep) = drag.point(seq) {
-					if bp != ep {
-						let from = pos2(bp.0 as f32, bp.1 as f32);
-						let to = pos2(ep.0 as f32, ep.1 as f32);
-						if let Some((from, to)) = view.calc_selection(from, to) {
-							view.emit_by_name::<()>(GuiView::TEXT_SELECTED_SIGNAL, &[
-								&(from.line as u64),
-								&(from.offset as u64),
-								&(to.line as u64),
-								&(to.offset as u64),
-							]);
-						}
+			let Some(from) = drag_from(&view, drag) else {
+				return;
+			};
+			if let Some(ep) = drag.point(seq) {
+				let to = pos2(ep.0 as f32, ep.1 as f32);
+				if from != to {
+					if let Some((from, to)) = view.calc_selection(from, to) {
+						view.emit_by_name::<()>(GuiView::TEXT_SELECTED_SIGNAL, &[
+							&(from.line as u64),
+							&(from.offset as u64),
+							&(to.line as u64),
+							&(to.offset as u64),
+						]);
 					}
 				}
 			}
@@ -176,6 +291,11 @@ impl GuiView {
 				let pos = pos2(x as f32, y as f32);
 				let imp = view.imp();
 				let state = gesture.current_event_state();
+				let touch = is_touch(gesture);
+				if touch && imp.touch_anchor().is_some() {
+					// released after long press or on selection handle
+					return;
+				}
 				match imp.resolve_click(pos, state) {
 					ClickTarget::Link(line, link_index) => view.emit_by_name::<()>(GuiView::OPEN_LINK_SIGNAL, &[
 						&(line as u64),
@@ -189,8 +309,14 @@ impl GuiView {
 						&(line as u64),
 						&(offset as u64),
 					]),
-					ClickTarget::None | ClickTarget::Char(..) =>
-						view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]),
+					ClickTarget::None | ClickTarget::Char(..) => if touch && !imp.has_selection() {
+						let delta = tap_zone(x, view.width() as f64, imp.render_han());
+						if delta != 0 {
+							view.emit_by_name::<()>(GuiView::PAGE_SIGNAL, &[&delta]);
+						}
+					} else {
+						view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]);
+					}
 				}
 			} else if n_press == 2 {
 				gesture.set_state(gtk4::EventSequenceState::Claimed);
@@ -207,6 +333,70 @@ impl GuiView {
 		});
 		self.add_controller(gesture);
 
+		let long_press = GestureLongPress::builder()
+			.touch_only(true)
+			.build();
+		let view = self.clone();
+		long_press.connect_pressed(move |_, x, y| {
+			let pos = pos2(x as f32, y as f32);
+			let imp = view.imp();
+			imp.set_touch_input(true);
+			imp.set_touch_anchor(Some(pos));
+			if let ClickTarget::Char(line, offset) = imp.resolve_click(pos, ModifierType::empty()) {
+				view.emit_by_name::<()>(GuiView::SELECT_WORD_SIGNAL, &[
+					&(line as u64),
+					&(offset as u64),
+				]);
+			}
+		});
+		self.add_controller(long_press);
+
+		let swipe = GestureSwipe::builder()
+			.touch_only(true)
+			.build();
+		let view = self.clone();
+		swipe.connect_swipe(move |_, vx, vy| {
+			let imp = view.imp();
+			if imp.touch_anchor().is_some()
+				|| vx.abs() < MIN_SWIPE_VELOCITY
+				|| vx.abs() < vy.abs() * 2. {
+				return;
+			}
+			// han text goes from right to left
+			let forward = (vx < 0.) != imp.render_han();
+			view.emit_by_name::<()>(GuiView::CHAPTER_SIGNAL, &[&forward]);
+		});
+		self.add_controller(swipe);
+
+		let zoom = GestureZoom::new();
+		let zoom_image = Rc::new(Cell::new(false));
+		{
+			let view = self.clone();
+			let zoom_image = zoom_image.clone();
+			zoom.connect_begin(move |gesture, _| {
+				let image = gesture.bounding_box_center()
+					.and_then(|(x, y)| view.imp().image_at(pos2(x as f32, y as f32)));
+				zoom_image.replace(image.is_some());
+				if let Some((line, offset)) = image {
+					view.emit_by_name::<()>(GuiView::ZOOM_IMAGE_SIGNAL, &[
+						&(line as u64),
+						&(offset as u64),
+					]);
+				}
+			});
+		}
+		let view = self.clone();
+		zoom.connect_end(move |gesture, _| {
+			if zoom_image.replace(false) {
+				return;
+			}
+			let scale = gesture.scale_delta();
+			if (scale - 1.).abs() >= MIN_ZOOM_DELTA {
+				view.emit_by_name::<()>(GuiView::ZOOM_SIGNAL, &[&scale]);
+			}
+		});
+		self.add_controller(zoom);
+
 		let mouse_event = EventControllerMotion::new();
 		let view = self.clone();
 		mouse_event.connect_motion(move |motion, x, y| {
@@ -215,18 +405,83 @@ impl GuiView {
 		self.add_controller(mouse_event);
 
 		if !self.scrollable() {
-			let scroll_event = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+			let scroll_event = EventControllerScroll::new(
+				EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::KINETIC);
+			// ticks scrolled but not emitted yet, and ticks per second of flick
+			let scrolled = Rc::new(Cell::new(0f64));
+			let momentum = Rc::new(Cell::new(0f64));
+			{
+				let view = self.clone();
+				let scrolled = scrolled.clone();
+				let momentum = momentum.clone();
+				scroll_event.connect_scroll(move |controller, _, y| {
+					view.grab_focus();
+					momentum.replace(0.);
+					scroll_ticks(&view, &scrolled, y / scroll_step(controller));
+					glib::Propagation::Stop
+				});
+			}
 			let view = self.clone();
-			scroll_event.connect_scroll(move |_, _, y| {
-				view.grab_focus();
-				let delta = if y > 0. { 1 } else { -1 };
-				view.emit_by_name::<()>(GuiView::SCROLL_SIGNAL, &[&delta]);
-				glib::Propagation::Stop
+			scroll_event.connect_decelerate(move |controller, _, velocity| {
+				if !view.imp().smooth_scroll() {
+					return;
+				}
+				let running = momentum.replace(velocity / scroll_step(controller)) != 0.;
+				if running {
+					return;
+				}
+				let scrolled = scrolled.clone();
+				let momentum = momentum.clone();
+				let last_frame = Cell::new(None);
+				view.add_tick_callback(move |view, clock| {
+					let velocity = momentum.get();
+					if velocity.abs() < MIN_MOMENTUM {
+						momentum.replace(0.);
+						return ControlFlow::Break;
+					}
+					let now = clock.frame_time();
+					if let Some(last) = last_frame.replace(Some(now)) {
+						let elapsed = (now - last) as f64 / 1_000_000.;
+						scroll_ticks(view, &scrolled, velocity * elapsed);
+						momentum.replace(velocity * (-MOMENTUM_FRICTION * elapsed).exp());
+					}
+					ControlFlow::Continue
+				});
 			});
 			self.add_controller(scroll_event);
 		}
 	}
 
+	/// eased line scrolling and momentum of flicks
+	#[inline]
+	pub fn set_smooth_scroll(&self, smooth_scroll: bool)
+	{
+		self.imp().set_smooth_scroll(smooth_scroll);
+	}
+
+	/// save current page as png, scale for higher resolution
+	pub fn export_png(&self, path: &Path, scale: f64, background: &Color32) -> Result<()>
+	{
+		let width = (self.width() as f64 * scale) as i32;
+		let height = (self.height() as f64 * scale) as i32;
+		let mut surface = ImageSurface::create(Format::ARgb32, width, height)?;
+		{
+			let cairo = CairoContext::new(&surface)?;
+			cairo.scale(scale, scale);
+			background.apply(&cairo);
+			cairo.paint()?;
+			self.imp().draw_page(&cairo);
+		}
+		surface.flush();
+		let stride = surface.stride() as usize;
+		let data = surface.data()?;
+		let bytes = glib::Bytes::from(&*data);
+		let texture = MemoryTexture::new(width, height, SURFACE_MEMORY_FORMAT,
+			&bytes, stride);
+		texture.save_to_png(path)?;
+		Ok(())
+	}
+
 	#[inline]
 	pub fn get_pango(&self) -> PangoContext
 	{
@@ -243,6 +498,13 @@ impl GuiView {
 		self.imp().set_render_type(render_han, render_context);
 	}
 
+	/// render type for books without their own
+	#[inline]
+	pub fn set_default_render_han(&self, render_han: bool)
+	{
+		self.imp().set_default_render_han(render_han);
+	}
+
 	#[inline]
 	pub fn resized(&self, width: i32, height: i32, render_context: &mut RenderContext)
 	{
@@ -267,6 +529,50 @@ impl GuiView {
 		self.imp().set_custom_font(custom_font, book_fonts, &self.get_pango(), render_context);
 	}
 
+	/// free decoded images
+	#[inline]
+	pub fn clear_image_cache(&self)
+	{
+		self.imp().clear_image_cache();
+	}
+
+	/// font options in render context changed
+	#[inline]
+	pub fn reload_fonts(&self, book_fonts: Option<&HtmlFonts>, render_context: &mut RenderContext)
+	{
+		self.imp().reload_fonts(book_fonts, &self.get_pango(), render_context);
+	}
+
+	/// flash on page turn and ignore book colors
+	#[inline]
+	pub fn set_eink(&self, eink: bool)
+	{
+		self.imp().set_eink(eink);
+	}
+
+	/// transition between pages, not animated in e-ink mode
+	#[inline]
+	pub fn set_page_turn(&self, page_turn: PageTurn, millis: u16)
+	{
+		self.imp().set_page_turn(page_turn, millis);
+	}
+
+	/// image drawn beneath text with mode and opacity, none for no paper
+	#[inline]
+	pub fn set_paper(&self, paper: Option<(gdk::Texture, PaperMode, f32)>)
+	{
+		self.imp().set_paper(paper);
+		self.queue_draw();
+	}
+
+	/// dim text out of focus with the color, disabled with None
+	#[inline]
+	pub fn set_focus(&self, focus: Option<(FocusMode, Color32)>)
+	{
+		self.imp().set_focus(focus);
+		self.queue_draw();
+	}
+
 	#[inline(always)]
 	pub fn scroll_pos(&self) -> f64
 	{
@@ -280,6 +586,12 @@ impl GuiView {
 		self.queue_draw();
 	}
 
+	#[inline(always)]
+	pub fn selection_rect(&self) -> Option<Rect>
+	{
+		self.imp().selection_rect()
+	}
+
 	#[inline(always)]
 	pub fn calc_selection(&self, original_pos: Pos2, current_pos: Pos2)
 		-> Option<(Position, Position)>
@@ -293,9 +605,11 @@ mod imp {
 	use std::cmp::min;
 	use std::rc::Rc;
 	use std::sync::OnceLock;
+	use std::time::{Duration, Instant};
 
 	use glib::Properties;
-	use gtk4::{Adjustment, glib, graphene, Scrollable, ScrollablePolicy, Snapshot};
+	use gtk4::{Adjustment, gdk, glib, graphene, Scrollable, ScrollablePolicy, Snapshot};
+	use gtk4::cairo::{Context as CairoContext, FillRule, LinearGradient};
 	use gtk4::gdk::ModifierType;
 	use gtk4::glib::prelude::StaticType;
 	use gtk4::glib::subclass::Signal;
@@ -306,13 +620,14 @@ mod imp {
 	use indexmap::IndexSet;
 
 	use crate::book::{Book, Line};
+	use crate::color::Color32;
 	use crate::common::Position;
-	use crate::config::ReadingInfo;
-	use crate::controller::HighlightInfo;
+	use crate::config::{FocusMode, PageTurn, PaperMode, ReadingInfo};
+	use crate::controller::{HighlightInfo, HighlightMode};
 	use crate::gui::font::{HtmlFonts, UserFonts};
-	use crate::gui::math::{Pos2, Rect};
+	use crate::gui::math::{pos2, Pos2, Rect, vec2, Vec2};
 	use crate::gui::render::{BlockBackgroundEntry, create_render, GuiRender, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollRedrawMethod, TextDecoration};
-	use crate::gui::view::{ClickTarget, MIN_TEXT_SELECT_DISTANCE, ScrollPosition};
+	use crate::gui::view::{ClickTarget, LINK_FOCUS_PADDING, LINK_FOCUS_WIDTH, MIN_TEXT_SELECT_DISTANCE, PAGE_CURL_SHADOW_WIDTH, ScrollPosition, SELECTION_HANDLE_RADIUS, SMOOTH_SCROLL_MILLIS};
 
 	#[derive(Properties)]
 	#[properties(wrapper_type = super::GuiView)]
@@ -328,8 +643,35 @@ mod imp {
 		#[property(get, set)]
 		scrollable: Cell<bool>,
 		render_han: Cell<bool>,
+		default_render_han: Cell<bool>,
 		data: RefCell<GuiViewData>,
 		render: RefCell<Box<dyn GuiRender>>,
+		focus: RefCell<Option<(FocusMode, Color32)>>,
+		// (line, first offset) of the render line under pointer
+		focus_target: Cell<Option<(usize, usize)>>,
+		eink: Cell<bool>,
+		// page start of last redraw, for flash on page turn
+		eink_page: Cell<Option<(usize, usize)>>,
+		flashing: Cell<bool>,
+		// last selecting or clicking from touch screen, to show selection handles
+		touch_input: Cell<bool>,
+		// the fixed end when selecting text by touch
+		touch_anchor: Cell<Option<Pos2>>,
+		// rect of first and last selected chars, with handle color
+		selection_handles: RefCell<Option<(Rect, Rect, Color32)>>,
+		// rects of the focused link in every render line, with outline color
+		link_focus: RefCell<Option<(Vec<Rect>, Color32)>>,
+		// image with mode and opacity, fixed beneath scrolled text
+		paper: RefCell<Option<(gdk::Texture, PaperMode, f32)>>,
+		page_turn: Cell<PageTurn>,
+		page_turn_duration: Cell<Duration>,
+		// (chapter, line, offset) of shown page, for detecting page turn
+		shown_page: Cell<Option<(usize, usize, usize)>>,
+		turning: RefCell<Option<PageTurning>>,
+		turning_started: Cell<bool>,
+		smooth_scroll: Cell<bool>,
+		// distance from the position of last page, and start time of scrolling
+		scroll_from: Cell<Option<(Vec2, Instant)>>,
 	}
 
 	impl Default for GuiView {
@@ -341,6 +683,7 @@ mod imp {
 				vscroll_policy: Cell::new(ScrollablePolicy::Minimum),
 				scrollable: Cell::new(false),
 				render_han: Cell::new(false),
+				default_render_han: Cell::new(false),
 				data: RefCell::new(GuiViewData {
 					render_rect: Rect::NOTHING,
 					render_lines: vec![],
@@ -350,6 +693,126 @@ mod imp {
 					font_family_names: None,
 				}),
 				render: RefCell::new(create_render(false)),
+				focus: RefCell::new(None),
+				focus_target: Cell::new(None),
+				eink: Cell::new(false),
+				eink_page: Cell::new(None),
+				flashing: Cell::new(false),
+				touch_input: Cell::new(false),
+				touch_anchor: Cell::new(None),
+				selection_handles: RefCell::new(None),
+				link_focus: RefCell::new(None),
+				paper: RefCell::new(None),
+				page_turn: Cell::new(PageTurn::None),
+				page_turn_duration: Cell::new(Duration::ZERO),
+				shown_page: Cell::new(None),
+				turning: RefCell::new(None),
+				turning_started: Cell::new(false),
+				smooth_scroll: Cell::new(false),
+				scroll_from: Cell::new(None),
+			}
+		}
+	}
+
+	/// the page turned away, drawn with the new page until finished
+	struct PageTurning {
+		render_lines: Vec<RenderLine>,
+		block_borders: Vec<TextDecoration>,
+		block_backgrounds: Vec<BlockBackgroundEntry>,
+		background: Color32,
+		// pages move toward left, as turning forward in xi render
+		leftward: bool,
+		start: Instant,
+	}
+
+	impl PageTurning {
+		#[inline]
+		fn progress(&self, duration: Duration) -> f64
+		{
+			if duration.is_zero() {
+				1.
+			} else {
+				(self.start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.)
+			}
+		}
+
+		fn draw(&self, page_turn: PageTurn, progress: f64, new_page: &GuiViewData,
+			render: &dyn GuiRender, width: f64, height: f64,
+			cairo: &CairoContext, pango: &PangoContext)
+		{
+			let draw_old = || render.draw(
+				&self.render_lines,
+				&self.block_borders,
+				&self.block_backgrounds,
+				&new_page.font_family_names,
+				cairo,
+				pango);
+			let draw_new = || render.draw(
+				&new_page.render_lines,
+				&new_page.block_borders,
+				&new_page.block_backgrounds,
+				&new_page.font_family_names,
+				cairo,
+				pango);
+			let direction = if self.leftward { -1. } else { 1. };
+			let result = match page_turn {
+				PageTurn::None => {
+					draw_new();
+					Ok(())
+				}
+				PageTurn::Slide => {
+					cairo.save().and_then(|_| {
+						cairo.translate(direction * progress * width, 0.);
+						draw_old();
+						cairo.restore()
+					}).and_then(|_| cairo.save()).and_then(|_| {
+						cairo.translate(-direction * (1. - progress) * width, 0.);
+						draw_new();
+						cairo.restore()
+					})
+				}
+				PageTurn::Fade => {
+					cairo.push_group();
+					draw_old();
+					cairo.pop_group_to_source()
+						.and_then(|_| cairo.paint_with_alpha(1. - progress))
+						.and_then(|_| {
+							cairo.push_group();
+							draw_new();
+							cairo.pop_group_to_source()
+						})
+						.and_then(|_| cairo.paint_with_alpha(progress))
+				}
+				PageTurn::Curl => {
+					draw_new();
+					// rest of old page, folding from the edge pages moving to
+					let edge = if self.leftward {
+						width * (1. - progress)
+					} else {
+						width * progress
+					};
+					let (left, right) = if self.leftward { (0., edge) } else { (edge, width) };
+					cairo.save().and_then(|_| {
+						cairo.rectangle(left, 0., right - left, height);
+						cairo.clip();
+						self.background.apply(cairo);
+						cairo.paint()
+					}).and_then(|_| {
+						draw_old();
+						cairo.restore()
+					}).and_then(|_| {
+						let shadow_end = edge - direction * PAGE_CURL_SHADOW_WIDTH;
+						let shadow = LinearGradient::new(edge, 0., shadow_end, 0.);
+						shadow.add_color_stop_rgba(0., 0., 0., 0., 0.3);
+						shadow.add_color_stop_rgba(1., 0., 0., 0., 0.);
+						cairo.set_source(&shadow)?;
+						cairo.rectangle(edge.min(shadow_end), 0., PAGE_CURL_SHADOW_WIDTH, height);
+						cairo.fill()
+					})
+				}
+			};
+			if let Err(err) = result {
+				eprintln!("Failed cairo call: {}", err.to_string());
 			}
 		}
 	}
@@ -372,6 +835,7 @@ mod imp {
 
 		fn class_init(clazz: &mut Self::Class) {
 			clazz.set_css_name(super::GuiView::WIDGET_NAME);
+			clazz.set_accessible_role(gtk4::AccessibleRole::Document);
 		}
 	}
 
@@ -445,6 +909,31 @@ mod imp {
 						])
 						.run_last()
 						.build(),
+					Signal::builder(super::GuiView::PAGE_SIGNAL)
+						.param_types([
+							<i32>::static_type(),
+						])
+						.run_last()
+						.build(),
+					Signal::builder(super::GuiView::CHAPTER_SIGNAL)
+						.param_types([
+							<bool>::static_type(),
+						])
+						.run_last()
+						.build(),
+					Signal::builder(super::GuiView::ZOOM_SIGNAL)
+						.param_types([
+							<f64>::static_type(),
+						])
+						.run_last()
+						.build(),
+					Signal::builder(super::GuiView::ZOOM_IMAGE_SIGNAL)
+						.param_types([
+							<u64>::static_type(),
+							<u64>::static_type(),
+						])
+						.run_last()
+						.build(),
 				]
 			})
 		}
@@ -458,18 +947,50 @@ mod imp {
 			let width = obj.width() as f32;
 			let height = obj.height() as f32;
 			let rect = graphene::Rect::new(0.0, 0.0, width, height);
+			if self.flashing.get() {
+				snapshot.append_color(&gdk::RGBA::BLACK, &rect);
+				return;
+			}
+			if let Some((texture, mode, opacity)) = self.paper.borrow().as_ref() {
+				snapshot.push_opacity(*opacity as f64);
+				match mode {
+					PaperMode::Tile => {
+						let tile = graphene::Rect::new(0.0, 0.0,
+							texture.width() as f32, texture.height() as f32);
+						snapshot.push_repeat(&rect, Some(&tile));
+						snapshot.append_texture(texture, &tile);
+						snapshot.pop();
+					}
+					PaperMode::Stretch => snapshot.append_texture(texture, &rect),
+				}
+				snapshot.pop();
+			}
 			let cairo = snapshot.append_cairo(&rect);
 			let render = self.render.borrow();
-			let (render_lines, block_borders, block_backgrounds) = if let Some(draw_data) = &data.draw_data {
+			let page_turn = self.page_turn.get();
+			if let Some(turning) = self.turning.borrow().as_ref() {
+				let progress = turning.progress(self.page_turn_duration.get());
+				if progress < 1. {
+					turning.draw(page_turn, progress, &data, render.as_ref(),
+						width as f64, height as f64, &cairo, &obj.get_pango());
+					return;
+				}
+			}
+			if let Some(offset) = self.scroll_offset() {
+				cairo.translate(offset.x as f64, offset.y as f64);
+			}
+			let (render_lines, block_borders, block_backgrounds, area) = if let Some(draw_data) = &data.draw_data {
 				let offset = &draw_data.offset;
 				cairo.translate(offset.x as f64, offset.y as f64);
 				(&data.render_lines[draw_data.range.clone()],
 					&data.block_borders,
-					&data.block_backgrounds)
+					&data.block_backgrounds,
+					Rect::new(-offset.x, -offset.y, width, height))
 			} else {
 				(data.render_lines.as_slice(),
 					&data.block_borders,
-					&data.block_backgrounds)
+					&data.block_backgrounds,
+					Rect::new(0., 0., width, height))
 			};
 			render.draw(
 				render_lines,
@@ -478,6 +999,36 @@ mod imp {
 				&data.font_family_names,
 				&cairo,
 				&self.obj().get_pango());
+			if let Some((mode, color)) = self.focus.borrow().as_ref() {
+				self.draw_focus(*mode, color, render_lines, &area, &cairo);
+			}
+			if let Some((rects, color)) = self.link_focus.borrow().as_ref() {
+				color.apply(&cairo);
+				cairo.set_line_width(LINK_FOCUS_WIDTH);
+				for rect in rects {
+					cairo.rectangle(
+						(rect.min.x - LINK_FOCUS_PADDING) as f64,
+						(rect.min.y - LINK_FOCUS_PADDING) as f64,
+						(rect.width() + LINK_FOCUS_PADDING * 2.) as f64,
+						(rect.height() + LINK_FOCUS_PADDING * 2.) as f64);
+				}
+				if let Err(err) = cairo.stroke() {
+					eprintln!("Failed cairo call: {}", err.to_string());
+				}
+			}
+			if self.touch_input.get() {
+				if let Some((start, end, color)) = self.selection_handles.borrow().as_ref() {
+					color.apply(&cairo);
+					let (start, end) = handle_points(start, end);
+					for point in [start, end] {
+						cairo.arc(point.x as f64, point.y as f64,
+							SELECTION_HANDLE_RADIUS as f64, 0., std::f64::consts::TAU);
+						if let Err(err) = cairo.fill() {
+							eprintln!("Failed cairo call: {}", err.to_string());
+						}
+					}
+				}
+			}
 		}
 	}
 
@@ -486,6 +1037,318 @@ mod imp {
 	impl ScrollableImpl for GuiView {}
 
 	impl GuiView {
+		/// current page only, without focus dimming and selection handles
+		pub(super) fn draw_page(&self, cairo: &CairoContext)
+		{
+			let data = self.data.borrow();
+			let render = self.render.borrow();
+			render.draw(
+				&data.render_lines,
+				&data.block_borders,
+				&data.block_backgrounds,
+				&data.font_family_names,
+				cairo,
+				&self.obj().get_pango());
+		}
+
+		#[inline]
+		pub(super) fn render_han(&self) -> bool
+		{
+			self.render_han.get()
+		}
+
+		#[inline]
+		pub(super) fn set_default_render_han(&self, render_han: bool)
+		{
+			self.default_render_han.replace(render_han);
+		}
+
+		#[inline]
+		pub(super) fn set_touch_input(&self, touch: bool)
+		{
+			self.touch_input.replace(touch);
+		}
+
+		#[inline]
+		pub(super) fn touch_anchor(&self) -> Option<Pos2>
+		{
+			self.touch_anchor.get()
+		}
+
+		#[inline]
+		pub(super) fn set_touch_anchor(&self, anchor: Option<Pos2>)
+		{
+			self.touch_anchor.replace(anchor);
+		}
+
+		#[inline]
+		pub(super) fn has_selection(&self) -> bool
+		{
+			self.selection_handles.borrow().is_some()
+		}
+
+		/// bounding rect of selection in view, None if not in current page
+		pub(super) fn selection_rect(&self) -> Option<Rect>
+		{
+			let handles = self.selection_handles.borrow();
+			let (start, end, _) = handles.as_ref()?;
+			let rect = start.union(end);
+			let data = self.data.borrow();
+			let Some(draw_data) = &data.draw_data else {
+				return Some(rect);
+			};
+			let offset = &draw_data.offset;
+			Some(Rect::from_min_max(
+				pos2(rect.min.x + offset.x, rect.min.y + offset.y),
+				pos2(rect.max.x + offset.x, rect.max.y + offset.y)))
+		}
+
+		/// center of the other selection end, if pointer on a selection handle
+		pub(super) fn handle_anchor(&self, pos: Pos2) -> Option<Pos2>
+		{
+			#[inline]
+			fn near(pos: &Pos2, point: &Pos2) -> bool
+			{
+				let d = *pos - *point;
+				let reach = SELECTION_HANDLE_RADIUS * 3.;
+				d.x * d.x + d.y * d.y <= reach * reach
+			}
+			#[inline]
+			fn center(rect: &Rect) -> Pos2
+			{
+				pos2((rect.min.x + rect.max.x) / 2., (rect.min.y + rect.max.y) / 2.)
+			}
+			let handles = self.selection_handles.borrow();
+			let (start, end, _) = handles.as_ref()?;
+			let (start_point, end_point) = handle_points(start, end);
+			if near(&pos, &start_point) {
+				Some(center(end))
+			} else if near(&pos, &end_point) {
+				Some(center(start))
+			} else {
+				None
+			}
+		}
+
+		/// (line, offset) of image under pointer
+		pub(super) fn image_at(&self, pos: Pos2) -> Option<(usize, usize)>
+		{
+			self.pointer_info(pos, |info| {
+				let (render_line, dc) = info?;
+				if let RenderCell::Image(..) = dc.cell {
+					Some((render_line.line(), dc.offset))
+				} else {
+					None
+				}
+			})
+		}
+
+		#[inline]
+		pub(super) fn set_paper(&self, paper: Option<(gdk::Texture, PaperMode, f32)>)
+		{
+			self.paper.replace(paper);
+		}
+
+		#[inline]
+		pub(super) fn set_eink(&self, eink: bool)
+		{
+			self.eink.replace(eink);
+			self.eink_page.replace(None);
+		}
+
+		#[inline]
+		pub(super) fn set_page_turn(&self, page_turn: PageTurn, millis: u16)
+		{
+			self.page_turn.replace(page_turn);
+			self.page_turn_duration.replace(Duration::from_millis(millis as u64));
+		}
+
+		/// keep the old page for transition if page turned
+		fn prepare_page_turning(&self, page: (usize, usize, usize), data: &mut GuiViewData,
+			background: &Color32)
+		{
+			let Some(prev) = self.shown_page.replace(Some(page)) else {
+				return;
+			};
+			if prev == page || self.page_turn.get() == PageTurn::None || self.eink.get() {
+				return;
+			}
+			// han render turns pages toward right
+			let leftward = (page > prev) != self.render_han.get();
+			let prev_turning = self.turning.replace(Some(PageTurning {
+				render_lines: std::mem::take(&mut data.render_lines),
+				block_borders: std::mem::take(&mut data.block_borders),
+				block_backgrounds: std::mem::take(&mut data.block_backgrounds),
+				background: background.clone(),
+				leftward,
+				start: Instant::now(),
+			}));
+			if prev_turning.is_none() {
+				self.turning_started.replace(true);
+			}
+		}
+
+		#[inline]
+		pub(super) fn set_smooth_scroll(&self, smooth_scroll: bool)
+		{
+			self.smooth_scroll.replace(smooth_scroll);
+		}
+
+		#[inline]
+		pub(super) fn smooth_scroll(&self) -> bool
+		{
+			self.smooth_scroll.get() && !self.eink.get()
+		}
+
+		/// distance to scroll yet, none if finished
+		fn scroll_offset(&self) -> Option<Vec2>
+		{
+			let (from, start) = self.scroll_from.get()?;
+			let progress = start.elapsed().as_secs_f64()
+				/ Duration::from_millis(SMOOTH_SCROLL_MILLIS).as_secs_f64();
+			if progress >= 1. {
+				return None;
+			}
+			// ease out cubic
+			let left = (1. - progress).powi(3) as f32;
+			Some(vec2(from.x * left, from.y * left))
+		}
+
+		/// false and scrolling dropped if finished
+		pub(super) fn scrolling(&self) -> bool
+		{
+			if self.scroll_offset().is_some() {
+				true
+			} else {
+				self.scroll_from.replace(None);
+				false
+			}
+		}
+
+		/// scroll from position of last page if text of both shown,
+		/// otherwise turn page
+		fn prepare_scrolling(&self, page: (usize, usize, usize), data: &mut GuiViewData,
+			render_lines: &[RenderLine], background: &Color32)
+		{
+			let scrolled = self.shown_page.get()
+				.map_or(false, |prev| prev != page && prev.0 == page.0);
+			if self.smooth_scroll() && scrolled {
+				if let Some(from) = scrolled_distance(&data.render_lines, render_lines) {
+					let running = self.scroll_offset();
+					let from = running.map_or(from, |left| from + left);
+					self.shown_page.replace(Some(page));
+					self.turning.replace(None);
+					self.scroll_from.replace(Some((from, Instant::now())));
+					// ticking already if running
+					if running.is_none() {
+						self.turning_started.replace(true);
+					}
+					return;
+				}
+			}
+			self.scroll_from.replace(None);
+			self.prepare_page_turning(page, data, background);
+		}
+
+		/// true if a transition started since last call, for ticking until finished
+		#[inline]
+		pub(super) fn page_turning_started(&self) -> bool
+		{
+			self.turning_started.replace(false)
+		}
+
+		/// false and transition dropped if finished
+		pub(super) fn page_turning(&self) -> bool
+		{
+			let mut turning = self.turning.borrow_mut();
+			match turning.as_ref() {
+				Some(page) if page.progress(self.page_turn_duration.get()) < 1. => true,
+				Some(_) => {
+					*turning = None;
+					false
+				}
+				None => false,
+			}
+		}
+
+		/// true if page turned in e-ink mode, and the flash should be stopped later
+		pub(super) fn start_flash(&self, line: usize, offset: usize) -> bool
+		{
+			if !self.eink.get() || self.scrollable.get() {
+				return false;
+			}
+			let prev = self.eink_page.replace(Some((line, offset)));
+			if prev.is_none() || prev == Some((line, offset)) {
+				return false;
+			}
+			self.flashing.replace(true);
+			true
+		}
+
+		#[inline]
+		pub(super) fn stop_flash(&self)
+		{
+			self.flashing.replace(false);
+		}
+
+		#[inline]
+		pub(super) fn set_focus(&self, focus: Option<(FocusMode, Color32)>)
+		{
+			self.focus.replace(focus);
+		}
+
+		/// remember the render line under pointer, true if focus changed
+		pub(super) fn focus_on(&self, render_line: &RenderLine) -> bool
+		{
+			let Some((mode, _)) = *self.focus.borrow() else {
+				return false;
+			};
+			let target = (render_line.line(), render_line.first_offset());
+			match self.focus_target.replace(Some(target)) {
+				Some(prev) => match mode {
+					FocusMode::Paragraph => prev.0 != target.0,
+					FocusMode::Ruler => prev != target,
+				}
+				None => true,
+			}
+		}
+
+		/// cover the area except focused lines, first line focused if pointer not on text
+		fn draw_focus(&self, mode: FocusMode, color: &Color32,
+			render_lines: &[RenderLine], area: &Rect, cairo: &CairoContext)
+		{
+			let target = self.focus_target.get();
+			let focused = target
+				.and_then(|(line, offset)| render_lines
+					.iter()
+					.find(|render_line| render_line.line() == line
+						&& (mode == FocusMode::Paragraph || render_line.first_offset() == offset)))
+				.or_else(|| render_lines.first());
+			let Some(focused) = focused else {
+				return;
+			};
+			let bounds = match mode {
+				FocusMode::Paragraph => render_lines
+					.iter()
+					.filter(|render_line| render_line.line() == focused.line())
+					.filter_map(|render_line| render_line.bounds())
+					.reduce(|rect, bounds| rect.union(&bounds)),
+				FocusMode::Ruler => focused.bounds(),
+			};
+			color.apply(cairo);
+			cairo.set_fill_rule(FillRule::EvenOdd);
+			cairo.rectangle(area.min.x as f64, area.min.y as f64,
+				area.width() as f64, area.height() as f64);
+			if let Some(bounds) = bounds {
+				cairo.rectangle(bounds.min.x as f64, bounds.min.y as f64,
+					bounds.width() as f64, bounds.height() as f64);
+			}
+			if let Err(err) = cairo.fill() {
+				eprintln!("Failed cairo call: {}", err.to_string());
+			}
+			cairo.set_fill_rule(FillRule::Winding);
+		}
+
 		fn adjustment_value_handle(&self, adjustment: &Option<Adjustment>)
 		{
 			if let Some(adjustment) = &adjustment {
@@ -559,14 +1422,25 @@ mod imp {
 			pango: &PangoContext, context: &mut RenderContext)
 		{
 			context.custom_font = reading.custom_font;
-			context.custom_color = reading.custom_color;
+			context.custom_color = reading.custom_color && !self.eink.get();
+			context.text_only = reading.text_only;
 			context.leading_chars = book.leading_space();
+			let render_han = reading.render_han.unwrap_or(self.default_render_han.get());
+			if render_han != self.render_han.get() {
+				self.set_render_type(render_han, context);
+			}
 			let mut render = self.render.borrow_mut();
 			render.image_cache_mut().clear();
 			render.apply_font_modified(book.custom_fonts(), pango, context);
 			render.reset_render_context(context);
 		}
 
+		#[inline]
+		pub(super) fn clear_image_cache(&self)
+		{
+			self.render.borrow_mut().image_cache_mut().clear();
+		}
+
 		pub(super) fn redraw(&self, book: &dyn Book, lines: &Vec<Line>, line: usize,
 			offset: usize, highlight: &Option<HighlightInfo>, context: &mut RenderContext,
 			pango: &PangoContext) -> Option<Position>
@@ -584,9 +1458,15 @@ mod imp {
 				);
 				let (render_lines, block_borders, block_backgrounds, next) = render.gui_redraw(book, lines, line, offset, highlight,
 					pango, context);
+				self.prepare_scrolling((book.current_chapter(), line, offset), &mut data,
+					&render_lines, &context.colors.background);
 				data.render_lines = render_lines;
 				data.block_borders = block_borders;
 				data.block_backgrounds = block_backgrounds;
+				self.selection_handles.replace(selection_handles(
+					&data.render_lines, highlight, &context.colors.color));
+				self.link_focus.replace(link_focus(
+					&data.render_lines, highlight, &context.colors.link));
 				next
 			}
 		}
@@ -635,6 +1515,8 @@ mod imp {
 				highlight, pango, render_context);
 			let sizing = render.scroll_size(render_context);
 			render_context.max_page_size = view_size;
+			self.link_focus.replace(link_focus(
+				&lines, highlight, &render_context.colors.link));
 			sync_font_family_names(
 				&mut self.data.borrow_mut(),
 				book.font_family_names(),
@@ -721,7 +1603,7 @@ mod imp {
 		{
 			render_context.font_size = font_size;
 			let mut render = self.render.borrow_mut();
-			render.apply_font_modified(book_fonts, pango, render_context);
+			render.apply_font_size_modified(book_fonts, pango, render_context);
 		}
 
 		pub(super) fn set_custom_font(&self, custom_font: bool, book_fonts: Option<&HtmlFonts>,
@@ -734,6 +1616,14 @@ mod imp {
 			}
 		}
 
+		#[inline]
+		pub(super) fn reload_fonts(&self, book_fonts: Option<&HtmlFonts>,
+			pango: &PangoContext, render_context: &mut RenderContext)
+		{
+			let mut render = self.render.borrow_mut();
+			render.apply_font_modified(book_fonts, pango, render_context);
+		}
+
 		pub fn resized(&self, width: i32, height: i32, render_context: &mut RenderContext)
 		{
 			render_context.update_render_rect(width as f32, height as f32);
@@ -935,6 +1825,84 @@ mod imp {
 		}
 	}
 
+	/// rects of first and last selected chars, both in current page
+	fn selection_handles(render_lines: &Vec<RenderLine>, highlight: &Option<HighlightInfo>,
+		color: &Color32) -> Option<(Rect, Rect, Color32)>
+	{
+		#[inline]
+		fn char_rect(render_lines: &Vec<RenderLine>, line: usize, offset: usize) -> Option<Rect>
+		{
+			render_lines
+				.iter()
+				.filter(|render_line| render_line.line() == line)
+				.find_map(|render_line| render_line.find(|_, dc|
+					if dc.offset == offset { Some(dc.rect.clone()) } else { None }))
+		}
+		let highlight = highlight.as_ref()?;
+		let HighlightMode::Selection(_, end_line) = &highlight.mode else {
+			return None;
+		};
+		let start = char_rect(render_lines, highlight.line, highlight.start)?;
+		let end = char_rect(render_lines, *end_line, highlight.end.saturating_sub(1))?;
+		Some((start, end, color.clone()))
+	}
+
+	/// distance of text shown in both pages, from old position to new one
+	fn scrolled_distance(old: &[RenderLine], new: &[RenderLine]) -> Option<Vec2>
+	{
+		#[inline]
+		fn char_pos(render_lines: &[RenderLine], line: usize, offset: usize) -> Option<Pos2>
+		{
+			render_lines
+				.iter()
+				.filter(|render_line| render_line.line() == line)
+				.find_map(|render_line| render_line.find(|_, dc|
+					if dc.offset == offset { Some(dc.rect.min) } else { None }))
+		}
+		let new_first = new.first()?;
+		let new_char = new_first.first_render_char()?;
+		let distance = if let Some(pos) = char_pos(old, new_first.line(), new_char.offset) {
+			pos - new_char.rect.min
+		} else {
+			let old_first = old.first()?;
+			let old_char = old_first.first_render_char()?;
+			old_char.rect.min - char_pos(new, old_first.line(), old_char.offset)?
+		};
+		if distance == Vec2::ZERO {
+			None
+		} else {
+			Some(distance)
+		}
+	}
+
+	/// rects of the link focused by keyboard, a link may wrap to lines
+	fn link_focus(render_lines: &Vec<RenderLine>, highlight: &Option<HighlightInfo>,
+		color: &Color32) -> Option<(Vec<Rect>, Color32)>
+	{
+		let highlight = highlight.as_ref()?;
+		let HighlightMode::Link(_) = &highlight.mode else {
+			return None;
+		};
+		let range = highlight.start..highlight.end;
+		let rects = render_lines
+			.iter()
+			.filter(|render_line| render_line.line() == highlight.line)
+			.filter_map(|render_line| render_line.range_bounds(&range))
+			.collect::<Vec<_>>();
+		if rects.is_empty() {
+			None
+		} else {
+			Some((rects, color.clone()))
+		}
+	}
+
+	/// handle below the start of first char and the end of last char
+	#[inline]
+	fn handle_points(start: &Rect, end: &Rect) -> (Pos2, Pos2)
+	{
+		(pos2(start.min.x, start.max.y), end.max)
+	}
+
 	// with different name for family index, the cache with family invalid
 	fn sync_font_family_names(data: &mut GuiViewData,
 		font_family_names: Option<&IndexSet<String>>,
@@ -982,15 +1950,75 @@ pub fn update_css(css_provider: &CssProvider, colors: &Colors)
 	css_provider.load_from_string(&css);
 }
 
+#[inline]
+fn is_touch(controller: &impl IsA<EventController>) -> bool
+{
+	controller.current_event_device()
+		.map_or(false, |device| device.source() == InputSource::Touchscreen)
+}
+
+/// scroll delta for a wheel tick, trackpads scroll in pixels
+#[inline]
+fn scroll_step(controller: &EventControllerScroll) -> f64
+{
+	if controller.unit() == gdk::ScrollUnit::Surface {
+		SURFACE_SCROLL_STEP
+	} else {
+		1.
+	}
+}
+
+/// emit whole ticks scrolled, the rest kept for next scroll,
+/// high resolution deltas accumulated proportionally
+fn scroll_ticks(view: &GuiView, scrolled: &Cell<f64>, ticks: f64)
+{
+	let total = scrolled.get() + ticks;
+	let steps = total.trunc();
+	scrolled.replace(total - steps);
+	if steps != 0. {
+		view.emit_by_name::<()>(GuiView::SCROLL_SIGNAL, &[&(steps as i32)]);
+	}
+}
+
+/// start point of text selecting, None for touch without anchor
+fn drag_from(view: &GuiView, drag: &GestureDrag) -> Option<Pos2>
+{
+	if is_touch(drag) {
+		view.imp().touch_anchor()
+	} else {
+		drag.start_point().map(|(x, y)| pos2(x as f32, y as f32))
+	}
+}
+
+/// page delta for tap at left or right third of the view
+#[inline]
+fn tap_zone(x: f64, width: f64, render_han: bool) -> i32
+{
+	let third = width / 3.;
+	let delta = if x < third {
+		-1
+	} else if x > third * 2. {
+		1
+	} else {
+		0
+	};
+	// han pages go from right to left
+	if render_han { -delta } else { delta }
+}
+
 pub fn update_mouse_pointer(view: &GuiView, x: f32, y: f32, state: ModifierType)
 {
 	let pos = pos2(x, y);
 	let imp = view.imp();
 	let title_info = imp.pointer_info(pos, |info| {
 		if let Some((render_line, render_char)) = info {
+			if imp.focus_on(render_line) {
+				view.queue_draw();
+			}
 			let cursor_name = imp.pointer_cursor(render_char, state);
 			view.set_cursor_from_name(Some(cursor_name));
-			if render_char.has_title {
+			let is_link = matches!(render_char.cell, RenderCell::Link(..) | RenderCell::Image(_, Some(_)));
+			if render_char.has_title || is_link {
 				Some((render_line.line(), render_char.offset))
 			} else {
 				None
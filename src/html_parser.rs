@@ -15,6 +15,7 @@ use lightningcss::properties::border::{Border, BorderSideWidth};
 use lightningcss::properties::display::{Display, DisplayKeyword, DisplayOutside, DisplayPair};
 use lightningcss::properties::font::{AbsoluteFontWeight, FontFamily, FontSize, FontWeight as CssFontWeight};
 use lightningcss::properties::size::Size;
+use lightningcss::printer::PrinterOptions;
 use lightningcss::properties::text::{TextDecoration as CssTextDecoration, TextDecorationLine as CssTextDecorationLine, TextDecorationStyle as CssTextDecorationStyle};
 use lightningcss::rules::{CssRule, font_face};
 use lightningcss::rules::font_face::FontFaceProperty;
@@ -26,10 +27,10 @@ use lightningcss::values::length::{Length, LengthPercentage, LengthValue};
 use lightningcss::values::percentage;
 use markup5ever::{LocalName, Namespace, Prefix, QualName};
 use roxmltree::{Document, ParsingOptions};
-use scraper::{Html, Node, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
 use scraper::node::Element;
 
-use crate::book::{EMPTY_CHAPTER_CONTENT, IMAGE_CHAR, Line};
+use crate::book::{EMPTY_CHAPTER_CONTENT, IMAGE_CHAR, Line, MEDIA_CHAR, MEDIA_LINK_PREFIX, NoteInfo};
 use crate::color::Color32;
 use crate::common::Position;
 
@@ -218,15 +219,19 @@ pub struct ImageStyle {
 	pub href: String,
 	pub width: Option<ElementSize>,
 	pub height: Option<ElementSize>,
+	// alt text follows the image char in line, hidden if image drawn
+	pub alt: Option<String>,
 }
 impl ImageStyle {
 	#[inline]
-	fn new(href: &str, width: Option<ElementSize>, height: Option<ElementSize>) -> Self
+	pub(crate) fn new(href: &str, width: Option<ElementSize>, height: Option<ElementSize>,
+		alt: Option<String>) -> Self
 	{
 		Self {
 			href: href.to_owned(),
 			width,
 			height,
+			alt,
 		}
 	}
 	#[inline]
@@ -406,12 +411,53 @@ impl FontWeight {
 	}
 }
 
+/// writing-mode and text-orientation of html or body element
+#[derive(Clone, Copy, Default)]
+pub struct WritingMode {
+	pub vertical: bool,
+	// latin chars not rotated in vertical writing
+	pub upright: bool,
+}
+
+impl WritingMode {
+	/// apply the properties in declarations, prefixed ones included,
+	/// false if none of them
+	fn apply(&mut self, declarations: &DeclarationBlock) -> bool
+	{
+		let mut applied = false;
+		for property in declarations.declarations.iter()
+			.chain(&declarations.important_declarations) {
+			let id = property.property_id();
+			let name = id.name();
+			let name = name.strip_prefix("-epub-")
+				.or_else(|| name.strip_prefix("-webkit-"))
+				.unwrap_or(name);
+			if name != "writing-mode" && name != "text-orientation" {
+				continue;
+			}
+			let Ok(value) = property.value_to_css_string(PrinterOptions::default()) else {
+				continue;
+			};
+			let value = value.trim().to_lowercase();
+			if name == "writing-mode" {
+				self.vertical = value.starts_with("vertical") || value.starts_with("tb");
+			} else {
+				self.upright = value == "upright";
+			}
+			applied = true;
+		}
+		applied
+	}
+}
+
 pub struct HtmlContent {
 	title: Option<String>,
 	lines: Vec<Line>,
 	#[allow(unused)]
 	block_styles: Option<Vec<BlockStyle>>,
 	id_map: HashMap<String, Position>,
+	notes: Vec<NoteInfo>,
+	writing_mode: WritingMode,
 }
 
 impl HtmlContent
@@ -425,6 +471,8 @@ impl HtmlContent
 			lines: vec![],
 			block_styles: None,
 			id_map: HashMap::new(),
+			notes: vec![],
+			writing_mode: WritingMode::default(),
 		}
 	}
 	#[inline]
@@ -448,6 +496,92 @@ impl HtmlContent
 	{
 		self.id_map.get(id)
 	}
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn notes(&self) -> &Vec<NoteInfo>
+	{
+		&self.notes
+	}
+	#[inline]
+	pub fn writing_mode(&self) -> WritingMode
+	{
+		self.writing_mode
+	}
+
+	/// lines in ranges as separated contents, for huge content split into
+	/// chapters, positions of ids, notes and block styles moved along
+	pub fn split(self, ranges: &[Range<usize>]) -> Vec<HtmlContent>
+	{
+		#[inline]
+		fn contains(range: &Range<usize>, note: &Range<Position>) -> bool
+		{
+			range.contains(&note.start.line) && note.end.line < range.end
+		}
+		#[inline]
+		fn relative(note: &Range<Position>, start: usize) -> Range<Position>
+		{
+			Position::new(note.start.line - start, note.start.offset)
+				..Position::new(note.end.line - start, note.end.offset)
+		}
+		let mut lines = self.lines;
+		let mut parts = ranges
+			.iter()
+			.rev()
+			.map(|range| lines.split_off(range.start))
+			.collect::<Vec<_>>();
+		parts.reverse();
+		parts
+			.into_iter()
+			.zip(ranges)
+			.map(|(lines, range)| {
+				let start = range.start;
+				let clip = |block: &Range<usize>| {
+					let from = block.start.max(range.start);
+					let to = block.end.min(range.end);
+					if from < to { Some(from - start..to - start) } else { None }
+				};
+				let block_styles = self.block_styles.as_ref().map(|styles| styles
+					.iter()
+					.filter_map(|style| match style {
+						BlockStyle::Border { range, lines, color } => Some(BlockStyle::Border {
+							range: clip(range)?,
+							lines: *lines,
+							color: color.clone(),
+						}),
+						BlockStyle::Background { range, color } => Some(BlockStyle::Background {
+							range: clip(range)?,
+							color: color.clone(),
+						}),
+					})
+					.collect());
+				let id_map = self.id_map
+					.iter()
+					.filter(|(_, position)| range.contains(&position.line))
+					.map(|(id, position)| (id.clone(),
+						Position::new(position.line - start, position.offset)))
+					.collect();
+				let notes = self.notes
+					.iter()
+					.filter(|info| contains(range, &info.note))
+					.map(|info| NoteInfo {
+						note: relative(&info.note, start),
+						reference: info.reference
+							.as_ref()
+							.filter(|reference| contains(range, reference))
+							.map(|reference| relative(reference, start)),
+					})
+					.collect();
+				HtmlContent {
+					title: self.title.clone(),
+					lines,
+					block_styles,
+					id_map,
+					notes,
+					writing_mode: self.writing_mode,
+				}
+			})
+			.collect()
+	}
 }
 
 struct StyleDescription {
@@ -471,11 +605,16 @@ pub struct HtmlParser<'a> {
 	font_face_map: HashMap<&'a str, Option<String>>,
 	styles: Vec<StyleDescription>,
 	dark_mode: bool,
+	writing_mode: WritingMode,
 
 	title: Option<String>,
 	lines: Vec<Line>,
 	block_styles: Vec<BlockStyle>,
 	id_map: HashMap<String, Position>,
+	// footnote or endnote elements: (id, range)
+	notes: Vec<(String, Range<Position>)>,
+	// first reference link of note: (note id, range)
+	note_refs: HashMap<String, Range<Position>>,
 }
 
 impl<'a> HtmlParser<'a> {
@@ -554,6 +693,11 @@ impl<'a> HtmlParser<'a> {
 								insert_or_replace_tag(&mut styles, style, false)
 							}
 						}
+						let mut writing_mode = self.writing_mode;
+						if writing_mode.apply(&style_rule.declarations)
+							&& root_selected(document, &style_rule.selectors.to_string()) {
+							self.writing_mode = writing_mode;
+						}
 						if styles.len() == 0 {
 							continue;
 						}
@@ -584,6 +728,7 @@ impl<'a> HtmlParser<'a> {
 		Vec<Line>,
 		Option<Vec<BlockStyle>>,
 		HashMap<String, Position>,
+		Vec<NoteInfo>,
 		Vec<HtmlFontFaceDesc>)
 	{
 		let lines = &mut self.lines;
@@ -627,11 +772,13 @@ impl<'a> HtmlParser<'a> {
 		} else {
 			Some(self.block_styles)
 		};
+		let notes = collect_notes(self.notes, self.note_refs, &self.id_map, &self.lines);
 		(
 			self.title,
 			self.lines,
 			block_styles,
 			self.id_map,
+			notes,
 			self.font_faces)
 	}
 
@@ -675,6 +822,7 @@ impl<'a> HtmlParser<'a> {
 				if let Some(id) = element.id() {
 					self.id_map.insert(id.to_string(), position.clone());
 				}
+				let note_id = element.id().filter(|_| is_note(element));
 				let mut element_tags = self.load_element_tags(
 					element,
 					node.id());
@@ -776,10 +924,16 @@ impl<'a> HtmlParser<'a> {
 							insert_or_replace_tag(&mut element_tags, ParseTag::Style(TextStyle::Link(href.to_string())), false);
 						}
 						self.convert_node_children(node.children());
+						if let Some(id) = note_ref_id(element, node) {
+							if !self.note_refs.contains_key(id) {
+								let end = self.last_position();
+								self.note_refs.insert(id.to_owned(), position.clone()..end);
+							}
+						}
 					}
 					local_name!("img") => {
 						if let Some(href) = element.attr("src") {
-							self.add_image(href, &element_tags);
+							self.add_image(href, element.attr("alt"), &element_tags);
 						}
 					}
 					local_name!("image") => {
@@ -789,13 +943,29 @@ impl<'a> HtmlParser<'a> {
 							LocalName::from("href"));
 						let href = element.attrs.get(&name).map(Deref::deref);
 						if let Some(href) = href {
-							self.add_image(href, &element_tags);
+							self.add_image(href, None, &element_tags);
+						}
+					}
+					local_name!("audio") | local_name!("video") => {
+						// children other than source are fallback content
+						let src = element.attr("src")
+							.or_else(|| node.children().find_map(|child| match child.value() {
+								Node::Element(source) if source.name.local == local_name!("source") =>
+									source.attr("src"),
+								_ => None,
+							}));
+						if let Some(src) = src {
+							self.add_media(src, element.attr("title"));
 						}
 					}
 					local_name!("noscript") |
 					local_name!("script") => {}
 					_ => self.convert_node_children(node.children()),
 				}
+				if let Some(id) = note_id {
+					let end = self.last_position();
+					self.notes.push((id.to_owned(), position.clone()..end));
+				}
 				if force_paragraph {
 					self.new_line();
 				}
@@ -857,7 +1027,7 @@ impl<'a> HtmlParser<'a> {
 		element_tags
 	}
 
-	fn add_image(&mut self, href: &str, element_tags: &LeveledParseTagSet)
+	fn add_image(&mut self, href: &str, alt: Option<&str>, element_tags: &LeveledParseTagSet)
 	{
 		let mut width = None;
 		let mut height = None;
@@ -870,10 +1040,42 @@ impl<'a> HtmlParser<'a> {
 				_ => {}
 			}
 		}
+		let alt = alt
+			.map(|alt| alt.trim())
+			.filter(|alt| !alt.is_empty())
+			.map(|alt| alt.to_owned());
 		let line = self.lines.last_mut().unwrap();
 		let start = line.len();
 		line.push(IMAGE_CHAR);
-		line.push_style(TextStyle::Image(ImageStyle::new(href, width, height)), start..start + 1);
+		if let Some(alt) = &alt {
+			line.concat(alt);
+		}
+		let end = line.len();
+		line.push_style(TextStyle::Image(ImageStyle::new(href, width, height, alt)), start..end);
+	}
+
+	/// placeholder line of audio or video, as a link for external playback
+	fn add_media(&mut self, src: &str, title: Option<&str>)
+	{
+		let label = title
+			.map(|title| title.trim())
+			.filter(|title| !title.is_empty())
+			.unwrap_or_else(|| src.rsplit('/').next().unwrap_or(src));
+		self.new_line();
+		let line = self.lines.last_mut().unwrap();
+		let start = line.len();
+		line.push(MEDIA_CHAR);
+		line.push(' ');
+		line.concat(label);
+		let end = line.len();
+		line.push_style(TextStyle::Link(format!("{MEDIA_LINK_PREFIX}{src}")), start..end);
+		self.new_line();
+	}
+
+	#[inline]
+	fn last_position(&self) -> Position
+	{
+		Position::new(self.lines.len() - 1, self.lines.last().unwrap().len())
 	}
 
 	fn newline_for_class(&mut self, element: &Element)
@@ -1384,11 +1586,14 @@ pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFace
 		font_face_map: Default::default(),
 		styles: vec![],
 		dark_mode: options.dark_mode,
+		writing_mode: WritingMode::default(),
 
 		title: None,
 		lines: vec![Line::default()],
 		block_styles: vec![],
 		id_map: Default::default(),
+		notes: vec![],
+		note_refs: Default::default(),
 	};
 
 	parser.load_styles(&html, &stylesheets);
@@ -1425,9 +1630,19 @@ pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFace
 		.next()
 		.ok_or(anyhow!("No body in the document"))?;
 
+	// inline style of root elements
+	for element in [html.root_element(), body] {
+		if let Some(style) = element.value().attr("style") {
+			if let Ok(declarations) = DeclarationBlock::parse_string(style, style_parse_options()) {
+				parser.writing_mode.apply(&declarations);
+			}
+		}
+	}
+
 	parser.convert_node_to_lines(*body.deref());
 
-	let (title, lines, block_styles, id_map, font_faces) = parser.finalize();
+	let writing_mode = parser.writing_mode;
+	let (title, lines, block_styles, id_map, notes, font_faces) = parser.finalize();
 	let title = if options.custom_title.is_some() {
 		options.custom_title
 	} else {
@@ -1438,5 +1653,86 @@ pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFace
 		lines,
 		block_styles,
 		id_map,
+		notes,
+		writing_mode,
 	}, font_faces))
 }
+
+/// the selector matches html or body element
+fn root_selected(document: &Html, selector: &str) -> bool
+{
+	let Ok(selector) = Selector::parse(selector) else {
+		return false;
+	};
+	let selected = document.select(&selector)
+		.any(|element| matches!(element.value().name(), "html" | "body"));
+	selected
+}
+
+/// note element by epub:type or DPUB-ARIA role
+fn is_note(element: &Element) -> bool
+{
+	const NOTE_TYPES: [&str; 6] = ["footnote", "endnote", "rearnote", "note", "doc-footnote", "doc-endnote"];
+	let types = element.attr("epub:type").into_iter()
+		.chain(element.attr("role"))
+		.flat_map(|value| value.split_ascii_whitespace());
+	for value in types {
+		if NOTE_TYPES.contains(&value) {
+			return true;
+		}
+	}
+	false
+}
+
+/// target id of link to note in same document, by epub:type, role
+/// or marker like text of link: 1, [2], *
+fn note_ref_id<'a>(element: &'a Element, node: NodeRef<Node>) -> Option<&'a str>
+{
+	let id = element.attr("href")?.strip_prefix('#')?;
+	if id.is_empty() {
+		return None;
+	}
+	let types = element.attr("epub:type").into_iter()
+		.chain(element.attr("role"))
+		.flat_map(|value| value.split_ascii_whitespace());
+	for value in types {
+		if value == "noteref" || value == "doc-noteref" {
+			return Some(id);
+		}
+	}
+	let text = ElementRef::wrap(node)?.text().collect::<String>();
+	let marker = text.trim().trim_matches(|c| matches!(c,
+		'[' | ']' | '(' | ')' | '【' | '】' | '〔' | '〕' | '（' | '）' | '注'));
+	if !marker.is_empty() && marker.chars().all(|c| c.is_ascii_digit()
+		|| matches!(c, '*' | '†' | '‡' | '①'..='⑳')) {
+		Some(id)
+	} else {
+		None
+	}
+}
+
+fn collect_notes(notes: Vec<(String, Range<Position>)>,
+	mut note_refs: HashMap<String, Range<Position>>,
+	id_map: &HashMap<String, Position>, lines: &Vec<Line>) -> Vec<NoteInfo>
+{
+	let mut infos: Vec<NoteInfo> = notes
+		.into_iter()
+		.map(|(id, note)| {
+			let reference = note_refs.remove(&id);
+			NoteInfo { note, reference }
+		})
+		.collect();
+	// referenced only, note ends at end of the line
+	for (id, reference) in note_refs {
+		if let Some(start) = id_map.get(&id) {
+			let nested = infos.iter().any(|info| info.note.start.line <= start.line
+				&& start.line <= info.note.end.line);
+			if !nested {
+				let end = Position::new(start.line, lines.get(start.line).map_or(0, Line::len));
+				infos.push(NoteInfo { note: start.clone()..end, reference: Some(reference) });
+			}
+		}
+	}
+	infos.sort_by_key(|info| (info.note.start.line, info.note.start.offset));
+	infos
+}
@@ -1,11 +1,22 @@
 use anyhow::Result;
 use std::borrow::Cow;
 use std::collections::HashMap;
+#[cfg(feature = "gui")]
+use std::fs;
+use std::path::Path;
 use fluent::{FluentArgs, FluentBundle, FluentResource, FluentValue};
 use unic_langid::LanguageIdentifier;
 use crate::Asset;
 
 pub const DEFAULT_LOCALE: &str = "en_US";
+/// user locale files in config dir, named like zh_TW.ftl,
+/// messages override the embedded ones of the same locale,
+/// and missing ones fall back to the default locale
+#[cfg(feature = "gui")]
+pub const LOCALES_FOLDER: &str = "locales";
+// languages written right to left, sorted
+#[cfg(feature = "gui")]
+const RTL_LANGUAGES: [&str; 5] = ["ar", "fa", "he", "ps", "ur"];
 
 pub struct LocaleEntry {
 	pub locale: String,
@@ -37,7 +48,8 @@ pub struct I18n {
 
 impl I18n
 {
-	pub fn new(locale: &str) -> Result<Self>
+	#[cfg_attr(not(feature = "gui"), allow(unused_variables))]
+	pub fn new(locale: &str, locales_dir: &Path) -> Result<Self>
 	{
 		let mut bundles = HashMap::new();
 		let mut locale_list = vec![];
@@ -56,6 +68,10 @@ impl I18n
 				bundles.insert(name.to_string(), bundle);
 			}
 		}
+		#[cfg(feature = "gui")]
+		if let Err(err) = load_user_locales(locales_dir, &mut bundles, &mut locale_list) {
+			eprintln!("Failed load locales in {}: {}", locales_dir.display(), err);
+		}
 		let locale = if bundles.contains_key(locale) {
 			locale
 		} else {
@@ -67,7 +83,9 @@ impl I18n
 	pub fn msg(&self, key: &str) -> Cow<str>
 	{
 		let bundle = self.bundles.get(&self.locale).unwrap();
-		bundle_msg(bundle, key, None).expect(&format!("No {key} defined in {}", self.locale))
+		bundle_msg(bundle, key, None)
+			.or_else(|| bundle_msg(self.default_bundle(), key, None))
+			.expect(&format!("No {key} defined in {}", self.locale))
 	}
 
 	pub fn args_msg<'a>(&self, key: &str, msg_args: Vec<(&'a str, impl Into<FluentValue<'a>>)>) -> String
@@ -77,15 +95,115 @@ impl I18n
 		for (name, value) in msg_args {
 			args.set(name, value);
 		}
-		let msg = bundle_msg(bundle, key, Some(&args)).expect(&format!("No {key} defined in {}", self.locale));
+		let msg = bundle_msg(bundle, key, Some(&args))
+			.or_else(|| bundle_msg(self.default_bundle(), key, Some(&args)))
+			.expect(&format!("No {key} defined in {}", self.locale));
 		msg.to_string()
 	}
 
+	/// number with digits grouped by three, for the shipped locales which
+	/// define the separators, other locales get plain digits, for their
+	/// grouping may differ
+	pub fn format_number(&self, value: f64, decimals: usize) -> String
+	{
+		let bundle = self.bundles.get(&self.locale).unwrap();
+		let (Some(group_separator), Some(decimal_separator)) = (
+			bundle_msg(bundle, "number-group-separator", None),
+			bundle_msg(bundle, "number-decimal-separator", None)) else {
+			return format!("{:.*}", decimals, value);
+		};
+		let text = format!("{:.*}", decimals, value.abs());
+		let (integer, fraction) = text.split_once('.')
+			.unwrap_or((&text, ""));
+		let mut formatted = String::new();
+		if value < 0. && text.chars().any(|ch| ch != '0' && ch != '.') {
+			formatted.push('-');
+		}
+		for (index, ch) in integer.chars().enumerate() {
+			if index > 0 && (integer.len() - index) % 3 == 0 {
+				formatted.push_str(&group_separator);
+			}
+			formatted.push(ch);
+		}
+		if !fraction.is_empty() {
+			formatted.push_str(&decimal_separator);
+			formatted.push_str(fraction);
+		}
+		formatted
+	}
+
+	#[inline]
+	fn default_bundle(&self) -> &FluentBundle<FluentResource>
+	{
+		self.bundles.get(DEFAULT_LOCALE).unwrap()
+	}
+
 	#[inline]
 	pub fn locales(&self) -> &Vec<LocaleEntry>
 	{
 		&self.locale_list
 	}
+
+	/// ui mirrored for the locale
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn is_rtl(&self) -> bool
+	{
+		let language = self.locale.split(['_', '-']).next().unwrap_or(&self.locale);
+		RTL_LANGUAGES.binary_search(&language).is_ok()
+	}
+}
+
+/// broken files skipped with error printed
+#[cfg(feature = "gui")]
+fn load_user_locales(locales_dir: &Path,
+	bundles: &mut HashMap<String, FluentBundle<FluentResource>>,
+	locale_list: &mut Vec<LocaleEntry>) -> Result<()>
+{
+	if !locales_dir.is_dir() {
+		return Ok(());
+	}
+	for entry in fs::read_dir(locales_dir)? {
+		let path = entry?.path();
+		if path.extension().map_or(true, |ext| ext != "ftl") {
+			continue;
+		}
+		let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+			continue;
+		};
+		let Ok(langid) = name.parse::<LanguageIdentifier>() else {
+			eprintln!("Invalid locale name: {}", path.display());
+			continue;
+		};
+		let text = fs::read_to_string(&path)?;
+		let res = match FluentResource::try_new(text) {
+			Ok(res) => res,
+			Err((_, errors)) => {
+				eprintln!("Failed parse {}: {:?}", path.display(), errors);
+				continue;
+			}
+		};
+		if let Some(bundle) = bundles.get_mut(name) {
+			bundle.add_resource_overriding(res);
+			if let Some(locale_name) = bundle_msg(bundle, "title", None) {
+				let locale_name = locale_name.to_string();
+				if let Some(entry) = locale_list.iter_mut().find(|entry| entry.locale == name) {
+					entry.name = locale_name;
+				}
+			}
+		} else {
+			let mut bundle = FluentBundle::new(vec![langid]);
+			if let Err(errors) = bundle.add_resource(res) {
+				eprintln!("Failed load {}: {:?}", path.display(), errors);
+				continue;
+			}
+			let locale_name = bundle_msg(&bundle, "title", None)
+				.map_or_else(|| name.to_owned(), |title| title.to_string());
+			locale_list.push(LocaleEntry::new(name, &locale_name));
+			bundles.insert(name.to_owned(), bundle);
+		}
+	}
+	Ok(())
 }
 
 fn bundle_msg<'a>(bundle: &'a FluentBundle<FluentResource>, key: &str, args: Option<&'a FluentArgs>) -> Option<Cow<'a, str>>
@@ -95,4 +213,37 @@ fn bundle_msg<'a>(bundle: &'a FluentBundle<FluentResource>, key: &str, args: Opt
 	let mut errors = vec![];
 	let text = bundle.format_pattern(pattern, args, &mut errors);
 	Some(text)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+	use crate::i18n::I18n;
+
+	fn format(locale: &str, value: f64, decimals: usize) -> String
+	{
+		I18n::new(locale, Path::new("")).unwrap().format_number(value, decimals)
+	}
+
+	#[test]
+	fn format_number_en_us()
+	{
+		assert_eq!(format("en_US", 0., 0), "0");
+		assert_eq!(format("en_US", 999., 0), "999");
+		assert_eq!(format("en_US", 1234567., 0), "1,234,567");
+		assert_eq!(format("en_US", 1234.56, 1), "1,234.6");
+		assert_eq!(format("en_US", -1234., 0), "-1,234");
+		assert_eq!(format("en_US", -0.01, 1), "0.0");
+	}
+
+	#[test]
+	fn format_number_zh_cn()
+	{
+		assert_eq!(format("zh_CN", 0., 0), "0");
+		assert_eq!(format("zh_CN", 999., 0), "999");
+		assert_eq!(format("zh_CN", 1234567., 0), "1,234,567");
+		assert_eq!(format("zh_CN", 1234.56, 1), "1,234.6");
+		assert_eq!(format("zh_CN", -1234., 0), "-1,234");
+		assert_eq!(format("zh_CN", -0.01, 1), "0.0");
+	}
+}
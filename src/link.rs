@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "gui")]
+use crate::config::ReadingInfo;
+
+/// link to a reading position, for sharing between machines or referencing
+/// in notes, like: ter:///path/to/book.epub?book=0&chapter=3&line=12&offset=5
+pub const LINK_SCHEME: &str = "ter";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionLink {
+	pub filename: String,
+	pub inner_book: usize,
+	pub chapter: usize,
+	pub line: usize,
+	pub offset: usize,
+}
+
+impl PositionLink {
+	#[inline]
+	#[cfg(feature = "gui")]
+	pub fn of(reading: &ReadingInfo) -> Self
+	{
+		PositionLink {
+			filename: reading.filename.clone(),
+			inner_book: reading.inner_book,
+			chapter: reading.chapter,
+			line: reading.line,
+			offset: reading.position,
+		}
+	}
+
+	#[inline]
+	pub fn is_link(text: &str) -> bool
+	{
+		text.len() > LINK_SCHEME.len()
+			&& text[..LINK_SCHEME.len()].eq_ignore_ascii_case(LINK_SCHEME)
+			&& text[LINK_SCHEME.len()..].starts_with("://")
+	}
+
+	/// missing position parts are 0
+	pub fn parse(text: &str) -> Option<Self>
+	{
+		if !Self::is_link(text) {
+			return None;
+		}
+		let text = &text[LINK_SCHEME.len() + 3..];
+		let (path, query) = text.split_once('?').unwrap_or((text, ""));
+		let filename = percent_decode(path)?;
+		if filename.is_empty() {
+			return None;
+		}
+		let mut link = PositionLink {
+			filename,
+			inner_book: 0,
+			chapter: 0,
+			line: 0,
+			offset: 0,
+		};
+		for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+			let (name, value) = pair.split_once('=')?;
+			let value = value.parse().ok()?;
+			match name {
+				"book" => link.inner_book = value,
+				"chapter" => link.chapter = value,
+				"line" => link.line = value,
+				"offset" => link.offset = value,
+				_ => {}
+			}
+		}
+		Some(link)
+	}
+}
+
+impl Display for PositionLink {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		let path = percent_encode(&self.filename);
+		// absolute path of windows without the leading slash
+		let slash = if path.starts_with('/') { "" } else { "/" };
+		write!(f, "{}://{}{}?book={}&chapter={}&line={}&offset={}",
+			LINK_SCHEME, slash, path, self.inner_book, self.chapter,
+			self.line, self.offset)
+	}
+}
+
+/// path separators kept for readability
+fn percent_encode(text: &str) -> String
+{
+	let mut encoded = String::new();
+	for byte in text.bytes() {
+		if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+			encoded.push(byte as char);
+		} else {
+			encoded.push_str(&format!("%{:02X}", byte));
+		}
+	}
+	encoded
+}
+
+fn percent_decode(text: &str) -> Option<String>
+{
+	let bytes = text.as_bytes();
+	let mut decoded = vec![];
+	let mut index = 0;
+	while index < bytes.len() {
+		if bytes[index] == b'%' {
+			let hex = text.get(index + 1..index + 3)?;
+			decoded.push(u8::from_str_radix(hex, 16).ok()?);
+			index += 3;
+		} else {
+			decoded.push(bytes[index]);
+			index += 1;
+		}
+	}
+	let decoded = String::from_utf8(decoded).ok()?;
+	// windows path after the leading slash, like /C:/books
+	let windows_path = decoded.len() > 2
+		&& decoded.as_bytes()[2] == b':'
+		&& decoded.as_bytes()[1].is_ascii_alphabetic();
+	if windows_path {
+		Some(decoded[1..].to_owned())
+	} else {
+		Some(decoded)
+	}
+}
@@ -5,17 +5,19 @@ extern crate core;
 extern crate markup5ever;
 
 use std::env;
-use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, bail, Result};
 use clap::Parser;
 use dirs::{cache_dir, config_dir};
 use rust_embed::RustEmbed;
 
 use crate::book::BookLoader;
 use crate::common::Position;
-use crate::config::load_config;
-use crate::container::ContainerManager;
+use crate::config::{export_backup, import_backup, load_config};
+use crate::container::{ContainerManager, DEFAULT_STDIN_FORMAT, read_stdin, set_format, STDIN_FILENAME};
 #[cfg(feature = "i18n")]
 use crate::i18n::I18n;
+use crate::link::PositionLink;
 
 mod terminal;
 mod common;
@@ -33,8 +35,17 @@ mod color;
 mod open;
 mod config;
 mod xhtml;
+mod download;
+mod link;
+mod secret;
+mod segment;
+mod crash;
+#[cfg(feature = "script")]
+mod script;
 
 const TBR_BOOK_ENV_KEY: &str = "TBR_BOOK";
+const TBR_CONFIG_DIR_ENV_KEY: &str = "TBR_CONFIG_DIR";
+const TBR_CACHE_DIR_ENV_KEY: &str = "TBR_CACHE_DIR";
 
 #[macro_export]
 macro_rules! description {
@@ -63,6 +74,37 @@ struct Cli {
 		help = "Using terminal to read e-book, by default if gui exists, tbr will using gui view."
 	)]
 	terminal: bool,
+	#[clap(
+		long,
+		value_name = "DIR",
+		help = "Directory of config, themes and history, overrides TBR_CONFIG_DIR."
+	)]
+	config_dir: Option<PathBuf>,
+	#[clap(
+		long,
+		value_name = "DIR",
+		help = "Directory of cache, overrides TBR_CACHE_DIR."
+	)]
+	cache_dir: Option<PathBuf>,
+	#[clap(
+		long,
+		value_name = "ARCHIVE",
+		help = "Export config and reading history to archive, then exit."
+	)]
+	export: Option<String>,
+	#[clap(
+		long,
+		value_name = "ARCHIVE",
+		conflicts_with = "export",
+		help = "Restore config and reading history from archive created by --export, then exit."
+	)]
+	import: Option<String>,
+	#[clap(
+		long,
+		value_name = "FORMAT",
+		help = "Format of the book, like txt, html, epub or haodoo, for file of unknown extension or read from stdin by filename -, txt by default for stdin."
+	)]
+	format: Option<String>,
 	filename: Option<String>,
 }
 
@@ -78,36 +120,99 @@ struct Asset;
 
 fn main() -> Result<()> {
 	let cli = Cli::parse();
-	let config_dir = match config_dir() {
+	let config_dir = match custom_dir(cli.config_dir, TBR_CONFIG_DIR_ENV_KEY)
+		.or_else(|| config_dir().map(|x| x.join(package_name!()))) {
 		None => return Err(anyhow!("Can not find config dir.")),
-		Some(x) => x.join(package_name!()),
+		Some(x) => x,
 	};
-	let cache_dir = match cache_dir() {
+	let cache_dir = match custom_dir(cli.cache_dir, TBR_CACHE_DIR_ENV_KEY)
+		.or_else(|| cache_dir().map(|x| x.join(package_name!()))) {
 		None => return Err(anyhow!("Can not find cache dir.")),
-		Some(x) => x.join(package_name!()),
+		Some(x) => x,
 	};
 	let config_file = config_dir.join("tbr.toml");
-	let filename = cli.filename
+	#[cfg(feature = "plugin")]
+	for error in book::plugin::init(&config_dir.join(book::plugin::PLUGINS_FOLDER)) {
+		eprintln!("{}", error);
+	}
+	if let Some(archive) = cli.export {
+		return export_backup(&config_file, &config_dir, &archive);
+	}
+	if let Some(archive) = cli.import {
+		return import_backup(&archive, &config_file, &config_dir);
+	}
+	let from_stdin = cli.filename.as_deref() == Some(STDIN_FILENAME);
+	let filename = if from_stdin {
+		let format = cli.format.as_deref().unwrap_or(DEFAULT_STDIN_FORMAT);
+		Some(read_stdin(format, &BookLoader::default())?)
+	} else {
+		cli.filename
+	};
+	let filename = filename
 		.map_or_else(
 			|| env::var(TBR_BOOK_ENV_KEY).map_or(None, |name| {
 				Some(name)
 			}),
 			|name| Some(name));
+	let link = filename.as_deref().and_then(PositionLink::parse);
+	let filename = match (filename, &link) {
+		(_, Some(link)) => {
+			if !Path::new(&link.filename).is_file() {
+				bail!("Book of the link not found: {}", link.filename);
+			}
+			Some(link.filename.clone())
+		}
+		(Some(url), _) if download::is_url(&url) => {
+			println!("Downloading {} ...", url);
+			let path = download::download(&url, &cache_dir)?;
+			Some(path.to_string_lossy().to_string())
+		}
+		(filename, _) => filename,
+	};
+	// format of stdin content applied by the pseudo filename
+	let format = cli.format.filter(|_| !from_stdin
+		&& filename.as_ref().map_or(false, |filename| Path::new(filename).is_file()));
+	#[cfg(feature = "gui")]
+	let book_specified = filename.is_some();
 	#[allow(unused_mut)]
 		let (mut current, mut configuration) = load_config(
 		filename,
 		config_file,
 		&config_dir,
 		&cache_dir)?;
+	crash::install_hook(&config_dir, &configuration);
+	if let (Some(format), Some(current)) = (&format, &current) {
+		if BookLoader::default().format_filename(current, format).is_none() {
+			bail!("Not support format: {}", format);
+		}
+		set_format(current, format);
+	}
+	if let Some(label) = &configuration.zip_name_encoding {
+		if !common::set_zip_name_encoding(label) {
+			eprintln!("Unknown encoding of zip entry names: {}", label);
+		}
+	}
+	// book of the link opened by canonical path
+	let link = link.zip(current.clone())
+		.map(|(link, filename)| PositionLink { filename, ..link });
 	#[cfg(feature = "gui")]
 	if !cli.terminal {
-		if let Some((curr, c)) = gui::start(current, configuration)? {
+		if let Some((curr, c)) = gui::start(current, configuration, book_specified, link.clone())? {
 			current = curr;
 			configuration = c;
 		} else {
 			return Ok(());
 		}
 	}
-	terminal::start(current, configuration, config_dir)?;
+	terminal::start(current, configuration, config_dir, link)?;
 	Ok(())
 }
+
+/// dir specified in command line, or by env
+#[inline]
+fn custom_dir(dir: Option<PathBuf>, env_key: &str) -> Option<PathBuf>
+{
+	dir.or_else(|| env::var_os(env_key)
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from))
+}
@@ -1,9 +1,12 @@
 use std::{env, fs};
 use std::path::PathBuf;
-use anyhow::Result;
+use std::process::{Command, Stdio};
+use anyhow::{bail, Result};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 
+const FFMPEG_COMMAND: &str = "ffmpeg";
+
 pub struct Opener {
 	files: Vec<PathBuf>,
 }
@@ -16,7 +19,20 @@ impl Default for Opener {
 }
 
 impl Opener {
+	#[inline]
 	pub fn open_image(&mut self, path: &str, bytes: &[u8]) -> Result<()>
+	{
+		self.open_tmp(path, bytes)
+	}
+
+	/// save audio or video to temp file and open with system player
+	#[inline]
+	pub fn open_media(&mut self, path: &str, bytes: &[u8]) -> Result<()>
+	{
+		self.open_tmp(path, bytes)
+	}
+
+	fn open_tmp(&mut self, path: &str, bytes: &[u8]) -> Result<()>
 	{
 		if let Some(ext_idx) = path.rfind('.') {
 			let ext = &path[ext_idx..];
@@ -28,6 +44,40 @@ impl Opener {
 		Ok(())
 	}
 
+	/// save audio to temp file for media player
+	pub fn audio_file(&mut self, path: &str, bytes: &[u8]) -> Result<PathBuf>
+	{
+		let ext = path.rfind('.').map_or("", |ext_idx| &path[ext_idx..]);
+		let tmp_file_path = create_tmp(&env::temp_dir(), ext, bytes)?;
+		self.files.push(tmp_file_path.clone());
+		Ok(tmp_file_path)
+	}
+
+	/// audio played at rate, tempo changed by ffmpeg with pitch kept
+	pub fn audio_file_at_rate(&mut self, path: &str, bytes: &[u8], rate: f64) -> Result<PathBuf>
+	{
+		let source = self.audio_file(path, bytes)?;
+		if rate == 1.0 {
+			return Ok(source);
+		}
+		let ext = path.rfind('.').map_or("", |ext_idx| &path[ext_idx..]);
+		let target = create_tmp(&env::temp_dir(), ext, &[])?;
+		self.files.push(target.clone());
+		let status = Command::new(FFMPEG_COMMAND)
+			.args(["-y", "-loglevel", "error", "-i"])
+			.arg(&source)
+			.arg("-filter:a")
+			.arg(format!("atempo={}", rate))
+			.arg(&target)
+			.stdin(Stdio::null())
+			.status();
+		match status {
+			Ok(status) if status.success() => Ok(target),
+			Ok(status) => bail!("Failed change audio speed with {}: {}", FFMPEG_COMMAND, status),
+			Err(err) => bail!("Failed run {}: {}", FFMPEG_COMMAND, err),
+		}
+	}
+
 	pub fn open_link(&mut self, url: &str) -> Result<()>
 	{
 		if url.starts_with("http://") || url.starts_with("https://") {
@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Result};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, FuncArgs, Scope};
+
+// user script in config dir, loaded if exists
+pub const SCRIPT_FILE: &str = "script.rhai";
+
+const BOOK_OPENED_HOOK: &str = "on_book_opened";
+const PAGE_TURNED_HOOK: &str = "on_page_turned";
+// only gui has text selection
+#[cfg(feature = "gui")]
+const TEXT_SELECTED_HOOK: &str = "on_text_selected";
+
+/// requests from script, applied to controller after the hook returned
+pub enum ScriptCommand {
+	Goto { chapter: usize, line: usize, offset: usize },
+	Search(String),
+}
+
+/// rhai script with hooks of reading events, hooks are optional:
+///   on_book_opened(filename, title)
+///   on_page_turned(chapter, line, offset)
+///   on_text_selected(text, chapter, line)
+/// and functions for driving the reader:
+///   goto(chapter, line, offset), search(pattern), append_file(path, text)
+pub struct Script {
+	engine: Engine,
+	ast: AST,
+	hooks: HashSet<String>,
+	commands: Rc<RefCell<Vec<ScriptCommand>>>,
+}
+
+impl Script {
+	pub fn load(path: &Path) -> Result<Option<Self>>
+	{
+		if !path.is_file() {
+			return Ok(None);
+		}
+		let commands = Rc::new(RefCell::new(vec![]));
+		let mut engine = Engine::new();
+		{
+			let commands = commands.clone();
+			engine.register_fn("goto", move |chapter: i64, line: i64, offset: i64| {
+				commands.borrow_mut().push(ScriptCommand::Goto {
+					chapter: chapter.max(0) as usize,
+					line: line.max(0) as usize,
+					offset: offset.max(0) as usize,
+				});
+			});
+		}
+		{
+			let commands = commands.clone();
+			engine.register_fn("search", move |pattern: &str| {
+				commands.borrow_mut().push(ScriptCommand::Search(pattern.to_owned()));
+			});
+		}
+		// for logging quotes, appended immediately
+		engine.register_fn("append_file", |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+			let mut file = OpenOptions::new()
+				.create(true)
+				.append(true)
+				.open(path)
+				.map_err(|err| err.to_string())?;
+			writeln!(file, "{}", text).map_err(|err| err.to_string())?;
+			Ok(())
+		});
+		let ast = engine.compile_file(path.to_path_buf())
+			.map_err(|err| anyhow!("Failed load script {}: {}", path.display(), err))?;
+		let hooks = ast.iter_functions()
+			.map(|function| function.name.to_owned())
+			.collect();
+		Ok(Some(Script { engine, ast, hooks, commands }))
+	}
+
+	#[inline]
+	pub fn book_opened(&self, filename: &str, title: &str) -> Result<Vec<ScriptCommand>>
+	{
+		self.call(BOOK_OPENED_HOOK, (filename.to_owned(), title.to_owned()))
+	}
+
+	#[inline]
+	pub fn page_turned(&self, chapter: usize, line: usize, offset: usize) -> Result<Vec<ScriptCommand>>
+	{
+		self.call(PAGE_TURNED_HOOK, (chapter as i64, line as i64, offset as i64))
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn text_selected(&self, text: &str, chapter: usize, line: usize) -> Result<Vec<ScriptCommand>>
+	{
+		self.call(TEXT_SELECTED_HOOK, (text.to_owned(), chapter as i64, line as i64))
+	}
+
+	fn call(&self, hook: &str, args: impl FuncArgs) -> Result<Vec<ScriptCommand>>
+	{
+		if !self.hooks.contains(hook) {
+			return Ok(vec![]);
+		}
+		// return value of hook ignored
+		let result = self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, hook, args);
+		let commands = self.commands.take();
+		if let Err(err) = result {
+			bail!("Script {} failed: {}", hook, err);
+		}
+		Ok(commands)
+	}
+}
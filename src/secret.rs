@@ -0,0 +1,174 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use aes::Aes256;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::cipher::generic_array::GenericArray;
+use anyhow::{anyhow, bail, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const CIPHER_KEY_SIZE: usize = 32;
+// hmac key of sha256 block size
+const MAC_KEY_SIZE: usize = 64;
+const KEY_FILE_SIZE: usize = CIPHER_KEY_SIZE + MAC_KEY_SIZE;
+const NONCE_SIZE: usize = 16;
+const TAG_SIZE: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// random key encrypting passwords saved in history db, the key file
+/// kept apart from the db, so a copied db reveals no password
+pub struct SecretKey {
+	cipher: [u8; CIPHER_KEY_SIZE],
+	mac: [u8; MAC_KEY_SIZE],
+}
+
+impl SecretKey {
+	/// None if key file not created yet
+	pub fn load(path: &Path) -> Result<Option<Self>>
+	{
+		if !path.exists() {
+			return Ok(None);
+		}
+		let mut bytes = vec![];
+		File::open(path)?.read_to_end(&mut bytes)?;
+		if bytes.len() != KEY_FILE_SIZE {
+			bail!("Invalid key file: {}", path.display());
+		}
+		Ok(Some(Self::from_bytes(&bytes)))
+	}
+
+	pub fn load_or_create(path: &Path) -> Result<Self>
+	{
+		if let Some(key) = Self::load(path)? {
+			return Ok(key);
+		}
+		let mut bytes = [0; KEY_FILE_SIZE];
+		random(&mut bytes)?;
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+		// readable by owner only
+		#[cfg(unix)]
+		options.mode(0o600);
+		options.open(path)?.write_all(&bytes)?;
+		Ok(Self::from_bytes(&bytes))
+	}
+
+	#[inline]
+	fn from_bytes(bytes: &[u8]) -> Self
+	{
+		let mut cipher = [0; CIPHER_KEY_SIZE];
+		let mut mac = [0; MAC_KEY_SIZE];
+		cipher.copy_from_slice(&bytes[..CIPHER_KEY_SIZE]);
+		mac.copy_from_slice(&bytes[CIPHER_KEY_SIZE..]);
+		SecretKey { cipher, mac }
+	}
+
+	/// nonce, encrypted text, then tag of both
+	pub fn encrypt(&self, text: &str) -> Result<Vec<u8>>
+	{
+		let mut nonce = [0; NONCE_SIZE];
+		random(&mut nonce)?;
+		let mut data = text.as_bytes().to_vec();
+		self.apply_keystream(&nonce, &mut data);
+		data.splice(0..0, nonce);
+		let tag = self.mac(&data);
+		data.extend_from_slice(&tag);
+		Ok(data)
+	}
+
+	/// None if not encrypted by this key or modified
+	pub fn decrypt(&self, data: &[u8]) -> Option<String>
+	{
+		if data.len() < NONCE_SIZE + TAG_SIZE {
+			return None;
+		}
+		let (data, tag) = data.split_at(data.len() - TAG_SIZE);
+		let mut mac = self.hmac();
+		mac.update(data);
+		mac.verify_slice(tag).ok()?;
+		let (nonce, text) = data.split_at(NONCE_SIZE);
+		let nonce: &[u8; NONCE_SIZE] = nonce.try_into().ok()?;
+		let mut text = text.to_vec();
+		self.apply_keystream(nonce, &mut text);
+		String::from_utf8(text).ok()
+	}
+
+	/// aes-256 in counter mode, the nonce as initial counter block
+	fn apply_keystream(&self, nonce: &[u8; NONCE_SIZE], data: &mut [u8])
+	{
+		let cipher = Aes256::new(GenericArray::from_slice(&self.cipher));
+		let mut counter = u128::from_be_bytes(*nonce);
+		for chunk in data.chunks_mut(NONCE_SIZE) {
+			let mut block = GenericArray::from(counter.to_be_bytes());
+			cipher.encrypt_block(&mut block);
+			for (byte, key) in chunk.iter_mut().zip(block.iter()) {
+				*byte ^= key;
+			}
+			counter = counter.wrapping_add(1);
+		}
+	}
+
+	#[inline]
+	fn hmac(&self) -> HmacSha256
+	{
+		<HmacSha256 as Mac>::new(GenericArray::from_slice(&self.mac))
+	}
+
+	#[inline]
+	fn mac(&self, data: &[u8]) -> Vec<u8>
+	{
+		let mut mac = self.hmac();
+		mac.update(data);
+		mac.finalize().into_bytes().to_vec()
+	}
+}
+
+#[inline]
+fn random(buf: &mut [u8]) -> Result<()>
+{
+	getrandom::getrandom(buf)
+		.map_err(|err| anyhow!("Failed generate random bytes: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::secret::{KEY_FILE_SIZE, NONCE_SIZE, SecretKey, TAG_SIZE};
+
+	fn key(seed: u8) -> SecretKey
+	{
+		let bytes = (0..KEY_FILE_SIZE)
+			.map(|i| (i as u8).wrapping_mul(seed))
+			.collect::<Vec<_>>();
+		SecretKey::from_bytes(&bytes)
+	}
+
+	#[test]
+	fn round_trip()
+	{
+		let key = key(3);
+		for password in ["", "secret", "a password longer than one aes block, 密码"] {
+			let data = key.encrypt(password).unwrap();
+			assert_eq!(data.len(), NONCE_SIZE + password.len() + TAG_SIZE);
+			if !password.is_empty() {
+				assert_ne!(&data[NONCE_SIZE..NONCE_SIZE + password.len()], password.as_bytes());
+			}
+			assert_eq!(key.decrypt(&data).as_deref(), Some(password));
+		}
+	}
+
+	#[test]
+	fn rejected()
+	{
+		let data = key(3).encrypt("secret").unwrap();
+		assert_eq!(key(5).decrypt(&data), None);
+		let mut modified = data.clone();
+		modified[NONCE_SIZE] ^= 1;
+		assert_eq!(key(3).decrypt(&modified), None);
+		assert_eq!(key(3).decrypt(&data[..10]), None);
+	}
+}
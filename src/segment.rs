@@ -0,0 +1,120 @@
+use std::ops::Range;
+#[cfg(feature = "segment")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "segment")]
+use jieba_rs::Jieba;
+
+use crate::book::TEXT_SELECTION_SPLITTER;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+	Splitter,
+	Han,
+	Hiragana,
+	Katakana,
+	Other,
+}
+
+#[inline]
+fn script(ch: char) -> Script
+{
+	if TEXT_SELECTION_SPLITTER.binary_search(&ch).is_ok() {
+		return Script::Splitter;
+	}
+	match ch {
+		'\u{3005}' | '\u{3007}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}'
+		| '\u{f900}'..='\u{faff}' | '\u{20000}'..='\u{2ebef}' => Script::Han,
+		'\u{3041}'..='\u{309f}' => Script::Hiragana,
+		// with prolonged sound mark
+		'\u{30a0}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => Script::Katakana,
+		_ => Script::Other,
+	}
+}
+
+#[cfg(feature = "segment")]
+fn jieba() -> &'static Jieba
+{
+	static JIEBA: OnceLock<Jieba> = OnceLock::new();
+	JIEBA.get_or_init(Jieba::new)
+}
+
+/// words of han text, a word for every char without segmenter
+fn han_words(chars: &[char], start: usize, words: &mut Vec<Range<usize>>)
+{
+	#[cfg(feature = "segment")]
+	{
+		let text = chars.iter().collect::<String>();
+		let mut from = start;
+		for word in jieba().cut(&text, false) {
+			let to = from + word.chars().count();
+			words.push(from..to);
+			from = to;
+		}
+	}
+	#[cfg(not(feature = "segment"))]
+	for index in 0..chars.len() {
+		words.push(start + index..start + index + 1);
+	}
+}
+
+/// split chars into words, splitters are words of single char,
+/// text between splitters cut at script changes, han text segmented
+pub fn words(chars: &[char]) -> Vec<Range<usize>>
+{
+	let mut words = vec![];
+	let mut start = 0;
+	while start < chars.len() {
+		let current = script(chars[start]);
+		let mut end = start + 1;
+		if current != Script::Splitter {
+			while end < chars.len() && script(chars[end]) == current {
+				end += 1;
+			}
+		}
+		if current == Script::Han {
+			han_words(&chars[start..end], start, &mut words);
+		} else {
+			words.push(start..end);
+		}
+		start = end;
+	}
+	words
+}
+
+/// range of the word contains the char at offset
+#[inline]
+pub fn word_at(chars: &[char], offset: usize) -> Option<Range<usize>>
+{
+	if offset >= chars.len() {
+		return None;
+	}
+	if script(chars[offset]) == Script::Splitter {
+		return Some(offset..offset + 1);
+	}
+	// only the text between splitters needed
+	let mut from = offset;
+	while from > 0 && script(chars[from - 1]) != Script::Splitter {
+		from -= 1;
+	}
+	let mut to = offset + 1;
+	while to < chars.len() && script(chars[to]) != Script::Splitter {
+		to += 1;
+	}
+	words(&chars[from..to])
+		.into_iter()
+		.find(|word| word.contains(&(offset - from)))
+		.map(|word| word.start + from..word.end + from)
+}
+
+/// word boundaries of chars, true at the index where a word starts or ends,
+/// with len + 1 entries
+pub fn word_bounds(chars: &[char]) -> Vec<bool>
+{
+	let mut bounds = vec![false; chars.len() + 1];
+	for word in words(chars) {
+		bounds[word.start] = true;
+		bounds[word.end] = true;
+	}
+	bounds
+}
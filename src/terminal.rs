@@ -1,7 +1,11 @@
 use std::fs;
+use std::io;
+use std::io::Write;
 use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use cursive::Cursive;
 use cursive::CursiveExt;
 use cursive::event::{Callback, Event};
@@ -15,6 +19,10 @@ use view::ReadingView;
 
 use crate::{Asset, description, version, version_string};
 use crate::config::{BookLoadingInfo, Configuration};
+use crate::container::password_required;
+use crate::link::PositionLink;
+#[cfg(feature = "script")]
+use crate::script::Script;
 use crate::list::{list_dialog, ListIterator};
 use crate::terminal::input_method::{InputMethod, setup_im};
 
@@ -28,6 +36,10 @@ const INPUT_VIEW_NAME: &str = "input";
 const INPUT_LAYOUT_NAME: &str = "input_layout";
 const SEARCH_LABEL_TEXT: &str = "Search: ";
 const GOTO_LABEL_TEXT: &str = "Goto line: ";
+const GOTO_PAGE_LABEL_TEXT: &str = "Goto page: ";
+const THEME_FILES: [&str; 2] = ["dark.toml", "bright.toml"];
+// interval checking theme files edited while running
+const THEME_WATCH_MILLIS: u64 = 1000;
 
 struct Themes {
 	bright: Theme,
@@ -72,18 +84,46 @@ impl<'a> Listable for (&'a str, usize) {
 }
 
 pub fn start(current: Option<String>, mut configuration: Configuration,
-	config_dir: PathBuf) -> Result<()>
+	config_dir: PathBuf, link: Option<PositionLink>) -> Result<()>
 {
 	let current = current.ok_or(anyhow!("No file to open."))?;
 	println!("Loading {} ...", current);
-	let loading = configuration.reading(&current)?;
+	let mut password = configuration.password(&current)?;
+	let mut remember = false;
+	let mut reading_view = loop {
+		let loading = configuration.reading(&current)?;
+		match ReadingView::new(configuration.render_han, loading, password.clone()) {
+			Err(err) if password_required(&err) => {
+				let (input, save) = ask_password(&current, password.is_some())?;
+				password = Some(input);
+				remember = save;
+			}
+			result => break result?,
+		}
+	};
+	if let (true, Some(password)) = (remember, &password) {
+		configuration.save_password(&current, password)?;
+	}
+	reading_view.set_search_fold_width(configuration.search_fold_width);
+	reading_view.set_chapter_continuation(configuration.chapter_continuation);
+	reading_view.set_han_column_spacing(configuration.han_column_spacing);
+	if let Some((trace, current)) = configuration.trace(&reading_view.reading_info())? {
+		reading_view.restore_trace(trace, current);
+	}
+	if let Some(link) = link {
+		reading_view.goto_position(&link)?;
+	}
+	#[cfg(feature = "script")]
+	if let Some(script) = Script::load(&configuration.script_file())? {
+		reading_view.attach_script(script)?;
+	}
 	let mut app = Cursive::new();
 	let themes = load_themes(&config_dir)?;
 	let theme = themes.get(configuration.dark_theme);
 	app.set_theme(theme.clone());
-	let reading_view = ReadingView::new(configuration.render_han, loading)?;
 	// turn off ime at start
 	let im = setup_im();
+	let history_problem = configuration.take_history_problem();
 	app.set_user_data(TerminalContext { current, configuration, themes, im });
 	let status_view = LinearLayout::horizontal()
 		.child(TextView::new(&reading_view.status_msg())
@@ -98,8 +138,11 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 			.on_event('q', |s| s.quit())
 			.on_event('v', |s| update_status(s, version_string!()))
 			.on_event('g', |s| goto_line(s))
+			.on_event('p', |s| goto_page(s))
 			.on_event('b', |s| select_book(s))
 			.on_event('h', |s| select_history(s))
+			.on_event('[', |s| open_sibling(s, false))
+			.on_event(']', |s| open_sibling(s, true))
 			.on_event('t', |s| switch_theme(s))
 			.on_event('c', move |s| {
 				let reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
@@ -124,16 +167,58 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 			}))
 		.child(status_view);
 	app.add_fullscreen_layer(layout);
+	if let Some(problem) = history_problem {
+		update_status(&mut app, &problem.to_string());
+	}
+	watch_themes(&app, config_dir);
 	app.run();
 	let reading_view: ViewRef<ReadingView> = app.find_name(TEXT_VIEW_NAME).unwrap();
 	let mut reading_now = reading_view.reading_info();
+	let (trace, current_trace) = reading_view.trace();
+	let at_end = reading_view.at_end();
 	let controller_context: TerminalContext = app.take_user_data().unwrap();
 	configuration = controller_context.configuration;
-	configuration.save_reading(&mut reading_now)?;
+	if at_end {
+		if let Err(err) = configuration.mark_finished(&reading_now.filename) {
+			eprintln!("Failed save finished state: {}", err);
+		}
+	}
+	// config still saved when history failed
+	if let Err(err) = configuration.save_reading(&mut reading_now) {
+		eprintln!("Failed save reading history: {}", err);
+	}
+	if let Err(err) = configuration.save_trace(&reading_now, &trace, current_trace) {
+		eprintln!("Failed save trace: {}", err);
+	}
 	configuration.save()?;
 	Ok(())
 }
 
+/// read password from stdin before cursive started, empty for quit
+fn ask_password(filename: &str, retry: bool) -> Result<(String, bool)>
+{
+	#[inline]
+	fn read_line(prompt: &str) -> Result<String>
+	{
+		print!("{}", prompt);
+		io::stdout().flush()?;
+		let mut line = String::new();
+		io::stdin().read_line(&mut line)?;
+		Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+	}
+
+	if retry {
+		println!("Invalid password.");
+	}
+	println!("{} is encrypted.", filename);
+	let password = read_line("Password (empty for quit): ")?;
+	if password.is_empty() {
+		bail!("No password for encrypted file: {}", filename);
+	}
+	let remember = read_line("Remember the password? [y/N]: ")?;
+	Ok((password, remember.eq_ignore_ascii_case("y")))
+}
+
 pub(crate) fn update_status_callback(status: String) -> Callback {
 	Callback::from_fn(move |s| {
 		update_status(s, &status);
@@ -142,11 +227,7 @@ pub(crate) fn update_status_callback(status: String) -> Callback {
 
 fn switch_render(s: &mut Cursive) {
 	let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
-	s.with_user_data(|controller_context: &mut TerminalContext| {
-		let configuration = &mut controller_context.configuration;
-		configuration.render_han = !configuration.render_han;
-		reading_view.switch_render(configuration.render_han);
-	});
+	reading_view.switch_render();
 }
 
 fn select_book(s: &mut Cursive) {
@@ -171,18 +252,67 @@ fn select_book(s: &mut Cursive) {
 	}
 }
 
-fn select_history(s: &mut Cursive)
+#[inline]
+fn chk<T, F>(result: Result<T>, f: F) -> String
+	where F: FnOnce(T) -> String
 {
-	#[inline]
-	fn chk<T, F>(result: Result<T>, f: F) -> String
-		where F: FnOnce(T) -> String
-	{
-		match result {
-			Ok(v) => f(v),
-			Err(err) => err.to_string(),
+	match result {
+		Ok(v) => f(v),
+		Err(err) => err.to_string(),
+	}
+}
+
+/// switch book in reading view, reading now saved in history
+fn switch_reading<F>(s: &mut Cursive, switch: F)
+	where F: FnOnce(&Configuration, &mut ReadingView) -> Result<String>
+{
+	let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+	let mut reading_now = reading_view.reading_info();
+	let (trace, current_trace) = reading_view.trace();
+	let at_end = reading_view.at_end();
+	let msg = s.with_user_data(|controller_context: &mut TerminalContext| {
+		let configuration = &mut controller_context.configuration;
+		if at_end {
+			if let Err(err) = configuration.mark_finished(&reading_now.filename) {
+				return err.to_string();
+			}
 		}
+		chk(switch(configuration, &mut reading_view), |msg| {
+			let reading = reading_view.reading_info();
+			controller_context.current = reading.filename.clone();
+			chk(configuration.trace(&reading), |last_trace| {
+				if let Some((last_trace, current)) = last_trace {
+					reading_view.restore_trace(last_trace, current);
+				}
+				chk(configuration.save_reading(&mut reading_now), |()|
+					chk(configuration.save_trace(&reading_now, &trace, current_trace), |()|
+						msg))
+			})
+		})
+	}).unwrap();
+	update_status(s, &msg);
+}
+
+fn open_sibling(s: &mut Cursive, forward: bool)
+{
+	let reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+	let sibling = reading_view.sibling_book(forward);
+	drop(reading_view);
+	match sibling {
+		Ok(Some(filename)) => switch_reading(s, move |configuration, reading_view| {
+			if let Some(password) = configuration.password(&filename)? {
+				reading_view.set_password(&filename, password);
+			}
+			let loading = configuration.reading(&filename)?;
+			reading_view.switch_container(loading)
+		}),
+		Ok(None) => update_status(s, "No more book in the folder"),
+		Err(err) => update_status(s, &err.to_string()),
 	}
+}
 
+fn select_history(s: &mut Cursive)
+{
 	let option = s.with_user_data(|controller_context: &mut TerminalContext| {
 		let configuration = &mut controller_context.configuration;
 		let history = match configuration.history(Some(&controller_context.current), None) {
@@ -197,20 +327,13 @@ fn select_history(s: &mut Cursive)
 			return None;
 		}
 		let dialog = list_dialog("Reopen", history.into_iter(), 0, |s, selected| {
-			let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
-			let mut reading_now = reading_view.reading_info();
-			let msg = s.with_user_data(|controller_context: &mut TerminalContext| {
-				let configuration = &mut controller_context.configuration;
-				chk(configuration.reading_by_id(selected as i64), |reading| {
-					let loading = BookLoadingInfo::History(reading);
-					chk(reading_view.switch_container(loading), |msg| {
-						controller_context.current = reading_view.reading_info().filename;
-						chk(configuration.save_reading(&mut reading_now), |()|
-							msg)
-					})
-				})
-			}).unwrap();
-			update_status(s, &msg);
+			switch_reading(s, move |configuration, reading_view| {
+				let reading = configuration.reading_by_id(selected as i64)?;
+				if let Some(password) = configuration.password(&reading.filename)? {
+					reading_view.set_password(&reading.filename, password);
+				}
+				reading_view.switch_container(BookLoadingInfo::History(reading))
+			});
 		});
 		Some(dialog)
 	}).unwrap();
@@ -230,6 +353,55 @@ fn switch_theme(s: &mut Cursive) {
 	s.set_theme(theme.clone());
 }
 
+/// reload themes when theme files modified
+fn watch_themes(app: &Cursive, config_dir: PathBuf)
+{
+	let sink = app.cb_sink().clone();
+	thread::spawn(move || {
+		let mut modified = themes_modified(&config_dir);
+		loop {
+			thread::sleep(Duration::from_millis(THEME_WATCH_MILLIS));
+			let current = themes_modified(&config_dir);
+			if current == modified {
+				continue;
+			}
+			modified = current;
+			let config_dir = config_dir.clone();
+			// quit when cursive stopped
+			if sink.send(Box::new(move |s| reload_themes(s, &config_dir))).is_err() {
+				break;
+			}
+		}
+	});
+}
+
+#[inline]
+fn themes_modified(config_dir: &PathBuf) -> Vec<Option<SystemTime>>
+{
+	THEME_FILES
+		.iter()
+		.map(|file| fs::metadata(config_dir.join(file))
+			.and_then(|metadata| metadata.modified())
+			.ok())
+		.collect()
+}
+
+fn reload_themes(s: &mut Cursive, config_dir: &PathBuf)
+{
+	match load_themes(config_dir) {
+		Ok(themes) => {
+			let theme = s.with_user_data(|controller_context: &mut TerminalContext| {
+				controller_context.themes = themes;
+				let dark = controller_context.configuration.dark_theme;
+				controller_context.themes.get(dark).clone()
+			}).unwrap();
+			s.set_theme(theme);
+			update_status(s, "Themes reloaded");
+		}
+		Err(err) => update_status(s, &format!("Failed reload themes: {}", err)),
+	}
+}
+
 fn update_status(s: &mut Cursive, msg: &str) {
 	s.call_on_name(STATUS_VIEW_NAME, |view: &mut TextView| {
 		view.set_content(msg);
@@ -250,6 +422,19 @@ fn goto_line(app: &mut Cursive) {
 	}, |_| {});
 }
 
+fn goto_page(app: &mut Cursive) {
+	let reading_view: ViewRef<ReadingView> = app.find_name(TEXT_VIEW_NAME).unwrap();
+	let page = reading_view.page_label();
+	setup_input_view(app, GOTO_PAGE_LABEL_TEXT, &page, |s, label| {
+		if let Some(label) = label {
+			let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+			reading_view.goto_page(label)
+		} else {
+			Ok(())
+		}
+	}, |_| {});
+}
+
 fn setup_search_view(app: &mut Cursive) {
 	fn set_im_active(s: &mut Cursive, active: Option<bool>, update_restore: bool)
 	{
@@ -328,8 +513,8 @@ fn setup_input_view<F, C>(app: &mut Cursive, prefix: &str, preset: &str, submit:
 
 fn load_themes(config_dir: &PathBuf) -> Result<Themes>
 {
-	let dark = load_theme(config_dir, "dark.toml")?;
-	let bright = load_theme(config_dir, "bright.toml")?;
+	let dark = load_theme(config_dir, THEME_FILES[0])?;
+	let bright = load_theme(config_dir, THEME_FILES[1])?;
 	Ok(Themes { dark, bright })
 }
 
@@ -1,3 +1,6 @@
+#[cfg(feature = "script")]
+use std::rc::Rc;
+
 use anyhow::Result;
 use cursive::{Printer, Vec2, View, XY};
 use cursive::event::{Event, EventResult, Key, MouseButton, MouseEvent};
@@ -5,10 +8,14 @@ use cursive::theme::{ColorStyle, PaletteColor};
 
 
 use crate::book::{Book, Line};
-use crate::common::{char_width, Position};
+use crate::common::{char_width, Position, TraceInfo};
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::container::Container;
 use crate::controller::{Controller, HighlightInfo, HighlightMode, Render};
+use crate::crash;
+use crate::link::PositionLink;
+#[cfg(feature = "script")]
+use crate::script::Script;
 use crate::terminal::update_status_callback;
 use crate::terminal::view::han::Han;
 use crate::terminal::view::xi::Xi;
@@ -19,6 +26,9 @@ mod xi;
 pub struct ReadingView {
 	controller: Controller<RenderContext, dyn TerminalRender>,
 	render_context: RenderContext,
+	// render type for books without their own, and the current one
+	default_render_han: bool,
+	render_han: bool,
 
 	search_color: ColorStyle,
 	link_color: ColorStyle,
@@ -80,6 +90,8 @@ pub struct RenderContext {
 	height: usize,
 	print_lines: Vec<Vec<DrawChar>>,
 	leading_space: usize,
+	// blank cells between columns in han mode
+	column_spacing: usize,
 }
 
 impl RenderContext {
@@ -89,6 +101,7 @@ impl RenderContext {
 			height: 0,
 			print_lines: vec![],
 			leading_space: 0,
+			column_spacing: 1,
 		}
 	}
 }
@@ -184,24 +197,30 @@ impl View for ReadingView {
 }
 
 impl ReadingView {
-	pub(crate) fn new(render_han: bool, reading: BookLoadingInfo) -> Result<ReadingView> {
+	pub(crate) fn new(render_han: bool, reading: BookLoadingInfo, password: Option<String>)
+		-> Result<ReadingView> {
 		let render: Box<dyn TerminalRender> = load_render(render_han);
 		let mut render_context = RenderContext::new();
 		let controller = Controller::new(
 			reading,
+			password,
 			render,
 			&mut render_context)?;
 		let link_color = ColorStyle::new(ColorStyle::secondary().front, PaletteColor::Background);
 		let highlight_link_color = ColorStyle::new(ColorStyle::secondary().front, ColorStyle::highlight().back);
-		Ok(ReadingView {
+		let mut view = ReadingView {
 			controller,
 			render_context,
+			default_render_han: render_han,
+			render_han,
 
 			search_color: ColorStyle::highlight(),
 			link_color,
 			highlight_link_color,
 			color: ColorStyle::new(PaletteColor::Primary, PaletteColor::Background),
-		})
+		};
+		view.sync_render();
+		Ok(view)
 	}
 
 	#[inline]
@@ -210,6 +229,44 @@ impl ReadingView {
 		self.controller.reading_info().clone()
 	}
 
+	#[inline]
+	pub fn trace(&self) -> (Vec<TraceInfo>, usize)
+	{
+		let (trace, current) = self.controller.trace();
+		(trace.to_vec(), current)
+	}
+
+	#[inline]
+	pub fn restore_trace(&mut self, trace: Vec<TraceInfo>, current: usize)
+	{
+		self.controller.restore_trace(trace, current)
+	}
+
+	#[inline]
+	pub fn page_label(&self) -> String
+	{
+		self.controller.status().page.unwrap_or("").to_owned()
+	}
+
+	#[inline]
+	pub fn at_end(&self) -> bool
+	{
+		self.controller.at_end()
+	}
+
+	#[inline]
+	pub fn set_password(&mut self, filename: &str, password: String)
+	{
+		self.controller.container_manager.set_password(filename, password);
+	}
+
+	#[inline]
+	pub fn sibling_book(&self, forward: bool) -> Result<Option<String>>
+	{
+		self.controller.container_manager
+			.sibling_book(&self.controller.reading.filename, forward)
+	}
+
 	#[inline]
 	pub fn status_msg(&self) -> String
 	{
@@ -237,14 +294,26 @@ impl ReadingView {
 	#[inline]
 	pub fn switch_book(&mut self, inner_book: usize) -> String
 	{
-		self.controller.switch_book(inner_book, &mut self.render_context)
-			.unwrap_or_else(|e|e.to_string())
+		let msg = self.controller.switch_book(inner_book, &mut self.render_context)
+			.unwrap_or_else(|e|e.to_string());
+		self.sync_render();
+		msg
 	}
 
 	#[inline]
 	pub fn switch_container(&mut self, loading: BookLoadingInfo) -> Result<String>
 	{
-		self.controller.switch_container(loading, &mut self.render_context)
+		let msg = self.controller.switch_container(loading, &mut self.render_context)?;
+		self.sync_render();
+		Ok(msg)
+	}
+
+	pub fn goto_position(&mut self, link: &PositionLink) -> Result<String>
+	{
+		let msg = self.controller.goto_position(link.inner_book, link.chapter,
+			link.line, link.offset, &mut self.render_context)?;
+		self.sync_render();
+		Ok(msg)
 	}
 
 	#[inline]
@@ -253,6 +322,12 @@ impl ReadingView {
 		self.controller.goto_line(line, &mut self.render_context)
 	}
 
+	#[inline]
+	pub fn goto_page(&mut self, label: &str) -> Result<()>
+	{
+		self.controller.goto_page(label, &mut self.render_context)
+	}
+
 	#[inline]
 	pub fn search(&mut self, pattern: &str) -> Result<()>
 	{
@@ -271,13 +346,53 @@ impl ReadingView {
 		self.controller.goto_toc(toc_index, &mut self.render_context)
 	}
 
-	pub(crate) fn switch_render(&mut self, render_han: bool) {
-		self.controller.render = load_render(render_han);
-		self.controller.render.resized(&self.render_context);
-		self.controller.redraw(&mut self.render_context);
+	#[inline]
+	pub(crate) fn set_search_fold_width(&mut self, fold_width: bool)
+	{
+		self.controller.search_fold_width = fold_width;
+	}
+
+	#[inline]
+	pub(crate) fn set_chapter_continuation(&mut self, continuation: bool)
+	{
+		self.controller.chapter_continuation = continuation;
+	}
+
+	#[inline]
+	pub(crate) fn set_han_column_spacing(&mut self, spacing: usize)
+	{
+		self.render_context.column_spacing = spacing;
+	}
+
+	#[cfg(feature = "script")]
+	#[inline]
+	pub(crate) fn attach_script(&mut self, script: Script) -> Result<()>
+	{
+		self.controller.attach_script(Rc::new(script), &mut self.render_context)
+	}
+
+	/// switch render type of current book only
+	pub(crate) fn switch_render(&mut self) {
+		self.controller.reading.render_han = Some(!self.render_han);
+		self.sync_render();
+	}
+
+	/// render type of the book, or the default one
+	fn sync_render(&mut self) {
+		let render_han = self.controller.reading.render_han
+			.unwrap_or(self.default_render_han);
+		if render_han != self.render_han {
+			self.render_han = render_han;
+			self.controller.render = load_render(render_han);
+			self.controller.render.resized(&self.render_context);
+			self.controller.redraw(&mut self.render_context);
+		}
 	}
 
 	fn process_event(&mut self, e: Event) -> Result<bool> {
+		if e != Event::Refresh {
+			crash::track_action(&format!("{:?}", e));
+		}
 		match e {
 			Event::Char(' ') | Event::Key(Key::PageDown) => self.controller.next_page(&mut self.render_context)?,
 			Event::Key(Key::PageUp) => self.controller.prev_page(&mut self.render_context)?,
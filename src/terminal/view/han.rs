@@ -1,11 +1,12 @@
-use unicode_width::UnicodeWidthChar;
-
 use crate::book::{Book, Line};
-use crate::common::{han_render_char, length_with_leading, with_leading};
+use crate::common::{char_width, han_render_char, length_with_leading, with_leading};
 use crate::config::ReadingInfo;
 use crate::controller::HighlightInfo;
 use crate::terminal::view::{DrawChar, Position, Render, RenderContext, TerminalRender};
 
+// every column takes 2 cells, half-width chars padded
+const COLUMN_WIDTH: usize = 2;
+
 pub struct Han {
 	line_count: usize,
 }
@@ -23,13 +24,15 @@ impl Han {
 		let print_lines = &mut context.print_lines;
 		print_lines.clear();
 		let line_count = self.line_count;
+		let spacing = context.column_spacing;
 		let blank_lines = line_count - draw_lines.len();
 		let (blank_prefix_length, mut need_split_space) = if blank_lines > 0 {
-			((blank_lines - 1) * 3 + 2, true)
+			((blank_lines - 1) * (COLUMN_WIDTH + spacing) + COLUMN_WIDTH, true)
 		} else {
 			(0, false)
 		};
-		let print_suffix_length = context.width - (line_count * 3 - 1);
+		let print_suffix_length = context.width
+			.saturating_sub(line_count * (COLUMN_WIDTH + spacing) - spacing);
 		for _x in 0..context.height {
 			let mut line = vec![];
 			if blank_prefix_length > 0 {
@@ -44,7 +47,9 @@ impl Han {
 			for idx in 0..context.height {
 				let print_line = &mut print_lines[idx];
 				if need_split_space {
-					print_line.push(DrawChar::space());
+					for _s in 0..spacing {
+						print_line.push(DrawChar::space());
+					}
 				}
 				let dc = chars.next();
 				append_char(print_line, dc);
@@ -61,18 +66,18 @@ impl Han {
 	}
 }
 
+/// fill the column with exactly COLUMN_WIDTH cells, chars without width,
+/// like combining marks or control chars, drawn as blank
 fn append_char(line: &mut Vec<DrawChar>, dc_option: Option<&DrawChar>) {
 	match dc_option {
 		Some(dc) => {
-			match dc.char.width() {
-				Some(s) => {
-					line.push(DrawChar::new(dc.char, dc.mode.clone()));
-					if s == 1 {
-						line.push(DrawChar::new(' ', dc.mode.clone()));
-					}
-				}
-				None => {
-					line.push(DrawChar::new(' ', dc.mode.clone()));
+			let width = char_width(dc.char);
+			if width == 0 || width > COLUMN_WIDTH {
+				line.push(DrawChar::new(' ', dc.mode.clone()));
+				line.push(DrawChar::new(' ', dc.mode.clone()));
+			} else {
+				line.push(DrawChar::new(dc.char, dc.mode.clone()));
+				for _w in width..COLUMN_WIDTH {
 					line.push(DrawChar::new(' ', dc.mode.clone()));
 				}
 			}
@@ -88,8 +93,8 @@ impl TerminalRender for Han
 {
 	fn resized(&mut self, context: &RenderContext)
 	{
-		let width = context.width;
-		self.line_count = if width % 3 == 2 { width / 3 + 1 } else { width / 3 };
+		let column = COLUMN_WIDTH + context.column_spacing;
+		self.line_count = ((context.width + context.column_spacing) / column).max(1);
 	}
 }
 
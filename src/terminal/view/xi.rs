@@ -319,6 +319,7 @@ mod tests {
 			height: 23,
 			print_lines: vec![],
 			leading_space: 2,
+			column_spacing: 1,
 		};
 		let book: Box<dyn Book> = Box::new(DummyBook { lines });
 		let mut xi = Xi {};